@@ -0,0 +1,99 @@
+//! Adapter module for StellarLend protocol
+//! Yield-bearing collateral (LP shares, staked XLM derivatives, vault tokens) whose value
+//! cannot be read off a plain price feed is delegated to an adapter contract registered
+//! per asset. The adapter values the asset and is told about every seizure so it can
+//! unwind whatever position backs it, keeping such quirks out of the core flows.
+
+use crate::cross_asset::CrossAssetManager;
+use crate::events::SequencedEvents;
+use crate::external::ExternalCall;
+use crate::storage::DataKey;
+use crate::{ProtocolConfig, ProtocolError};
+use soroban_sdk::{contractclient, vec, Address, Env, IntoVal, Map, Symbol};
+
+/// Interface a collateral adapter contract implements
+#[allow(dead_code)]
+#[contractclient(name = "CollateralAdapterClient")]
+pub trait CollateralAdapter {
+    /// Value of `amount` units of `asset` in primary asset units
+    fn value_of(env: Env, asset: Address, amount: i128) -> i128;
+
+    /// Called after `amount` units of `asset` were seized from `user` in a liquidation
+    /// on behalf of `receiver`
+    fn on_seize(env: Env, asset: Address, user: Address, amount: i128, receiver: Address);
+}
+
+/// Registry of adapters by collateral asset
+pub struct CollateralAdapters;
+
+impl CollateralAdapters {
+    fn key() -> DataKey {
+        DataKey::CollateralAdapters
+    }
+
+    pub fn get_all(env: &Env) -> Map<Address, Address> {
+        env.storage()
+            .instance()
+            .get(&Self::key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn get(env: &Env, asset: &Address) -> Option<Address> {
+        Self::get_all(env).get(asset.clone())
+    }
+
+    /// Admin: route valuation and seizure of a listed cross asset through `adapter`, or
+    /// back to its price feed with `None`
+    pub fn set(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        adapter: Option<Address>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        CrossAssetManager::get_asset_params(env, asset)?;
+        let mut adapters = Self::get_all(env);
+        match &adapter {
+            Some(adapter) => adapters.set(asset.clone(), adapter.clone()),
+            None => {
+                adapters.remove(asset.clone());
+            }
+        }
+        env.storage().instance().set(&Self::key(), &adapters);
+        env.publish_event(
+            (Symbol::new(env, "collateral_adapter_set"), asset.clone()),
+            adapter,
+        );
+        Ok(())
+    }
+
+    /// Price of `asset` (primary asset units per unit, scaled by 1e8) as valued by its
+    /// adapter; a failing or non-positive valuation is an oracle failure
+    pub fn price(env: &Env, adapter: &Address, asset: &Address) -> Result<i128, ProtocolError> {
+        match CollateralAdapterClient::new(env, adapter).try_value_of(asset, &100000000) {
+            Ok(Ok(price)) if price > 0 => Ok(price),
+            _ => Err(ProtocolError::OracleFailure),
+        }
+    }
+
+    /// Let the asset's adapter, if any, react to a seizure
+    pub fn notify_seizure(
+        env: &Env,
+        asset: &Address,
+        user: &Address,
+        amount: i128,
+        receiver: &Address,
+    ) -> Result<(), ProtocolError> {
+        if let Some(adapter) = Self::get(env, asset) {
+            let args = vec![
+                env,
+                asset.into_val(env),
+                user.into_val(env),
+                amount.into_val(env),
+                receiver.into_val(env),
+            ];
+            ExternalCall::invoke::<(), _>(env, &adapter, "on_seize", args, || Ok(()))?;
+        }
+        Ok(())
+    }
+}
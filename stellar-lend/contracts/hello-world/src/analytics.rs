@@ -8,12 +8,14 @@
 //! - Risk analytics
 //! - Activity tracking
 
+use crate::storage::DataKey;
 use soroban_sdk::{
-    contracterror, contracttype, vec, Address, Env, Map, String, Symbol, Vec,
+    contracterror, contracttype, vec, Address, Env, Map, String, Vec,
 };
 use alloc::string::ToString;
 
-use crate::{ProtocolError, ProtocolEvent};
+use crate::revenue::RevenueStorage;
+use crate::{InterestRateStorage, ProtocolError, ProtocolEvent, TokenRegistry};
 
 /// Analytics-specific error types
 #[contracterror]
@@ -62,10 +64,14 @@ pub struct ProtocolMetrics {
     pub total_liquidations: i128,
     /// Total protocol fees collected
     pub total_fees_collected: i128,
-    /// Number of active users
+    /// Number of users active in the last 24 hours
     pub active_users: i128,
+    /// Number of users active in the last 7 days
+    pub active_users_7d: i128,
     /// Number of total users
     pub total_users: i128,
+    /// Protocol calls recorded across all users
+    pub total_transactions: i128,
     /// Average utilization rate across all assets
     pub avg_utilization_rate: i128,
     /// Total volume (deposits + borrows)
@@ -87,7 +93,9 @@ impl ProtocolMetrics {
             total_liquidations: 0,
             total_fees_collected: 0,
             active_users: 0,
+            active_users_7d: 0,
             total_users: 0,
+            total_transactions: 0,
             avg_utilization_rate: 0,
             total_volume: 0,
             last_update: 0,
@@ -96,6 +104,29 @@ impl ProtocolMetrics {
     }
 }
 
+/// Protocol action recorded in the analytics log
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ActivityKind {
+    Deposit,
+    Borrow,
+    Repay,
+    Withdraw,
+    Liquidate,
+}
+
+impl ActivityKind {
+    fn name(self) -> &'static str {
+        match self {
+            ActivityKind::Deposit => "deposit",
+            ActivityKind::Borrow => "borrow",
+            ActivityKind::Repay => "repay",
+            ActivityKind::Withdraw => "withdraw",
+            ActivityKind::Liquidate => "liquidate",
+        }
+    }
+}
+
 /// User-specific analytics
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -206,6 +237,27 @@ pub struct HistoricalDataPoint {
     pub asset_data: Map<Address, AssetAnalytics>,
 }
 
+/// Compact daily checkpoint of protocol totals, in primary asset units
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ProtocolCheckpoint {
+    /// Day number (`timestamp / 86400`) the checkpoint covers
+    pub day: u64,
+    /// Timestamp of the latest update within the day
+    pub timestamp: u64,
+    /// Total supplied to the market
+    pub total_value_locked: i128,
+    /// Total borrowed from the market
+    pub total_debt: i128,
+    /// Reserves owed to the treasury but not yet distributed
+    pub reserves: i128,
+    /// Number of users who have ever interacted with the protocol
+    pub total_users: i128,
+}
+
+/// Daily checkpoints retained; older ones are dropped
+pub const MAX_PROTOCOL_CHECKPOINTS: u32 = 90;
+
 /// Risk analytics
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -268,116 +320,178 @@ impl PerformanceMetrics {
     }
 }
 
+/// Width of the buckets behind the 24 hour active user count
+pub const ACTIVE_HOUR_SECS: u64 = 60 * 60;
+
+/// Width of the buckets behind the 7 day active user count
+pub const ACTIVE_DAY_SECS: u64 = 24 * 60 * 60;
+
 /// Analytics storage management
 pub struct AnalyticsStorage;
 
 impl AnalyticsStorage {
     // Storage keys
-    fn protocol_metrics_key(env: &Env) -> Symbol { Symbol::new(env, "protocol_metrics") }
-    fn user_analytics_key(env: &Env) -> Symbol { Symbol::new(env, "user_analytics") }
-    fn asset_analytics_key(env: &Env) -> Symbol { Symbol::new(env, "asset_analytics") }
-    fn historical_data_key(env: &Env) -> Symbol { Symbol::new(env, "historical_data") }
-    fn risk_analytics_key(env: &Env) -> Symbol { Symbol::new(env, "risk_analytics") }
-    fn performance_metrics_key(env: &Env) -> Symbol { Symbol::new(env, "performance_metrics") }
-    fn activity_log_key(env: &Env) -> Symbol { Symbol::new(env, "activity_log") }
+    fn protocol_metrics_key() -> DataKey { DataKey::ProtocolMetrics }
+    fn user_analytics_key(user: &Address) -> DataKey { DataKey::UserAnalytics(user.clone()) }
+    fn analytics_users_key() -> DataKey { DataKey::AnalyticsUsers }
+    fn active_bucket_key(width: u64, bucket: u64) -> DataKey { DataKey::ActiveUsers(width, bucket) }
+    fn asset_analytics_key() -> DataKey { DataKey::AssetAnalytics }
+    fn historical_data_key() -> DataKey { DataKey::HistoricalData }
+    fn checkpoints_key() -> DataKey { DataKey::ProtocolCheckpoints }
+    fn risk_analytics_key() -> DataKey { DataKey::RiskAnalytics }
+    fn performance_metrics_key() -> DataKey { DataKey::PerformanceMetrics }
+    fn activity_log_key() -> DataKey { DataKey::ActivityLog }
 
     // Protocol metrics
     pub fn get_protocol_metrics(env: &Env) -> ProtocolMetrics {
         env.storage().instance()
-            .get(&Self::protocol_metrics_key(env))
+            .get(&Self::protocol_metrics_key())
             .unwrap_or_else(ProtocolMetrics::new)
     }
 
     pub fn put_protocol_metrics(env: &Env, metrics: &ProtocolMetrics) {
-        env.storage().instance().set(&Self::protocol_metrics_key(env), metrics);
+        env.storage().instance().set(&Self::protocol_metrics_key(), metrics);
     }
 
     // User analytics
-    pub fn get_user_analytics(env: &Env) -> Map<Address, UserAnalytics> {
+    /// Users with recorded analytics, in order of first activity
+    pub fn get_analytics_users(env: &Env) -> Vec<Address> {
         env.storage().instance()
-            .get(&Self::user_analytics_key(env))
-            .unwrap_or_else(|| Map::new(env))
+            .get(&Self::analytics_users_key())
+            .unwrap_or_else(|| vec![env])
     }
 
-    pub fn put_user_analytics(env: &Env, analytics: &Map<Address, UserAnalytics>) {
-        env.storage().instance().set(&Self::user_analytics_key(env), analytics);
+    /// Analytics of every tracked user, for protocol-wide reports
+    pub fn get_user_analytics(env: &Env) -> Map<Address, UserAnalytics> {
+        let mut analytics_map = Map::new(env);
+        for user in Self::get_analytics_users(env).iter() {
+            let analytics = Self::get_user_analytics_for_user(env, &user);
+            analytics_map.set(user, analytics);
+        }
+        analytics_map
     }
 
     pub fn get_user_analytics_for_user(env: &Env, user: &Address) -> UserAnalytics {
-        let analytics_map = Self::get_user_analytics(env);
-        analytics_map.get(user.clone()).unwrap_or_else(UserAnalytics::new)
+        env.storage().persistent()
+            .get(&Self::user_analytics_key(user))
+            .unwrap_or_else(UserAnalytics::new)
+    }
+
+    /// Delete a user's analytics and their entries in the activity log
+    pub fn forget_user(env: &Env, user: &Address) {
+        let key = Self::user_analytics_key(user);
+        if env.storage().persistent().has(&key) {
+            env.storage().persistent().remove(&key);
+            let mut users = Self::get_analytics_users(env);
+            if let Some(index) = users.first_index_of(user) {
+                users.remove(index);
+            }
+            env.storage().instance().set(&Self::analytics_users_key(), &users);
+        }
+
+        let log = Self::get_activity_log(env);
+        let mut kept = vec![env];
+        for entry in log.iter() {
+            if entry.user != *user {
+                kept.push_back(entry);
+            }
+        }
+        if kept.len() != log.len() {
+            Self::put_activity_log(env, &kept);
+        }
     }
 
     pub fn update_user_analytics(env: &Env, user: &Address, analytics: &UserAnalytics) {
-        let mut analytics_map = Self::get_user_analytics(env);
-        analytics_map.set(user.clone(), analytics.clone());
-        Self::put_user_analytics(env, &analytics_map);
+        let key = Self::user_analytics_key(user);
+        if !env.storage().persistent().has(&key) {
+            let mut users = Self::get_analytics_users(env);
+            users.push_back(user.clone());
+            env.storage().instance().set(&Self::analytics_users_key(), &users);
+        }
+        env.storage().persistent().set(&key, analytics);
     }
 
     // Asset analytics
     pub fn get_asset_analytics(env: &Env) -> Map<Address, AssetAnalytics> {
         env.storage().instance()
-            .get(&Self::asset_analytics_key(env))
+            .get(&Self::asset_analytics_key())
             .unwrap_or_else(|| Map::new(env))
     }
 
-    pub fn put_asset_analytics(env: &Env, analytics: &Map<Address, AssetAnalytics>) {
-        env.storage().instance().set(&Self::asset_analytics_key(env), analytics);
-    }
-
     pub fn get_asset_analytics_for_asset(env: &Env, asset: &Address) -> AssetAnalytics {
         let analytics_map = Self::get_asset_analytics(env);
         analytics_map.get(asset.clone()).unwrap_or_else(|| AssetAnalytics::new(asset.clone()))
     }
 
-    pub fn update_asset_analytics(env: &Env, asset: &Address, analytics: &AssetAnalytics) {
-        let mut analytics_map = Self::get_asset_analytics(env);
-        analytics_map.set(asset.clone(), analytics.clone());
-        Self::put_asset_analytics(env, &analytics_map);
-    }
-
     // Historical data
     pub fn get_historical_data(env: &Env) -> Map<u64, HistoricalDataPoint> {
         env.storage().instance()
-            .get(&Self::historical_data_key(env))
+            .get(&Self::historical_data_key())
             .unwrap_or_else(|| Map::new(env))
     }
 
     pub fn put_historical_data(env: &Env, data: &Map<u64, HistoricalDataPoint>) {
-        env.storage().instance().set(&Self::historical_data_key(env), data);
+        env.storage().instance().set(&Self::historical_data_key(), data);
     }
 
     // Risk analytics
     pub fn get_risk_analytics(env: &Env) -> RiskAnalytics {
         env.storage().instance()
-            .get(&Self::risk_analytics_key(env))
+            .get(&Self::risk_analytics_key())
             .unwrap_or_else(RiskAnalytics::new)
     }
 
     pub fn put_risk_analytics(env: &Env, analytics: &RiskAnalytics) {
-        env.storage().instance().set(&Self::risk_analytics_key(env), analytics);
+        env.storage().instance().set(&Self::risk_analytics_key(), analytics);
     }
 
     // Performance metrics
     pub fn get_performance_metrics(env: &Env) -> PerformanceMetrics {
         env.storage().instance()
-            .get(&Self::performance_metrics_key(env))
+            .get(&Self::performance_metrics_key())
             .unwrap_or_else(PerformanceMetrics::new)
     }
 
     pub fn put_performance_metrics(env: &Env, metrics: &PerformanceMetrics) {
-        env.storage().instance().set(&Self::performance_metrics_key(env), metrics);
+        env.storage().instance().set(&Self::performance_metrics_key(), metrics);
+    }
+
+    // Active user buckets: users whose latest activity falls in each bucket
+    pub fn get_active_bucket(env: &Env, width: u64, bucket: u64) -> i128 {
+        env.storage().persistent()
+            .get(&Self::active_bucket_key(width, bucket))
+            .unwrap_or(0)
+    }
+
+    pub fn put_active_bucket(env: &Env, width: u64, bucket: u64, count: i128) {
+        let key = Self::active_bucket_key(width, bucket);
+        if count > 0 {
+            env.storage().persistent().set(&key, &count);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+    }
+
+    // Daily checkpoints, oldest first
+    pub fn get_checkpoints(env: &Env) -> Vec<ProtocolCheckpoint> {
+        env.storage().instance()
+            .get(&Self::checkpoints_key())
+            .unwrap_or_else(|| vec![env])
+    }
+
+    pub fn put_checkpoints(env: &Env, checkpoints: &Vec<ProtocolCheckpoint>) {
+        env.storage().instance().set(&Self::checkpoints_key(), checkpoints);
     }
 
     // Activity log
     pub fn get_activity_log(env: &Env) -> Vec<ActivityLogEntry> {
         env.storage().instance()
-            .get(&Self::activity_log_key(env))
+            .get(&Self::activity_log_key())
             .unwrap_or_else(|| vec![env])
     }
 
     pub fn put_activity_log(env: &Env, log: &Vec<ActivityLogEntry>) {
-        env.storage().instance().set(&Self::activity_log_key(env), log);
+        env.storage().instance().set(&Self::activity_log_key(), log);
     }
 }
 
@@ -390,7 +504,7 @@ pub struct ActivityLogEntry {
     /// User who performed the activity
     pub user: Address,
     /// Type of activity
-    pub activity_type: String,
+    pub activity_type: ActivityKind,
     /// Amount involved
     pub amount: i128,
     /// Asset involved (if applicable)
@@ -403,11 +517,12 @@ pub struct ActivityLogEntry {
 pub struct AnalyticsModule;
 
 impl AnalyticsModule {
-    /// Record a user activity
-    pub fn record_activity(
+    /// Record a user activity; called from the protocol's own deposit, borrow, repay,
+    /// withdraw and liquidate paths only
+    pub(crate) fn record_activity(
         env: &Env,
         user: &Address,
-        activity_type: &str,
+        activity_type: ActivityKind,
         amount: i128,
         asset: Option<Address>,
     ) -> Result<(), ProtocolError> {
@@ -420,7 +535,7 @@ impl AnalyticsModule {
         let entry = ActivityLogEntry {
             timestamp,
             user: user.clone(),
-            activity_type: String::from_str(env, activity_type),
+            activity_type,
             amount,
             asset,
             metadata,
@@ -438,15 +553,15 @@ impl AnalyticsModule {
         AnalyticsStorage::put_activity_log(env, &log);
 
         // Update user analytics
-        Self::update_user_activity(env, user, activity_type, amount)?;
+        let first_activity = Self::update_user_activity(env, user, activity_type, amount)?;
 
         // Update protocol metrics
-        Self::update_protocol_metrics(env, activity_type, amount)?;
+        Self::update_protocol_metrics(env, activity_type, amount, first_activity)?;
 
         // Emit analytics event
         ProtocolEvent::AnalyticsUpdated(
             user.clone(),
-            String::from_str(env, activity_type),
+            String::from_str(env, activity_type.name()),
             amount,
             timestamp,
         ).emit(env);
@@ -454,23 +569,29 @@ impl AnalyticsModule {
         Ok(())
     }
 
-    /// Update user activity metrics
+    /// Update user activity metrics; returns true on the user's first recorded activity
     fn update_user_activity(
         env: &Env,
         user: &Address,
-        activity_type: &str,
+        activity_type: ActivityKind,
         amount: i128,
-    ) -> Result<(), ProtocolError> {
+    ) -> Result<bool, ProtocolError> {
         let mut user_analytics = AnalyticsStorage::get_user_analytics_for_user(env, user);
         let timestamp = env.ledger().timestamp();
+        let first_activity = user_analytics.transaction_count == 0;
+
+        // Move the user from the bucket of their previous activity to the current one
+        let previous = if first_activity { None } else { Some(user_analytics.last_activity) };
+        Self::move_active_user(env, ACTIVE_HOUR_SECS, previous, timestamp);
+        Self::move_active_user(env, ACTIVE_DAY_SECS, previous, timestamp);
 
         // Update activity counters
         match activity_type {
-            "deposit" => user_analytics.total_deposits += amount,
-            "borrow" => user_analytics.total_borrows += amount,
-            "withdraw" => user_analytics.total_withdrawals += amount,
-            "repay" => user_analytics.total_repayments += amount,
-            _ => {}
+            ActivityKind::Deposit => user_analytics.total_deposits += amount,
+            ActivityKind::Borrow => user_analytics.total_borrows += amount,
+            ActivityKind::Withdraw => user_analytics.total_withdrawals += amount,
+            ActivityKind::Repay => user_analytics.total_repayments += amount,
+            ActivityKind::Liquidate => {}
         }
 
         // Update timestamps
@@ -495,38 +616,67 @@ impl AnalyticsModule {
         };
 
         AnalyticsStorage::update_user_analytics(env, user, &user_analytics);
-        Ok(())
+        Ok(first_activity)
+    }
+
+    fn move_active_user(env: &Env, width: u64, previous: Option<u64>, now: u64) {
+        let current = now / width;
+        if let Some(previous) = previous {
+            let previous = previous / width;
+            if previous == current {
+                return;
+            }
+            let count = AnalyticsStorage::get_active_bucket(env, width, previous);
+            AnalyticsStorage::put_active_bucket(env, width, previous, count - 1);
+        }
+        let count = AnalyticsStorage::get_active_bucket(env, width, current);
+        AnalyticsStorage::put_active_bucket(env, width, current, count + 1);
+    }
+
+    /// Distinct users whose latest activity falls in the last `buckets` buckets of `width`
+    pub fn active_users(env: &Env, width: u64, buckets: u64) -> i128 {
+        let current = env.ledger().timestamp() / width;
+        let mut total = 0;
+        for offset in 0..buckets.min(current + 1) {
+            total += AnalyticsStorage::get_active_bucket(env, width, current - offset);
+        }
+        total
     }
 
     /// Update protocol-wide metrics
     fn update_protocol_metrics(
         env: &Env,
-        activity_type: &str,
+        activity_type: ActivityKind,
         amount: i128,
+        first_activity: bool,
     ) -> Result<(), ProtocolError> {
         let mut metrics = AnalyticsStorage::get_protocol_metrics(env);
         let timestamp = env.ledger().timestamp();
 
+        metrics.total_transactions += 1;
+        if first_activity {
+            metrics.total_users += 1;
+        }
+
         // Update activity counters
         match activity_type {
-            "deposit" => {
+            ActivityKind::Deposit => {
                 metrics.total_deposits += amount;
                 metrics.total_value_locked += amount;
             },
-            "borrow" => {
+            ActivityKind::Borrow => {
                 metrics.total_borrows += amount;
             },
-            "withdraw" => {
+            ActivityKind::Withdraw => {
                 metrics.total_withdrawals += amount;
                 metrics.total_value_locked -= amount;
             },
-            "repay" => {
+            ActivityKind::Repay => {
                 metrics.total_repayments += amount;
             },
-            "liquidate" => {
+            ActivityKind::Liquidate => {
                 metrics.total_liquidations += 1;
             },
-            _ => {}
         }
 
         // Update volume
@@ -549,10 +699,53 @@ impl AnalyticsModule {
 
         // Store historical snapshot (daily)
         Self::store_historical_snapshot(env, &metrics)?;
+        Self::record_checkpoint(env, &metrics);
 
         Ok(())
     }
 
+    /// Write today's checkpoint into the bounded history, replacing an earlier one from
+    /// the same day so each day keeps its closing figures
+    fn record_checkpoint(env: &Env, metrics: &ProtocolMetrics) {
+        let timestamp = env.ledger().timestamp();
+        let day = timestamp / 86400;
+        let state = InterestRateStorage::get_state(env);
+        let reserves = match TokenRegistry::require_primary_asset(env) {
+            Ok(asset) => {
+                let balance = RevenueStorage::get_treasury_balance(env, &asset);
+                balance.accrued - balance.distributed
+            }
+            Err(_) => 0,
+        };
+        let checkpoint = ProtocolCheckpoint {
+            day,
+            timestamp,
+            total_value_locked: state.total_supplied,
+            total_debt: state.total_borrowed,
+            reserves,
+            total_users: metrics.total_users,
+        };
+
+        let mut checkpoints = AnalyticsStorage::get_checkpoints(env);
+        if checkpoints.last().is_some_and(|last| last.day == day) {
+            checkpoints.pop_back();
+        }
+        checkpoints.push_back(checkpoint);
+        while checkpoints.len() > MAX_PROTOCOL_CHECKPOINTS {
+            checkpoints.pop_front();
+        }
+        AnalyticsStorage::put_checkpoints(env, &checkpoints);
+    }
+
+    /// The latest `limit` daily checkpoints, oldest first; zero returns all retained
+    pub fn get_protocol_history(env: &Env, limit: u32) -> Vec<ProtocolCheckpoint> {
+        let checkpoints = AnalyticsStorage::get_checkpoints(env);
+        if limit > 0 && checkpoints.len() > limit {
+            return checkpoints.slice(checkpoints.len() - limit..);
+        }
+        checkpoints
+    }
+
     /// Store historical snapshot
     fn store_historical_snapshot(
         env: &Env,
@@ -575,28 +768,26 @@ impl AnalyticsModule {
         Ok(())
     }
 
+    /// Protocol metrics with the active user counts brought up to date
+    pub fn get_protocol_metrics(env: &Env) -> ProtocolMetrics {
+        let mut metrics = AnalyticsStorage::get_protocol_metrics(env);
+        metrics.active_users = Self::active_users(env, ACTIVE_HOUR_SECS, 24);
+        metrics.active_users_7d = Self::active_users(env, ACTIVE_DAY_SECS, 7);
+        metrics
+    }
+
     /// Get comprehensive protocol report
     pub fn get_protocol_report(env: &Env) -> Result<ProtocolReport, ProtocolError> {
-        let protocol_metrics = AnalyticsStorage::get_protocol_metrics(env);
+        let protocol_metrics = Self::get_protocol_metrics(env);
         let risk_analytics = AnalyticsStorage::get_risk_analytics(env);
         let performance_metrics = AnalyticsStorage::get_performance_metrics(env);
-        let user_analytics = AnalyticsStorage::get_user_analytics(env);
-
-        // Calculate additional metrics
-        let total_users = user_analytics.len() as i128;
-        let active_users = user_analytics.iter()
-            .filter(|(_, analytics)| {
-                let cutoff = env.ledger().timestamp() - 86400; // 24 hours
-                analytics.last_activity > cutoff
-            })
-            .count() as i128;
 
         Ok(ProtocolReport {
+            total_users: protocol_metrics.total_users,
+            active_users: protocol_metrics.active_users,
             protocol_metrics,
             risk_analytics,
             performance_metrics,
-            total_users,
-            active_users,
             generated_at: env.ledger().timestamp(),
         })
     }
@@ -699,10 +890,10 @@ impl AnalyticsModule {
         // Update success/error rates (simplified)
         if success {
             metrics.success_rate = (metrics.success_rate + 100) / 2;
-            metrics.error_rate = metrics.error_rate / 2;
+            metrics.error_rate /= 2;
         } else {
             metrics.error_rate = (metrics.error_rate + 100) / 2;
-            metrics.success_rate = metrics.success_rate / 2;
+            metrics.success_rate /= 2;
         }
 
         metrics.last_update = timestamp;
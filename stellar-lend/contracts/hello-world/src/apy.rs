@@ -0,0 +1,61 @@
+//! APY module for StellarLend protocol
+//! Portfolio-level yield view: the supply rate on primary collateral less any storage
+//! fee, the borrow rate after the user's staking discount and the liquidity incentives of
+//! every market the user is in, folded into one signed rate on the user's net worth so
+//! wallets do not have to repeat the protocol's rate math.
+
+use crate::cross_asset::{CrossAssetManager, CrossStateHelper, UserMarkets};
+use crate::rewards::RewardManager;
+use crate::staking::Staking;
+use crate::supply_fee::SupplyFee;
+use crate::{InterestRateStorage, ProtocolError, SafeMath, StateHelper};
+use soroban_sdk::{Address, Env};
+
+/// Net yield of a user's whole portfolio
+pub struct NetApy;
+
+impl NetApy {
+    /// Annual income minus borrow cost as a share of the user's net worth (supplied value
+    /// less debt, in primary asset units), scaled by 1e8 and negative when borrowing
+    /// costs outweigh income. Reward units are valued at par with the primary asset. Zero
+    /// when the user has no positive net worth.
+    pub fn for_user(env: &Env, user: &Address) -> Result<i128, ProtocolError> {
+        let state = InterestRateStorage::get_state(env);
+        let mut supplied = 0i128;
+        let mut borrowed = 0i128;
+        let mut income = 0i128;
+
+        if let Some(position) = StateHelper::get_position(env, user) {
+            let primary_supplied = position.collateral + position.supply_interest;
+            supplied += primary_supplied;
+            borrowed += position.debt + position.borrow_interest;
+            income += SafeMath::mul_div(primary_supplied, state.current_supply_rate, 100000000);
+            income -= SafeMath::mul_div(
+                SupplyFee::chargeable(env, &position),
+                SupplyFee::annual_rate(env),
+                100000000,
+            );
+            // Interest accrues on principal only
+            let borrow_rate = Staking::discounted_rate(env, user, state.current_borrow_rate);
+            income -= SafeMath::mul_div(position.debt, borrow_rate, 100000000);
+        }
+
+        // Cross-asset collateral earns no interest but counts towards net worth
+        let cross = CrossStateHelper::get_or_init_position(env, user);
+        for (asset, amount) in cross.collateral.iter() {
+            if amount > 0 {
+                supplied += CrossAssetManager::value_of_units(env, &asset, amount)?;
+            }
+        }
+
+        for asset in UserMarkets::get(env, user).iter() {
+            income += RewardManager::annual_rewards(env, user, &asset);
+        }
+
+        let net_worth = supplied - borrowed;
+        if net_worth <= 0 {
+            return Ok(0);
+        }
+        Ok(SafeMath::mul_div(income, 100000000, net_worth))
+    }
+}
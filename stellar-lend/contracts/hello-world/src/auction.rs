@@ -0,0 +1,233 @@
+//! Auction module for StellarLend protocol
+//! Debt auctions recapitalize the protocol when the insurance fund cannot absorb a
+//! position's bad debt: protocol tokens are minted to keepers at a declining price and
+//! the debt asset they pay is used to write the bad debt down
+
+use crate::cross_asset::{CrossAssetManager, CrossStateHelper};
+use crate::events::SequencedEvents;
+use crate::repay::RepayModule;
+use crate::storage::DataKey;
+use crate::{
+    EmergencyStorage, InterestRateManager, InterestRateStorage, Position, ProtocolConfig,
+    ProtocolError, StateHelper, TransferEnforcer,
+};
+use soroban_sdk::{contracttype, token::StellarAssetClient, Address, Env, Symbol};
+
+/// Parameters for a new debt auction
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct DebtAuctionParams {
+    /// Token minted to bidders; the contract must be its minting admin
+    pub protocol_token: Address,
+    /// Most protocol tokens a single bid can buy
+    pub lot_size: i128,
+    /// Debt asset paid per protocol token when the auction opens (scaled by 1e8)
+    pub start_price: i128,
+    /// Price floor reached at the end of the auction (scaled by 1e8)
+    pub min_price: i128,
+    /// Seconds over which the price decays linearly to the floor
+    pub duration: u64,
+}
+
+/// A running or finished debt auction for one position's bad debt
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct DebtAuction {
+    pub id: u64,
+    pub user: Address,
+    pub params: DebtAuctionParams,
+    /// Bad debt the auction set out to cover
+    pub debt_target: i128,
+    /// Debt asset raised so far
+    pub raised: i128,
+    /// Protocol tokens minted so far
+    pub sold: i128,
+    pub started_at: u64,
+    pub settled: bool,
+}
+
+/// Storage helper for debt auctions
+pub struct AuctionStorage;
+
+impl AuctionStorage {
+    fn auction_key(id: u64) -> DataKey {
+        DataKey::DebtAuction(id)
+    }
+
+    fn next_id_key() -> DataKey {
+        DataKey::DebtAuctionSeq
+    }
+
+    pub fn next_id(env: &Env) -> u64 {
+        let id: u64 = env
+            .storage()
+            .instance()
+            .get(&Self::next_id_key())
+            .unwrap_or(0)
+            + 1;
+        env.storage().instance().set(&Self::next_id_key(), &id);
+        id
+    }
+
+    pub fn get(env: &Env, id: u64) -> Option<DebtAuction> {
+        env.storage().persistent().get(&Self::auction_key(id))
+    }
+
+    pub fn save(env: &Env, auction: &DebtAuction) {
+        env.storage()
+            .persistent()
+            .set(&Self::auction_key(auction.id), auction);
+    }
+}
+
+/// Debt auction lifecycle
+pub struct DebtAuctions;
+
+impl DebtAuctions {
+    fn accrued_position(env: &Env, user: &Address) -> Result<Position, ProtocolError> {
+        let mut position =
+            StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        let state = InterestRateStorage::update_state(env);
+        InterestRateManager::accrue_interest_for_position(
+            env,
+            &mut position,
+            state.current_borrow_rate,
+            state.liquidity_index,
+        );
+        Ok(position)
+    }
+
+    /// Debt a position owes beyond its risk-adjusted collateral
+    pub fn bad_debt(env: &Env, position: &Position) -> Result<i128, ProtocolError> {
+        let cross = CrossStateHelper::get_or_init_position(env, &position.user);
+        let backing = CrossAssetManager::liquidation_value(
+            env,
+            &position.user,
+            position.collateral,
+            &cross.collateral,
+        )?;
+        Ok((RepayModule::payoff_amount(position) - backing).max(0))
+    }
+
+    /// Start an auction for `user`'s bad debt; only allowed once the unreserved insurance
+    /// fund can no longer cover it
+    pub fn start(
+        env: &Env,
+        caller: &Address,
+        user: &Address,
+        params: DebtAuctionParams,
+    ) -> Result<u64, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if params.lot_size <= 0
+            || params.min_price <= 0
+            || params.start_price < params.min_price
+            || params.duration == 0
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        let position = Self::accrued_position(env, user)?;
+        StateHelper::save_position(env, &position);
+        let debt_target = Self::bad_debt(env, &position)?;
+        if debt_target == 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let fund = EmergencyStorage::get(env).fund;
+        if fund.balance - fund.reserved >= debt_target {
+            return Err(ProtocolError::InvalidOperation);
+        }
+
+        let auction = DebtAuction {
+            id: AuctionStorage::next_id(env),
+            user: user.clone(),
+            params,
+            debt_target,
+            raised: 0,
+            sold: 0,
+            started_at: env.ledger().timestamp(),
+            settled: false,
+        };
+        AuctionStorage::save(env, &auction);
+        env.publish_event(
+            (Symbol::new(env, "debt_auction_started"), user.clone()),
+            (auction.id, debt_target),
+        );
+        Ok(auction.id)
+    }
+
+    /// Debt asset per protocol token at the current time
+    pub fn price(env: &Env, auction: &DebtAuction) -> i128 {
+        let params = &auction.params;
+        let elapsed = env.ledger().timestamp().saturating_sub(auction.started_at);
+        if elapsed >= params.duration {
+            return params.min_price;
+        }
+        params.start_price
+            - (params.start_price - params.min_price) * elapsed as i128 / params.duration as i128
+    }
+
+    /// Buy up to `amount` protocol tokens at the current price, paying in the debt asset;
+    /// returns the protocol tokens minted to the bidder
+    pub fn bid(
+        env: &Env,
+        bidder: &Address,
+        id: u64,
+        amount: i128,
+        max_price: i128,
+    ) -> Result<i128, ProtocolError> {
+        let mut auction = AuctionStorage::get(env, id).ok_or(ProtocolError::NotFound)?;
+        if auction.settled
+            || env.ledger().timestamp() >= auction.started_at + auction.params.duration
+        {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let price = Self::price(env, &auction);
+        if price > max_price {
+            return Err(ProtocolError::SlippageExceeded);
+        }
+
+        let mut position = Self::accrued_position(env, &auction.user)?;
+        let remaining =
+            (auction.debt_target - auction.raised).min(RepayModule::payoff_amount(&position));
+        let mut bought = amount.min(auction.params.lot_size);
+        let mut cost = bought * price / 100000000;
+        if cost > remaining {
+            // Round the final lot up so the auction can close out exactly
+            bought = (remaining * 100000000 + price - 1) / price;
+            cost = remaining;
+        }
+        if cost <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+
+        let received =
+            TransferEnforcer::transfer_in(env, bidder, cost, Symbol::new(env, "debt_auction"))?;
+        RepayModule::apply_repayment(&mut position, received);
+        StateHelper::save_position(env, &position);
+        StellarAssetClient::new(env, &auction.params.protocol_token).mint(bidder, &bought);
+
+        auction.raised += received;
+        auction.sold += bought;
+        auction.settled =
+            auction.raised >= auction.debt_target || RepayModule::payoff_amount(&position) == 0;
+        AuctionStorage::save(env, &auction);
+
+        env.publish_event(
+            (Symbol::new(env, "debt_auction_bid"), bidder.clone()),
+            (id, bought, received),
+        );
+        if auction.settled {
+            env.publish_event(
+                (
+                    Symbol::new(env, "debt_auction_settled"),
+                    auction.user.clone(),
+                ),
+                (id, auction.raised, auction.sold),
+            );
+        }
+        Ok(bought)
+    }
+}
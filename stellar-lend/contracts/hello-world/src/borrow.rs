@@ -1,13 +1,21 @@
 //! Borrow module for StellarLend protocol
 //! Handles borrowing functionality and related operations
 
-use crate::analytics::AnalyticsModule;
+use crate::analytics::{ActivityKind, AnalyticsModule};
+use crate::compliance::ComplianceGate;
+use crate::cross_asset::CrossAssetManager;
+use crate::grace::GracePeriod;
+use crate::history::PositionHistory;
+use crate::limits::BorrowLimits;
+use crate::notify::Notifications;
+use crate::recovery::RecoveryMode;
+use crate::ttl::Ttl;
 use crate::{
-    EmergencyManager, InterestRateManager, InterestRateStorage, OperationKind, ProtocolConfig,
-    ProtocolError, ProtocolEvent, ReentrancyGuard, RiskConfigStorage, StateHelper,
-    TransferEnforcer, UserManager,
+    ErrorContext, InterestRateManager, InterestRateStorage, OperationKind, ProtocolConfig,
+    ProtocolError, ProtocolEvent, ReentrancyScope, StateHelper, TokenRegistry, TransferEnforcer,
+    UserManager,
 };
-use soroban_sdk::{contracterror, contracttype, Address, Env, String, Symbol};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol};
 
 /// Borrow-specific errors
 #[contracterror]
@@ -71,21 +79,16 @@ pub struct BorrowModule;
 impl BorrowModule {
     /// Borrow assets from the protocol
     pub fn borrow(env: &Env, borrower: &Address, amount: i128) -> Result<(), ProtocolError> {
-        ReentrancyGuard::enter(env)?;
+        let _guard =
+            ErrorContext::report(env, ReentrancyScope::enter(env), "borrow", borrower, amount)?;
+        Ttl::extend_instance(env);
         let result = (|| -> Result<(), ProtocolError> {
+            borrower.require_auth();
             if amount <= 0 {
                 return Err(BorrowError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Borrow)?;
-
-            // Check if borrow is paused
-            let risk_config = RiskConfigStorage::get(env);
-            if risk_config.pause_borrow {
-                return Err(BorrowError::ProtocolPaused.into());
-            }
-
-            UserManager::ensure_operation_allowed(env, borrower, OperationKind::Borrow, amount)?;
+            ComplianceGate::check(env, borrower, OperationKind::Borrow, amount)?;
 
             // Load user position
             let mut position = match StateHelper::get_position(env, borrower) {
@@ -99,26 +102,33 @@ impl BorrowModule {
                 env,
                 &mut position,
                 state.current_borrow_rate,
-                state.current_supply_rate,
+                state.liquidity_index,
             );
 
-            // Check collateral ratio
-            let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
+            BorrowLimits::ensure_can_borrow(env, amount)?;
+            let primary = TokenRegistry::require_primary_asset(env)?;
+            BorrowLimits::ensure_market_active(env, &primary)?;
+            RecoveryMode::ensure_borrowable(env, &primary)?;
+            BorrowLimits::ensure_no_breaker_cooldown(env, &primary)?;
+            BorrowLimits::ensure_not_same_ledger(env, borrower)?;
+            CrossAssetManager::ensure_prices_fresh(env, borrower)?;
+
+            // Check risk-adjusted collateral ratio across all collateral assets, keeping the
+            // health factor buffer above the liquidation threshold
+            let min_ratio = ProtocolConfig::get_action_min_ratio(env);
             let new_debt = position.debt + amount;
-            let collateral_ratio = if new_debt > 0 {
-                (position.collateral * 100) / new_debt
-            } else {
-                0
-            };
+            let collateral_ratio =
+                CrossAssetManager::collateral_ratio(env, borrower, position.collateral, new_debt)?;
 
             if collateral_ratio < min_ratio {
                 return Err(BorrowError::InsufficientCollateralRatio.into());
             }
 
-            // Update position
-            TransferEnforcer::transfer_out(env, borrower, amount, Symbol::new(env, "borrow"))?;
+            // Update position before the funds leave
             position.debt = new_debt;
             StateHelper::save_position(env, &position);
+            TransferEnforcer::transfer_out(env, borrower, amount, Symbol::new(env, "borrow"))?;
+            GracePeriod::record_borrow(env, borrower, &primary, amount);
 
             // Emit event
             ProtocolEvent::PositionUpdated(
@@ -130,109 +140,69 @@ impl BorrowModule {
             .emit(env);
 
             // Analytics
-            AnalyticsModule::record_activity(env, borrower, "borrow", amount, None)?;
+            AnalyticsModule::record_activity(env, borrower, ActivityKind::Borrow, amount, None)?;
             UserManager::record_activity(env, borrower, OperationKind::Borrow, amount)?;
+            PositionHistory::record(env, borrower, ActivityKind::Borrow, None, amount);
+            BorrowLimits::record_ledger_action(env, borrower, &primary);
+            Notifications::check(env, borrower)?;
 
             Ok(())
         })();
-
-        ReentrancyGuard::exit(env);
-        result
+        ErrorContext::report(env, result, "borrow", borrower, amount)
     }
 
     /// Borrow a specific asset against total cross-asset collateral
     pub fn borrow_asset(
         env: &Env,
-        user: &String,
+        user: &Address,
         asset: &Address,
         amount: i128,
     ) -> Result<(), ProtocolError> {
-        ReentrancyGuard::enter(env)?;
-        let result = (|| -> Result<(), ProtocolError> {
-            if user.is_empty() {
-                return Err(BorrowError::InvalidAddress.into());
-            }
-            if amount <= 0 {
-                return Err(BorrowError::InvalidAmount.into());
-            }
-
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Borrow)?;
-
-            let user_addr = Address::from_string(user);
-
-            // For cross-asset borrowing, we would need to implement cross-asset position handling
-            // This is a simplified version for the modular structure
-            let mut position = match StateHelper::get_position(env, &user_addr) {
-                Some(pos) => pos,
-                None => return Err(BorrowError::PositionNotFound.into()),
-            };
-
-            // Check collateral ratio
-            let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-            let new_debt = position.debt + amount;
-            let collateral_ratio = if new_debt > 0 {
-                (position.collateral * 100) / new_debt
-            } else {
-                0
-            };
-
-            if collateral_ratio < min_ratio {
-                return Err(BorrowError::InsufficientCollateralRatio.into());
-            }
-
-            // Update position
-            position.debt = new_debt;
-            StateHelper::save_position(env, &position);
-
-            // Emit cross-asset borrow event
-            ProtocolEvent::CrossBorrow(user_addr, asset.clone(), amount).emit(env);
-
-            Ok(())
-        })();
-
-        ReentrancyGuard::exit(env);
-        result
-    }
-
-    /// Validate borrow parameters
-    pub fn validate_borrow_params(params: &BorrowParams) -> Result<(), BorrowError> {
-        if params.amount <= 0 {
-            return Err(BorrowError::InvalidAmount);
+        let _guard = ReentrancyScope::enter(env)?;
+        user.require_auth();
+        if amount <= 0 {
+            return Err(BorrowError::InvalidAmount.into());
         }
-        Ok(())
-    }
 
-    /// Calculate maximum borrowable amount based on collateral
-    pub fn calculate_max_borrowable(
-        collateral: i128,
-        current_debt: i128,
-        min_collateral_ratio: i128,
-    ) -> i128 {
-        if min_collateral_ratio <= 0 {
-            return 0;
+        ComplianceGate::check_market(env, user, asset, OperationKind::Borrow, amount)?;
+
+        // For cross-asset borrowing, we would need to implement cross-asset position handling
+        // This is a simplified version for the modular structure
+        let mut position = match StateHelper::get_position(env, user) {
+            Some(pos) => pos,
+            None => return Err(BorrowError::PositionNotFound.into()),
+        };
+
+        BorrowLimits::ensure_can_borrow(env, amount)?;
+        BorrowLimits::ensure_market_active(env, asset)?;
+        RecoveryMode::ensure_borrowable(env, asset)?;
+        BorrowLimits::ensure_no_breaker_cooldown(env, asset)?;
+        BorrowLimits::ensure_not_same_ledger(env, user)?;
+        if CrossAssetManager::is_price_stale(env, asset) {
+            return Err(ProtocolError::OracleStale);
         }
+        CrossAssetManager::ensure_prices_fresh(env, user)?;
 
-        let max_debt = (collateral * 100) / min_collateral_ratio;
-        if max_debt > current_debt {
-            max_debt - current_debt
-        } else {
-            0
-        }
-    }
+        // Check risk-adjusted collateral ratio across all collateral assets, keeping the
+        // health factor buffer above the liquidation threshold
+        let min_ratio = ProtocolConfig::get_action_min_ratio(env);
+        let new_debt = position.debt + amount;
+        let collateral_ratio =
+            CrossAssetManager::collateral_ratio(env, user, position.collateral, new_debt)?;
 
-    /// Check if borrow is allowed based on collateral ratio
-    pub fn is_borrow_allowed(
-        collateral: i128,
-        current_debt: i128,
-        borrow_amount: i128,
-        min_collateral_ratio: i128,
-    ) -> bool {
-        let new_debt = current_debt + borrow_amount;
-        if new_debt == 0 {
-            return true;
+        if collateral_ratio < min_ratio {
+            return Err(BorrowError::InsufficientCollateralRatio.into());
         }
 
-        let collateral_ratio = (collateral * 100) / new_debt;
-        collateral_ratio >= min_collateral_ratio
+        // Update position
+        position.debt = new_debt;
+        StateHelper::save_position(env, &position);
+        GracePeriod::record_borrow(env, user, asset, amount);
+        BorrowLimits::record_ledger_action(env, user, asset);
+
+        // Emit cross-asset borrow event
+        ProtocolEvent::CrossBorrow(user.clone(), asset.clone(), amount).emit(env);
+
+        Ok(())
     }
 }
@@ -0,0 +1,145 @@
+//! Compliance module for StellarLend protocol
+//! One gate every core flow (deposit, borrow, repay, withdraw, liquidate) passes through,
+//! so emergency status, pause switches and the acting user's KYC/AML profile are
+//! enforced the same way wherever funds move. Each market also carries its own pause
+//! switches, so a risky borrow side can be stopped without locking its suppliers in.
+//! Repayment is never pausable: a borrower must always be able to reduce their risk.
+
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{
+    EmergencyManager, OperationKind, ProtocolError, RiskConfigStorage, TokenRegistry, UserManager,
+};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Pause switches of one market, each stopping a single flow in that asset only
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct MarketPauses {
+    pub deposit: bool,
+    pub withdraw: bool,
+    pub borrow: bool,
+    pub liquidate: bool,
+}
+
+impl MarketPauses {
+    fn blocks(&self, action: OperationKind) -> bool {
+        match action {
+            OperationKind::Deposit => self.deposit,
+            OperationKind::Withdraw => self.withdraw,
+            OperationKind::Borrow => self.borrow,
+            OperationKind::Liquidate => self.liquidate,
+            _ => false,
+        }
+    }
+}
+
+/// Storage helper for per-market pause switches
+pub struct MarketPauseStorage;
+
+impl MarketPauseStorage {
+    fn key(asset: &Address) -> DataKey {
+        DataKey::MarketPauses(asset.clone())
+    }
+
+    pub fn get(env: &Env, asset: &Address) -> MarketPauses {
+        env.storage()
+            .instance()
+            .get(&Self::key(asset))
+            .unwrap_or_default()
+    }
+
+    pub fn save(env: &Env, asset: &Address, pauses: &MarketPauses) {
+        if *pauses == MarketPauses::default() {
+            env.storage().instance().remove(&Self::key(asset));
+        } else {
+            env.storage().instance().set(&Self::key(asset), pauses);
+        }
+    }
+}
+
+/// Shared policy checks for core protocol flows
+pub struct ComplianceGate;
+
+impl ComplianceGate {
+    /// Fail unless `user` may perform `action` for `amount` of the primary asset right
+    /// now: the protocol must not be paused or in recovery for the action, neither the
+    /// action's risk pause switch nor the primary market's must be on, and the user's
+    /// profile must pass verification, freeze and limit checks
+    pub fn check(
+        env: &Env,
+        user: &Address,
+        action: OperationKind,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        EmergencyManager::ensure_operation_allowed(env, action)?;
+        if Self::is_paused(env, action) {
+            return Err(ProtocolError::ProtocolPaused);
+        }
+        if let Ok(primary) = TokenRegistry::require_primary_asset(env) {
+            Self::ensure_market_open(env, &primary, action)?;
+        }
+        UserManager::ensure_operation_allowed(env, user, action, amount)
+    }
+
+    /// `check` for a flow in `asset`'s market instead of the primary one
+    pub fn check_market(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        action: OperationKind,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        EmergencyManager::ensure_operation_allowed(env, action)?;
+        if Self::is_paused(env, action) {
+            return Err(ProtocolError::ProtocolPaused);
+        }
+        Self::ensure_market_open(env, asset, action)?;
+        UserManager::ensure_operation_allowed(env, user, action, amount)
+    }
+
+    /// Fail if `asset`'s market has `action` paused
+    pub fn ensure_market_open(
+        env: &Env,
+        asset: &Address,
+        action: OperationKind,
+    ) -> Result<(), ProtocolError> {
+        if MarketPauseStorage::get(env, asset).blocks(action) {
+            return Err(ProtocolError::ProtocolPaused);
+        }
+        Ok(())
+    }
+
+    /// Admin or emergency manager: set the pause switches of `asset`'s market
+    pub fn set_market_pauses(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        pauses: MarketPauses,
+    ) -> Result<(), ProtocolError> {
+        EmergencyManager::ensure_authorized(env, caller)?;
+        MarketPauseStorage::save(env, asset, &pauses);
+        env.publish_event(
+            (Symbol::new(env, "market_pauses_set"), asset.clone()),
+            (
+                caller.clone(),
+                pauses.deposit,
+                pauses.withdraw,
+                pauses.borrow,
+                pauses.liquidate,
+            ),
+        );
+        Ok(())
+    }
+
+    fn is_paused(env: &Env, action: OperationKind) -> bool {
+        let risk_config = RiskConfigStorage::get(env);
+        match action {
+            OperationKind::Deposit => risk_config.pause_deposit,
+            OperationKind::Borrow => risk_config.pause_borrow,
+            OperationKind::Withdraw => risk_config.pause_withdraw,
+            OperationKind::Liquidate => risk_config.pause_liquidate,
+            _ => false,
+        }
+    }
+}
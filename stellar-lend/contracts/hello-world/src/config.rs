@@ -0,0 +1,289 @@
+//! Configuration module for StellarLend protocol
+//! Snapshots of the live rate and risk parameters that governance proposals can carry,
+//! so reviewers can see exactly what a proposal would change, and a numbered history of
+//! activated configurations that can be rolled back to
+
+use crate::events::SequencedEvents;
+use crate::governance::{GovStorage, Governance, Proposal};
+use crate::storage::DataKey;
+use crate::{InterestRateStorage, ProtocolConfig, ProtocolError, ProtocolEvent, RiskConfigStorage};
+use alloc::string::ToString;
+use soroban_sdk::{contracttype, Address, Env, String, Symbol, Vec};
+
+/// Rate and risk parameters that govern the core flows
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ProtocolConfiguration {
+    /// Minimum collateral ratio in percent
+    pub min_collateral_ratio: i128,
+    /// Interest rate model (scaled by 1e8)
+    pub base_rate: i128,
+    pub kink_utilization: i128,
+    pub multiplier: i128,
+    pub reserve_factor: i128,
+    pub rate_ceiling: i128,
+    pub rate_floor: i128,
+    /// Liquidation parameters (scaled by 1e8)
+    pub close_factor: i128,
+    pub liquidation_incentive: i128,
+}
+
+impl ProtocolConfiguration {
+    /// Field names and values in declaration order
+    fn fields(&self) -> [(&'static str, i128); 9] {
+        [
+            ("min_collateral_ratio", self.min_collateral_ratio),
+            ("base_rate", self.base_rate),
+            ("kink_utilization", self.kink_utilization),
+            ("multiplier", self.multiplier),
+            ("reserve_factor", self.reserve_factor),
+            ("rate_ceiling", self.rate_ceiling),
+            ("rate_floor", self.rate_floor),
+            ("close_factor", self.close_factor),
+            ("liquidation_incentive", self.liquidation_incentive),
+        ]
+    }
+}
+
+/// A configuration as it was activated
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ConfigVersion {
+    pub version: u32,
+    pub config: ProtocolConfiguration,
+    pub activated_at: u64,
+}
+
+/// Storage helper for proposed configurations and the version history
+pub struct ConfigStorage;
+
+impl ConfigStorage {
+    fn proposal_key(proposal_id: u64) -> DataKey {
+        DataKey::ConfigProposal(proposal_id)
+    }
+
+    pub fn get_proposed(env: &Env, proposal_id: u64) -> Option<ProtocolConfiguration> {
+        env.storage()
+            .persistent()
+            .get(&Self::proposal_key(proposal_id))
+    }
+
+    pub fn save_proposed(env: &Env, proposal_id: u64, config: &ProtocolConfiguration) {
+        env.storage()
+            .persistent()
+            .set(&Self::proposal_key(proposal_id), config);
+    }
+
+    fn version_key(version: u32) -> DataKey {
+        DataKey::ConfigVersion(version)
+    }
+
+    fn latest_version_key() -> DataKey {
+        DataKey::ConfigLatestVersion
+    }
+
+    /// Number of the active configuration version, zero before the first activation
+    pub fn latest_version(env: &Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Self::latest_version_key())
+            .unwrap_or(0)
+    }
+
+    pub fn get_version(env: &Env, version: u32) -> Option<ConfigVersion> {
+        env.storage().persistent().get(&Self::version_key(version))
+    }
+
+    pub fn save_version(env: &Env, entry: &ConfigVersion) {
+        env.storage()
+            .persistent()
+            .set(&Self::version_key(entry.version), entry);
+        env.storage()
+            .instance()
+            .set(&Self::latest_version_key(), &entry.version);
+    }
+}
+
+/// Configuration snapshots and governance proposals over them
+pub struct ConfigManager;
+
+impl ConfigManager {
+    /// Configuration the core flows currently read
+    pub fn current(env: &Env) -> ProtocolConfiguration {
+        let rates = InterestRateStorage::get_config(env);
+        let risk = RiskConfigStorage::get(env);
+        ProtocolConfiguration {
+            min_collateral_ratio: ProtocolConfig::get_min_collateral_ratio(env),
+            base_rate: rates.base_rate,
+            kink_utilization: rates.kink_utilization,
+            multiplier: rates.multiplier,
+            reserve_factor: rates.reserve_factor,
+            rate_ceiling: rates.rate_ceiling,
+            rate_floor: rates.rate_floor,
+            close_factor: risk.close_factor,
+            liquidation_incentive: risk.liquidation_incentive,
+        }
+    }
+
+    /// Reject configurations the individual setters would not accept
+    pub fn validate(config: &ProtocolConfiguration) -> Result<(), ProtocolError> {
+        let scale = 100000000;
+        if config.min_collateral_ratio <= 0
+            || config.base_rate < 0
+            || config.multiplier < 0
+            || config.kink_utilization <= 0
+            || config.kink_utilization > scale
+            || !(0..=scale).contains(&config.reserve_factor)
+            || config.rate_floor < 0
+            || config.rate_floor > config.rate_ceiling
+            || config.close_factor <= 0
+            || config.close_factor > scale
+            || !(0..=scale).contains(&config.liquidation_incentive)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        Ok(())
+    }
+
+    fn record_version(env: &Env, config: &ProtocolConfiguration) -> u32 {
+        let version = ConfigStorage::latest_version(env) + 1;
+        ConfigStorage::save_version(
+            env,
+            &ConfigVersion {
+                version,
+                config: config.clone(),
+                activated_at: env.ledger().timestamp(),
+            },
+        );
+        version
+    }
+
+    /// Write `config` into the storage the core flows read and record it as a new
+    /// version, reporting every changed parameter against `changed_by`. The configuration
+    /// in force before the first activation is kept as version 1 so it can be restored.
+    pub fn activate(env: &Env, changed_by: &Address, config: &ProtocolConfiguration) -> u32 {
+        let previous = Self::current(env);
+        if ConfigStorage::latest_version(env) == 0 {
+            Self::record_version(env, &previous);
+        }
+        for ((name, old), (_, new)) in previous.fields().into_iter().zip(config.fields()) {
+            if old != new {
+                ProtocolEvent::ConfigUpdated(changed_by.clone(), Symbol::new(env, name), old, new)
+                    .emit(env);
+            }
+        }
+
+        // Settle accrual under the outgoing rate model first
+        InterestRateStorage::update_state(env);
+        let now = env.ledger().timestamp();
+        let mut rates = InterestRateStorage::get_config(env);
+        rates.base_rate = config.base_rate;
+        rates.kink_utilization = config.kink_utilization;
+        rates.multiplier = config.multiplier;
+        rates.reserve_factor = config.reserve_factor;
+        rates.rate_ceiling = config.rate_ceiling;
+        rates.rate_floor = config.rate_floor;
+        rates.last_update = now;
+        InterestRateStorage::save_config(env, &rates);
+
+        let mut risk = RiskConfigStorage::get(env);
+        risk.close_factor = config.close_factor;
+        risk.liquidation_incentive = config.liquidation_incentive;
+        risk.last_update = now;
+        RiskConfigStorage::save(env, &risk);
+        ProtocolConfig::save_min_collateral_ratio(env, config.min_collateral_ratio);
+
+        let version = Self::record_version(env, config);
+        env.publish_event((Symbol::new(env, "config_activated"),), version);
+        version
+    }
+
+    /// Admin: validate and activate `config` directly, bypassing governance
+    pub fn apply(
+        env: &Env,
+        caller: &Address,
+        config: &ProtocolConfiguration,
+    ) -> Result<u32, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        Self::validate(config)?;
+        Ok(Self::activate(env, caller, config))
+    }
+
+    /// Reactivate historic `version` as a new version after checking it against the
+    /// current validation rules
+    pub fn rollback(env: &Env, caller: &Address, version: u32) -> Result<u32, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let entry = ConfigStorage::get_version(env, version).ok_or(ProtocolError::NotFound)?;
+        Self::validate(&entry.config)?;
+        let new_version = Self::activate(env, caller, &entry.config);
+        env.publish_event(
+            (
+                Symbol::new(env, "configuration_rolled_back"),
+                caller.clone(),
+            ),
+            (version, new_version),
+        );
+        Ok(new_version)
+    }
+
+    /// Open a governance proposal that would switch the protocol to `config`
+    pub fn propose(
+        env: &Env,
+        proposer: &Address,
+        title: String,
+        voting_period_secs: u64,
+        config: ProtocolConfiguration,
+    ) -> Result<u64, ProtocolError> {
+        Self::validate(&config)?;
+        let proposal = Governance::propose(env, proposer, title, voting_period_secs);
+        ConfigStorage::save_proposed(env, proposal.id, &config);
+        env.publish_event(
+            (Symbol::new(env, "config_proposed"), proposer.clone()),
+            proposal.id,
+        );
+        Ok(proposal.id)
+    }
+
+    /// Execute a proposal whose timelock has passed; a configuration attached to it is
+    /// re-validated and activated in the same call so the vote takes effect immediately
+    pub fn execute_proposal(env: &Env, proposal_id: u64) -> Result<Proposal, ProtocolError> {
+        let before = GovStorage::get_proposal(env, proposal_id).ok_or(ProtocolError::NotFound)?;
+        let proposal = Governance::execute(env, proposal_id);
+        if proposal.executed && !before.executed {
+            if let Some(config) = ConfigStorage::get_proposed(env, proposal_id) {
+                Self::validate(&config)?;
+                let version = Self::activate(env, &proposal.proposer, &config);
+                env.publish_event(
+                    (Symbol::new(env, "proposal_config_applied"),),
+                    (proposal_id, version),
+                );
+            }
+        }
+        Ok(proposal)
+    }
+
+    /// Fields a proposal would change, as (field_name, current_value, proposed_value)
+    pub fn diff(
+        env: &Env,
+        proposal_id: u64,
+    ) -> Result<Vec<(String, String, String)>, ProtocolError> {
+        if GovStorage::get_proposal(env, proposal_id).is_none() {
+            return Err(ProtocolError::NotFound);
+        }
+        let proposed =
+            ConfigStorage::get_proposed(env, proposal_id).ok_or(ProtocolError::NotFound)?;
+        let current = Self::current(env);
+
+        let mut changes = Vec::new(env);
+        for ((name, old), (_, new)) in current.fields().into_iter().zip(proposed.fields()) {
+            if old != new {
+                changes.push_back((
+                    String::from_str(env, name),
+                    String::from_str(env, &old.to_string()),
+                    String::from_str(env, &new.to_string()),
+                ));
+            }
+        }
+        Ok(changes)
+    }
+}
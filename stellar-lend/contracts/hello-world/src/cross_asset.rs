@@ -0,0 +1,1069 @@
+//! Cross-asset module for StellarLend protocol
+//! Tracks collateral held in non-primary assets and values the whole portfolio
+//! using per-asset risk weights (collateral factors)
+
+use crate::adapter::CollateralAdapters;
+use crate::events::SequencedEvents;
+use crate::external::ExternalCall;
+use crate::limits::BorrowLimits;
+use crate::oracle::{Oracle, PriceTier};
+use crate::recovery::RecoveryMode;
+use crate::rewards::{RewardManager, RewardSide};
+use crate::storage::DataKey;
+use crate::ttl::Ttl;
+use crate::withdraw::WithdrawModule;
+use crate::{
+    ProtocolConfig, ProtocolError, ProtocolEvent, SafeMath, StateHelper, TokenRegistry,
+    UserManager, UserRole,
+};
+use soroban_sdk::{contracttype, token::TokenClient, vec, Address, Env, IntoVal, Map, Symbol, Vec};
+
+/// Risk parameters for an asset accepted as collateral
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AssetParams {
+    /// Share of the asset's value counted toward borrowing power (scaled by 1e8)
+    pub collateral_factor: i128,
+    /// Deposits enabled for this asset
+    pub deposit_enabled: bool,
+    /// Transfers may deliver less than the requested amount (fee-on-transfer or rebasing);
+    /// such assets are rejected unless this is set, and then only the received amount is credited
+    pub fee_on_transfer: bool,
+}
+
+/// Where an asset comes from, so bridged wrappers of the same token can be told apart
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AssetProvenance {
+    /// Logical asset this token represents, e.g. `USDC`
+    pub logical_asset: Symbol,
+    pub issuer: Address,
+    /// Bridge contract that minted the wrapper, if any
+    pub bridge: Option<Address>,
+    /// Canonical issuance; non-canonical wrappers get the wrapper haircut on their LTV
+    pub canonical: bool,
+}
+
+/// Per-asset collateral balances held outside the primary position
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CrossPosition {
+    pub user: Address,
+    /// Collateral balances by asset
+    pub collateral: Map<Address, i128>,
+    pub last_update: u64,
+}
+
+impl CrossPosition {
+    pub fn new(env: &Env, user: Address) -> Self {
+        Self {
+            user,
+            collateral: Map::new(env),
+            last_update: 0,
+        }
+    }
+}
+
+/// Storage helper for asset parameters and prices
+pub struct AssetRegistryStorage;
+
+impl AssetRegistryStorage {
+    fn params_key() -> DataKey {
+        DataKey::AssetParams
+    }
+
+    fn prices_key() -> DataKey {
+        DataKey::AssetPrices
+    }
+
+    fn price_times_key() -> DataKey {
+        DataKey::AssetPriceTimes
+    }
+
+    fn provenance_key() -> DataKey {
+        DataKey::AssetProvenance
+    }
+
+    fn wrapper_haircut_key() -> DataKey {
+        DataKey::WrapperHaircut
+    }
+
+    fn interest_collateral_key() -> DataKey {
+        DataKey::InterestCollateral
+    }
+
+    fn dex_adapters_key() -> DataKey {
+        DataKey::DexAdapters
+    }
+
+    fn confidences_key() -> DataKey {
+        DataKey::AssetConfidences
+    }
+
+    fn confidence_k_key() -> DataKey {
+        DataKey::ConfidenceK
+    }
+
+    /// Latest oracle confidence interval per asset, in the 1e8 price scale
+    pub fn get_confidences(env: &Env) -> Map<Address, i128> {
+        env.storage()
+            .instance()
+            .get(&Self::confidences_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_confidences(env: &Env, map: &Map<Address, i128>) {
+        env.storage().instance().set(&Self::confidences_key(), map);
+    }
+
+    /// Confidence intervals subtracted from prices for borrowing power (scaled by 1e8)
+    pub fn get_confidence_k(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::confidence_k_key())
+            .unwrap_or(0)
+    }
+
+    pub fn set_confidence_k(env: &Env, k: i128) {
+        env.storage().instance().set(&Self::confidence_k_key(), &k);
+    }
+
+    pub fn get_params_map(env: &Env) -> Map<Address, AssetParams> {
+        env.storage()
+            .instance()
+            .get(&Self::params_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_params_map(env: &Env, map: &Map<Address, AssetParams>) {
+        env.storage().instance().set(&Self::params_key(), map);
+    }
+
+    pub fn get_prices_map(env: &Env) -> Map<Address, i128> {
+        env.storage()
+            .instance()
+            .get(&Self::prices_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_prices_map(env: &Env, map: &Map<Address, i128>) {
+        env.storage().instance().set(&Self::prices_key(), map);
+    }
+
+    /// Timestamp of the last price update per asset
+    pub fn get_price_times(env: &Env) -> Map<Address, u64> {
+        env.storage()
+            .instance()
+            .get(&Self::price_times_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_price_times(env: &Env, map: &Map<Address, u64>) {
+        env.storage().instance().set(&Self::price_times_key(), map);
+    }
+
+    pub fn get_provenance_map(env: &Env) -> Map<Address, AssetProvenance> {
+        env.storage()
+            .instance()
+            .get(&Self::provenance_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_provenance_map(env: &Env, map: &Map<Address, AssetProvenance>) {
+        env.storage().instance().set(&Self::provenance_key(), map);
+    }
+
+    /// Assets whose accrued supply interest counts as collateral
+    pub fn get_interest_collateral(env: &Env) -> Map<Address, bool> {
+        env.storage()
+            .instance()
+            .get(&Self::interest_collateral_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_interest_collateral(env: &Env, map: &Map<Address, bool>) {
+        env.storage()
+            .instance()
+            .set(&Self::interest_collateral_key(), map);
+    }
+
+    pub fn get_dex_adapters(env: &Env) -> Map<Address, bool> {
+        env.storage()
+            .instance()
+            .get(&Self::dex_adapters_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_dex_adapters(env: &Env, map: &Map<Address, bool>) {
+        env.storage().instance().set(&Self::dex_adapters_key(), map);
+    }
+
+    /// Share of the collateral factor removed for non-canonical wrappers (scaled by 1e8)
+    pub fn get_wrapper_haircut(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::wrapper_haircut_key())
+            .unwrap_or(0)
+    }
+
+    pub fn set_wrapper_haircut(env: &Env, haircut: i128) {
+        env.storage()
+            .instance()
+            .set(&Self::wrapper_haircut_key(), &haircut);
+    }
+}
+
+/// Storage helper for cross-asset positions
+pub struct CrossStateHelper;
+
+impl CrossStateHelper {
+    fn position_key(user: &Address) -> DataKey {
+        DataKey::CrossPosition(user.clone())
+    }
+
+    pub fn get_or_init_position(env: &Env, user: &Address) -> CrossPosition {
+        env.storage()
+            .persistent()
+            .get(&Self::position_key(user))
+            .unwrap_or_else(|| CrossPosition::new(env, user.clone()))
+    }
+
+    pub fn remove_position(env: &Env, user: &Address) {
+        env.storage().persistent().remove(&Self::position_key(user));
+    }
+
+    /// Persist cross-asset balances, keeping the user's market list and reward
+    /// checkpoints in step with them
+    pub fn save_position(env: &Env, position: &CrossPosition) {
+        let old = Self::get_or_init_position(env, &position.user).collateral;
+        let key = Self::position_key(&position.user);
+        env.storage().persistent().set(&key, position);
+        Ttl::extend_entry(env, &key);
+        for (asset, amount) in position.collateral.iter() {
+            UserMarkets::sync(env, &position.user, &asset, amount > 0);
+            if old.get(asset.clone()).unwrap_or(0) != amount {
+                RewardManager::checkpoint(env, &position.user, &asset, RewardSide::Supply, amount);
+            }
+        }
+        for (asset, amount) in old.iter() {
+            if amount != 0 && !position.collateral.contains_key(asset.clone()) {
+                RewardManager::checkpoint(env, &position.user, &asset, RewardSide::Supply, 0);
+            }
+        }
+    }
+}
+
+/// Assets a user has opted out of counting as collateral; everything is on by default
+pub struct CollateralUsageStorage;
+
+impl CollateralUsageStorage {
+    fn key(user: &Address) -> DataKey {
+        DataKey::CollateralDisabled(user.clone())
+    }
+
+    pub fn get_disabled(env: &Env, user: &Address) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&Self::key(user))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn put_disabled(env: &Env, user: &Address, disabled: &Vec<Address>) {
+        let key = Self::key(user);
+        if disabled.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, disabled);
+        }
+    }
+}
+
+/// Markets (assets) each user currently holds collateral or debt in
+pub struct UserMarkets;
+
+impl UserMarkets {
+    fn key(user: &Address) -> DataKey {
+        DataKey::UserMarkets(user.clone())
+    }
+
+    pub fn get(env: &Env, user: &Address) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&Self::key(user))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Enter `asset` when the user holds a balance in it and exit it once that balance closes
+    pub fn sync(env: &Env, user: &Address, asset: &Address, active: bool) {
+        let mut markets = Self::get(env, user);
+        match (markets.first_index_of(asset), active) {
+            (None, true) => {
+                markets.push_back(asset.clone());
+                env.publish_event(
+                    (Symbol::new(env, "market_entered"), user.clone()),
+                    asset.clone(),
+                );
+            }
+            (Some(index), false) => {
+                markets.remove(index);
+                env.publish_event(
+                    (Symbol::new(env, "market_exited"), user.clone()),
+                    asset.clone(),
+                );
+            }
+            _ => return,
+        }
+
+        let key = Self::key(user);
+        if markets.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &markets);
+        }
+    }
+}
+
+/// Cross-asset collateral accounting and risk-adjusted valuation
+pub struct CrossAssetManager;
+
+impl CrossAssetManager {
+    /// Add or update an asset's risk weight (risk manager or admin)
+    pub fn set_asset_params(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        collateral_factor: i128,
+        deposit_enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        UserManager::ensure_can_manage(env, caller, UserRole::Manager)?;
+        if !(0..=100000000).contains(&collateral_factor) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut map = AssetRegistryStorage::get_params_map(env);
+        let fee_on_transfer = map
+            .get(asset.clone())
+            .map(|params| params.fee_on_transfer)
+            .unwrap_or(false);
+        map.set(
+            asset.clone(),
+            AssetParams {
+                collateral_factor,
+                deposit_enabled,
+                fee_on_transfer,
+            },
+        );
+        AssetRegistryStorage::put_params_map(env, &map);
+        ProtocolEvent::DynamicCFUpdated(asset.clone(), collateral_factor).emit(env);
+        Ok(())
+    }
+
+    /// Allow or forbid deposits of an asset whose transfers deliver less than requested.
+    /// The asset must already have parameters, except the primary asset which gets
+    /// unweighted defaults.
+    pub fn set_fee_on_transfer(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        allowed: bool,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut map = AssetRegistryStorage::get_params_map(env);
+        let mut params = match map.get(asset.clone()) {
+            Some(params) => params,
+            None if TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(asset) => {
+                AssetParams {
+                    collateral_factor: 100000000,
+                    deposit_enabled: true,
+                    fee_on_transfer: false,
+                }
+            }
+            None => return Err(ProtocolError::AssetNotSupported),
+        };
+        params.fee_on_transfer = allowed;
+        map.set(asset.clone(), params);
+        AssetRegistryStorage::put_params_map(env, &map);
+        env.publish_event(
+            (Symbol::new(env, "fee_on_transfer_updated"), asset.clone()),
+            allowed,
+        );
+        Ok(())
+    }
+
+    /// Whether short-delivering transfers of `asset` are accepted
+    pub fn allows_fee_on_transfer(env: &Env, asset: &Address) -> bool {
+        AssetRegistryStorage::get_params_map(env)
+            .get(asset.clone())
+            .map(|params| params.fee_on_transfer)
+            .unwrap_or(false)
+    }
+
+    /// Set price for an asset in 1e8 scale, denominated in the primary asset
+    pub fn set_asset_price(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        price: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if price <= 0 {
+            return Err(ProtocolError::InvalidInput);
+        }
+        // A manual price carries no confidence figure
+        Self::record_price(env, asset, price, 0);
+        Ok(())
+    }
+
+    fn record_price(env: &Env, asset: &Address, price: i128, confidence: i128) {
+        let mut map = AssetRegistryStorage::get_prices_map(env);
+        map.set(asset.clone(), price);
+        AssetRegistryStorage::put_prices_map(env, &map);
+        let mut times = AssetRegistryStorage::get_price_times(env);
+        times.set(asset.clone(), env.ledger().timestamp());
+        AssetRegistryStorage::put_price_times(env, &times);
+        let mut confidences = AssetRegistryStorage::get_confidences(env);
+        if confidence > 0 {
+            confidences.set(asset.clone(), confidence);
+        } else {
+            confidences.remove(asset.clone());
+        }
+        AssetRegistryStorage::put_confidences(env, &confidences);
+    }
+
+    /// Refresh `asset`'s price and confidence interval, failing over from its registered
+    /// oracle sources to its secondary feed when they are stale or move further than the
+    /// asset's max deviation from the last price. Only when no feed answers at all is the
+    /// asset's fallback price, if any, recorded instead; a static price never overrides
+    /// live feeds that disagree with the last one.
+    pub fn sync_oracle_price(env: &Env, asset: &Address) -> Result<(i128, i128), ProtocolError> {
+        Self::get_asset_params(env, asset)?;
+        let config = Oracle::effective_config(env, asset);
+        let mut out_of_bounds = false;
+        let mut served = None;
+        if let Some(price) = Oracle::aggregate_price(env, asset).filter(|price| *price > 0) {
+            if Self::ensure_within_deviation(env, asset, price, config.max_deviation_bps).is_ok() {
+                let confidence = Oracle::aggregate_confidence(env, asset);
+                served = Some((PriceTier::Primary, price, confidence));
+            } else {
+                out_of_bounds = true;
+            }
+        }
+        if served.is_none() {
+            if let Some((price, confidence)) = Oracle::secondary_price(env, asset) {
+                if Self::ensure_within_deviation(env, asset, price, config.max_deviation_bps)
+                    .is_ok()
+                {
+                    served = Some((PriceTier::Secondary, price, confidence));
+                } else {
+                    out_of_bounds = true;
+                }
+            }
+        }
+        let (tier, price, confidence) = match served {
+            Some(served) => served,
+            None if !out_of_bounds && config.fallback_price > 0 => {
+                (PriceTier::Fallback, config.fallback_price, 0)
+            }
+            None => return Err(ProtocolError::OracleFailure),
+        };
+
+        Self::record_price(env, asset, price, confidence);
+        env.publish_event(
+            (Symbol::new(env, "asset_price_synced"), asset.clone()),
+            (price, confidence),
+        );
+        ProtocolEvent::PriceServed(asset.clone(), tier, price, confidence).emit(env);
+        Ok((price, confidence))
+    }
+
+    /// Record a price and confidence interval pushed in a verified signed payload, subject
+    /// to the same max deviation as synced prices
+    pub fn record_pushed_price(
+        env: &Env,
+        asset: &Address,
+        price: i128,
+        confidence: i128,
+    ) -> Result<(), ProtocolError> {
+        Self::get_asset_params(env, asset)?;
+        if price <= 0 || confidence < 0 {
+            return Err(ProtocolError::InvalidInput);
+        }
+        let max_deviation_bps = Oracle::effective_config(env, asset).max_deviation_bps;
+        Self::ensure_within_deviation(env, asset, price, max_deviation_bps)?;
+        Self::record_price(env, asset, price, confidence);
+        Ok(())
+    }
+
+    /// Refuse a price moving further than `max_deviation_bps` from the last recorded one
+    fn ensure_within_deviation(
+        env: &Env,
+        asset: &Address,
+        price: i128,
+        max_deviation_bps: i128,
+    ) -> Result<(), ProtocolError> {
+        if let Some(last) = AssetRegistryStorage::get_prices_map(env).get(asset.clone()) {
+            if max_deviation_bps > 0 && (price - last).abs() * 10000 > last * max_deviation_bps {
+                return Err(ProtocolError::OracleFailure);
+            }
+        }
+        Ok(())
+    }
+
+    /// Set how many confidence intervals are subtracted from collateral prices when
+    /// computing borrowing power (scaled by 1e8; 0 disables the discount)
+    pub fn set_confidence_multiplier(
+        env: &Env,
+        caller: &Address,
+        k: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=1000000000).contains(&k) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        AssetRegistryStorage::set_confidence_k(env, k);
+        env.publish_event(
+            (
+                Symbol::new(env, "confidence_multiplier_updated"),
+                caller.clone(),
+            ),
+            k,
+        );
+        Ok(())
+    }
+
+    /// Price used for borrowing power: the oracle price less k confidence intervals
+    fn borrowing_price(env: &Env, asset: &Address, price: i128) -> i128 {
+        let k = AssetRegistryStorage::get_confidence_k(env);
+        if k == 0 {
+            return price;
+        }
+        let confidence = AssetRegistryStorage::get_confidences(env)
+            .get(asset.clone())
+            .unwrap_or(0);
+        (price - SafeMath::mul_div(confidence, k, 100000000)).max(0)
+    }
+
+    /// Whether `asset`'s price is older than its oracle heartbeat. The primary asset is
+    /// the unit of account and never goes stale.
+    pub fn is_price_stale(env: &Env, asset: &Address) -> bool {
+        match AssetRegistryStorage::get_price_times(env).get(asset.clone()) {
+            Some(updated_at) => {
+                env.ledger().timestamp().saturating_sub(updated_at) > Oracle::heartbeat(env, asset)
+            }
+            None => false,
+        }
+    }
+
+    /// Reject risk-increasing actions while any collateral the user borrows against is
+    /// priced from a stale feed
+    pub fn ensure_prices_fresh(env: &Env, user: &Address) -> Result<(), ProtocolError> {
+        let disabled = CollateralUsageStorage::get_disabled(env, user);
+        let cross = CrossStateHelper::get_or_init_position(env, user);
+        for (asset, amount) in cross.collateral.iter() {
+            if amount > 0 && !disabled.contains(&asset) && Self::is_price_stale(env, &asset) {
+                return Err(ProtocolError::OracleStale);
+            }
+        }
+        Ok(())
+    }
+
+    /// Record issuer, bridge and canonical status for a listed asset
+    pub fn set_asset_provenance(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        provenance: AssetProvenance,
+    ) -> Result<(), ProtocolError> {
+        UserManager::ensure_can_manage(env, caller, UserRole::Manager)?;
+        let is_primary = TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(asset);
+        if !is_primary && Self::get_asset_params(env, asset).is_err() {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+        let mut map = AssetRegistryStorage::get_provenance_map(env);
+        env.publish_event(
+            (Symbol::new(env, "asset_provenance_set"), asset.clone()),
+            (provenance.logical_asset.clone(), provenance.canonical),
+        );
+        map.set(asset.clone(), provenance);
+        AssetRegistryStorage::put_provenance_map(env, &map);
+        Ok(())
+    }
+
+    /// Set the LTV haircut applied to non-canonical wrappers (scaled by 1e8)
+    pub fn set_wrapper_haircut(
+        env: &Env,
+        caller: &Address,
+        haircut: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=100000000).contains(&haircut) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        AssetRegistryStorage::set_wrapper_haircut(env, haircut);
+        env.publish_event(
+            (Symbol::new(env, "wrapper_haircut_updated"), caller.clone()),
+            haircut,
+        );
+        Ok(())
+    }
+
+    /// Every listed token that represents `logical_asset`
+    pub fn get_wrappers(env: &Env, logical_asset: &Symbol) -> Vec<Address> {
+        let mut wrappers = Vec::new(env);
+        for (asset, provenance) in AssetRegistryStorage::get_provenance_map(env).iter() {
+            if provenance.logical_asset == *logical_asset {
+                wrappers.push_back(asset);
+            }
+        }
+        wrappers
+    }
+
+    /// Collateral factor after the haircut for non-canonical wrappers
+    fn effective_collateral_factor(env: &Env, asset: &Address, collateral_factor: i128) -> i128 {
+        match AssetRegistryStorage::get_provenance_map(env).get(asset.clone()) {
+            Some(provenance) if !provenance.canonical => {
+                let haircut = AssetRegistryStorage::get_wrapper_haircut(env);
+                collateral_factor * (100000000 - haircut) / 100000000
+            }
+            _ => collateral_factor,
+        }
+    }
+
+    pub fn get_asset_params(env: &Env, asset: &Address) -> Result<AssetParams, ProtocolError> {
+        AssetRegistryStorage::get_params_map(env)
+            .get(asset.clone())
+            .ok_or(ProtocolError::AssetNotSupported)
+    }
+
+    /// Price of `asset` in primary asset units per unit, scaled by 1e8
+    pub fn asset_price(env: &Env, asset: &Address) -> Result<i128, ProtocolError> {
+        // Yield-bearing collateral is valued by its adapter rather than a price feed
+        if let Some(adapter) = CollateralAdapters::get(env, asset) {
+            return CollateralAdapters::price(env, &adapter, asset);
+        }
+        AssetRegistryStorage::get_prices_map(env)
+            .get(asset.clone())
+            .ok_or(ProtocolError::OracleFailure)
+    }
+
+    /// Value of `units` of `asset` in primary asset units at the current price, rounded
+    /// down
+    pub fn value_of_units(env: &Env, asset: &Address, units: i128) -> Result<i128, ProtocolError> {
+        Ok(units * Self::asset_price(env, asset)? / 100000000)
+    }
+
+    /// Risk weight of the primary asset; unweighted unless a risk manager configured one
+    fn primary_collateral_factor(env: &Env) -> i128 {
+        let Ok(asset) = TokenRegistry::require_primary_asset(env) else {
+            return 100000000;
+        };
+        let factor = AssetRegistryStorage::get_params_map(env)
+            .get(asset.clone())
+            .map(|params| params.collateral_factor)
+            .unwrap_or(100000000);
+        Self::effective_collateral_factor(env, &asset, factor)
+    }
+
+    /// Collateral factor (1e8 scale) `asset` is weighted at in liquidation checks
+    pub(crate) fn liquidation_factor(env: &Env, asset: &Address) -> Result<i128, ProtocolError> {
+        if TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(asset) {
+            return Ok(Self::primary_collateral_factor(env));
+        }
+        let params = Self::get_asset_params(env, asset)?;
+        Ok(Self::effective_collateral_factor(
+            env,
+            asset,
+            params.collateral_factor,
+        ))
+    }
+
+    /// Whether `asset` counts toward the user's borrowing power and can be seized
+    pub fn is_collateral_enabled(env: &Env, user: &Address, asset: &Address) -> bool {
+        !CollateralUsageStorage::get_disabled(env, user).contains(asset)
+    }
+
+    /// Opt a supplied asset in or out of collateral use; opting out is refused if the
+    /// position would fall below the minimum collateral ratio
+    pub fn set_use_as_collateral(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        let is_primary = TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(asset);
+        if !is_primary {
+            Self::get_asset_params(env, asset)?;
+        }
+
+        let mut disabled = CollateralUsageStorage::get_disabled(env, user);
+        match (disabled.first_index_of(asset), enabled) {
+            (Some(index), true) => {
+                disabled.remove(index);
+            }
+            (None, false) => disabled.push_back(asset.clone()),
+            _ => return Ok(()),
+        }
+
+        if !enabled {
+            if let Some(position) = StateHelper::get_position(env, user) {
+                if position.debt > 0 {
+                    let cross = CrossStateHelper::get_or_init_position(env, user);
+                    let weighted = Self::weighted_value_excluding(
+                        env,
+                        position.collateral + Self::pledged_interest(env, user),
+                        &cross.collateral,
+                        &disabled,
+                        true,
+                    )?;
+                    if weighted * 100 / position.debt
+                        < ProtocolConfig::get_min_collateral_ratio(env)
+                    {
+                        return Err(ProtocolError::InsufficientCollateralRatio);
+                    }
+                }
+            }
+        }
+
+        CollateralUsageStorage::put_disabled(env, user, &disabled);
+        env.publish_event(
+            (Symbol::new(env, "collateral_usage_updated"), user.clone()),
+            (asset.clone(), enabled),
+        );
+        Ok(())
+    }
+
+    /// Risk-adjusted value of a user's collateral in primary asset units
+    pub fn weighted_collateral(
+        env: &Env,
+        user: &Address,
+        primary_collateral: i128,
+    ) -> Result<i128, ProtocolError> {
+        let cross = CrossStateHelper::get_or_init_position(env, user);
+        Self::weighted_value(env, user, primary_collateral, &cross.collateral)
+    }
+
+    /// Borrowing power of the given balances: prices are discounted by their oracle
+    /// confidence intervals so noisy feeds lend less
+    pub(crate) fn weighted_value(
+        env: &Env,
+        user: &Address,
+        primary_collateral: i128,
+        collateral: &Map<Address, i128>,
+    ) -> Result<i128, ProtocolError> {
+        let disabled = CollateralUsageStorage::get_disabled(env, user);
+        let primary_collateral = primary_collateral + Self::pledged_interest(env, user);
+        Self::weighted_value_excluding(env, primary_collateral, collateral, &disabled, true)
+    }
+
+    /// Risk-adjusted value of the given balances at undiscounted prices, for liquidation
+    /// checks, so feed noise alone never makes a position liquidatable
+    pub(crate) fn liquidation_value(
+        env: &Env,
+        user: &Address,
+        primary_collateral: i128,
+        collateral: &Map<Address, i128>,
+    ) -> Result<i128, ProtocolError> {
+        let disabled = CollateralUsageStorage::get_disabled(env, user);
+        let primary_collateral = primary_collateral + Self::pledged_interest(env, user);
+        Self::weighted_value_excluding(env, primary_collateral, collateral, &disabled, false)
+    }
+
+    /// Collateral ratio (percent) of `debt` at undiscounted prices, for liquidation checks
+    pub fn liquidation_ratio(
+        env: &Env,
+        user: &Address,
+        primary_collateral: i128,
+        debt: i128,
+    ) -> Result<i128, ProtocolError> {
+        if debt <= 0 {
+            return Ok(0);
+        }
+        let cross = CrossStateHelper::get_or_init_position(env, user);
+        Ok(Self::liquidation_value(env, user, primary_collateral, &cross.collateral)? * 100 / debt)
+    }
+
+    /// Settled but unclaimed supply interest counted as primary collateral, if the primary
+    /// asset allows it
+    pub fn pledged_interest(env: &Env, user: &Address) -> i128 {
+        let Ok(primary) = TokenRegistry::require_primary_asset(env) else {
+            return 0;
+        };
+        if !AssetRegistryStorage::get_interest_collateral(env)
+            .get(primary)
+            .unwrap_or(false)
+        {
+            return 0;
+        }
+        StateHelper::get_position(env, user)
+            .map(|position| position.supply_interest.max(0))
+            .unwrap_or(0)
+    }
+
+    /// Count accrued supply interest in `asset` toward borrowing power
+    pub fn set_interest_as_collateral(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut map = AssetRegistryStorage::get_interest_collateral(env);
+        if enabled {
+            map.set(asset.clone(), true);
+        } else {
+            map.remove(asset.clone());
+        }
+        AssetRegistryStorage::put_interest_collateral(env, &map);
+        env.publish_event(
+            (
+                Symbol::new(env, "interest_collateral_updated"),
+                asset.clone(),
+            ),
+            enabled,
+        );
+        Ok(())
+    }
+
+    /// Admin: allow or disallow `adapter` as the DEX users migrate collateral through
+    pub fn set_dex_adapter(
+        env: &Env,
+        caller: &Address,
+        adapter: &Address,
+        allowed: bool,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut map = AssetRegistryStorage::get_dex_adapters(env);
+        if allowed {
+            map.set(adapter.clone(), true);
+        } else {
+            map.remove(adapter.clone());
+        }
+        AssetRegistryStorage::put_dex_adapters(env, &map);
+        env.publish_event(
+            (Symbol::new(env, "dex_adapter_updated"), adapter.clone()),
+            allowed,
+        );
+        Ok(())
+    }
+
+    /// Whether users may migrate collateral through `adapter`
+    pub fn is_dex_adapter(env: &Env, adapter: &Address) -> bool {
+        AssetRegistryStorage::get_dex_adapters(env)
+            .get(adapter.clone())
+            .unwrap_or(false)
+    }
+
+    fn weighted_value_excluding(
+        env: &Env,
+        primary_collateral: i128,
+        collateral: &Map<Address, i128>,
+        disabled: &Vec<Address>,
+        discount_confidence: bool,
+    ) -> Result<i128, ProtocolError> {
+        let primary_enabled = TokenRegistry::require_primary_asset(env)
+            .map(|asset| !disabled.contains(&asset))
+            .unwrap_or(true);
+        let mut total = if primary_enabled {
+            primary_collateral * Self::primary_collateral_factor(env) / 100000000
+        } else {
+            0
+        };
+        for (asset, amount) in collateral.iter() {
+            if amount == 0 || disabled.contains(&asset) {
+                continue;
+            }
+            let mut price = Self::asset_price(env, &asset)?;
+            if discount_confidence {
+                price = Self::borrowing_price(env, &asset, price);
+            }
+            let params = Self::get_asset_params(env, &asset)?;
+            let factor = Self::effective_collateral_factor(env, &asset, params.collateral_factor);
+            total += amount * price / 100000000 * factor / 100000000;
+        }
+        // Recovery mode tightens borrowing power, never the liquidation threshold
+        if discount_confidence {
+            total = RecoveryMode::borrowing_power(env, total);
+        }
+        Ok(total)
+    }
+
+    /// Collateral ratio (percent) of `debt` against the user's risk-adjusted collateral
+    pub fn collateral_ratio(
+        env: &Env,
+        user: &Address,
+        primary_collateral: i128,
+        debt: i128,
+    ) -> Result<i128, ProtocolError> {
+        if debt <= 0 {
+            return Ok(0);
+        }
+        Ok(Self::weighted_collateral(env, user, primary_collateral)? * 100 / debt)
+    }
+
+    /// Pull `amount` of `asset` from the user into their cross-asset collateral
+    pub fn deposit(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        amount: i128,
+    ) -> Result<CrossPosition, ProtocolError> {
+        let params = Self::get_asset_params(env, asset)?;
+        if !params.deposit_enabled {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+
+        let mut cross = CrossStateHelper::get_or_init_position(env, user);
+        let balance = cross.collateral.get(asset.clone()).unwrap_or(0);
+        BorrowLimits::ensure_position_size(env, user, asset, balance.saturating_add(amount))?;
+
+        // Credit what actually arrived rather than trusting `amount`
+        let client = TokenClient::new(env, asset);
+        let contract = env.current_contract_address();
+        let before = client.balance(&contract);
+        client.transfer(user, &contract, &amount);
+        let received = client.balance(&contract).saturating_sub(before);
+        if received != amount && !(received > 0 && received < amount && params.fee_on_transfer) {
+            return Err(ProtocolError::BalanceInvariantViolation);
+        }
+
+        cross.collateral.set(asset.clone(), balance + received);
+        cross.last_update = env.ledger().timestamp();
+        CrossStateHelper::save_position(env, &cross);
+        Ok(cross)
+    }
+
+    /// Release `amount` of `asset` collateral if the position stays above the minimum ratio
+    pub fn withdraw(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        amount: i128,
+        primary_collateral: i128,
+        debt: i128,
+    ) -> Result<i128, ProtocolError> {
+        let mut cross = CrossStateHelper::get_or_init_position(env, user);
+        let balance = cross.collateral.get(asset.clone()).unwrap_or(0);
+        if balance < amount {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+        cross.collateral.set(asset.clone(), balance - amount);
+
+        let ratio = if debt > 0 {
+            Self::ensure_prices_fresh(env, user)?;
+            let weighted = Self::weighted_value(env, user, primary_collateral, &cross.collateral)?;
+            let ratio = weighted * 100 / debt;
+            if ratio < ProtocolConfig::get_action_min_ratio(env) {
+                return Err(ProtocolError::InsufficientCollateralRatio);
+            }
+            ratio
+        } else {
+            0
+        };
+
+        cross.last_update = env.ledger().timestamp();
+        CrossStateHelper::save_position(env, &cross);
+        TokenClient::new(env, asset).transfer(&env.current_contract_address(), user, &amount);
+        Ok(ratio)
+    }
+
+    /// Swap contract-held `from_asset` through a DEX adapter, returning the amount received.
+    /// The adapter is called as `swap(from, to, amount_in, min_out, recipient) -> i128`
+    /// after receiving the input tokens; the received amount is measured from balances.
+    pub fn swap_via_adapter(
+        env: &Env,
+        dex_adapter: &Address,
+        from_asset: &Address,
+        to_asset: &Address,
+        amount: i128,
+        min_out: i128,
+    ) -> Result<i128, ProtocolError> {
+        let contract = env.current_contract_address();
+        let to_client = TokenClient::new(env, to_asset);
+        let before = to_client.balance(&contract);
+
+        let args = vec![
+            env,
+            from_asset.clone().into_val(env),
+            to_asset.clone().into_val(env),
+            amount.into_val(env),
+            min_out.into_val(env),
+            contract.clone().into_val(env),
+        ];
+        ExternalCall::invoke::<i128, _>(env, dex_adapter, "swap", args, || {
+            TokenClient::new(env, from_asset).transfer(&contract, dex_adapter, &amount);
+            Ok(())
+        })?;
+
+        let received = to_client.balance(&contract).saturating_sub(before);
+        if received < min_out {
+            return Err(ProtocolError::SlippageExceeded);
+        }
+        Ok(received)
+    }
+
+    /// Swap `amount` of `from_asset` collateral into `to_asset` through a DEX adapter the
+    /// admin has allowed; the caller picks the adapter, so no other contract is trusted
+    /// with the protocol's collateral
+    #[allow(clippy::too_many_arguments)]
+    pub fn migrate(
+        env: &Env,
+        user: &Address,
+        from_asset: &Address,
+        to_asset: &Address,
+        amount: i128,
+        dex_adapter: &Address,
+        min_out: i128,
+        primary_collateral: i128,
+        debt: i128,
+    ) -> Result<i128, ProtocolError> {
+        if from_asset == to_asset || min_out <= 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        // Primary collateral is booked on the position, not in the cross-asset map
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        if *from_asset == primary || *to_asset == primary {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if !Self::is_dex_adapter(env, dex_adapter) {
+            return Err(ProtocolError::Unauthorized);
+        }
+        let target = Self::get_asset_params(env, to_asset)?;
+        if !target.deposit_enabled {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+
+        let mut cross = CrossStateHelper::get_or_init_position(env, user);
+        let balance = cross.collateral.get(from_asset.clone()).unwrap_or(0);
+        if balance < amount {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+
+        // The collateral leaves the book before the adapter is called
+        cross.collateral.set(from_asset.clone(), balance - amount);
+        cross.last_update = env.ledger().timestamp();
+        CrossStateHelper::save_position(env, &cross);
+
+        let received =
+            Self::swap_via_adapter(env, dex_adapter, from_asset, to_asset, amount, min_out)?;
+
+        let target_balance = cross.collateral.get(to_asset.clone()).unwrap_or(0);
+        cross
+            .collateral
+            .set(to_asset.clone(), target_balance + received);
+        CrossStateHelper::save_position(env, &cross);
+
+        // The position must come out of the swap as healthy as a withdrawal would leave it
+        WithdrawModule::ensure_can_release(env, user, primary_collateral, debt)?;
+
+        env.publish_event(
+            (Symbol::new(env, "collateral_migrated"), user.clone()),
+            (
+                from_asset.clone(),
+                amount,
+                to_asset.clone(),
+                received,
+                dex_adapter.clone(),
+            ),
+        );
+        Ok(received)
+    }
+}
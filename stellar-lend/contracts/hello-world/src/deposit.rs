@@ -1,13 +1,18 @@
 //! Deposit module for StellarLend protocol
 //! Handles collateral deposits and related functionality
 
-use crate::analytics::AnalyticsModule;
+use crate::analytics::{ActivityKind, AnalyticsModule};
+use crate::compliance::ComplianceGate;
+use crate::cross_asset::CrossAssetManager;
+use crate::history::PositionHistory;
+use crate::limits::BorrowLimits;
+use crate::session::SessionManager;
+use crate::ttl::Ttl;
 use crate::{
-    EmergencyManager, InterestRateManager, InterestRateStorage, OperationKind, Position,
-    ProtocolError, ProtocolEvent, ReentrancyGuard, RiskConfigStorage, StateHelper,
-    TransferEnforcer, UserManager,
+    ErrorContext, InterestRateManager, InterestRateStorage, OperationKind, Position, ProtocolError,
+    ProtocolEvent, ReentrancyScope, StateHelper, TokenRegistry, TransferEnforcer, UserManager,
 };
-use soroban_sdk::{contracterror, contracttype, Address, Env, String, Symbol};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol};
 
 /// Deposit-specific errors
 #[contracterror]
@@ -68,23 +73,47 @@ impl DepositModule {
         depositor: &Address,
         amount: i128,
     ) -> Result<(), ProtocolError> {
-        ReentrancyGuard::enter(env)?;
+        Self::deposit_collateral_for(env, depositor, depositor, amount)
+    }
+
+    /// Deposit collateral paid by `payer` into `depositor`'s position.
+    /// A payer other than the depositor must hold a session allowing deposits.
+    pub fn deposit_collateral_for(
+        env: &Env,
+        payer: &Address,
+        depositor: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        let _guard = ErrorContext::report(
+            env,
+            ReentrancyScope::enter(env),
+            "deposit",
+            depositor,
+            amount,
+        )?;
+        Ttl::extend_instance(env);
         let result = (|| -> Result<(), ProtocolError> {
+            payer.require_auth();
             if amount <= 0 {
                 return Err(DepositError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Deposit)?;
-
-            // Check if deposit is paused
-            let risk_config = RiskConfigStorage::get(env);
-            if risk_config.pause_deposit {
-                return Err(DepositError::ProtocolPaused.into());
-            }
+            ComplianceGate::check(env, depositor, OperationKind::Deposit, amount)?;
+            SessionManager::authorize(env, depositor, payer, OperationKind::Deposit, amount)?;
 
-            UserManager::ensure_operation_allowed(env, depositor, OperationKind::Deposit, amount)?;
+            let current = StateHelper::get_position(env, depositor)
+                .map(|position| position.collateral)
+                .unwrap_or(0);
+            let primary = TokenRegistry::require_primary_asset(env)?;
+            BorrowLimits::ensure_position_size(
+                env,
+                depositor,
+                &primary,
+                current.saturating_add(amount),
+            )?;
 
-            TransferEnforcer::transfer_in(env, depositor, amount, Symbol::new(env, "deposit"))?;
+            let received =
+                TransferEnforcer::transfer_in(env, payer, amount, Symbol::new(env, "deposit"))?;
 
             // Load user position with error handling
             let mut position = match StateHelper::get_position(env, depositor) {
@@ -98,11 +127,11 @@ impl DepositModule {
                 env,
                 &mut position,
                 state.current_borrow_rate,
-                state.current_supply_rate,
+                state.liquidity_index,
             );
 
-            // Update position
-            position.collateral += amount;
+            // Update position with what actually arrived
+            position.collateral += received;
 
             // Save position
             StateHelper::save_position(env, &position);
@@ -123,76 +152,57 @@ impl DepositModule {
             .emit(env);
 
             // Analytics
-            AnalyticsModule::record_activity(env, depositor, "deposit", amount, None)?;
-            UserManager::record_activity(env, depositor, OperationKind::Deposit, amount)?;
+            AnalyticsModule::record_activity(
+                env,
+                depositor,
+                ActivityKind::Deposit,
+                received,
+                None,
+            )?;
+            UserManager::record_activity(env, depositor, OperationKind::Deposit, received)?;
+            PositionHistory::record(env, depositor, ActivityKind::Deposit, None, received);
+            BorrowLimits::record_ledger_action(env, depositor, &primary);
 
             Ok(())
         })();
-
-        ReentrancyGuard::exit(env);
-        result
+        ErrorContext::report(env, result, "deposit", depositor, amount)
     }
 
     /// Deposit collateral for a specific asset (cross-asset)
     pub fn deposit_collateral_asset(
         env: &Env,
-        user: &String,
+        user: &Address,
         asset: &Address,
         amount: i128,
     ) -> Result<(), ProtocolError> {
-        ReentrancyGuard::enter(env)?;
-        let result = (|| -> Result<(), ProtocolError> {
-            if user.is_empty() {
-                return Err(DepositError::InvalidAddress.into());
-            }
-            if amount <= 0 {
-                return Err(DepositError::InvalidAmount.into());
-            }
-
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Deposit)?;
-
-            let user_addr = Address::from_string(user);
+        let _guard = ReentrancyScope::enter(env)?;
+        user.require_auth();
+        if amount <= 0 {
+            return Err(DepositError::InvalidAmount.into());
+        }
 
-            // For cross-asset deposits, we would need to implement cross-asset position handling
-            // This is a simplified version for the modular structure
-            let mut position = match StateHelper::get_position(env, &user_addr) {
-                Some(pos) => pos,
-                None => Position::new(user_addr.clone(), 0, 0),
-            };
+        ComplianceGate::check_market(env, user, asset, OperationKind::Deposit, amount)?;
 
-            // Update position
-            position.collateral += amount;
-            StateHelper::save_position(env, &position);
+        CrossAssetManager::deposit(env, user, asset, amount)?;
 
-            // Emit cross-asset deposit event
-            ProtocolEvent::CrossDeposit(user_addr, asset.clone(), amount).emit(env);
+        // Make sure the user has a primary position so ratio checks can find it
+        if StateHelper::get_position(env, user).is_none() {
+            StateHelper::save_position(env, &Position::new(user.clone(), 0, 0));
+        }
 
-            Ok(())
-        })();
+        UserManager::record_activity(env, user, OperationKind::Deposit, amount)?;
+        BorrowLimits::record_ledger_action(env, user, asset);
+        PositionHistory::record(
+            env,
+            user,
+            ActivityKind::Deposit,
+            Some(asset.clone()),
+            amount,
+        );
 
-        ReentrancyGuard::exit(env);
-        result
-    }
+        // Emit cross-asset deposit event
+        ProtocolEvent::CrossDeposit(user.clone(), asset.clone(), amount).emit(env);
 
-    /// Validate deposit parameters
-    pub fn validate_deposit_params(params: &DepositParams) -> Result<(), DepositError> {
-        if params.amount <= 0 {
-            return Err(DepositError::InvalidAmount);
-        }
         Ok(())
     }
-
-    /// Calculate deposit impact on collateral ratio
-    pub fn calculate_collateral_ratio_impact(
-        current_collateral: i128,
-        current_debt: i128,
-        deposit_amount: i128,
-    ) -> i128 {
-        let new_collateral = current_collateral + deposit_amount;
-        if current_debt > 0 {
-            (new_collateral * 100) / current_debt
-        } else {
-            0
-        }
-    }
 }
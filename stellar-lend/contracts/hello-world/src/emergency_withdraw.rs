@@ -0,0 +1,273 @@
+//! Emergency withdraw module for StellarLend protocol
+//! Emergency withdrawals of treasury reserves. No single key can move reserves out in a
+//! hurry: a withdrawal must be confirmed by a threshold of the configured signers, then
+//! waits out a delay during which any guardian (emergency manager) can veto it. Every
+//! withdrawal names the incident it responds to.
+
+use crate::events::SequencedEvents;
+use crate::revenue::{RevenueManager, RevenueStorage};
+use crate::storage::DataKey;
+use crate::{EmergencyManager, ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, token::TokenClient, Address, Env, String, Symbol, Vec};
+
+/// Time between reaching the confirmation threshold and execution (24 hours)
+pub const EMERGENCY_WITHDRAW_DELAY: u64 = 24 * 60 * 60;
+
+/// Most signers an emergency withdrawal signer set may hold
+pub const MAX_EMERGENCY_SIGNERS: u32 = 10;
+
+/// Signers allowed to propose and confirm emergency withdrawals
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct EmergencySigners {
+    pub signers: Vec<Address>,
+    /// Confirmations needed before the delay starts
+    pub threshold: u32,
+}
+
+/// Proposed withdrawal of treasury reserves
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct EmergencyWithdrawal {
+    pub id: u64,
+    pub asset: Address,
+    pub amount: i128,
+    pub recipient: Address,
+    /// Incident the withdrawal responds to, e.g. a post-mortem or ticket reference
+    pub incident_ref: String,
+    pub confirmations: Vec<Address>,
+    /// Earliest execution time; zero until the threshold is reached
+    pub executable_at: u64,
+    pub vetoed: bool,
+    pub executed: bool,
+}
+
+/// Storage helper for emergency withdrawals
+pub struct EmergencyWithdrawStorage;
+
+impl EmergencyWithdrawStorage {
+    fn signers_key() -> DataKey {
+        DataKey::EmergencySigners
+    }
+
+    fn withdrawal_key(id: u64) -> DataKey {
+        DataKey::EmergencyWithdrawal(id)
+    }
+
+    fn seq_key() -> DataKey {
+        DataKey::EmergencyWithdrawalSeq
+    }
+
+    pub fn get_signers(env: &Env) -> Option<EmergencySigners> {
+        env.storage().instance().get(&Self::signers_key())
+    }
+
+    pub fn put_signers(env: &Env, signers: &EmergencySigners) {
+        env.storage().instance().set(&Self::signers_key(), signers);
+    }
+
+    pub fn get(env: &Env, id: u64) -> Option<EmergencyWithdrawal> {
+        env.storage().persistent().get(&Self::withdrawal_key(id))
+    }
+
+    pub fn save(env: &Env, withdrawal: &EmergencyWithdrawal) {
+        env.storage()
+            .persistent()
+            .set(&Self::withdrawal_key(withdrawal.id), withdrawal);
+    }
+
+    fn next_id(env: &Env) -> u64 {
+        let id = env
+            .storage()
+            .instance()
+            .get::<DataKey, u64>(&Self::seq_key())
+            .unwrap_or(0)
+            + 1;
+        env.storage().instance().set(&Self::seq_key(), &id);
+        id
+    }
+}
+
+/// Multi-signer, delayed and vetoable withdrawals of treasury reserves
+pub struct EmergencyWithdrawals;
+
+impl EmergencyWithdrawals {
+    /// Admin: replace the signer set and confirmation threshold
+    pub fn set_signers(
+        env: &Env,
+        caller: &Address,
+        signers: Vec<Address>,
+        threshold: u32,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if signers.len() > MAX_EMERGENCY_SIGNERS || threshold == 0 || threshold > signers.len() {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        for (idx, signer) in signers.iter().enumerate() {
+            if signers.first_index_of(&signer) != Some(idx as u32) {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+        EmergencyWithdrawStorage::put_signers(
+            env,
+            &EmergencySigners {
+                signers: signers.clone(),
+                threshold,
+            },
+        );
+        env.publish_event(
+            (
+                Symbol::new(env, "emergency_signers_updated"),
+                caller.clone(),
+            ),
+            (signers.len(), threshold),
+        );
+        Ok(())
+    }
+
+    /// Signer: propose paying `amount` of `asset` reserves to `recipient`, counting as
+    /// the first confirmation. Returns the withdrawal id.
+    pub fn propose(
+        env: &Env,
+        signer: &Address,
+        asset: &Address,
+        amount: i128,
+        recipient: &Address,
+        incident_ref: String,
+    ) -> Result<u64, ProtocolError> {
+        Self::ensure_signer(env, signer)?;
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if incident_ref.is_empty() {
+            return Err(ProtocolError::InvalidInput);
+        }
+        let mut withdrawal = EmergencyWithdrawal {
+            id: EmergencyWithdrawStorage::next_id(env),
+            asset: asset.clone(),
+            amount,
+            recipient: recipient.clone(),
+            incident_ref,
+            confirmations: Vec::new(env),
+            executable_at: 0,
+            vetoed: false,
+            executed: false,
+        };
+        env.publish_event(
+            (
+                Symbol::new(env, "emergency_withdrawal_proposed"),
+                signer.clone(),
+            ),
+            (
+                withdrawal.id,
+                asset.clone(),
+                amount,
+                recipient.clone(),
+                withdrawal.incident_ref.clone(),
+            ),
+        );
+        Self::add_confirmation(env, &mut withdrawal, signer)?;
+        Ok(withdrawal.id)
+    }
+
+    /// Signer: confirm a pending withdrawal; reaching the threshold starts the delay
+    pub fn confirm(env: &Env, signer: &Address, id: u64) -> Result<(), ProtocolError> {
+        Self::ensure_signer(env, signer)?;
+        let mut withdrawal = Self::pending(env, id)?;
+        Self::add_confirmation(env, &mut withdrawal, signer)
+    }
+
+    /// Guardian: cancel a withdrawal that has not been executed
+    pub fn veto(env: &Env, guardian: &Address, id: u64) -> Result<(), ProtocolError> {
+        EmergencyManager::ensure_authorized(env, guardian)?;
+        let mut withdrawal = Self::pending(env, id)?;
+        withdrawal.vetoed = true;
+        EmergencyWithdrawStorage::save(env, &withdrawal);
+        env.publish_event(
+            (
+                Symbol::new(env, "emergency_withdrawal_vetoed"),
+                guardian.clone(),
+            ),
+            id,
+        );
+        Ok(())
+    }
+
+    /// Pay out a confirmed withdrawal once its delay has passed, provided the asset's
+    /// undistributed reserves cover it; permissionless. Returns the amount paid.
+    pub fn execute(env: &Env, id: u64) -> Result<i128, ProtocolError> {
+        let mut withdrawal = Self::pending(env, id)?;
+        if withdrawal.executable_at == 0 || env.ledger().timestamp() < withdrawal.executable_at {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let reconciliation = RevenueManager::reconcile_treasury(env, &withdrawal.asset);
+        if withdrawal.amount > reconciliation.pending.min(reconciliation.contract_balance) {
+            return Err(ProtocolError::EmergencyFundInsufficient);
+        }
+
+        withdrawal.executed = true;
+        EmergencyWithdrawStorage::save(env, &withdrawal);
+        let mut balance = RevenueStorage::get_treasury_balance(env, &withdrawal.asset);
+        balance.distributed += withdrawal.amount;
+        RevenueStorage::save_treasury_balance(env, &withdrawal.asset, &balance);
+        TokenClient::new(env, &withdrawal.asset).transfer(
+            &env.current_contract_address(),
+            &withdrawal.recipient,
+            &withdrawal.amount,
+        );
+        env.publish_event(
+            (
+                Symbol::new(env, "emergency_withdrawal_executed"),
+                withdrawal.recipient.clone(),
+            ),
+            (id, withdrawal.amount, withdrawal.incident_ref),
+        );
+        Ok(withdrawal.amount)
+    }
+
+    fn ensure_signer(env: &Env, signer: &Address) -> Result<(), ProtocolError> {
+        match EmergencyWithdrawStorage::get_signers(env) {
+            Some(config) if config.signers.contains(signer) => Ok(()),
+            _ => Err(ProtocolError::Unauthorized),
+        }
+    }
+
+    /// A withdrawal that is neither vetoed nor executed
+    fn pending(env: &Env, id: u64) -> Result<EmergencyWithdrawal, ProtocolError> {
+        let withdrawal = EmergencyWithdrawStorage::get(env, id).ok_or(ProtocolError::NotFound)?;
+        if withdrawal.vetoed || withdrawal.executed {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        Ok(withdrawal)
+    }
+
+    fn add_confirmation(
+        env: &Env,
+        withdrawal: &mut EmergencyWithdrawal,
+        signer: &Address,
+    ) -> Result<(), ProtocolError> {
+        if withdrawal.confirmations.contains(signer) {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        withdrawal.confirmations.push_back(signer.clone());
+        let threshold = EmergencyWithdrawStorage::get_signers(env)
+            .map(|config| config.threshold)
+            .ok_or(ProtocolError::ConfigurationError)?;
+        if withdrawal.executable_at == 0 && withdrawal.confirmations.len() >= threshold {
+            withdrawal.executable_at = env.ledger().timestamp() + EMERGENCY_WITHDRAW_DELAY;
+        }
+        EmergencyWithdrawStorage::save(env, withdrawal);
+        env.publish_event(
+            (
+                Symbol::new(env, "emergency_withdrawal_confirmed"),
+                signer.clone(),
+            ),
+            (
+                withdrawal.id,
+                withdrawal.confirmations.len(),
+                withdrawal.executable_at,
+            ),
+        );
+        Ok(())
+    }
+}
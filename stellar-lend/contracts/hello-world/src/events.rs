@@ -0,0 +1,55 @@
+//! Events module for StellarLend protocol
+//! Every protocol event is preceded by an `event_seq` event carrying a monotonically
+//! increasing sequence number, leaving the event's own topics and data untouched.
+//! Indexers compare consecutive numbers to detect events lost to RPC retention limits,
+//! and `get_last_event_seq` tells them where the stream stands, so they know whether to
+//! resume from a cursor or rebuild from a state snapshot. The counter lives in instance
+//! storage, which is written back once per invocation however many events it emits.
+//! Token-interface events of the receipt token keep the plain SEP-41 layout and are not
+//! numbered.
+
+use crate::storage::DataKey;
+use soroban_sdk::{events::Topics, Env, IntoVal, Symbol, Val};
+
+/// Storage helper for the event sequence
+pub struct EventSeq;
+
+impl EventSeq {
+    fn key() -> DataKey {
+        DataKey::EventSeq
+    }
+
+    /// Sequence number of the last event emitted; zero before the first
+    pub fn last(env: &Env) -> u64 {
+        env.storage().instance().get(&Self::key()).unwrap_or(0)
+    }
+
+    fn next(env: &Env) -> u64 {
+        let seq = Self::last(env) + 1;
+        env.storage().instance().set(&Self::key(), &seq);
+        seq
+    }
+}
+
+/// Publishing that stamps each event with the next sequence number
+pub trait SequencedEvents {
+    /// Publish an `event_seq` event with the next sequence number, then `data` under
+    /// `topics`
+    fn publish_event<T, D>(&self, topics: T, data: D)
+    where
+        T: Topics,
+        D: IntoVal<Env, Val>;
+}
+
+impl SequencedEvents for Env {
+    fn publish_event<T, D>(&self, topics: T, data: D)
+    where
+        T: Topics,
+        D: IntoVal<Env, Val>,
+    {
+        let seq = EventSeq::next(self);
+        self.events()
+            .publish((Symbol::new(self, "event_seq"),), seq);
+        self.events().publish(topics, data);
+    }
+}
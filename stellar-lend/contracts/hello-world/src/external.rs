@@ -0,0 +1,121 @@
+//! External call module for StellarLend protocol
+//! Single path for calls into contracts the protocol does not control: flash loan
+//! receivers, DEX adapters and collateral adapters. Every call runs its checks, then its
+//! state effects, then the interaction. When the admin has set an allowlist, only listed
+//! targets can be called. A call-depth counter marks the protocol as mid-call, so any
+//! nested entry, whether a guarded entrypoint or a second external call, is rejected.
+
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{ProtocolConfig, ProtocolError};
+use soroban_sdk::{Address, Env, Symbol, TryFromVal, Val, Vec};
+
+/// Most targets an external call allowlist may hold
+pub const MAX_EXTERNAL_TARGETS: u32 = 20;
+
+/// Storage helper for external call state
+pub struct ExternalCallStorage;
+
+impl ExternalCallStorage {
+    fn allowlist_key() -> DataKey {
+        DataKey::ExternalCallAllowlist
+    }
+
+    fn depth_key() -> DataKey {
+        DataKey::ExternalCallDepth
+    }
+
+    pub fn get_allowlist(env: &Env) -> Option<Vec<Address>> {
+        env.storage().instance().get(&Self::allowlist_key())
+    }
+
+    pub fn put_allowlist(env: &Env, allowlist: &Option<Vec<Address>>) {
+        match allowlist {
+            Some(allowlist) => env
+                .storage()
+                .instance()
+                .set(&Self::allowlist_key(), allowlist),
+            None => env.storage().instance().remove(&Self::allowlist_key()),
+        }
+    }
+
+    /// External calls in progress in this invocation
+    pub fn get_depth(env: &Env) -> u32 {
+        env.storage()
+            .temporary()
+            .get(&Self::depth_key())
+            .unwrap_or(0)
+    }
+
+    pub fn put_depth(env: &Env, depth: u32) {
+        if depth == 0 {
+            env.storage().temporary().remove(&Self::depth_key());
+        } else {
+            env.storage().temporary().set(&Self::depth_key(), &depth);
+        }
+    }
+}
+
+/// Checks-effects-interactions wrapper around calls to untrusted contracts
+pub struct ExternalCall;
+
+impl ExternalCall {
+    /// Admin: restrict external calls to `allowlist`, or (with `None`) allow any target
+    pub fn set_allowlist(
+        env: &Env,
+        caller: &Address,
+        allowlist: Option<Vec<Address>>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if let Some(allowlist) = &allowlist {
+            if allowlist.len() > MAX_EXTERNAL_TARGETS {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+        ExternalCallStorage::put_allowlist(env, &allowlist);
+        env.publish_event(
+            (
+                Symbol::new(env, "external_allowlist_updated"),
+                caller.clone(),
+            ),
+            allowlist.map(|allowlist| allowlist.len()),
+        );
+        Ok(())
+    }
+
+    /// True while an external call is in progress, i.e. the protocol is being re-entered
+    pub fn in_progress(env: &Env) -> bool {
+        ExternalCallStorage::get_depth(env) > 0
+    }
+
+    /// Call `func` on `target` with `args`. The target and call depth are checked first,
+    /// then `effects` applies the caller's state changes, and only then is the target
+    /// invoked; nothing the target does can observe a half-updated state.
+    pub fn invoke<T, F>(
+        env: &Env,
+        target: &Address,
+        func: &str,
+        args: Vec<Val>,
+        effects: F,
+    ) -> Result<T, ProtocolError>
+    where
+        T: TryFromVal<Env, Val>,
+        F: FnOnce() -> Result<(), ProtocolError>,
+    {
+        if let Some(allowlist) = ExternalCallStorage::get_allowlist(env) {
+            if !allowlist.contains(target) {
+                return Err(ProtocolError::Unauthorized);
+            }
+        }
+        let depth = ExternalCallStorage::get_depth(env);
+        if depth > 0 {
+            return Err(ProtocolError::ReentrancyDetected);
+        }
+        effects()?;
+
+        ExternalCallStorage::put_depth(env, depth + 1);
+        let result = env.invoke_contract(target, &Symbol::new(env, func), args);
+        ExternalCallStorage::put_depth(env, depth);
+        Ok(result)
+    }
+}
@@ -1,5 +1,8 @@
-use crate::{ProtocolError, ProtocolEvent, ReentrancyGuard};
-use soroban_sdk::{vec, Address, Env, IntoVal, Symbol};
+#![allow(dead_code)]
+use crate::external::ExternalCall;
+use crate::statement::{FeeKind, FeeLedger};
+use crate::{ProtocolError, ProtocolEvent, ReentrancyScope};
+use soroban_sdk::{vec, Address, Env, IntoVal};
 
 pub struct FlashLoan;
 
@@ -15,25 +18,22 @@ impl FlashLoan {
         if amount <= 0 {
             return Err(ProtocolError::InvalidAmount);
         }
-        ReentrancyGuard::enter(env)?;
-        let result = (|| {
-            let fee = (amount * fee_bps) / 10000;
+        let _guard = ReentrancyScope::enter(env)?;
+        let fee = (amount * fee_bps) / 10000;
+        let args = vec![
+            env,
+            asset.clone().into_val(env),
+            amount.into_val(env),
+            fee.into_val(env),
+            initiator.clone().into_val(env),
+        ];
+        ExternalCall::invoke::<(), _>(env, receiver_contract, "on_flash_loan", args, || {
             ProtocolEvent::FlashLoanInitiated(initiator.clone(), asset.clone(), amount, fee)
                 .emit(env);
-            let args = vec![
-                env,
-                asset.clone().into_val(env),
-                amount.into_val(env),
-                fee.into_val(env),
-                initiator.clone().into_val(env),
-            ];
-            let _: () =
-                env.invoke_contract(receiver_contract, &Symbol::new(env, "on_flash_loan"), args);
-            ProtocolEvent::FlashLoanCompleted(initiator.clone(), asset.clone(), amount, fee)
-                .emit(env);
+            FeeLedger::record(env, initiator, FeeKind::Origination, fee);
             Ok(())
-        })();
-        ReentrancyGuard::exit(env);
-        result
+        })?;
+        ProtocolEvent::FlashLoanCompleted(initiator.clone(), asset.clone(), amount, fee).emit(env);
+        Ok(())
     }
 }
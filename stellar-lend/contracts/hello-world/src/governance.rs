@@ -1,5 +1,6 @@
 #![allow(dead_code)]
-use soroban_sdk::{contracttype, Address, Env, Map, Symbol, Vec};
+use crate::storage::DataKey;
+use soroban_sdk::{contracttype, Address, Env, Map, Vec};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -15,6 +16,18 @@ pub struct Proposal {
     pub executed: bool,
 }
 
+/// Lifecycle stage used to index proposals
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ProposalStatus {
+    Active,
+    Queued,
+    Executed,
+}
+
+/// Largest page returned by proposal listings
+pub const MAX_PROPOSAL_PAGE: u32 = 50;
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
 pub struct VoteReceipt {
@@ -26,41 +39,61 @@ pub struct VoteReceipt {
 pub struct GovStorage;
 
 impl GovStorage {
-    fn proposals_key(env: &Env) -> Symbol { Symbol::new(env, "gov_proposals") }
-    fn receipts_key(env: &Env) -> Symbol { Symbol::new(env, "gov_receipts") }
-    fn counter_key(env: &Env) -> Symbol { Symbol::new(env, "gov_counter") }
-    fn quorum_bps_key(env: &Env) -> Symbol { Symbol::new(env, "gov_quorum_bps") }
-    fn timelock_key(env: &Env) -> Symbol { Symbol::new(env, "gov_timelock") }
-    fn delegation_key(env: &Env) -> Symbol { Symbol::new(env, "gov_delegation") }
+    fn proposal_key(id: u64) -> DataKey { DataKey::GovProposal(id) }
+    fn status_key(status: ProposalStatus) -> DataKey { DataKey::GovStatus(status) }
+    fn receipts_key(id: u64) -> DataKey { DataKey::GovReceipts(id) }
+    fn counter_key() -> DataKey { DataKey::GovCounter }
+    fn quorum_bps_key() -> DataKey { DataKey::GovQuorumBps }
+    fn timelock_key() -> DataKey { DataKey::GovTimelock }
+    fn delegation_key(from: &Address) -> DataKey { DataKey::GovDelegation(from.clone()) }
 
     pub fn next_id(env: &Env) -> u64 {
-        let id: u64 = env.storage().instance().get(&Self::counter_key(env)).unwrap_or(0);
-        env.storage().instance().set(&Self::counter_key(env), &(id + 1));
+        let id: u64 = env.storage().instance().get(&Self::counter_key()).unwrap_or(0);
+        env.storage().instance().set(&Self::counter_key(), &(id + 1));
         id + 1
     }
 
+    pub fn count(env: &Env) -> u64 { env.storage().instance().get(&Self::counter_key()).unwrap_or(0) }
+
     pub fn save_proposal(env: &Env, p: &Proposal) {
-        let mut map: Map<u64, Proposal> = env.storage().instance().get(&Self::proposals_key(env)).unwrap_or_else(|| Map::new(env));
-        map.set(p.id, p.clone());
-        env.storage().instance().set(&Self::proposals_key(env), &map);
+        env.storage().persistent().set(&Self::proposal_key(p.id), p);
     }
 
     pub fn get_proposal(env: &Env, id: u64) -> Option<Proposal> {
-        let map: Map<u64, Proposal> = env.storage().instance().get(&Self::proposals_key(env)).unwrap_or_else(|| Map::new(env));
-        map.get(id)
+        env.storage().persistent().get(&Self::proposal_key(id))
+    }
+
+    pub fn get_status_ids(env: &Env, status: ProposalStatus) -> Vec<u64> {
+        env.storage().instance().get(&Self::status_key(status)).unwrap_or_else(|| Vec::new(env))
+    }
+
+    fn put_status_ids(env: &Env, status: ProposalStatus, ids: &Vec<u64>) {
+        env.storage().instance().set(&Self::status_key(status), ids);
+    }
+
+    /// Move a proposal id between status indexes; `from` is None for new proposals
+    pub fn set_status(env: &Env, id: u64, from: Option<ProposalStatus>, to: ProposalStatus) {
+        if let Some(from) = from {
+            let mut ids = Self::get_status_ids(env, from);
+            if let Some(index) = ids.first_index_of(id) { ids.remove(index); }
+            Self::put_status_ids(env, from, &ids);
+        }
+        let mut ids = Self::get_status_ids(env, to);
+        ids.push_back(id);
+        Self::put_status_ids(env, to, &ids);
     }
 
     pub fn save_receipt(env: &Env, id: u64, r: &VoteReceipt) {
-        let key = (Self::receipts_key(env), id);
-        let mut map: Map<Address, VoteReceipt> = env.storage().instance().get(&key).unwrap_or_else(|| Map::new(env));
+        let key = Self::receipts_key(id);
+        let mut map: Map<Address, VoteReceipt> = env.storage().persistent().get(&key).unwrap_or_else(|| Map::new(env));
         map.set(r.voter.clone(), r.clone());
-        env.storage().instance().set(&key, &map);
+        env.storage().persistent().set(&key, &map);
     }
 
-    pub fn get_quorum_bps(env: &Env) -> i128 { env.storage().instance().get(&Self::quorum_bps_key(env)).unwrap_or(1000) }
-    pub fn set_quorum_bps(env: &Env, bps: i128) { env.storage().instance().set(&Self::quorum_bps_key(env), &bps); }
-    pub fn get_timelock(env: &Env) -> u64 { env.storage().instance().get(&Self::timelock_key(env)).unwrap_or(60) }
-    pub fn set_timelock(env: &Env, secs: u64) { env.storage().instance().set(&Self::timelock_key(env), &secs); }
+    pub fn get_quorum_bps(env: &Env) -> i128 { env.storage().instance().get(&Self::quorum_bps_key()).unwrap_or(1000) }
+    pub fn set_quorum_bps(env: &Env, bps: i128) { env.storage().instance().set(&Self::quorum_bps_key(), &bps); }
+    pub fn get_timelock(env: &Env) -> u64 { env.storage().instance().get(&Self::timelock_key()).unwrap_or(60) }
+    pub fn set_timelock(env: &Env, secs: u64) { env.storage().instance().set(&Self::timelock_key(), &secs); }
 }
 
 pub struct Governance;
@@ -71,6 +104,7 @@ impl Governance {
         let id = GovStorage::next_id(env);
         let p = Proposal { id, proposer: proposer.clone(), title, created: now, voting_ends: now + voting_period_secs, queued_until: 0, for_votes: 0, against_votes: 0, executed: false };
         GovStorage::save_proposal(env, &p);
+        GovStorage::set_status(env, id, None, ProposalStatus::Active);
         p
     }
 
@@ -89,7 +123,10 @@ impl Governance {
         let quorum = GovStorage::get_quorum_bps(env);
         let total = p.for_votes + p.against_votes;
         let have_quorum = if total == 0 { false } else { (p.for_votes * 10000 / total) >= quorum };
-        if have_quorum && now >= p.voting_ends { p.queued_until = now + GovStorage::get_timelock(env); }
+        if have_quorum && now >= p.voting_ends && p.queued_until == 0 {
+            p.queued_until = now + GovStorage::get_timelock(env);
+            GovStorage::set_status(env, id, Some(ProposalStatus::Active), ProposalStatus::Queued);
+        }
         GovStorage::save_proposal(env, &p);
         p
     }
@@ -97,18 +134,42 @@ impl Governance {
     pub fn execute(env: &Env, id: u64) -> Proposal {
         let mut p = GovStorage::get_proposal(env, id).unwrap();
         let now = env.ledger().timestamp();
-        if now >= p.queued_until && p.queued_until != 0 { p.executed = true; }
+        if now >= p.queued_until && p.queued_until != 0 && !p.executed {
+            p.executed = true;
+            GovStorage::set_status(env, id, Some(ProposalStatus::Queued), ProposalStatus::Executed);
+        }
         GovStorage::save_proposal(env, &p);
         p
     }
 
+    /// Proposals by id in creation order, `limit` capped at MAX_PROPOSAL_PAGE
+    pub fn get_proposals(env: &Env, offset: u64, limit: u32) -> Vec<Proposal> {
+        let mut page = Vec::new(env);
+        let end = GovStorage::count(env).min(offset.saturating_add(limit.min(MAX_PROPOSAL_PAGE) as u64));
+        for id in offset.saturating_add(1)..=end {
+            if let Some(p) = GovStorage::get_proposal(env, id) { page.push_back(p); }
+        }
+        page
+    }
+
+    /// Proposals currently in `status`, paginated like `get_proposals`
+    pub fn get_proposals_by_status(env: &Env, status: ProposalStatus, offset: u32, limit: u32) -> Vec<Proposal> {
+        let ids = GovStorage::get_status_ids(env, status);
+        let mut page = Vec::new(env);
+        let end = ids.len().min(offset.saturating_add(limit.min(MAX_PROPOSAL_PAGE)));
+        for index in offset..end {
+            if let Some(p) = GovStorage::get_proposal(env, ids.get(index).unwrap()) { page.push_back(p); }
+        }
+        page
+    }
+
     pub fn delegate(env: &Env, from: &Address, to: &Address) {
-        let key = (GovStorage::delegation_key(env), from.clone());
-        env.storage().instance().set(&key, to);
+        let key = GovStorage::delegation_key(from);
+        env.storage().persistent().set(&key, to);
     }
 
     pub fn get_delegate(env: &Env, from: &Address) -> Option<Address> {
-        let key = (GovStorage::delegation_key(env), from.clone());
-        env.storage().instance().get(&key)
+        let key = GovStorage::delegation_key(from);
+        env.storage().persistent().get(&key)
     }
 }
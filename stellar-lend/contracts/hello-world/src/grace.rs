@@ -0,0 +1,127 @@
+//! Grace period module for StellarLend protocol
+//! Promotional interest-free window at the start of each new borrow
+
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, Map, Symbol, Vec};
+
+/// Debt opened by a single borrow while a grace window was configured
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BorrowTranche {
+    pub amount: i128,
+    pub originated_at: u64,
+    /// Interest accrues on this tranche only after this timestamp
+    pub grace_ends: u64,
+}
+
+/// Storage helper for grace windows and borrow tranches
+pub struct GraceStorage;
+
+impl GraceStorage {
+    fn windows_key() -> DataKey {
+        DataKey::GraceWindows
+    }
+
+    fn tranches_key(user: &Address) -> DataKey {
+        DataKey::BorrowTranches(user.clone())
+    }
+
+    pub fn get_windows(env: &Env) -> Map<Address, u64> {
+        env.storage()
+            .instance()
+            .get(&Self::windows_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_windows(env: &Env, windows: &Map<Address, u64>) {
+        env.storage().instance().set(&Self::windows_key(), windows);
+    }
+
+    pub fn get_tranches(env: &Env, user: &Address) -> Vec<BorrowTranche> {
+        env.storage()
+            .persistent()
+            .get(&Self::tranches_key(user))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn put_tranches(env: &Env, user: &Address, tranches: &Vec<BorrowTranche>) {
+        let key = Self::tranches_key(user);
+        if tranches.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, tranches);
+        }
+    }
+}
+
+/// Grace window configuration and interest exclusion
+pub struct GracePeriod;
+
+impl GracePeriod {
+    /// Set the interest-free window for new borrows of `asset`; zero switches it off.
+    /// Tranches already opened keep the window they were originated with.
+    pub fn set_window(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        hours: u64,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut windows = GraceStorage::get_windows(env);
+        if hours == 0 {
+            windows.remove(asset.clone());
+        } else {
+            windows.set(asset.clone(), hours * 60 * 60);
+        }
+        GraceStorage::put_windows(env, &windows);
+        env.publish_event(
+            (Symbol::new(env, "grace_window_updated"), asset.clone()),
+            hours,
+        );
+        Ok(())
+    }
+
+    /// Configured window for `asset` in hours
+    pub fn get_window_hours(env: &Env, asset: &Address) -> u64 {
+        GraceStorage::get_windows(env)
+            .get(asset.clone())
+            .unwrap_or(0)
+            / (60 * 60)
+    }
+
+    /// Open a tranche for a new borrow if `asset` has a grace window, dropping expired ones
+    pub fn record_borrow(env: &Env, user: &Address, asset: &Address, amount: i128) {
+        let now = env.ledger().timestamp();
+        let mut tranches = Vec::new(env);
+        for tranche in GraceStorage::get_tranches(env, user).iter() {
+            if tranche.grace_ends > now {
+                tranches.push_back(tranche);
+            }
+        }
+        if let Some(window) = GraceStorage::get_windows(env).get(asset.clone()) {
+            tranches.push_back(BorrowTranche {
+                amount,
+                originated_at: now,
+                grace_ends: now + window,
+            });
+        }
+        GraceStorage::put_tranches(env, user, &tranches);
+    }
+
+    /// Debt-seconds between `from` and `to` that fall inside grace windows, never more than
+    /// the full `debt * (to - from)` so repaid tranches cannot make interest negative
+    pub fn graced_debt_seconds(env: &Env, user: &Address, debt: i128, from: u64, to: u64) -> i128 {
+        let mut graced: i128 = 0;
+        for tranche in GraceStorage::get_tranches(env, user).iter() {
+            let start = from.max(tranche.originated_at);
+            let end = to.min(tranche.grace_ends);
+            if end > start {
+                graced =
+                    graced.saturating_add(tranche.amount.saturating_mul((end - start) as i128));
+            }
+        }
+        graced.min(debt.saturating_mul((to - from) as i128))
+    }
+}
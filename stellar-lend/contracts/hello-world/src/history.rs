@@ -0,0 +1,96 @@
+//! History module for StellarLend protocol
+//! Keeps a bounded journal of each account's most recent position changes so support
+//! can reconstruct what happened to a position without an external indexer
+
+use crate::analytics::ActivityKind;
+use crate::cross_asset::CrossAssetManager;
+use crate::storage::DataKey;
+use crate::{ProtocolConfig, StateHelper, TokenRegistry};
+use soroban_sdk::{contracttype, Address, Env, Vec};
+
+/// Journal entries retained per account; older ones are dropped
+pub const MAX_POSITION_HISTORY: u32 = 50;
+
+/// One position-changing operation
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PositionHistoryEntry {
+    pub kind: ActivityKind,
+    pub asset: Option<Address>,
+    pub amount: i128,
+    /// Health factor once the operation settled (100 = at the minimum collateral ratio,
+    /// 0 without debt)
+    pub health_factor_after: i128,
+    pub timestamp: u64,
+}
+
+/// Storage helper for position journals
+pub struct PositionHistoryStorage;
+
+impl PositionHistoryStorage {
+    fn key(user: &Address) -> DataKey {
+        DataKey::PositionHistory(user.clone())
+    }
+
+    pub fn get(env: &Env, user: &Address) -> Vec<PositionHistoryEntry> {
+        env.storage()
+            .persistent()
+            .get(&Self::key(user))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save(env: &Env, user: &Address, history: &Vec<PositionHistoryEntry>) {
+        env.storage().persistent().set(&Self::key(user), history);
+    }
+
+    pub fn remove(env: &Env, user: &Address) {
+        env.storage().persistent().remove(&Self::key(user));
+    }
+}
+
+/// Per-account position journal
+pub struct PositionHistory;
+
+impl PositionHistory {
+    /// Append an operation on `asset` (the primary asset when `None`) to the user's journal
+    pub fn record(
+        env: &Env,
+        user: &Address,
+        kind: ActivityKind,
+        asset: Option<Address>,
+        amount: i128,
+    ) {
+        let asset = asset.or_else(|| TokenRegistry::require_primary_asset(env).ok());
+        let mut history = PositionHistoryStorage::get(env, user);
+        history.push_back(PositionHistoryEntry {
+            kind,
+            asset,
+            amount,
+            health_factor_after: Self::health_factor(env, user),
+            timestamp: env.ledger().timestamp(),
+        });
+        while history.len() > MAX_POSITION_HISTORY {
+            history.pop_front();
+        }
+        PositionHistoryStorage::save(env, user, &history);
+    }
+
+    fn health_factor(env: &Env, user: &Address) -> i128 {
+        let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
+        match StateHelper::get_position(env, user) {
+            Some(position) if min_ratio > 0 => {
+                CrossAssetManager::collateral_ratio(env, user, position.collateral, position.debt)
+                    .map(|ratio| ratio * 100 / min_ratio)
+                    .unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Most recent `limit` entries, oldest first
+    pub fn get(env: &Env, user: &Address, limit: u32) -> Vec<PositionHistoryEntry> {
+        let history = PositionHistoryStorage::get(env, user);
+        let start = history.len().saturating_sub(limit);
+        history.slice(start..)
+    }
+}
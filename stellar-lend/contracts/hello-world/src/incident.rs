@@ -0,0 +1,171 @@
+//! Incident module for StellarLend protocol
+//! One-shot circuit break for emergency managers: it pauses the whole protocol under a
+//! fresh incident id, but only the admin can make that pause stick. Unless the admin
+//! confirms the incident within the confirmation window the protocol resumes by itself,
+//! so a rogue guardian cannot freeze funds indefinitely. Each guardian gets a single
+//! break; once used it stays spent until the admin resets it.
+
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{EmergencyManager, EmergencyStatus, EmergencyStorage, ProtocolConfig, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, String, Symbol};
+
+/// Time the admin has to confirm a circuit break before it lifts by itself (48 hours)
+pub const CIRCUIT_BREAK_CONFIRM_WINDOW: u64 = 48 * 60 * 60;
+
+/// The circuit break currently holding the protocol paused
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CircuitBreak {
+    pub incident_id: u64,
+    pub guardian: Address,
+    pub triggered_at: u64,
+    /// Deadline for the admin's confirmation
+    pub confirm_by: u64,
+    pub confirmed: bool,
+}
+
+impl CircuitBreak {
+    /// Whether the confirmation window passed without the admin confirming
+    pub fn has_lapsed(&self, env: &Env) -> bool {
+        !self.confirmed && env.ledger().timestamp() >= self.confirm_by
+    }
+}
+
+/// Storage helper for circuit breaks and incident ids
+pub struct IncidentStorage;
+
+impl IncidentStorage {
+    fn circuit_break_key() -> DataKey {
+        DataKey::CircuitBreak
+    }
+
+    fn seq_key() -> DataKey {
+        DataKey::IncidentSeq
+    }
+
+    pub fn get(env: &Env) -> Option<CircuitBreak> {
+        env.storage().instance().get(&Self::circuit_break_key())
+    }
+
+    pub fn save(env: &Env, record: &CircuitBreak) {
+        env.storage()
+            .instance()
+            .set(&Self::circuit_break_key(), record);
+    }
+
+    pub fn remove(env: &Env) {
+        env.storage().instance().remove(&Self::circuit_break_key());
+    }
+
+    fn used_key(guardian: &Address) -> DataKey {
+        DataKey::CircuitBreakUsed(guardian.clone())
+    }
+
+    pub fn is_used(env: &Env, guardian: &Address) -> bool {
+        env.storage().persistent().has(&Self::used_key(guardian))
+    }
+
+    pub fn set_used(env: &Env, guardian: &Address, used: bool) {
+        if used {
+            env.storage()
+                .persistent()
+                .set(&Self::used_key(guardian), &true);
+        } else {
+            env.storage().persistent().remove(&Self::used_key(guardian));
+        }
+    }
+
+    fn next_id(env: &Env) -> u64 {
+        let id = env
+            .storage()
+            .instance()
+            .get::<DataKey, u64>(&Self::seq_key())
+            .unwrap_or(0)
+            + 1;
+        env.storage().instance().set(&Self::seq_key(), &id);
+        id
+    }
+}
+
+/// Guardian circuit breaks with admin confirmation
+pub struct CircuitBreaker;
+
+impl CircuitBreaker {
+    /// Whether a lapsed circuit break is what keeps the stored state paused
+    pub fn pause_lapsed(env: &Env) -> bool {
+        Self::get(env).is_some_and(|record| record.has_lapsed(env))
+    }
+
+    /// The circuit break on record, if any
+    pub fn get(env: &Env) -> Option<CircuitBreak> {
+        IncidentStorage::get(env)
+    }
+
+    /// Whether `guardian` has used its circuit break and awaits an admin reset
+    pub fn is_used(env: &Env, guardian: &Address) -> bool {
+        IncidentStorage::is_used(env, guardian)
+    }
+
+    /// Emergency manager or admin: pause everything under a new incident id. Refused while
+    /// an earlier circuit break is still in force, and to an emergency manager that has
+    /// already used its break.
+    pub fn trip(env: &Env, guardian: &Address) -> Result<u64, ProtocolError> {
+        EmergencyManager::ensure_authorized(env, guardian)?;
+        if EmergencyStorage::get(env).status != EmergencyStatus::Operational {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let is_admin = ProtocolConfig::get_admin(env).as_ref() == Some(guardian);
+        if !is_admin && IncidentStorage::is_used(env, guardian) {
+            return Err(ProtocolError::Unauthorized);
+        }
+        EmergencyManager::set_paused(env, guardian, Some(String::from_str(env, "circuit_break")));
+        if !is_admin {
+            IncidentStorage::set_used(env, guardian, true);
+        }
+
+        let now = env.ledger().timestamp();
+        let record = CircuitBreak {
+            incident_id: IncidentStorage::next_id(env),
+            guardian: guardian.clone(),
+            triggered_at: now,
+            confirm_by: now + CIRCUIT_BREAK_CONFIRM_WINDOW,
+            confirmed: false,
+        };
+        IncidentStorage::save(env, &record);
+        env.publish_event(
+            (Symbol::new(env, "circuit_break"), guardian.clone()),
+            (record.incident_id, record.confirm_by),
+        );
+        Ok(record.incident_id)
+    }
+
+    /// Admin: keep the protocol paused past the confirmation window
+    pub fn confirm(env: &Env, caller: &Address, incident_id: u64) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut record = IncidentStorage::get(env)
+            .filter(|record| record.incident_id == incident_id)
+            .ok_or(ProtocolError::NotFound)?;
+        if record.confirmed || record.has_lapsed(env) {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        record.confirmed = true;
+        IncidentStorage::save(env, &record);
+        env.publish_event(
+            (Symbol::new(env, "circuit_break_confirmed"), caller.clone()),
+            incident_id,
+        );
+        Ok(())
+    }
+
+    /// Admin: give `guardian` its circuit break back
+    pub fn reset(env: &Env, caller: &Address, guardian: &Address) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        IncidentStorage::set_used(env, guardian, false);
+        env.publish_event(
+            (Symbol::new(env, "circuit_break_reset"), caller.clone()),
+            guardian.clone(),
+        );
+        Ok(())
+    }
+}
@@ -6,19 +6,99 @@
 #![no_std]
 extern crate alloc;
 
-use alloc::format;
-use alloc::string::ToString;
 use soroban_sdk::token::TokenClient;
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, vec, Address, Bytes, Env, IntoVal, Map,
-    String, Symbol, Vec,
+    contract, contracterror, contractimpl, contracttype, Address, Env, Map, String, Symbol, Vec,
 };
 mod oracle;
-use oracle::{Oracle, OracleSource, OracleStorage};
+use oracle::{AssetOracleConfig, Oracle, OracleSource, OracleStorage, PriceTier};
 mod governance;
-use governance::{GovStorage, Governance, Proposal};
+use governance::{Governance, Proposal, ProposalStatus};
+mod adapter;
+use adapter::CollateralAdapters;
+mod apy;
+use apy::NetApy;
+mod auction;
+use auction::{DebtAuction, DebtAuctionParams, DebtAuctions};
+mod compliance;
+use compliance::{ComplianceGate, MarketPauseStorage, MarketPauses};
+mod config;
+use config::{ConfigManager, ConfigStorage, ConfigVersion, ProtocolConfiguration};
+mod emergency_withdraw;
+use emergency_withdraw::{EmergencyWithdrawStorage, EmergencyWithdrawal, EmergencyWithdrawals};
+mod events;
+use events::{EventSeq, SequencedEvents};
+mod external;
+use external::{ExternalCall, ExternalCallStorage};
 mod flash_loan;
-use flash_loan::FlashLoan;
+mod session;
+use session::{ProtectorStorage, SessionManager, SessionPolicy, SessionStorage};
+mod statement;
+use statement::{FeeKind, FeeLedger, FeeStatement};
+mod grace;
+use grace::GracePeriod;
+mod history;
+use history::{PositionHistory, PositionHistoryEntry};
+mod incident;
+use incident::{CircuitBreak, CircuitBreaker, IncidentStorage};
+mod limits;
+use limits::{
+    BootstrapConfig, BorrowLimits, MarketPhase, ParamChangeLimit, ProtocolParameters,
+    ThinMarketRate, UtilizationBreaker,
+};
+mod listing;
+use listing::{
+    ListingConfig, ListingProposal, ListingRevenueShare, ListingStorage, MarketListings,
+};
+mod notify;
+use notify::{NotificationPrefs, NotificationStorage, Notifications};
+mod preview;
+use preview::{ActionPreview, ActionPreviews};
+mod price_push;
+use price_push::{PricePayload, PricePush, PricePushConfig, PricePushStorage};
+mod privacy;
+use privacy::AccountPurge;
+mod quote;
+use quote::{BorrowQuote, RateQuotes};
+mod rebate;
+use rebate::{RebateConfig, RebateManager, RebatePot, RebateStorage};
+mod receipt;
+use receipt::ReceiptToken;
+mod recovery;
+use recovery::{RecoveryConfig, RecoveryMode, RecoveryStorage};
+mod relay;
+use relay::{Relay, RelayStorage, SignedAction};
+mod restructure;
+use restructure::PositionRestructure;
+mod revenue;
+mod rewards;
+mod risk;
+use revenue::{
+    AssetRevenue, BuybackConfig, FeeSplit, RevenueBucket, RevenueGranularity, RevenueManager,
+    RevenueMetrics, TreasuryReconciliation,
+};
+use rewards::{
+    CurrentEmissions, EmissionEpoch, RewardManager, RewardSide, RewardStorage, UtilizationBand,
+};
+use risk::{RiskMonitor, RiskOverview};
+mod shutdown;
+use shutdown::EmergencyRedemption;
+mod staking;
+use staking::{StakePosition, Staking, StakingConfig, StakingStorage};
+mod supply_fee;
+use supply_fee::{SupplyFee, SupplyFeeConfig, SupplyFeeStorage};
+mod storage;
+use storage::DataKey;
+mod ttl;
+use ttl::{StateEntry, Ttl};
+mod units;
+use units::Units;
+mod watchlist;
+use watchlist::LiquidationWatchlist;
+#[cfg(feature = "testutils")]
+mod stress;
+#[cfg(feature = "testutils")]
+use stress::StressTools;
 
 // Global allocator for Soroban contracts
 #[global_allocator]
@@ -30,6 +110,7 @@ mod test;
 // Core protocol modules
 mod analytics;
 mod borrow;
+mod cross_asset;
 mod deposit;
 mod liquidate;
 mod repay;
@@ -123,19 +204,27 @@ impl EmergencyState {
 pub struct EmergencyStorage;
 
 impl EmergencyStorage {
-    fn key(env: &Env) -> Symbol {
-        Symbol::new(env, "emergency_state")
+    fn key() -> DataKey {
+        DataKey::EmergencyState
     }
 
     pub fn save(env: &Env, state: &EmergencyState) {
-        env.storage().instance().set(&Self::key(env), state);
+        env.storage().instance().set(&Self::key(), state);
     }
 
+    /// Emergency state with the pause lifted once an unconfirmed circuit break has lapsed
     pub fn get(env: &Env) -> EmergencyState {
-        env.storage()
+        let mut state = env
+            .storage()
             .instance()
-            .get::<Symbol, EmergencyState>(&Self::key(env))
-            .unwrap_or_else(|| EmergencyState::default(env))
+            .get::<DataKey, EmergencyState>(&Self::key())
+            .unwrap_or_else(|| EmergencyState::default(env));
+        if state.status == EmergencyStatus::Paused && CircuitBreaker::pause_lapsed(env) {
+            state.status = EmergencyStatus::Operational;
+            state.paused_by = None;
+            state.reason = None;
+        }
+        state
     }
 }
 
@@ -159,6 +248,8 @@ pub enum UserRole {
     Suspended,
     Standard,
     Analyst,
+    ComplianceOfficer,
+    TreasuryManager,
     Manager,
     Admin,
 }
@@ -169,16 +260,20 @@ impl UserRole {
             UserRole::Suspended => 0,
             UserRole::Standard => 1,
             UserRole::Analyst => 2,
+            UserRole::ComplianceOfficer => 2,
+            UserRole::TreasuryManager => 2,
             UserRole::Manager => 3,
             UserRole::Admin => 4,
         }
     }
 
-    fn as_symbol<'a>(&self, env: &'a Env) -> Symbol {
+    fn as_symbol(&self, env: &Env) -> Symbol {
         match self {
             UserRole::Suspended => Symbol::new(env, "suspended"),
             UserRole::Standard => Symbol::new(env, "standard"),
             UserRole::Analyst => Symbol::new(env, "analyst"),
+            UserRole::ComplianceOfficer => Symbol::new(env, "compliance_officer"),
+            UserRole::TreasuryManager => Symbol::new(env, "treasury_manager"),
             UserRole::Manager => Symbol::new(env, "manager"),
             UserRole::Admin => Symbol::new(env, "admin"),
         }
@@ -201,6 +296,17 @@ impl VerificationStatus {
     }
 }
 
+/// How far compliance restricts an account
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum FreezeLevel {
+    None,
+    /// The account may only repay and withdraw, to wind its position down
+    WithdrawOnly,
+    /// The account may do nothing
+    Halted,
+}
+
 /// User-specific limits enforced by the protocol
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -239,20 +345,14 @@ impl UserLimits {
         }
 
         match operation {
-            OperationKind::Deposit => {
-                if amount > self.max_deposit {
-                    return Err(ProtocolError::UserLimitExceeded);
-                }
+            OperationKind::Deposit if amount > self.max_deposit => {
+                return Err(ProtocolError::UserLimitExceeded);
             }
-            OperationKind::Borrow => {
-                if amount > self.max_borrow {
-                    return Err(ProtocolError::UserLimitExceeded);
-                }
+            OperationKind::Borrow if amount > self.max_borrow => {
+                return Err(ProtocolError::UserLimitExceeded);
             }
-            OperationKind::Withdraw => {
-                if amount > self.max_withdraw {
-                    return Err(ProtocolError::UserLimitExceeded);
-                }
+            OperationKind::Withdraw if amount > self.max_withdraw => {
+                return Err(ProtocolError::UserLimitExceeded);
             }
             _ => {}
         }
@@ -294,7 +394,9 @@ pub struct UserProfile {
     pub limits: UserLimits,
     pub last_active: u64,
     pub activity_score: i128,
-    pub is_frozen: bool,
+    pub freeze: FreezeLevel,
+    /// Timestamp at which the freeze lapses pending review (0 = until lifted)
+    pub freeze_expires_at: u64,
 }
 
 impl UserProfile {
@@ -306,41 +408,143 @@ impl UserProfile {
             limits: UserLimits::default(env),
             last_active: env.ledger().timestamp(),
             activity_score: 0,
-            is_frozen: false,
+            freeze: FreezeLevel::None,
+            freeze_expires_at: 0,
+        }
+    }
+
+    /// Freeze level in force now, after any review expiry
+    pub fn freeze_level(&self, env: &Env) -> FreezeLevel {
+        if self.freeze_expires_at > 0 && env.ledger().timestamp() >= self.freeze_expires_at {
+            return FreezeLevel::None;
         }
+        self.freeze
+    }
+
+    fn set_freeze(&mut self, level: FreezeLevel, expires_at: u64) {
+        self.freeze = level;
+        self.freeze_expires_at = if level == FreezeLevel::None {
+            0
+        } else {
+            expires_at
+        };
     }
 }
 
-/// Storage key namespace for user profiles
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// Severity attached to a suspicious activity flag
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[contracttype]
-pub enum UserStorageKey {
-    Profile(Address),
+pub enum AlertSeverity {
+    Low,
+    Medium,
+    High,
+}
+
+/// Live compliance counters maintained alongside user profile changes
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct ComplianceSummary {
+    pub kyc_verified: u32,
+    /// Users whose verification was rejected and are barred from the protocol
+    pub blacklisted: u32,
+    pub frozen: u32,
+    pub suspicious_low: u32,
+    pub suspicious_medium: u32,
+    pub suspicious_high: u32,
 }
 
 /// Centralized user management helper
 pub struct UserManager;
 
 impl UserManager {
-    fn profile_key(user: &Address) -> UserStorageKey {
-        UserStorageKey::Profile(user.clone())
+    fn profile_key(user: &Address) -> DataKey {
+        DataKey::UserProfile(user.clone())
     }
 
     fn ensure_profile(env: &Env, user: &Address) -> UserProfile {
         let key = Self::profile_key(user);
         env.storage()
-            .instance()
-            .get::<UserStorageKey, UserProfile>(&key)
+            .persistent()
+            .get::<DataKey, UserProfile>(&key)
             .unwrap_or_else(|| {
                 let profile = UserProfile::new(env, user.clone());
-                env.storage().instance().set(&key, &profile);
+                env.storage().persistent().set(&key, &profile);
                 profile
             })
     }
 
     fn save_profile(env: &Env, profile: &UserProfile) {
         let key = Self::profile_key(&profile.user);
-        env.storage().instance().set(&key, profile);
+        let previous = env.storage().persistent().get::<DataKey, UserProfile>(&key);
+        Self::update_compliance(env, previous.as_ref(), profile);
+        env.storage().persistent().set(&key, profile);
+    }
+
+    /// Apply the counter deltas implied by a profile transition
+    fn update_compliance(env: &Env, previous: Option<&UserProfile>, current: &UserProfile) {
+        fn delta(counter: u32, before: bool, after: bool) -> u32 {
+            match (before, after) {
+                (false, true) => counter.saturating_add(1),
+                (true, false) => counter.saturating_sub(1),
+                _ => counter,
+            }
+        }
+
+        let was_verified = previous.is_some_and(|p| p.verification.is_verified());
+        let was_rejected = previous.is_some_and(|p| p.verification == VerificationStatus::Rejected);
+        let was_frozen = previous.is_some_and(|p| p.freeze != FreezeLevel::None);
+
+        let mut summary = Self::get_compliance_summary(env);
+        summary.kyc_verified = delta(
+            summary.kyc_verified,
+            was_verified,
+            current.verification.is_verified(),
+        );
+        summary.blacklisted = delta(
+            summary.blacklisted,
+            was_rejected,
+            current.verification == VerificationStatus::Rejected,
+        );
+        summary.frozen = delta(
+            summary.frozen,
+            was_frozen,
+            current.freeze != FreezeLevel::None,
+        );
+        env.storage()
+            .instance()
+            .set(&DataKey::ComplianceSummary, &summary);
+    }
+
+    pub fn get_compliance_summary(env: &Env) -> ComplianceSummary {
+        env.storage()
+            .instance()
+            .get(&DataKey::ComplianceSummary)
+            .unwrap_or_default()
+    }
+
+    pub fn flag_suspicious(
+        env: &Env,
+        caller: &Address,
+        user: &Address,
+        severity: AlertSeverity,
+        reason: Symbol,
+    ) -> Result<(), ProtocolError> {
+        Self::ensure_can_manage(env, caller, UserRole::Analyst)?;
+        let mut summary = Self::get_compliance_summary(env);
+        match severity {
+            AlertSeverity::Low => summary.suspicious_low = summary.suspicious_low.saturating_add(1),
+            AlertSeverity::Medium => {
+                summary.suspicious_medium = summary.suspicious_medium.saturating_add(1)
+            }
+            AlertSeverity::High => {
+                summary.suspicious_high = summary.suspicious_high.saturating_add(1)
+            }
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::ComplianceSummary, &summary);
+        ProtocolEvent::ComplianceAlert(user.clone(), reason).emit(env);
+        Ok(())
     }
 
     fn ensure_can_manage(
@@ -366,14 +570,55 @@ impl UserManager {
         Ok(())
     }
 
+    /// Allow only the admin or a verified holder of `role`
+    fn ensure_admin_or(env: &Env, caller: &Address, role: UserRole) -> Result<(), ProtocolError> {
+        if ProtocolConfig::get_admin(env).as_ref() == Some(caller) {
+            return Ok(());
+        }
+        let profile = Self::ensure_profile(env, caller);
+        if !profile.verification.is_verified() {
+            return Err(ProtocolError::UserNotVerified);
+        }
+        if profile.role != role && profile.role != UserRole::Admin {
+            return Err(ProtocolError::UserRoleViolation);
+        }
+        Ok(())
+    }
+
+    /// Allow only the admin or a verified compliance officer
+    pub(crate) fn ensure_compliance_officer(
+        env: &Env,
+        caller: &Address,
+    ) -> Result<(), ProtocolError> {
+        Self::ensure_admin_or(env, caller, UserRole::ComplianceOfficer)
+    }
+
+    /// Allow only the admin or a verified treasury manager
+    pub(crate) fn ensure_treasury_manager(
+        env: &Env,
+        caller: &Address,
+    ) -> Result<(), ProtocolError> {
+        Self::ensure_admin_or(env, caller, UserRole::TreasuryManager)
+    }
+
+    /// Delete a user's profile, backing it out of the compliance counters
+    pub(crate) fn forget_user(env: &Env, user: &Address) {
+        let key = Self::profile_key(user);
+        let previous = env.storage().persistent().get::<DataKey, UserProfile>(&key);
+        if previous.is_some() {
+            Self::update_compliance(env, previous.as_ref(), &UserProfile::new(env, user.clone()));
+            env.storage().persistent().remove(&key);
+        }
+    }
+
     pub fn bootstrap_admin(env: &Env, admin: &Address) {
         let mut profile = Self::ensure_profile(env, admin);
         profile.role = UserRole::Admin;
         profile.verification = VerificationStatus::Verified;
-        profile.is_frozen = false;
+        profile.set_freeze(FreezeLevel::None, 0);
         profile.last_active = env.ledger().timestamp();
         Self::save_profile(env, &profile);
-        env.events().publish(
+        env.publish_event(
             (
                 Symbol::new(env, "user_registered"),
                 Symbol::new(env, "user"),
@@ -397,20 +642,24 @@ impl UserManager {
         let mut profile = Self::ensure_profile(env, user);
         profile.role = role.clone();
         if matches!(role, UserRole::Suspended) {
-            profile.is_frozen = true;
+            profile.set_freeze(FreezeLevel::Halted, 0);
         } else {
-            profile.is_frozen = false;
+            profile.set_freeze(FreezeLevel::None, 0);
         }
         if matches!(
             role,
-            UserRole::Manager | UserRole::Admin | UserRole::Analyst
+            UserRole::Manager
+                | UserRole::Admin
+                | UserRole::Analyst
+                | UserRole::ComplianceOfficer
+                | UserRole::TreasuryManager
         ) && profile.verification != VerificationStatus::Verified
         {
             profile.verification = VerificationStatus::Verified;
         }
         let role_symbol = role.as_symbol(env);
         Self::save_profile(env, &profile);
-        env.events().publish(
+        env.publish_event(
             (
                 Symbol::new(env, "user_role_updated"),
                 Symbol::new(env, "user"),
@@ -435,14 +684,15 @@ impl UserManager {
         let mut profile = Self::ensure_profile(env, user);
         profile.verification = status.clone();
         if status == VerificationStatus::Rejected {
-            profile.is_frozen = true;
+            profile.set_freeze(FreezeLevel::Halted, 0);
         }
         if status == VerificationStatus::Verified {
-            profile.is_frozen = false;
+            profile.set_freeze(FreezeLevel::None, 0);
         }
         let status_symbol = Self::verification_symbol(env, &status);
         Self::save_profile(env, &profile);
-        env.events().publish(
+        ProtocolEvent::ComplianceKycUpdated(user.clone(), status.is_verified()).emit(env);
+        env.publish_event(
             (
                 Symbol::new(env, "user_verification_updated"),
                 Symbol::new(env, "user"),
@@ -476,7 +726,7 @@ impl UserManager {
         profile.limits.max_withdraw = max_withdraw;
         profile.limits.daily_limit = daily_limit;
         Self::save_profile(env, &profile);
-        env.events().publish(
+        env.publish_event(
             (
                 Symbol::new(env, "user_limits_updated"),
                 Symbol::new(env, "user"),
@@ -505,7 +755,13 @@ impl UserManager {
     ) -> Result<(), ProtocolError> {
         let profile = Self::ensure_profile(env, user);
 
-        if profile.is_frozen || profile.role == UserRole::Suspended {
+        let freeze = profile.freeze_level(env);
+        if freeze == FreezeLevel::Halted || profile.role == UserRole::Suspended {
+            return Err(ProtocolError::UserSuspended);
+        }
+        if freeze == FreezeLevel::WithdrawOnly
+            && !matches!(operation, OperationKind::Repay | OperationKind::Withdraw)
+        {
             return Err(ProtocolError::UserSuspended);
         }
 
@@ -552,7 +808,7 @@ impl UserManager {
                 .activity_score
                 .saturating_add(if amount >= 0 { amount } else { -amount });
         Self::save_profile(env, &profile);
-        env.events().publish(
+        env.publish_event(
             (
                 Symbol::new(env, "user_activity_tracked"),
                 Symbol::new(env, "user"),
@@ -575,12 +831,20 @@ impl UserManager {
         Self::ensure_profile(env, user)
     }
 
+    /// Profile lookup for read-only views; unknown users are not persisted
+    pub fn view_profile(env: &Env, user: &Address) -> UserProfile {
+        env.storage()
+            .persistent()
+            .get::<DataKey, UserProfile>(&Self::profile_key(user))
+            .unwrap_or_else(|| UserProfile::new(env, user.clone()))
+    }
+
     pub fn freeze_user(env: &Env, caller: &Address, user: &Address) -> Result<(), ProtocolError> {
         Self::ensure_can_manage(env, caller, UserRole::Manager)?;
         let mut profile = Self::ensure_profile(env, user);
-        profile.is_frozen = true;
+        profile.set_freeze(FreezeLevel::Halted, 0);
         Self::save_profile(env, &profile);
-        env.events().publish(
+        env.publish_event(
             (
                 Symbol::new(env, "user_role_updated"),
                 Symbol::new(env, "user"),
@@ -595,15 +859,37 @@ impl UserManager {
         Ok(())
     }
 
+    /// Restrict a user to `level`, lapsing at `expires_at` pending review (0 = until lifted)
+    pub fn set_freeze_level(
+        env: &Env,
+        caller: &Address,
+        user: &Address,
+        level: FreezeLevel,
+        expires_at: u64,
+    ) -> Result<(), ProtocolError> {
+        Self::ensure_can_manage(env, caller, UserRole::Manager)?;
+        if expires_at != 0 && expires_at <= env.ledger().timestamp() {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut profile = Self::ensure_profile(env, user);
+        profile.set_freeze(level, expires_at);
+        Self::save_profile(env, &profile);
+        env.publish_event(
+            (Symbol::new(env, "user_freeze_updated"), user.clone()),
+            (level, profile.freeze_expires_at),
+        );
+        Ok(())
+    }
+
     pub fn unfreeze_user(env: &Env, caller: &Address, user: &Address) -> Result<(), ProtocolError> {
         Self::ensure_can_manage(env, caller, UserRole::Manager)?;
         let mut profile = Self::ensure_profile(env, user);
-        profile.is_frozen = false;
+        profile.set_freeze(FreezeLevel::None, 0);
         if profile.role == UserRole::Suspended {
             profile.role = UserRole::Standard;
         }
         Self::save_profile(env, &profile);
-        env.events().publish(
+        env.publish_event(
             (
                 Symbol::new(env, "user_role_updated"),
                 Symbol::new(env, "user"),
@@ -721,53 +1007,68 @@ impl EventSummary {
 pub struct EventStorage;
 
 impl EventStorage {
-    fn aggregates_key(env: &Env) -> Symbol {
-        Symbol::new(env, "event_aggregates")
+    fn aggregates_key() -> DataKey {
+        DataKey::EventAggregates
     }
 
-    fn logs_key(env: &Env) -> Symbol {
-        Symbol::new(env, "event_logs")
+    fn logs_key() -> DataKey {
+        DataKey::EventLogs
     }
 
-    fn summary_key(env: &Env) -> Symbol {
-        Symbol::new(env, "event_summary")
+    fn summary_key() -> DataKey {
+        DataKey::EventSummary
     }
 
     pub fn get_aggregates(env: &Env) -> Map<Symbol, EventAggregate> {
         env.storage()
             .instance()
-            .get(&Self::aggregates_key(env))
+            .get(&Self::aggregates_key())
             .unwrap_or_else(|| Map::new(env))
     }
 
     pub fn save_aggregates(env: &Env, aggregates: &Map<Symbol, EventAggregate>) {
         env.storage()
             .instance()
-            .set(&Self::aggregates_key(env), aggregates);
+            .set(&Self::aggregates_key(), aggregates);
     }
 
     pub fn get_logs(env: &Env) -> Map<Symbol, Vec<EventRecord>> {
         env.storage()
             .instance()
-            .get(&Self::logs_key(env))
+            .get(&Self::logs_key())
             .unwrap_or_else(|| Map::new(env))
     }
 
     pub fn save_logs(env: &Env, logs: &Map<Symbol, Vec<EventRecord>>) {
-        env.storage().instance().set(&Self::logs_key(env), logs);
+        env.storage().instance().set(&Self::logs_key(), logs);
     }
 
     pub fn get_summary(env: &Env) -> EventSummary {
         env.storage()
             .instance()
-            .get(&Self::summary_key(env))
+            .get(&Self::summary_key())
             .unwrap_or_else(|| EventSummary::empty(env))
     }
 
     pub fn save_summary(env: &Env, summary: &EventSummary) {
-        env.storage()
-            .instance()
-            .set(&Self::summary_key(env), summary);
+        env.storage().instance().set(&Self::summary_key(), summary);
+    }
+
+    /// Drop every logged event recorded against `user`
+    pub fn forget_user(env: &Env, user: &Address) {
+        let mut logs = Self::get_logs(env);
+        for (event_type, events) in logs.clone().iter() {
+            let mut kept = Vec::new(env);
+            for record in events.iter() {
+                if record.user.as_ref() != Some(user) {
+                    kept.push_back(record);
+                }
+            }
+            if kept.len() != events.len() {
+                logs.set(event_type, kept);
+            }
+        }
+        Self::save_logs(env, &logs);
     }
 
     pub fn append_event(env: &Env, record: &EventRecord) {
@@ -830,7 +1131,7 @@ impl EventTracker {
         asset: Option<Address>,
         amount: i128,
     ) {
-        if topics.len() == 0 {
+        if topics.is_empty() {
             topics = Self::base_topics(env, &event_type);
         }
         let record = EventRecord::new(env, event_type, topics, user, asset, amount);
@@ -859,7 +1160,7 @@ impl EventTracker {
                 user = Some(addr.clone());
                 amount = *borrow_interest;
             }
-            ProtocolEvent::LiquidationExecuted(liquidator, target, seized, _) => {
+            ProtocolEvent::LiquidationExecuted(liquidator, _, target, seized, _) => {
                 event_type = Symbol::new(env, "liquidation_executed");
                 topics = Self::base_topics(env, &event_type);
                 topics.push_back(Symbol::new(env, "liquidator"));
@@ -1162,6 +1463,20 @@ impl EventTracker {
                 user = Some(manager.clone());
                 amount = if *flag { 1 } else { 0 };
             }
+            ProtocolEvent::ConfigUpdated(caller, _, _, new_value) => {
+                event_type = Symbol::new(env, "config_updated");
+                topics = Self::base_topics(env, &event_type);
+                topics.push_back(Symbol::new(env, "caller"));
+                user = Some(caller.clone());
+                amount = *new_value;
+            }
+            ProtocolEvent::PriceServed(served_asset, _, price, _) => {
+                event_type = Symbol::new(env, "price_served");
+                topics = Self::base_topics(env, &event_type);
+                topics.push_back(Symbol::new(env, "asset"));
+                asset = Some(served_asset.clone());
+                amount = *price;
+            }
             _ => {}
         }
 
@@ -1173,21 +1488,19 @@ impl EventTracker {
 pub struct TokenRegistry;
 
 impl TokenRegistry {
-    fn registry_key(env: &Env) -> Symbol {
-        Symbol::new(env, "token_registry")
+    fn registry_key() -> DataKey {
+        DataKey::TokenRegistry
     }
 
     fn assets(env: &Env) -> Map<Symbol, Address> {
         env.storage()
             .instance()
-            .get(&Self::registry_key(env))
+            .get(&Self::registry_key())
             .unwrap_or_else(|| Map::new(env))
     }
 
     fn save_assets(env: &Env, assets: &Map<Symbol, Address>) {
-        env.storage()
-            .instance()
-            .set(&Self::registry_key(env), assets);
+        env.storage().instance().set(&Self::registry_key(), assets);
     }
 
     fn primary_key(env: &Env) -> Symbol {
@@ -1211,12 +1524,14 @@ impl TokenRegistry {
         Self::assets(env).get(key)
     }
 
+    /// Make `token` the primary asset; the deposit receipt takes on its decimals
     pub fn set_primary_asset(
         env: &Env,
         caller: &Address,
         token: Address,
     ) -> Result<(), ProtocolError> {
-        Self::set_asset(env, caller, Self::primary_key(env), token)
+        Self::set_asset(env, caller, Self::primary_key(env), token.clone())?;
+        ReceiptToken::sync_decimals(env, &token)
     }
 
     pub fn require_primary_asset(env: &Env) -> Result<Address, ProtocolError> {
@@ -1228,7 +1543,7 @@ impl TokenRegistry {
 pub struct TransferEnforcer;
 
 impl TransferEnforcer {
-    fn token_client(env: &Env) -> Result<(TokenClient, Address), ProtocolError> {
+    fn token_client(env: &Env) -> Result<(TokenClient<'_>, Address), ProtocolError> {
         let asset = TokenRegistry::require_primary_asset(env)?;
         Ok((TokenClient::new(env, &asset), asset))
     }
@@ -1258,7 +1573,7 @@ impl TransferEnforcer {
             Some(asset.clone()),
             amount,
         );
-        env.events().publish(
+        env.publish_event(
             (event_type, flow.clone()),
             (
                 Symbol::new(env, "from"),
@@ -1294,7 +1609,7 @@ impl TransferEnforcer {
             Some(asset.clone()),
             amount,
         );
-        env.events().publish(
+        env.publish_event(
             (event_type, flow.clone()),
             (
                 Symbol::new(env, "from"),
@@ -1330,7 +1645,7 @@ impl TransferEnforcer {
             Some(asset.clone()),
             amount,
         );
-        env.events().publish(
+        env.publish_event(
             (event_type, flow.clone()),
             (
                 Symbol::new(env, "from"),
@@ -1347,12 +1662,35 @@ impl TransferEnforcer {
         );
     }
 
+    /// Pull `amount` from `user`, returning the amount the contract actually received.
+    /// Only assets allowed as fee-on-transfer may deliver less than `amount`.
     pub fn transfer_in(
         env: &Env,
         user: &Address,
         amount: i128,
         flow: Symbol,
-    ) -> Result<(), ProtocolError> {
+    ) -> Result<i128, ProtocolError> {
+        Self::receive(env, user, amount, flow, false)
+    }
+
+    /// Like `transfer_in`, but spends the allowance `user` granted the contract on the token
+    /// instead of requiring the user's authorization for this call
+    pub fn transfer_in_from_allowance(
+        env: &Env,
+        user: &Address,
+        amount: i128,
+        flow: Symbol,
+    ) -> Result<i128, ProtocolError> {
+        Self::receive(env, user, amount, flow, true)
+    }
+
+    fn receive(
+        env: &Env,
+        user: &Address,
+        amount: i128,
+        flow: Symbol,
+        use_allowance: bool,
+    ) -> Result<i128, ProtocolError> {
         if amount <= 0 {
             return Err(ProtocolError::InvalidAmount);
         }
@@ -1364,7 +1702,11 @@ impl TransferEnforcer {
 
         Self::emit_attempt(env, user, &contract, &asset, amount, &flow);
 
-        client.transfer(user, &contract, &amount);
+        if use_allowance {
+            client.transfer_from(&contract, user, &contract, &amount);
+        } else {
+            client.transfer(user, &contract, &amount);
+        }
 
         let after_contract = client.balance(&contract);
         let after_user = client.balance(user);
@@ -1372,7 +1714,10 @@ impl TransferEnforcer {
         let contract_delta = after_contract.saturating_sub(before_contract);
         let user_delta = before_user.saturating_sub(after_user);
 
-        if contract_delta != amount || user_delta != amount {
+        let short_delivery = contract_delta > 0
+            && contract_delta < amount
+            && cross_asset::CrossAssetManager::allows_fee_on_transfer(env, &asset);
+        if user_delta != amount || (contract_delta != amount && !short_delivery) {
             Self::emit_failure(
                 env,
                 user,
@@ -1385,8 +1730,8 @@ impl TransferEnforcer {
             return Err(ProtocolError::BalanceInvariantViolation);
         }
 
-        Self::emit_success(env, user, &contract, &asset, amount, &flow);
-        Ok(())
+        Self::emit_success(env, user, &contract, &asset, contract_delta, &flow);
+        Ok(contract_delta)
     }
 
     pub fn transfer_out(
@@ -1483,8 +1828,9 @@ impl EmergencyManager {
         let state = EmergencyStorage::get(env);
         match state.status {
             EmergencyStatus::Operational => Ok(()),
+            // Repayment only reduces risk, so it stays open even while paused
             EmergencyStatus::Paused => match operation {
-                OperationKind::Admin | OperationKind::Governance => Ok(()),
+                OperationKind::Repay | OperationKind::Admin | OperationKind::Governance => Ok(()),
                 _ => Err(ProtocolError::ProtocolPaused),
             },
             EmergencyStatus::Recovery => match operation {
@@ -1534,8 +1880,17 @@ impl EmergencyManager {
         Ok(())
     }
 
+    /// Admin only: emergency managers pause through a circuit break, which lapses
+    /// unless the admin confirms it
     pub fn pause(env: &Env, caller: &Address, reason: Option<String>) -> Result<(), ProtocolError> {
-        Self::ensure_authorized(env, caller)?;
+        ProtocolConfig::require_admin(env, caller)?;
+        Self::set_paused(env, caller, reason);
+        // An explicit pause supersedes any circuit break awaiting confirmation
+        IncidentStorage::remove(env);
+        Ok(())
+    }
+
+    fn set_paused(env: &Env, caller: &Address, reason: Option<String>) {
         let mut state = EmergencyStorage::get(env);
         state.status = EmergencyStatus::Paused;
         state.paused_by = Some(caller.clone());
@@ -1544,20 +1899,21 @@ impl EmergencyManager {
         EmergencyStorage::save(env, &state);
 
         ProtocolEvent::EmergencyStatusChanged(Symbol::new(env, "paused"), reason).emit(env);
-        Ok(())
     }
 
+    /// Admin only, as recovery also clears any circuit break awaiting confirmation
     pub fn enter_recovery(
         env: &Env,
         caller: &Address,
         plan: Option<String>,
     ) -> Result<(), ProtocolError> {
-        Self::ensure_authorized(env, caller)?;
+        ProtocolConfig::require_admin(env, caller)?;
         let mut state = EmergencyStorage::get(env);
         state.status = EmergencyStatus::Recovery;
         state.recovery_plan = plan.clone();
         state.last_recovery_update = env.ledger().timestamp();
         EmergencyStorage::save(env, &state);
+        IncidentStorage::remove(env);
 
         ProtocolEvent::EmergencyStatusChanged(Symbol::new(env, "recovery"), plan).emit(env);
         Ok(())
@@ -1571,6 +1927,7 @@ impl EmergencyManager {
         state.recovery_plan = None;
         state.last_recovery_update = env.ledger().timestamp();
         EmergencyStorage::save(env, &state);
+        IncidentStorage::remove(env);
 
         ProtocolEvent::EmergencyStatusChanged(Symbol::new(env, "operational"), None).emit(env);
         Ok(())
@@ -1623,7 +1980,7 @@ impl EmergencyManager {
 
         for idx in 0..len {
             if let Some(update) = updates.get(idx) {
-                Self::apply_single_update(env, &update)?;
+                Self::apply_single_update(env, caller, &update)?;
                 ProtocolEvent::EmergencyParamUpdateApplied(update.key.clone(), update.value)
                     .emit(env);
             }
@@ -1634,7 +1991,11 @@ impl EmergencyManager {
         Ok(())
     }
 
-    fn apply_single_update(env: &Env, update: &EmergencyParamUpdate) -> Result<(), ProtocolError> {
+    fn apply_single_update(
+        env: &Env,
+        caller: &Address,
+        update: &EmergencyParamUpdate,
+    ) -> Result<(), ProtocolError> {
         let key_min_collateral = Symbol::new(env, "min_collateral_ratio");
         let key_reserve_factor = Symbol::new(env, "reserve_factor");
         let key_base_rate = Symbol::new(env, "base_rate");
@@ -1643,6 +2004,7 @@ impl EmergencyManager {
         let key_rate_ceiling = Symbol::new(env, "rate_ceiling");
         let key_rate_floor = Symbol::new(env, "rate_floor");
         let key_flash_fee = Symbol::new(env, "flash_fee_bps");
+        let key_interest_cap = Symbol::new(env, "interest_cap_bps");
 
         if update.key == key_min_collateral {
             let admin = ProtocolConfig::get_admin(env).ok_or(ProtocolError::ConfigurationError)?;
@@ -1651,27 +2013,33 @@ impl EmergencyManager {
         }
 
         let mut config = InterestRateStorage::get_config(env);
-        if update.key == key_reserve_factor {
-            config.reserve_factor = update.value;
+        let field = if update.key == key_reserve_factor {
+            &mut config.reserve_factor
         } else if update.key == key_base_rate {
-            config.base_rate = update.value;
+            &mut config.base_rate
         } else if update.key == key_kink_util {
-            config.kink_utilization = update.value;
+            &mut config.kink_utilization
         } else if update.key == key_multiplier {
-            config.multiplier = update.value;
+            &mut config.multiplier
         } else if update.key == key_rate_ceiling {
-            config.rate_ceiling = update.value;
+            &mut config.rate_ceiling
         } else if update.key == key_rate_floor {
-            config.rate_floor = update.value;
+            &mut config.rate_floor
+        } else if update.key == key_interest_cap {
+            &mut config.interest_cap_bps
         } else if update.key == key_flash_fee {
             let admin = ProtocolConfig::get_admin(env).ok_or(ProtocolError::ConfigurationError)?;
             ProtocolConfig::set_flash_loan_fee_bps(env, &admin, update.value)?;
             return Ok(());
         } else {
             return Err(ProtocolError::InvalidParameters);
-        }
+        };
+        let old_value = *field;
+        *field = update.value;
 
         InterestRateStorage::save_config(env, &config);
+        ProtocolEvent::ConfigUpdated(caller.clone(), update.key.clone(), old_value, update.value)
+            .emit(env);
         Ok(())
     }
 
@@ -1707,34 +2075,134 @@ impl EmergencyManager {
         ProtocolEvent::EmergencyFundUpdated(caller.clone(), delta, reserve_delta).emit(env);
         Ok(())
     }
+
+    fn cover_cap_key() -> DataKey {
+        DataKey::CoverCap
+    }
+
+    fn cover_usage_key() -> DataKey {
+        DataKey::CoverUsage
+    }
+
+    /// Most insurance funds `cover_position` may spend per incident
+    pub fn get_cover_cap(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Self::cover_cap_key())
+            .unwrap_or(0)
+    }
+
+    pub fn set_cover_cap(env: &Env, caller: &Address, cap: i128) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if cap < 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        env.storage().instance().set(&Self::cover_cap_key(), &cap);
+        env.publish_event((Symbol::new(env, "cover_cap_updated"), caller.clone()), cap);
+        Ok(())
+    }
+
+    /// Repay `user`'s debt from the unreserved insurance fund while an incident is open.
+    /// Incidents are identified by the time the protocol was paused, and each one may spend
+    /// at most the cover cap. Returns the debt repaid.
+    pub fn cover_position(
+        env: &Env,
+        caller: &Address,
+        user: &Address,
+        asset: &Address,
+        amount: i128,
+        reason: String,
+    ) -> Result<i128, ProtocolError> {
+        Self::ensure_authorized(env, caller)?;
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if reason.is_empty() {
+            return Err(ProtocolError::InvalidInput);
+        }
+        if *asset != TokenRegistry::require_primary_asset(env)? {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+        let mut state = EmergencyStorage::get(env);
+        if state.status == EmergencyStatus::Operational {
+            return Err(ProtocolError::InvalidOperation);
+        }
+
+        let (incident, covered): (u64, i128) = env
+            .storage()
+            .instance()
+            .get(&Self::cover_usage_key())
+            .unwrap_or((0, 0));
+        let covered = if incident == state.paused_at {
+            covered
+        } else {
+            0
+        };
+
+        let mut position =
+            StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        let rates = InterestRateStorage::update_state(env);
+        InterestRateManager::accrue_interest_for_position(
+            env,
+            &mut position,
+            rates.current_borrow_rate,
+            rates.liquidity_index,
+        );
+        let repaid = amount.min(position.debt);
+        if repaid == 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        if covered + repaid > Self::get_cover_cap(env) {
+            return Err(ProtocolError::CapExceeded);
+        }
+        if repaid > state.fund.balance - state.fund.reserved {
+            return Err(ProtocolError::EmergencyFundInsufficient);
+        }
+
+        state.fund.balance -= repaid;
+        state.fund.last_update = env.ledger().timestamp();
+        EmergencyStorage::save(env, &state);
+        env.storage().instance().set(
+            &Self::cover_usage_key(),
+            &(state.paused_at, covered + repaid),
+        );
+        position.debt -= repaid;
+        StateHelper::save_position(env, &position);
+
+        ProtocolEvent::EmergencyFundUpdated(caller.clone(), -repaid, 0).emit(env);
+        env.publish_event(
+            (Symbol::new(env, "position_covered"), user.clone()),
+            (caller.clone(), repaid, reason),
+        );
+        Ok(repaid)
+    }
 }
 
-/// Reentrancy guard for security
+/// Reentrancy guard for security. The lock lives in temporary storage and is removed on
+/// exit rather than reset, so it never outlives the invocation that took it; a trapped call
+/// rolls back its writes, lock included. Entry is also refused while an `ExternalCall` is
+/// in progress. Prefer `ReentrancyScope` over calling this directly.
 pub struct ReentrancyGuard;
 
 impl ReentrancyGuard {
-    fn key(env: &Env) -> Symbol {
-        Symbol::new(env, "reentrancy")
+    fn key() -> DataKey {
+        DataKey::Reentrancy
     }
     pub fn enter(env: &Env) -> Result<(), ProtocolError> {
-        let entered = env
-            .storage()
-            .instance()
-            .get::<Symbol, bool>(&Self::key(env))
-            .unwrap_or(false);
-        if entered {
+        if env.storage().temporary().has(&Self::key()) || ExternalCall::in_progress(env) {
             let error = ProtocolError::ReentrancyDetected;
             return Err(error);
         }
-        env.storage().instance().set(&Self::key(env), &true);
+        env.storage().temporary().set(&Self::key(), &true);
         Ok(())
     }
     pub fn exit(env: &Env) {
-        env.storage().instance().set(&Self::key(env), &false);
+        env.storage().temporary().remove(&Self::key());
     }
 }
 
-/// RAII helper to ensure reentrancy guard exit on scope drop
+/// RAII helper holding the reentrancy lock for the scope of one entrypoint or module flow;
+/// the lock is released on every return path, including early `?` returns
 pub struct ReentrancyScope<'a> {
     env: &'a Env,
 }
@@ -1772,6 +2240,12 @@ pub struct Position {
     pub supply_interest: i128,
     /// Last time interest was accrued for this position
     pub last_accrual_time: u64,
+    /// Borrow interest hit the configured cap and accrual is paused
+    pub interest_capped: bool,
+    /// Fold accrued supply interest into collateral on every accrual
+    pub auto_compound: bool,
+    /// Liquidity index at the last supply accrual (scaled by LIQUIDITY_INDEX_SCALE)
+    pub supply_index: i128,
 }
 
 impl Position {
@@ -1784,10 +2258,45 @@ impl Position {
             borrow_interest: 0,
             supply_interest: 0,
             last_accrual_time: 0,
+            interest_capped: false,
+            auto_compound: false,
+            supply_index: 0,
         }
     }
 }
 
+/// Everything a dashboard needs to render a user, gathered in one read
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UserState {
+    pub user: Address,
+    /// Collateral held in the primary asset
+    pub collateral: i128,
+    pub debt: i128,
+    /// Accrued borrow interest as of the last position update
+    pub borrow_interest: i128,
+    /// Accrued supply interest as of the last position update
+    pub supply_interest: i128,
+    pub interest_capped: bool,
+    /// Collateral balances held in non-primary assets
+    pub asset_collateral: Map<Address, i128>,
+    /// Risk-adjusted value of all collateral in primary asset units
+    pub weighted_collateral: i128,
+    pub collateral_ratio: i128,
+    /// Collateral ratio relative to the minimum (100 = at the liquidation threshold)
+    pub health_factor: i128,
+    pub role: UserRole,
+    pub verification: VerificationStatus,
+    /// Freeze level in force, after any review expiry
+    pub freeze: FreezeLevel,
+    pub freeze_expires_at: u64,
+    /// Verification was rejected and the user is barred from the protocol
+    pub blacklisted: bool,
+    pub activity_score: i128,
+    pub last_active: u64,
+    pub analytics: analytics::UserAnalytics,
+}
+
 /// Interest rate configuration parameters
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -1810,11 +2319,15 @@ pub struct InterestRateConfig {
     pub smoothing_bps: i128,
     /// Volatility sensitivity in bps (impact of utilization change)
     pub util_sensitivity_bps: i128,
+    /// Max unpaid borrow interest relative to principal in bps (0 disables the cap)
+    pub interest_cap_bps: i128,
+    /// Half-life in seconds of the utilization EWMA used for rates (0 uses raw utilization)
+    pub utilization_half_life: u64,
 }
 
-impl InterestRateConfig {
+impl Default for InterestRateConfig {
     /// Create default interest rate configuration
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self {
             base_rate: 2000000,         // 2%
             kink_utilization: 80000000, // 80%
@@ -1825,10 +2338,18 @@ impl InterestRateConfig {
             last_update: 0,
             smoothing_bps: 2000,       // 20% smoothing by default
             util_sensitivity_bps: 100, // 1% per 1% util change
+            interest_cap_bps: 0,       // uncapped
+            utilization_half_life: 0,  // raw utilization
         }
     }
 }
 
+/// Fixed-point scale of the liquidity index; finer than rates so short intervals still register
+pub const LIQUIDITY_INDEX_SCALE: i128 = 1_000_000_000_000_000_000;
+
+/// Most positions `accrue_positions` brings current in one call
+pub const MAX_ACCRUAL_BATCH: u32 = 25;
+
 /// Current interest rate state
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -1847,6 +2368,11 @@ pub struct InterestRateState {
     pub last_accrual_time: u64,
     /// Smoothed borrow rate
     pub smoothed_borrow_rate: i128,
+    /// Time-weighted (EWMA) utilization the borrow rate is derived from (scaled by 1e8)
+    pub smoothed_utilization: i128,
+    /// Cumulative growth of one unit supplied, checkpointed at every accrual
+    /// (scaled by LIQUIDITY_INDEX_SCALE)
+    pub liquidity_index: i128,
 }
 
 impl InterestRateState {
@@ -1860,10 +2386,47 @@ impl InterestRateState {
             total_supplied: 0,
             last_accrual_time: 0,
             smoothed_borrow_rate: 0,
+            smoothed_utilization: 0,
+            liquidity_index: LIQUIDITY_INDEX_SCALE,
         }
     }
 }
 
+/// Utilization snapshot returned by `get_utilization_metrics`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UtilizationMetrics {
+    /// Instantaneous borrowed / supplied (scaled by 1e8)
+    pub raw_utilization: i128,
+    /// EWMA of utilization used for rate setting (scaled by 1e8)
+    pub smoothed_utilization: i128,
+    /// EWMA half-life in seconds; zero means rates follow raw utilization
+    pub half_life: u64,
+    pub borrow_rate: i128,
+}
+
+/// Per-asset market snapshot returned by `get_market_data`; amounts are in the asset's
+/// own units, `decimals` tells frontends how to scale them
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct MarketData {
+    pub asset: Address,
+    pub decimals: u32,
+    pub total_supplied: i128,
+    pub total_borrowed: i128,
+    /// Tokens held by the contract that are not owed to the treasury
+    pub available_liquidity: i128,
+    /// Reserve income accrued for the treasury and not yet distributed
+    pub reserve_balance: i128,
+    /// Annual rates (scaled by 1e8)
+    pub borrow_rate: i128,
+    pub supply_rate: i128,
+    /// Borrowed / supplied (scaled by 1e8)
+    pub utilization: i128,
+    /// Cumulative supply growth (scaled by LIQUIDITY_INDEX_SCALE)
+    pub liquidity_index: i128,
+}
+
 /// Risk management configuration
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
@@ -1881,8 +2444,8 @@ pub struct RiskConfig {
     pub last_update: u64,
 }
 
-impl RiskConfig {
-    pub fn default() -> Self {
+impl Default for RiskConfig {
+    fn default() -> Self {
         Self {
             close_factor: 50000000,          // 50%
             liquidation_incentive: 10000000, // 10%
@@ -1899,19 +2462,95 @@ impl RiskConfig {
 pub struct RiskConfigStorage;
 
 impl RiskConfigStorage {
-    fn key(env: &Env) -> Symbol {
-        Symbol::new(env, "risk_config")
+    fn key() -> DataKey {
+        DataKey::RiskConfig
     }
 
     pub fn save(env: &Env, config: &RiskConfig) {
-        env.storage().instance().set(&Self::key(env), config);
+        env.storage().instance().set(&Self::key(), config);
     }
 
+    /// Risk config with the pause switches lifted once a timed pause has expired
     pub fn get(env: &Env) -> RiskConfig {
-        env.storage()
+        let mut config: RiskConfig = env
+            .storage()
             .instance()
-            .get(&Self::key(env))
-            .unwrap_or_else(RiskConfig::default)
+            .get(&Self::key())
+            .unwrap_or_default();
+        if PauseStorage::get(env).is_some_and(|info| info.is_expired(env)) {
+            config.pause_borrow = false;
+            config.pause_deposit = false;
+            config.pause_withdraw = false;
+            config.pause_liquidate = false;
+        }
+        config
+    }
+}
+
+/// Who paused the market, why, and until when
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PauseInfo {
+    pub paused_by: Address,
+    /// Operator-defined reason code (0 = unspecified)
+    pub reason_code: u32,
+    pub paused_at: u64,
+    /// Timestamp at which the pause lifts by itself (0 = until unpaused)
+    pub expires_at: u64,
+}
+
+impl PauseInfo {
+    pub fn is_expired(&self, env: &Env) -> bool {
+        self.expires_at != 0 && env.ledger().timestamp() >= self.expires_at
+    }
+}
+
+/// Storage helper for the active pause record
+pub struct PauseStorage;
+
+impl PauseStorage {
+    fn key() -> DataKey {
+        DataKey::PauseInfo
+    }
+
+    pub fn get(env: &Env) -> Option<PauseInfo> {
+        env.storage().instance().get(&Self::key())
+    }
+
+    pub fn save(env: &Env, info: &PauseInfo) {
+        env.storage().instance().set(&Self::key(), info);
+    }
+
+    pub fn remove(env: &Env) {
+        env.storage().instance().remove(&Self::key());
+    }
+}
+
+/// Overflow-safe fixed-point helpers for rate and utilization math
+pub struct SafeMath;
+
+impl SafeMath {
+    /// `a * b / denominator` without overflowing on the intermediate product, saturating at
+    /// the i128 bounds when the result itself does not fit; zero when `denominator` is zero
+    pub fn mul_div(a: i128, b: i128, denominator: i128) -> i128 {
+        if denominator == 0 {
+            return 0;
+        }
+        match a.checked_mul(b) {
+            Some(product) => product / denominator,
+            // a * b / d == (a / d) * b + (a % d) * b / d
+            None => (a / denominator)
+                .saturating_mul(b)
+                .saturating_add((a % denominator).saturating_mul(b) / denominator),
+        }
+    }
+
+    /// Borrowed share of supply scaled by 1e8
+    pub fn utilization(total_borrowed: i128, total_supplied: i128) -> i128 {
+        if total_supplied <= 0 {
+            return 0;
+        }
+        Self::mul_div(total_borrowed, 100000000, total_supplied)
     }
 }
 
@@ -1919,66 +2558,89 @@ impl RiskConfigStorage {
 pub struct InterestRateStorage;
 
 impl InterestRateStorage {
-    fn config_key(env: &Env) -> Symbol {
-        Symbol::new(env, "interest_config")
+    fn config_key() -> DataKey {
+        DataKey::InterestConfig
     }
 
-    fn state_key(env: &Env) -> Symbol {
-        Symbol::new(env, "interest_state")
+    fn state_key() -> DataKey {
+        DataKey::InterestState
     }
 
     pub fn save_config(env: &Env, config: &InterestRateConfig) {
-        env.storage().instance().set(&Self::config_key(env), config);
+        env.storage().instance().set(&Self::config_key(), config);
     }
 
     pub fn get_config(env: &Env) -> InterestRateConfig {
         env.storage()
             .instance()
-            .get(&Self::config_key(env))
-            .unwrap_or_else(InterestRateConfig::default)
+            .get(&Self::config_key())
+            .unwrap_or_default()
     }
 
     pub fn save_state(env: &Env, state: &InterestRateState) {
-        env.storage().instance().set(&Self::state_key(env), state);
+        env.storage().instance().set(&Self::state_key(), state);
     }
 
     pub fn get_state(env: &Env) -> InterestRateState {
         env.storage()
             .instance()
-            .get(&Self::state_key(env))
+            .get(&Self::state_key())
             .unwrap_or_else(InterestRateState::initial)
     }
 
+    /// Adjust pool totals by a position change and re-evaluate the borrow kill-switch
+    pub fn apply_position_delta(env: &Env, collateral_delta: i128, debt_delta: i128) {
+        if collateral_delta == 0 && debt_delta == 0 {
+            return;
+        }
+        let mut state = Self::get_state(env);
+        state.total_supplied = (state.total_supplied + collateral_delta).max(0);
+        state.total_borrowed = (state.total_borrowed + debt_delta).max(0);
+        state.utilization_rate = SafeMath::utilization(state.total_borrowed, state.total_supplied);
+        Self::save_state(env, &state);
+        BorrowLimits::refresh(env, state.utilization_rate);
+        RecoveryMode::refresh(env);
+    }
+
     pub fn update_state(env: &Env) -> InterestRateState {
         let mut state = Self::get_state(env);
         let config = Self::get_config(env);
 
-        // Simple interest rate calculation based on utilization
-        if state.total_supplied > 0 {
-            state.utilization_rate = (state.total_borrowed * 100000000) / state.total_supplied;
-        } else {
-            state.utilization_rate = 0;
-        }
+        // Checkpoint the liquidity index at the supply rate in force since the last accrual,
+        // so supplier payouts stay exact across rate changes
+        let now = env.ledger().timestamp();
+        if state.last_accrual_time > 0 && now > state.last_accrual_time {
+            let growth = SafeMath::mul_div(
+                state.liquidity_index,
+                state.current_supply_rate * (now - state.last_accrual_time) as i128,
+                365 * 24 * 60 * 60 * 100000000,
+            );
+            state.liquidity_index = state.liquidity_index.saturating_add(growth);
+        }
+
+        // Simple interest rate calculation based on utilization
+        state.utilization_rate = SafeMath::utilization(state.total_borrowed, state.total_supplied);
 
-        // Calculate borrow rate based on utilization
-        if state.utilization_rate <= config.kink_utilization {
-            state.current_borrow_rate =
-                config.base_rate + (state.utilization_rate * config.multiplier) / 100000000;
+        // Rates follow the time-weighted utilization so a single large deposit or borrow
+        // moves them gradually
+        state.smoothed_utilization = if config.utilization_half_life == 0
+            || state.last_accrual_time == 0
+        {
+            state.utilization_rate
         } else {
-            let kink_rate =
-                config.base_rate + (config.kink_utilization * config.multiplier) / 100000000;
-            let excess_utilization = state.utilization_rate - config.kink_utilization;
-            state.current_borrow_rate =
-                kink_rate + (excess_utilization * config.multiplier * 2) / 100000000;
-        }
+            let weight = InterestRateManager::decay_weight(
+                now - state.last_accrual_time,
+                config.utilization_half_life,
+            );
+            (state.smoothed_utilization * weight + state.utilization_rate * (100000000 - weight))
+                / 100000000
+        };
 
-        // Apply rate limits
-        if state.current_borrow_rate > config.rate_ceiling {
-            state.current_borrow_rate = config.rate_ceiling;
-        }
-        if state.current_borrow_rate < config.rate_floor {
-            state.current_borrow_rate = config.rate_floor;
-        }
+        // Markets too thin to price from utilization borrow at a fixed conservative rate
+        state.current_borrow_rate = BorrowLimits::thin_market_rate(env, state.total_supplied)
+            .unwrap_or_else(|| {
+                InterestRateManager::calculate_borrow_rate(&config, state.smoothed_utilization)
+            });
 
         // Smoothing for borrow rate: new = old*(s) + current*(1-s)
         let s_bps = config.smoothing_bps;
@@ -1990,8 +2652,9 @@ impl InterestRateStorage {
         state.current_supply_rate =
             state.smoothed_borrow_rate * (100000000 - config.reserve_factor) / 100000000;
 
-        state.last_accrual_time = env.ledger().timestamp();
+        state.last_accrual_time = now;
         Self::save_state(env, &state);
+        RewardManager::refresh_bands(env);
         state
     }
 }
@@ -2000,15 +2663,43 @@ impl InterestRateStorage {
 pub struct InterestRateManager;
 
 impl InterestRateManager {
+    /// Borrow rate for a utilization under the kinked model, clamped to the rate limits
+    pub fn calculate_borrow_rate(config: &InterestRateConfig, utilization: i128) -> i128 {
+        let rate = if utilization <= config.kink_utilization {
+            config.base_rate + (utilization * config.multiplier) / 100000000
+        } else {
+            let kink_rate =
+                config.base_rate + (config.kink_utilization * config.multiplier) / 100000000;
+            let excess_utilization = utilization - config.kink_utilization;
+            kink_rate + (excess_utilization * config.multiplier * 2) / 100000000
+        };
+
+        // Apply rate limits
+        rate.min(config.rate_ceiling).max(config.rate_floor)
+    }
+
+    /// Weight (scaled by 1e8) kept by the previous EWMA value after `elapsed` seconds,
+    /// i.e. 2^(-elapsed / half_life), interpolated linearly between whole half-lives
+    pub fn decay_weight(elapsed: u64, half_life: u64) -> i128 {
+        let halvings = elapsed / half_life;
+        if halvings >= 64 {
+            return 0;
+        }
+        let whole = 100000000i128 >> halvings;
+        let remainder = (elapsed % half_life) as i128;
+        whole - whole * remainder / (2 * half_life as i128)
+    }
+
     pub fn accrue_interest_for_position(
         env: &Env,
         position: &mut Position,
         borrow_rate: i128,
-        supply_rate: i128,
+        liquidity_index: i128,
     ) {
         let current_time = env.ledger().timestamp();
         if position.last_accrual_time == 0 {
             position.last_accrual_time = current_time;
+            position.supply_index = liquidity_index;
             return;
         }
 
@@ -2017,40 +2708,222 @@ impl InterestRateManager {
             return;
         }
 
-        // Accrue borrow interest
+        // Accrue borrow interest, stopping at the configured cap
         if position.debt > 0 {
-            let interest = (position.debt * borrow_rate * time_delta as i128)
-                / (365 * 24 * 60 * 60 * 100000000);
-            position.borrow_interest += interest;
+            // Debt still inside a promotional grace window accrues nothing
+            let graced = GracePeriod::graced_debt_seconds(
+                env,
+                &position.user,
+                position.debt,
+                position.last_accrual_time,
+                current_time,
+            );
+            let debt_seconds = position.debt.saturating_mul(time_delta as i128) - graced;
+            // Debt borrowed against a quote accrues at the quoted rate for its first period
+            let borrow_rate =
+                RateQuotes::accrual_rate(env, &position.user, position.debt, borrow_rate);
+            let borrow_rate = Staking::discounted_rate(env, &position.user, borrow_rate);
+            let interest =
+                SafeMath::mul_div(debt_seconds, borrow_rate, 365 * 24 * 60 * 60 * 100000000);
+
+            let config = InterestRateStorage::get_config(env);
+            let cap_bps = config.interest_cap_bps;
+            let was_capped = position.interest_capped;
+            let interest_before = position.borrow_interest;
+            if cap_bps > 0 {
+                let cap = SafeMath::mul_div(position.debt, cap_bps, 10000);
+                // Interest already above the cap (e.g. after a partial repay) is kept, not clawed back
+                if position.borrow_interest < cap {
+                    position.borrow_interest =
+                        position.borrow_interest.saturating_add(interest).min(cap);
+                }
+                position.interest_capped = position.borrow_interest >= cap;
+            } else {
+                position.borrow_interest = position.borrow_interest.saturating_add(interest);
+                position.interest_capped = false;
+            }
+
+            // The reserve factor's share of newly accrued interest is protocol revenue
+            let accrued = position.borrow_interest - interest_before;
+            RevenueManager::record_interest(
+                env,
+                SafeMath::mul_div(accrued, config.reserve_factor, 100000000),
+            );
+            FeeLedger::record(env, &position.user, FeeKind::Interest, accrued);
+
+            if position.interest_capped && !was_capped {
+                env.publish_event(
+                    (Symbol::new(env, "interest_capped"), position.user.clone()),
+                    (
+                        Symbol::new(env, "debt"),
+                        position.debt,
+                        Symbol::new(env, "borrow_interest"),
+                        position.borrow_interest,
+                    ),
+                );
+            }
+        }
+
+        // Supply interest is the growth of the supplied balance, including interest not yet
+        // claimed, along the liquidity index since the position's last checkpoint; this keeps
+        // payouts independent of how often the position is touched
+        let supplied = position.collateral + position.supply_interest;
+        if supplied > 0 && position.supply_index > 0 {
+            let interest = SafeMath::mul_div(
+                supplied,
+                liquidity_index - position.supply_index,
+                position.supply_index,
+            );
+            position.supply_interest = position.supply_interest.saturating_add(interest.max(0));
         }
+        position.supply_index = liquidity_index;
 
-        // Accrue supply interest
-        if position.collateral > 0 {
-            let interest = (position.collateral * supply_rate * time_delta as i128)
-                / (365 * 24 * 60 * 60 * 100000000);
-            position.supply_interest += interest;
+        // Idle markets may charge collateral a storage fee instead
+        SupplyFee::charge(env, position, time_delta);
+
+        if position.auto_compound && position.supply_interest > 0 {
+            position.collateral += position.supply_interest;
+            position.supply_interest = 0;
         }
 
         position.last_accrual_time = current_time;
     }
+
+    /// Bring a batch of positions current against the global index, skipping addresses
+    /// without a position; returns how many positions were accrued
+    pub fn accrue_positions(
+        env: &Env,
+        asset: &Address,
+        users: &Vec<Address>,
+    ) -> Result<u32, ProtocolError> {
+        if users.is_empty() || users.len() > MAX_ACCRUAL_BATCH {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        // Only the primary asset's pool carries an interest index
+        if *asset != TokenRegistry::require_primary_asset(env)? {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+
+        let state = InterestRateStorage::update_state(env);
+        let mut accrued = 0;
+        for user in users.iter() {
+            if let Some(mut position) = StateHelper::get_position(env, &user) {
+                Self::accrue_interest_for_position(
+                    env,
+                    &mut position,
+                    state.current_borrow_rate,
+                    state.liquidity_index,
+                );
+                StateHelper::save_position(env, &position);
+                accrued += 1;
+            }
+        }
+        env.publish_event(
+            (Symbol::new(env, "positions_accrued"), asset.clone()),
+            (accrued, state.liquidity_index),
+        );
+        Ok(accrued)
+    }
 }
 
 /// State helper for managing user positions
 pub struct StateHelper;
 
 impl StateHelper {
-    fn position_key(env: &Env, _user: &Address) -> Symbol {
-        Symbol::new(env, &format!("position_{}", "user"))
+    fn position_key(user: &Address) -> DataKey {
+        DataKey::Position(user.clone())
     }
 
+    /// Persist a position and fold its collateral and debt changes into the pool totals
     pub fn save_position(env: &Env, position: &Position) {
-        let key = Self::position_key(env, &position.user);
-        env.storage().instance().set(&key, position);
+        let (old_collateral, old_debt) = Self::get_position(env, &position.user)
+            .map(|old| (old.collateral, old.debt))
+            .unwrap_or((0, 0));
+        let key = Self::position_key(&position.user);
+        env.storage().persistent().set(&key, position);
+        Ttl::extend_entry(env, &key);
+        InterestRateStorage::apply_position_delta(
+            env,
+            position.collateral - old_collateral,
+            position.debt - old_debt,
+        );
+        if let Ok(primary) = TokenRegistry::require_primary_asset(env) {
+            let active = position.collateral > 0 || position.debt > 0;
+            cross_asset::UserMarkets::sync(env, &position.user, &primary, active);
+            // Rewards accrue on principal, so only principal changes need a checkpoint
+            if position.collateral != old_collateral {
+                RewardManager::checkpoint(
+                    env,
+                    &position.user,
+                    &primary,
+                    RewardSide::Supply,
+                    position.collateral,
+                );
+            }
+            if position.debt != old_debt {
+                RewardManager::checkpoint(
+                    env,
+                    &position.user,
+                    &primary,
+                    RewardSide::Borrow,
+                    position.debt,
+                );
+            }
+        }
+        LiquidationWatchlist::sync(env, position);
     }
 
     pub fn get_position(env: &Env, user: &Address) -> Option<Position> {
-        let key = Self::position_key(env, user);
-        env.storage().instance().get::<Symbol, Position>(&key)
+        let key = Self::position_key(user);
+        env.storage().persistent().get(&key)
+    }
+
+    /// Delete a position; callers zero it through `save_position` first so the pool
+    /// totals stay balanced
+    pub fn remove_position(env: &Env, user: &Address) {
+        env.storage().persistent().remove(&Self::position_key(user));
+    }
+}
+
+/// Deployment settings supplied to `initialize`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct InitConfig {
+    /// Recipient of protocol fees
+    pub treasury: Address,
+    /// Token contract of the default (primary) asset, e.g. native XLM
+    pub native_asset: Address,
+    /// Minimum collateral ratio in percent
+    pub min_collateral_ratio: i128,
+    /// Max % of debt repaid per liquidation (scaled by 1e8)
+    pub close_factor: i128,
+    /// % bonus collateral given to liquidators (scaled by 1e8)
+    pub liquidation_incentive: i128,
+}
+
+/// Sanity checks for deployment configuration
+pub struct ConfigurationValidator;
+
+impl ConfigurationValidator {
+    pub fn validate_init(env: &Env, config: &InitConfig) -> Result<(), ProtocolError> {
+        let contract = env.current_contract_address();
+        if config.treasury == contract || config.native_asset == contract {
+            return Err(ProtocolError::InvalidAddress);
+        }
+        if config.treasury == config.native_asset {
+            return Err(ProtocolError::InvalidAddress);
+        }
+        // Positions must be over-collateralized
+        if config.min_collateral_ratio < 100 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if config.close_factor <= 0 || config.close_factor > 100000000 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if !(0..=100000000).contains(&config.liquidation_incentive) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        Ok(())
     }
 }
 
@@ -2058,30 +2931,48 @@ impl StateHelper {
 pub struct ProtocolConfig;
 
 impl ProtocolConfig {
-    fn admin_key(env: &Env) -> Symbol {
-        Symbol::new(env, "admin")
+    fn admin_key() -> DataKey {
+        DataKey::Admin
+    }
+
+    fn oracle_key() -> DataKey {
+        DataKey::Oracle
+    }
+
+    fn min_collateral_ratio_key() -> DataKey {
+        DataKey::MinCollateralRatio
     }
 
-    fn oracle_key(env: &Env) -> Symbol {
-        Symbol::new(env, "oracle")
+    fn flash_fee_bps_key() -> DataKey {
+        DataKey::FlashFeeBps
     }
 
-    fn min_collateral_ratio_key(env: &Env) -> Symbol {
-        Symbol::new(env, "min_ratio")
+    fn treasury_key() -> DataKey {
+        DataKey::Treasury
     }
 
-    fn flash_fee_bps_key(env: &Env) -> Symbol {
-        Symbol::new(env, "flash_fee_bps")
+    fn health_buffer_key() -> DataKey {
+        DataKey::HealthFactorBuffer
+    }
+
+    pub fn set_treasury(env: &Env, treasury: &Address) {
+        env.storage()
+            .instance()
+            .set(&Self::treasury_key(), treasury);
+    }
+
+    pub fn get_treasury(env: &Env) -> Option<Address> {
+        env.storage().instance().get(&Self::treasury_key())
     }
 
     pub fn set_admin(env: &Env, admin: &Address) {
-        env.storage().instance().set(&Self::admin_key(env), admin);
+        env.storage().instance().set(&Self::admin_key(), admin);
     }
 
     pub fn get_admin(env: &Env) -> Option<Address> {
         env.storage()
             .instance()
-            .get::<Symbol, Address>(&Self::admin_key(env))
+            .get::<DataKey, Address>(&Self::admin_key())
     }
 
     pub fn require_admin(env: &Env, caller: &Address) -> Result<(), ProtocolError> {
@@ -2094,7 +2985,7 @@ impl ProtocolConfig {
 
     pub fn set_oracle(env: &Env, caller: &Address, oracle: &Address) -> Result<(), ProtocolError> {
         Self::require_admin(env, caller)?;
-        env.storage().instance().set(&Self::oracle_key(env), oracle);
+        env.storage().instance().set(&Self::oracle_key(), oracle);
         Ok(())
     }
 
@@ -2107,16 +2998,20 @@ impl ProtocolConfig {
         if ratio <= 0 {
             return Err(ProtocolError::InvalidInput);
         }
+        Self::save_min_collateral_ratio(env, ratio);
+        Ok(())
+    }
+
+    pub(crate) fn save_min_collateral_ratio(env: &Env, ratio: i128) {
         env.storage()
             .instance()
-            .set(&Self::min_collateral_ratio_key(env), &ratio);
-        Ok(())
+            .set(&Self::min_collateral_ratio_key(), &ratio);
     }
 
     pub fn get_min_collateral_ratio(env: &Env) -> i128 {
         env.storage()
             .instance()
-            .get::<Symbol, i128>(&Self::min_collateral_ratio_key(env))
+            .get::<DataKey, i128>(&Self::min_collateral_ratio_key())
             .unwrap_or(150)
     }
 
@@ -2126,23 +3021,61 @@ impl ProtocolConfig {
         bps: i128,
     ) -> Result<(), ProtocolError> {
         Self::require_admin(env, caller)?;
-        if bps < 0 || bps > 10000 {
+        if !(0..=10000).contains(&bps) {
             return Err(ProtocolError::InvalidInput);
         }
         env.storage()
             .instance()
-            .set(&Self::flash_fee_bps_key(env), &bps);
+            .set(&Self::flash_fee_bps_key(), &bps);
         Ok(())
     }
 
     pub fn get_flash_loan_fee_bps(env: &Env) -> i128 {
         env.storage()
             .instance()
-            .get::<Symbol, i128>(&Self::flash_fee_bps_key(env))
+            .get::<DataKey, i128>(&Self::flash_fee_bps_key())
             .unwrap_or(5) // 0.05%
     }
+
+    /// Set the health factor headroom (basis points above 1.0) user-initiated borrows and
+    /// withdrawals must leave; liquidation eligibility is unaffected
+    pub fn set_health_factor_buffer(
+        env: &Env,
+        caller: &Address,
+        bps: i128,
+    ) -> Result<(), ProtocolError> {
+        Self::require_admin(env, caller)?;
+        if !(0..=MAX_HEALTH_FACTOR_BUFFER_BPS).contains(&bps) {
+            return Err(ProtocolError::InvalidInput);
+        }
+        env.storage()
+            .instance()
+            .set(&Self::health_buffer_key(), &bps);
+        env.publish_event(
+            (Symbol::new(env, "health_buffer_updated"), caller.clone()),
+            bps,
+        );
+        Ok(())
+    }
+
+    pub fn get_health_factor_buffer(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get::<DataKey, i128>(&Self::health_buffer_key())
+            .unwrap_or(0)
+    }
+
+    /// Collateral ratio (percent) a position must keep after a user-initiated borrow or
+    /// withdrawal: the minimum ratio raised by the health factor buffer
+    pub fn get_action_min_ratio(env: &Env) -> i128 {
+        let min_ratio = Self::get_min_collateral_ratio(env);
+        min_ratio + SafeMath::mul_div(min_ratio, Self::get_health_factor_buffer(env), 10000)
+    }
 }
 
+/// Largest health factor buffer the admin can require (basis points)
+pub const MAX_HEALTH_FACTOR_BUFFER_BPS: i128 = 5000;
+
 /// Protocol errors
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -2178,16 +3111,93 @@ pub enum ProtocolError {
     UserRoleViolation = 28,
     BalanceInvariantViolation = 29,
     InsufficientLiquidity = 30,
+    RateLimitExceeded = 31,
+    CapExceeded = 32,
+    OracleStale = 33,
+    SlippageExceeded = 34,
+    InsufficientAllowance = 35,
+    ParameterChangeTooLarge = 36,
+    MarketBootstrapping = 37,
+    SeizureOrderViolation = 38,
+    AddressCapExceeded = 39,
+    AmountOutOfRange = 40,
+    AmountPrecisionLoss = 41,
 }
 
-/// Protocol events
+/// Diagnostic payload published alongside a failing user flow. A failed invocation rolls
+/// back its contract events, so on-chain callers only ever receive the `ProtocolError`
+/// code; the context is visible in the host's diagnostic events (e.g. when simulating
+/// the transaction) and inside in-contract tests, never in ledger meta.
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
+pub struct ErrorContext {
+    /// Numeric `ProtocolError` code returned to the caller
+    pub code: u32,
+    /// Flow that failed (deposit, borrow, ...)
+    pub operation: Symbol,
+    /// Account the flow was acting for
+    pub user: Option<Address>,
+    /// Amount argument supplied to the flow
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+impl ErrorContext {
+    pub fn new(
+        env: &Env,
+        error: ProtocolError,
+        operation: Symbol,
+        user: Option<Address>,
+        amount: i128,
+    ) -> Self {
+        Self {
+            code: error as u32,
+            operation,
+            user,
+            amount,
+            timestamp: env.ledger().timestamp(),
+        }
+    }
+
+    /// Publish the context so dapps can see which parameters were rejected
+    pub fn emit(&self, env: &Env) {
+        env.publish_event(
+            (Symbol::new(env, "error_context"), self.operation.clone()),
+            self.clone(),
+        );
+    }
+
+    /// Emit a context event when `result` is an error, passing the result through. The
+    /// event is discarded with the rest of the failed call; see `ErrorContext`.
+    pub fn report<T>(
+        env: &Env,
+        result: Result<T, ProtocolError>,
+        operation: &str,
+        user: &Address,
+        amount: i128,
+    ) -> Result<T, ProtocolError> {
+        if let Err(error) = &result {
+            Self::new(
+                env,
+                *error,
+                Symbol::new(env, operation),
+                Some(user.clone()),
+                amount,
+            )
+            .emit(env);
+        }
+        result
+    }
+}
+
+/// Protocol events. Only published, never stored or passed to entrypoints, so the enum is
+/// not a contract type and is not bound by the contract spec's variant limit.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ProtocolEvent {
     PositionUpdated(Address, i128, i128, i128), // user, collateral, debt, collateral_ratio
     InterestAccrued(Address, i128, i128),       // user, borrow_interest, supply_interest
-    LiquidationExecuted(Address, Address, i128, i128), // liquidator, user, collateral_seized, debt_repaid
-    RiskParamsUpdated(i128, i128),                     // close_factor, liquidation_incentive
+    LiquidationExecuted(Address, Address, Address, i128, i128), // liquidator, receiver, user, collateral_seized, debt_repaid
+    RiskParamsUpdated(i128, i128), // close_factor, liquidation_incentive
     PauseSwitchesUpdated(bool, bool, bool, bool), // pause_borrow, pause_deposit, pause_withdraw, pause_liquidate
     // Cross-asset events
     CrossDeposit(Address, Address, i128),  // user, asset, amount
@@ -2252,6 +3262,10 @@ pub enum ProtocolEvent {
     EmergencyParamUpdateApplied(Symbol, i128),
     EmergencyFundUpdated(Address, i128, i128),
     EmergencyManagerUpdated(Address, bool),
+    // Configuration
+    ConfigUpdated(Address, Symbol, i128, i128), // caller, parameter, old_value, new_value
+    // Oracle
+    PriceServed(Address, PriceTier, i128, i128), // asset, tier, price, confidence
 }
 
 impl ProtocolEvent {
@@ -2259,7 +3273,7 @@ impl ProtocolEvent {
         EventTracker::capture(env, self);
         match self {
             ProtocolEvent::PositionUpdated(user, collateral, debt, collateral_ratio) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "position_updated"),
                         Symbol::new(env, "user"),
@@ -2277,7 +3291,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::InterestAccrued(user, borrow_interest, supply_interest) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "interest_accrued"),
                         Symbol::new(env, "user"),
@@ -2294,11 +3308,12 @@ impl ProtocolEvent {
             }
             ProtocolEvent::LiquidationExecuted(
                 liquidator,
+                receiver,
                 user,
                 collateral_seized,
                 debt_repaid,
             ) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "liquidation_executed"),
                         Symbol::new(env, "liquidator"),
@@ -2306,6 +3321,8 @@ impl ProtocolEvent {
                     (
                         Symbol::new(env, "liquidator"),
                         liquidator.clone(),
+                        Symbol::new(env, "receiver"),
+                        receiver.clone(),
                         Symbol::new(env, "user"),
                         user.clone(),
                         Symbol::new(env, "collateral_seized"),
@@ -2316,7 +3333,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::RiskParamsUpdated(close_factor, liquidation_incentive) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "risk_params_updated"),
                         Symbol::new(env, "close_factor"),
@@ -2335,7 +3352,7 @@ impl ProtocolEvent {
                 pause_withdraw,
                 pause_liquidate,
             ) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "pause_switches_updated"),
                         Symbol::new(env, "pause_borrow"),
@@ -2353,7 +3370,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::CrossDeposit(user, asset, amount) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "cross_deposit"), Symbol::new(env, "user")),
                     (
                         Symbol::new(env, "user"),
@@ -2366,7 +3383,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::CrossBorrow(user, asset, amount) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "cross_borrow"), Symbol::new(env, "user")),
                     (
                         Symbol::new(env, "user"),
@@ -2379,7 +3396,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::CrossRepay(user, asset, amount) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "cross_repay"), Symbol::new(env, "user")),
                     (
                         Symbol::new(env, "user"),
@@ -2392,7 +3409,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::CrossWithdraw(user, asset, amount) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "cross_withdraw"), Symbol::new(env, "user")),
                     (
                         Symbol::new(env, "user"),
@@ -2405,7 +3422,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::EmergencyStatusChanged(status, reason) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "emergency_status"), status.clone()),
                     (
                         Symbol::new(env, "status"),
@@ -2416,7 +3433,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::EmergencyRecoveryStep(step) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "emergency_recovery_step"),
                         Symbol::new(env, "step"),
@@ -2425,7 +3442,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::EmergencyParamUpdateQueued(key, value) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "emergency_param_update_queued"),
                         key.clone(),
@@ -2439,7 +3456,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::EmergencyParamUpdateApplied(key, value) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "emergency_param_update_applied"),
                         key.clone(),
@@ -2453,7 +3470,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::EmergencyFundUpdated(actor, delta, reserve_delta) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "emergency_fund"), actor.clone()),
                     (
                         Symbol::new(env, "actor"),
@@ -2466,7 +3483,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::EmergencyManagerUpdated(manager, enabled) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "emergency_manager"), manager.clone()),
                     (
                         Symbol::new(env, "manager"),
@@ -2476,8 +3493,27 @@ impl ProtocolEvent {
                     ),
                 );
             }
+            ProtocolEvent::ConfigUpdated(caller, parameter, old_value, new_value) => {
+                env.publish_event(
+                    (Symbol::new(env, "config_updated"), parameter.clone()),
+                    (
+                        Symbol::new(env, "caller"),
+                        caller.clone(),
+                        Symbol::new(env, "old_value"),
+                        *old_value,
+                        Symbol::new(env, "new_value"),
+                        *new_value,
+                    ),
+                );
+            }
+            ProtocolEvent::PriceServed(asset, tier, price, confidence) => {
+                env.publish_event(
+                    (Symbol::new(env, "price_served"), asset.clone()),
+                    (*tier, *price, *confidence),
+                );
+            }
             ProtocolEvent::FlashLoanInitiated(initiator, asset, amount, fee) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "flash_loan_initiated"),
                         Symbol::new(env, "user"),
@@ -2495,7 +3531,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::FlashLoanCompleted(initiator, asset, amount, fee) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "flash_loan_completed"),
                         Symbol::new(env, "user"),
@@ -2513,7 +3549,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::DynamicCFUpdated(asset, new_cf) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "dynamic_cf_updated"),
                         Symbol::new(env, "asset"),
@@ -2527,7 +3563,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::AMMSwap(user, asset_in, asset_out, amount_in, amount_out) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "amm_swap"), Symbol::new(env, "user")),
                     (
                         Symbol::new(env, "user"),
@@ -2544,7 +3580,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::AMMLiquidityAdded(user, asset_a, asset_b, amt_a, amt_b) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "amm_liquidity_added"),
                         Symbol::new(env, "user"),
@@ -2564,7 +3600,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::AMMLiquidityRemoved(user, pool, lp_amount) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "amm_liquidity_removed"),
                         Symbol::new(env, "user"),
@@ -2580,7 +3616,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::RiskParamsSet(base_limit, factor, min_rate_bps, max_rate_bps) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "risk_params_set"),
                         Symbol::new(env, "base_limit"),
@@ -2598,7 +3634,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::UserRiskUpdated(user, score, limit) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "user_risk_updated"),
                         Symbol::new(env, "user"),
@@ -2614,7 +3650,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::AuctionStarted(user, asset, debt_portion) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "auction_started"),
                         Symbol::new(env, "user"),
@@ -2630,7 +3666,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::AuctionBidPlaced(bidder, user, bid_amount) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "auction_bid"), Symbol::new(env, "bidder")),
                     (
                         Symbol::new(env, "bidder"),
@@ -2643,7 +3679,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::AuctionSettled(winner, user, seized, repaid) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "auction_settled"),
                         Symbol::new(env, "winner"),
@@ -2661,7 +3697,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::RiskAlert(user, score) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "risk_alert"), Symbol::new(env, "user")),
                     (
                         Symbol::new(env, "user"),
@@ -2672,7 +3708,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::BridgeRegistered(network_id, bridge, fee_bps) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "bridge_registered"),
                         Symbol::new(env, "network"),
@@ -2688,7 +3724,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::BridgeFeeUpdated(network_id, fee_bps) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "bridge_fee_updated"),
                         Symbol::new(env, "network"),
@@ -2702,7 +3738,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::AssetBridgedIn(user, network_id, asset, amount, fee) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "asset_bridged_in"),
                         Symbol::new(env, "user"),
@@ -2722,7 +3758,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::AssetBridgedOut(user, network_id, asset, amount, fee) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "asset_bridged_out"),
                         Symbol::new(env, "user"),
@@ -2742,13 +3778,13 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::HealthReported(msg) => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "health_report"), Symbol::new(env, "msg")),
                     (Symbol::new(env, "msg"), msg.clone()),
                 );
             }
             ProtocolEvent::PerformanceReported(gas) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "performance_report"),
                         Symbol::new(env, "gas"),
@@ -2757,7 +3793,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::SecurityIncident(msg) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "security_incident"),
                         Symbol::new(env, "msg"),
@@ -2766,7 +3802,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::IntegrationRegistered(name, addr) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "integration_registered"),
                         Symbol::new(env, "name"),
@@ -2780,7 +3816,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::IntegrationCalled(name, method) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "integration_called"),
                         Symbol::new(env, "name"),
@@ -2794,7 +3830,7 @@ impl ProtocolEvent {
                 );
             }
             ProtocolEvent::AnalyticsUpdated(user, activity_type, amount, timestamp) => {
-                env.events().publish(
+                env.publish_event(
                     (
                         Symbol::new(env, "analytics_updated"),
                         Symbol::new(env, "user"),
@@ -2813,7 +3849,7 @@ impl ProtocolEvent {
             }
             // Add placeholder implementations for previously skipped event variants
             _ => {
-                env.events().publish(
+                env.publish_event(
                     (Symbol::new(env, "protocol_event"), Symbol::new(env, "misc")),
                     Symbol::new(env, "captured"),
                 );
@@ -2823,7 +3859,7 @@ impl ProtocolEvent {
 }
 
 /// Analytics helper function
-pub fn analytics_record_action(env: &Env, user: &Address, action: &str, amount: i128) {
+pub fn analytics_record_action(env: &Env, user: &Address, _action: &str, amount: i128) {
     // Simple analytics recording - can be enhanced later
     let timestamp = env.ledger().timestamp();
     // For now, just emit a simple event
@@ -2839,446 +3875,3396 @@ fn ensure_amount_positive(amount: i128) -> Result<(), ProtocolError> {
 }
 
 /// Core protocol functions
-pub fn deposit_collateral(env: Env, depositor: String, amount: i128) -> Result<(), ProtocolError> {
-    if depositor.is_empty() {
-        return Err(ProtocolError::InvalidAddress);
-    }
-    let depositor_addr = Address::from_string(&depositor);
-    deposit::DepositModule::deposit_collateral(&env, &depositor_addr, amount)
+pub fn deposit_collateral(env: Env, depositor: Address, amount: i128) -> Result<(), ProtocolError> {
+    deposit::DepositModule::deposit_collateral(&env, &depositor, amount)
 }
 
-pub fn borrow(env: Env, borrower: String, amount: i128) -> Result<(), ProtocolError> {
-    if borrower.is_empty() {
-        return Err(ProtocolError::InvalidAddress);
-    }
-    let borrower_addr = Address::from_string(&borrower);
-    borrow::BorrowModule::borrow(&env, &borrower_addr, amount)
+pub fn borrow(env: Env, borrower: Address, amount: i128) -> Result<(), ProtocolError> {
+    borrow::BorrowModule::borrow(&env, &borrower, amount)
 }
 
-pub fn repay(env: Env, repayer: String, amount: i128) -> Result<(), ProtocolError> {
-    if repayer.is_empty() {
-        return Err(ProtocolError::InvalidAddress);
-    }
-    let repayer_addr = Address::from_string(&repayer);
-    repay::RepayModule::repay(&env, &repayer_addr, amount)
+pub fn repay(env: Env, repayer: Address, amount: i128) -> Result<(), ProtocolError> {
+    repay::RepayModule::repay(&env, &repayer, amount)
 }
 
-pub fn withdraw(env: Env, withdrawer: String, amount: i128) -> Result<(), ProtocolError> {
-    if withdrawer.is_empty() {
-        return Err(ProtocolError::InvalidAddress);
-    }
-    let withdrawer_addr = Address::from_string(&withdrawer);
-    withdraw::WithdrawModule::withdraw(&env, &withdrawer_addr, amount)
+pub fn withdraw(env: Env, withdrawer: Address, amount: i128) -> Result<(), ProtocolError> {
+    withdraw::WithdrawModule::withdraw(&env, &withdrawer, amount)
+}
+
+/// Run an action the user signed for a relayer to submit
+pub fn execute_signed(env: Env, user: Address, payload: SignedAction) -> Result<(), ProtocolError> {
+    Relay::execute(&env, &user, payload)
+}
+
+/// Preview depositing `amount` of primary collateral for `user`
+pub fn preview_deposit(
+    env: Env,
+    user: Address,
+    amount: i128,
+) -> Result<ActionPreview, ProtocolError> {
+    ActionPreviews::deposit(&env, &user, amount)
+}
+
+/// Preview `user` withdrawing `amount` of primary collateral
+pub fn preview_withdraw(
+    env: Env,
+    user: Address,
+    amount: i128,
+) -> Result<ActionPreview, ProtocolError> {
+    ActionPreviews::withdraw(&env, &user, amount)
+}
+
+/// Preview `user` borrowing `amount` of the primary asset
+pub fn preview_borrow(
+    env: Env,
+    user: Address,
+    amount: i128,
+) -> Result<ActionPreview, ProtocolError> {
+    ActionPreviews::borrow(&env, &user, amount)
+}
+
+/// Preview repaying up to `amount` of `user`'s debt
+pub fn preview_repay(
+    env: Env,
+    user: Address,
+    amount: i128,
+) -> Result<ActionPreview, ProtocolError> {
+    ActionPreviews::repay(&env, &user, amount)
+}
+
+/// Preview a liquidation against `target` without executing it
+pub fn simulate_liquidation(
+    env: Env,
+    target: Address,
+    debt_asset: Address,
+    collateral_asset: Address,
+    repay_amount: i128,
+) -> Result<liquidate::LiquidationSimulation, ProtocolError> {
+    liquidate::LiquidationModule::simulate(
+        &env,
+        &target,
+        &debt_asset,
+        &collateral_asset,
+        repay_amount,
+    )
 }
 
 pub fn liquidate(
     env: Env,
-    liquidator: String,
-    user: String,
+    liquidator: Address,
+    user: Address,
     amount: i128,
 ) -> Result<(), ProtocolError> {
-    if liquidator.is_empty() {
-        return Err(ProtocolError::InvalidAddress);
-    }
-    let liquidator_addr = Address::from_string(&liquidator);
-    UserManager::ensure_operation_allowed(
+    let result = liquidate::LiquidationModule::liquidate(&env, &liquidator, &user, amount);
+    ErrorContext::report(&env, result, "liquidate", &liquidator, amount)?;
+    UserManager::record_activity(&env, &liquidator, OperationKind::Liquidate, amount)?;
+    Ok(())
+}
+
+pub fn liquidate_collateral(
+    env: Env,
+    liquidator: Address,
+    user: Address,
+    collateral_asset: Address,
+    amount: i128,
+    receiver: Option<Address>,
+) -> Result<(), ProtocolError> {
+    let receiver = receiver.unwrap_or_else(|| liquidator.clone());
+    let result = liquidate::LiquidationModule::liquidate_collateral(
         &env,
-        &liquidator_addr,
-        OperationKind::Liquidate,
+        &liquidator,
+        &receiver,
+        &user,
+        &collateral_asset,
         amount,
-    )?;
-    liquidate::LiquidationModule::liquidate(&env, &liquidator, &user, amount)?;
-    UserManager::record_activity(&env, &liquidator_addr, OperationKind::Liquidate, amount)?;
+    );
+    ErrorContext::report(&env, result, "liquidate_collateral", &liquidator, amount)?;
+    UserManager::record_activity(&env, &liquidator, OperationKind::Liquidate, amount)?;
     Ok(())
 }
 
-pub fn get_position(env: Env, user: String) -> Result<(i128, i128, i128), ProtocolError> {
-    if user.is_empty() {
-        return Err(ProtocolError::InvalidAddress);
-    }
-    let user_addr = Address::from_string(&user);
-    match StateHelper::get_position(&env, &user_addr) {
-        Some(position) => {
-            let collateral_ratio = if position.debt > 0 {
-                (position.collateral * 100) / position.debt
-            } else {
-                0
-            };
-            Ok((position.collateral, position.debt, collateral_ratio))
-        }
-        None => Err(ProtocolError::PositionNotFound),
-    }
+/// Rank the caller's collateral assets for seizure during liquidation
+pub fn set_seizure_order(
+    env: Env,
+    user: Address,
+    order: Vec<Address>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    user.require_auth();
+    liquidate::LiquidationModule::set_seizure_order(&env, &user, order)
 }
 
-pub fn set_risk_params(
+/// Set the seizure order applied to users without their own preference
+pub fn set_default_seizure_order(
     env: Env,
-    caller: String,
-    close_factor: i128,
-    liquidation_incentive: i128,
+    caller: Address,
+    order: Vec<Address>,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    ProtocolConfig::require_admin(&env, &caller_addr)?;
+    caller.require_auth();
+    liquidate::LiquidationModule::set_default_seizure_order(&env, &caller, order)
+}
 
-    let mut config = RiskConfigStorage::get(&env);
-    config.close_factor = close_factor;
-    config.liquidation_incentive = liquidation_incentive;
-    config.last_update = env.ledger().timestamp();
-    RiskConfigStorage::save(&env, &config);
+/// Set the health factor targeted liquidations restore positions to
+pub fn set_liquidation_target_hf(
+    env: Env,
+    caller: Address,
+    target: Option<i128>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    liquidate::LiquidationModule::set_target_health_factor(&env, &caller, target)
+}
 
-    ProtocolEvent::RiskParamsUpdated(close_factor, liquidation_incentive).emit(&env);
-    Ok(())
+/// Set which side keeps the fractional unit when seized collateral is rounded
+pub fn set_seizure_rounding(
+    env: Env,
+    caller: Address,
+    rounding: liquidate::SeizureRounding,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    liquidate::LiquidationModule::set_seizure_rounding(&env, &caller, rounding)
 }
 
-pub fn set_pause_switches(
+/// Replace the emission schedule for one side of an asset's market
+pub fn set_emission_schedule(
     env: Env,
-    caller: String,
-    pause_borrow: bool,
-    pause_deposit: bool,
-    pause_withdraw: bool,
-    pause_liquidate: bool,
+    caller: Address,
+    asset: Address,
+    side: RewardSide,
+    epochs: Vec<EmissionEpoch>,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    ProtocolConfig::require_admin(&env, &caller_addr)?;
+    caller.require_auth();
+    RewardManager::set_schedule(&env, &caller, &asset, side, epochs)
+}
 
-    let mut config = RiskConfigStorage::get(&env);
-    config.pause_borrow = pause_borrow;
-    config.pause_deposit = pause_deposit;
-    config.pause_withdraw = pause_withdraw;
-    config.pause_liquidate = pause_liquidate;
-    config.last_update = env.ledger().timestamp();
-    RiskConfigStorage::save(&env, &config);
+/// Scale emissions on one side of the primary market by utilization band
+pub fn set_emission_bands(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    side: RewardSide,
+    bands: Vec<UtilizationBand>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    RewardManager::set_bands(&env, &caller, &asset, side, bands)
+}
 
-    ProtocolEvent::PauseSwitchesUpdated(
-        pause_borrow,
-        pause_deposit,
-        pause_withdraw,
-        pause_liquidate,
-    )
-    .emit(&env);
+/// Quote the current borrow rate for `amount` of `asset`
+pub fn quote_borrow(env: Env, asset: Address, amount: i128) -> Result<BorrowQuote, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    RateQuotes::quote(&env, &asset, amount)
+}
+
+/// Borrow the quoted amount at the quoted rate while the quote is live
+pub fn borrow_with_quote(env: Env, user: Address, quote_id: u64) -> Result<(), ProtocolError> {
+    let quote = RateQuotes::take(&env, quote_id)?;
+    if quote.asset == TokenRegistry::require_primary_asset(&env)? {
+        borrow::BorrowModule::borrow(&env, &user, quote.amount)?;
+    } else {
+        borrow::BorrowModule::borrow_asset(&env, &user, &quote.asset, quote.amount)?;
+    }
+    RateQuotes::lock(&env, &user, quote.amount, quote.rate);
+    env.publish_event(
+        (Symbol::new(&env, "quote_executed"), user),
+        (quote_id, quote.amount, quote.rate),
+    );
     Ok(())
 }
 
-pub fn get_protocol_params(
+/// Open a governance proposal to switch to `config`
+pub fn propose_configuration(
     env: Env,
-) -> Result<(i128, i128, i128, i128, i128, i128), ProtocolError> {
-    let config = InterestRateStorage::get_config(&env);
-    let risk_config = RiskConfigStorage::get(&env);
+    proposer: Address,
+    title: String,
+    voting_period_secs: u64,
+    config: ProtocolConfiguration,
+) -> Result<u64, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    proposer.require_auth();
+    ConfigManager::propose(&env, &proposer, title, voting_period_secs, config)
+}
 
-    Ok((
-        config.base_rate,                  // 2000000 (2%)
-        config.kink_utilization,           // 80000000 (80%)
-        config.multiplier,                 // 10000000 (10x)
-        config.reserve_factor,             // 10000000 (10%)
-        risk_config.close_factor,          // 50000000 (50%)
-        risk_config.liquidation_incentive, // 10000000 (10%)
-    ))
+/// Activate `config` as a new configuration version
+pub fn apply_configuration(
+    env: Env,
+    caller: Address,
+    config: ProtocolConfiguration,
+) -> Result<u32, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ConfigManager::apply(&env, &caller, &config)
 }
 
-pub fn get_risk_config(env: Env) -> Result<(i128, i128, bool, bool, bool, bool), ProtocolError> {
-    let config = RiskConfigStorage::get(&env);
-    Ok((
-        config.close_factor,
-        config.liquidation_incentive,
-        config.pause_borrow,
-        config.pause_deposit,
-        config.pause_withdraw,
-        config.pause_liquidate,
-    ))
+/// Reactivate a historic configuration version as a new version
+pub fn rollback_configuration(
+    env: Env,
+    caller: Address,
+    version: u32,
+) -> Result<u32, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ConfigManager::rollback(&env, &caller, version)
 }
 
-pub fn get_system_stats(env: Env) -> Result<(i128, i128, i128, i128), ProtocolError> {
-    let state = InterestRateStorage::get_state(&env);
+/// Execute a queued proposal, activating any configuration it carries
+pub fn execute_proposal(env: Env, proposal_id: u64) -> Result<Proposal, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    ConfigManager::execute_proposal(&env, proposal_id)
+}
 
-    Ok((
-        state.total_supplied,
-        state.total_borrowed,
-        state.utilization_rate,
-        0, // active_users - simplified for now
-    ))
+/// Set or clear the liquidation incentive curve used when seizing an asset
+pub fn set_liquidation_incentive_curve(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    curve: Option<liquidate::IncentiveCurve>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    liquidate::LiquidationModule::set_incentive_curve(&env, &caller, &asset, curve)
 }
 
-pub fn set_emergency_manager(
+/// Set the protocol fee charged on collateral seized from an asset
+pub fn set_liquidation_fee(
     env: Env,
-    caller: String,
-    manager: String,
-    enabled: bool,
+    caller: Address,
+    asset: Address,
+    fee_bps: i128,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    let manager_addr = Address::from_string(&manager);
-    EmergencyManager::set_manager(&env, &caller_addr, &manager_addr, enabled)
+    caller.require_auth();
+    liquidate::LiquidationModule::set_liquidation_fee(&env, &caller, &asset, fee_bps)
 }
 
-pub fn trigger_emergency_pause(
+/// Replace the health-factor banded close factor table
+pub fn set_close_factor_bands(
     env: Env,
-    caller: String,
-    reason: Option<String>,
+    caller: Address,
+    bands: Vec<liquidate::CloseFactorBand>,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    EmergencyManager::pause(&env, &caller_addr, reason)
+    caller.require_auth();
+    liquidate::LiquidationModule::set_close_factor_bands(&env, &caller, bands)
 }
 
-pub fn enter_recovery_mode(
+/// Configure the share of liquidation bonuses withheld for borrower rebates
+pub fn set_liquidation_rebate(
     env: Env,
-    caller: String,
-    plan: Option<String>,
+    caller: Address,
+    tax_bps: i128,
+    cooldown: u64,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    EmergencyManager::enter_recovery(&env, &caller_addr, plan)
+    caller.require_auth();
+    RebateManager::set_config(&env, &caller, RebateConfig { tax_bps, cooldown })
+}
+
+/// Claim the liquidation rebate pot once healthy and liquidation-free for the cooldown
+pub fn claim_liquidation_rebate(env: Env, user: Address) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    user.require_auth();
+    let result = RebateManager::claim(&env, &user);
+    ErrorContext::report(&env, result, "claim_liquidation_rebate", &user, 0)
+}
+
+/// Liquidate up to MAX_LIQUIDATION_BATCH positions, skipping entries that are no longer eligible
+pub fn liquidate_batch(
+    env: Env,
+    liquidator: Address,
+    entries: Vec<(Address, Address, Address, i128)>,
+) -> Result<Vec<liquidate::BatchLiquidationResult>, ProtocolError> {
+    let requested = entries.iter().fold(0i128, |total, (_, _, _, amount)| {
+        total.saturating_add(amount)
+    });
+    let result = liquidate::LiquidationModule::liquidate_batch(&env, &liquidator, &entries);
+    let results = ErrorContext::report(&env, result, "liquidate_batch", &liquidator, requested)?;
+
+    let repaid = results
+        .iter()
+        .fold(0i128, |total, entry| total + entry.debt_repaid);
+    if repaid > 0 {
+        UserManager::record_activity(&env, &liquidator, OperationKind::Liquidate, repaid)?;
+    }
+    Ok(results)
 }
 
-pub fn resume_operations(env: Env, caller: String) -> Result<(), ProtocolError> {
+/// Session key functions
+pub fn grant_session(
+    env: Env,
+    user: Address,
+    delegate: Address,
+    max_amount_per_action: i128,
+    allow_deposit: bool,
+    allow_repay: bool,
+    expires_at: u64,
+) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    EmergencyManager::resume(&env, &caller_addr)
+    let policy = SessionPolicy {
+        delegate,
+        max_amount_per_action,
+        allow_deposit,
+        allow_repay,
+        expires_at,
+    };
+    SessionManager::grant(&env, &user, policy)
 }
 
-pub fn record_recovery_step(env: Env, caller: String, step: String) -> Result<(), ProtocolError> {
+pub fn revoke_session(env: Env, user: Address, delegate: Address) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    EmergencyManager::record_recovery_step(&env, &caller_addr, step)
+    SessionManager::revoke(&env, &user, &delegate)
 }
 
-pub fn queue_emergency_param_update(
+pub fn deposit_collateral_for(
     env: Env,
-    caller: String,
-    parameter: Symbol,
-    value: i128,
+    delegate: Address,
+    user: Address,
+    amount: i128,
+) -> Result<(), ProtocolError> {
+    deposit::DepositModule::deposit_collateral_for(&env, &delegate, &user, amount)
+}
+
+pub fn repay_for(
+    env: Env,
+    delegate: Address,
+    user: Address,
+    amount: i128,
 ) -> Result<(), ProtocolError> {
+    repay::RepayModule::repay_for(&env, &delegate, &user, amount)
+}
+
+/// Opt in to keeper-driven repayments up to `cap`; zero opts out
+pub fn set_auto_repay_cap(env: Env, user: Address, cap: i128) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    EmergencyManager::queue_param_update(&env, &caller_addr, parameter, value)
+    user.require_auth();
+    repay::RepayModule::set_auto_repay_cap(&env, &user, cap)
+}
+
+pub fn pull_repay(
+    env: Env,
+    keeper: Address,
+    user: Address,
+    amount: i128,
+) -> Result<i128, ProtocolError> {
+    repay::RepayModule::pull_repay(&env, &keeper, &user, amount)
 }
 
-pub fn apply_emergency_param_updates(env: Env, caller: String) -> Result<(), ProtocolError> {
+/// Set the interest-free window (hours) for new borrows of an asset; zero switches it off
+pub fn set_grace_period(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    hours: u64,
+) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    EmergencyManager::apply_param_updates(&env, &caller_addr)
+    caller.require_auth();
+    GracePeriod::set_window(&env, &caller, &asset, hours)
 }
 
-pub fn adjust_emergency_fund(
+/// Choose whether a supplied asset counts as collateral (on by default)
+pub fn set_use_as_collateral(
     env: Env,
-    caller: String,
-    token: Option<Address>,
-    delta: i128,
-    reserve_delta: i128,
+    user: Address,
+    asset: Address,
+    enabled: bool,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    EmergencyManager::adjust_fund(&env, &caller_addr, token, delta, reserve_delta)
+    user.require_auth();
+    let result =
+        cross_asset::CrossAssetManager::set_use_as_collateral(&env, &user, &asset, enabled);
+    ErrorContext::report(&env, result, "set_use_as_collateral", &user, 0)
 }
 
-pub fn get_emergency_state(env: Env) -> Result<EmergencyState, ProtocolError> {
-    Ok(EmergencyStorage::get(&env))
+/// Cap the collateral a single account may hold in an asset; zero removes the cap
+pub fn set_max_position_size(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    max_size: i128,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    BorrowLimits::set_max_position_size(&env, &caller, &asset, max_size)
 }
 
-pub fn get_event_summary(env: Env) -> Result<EventSummary, ProtocolError> {
-    Ok(EventStorage::get_summary(&env))
+/// Cap how much one address may supply of an asset; `None` removes the cap
+pub fn set_address_supply_cap(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    user: Address,
+    cap: Option<i128>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    BorrowLimits::set_address_supply_cap(&env, &caller, &asset, &user, cap)
 }
 
-pub fn get_event_aggregates(env: Env) -> Result<Map<Symbol, EventAggregate>, ProtocolError> {
-    Ok(EventStorage::get_aggregates(&env))
+/// Restrict liquidations to allowlisted liquidators, or reopen them to everyone
+pub fn set_restricted_liquidations(
+    env: Env,
+    caller: Address,
+    restricted: bool,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    liquidate::LiquidationModule::set_restricted_liquidations(&env, &caller, restricted)
 }
 
-pub fn get_events_for_type(
+/// Add a liquidator to or remove it from the allowlist
+pub fn set_approved_liquidator(
     env: Env,
-    event_type: Symbol,
-    limit: u32,
-) -> Result<Vec<EventRecord>, ProtocolError> {
-    let logs = EventStorage::get_logs(&env);
-    let mut events = logs
-        .get(event_type.clone())
-        .unwrap_or_else(|| Vec::new(&env));
-    if limit > 0 && events.len() > limit {
-        let start = events.len() - limit;
-        events = events.slice(start..);
-    }
-    Ok(events)
+    caller: Address,
+    liquidator: Address,
+    approved: bool,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    liquidate::LiquidationModule::set_approved_liquidator(&env, &caller, &liquidator, approved)
 }
 
-pub fn get_recent_event_types(env: Env) -> Result<Vec<Symbol>, ProtocolError> {
-    Ok(EventStorage::get_summary(&env).recent_types)
+/// Arm or remove the utilization spike circuit breaker for an asset
+pub fn set_utilization_breaker(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    breaker: Option<UtilizationBreaker>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    BorrowLimits::set_utilization_breaker(&env, &caller, &asset, breaker)
 }
 
-pub fn register_token_asset(
+pub fn set_thin_market_rate(
     env: Env,
-    caller: String,
-    key: Symbol,
-    token: Address,
+    caller: Address,
+    asset: Address,
+    rate: Option<ThinMarketRate>,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    TokenRegistry::set_asset(&env, &caller_addr, key, token)
+    caller.require_auth();
+    BorrowLimits::set_thin_market_rate(&env, &caller, &asset, rate)
 }
 
-pub fn set_primary_asset(env: Env, caller: String, token: Address) -> Result<(), ProtocolError> {
+/// Extend individual stored entries ahead of state archival
+pub fn bump_entries(env: Env, entries: Vec<StateEntry>) -> Result<Vec<u32>, ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    TokenRegistry::set_primary_asset(&env, &caller_addr, token)
+    Ttl::bump_entries(&env, &entries)
 }
 
-pub fn get_registered_asset(env: Env, key: Symbol) -> Result<Option<Address>, ProtocolError> {
-    Ok(TokenRegistry::get_asset(&env, key))
+/// Arm, retune or remove automatic recovery mode
+pub fn set_recovery_mode_config(
+    env: Env,
+    caller: Address,
+    config: Option<RecoveryConfig>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    RecoveryMode::set_config(&env, &caller, config)
 }
 
-pub fn set_user_role(
+/// Allow `spender` to move up to `amount` deposit receipts until `expiration_ledger`
+pub fn approve(
+    env: Env,
+    from: Address,
+    spender: Address,
+    amount: i128,
+    expiration_ledger: u32,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    ReceiptToken::approve(&env, &from, &spender, amount, expiration_ledger)
+}
+
+/// Move deposit receipts, and the collateral they represent, to another account
+pub fn transfer(env: Env, from: Address, to: Address, amount: i128) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    let result = ReceiptToken::transfer(&env, &from, &to, amount);
+    ErrorContext::report(&env, result, "transfer", &from, amount)
+}
+
+pub fn transfer_from(
+    env: Env,
+    spender: Address,
+    from: Address,
+    to: Address,
+    amount: i128,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    let result = ReceiptToken::transfer_from(&env, &spender, &from, &to, amount);
+    ErrorContext::report(&env, result, "transfer_from", &from, amount)
+}
+
+/// Redeem deposit receipts for the underlying primary asset
+pub fn burn(env: Env, from: Address, amount: i128) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    let result = ReceiptToken::burn(&env, &from, amount);
+    ErrorContext::report(&env, result, "burn", &from, amount)
+}
+
+pub fn burn_from(
+    env: Env,
+    spender: Address,
+    from: Address,
+    amount: i128,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    let result = ReceiptToken::burn_from(&env, &spender, &from, amount);
+    ErrorContext::report(&env, result, "burn_from", &from, amount)
+}
+
+/// Set the global debt ceiling and the utilization kill-switch thresholds
+pub fn set_protocol_parameters(
+    env: Env,
+    caller: Address,
+    params: ProtocolParameters,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    BorrowLimits::set_parameters(&env, &caller, params)
+}
+
+pub fn set_protector(
     env: Env,
-    caller: String,
     user: Address,
-    role: UserRole,
+    protector: Address,
+    enabled: bool,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    UserManager::set_role(&env, &caller_addr, &user, role)
+    SessionManager::set_protector(&env, &user, &protector, enabled)
 }
 
-pub fn set_user_verification(
+pub fn get_position(env: Env, user: Address) -> Result<(i128, i128, i128), ProtocolError> {
+    match StateHelper::get_position(&env, &user) {
+        Some(position) => {
+            let collateral_ratio = cross_asset::CrossAssetManager::collateral_ratio(
+                &env,
+                &user,
+                position.collateral,
+                position.debt,
+            )?;
+            Ok((position.collateral, position.debt, collateral_ratio))
+        }
+        None => Err(ProtocolError::PositionNotFound),
+    }
+}
+
+pub fn split_position(
     env: Env,
-    caller: String,
     user: Address,
-    status: VerificationStatus,
+    asset: Address,
+    amount: i128,
+    target_account: Address,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    UserManager::set_verification_status(&env, &caller_addr, &user, status)
+    user.require_auth();
+    target_account.require_auth();
+    PositionRestructure::split(&env, &user, &asset, amount, &target_account)
 }
 
-pub fn set_user_limits(
+pub fn merge_positions(
     env: Env,
-    caller: String,
     user: Address,
-    max_deposit: i128,
-    max_borrow: i128,
-    max_withdraw: i128,
-    daily_limit: i128,
+    from_account: Address,
+    to_account: Address,
 ) -> Result<(), ProtocolError> {
     let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    UserManager::set_limits(
+    from_account.require_auth();
+    to_account.require_auth();
+    PositionRestructure::merge(&env, &user, &from_account, &to_account)
+}
+
+/// Deposit collateral for a specific asset (cross-asset)
+pub fn deposit_collateral_asset(
+    env: Env,
+    user: Address,
+    asset: Address,
+    amount: i128,
+) -> Result<(), ProtocolError> {
+    deposit::DepositModule::deposit_collateral_asset(&env, &user, &asset, amount)
+}
+
+/// Withdraw collateral for a specific asset (checks cross-asset ratio)
+pub fn withdraw_asset(
+    env: Env,
+    user: Address,
+    asset: Address,
+    amount: i128,
+) -> Result<(), ProtocolError> {
+    withdraw::WithdrawModule::withdraw_asset(&env, &user, &asset, amount)
+}
+
+/// Get cross-asset position summary (risk-adjusted collateral, debt, ratio)
+pub fn get_cross_position_summary(
+    env: Env,
+    user: Address,
+) -> Result<(i128, i128, i128), ProtocolError> {
+    let (collateral, debt) = StateHelper::get_position(&env, &user)
+        .map(|position| (position.collateral, position.debt))
+        .unwrap_or((0, 0));
+    let weighted = cross_asset::CrossAssetManager::weighted_collateral(&env, &user, collateral)?;
+    let ratio = if debt > 0 { (weighted * 100) / debt } else { 0 };
+    Ok((weighted, debt, ratio))
+}
+
+/// Collect a user's positions, risk figures, compliance flags and activity in one call
+pub fn get_full_user_state(env: Env, user: Address) -> Result<UserState, ProtocolError> {
+    let position =
+        StateHelper::get_position(&env, &user).unwrap_or_else(|| Position::new(user.clone(), 0, 0));
+    let cross = cross_asset::CrossStateHelper::get_or_init_position(&env, &user);
+    let weighted_collateral =
+        cross_asset::CrossAssetManager::weighted_collateral(&env, &user, position.collateral)?;
+    let collateral_ratio = cross_asset::CrossAssetManager::collateral_ratio(
         &env,
-        &caller_addr,
         &user,
-        max_deposit,
-        max_borrow,
-        max_withdraw,
-        daily_limit,
+        position.collateral,
+        position.debt,
+    )?;
+    let min_ratio = ProtocolConfig::get_min_collateral_ratio(&env);
+    let health_factor = if min_ratio > 0 {
+        (collateral_ratio * 100) / min_ratio
+    } else {
+        0
+    };
+    let profile = UserManager::view_profile(&env, &user);
+
+    Ok(UserState {
+        user: user.clone(),
+        collateral: position.collateral,
+        debt: position.debt,
+        borrow_interest: position.borrow_interest,
+        supply_interest: position.supply_interest,
+        interest_capped: position.interest_capped,
+        asset_collateral: cross.collateral,
+        weighted_collateral,
+        collateral_ratio,
+        health_factor,
+        freeze: profile.freeze_level(&env),
+        role: profile.role,
+        blacklisted: profile.verification == VerificationStatus::Rejected,
+        verification: profile.verification,
+        freeze_expires_at: profile.freeze_expires_at,
+        activity_score: profile.activity_score,
+        last_active: profile.last_active,
+        analytics: analytics::AnalyticsStorage::get_user_analytics_for_user(&env, &user),
+    })
+}
+
+/// Configure how collected fees are routed into protocol token buybacks
+pub fn set_buyback_config(
+    env: Env,
+    caller: Address,
+    config: BuybackConfig,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    RevenueManager::set_buyback_config(&env, &caller, config)
+}
+
+/// Swap the buyback reserve for the protocol token, then burn or stream it
+pub fn execute_buyback(env: Env, caller: Address, min_out: i128) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    RevenueManager::execute_buyback(&env, &caller, min_out)
+}
+
+/// Split treasury distributions between weighted recipients
+pub fn set_fee_splits(
+    env: Env,
+    caller: Address,
+    splits: Vec<FeeSplit>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    RevenueManager::set_fee_splits(&env, &caller, splits)
+}
+
+/// Pay the segregated reserve income in `asset` out to the treasury or fee split
+pub fn distribute_fees_to_treasury(
+    env: Env,
+    caller: Address,
+    asset: Address,
+) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    RevenueManager::distribute_to_treasury(&env, &caller, &asset)
+}
+
+/// Opt in or out of folding supply interest into collateral on every accrual
+pub fn set_auto_compound(env: Env, user: Address, enabled: bool) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    user.require_auth();
+    let mut position =
+        StateHelper::get_position(&env, &user).ok_or(ProtocolError::PositionNotFound)?;
+    let state = InterestRateStorage::update_state(&env);
+    position.auto_compound = enabled;
+    InterestRateManager::accrue_interest_for_position(
+        &env,
+        &mut position,
+        state.current_borrow_rate,
+        state.liquidity_index,
+    );
+    StateHelper::save_position(&env, &position);
+    env.publish_event(
+        (Symbol::new(&env, "auto_compound_updated"), user),
+        (enabled, position.collateral),
+    );
+    Ok(())
+}
+
+/// Register, replace or clear the caller's notification preferences
+pub fn set_notification_prefs(
+    env: Env,
+    user: Address,
+    prefs: Option<NotificationPrefs>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    user.require_auth();
+    Notifications::set_prefs(&env, &user, prefs)
+}
+
+/// Close out a debt-free account and delete the records kept about it
+pub fn purge_account(env: Env, user: Address) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    user.require_auth();
+    AccountPurge::purge(&env, &user)
+}
+
+/// Pay out accrued supply interest in the primary asset
+pub fn claim_supply_interest(env: Env, user: Address) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    user.require_auth();
+    let result = (|| {
+        let mut position =
+            StateHelper::get_position(&env, &user).ok_or(ProtocolError::PositionNotFound)?;
+        let state = InterestRateStorage::update_state(&env);
+        InterestRateManager::accrue_interest_for_position(
+            &env,
+            &mut position,
+            state.current_borrow_rate,
+            state.liquidity_index,
+        );
+
+        let amount = position.supply_interest;
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        ComplianceGate::check(&env, &user, OperationKind::Withdraw, amount)?;
+        let pledged = cross_asset::CrossAssetManager::pledged_interest(&env, &user);
+        position.supply_interest = 0;
+        StateHelper::save_position(&env, &position);
+        // Interest pledged as collateral may be backing the debt, so it leaves under the
+        // same checks as a withdrawal; the error reverts the save
+        if pledged > 0 {
+            withdraw::WithdrawModule::ensure_can_release(
+                &env,
+                &user,
+                position.collateral,
+                position.debt,
+            )?;
+        }
+        TransferEnforcer::transfer_out(&env, &user, amount, Symbol::new(&env, "claim_interest"))?;
+        env.publish_event(
+            (Symbol::new(&env, "supply_interest_claimed"), user.clone()),
+            amount,
+        );
+        Ok(amount)
+    })();
+    ErrorContext::report(&env, result, "claim_supply_interest", &user, 0)
+}
+
+/// Convert collateral between supported assets through a DEX adapter
+pub fn migrate_collateral(
+    env: Env,
+    user: Address,
+    from_asset: Address,
+    to_asset: Address,
+    amount: i128,
+    dex_adapter: Address,
+    min_out: i128,
+) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    ensure_amount_positive(amount)?;
+    user.require_auth();
+    ComplianceGate::check_market(&env, &user, &from_asset, OperationKind::Withdraw, amount)?;
+    ComplianceGate::ensure_market_open(&env, &to_asset, OperationKind::Deposit)?;
+
+    let (collateral, debt) = StateHelper::get_position(&env, &user)
+        .map(|position| (position.collateral, position.debt))
+        .unwrap_or((0, 0));
+    let result = cross_asset::CrossAssetManager::migrate(
+        &env,
+        &user,
+        &from_asset,
+        &to_asset,
+        amount,
+        &dex_adapter,
+        min_out,
+        collateral,
+        debt,
+    );
+    ErrorContext::report(&env, result, "migrate_collateral", &user, amount)
+}
+
+/// Add or update an asset's risk weight (risk manager or admin)
+pub fn set_asset_params(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    collateral_factor: i128,
+    deposit_enabled: bool,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    cross_asset::CrossAssetManager::set_asset_params(
+        &env,
+        &caller,
+        &asset,
+        collateral_factor,
+        deposit_enabled,
     )
 }
 
-pub fn freeze_user(env: Env, caller: String, user: Address) -> Result<(), ProtocolError> {
-    let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    UserManager::freeze_user(&env, &caller_addr, &user)
-}
+/// Set the listing fee and proposer revenue share for permissionless listings
+pub fn set_listing_config(
+    env: Env,
+    caller: Address,
+    config: Option<ListingConfig>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    MarketListings::set_config(&env, &caller, config)
+}
+
+/// Propose listing an asset, escrowing the listing fee
+pub fn propose_market_listing(
+    env: Env,
+    proposer: Address,
+    asset: Address,
+    collateral_factor: i128,
+) -> Result<u64, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    proposer.require_auth();
+    MarketListings::propose(&env, &proposer, &asset, collateral_factor)
+}
+
+pub fn approve_market_listing(env: Env, caller: Address, id: u64) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    MarketListings::approve(&env, &caller, id)
+}
+
+pub fn reject_market_listing(env: Env, caller: Address, id: u64) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    MarketListings::reject(&env, &caller, id)
+}
+
+/// Claim a listing proposer's share of the market's reserve revenue
+pub fn claim_listing_revenue(
+    env: Env,
+    proposer: Address,
+    asset: Address,
+) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    proposer.require_auth();
+    MarketListings::claim(&env, &proposer, &asset)
+}
+
+/// Count accrued supply interest in an asset toward borrowing power
+pub fn set_interest_as_collateral(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    enabled: bool,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    cross_asset::CrossAssetManager::set_interest_as_collateral(&env, &caller, &asset, enabled)
+}
+
+/// Allow or disallow a DEX adapter for collateral migrations
+pub fn set_dex_adapter(
+    env: Env,
+    caller: Address,
+    adapter: Address,
+    allowed: bool,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    cross_asset::CrossAssetManager::set_dex_adapter(&env, &caller, &adapter, allowed)
+}
+
+/// Value and seize a listed asset through an adapter contract
+pub fn set_collateral_adapter(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    adapter: Option<Address>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    CollateralAdapters::set(&env, &caller, &asset, adapter)
+}
+
+/// Restrict the contracts the protocol may call out to
+pub fn set_external_call_allowlist(
+    env: Env,
+    caller: Address,
+    allowlist: Option<Vec<Address>>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ExternalCall::set_allowlist(&env, &caller, allowlist)
+}
+
+/// Record where a listed asset comes from (issuer, bridge, canonical issuance)
+pub fn set_asset_provenance(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    provenance: cross_asset::AssetProvenance,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    cross_asset::CrossAssetManager::set_asset_provenance(&env, &caller, &asset, provenance)
+}
+
+/// Set the collateral factor haircut for non-canonical bridged wrappers
+pub fn set_wrapper_haircut(env: Env, caller: Address, haircut: i128) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    cross_asset::CrossAssetManager::set_wrapper_haircut(&env, &caller, haircut)
+}
+
+/// Set how long an asset price stays usable for borrows and withdrawals, in seconds
+pub fn set_oracle_heartbeat(env: Env, caller: Address, ttl: u64) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ProtocolConfig::require_admin(&env, &caller)?;
+    if ttl == 0 {
+        return Err(ProtocolError::InvalidParameters);
+    }
+    OracleStorage::set_heartbeat_ttl(&env, ttl);
+    env.publish_event((Symbol::new(&env, "oracle_heartbeat_updated"), caller), ttl);
+    Ok(())
+}
+
+/// Set the default max move of a synced price vs the last one, in basis points (0 = unbounded)
+pub fn set_oracle_max_deviation(env: Env, caller: Address, bps: i128) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ProtocolConfig::require_admin(&env, &caller)?;
+    if !(0..=10000).contains(&bps) {
+        return Err(ProtocolError::InvalidParameters);
+    }
+    OracleStorage::set_max_deviation(&env, bps);
+    env.publish_event((Symbol::new(&env, "oracle_deviation_updated"), caller), bps);
+    Ok(())
+}
+
+/// Override the oracle heartbeat, max deviation and fallback price of one asset; `None`
+/// returns it to the global defaults
+pub fn set_asset_oracle_config(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    config: Option<AssetOracleConfig>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ProtocolConfig::require_admin(&env, &caller)?;
+    cross_asset::CrossAssetManager::get_asset_params(&env, &asset)?;
+    if let Some(config) = &config {
+        if !(0..=10000).contains(&config.max_deviation_bps) || config.fallback_price < 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+    }
+    OracleStorage::put_asset_config(&env, &asset, &config);
+    env.publish_event(
+        (Symbol::new(&env, "asset_oracle_config_set"), asset.clone()),
+        Oracle::effective_config(&env, &asset),
+    );
+    Ok(())
+}
+
+/// Allow deposits of an asset whose transfers deliver less than the requested amount
+pub fn set_fee_on_transfer(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    allowed: bool,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    cross_asset::CrossAssetManager::set_fee_on_transfer(&env, &caller, &asset, allowed)
+}
+
+/// Set price for an asset in 1e8 scale, denominated in the primary asset
+pub fn set_asset_price(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    price: i128,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    cross_asset::CrossAssetManager::set_asset_price(&env, &caller, &asset, price)
+}
+
+/// Register or reweight an oracle feed for an asset
+pub fn set_oracle_source(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    source: Address,
+    weight: i128,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ProtocolConfig::require_admin(&env, &caller)?;
+    if weight <= 0 {
+        return Err(ProtocolError::InvalidParameters);
+    }
+    let now = env.ledger().timestamp();
+    Oracle::set_source(&env, &asset, OracleSource::new(source.clone(), weight, now));
+    env.publish_event(
+        (Symbol::new(&env, "oracle_source_set"), asset),
+        (source, weight),
+    );
+    Ok(())
+}
+
+/// Set or clear the feed an asset's prices fail over to when its sources are unusable
+pub fn set_secondary_oracle(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    source: Option<Address>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ProtocolConfig::require_admin(&env, &caller)?;
+    Oracle::set_secondary(&env, &asset, source.clone());
+    env.publish_event((Symbol::new(&env, "secondary_oracle_set"), asset), source);
+    Ok(())
+}
+
+/// Refresh an asset's price and confidence interval from its oracle feeds; permissionless
+pub fn sync_asset_price(env: Env, asset: Address) -> Result<(i128, i128), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    cross_asset::CrossAssetManager::sync_oracle_price(&env, &asset)
+}
+
+/// Register signed price provider keys and the per-payload update fee
+pub fn set_price_push_config(
+    env: Env,
+    caller: Address,
+    config: Option<PricePushConfig>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    PricePush::set_config(&env, &caller, config)
+}
+
+/// Verify and record signed price payloads, charging `payer` the update fee
+pub fn update_price_feeds(
+    env: Env,
+    payer: Address,
+    payloads: Vec<PricePayload>,
+) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    payer.require_auth();
+    PricePush::update(&env, &payer, &payloads)
+}
+
+/// Set how many confidence intervals are subtracted from prices for borrowing power
+pub fn set_confidence_multiplier(env: Env, caller: Address, k: i128) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    cross_asset::CrossAssetManager::set_confidence_multiplier(&env, &caller, k)
+}
+
+pub fn set_risk_params(
+    env: Env,
+    caller: Address,
+    close_factor: i128,
+    liquidation_incentive: i128,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ProtocolConfig::require_admin(&env, &caller)?;
+
+    let mut config = RiskConfigStorage::get(&env);
+    let old_close_factor = config.close_factor;
+    let old_incentive = config.liquidation_incentive;
+    config.close_factor = close_factor;
+    config.liquidation_incentive = liquidation_incentive;
+    config.last_update = env.ledger().timestamp();
+    RiskConfigStorage::save(&env, &config);
+
+    ProtocolEvent::RiskParamsUpdated(close_factor, liquidation_incentive).emit(&env);
+    ProtocolEvent::ConfigUpdated(
+        caller.clone(),
+        Symbol::new(&env, "close_factor"),
+        old_close_factor,
+        close_factor,
+    )
+    .emit(&env);
+    ProtocolEvent::ConfigUpdated(
+        caller,
+        Symbol::new(&env, "liquidation_incentive"),
+        old_incentive,
+        liquidation_incentive,
+    )
+    .emit(&env);
+    Ok(())
+}
+
+pub fn set_supply_fee(
+    env: Env,
+    caller: Address,
+    config: Option<SupplyFeeConfig>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    SupplyFee::set(&env, &caller, config)
+}
+
+pub fn set_interest_cap(env: Env, caller: Address, cap_bps: i128) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ProtocolConfig::require_admin(&env, &caller)?;
+    if cap_bps < 0 {
+        return Err(ProtocolError::InvalidParameters);
+    }
+
+    let mut config = InterestRateStorage::get_config(&env);
+    let old_value = config.interest_cap_bps;
+    config.interest_cap_bps = cap_bps;
+    config.last_update = env.ledger().timestamp();
+    InterestRateStorage::save_config(&env, &config);
+
+    ProtocolEvent::ConfigUpdated(
+        caller,
+        Symbol::new(&env, "interest_cap_bps"),
+        old_value,
+        cap_bps,
+    )
+    .emit(&env);
+    Ok(())
+}
+
+/// Update one rate model parameter, subject to the parameter change guardrail
+fn update_rate_param(
+    env: &Env,
+    caller: &Address,
+    param: &str,
+    value: i128,
+    apply: fn(&mut InterestRateConfig) -> &mut i128,
+) -> Result<(), ProtocolError> {
+    ProtocolConfig::require_admin(env, caller)?;
+    let mut config = InterestRateStorage::get_config(env);
+    let field = apply(&mut config);
+    let old_value = *field;
+    BorrowLimits::ensure_param_change(env, param, old_value, value)?;
+    *field = value;
+    config.last_update = env.ledger().timestamp();
+    InterestRateStorage::save_config(env, &config);
+
+    ProtocolEvent::ConfigUpdated(caller.clone(), Symbol::new(env, param), old_value, value)
+        .emit(env);
+    Ok(())
+}
+
+pub fn set_base_rate(env: Env, caller: Address, base_rate: i128) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    if base_rate < 0 {
+        return Err(ProtocolError::InvalidParameters);
+    }
+    update_rate_param(&env, &caller, "base_rate", base_rate, |c| &mut c.base_rate)
+}
+
+pub fn set_multiplier(env: Env, caller: Address, multiplier: i128) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    if multiplier < 0 {
+        return Err(ProtocolError::InvalidParameters);
+    }
+    update_rate_param(&env, &caller, "multiplier", multiplier, |c| {
+        &mut c.multiplier
+    })
+}
+
+pub fn set_reserve_factor(
+    env: Env,
+    caller: Address,
+    reserve_factor: i128,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    if !(0..=100_000_000).contains(&reserve_factor) {
+        return Err(ProtocolError::InvalidParameters);
+    }
+    update_rate_param(&env, &caller, "reserve_factor", reserve_factor, |c| {
+        &mut c.reserve_factor
+    })
+}
+
+/// Keep borrowing closed on `asset`'s market until it holds `tvl_threshold` or
+/// `start_time` passes
+pub fn start_market_bootstrap(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    tvl_threshold: i128,
+    start_time: u64,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    BorrowLimits::start_bootstrap(
+        &env,
+        &caller,
+        &asset,
+        BootstrapConfig {
+            tvl_threshold,
+            start_time,
+        },
+    )
+}
+
+/// Restrict borrows and withdrawals in the same ledger as an action on `asset`
+pub fn set_same_ledger_guard(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    enabled: bool,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    BorrowLimits::set_same_ledger_guard(&env, &caller, &asset, enabled)
+}
+
+/// Limit how far rate and collateral parameters may move per window; emergency
+/// parameter updates are exempt
+pub fn set_param_change_limit(
+    env: Env,
+    caller: Address,
+    max_change_bps: i128,
+    window: u64,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    BorrowLimits::set_change_limit(
+        &env,
+        &caller,
+        ParamChangeLimit {
+            max_change_bps,
+            window,
+        },
+    )
+}
+
+/// Set the half-life (seconds) of the utilization EWMA that drives rates; zero uses raw utilization
+pub fn set_utilization_half_life(
+    env: Env,
+    caller: Address,
+    half_life: u64,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ProtocolConfig::require_admin(&env, &caller)?;
+
+    // Fold elapsed time into the average under the old half-life before switching
+    InterestRateStorage::update_state(&env);
+    let mut config = InterestRateStorage::get_config(&env);
+    let old_value = config.utilization_half_life;
+    config.utilization_half_life = half_life;
+    config.last_update = env.ledger().timestamp();
+    InterestRateStorage::save_config(&env, &config);
+
+    ProtocolEvent::ConfigUpdated(
+        caller,
+        Symbol::new(&env, "utilization_half_life"),
+        old_value as i128,
+        half_life as i128,
+    )
+    .emit(&env);
+    Ok(())
+}
+
+/// Accrue interest on a batch of positions; permissionless
+pub fn accrue_positions(
+    env: Env,
+    asset: Address,
+    users: Vec<Address>,
+) -> Result<u32, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    InterestRateManager::accrue_positions(&env, &asset, &users)
+}
+
+/// Set the staking token and discount schedule
+pub fn set_staking_config(
+    env: Env,
+    caller: Address,
+    config: StakingConfig,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    Staking::set_config(&env, &caller, config)
+}
+
+/// Lock protocol tokens for a borrow rate discount
+pub fn stake(env: Env, user: Address, amount: i128) -> Result<StakePosition, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    user.require_auth();
+    Staking::stake(&env, &user, amount)
+}
+
+/// Release staked protocol tokens after the lock period
+pub fn unstake(env: Env, user: Address, amount: i128) -> Result<StakePosition, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    user.require_auth();
+    Staking::unstake(&env, &user, amount)
+}
+
+/// Borrow rate a user currently accrues at after their staking discount
+pub fn get_user_borrow_rate(
+    env: Env,
+    user: Address,
+    asset: Address,
+) -> Result<i128, ProtocolError> {
+    if asset != TokenRegistry::require_primary_asset(&env)? {
+        return Err(ProtocolError::AssetNotSupported);
+    }
+    let rate = InterestRateStorage::get_state(&env).current_borrow_rate;
+    Ok(Staking::discounted_rate(&env, &user, rate))
+}
+
+/// Raw and time-weighted utilization plus the resulting borrow rate
+pub fn get_utilization_metrics(env: Env) -> UtilizationMetrics {
+    let state = InterestRateStorage::get_state(&env);
+    UtilizationMetrics {
+        raw_utilization: state.utilization_rate,
+        smoothed_utilization: state.smoothed_utilization,
+        half_life: InterestRateStorage::get_config(&env).utilization_half_life,
+        borrow_rate: state.current_borrow_rate,
+    }
+}
+
+/// Token-denominated view of one market. Only the primary asset carries pool accounting;
+/// other assets report the collateral the contract holds and no borrow-side figures.
+pub fn get_market_data(env: Env, asset: Address) -> Result<MarketData, ProtocolError> {
+    let primary = TokenRegistry::require_primary_asset(&env)?;
+    if asset != primary {
+        cross_asset::CrossAssetManager::get_asset_params(&env, &asset)?;
+    }
+    let token = TokenClient::new(&env, &asset);
+    let treasury = revenue::RevenueStorage::get_treasury_balance(&env, &asset);
+    let reserve_balance = treasury.accrued - treasury.distributed;
+    let held = token.balance(&env.current_contract_address());
+    let mut data = MarketData {
+        asset: asset.clone(),
+        decimals: token.decimals(),
+        total_supplied: held,
+        total_borrowed: 0,
+        available_liquidity: (held - reserve_balance).max(0),
+        reserve_balance,
+        borrow_rate: 0,
+        supply_rate: 0,
+        utilization: 0,
+        liquidity_index: LIQUIDITY_INDEX_SCALE,
+    };
+    if asset == primary {
+        let state = InterestRateStorage::get_state(&env);
+        data.total_supplied = state.total_supplied;
+        data.total_borrowed = state.total_borrowed;
+        data.borrow_rate = state.current_borrow_rate;
+        data.supply_rate = state.current_supply_rate;
+        data.utilization = state.utilization_rate;
+        data.liquidity_index = state.liquidity_index;
+    }
+    Ok(data)
+}
+
+pub fn set_pause_switches(
+    env: Env,
+    caller: Address,
+    pause_borrow: bool,
+    pause_deposit: bool,
+    pause_withdraw: bool,
+    pause_liquidate: bool,
+) -> Result<(), ProtocolError> {
+    pause_with_reason(
+        env,
+        caller,
+        pause_borrow,
+        pause_deposit,
+        pause_withdraw,
+        pause_liquidate,
+        0,
+        0,
+    )
+}
+
+/// Set pause switches recording a reason code; a non-zero `duration` (seconds) makes the
+/// pause lift automatically once it elapses
+#[allow(clippy::too_many_arguments)]
+pub fn pause_with_reason(
+    env: Env,
+    caller: Address,
+    pause_borrow: bool,
+    pause_deposit: bool,
+    pause_withdraw: bool,
+    pause_liquidate: bool,
+    reason_code: u32,
+    duration: u64,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ProtocolConfig::require_admin(&env, &caller)?;
+
+    let now = env.ledger().timestamp();
+    let mut config = RiskConfigStorage::get(&env);
+    config.pause_borrow = pause_borrow;
+    config.pause_deposit = pause_deposit;
+    config.pause_withdraw = pause_withdraw;
+    config.pause_liquidate = pause_liquidate;
+    config.last_update = now;
+    RiskConfigStorage::save(&env, &config);
+
+    if pause_borrow || pause_deposit || pause_withdraw || pause_liquidate {
+        let info = PauseInfo {
+            paused_by: caller.clone(),
+            reason_code,
+            paused_at: now,
+            expires_at: if duration == 0 { 0 } else { now + duration },
+        };
+        PauseStorage::save(&env, &info);
+        env.publish_event(
+            (Symbol::new(&env, "market_paused"), caller),
+            (info.reason_code, info.paused_at, info.expires_at),
+        );
+    } else if PauseStorage::get(&env).is_some() {
+        PauseStorage::remove(&env);
+        env.publish_event((Symbol::new(&env, "market_unpaused"), caller), now);
+    }
+
+    ProtocolEvent::PauseSwitchesUpdated(
+        pause_borrow,
+        pause_deposit,
+        pause_withdraw,
+        pause_liquidate,
+    )
+    .emit(&env);
+    Ok(())
+}
+
+/// Why and until when a market is paused; the pause switches govern the primary asset market
+pub fn get_pause_info(env: Env, asset: Address) -> Option<PauseInfo> {
+    if TokenRegistry::require_primary_asset(&env).ok() != Some(asset) {
+        return None;
+    }
+    PauseStorage::get(&env).filter(|info| !info.is_expired(&env))
+}
+
+/// Pause deposits, withdrawals, borrows or liquidations in one market only
+pub fn set_market_pauses(
+    env: Env,
+    caller: Address,
+    asset: Address,
+    pauses: MarketPauses,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    ComplianceGate::set_market_pauses(&env, &caller, &asset, pauses)
+}
+
+pub fn get_protocol_params(
+    env: Env,
+) -> Result<(i128, i128, i128, i128, i128, i128), ProtocolError> {
+    let config = InterestRateStorage::get_config(&env);
+    let risk_config = RiskConfigStorage::get(&env);
+
+    Ok((
+        config.base_rate,                  // 2000000 (2%)
+        config.kink_utilization,           // 80000000 (80%)
+        config.multiplier,                 // 10000000 (10x)
+        config.reserve_factor,             // 10000000 (10%)
+        risk_config.close_factor,          // 50000000 (50%)
+        risk_config.liquidation_incentive, // 10000000 (10%)
+    ))
+}
+
+pub fn get_risk_config(env: Env) -> Result<(i128, i128, bool, bool, bool, bool), ProtocolError> {
+    let config = RiskConfigStorage::get(&env);
+    Ok((
+        config.close_factor,
+        config.liquidation_incentive,
+        config.pause_borrow,
+        config.pause_deposit,
+        config.pause_withdraw,
+        config.pause_liquidate,
+    ))
+}
+
+pub fn get_system_stats(env: Env) -> Result<(i128, i128, i128, i128), ProtocolError> {
+    let state = InterestRateStorage::get_state(&env);
+
+    Ok((
+        state.total_supplied,
+        state.total_borrowed,
+        state.utilization_rate,
+        analytics::AnalyticsModule::get_protocol_metrics(&env).active_users,
+    ))
+}
+
+pub fn set_emergency_manager(
+    env: Env,
+    caller: Address,
+    manager: Address,
+    enabled: bool,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyManager::set_manager(&env, &caller, &manager, enabled)
+}
+
+pub fn trigger_emergency_pause(
+    env: Env,
+    caller: Address,
+    reason: Option<String>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyManager::pause(&env, &caller, reason)
+}
+
+/// Pause everything under a new incident id that lifts unless the admin confirms it
+pub fn circuit_break(env: Env, caller: Address) -> Result<u64, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    CircuitBreaker::trip(&env, &caller)
+}
+
+/// Keep a circuit break in force past its confirmation window
+pub fn confirm_circuit_break(
+    env: Env,
+    caller: Address,
+    incident_id: u64,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    CircuitBreaker::confirm(&env, &caller, incident_id)
+}
+
+/// Hand a guardian's spent circuit break back to it
+pub fn reset_circuit_break(
+    env: Env,
+    caller: Address,
+    guardian: Address,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    CircuitBreaker::reset(&env, &caller, &guardian)
+}
+
+pub fn enter_recovery_mode(
+    env: Env,
+    caller: Address,
+    plan: Option<String>,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyManager::enter_recovery(&env, &caller, plan)
+}
+
+pub fn resume_operations(env: Env, caller: Address) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyManager::resume(&env, &caller)
+}
+
+/// Set how long the protocol must stay paused before emergency redemptions open
+pub fn set_emergency_redeem_delay(
+    env: Env,
+    caller: Address,
+    delay: u64,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyRedemption::set_delay(&env, &caller, delay)
+}
+
+/// Redeem all of the caller's collateral in an asset after a prolonged shutdown
+pub fn emergency_redeem(env: Env, user: Address, asset: Address) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    user.require_auth();
+    EmergencyRedemption::redeem(&env, &user, &asset)
+}
+
+pub fn record_recovery_step(env: Env, caller: Address, step: String) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyManager::record_recovery_step(&env, &caller, step)
+}
+
+pub fn queue_emergency_param_update(
+    env: Env,
+    caller: Address,
+    parameter: Symbol,
+    value: i128,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyManager::queue_param_update(&env, &caller, parameter, value)
+}
+
+pub fn apply_emergency_param_updates(env: Env, caller: Address) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyManager::apply_param_updates(&env, &caller)
+}
+
+/// Replace the signers and threshold for emergency reserve withdrawals
+pub fn set_emergency_signers(
+    env: Env,
+    caller: Address,
+    signers: Vec<Address>,
+    threshold: u32,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyWithdrawals::set_signers(&env, &caller, signers, threshold)
+}
+
+/// Propose an emergency withdrawal of treasury reserves tied to an incident
+pub fn propose_emergency_withdrawal(
+    env: Env,
+    signer: Address,
+    asset: Address,
+    amount: i128,
+    recipient: Address,
+    incident_ref: String,
+) -> Result<u64, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    signer.require_auth();
+    EmergencyWithdrawals::propose(&env, &signer, &asset, amount, &recipient, incident_ref)
+}
+
+pub fn confirm_emergency_withdrawal(
+    env: Env,
+    signer: Address,
+    id: u64,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    signer.require_auth();
+    EmergencyWithdrawals::confirm(&env, &signer, id)
+}
+
+pub fn veto_emergency_withdrawal(
+    env: Env,
+    guardian: Address,
+    id: u64,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    guardian.require_auth();
+    EmergencyWithdrawals::veto(&env, &guardian, id)
+}
+
+pub fn execute_emergency_withdrawal(env: Env, id: u64) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    EmergencyWithdrawals::execute(&env, id)
+}
+
+pub fn adjust_emergency_fund(
+    env: Env,
+    caller: Address,
+    token: Option<Address>,
+    delta: i128,
+    reserve_delta: i128,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyManager::adjust_fund(&env, &caller, token, delta, reserve_delta)
+}
+
+/// Set the most insurance funds `cover_position` may spend per incident
+pub fn set_cover_cap(env: Env, caller: Address, cap: i128) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyManager::set_cover_cap(&env, &caller, cap)
+}
+
+/// Start a debt auction for bad debt the insurance fund cannot cover
+pub fn start_debt_auction(
+    env: Env,
+    caller: Address,
+    user: Address,
+    params: DebtAuctionParams,
+) -> Result<u64, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    DebtAuctions::start(&env, &caller, &user, params)
+}
+
+/// Buy protocol tokens from a debt auction with the debt asset
+pub fn bid_debt_auction(
+    env: Env,
+    bidder: Address,
+    auction_id: u64,
+    amount: i128,
+    max_price: i128,
+) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    bidder.require_auth();
+    DebtAuctions::bid(&env, &bidder, auction_id, amount, max_price)
+}
+
+/// Repay a position's debt from the insurance fund during an incident
+pub fn cover_position(
+    env: Env,
+    caller: Address,
+    user: Address,
+    asset: Address,
+    amount: i128,
+    reason: String,
+) -> Result<i128, ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    EmergencyManager::cover_position(&env, &caller, &user, &asset, amount, reason)
+}
+
+pub fn get_emergency_state(env: Env) -> Result<EmergencyState, ProtocolError> {
+    Ok(EmergencyStorage::get(&env))
+}
+
+pub fn get_event_summary(env: Env) -> Result<EventSummary, ProtocolError> {
+    Ok(EventStorage::get_summary(&env))
+}
+
+pub fn get_event_aggregates(env: Env) -> Result<Map<Symbol, EventAggregate>, ProtocolError> {
+    Ok(EventStorage::get_aggregates(&env))
+}
+
+pub fn get_events_for_type(
+    env: Env,
+    event_type: Symbol,
+    limit: u32,
+) -> Result<Vec<EventRecord>, ProtocolError> {
+    let logs = EventStorage::get_logs(&env);
+    let mut events = logs
+        .get(event_type.clone())
+        .unwrap_or_else(|| Vec::new(&env));
+    if limit > 0 && events.len() > limit {
+        let start = events.len() - limit;
+        events = events.slice(start..);
+    }
+    Ok(events)
+}
+
+pub fn get_recent_event_types(env: Env) -> Result<Vec<Symbol>, ProtocolError> {
+    Ok(EventStorage::get_summary(&env).recent_types)
+}
+
+pub fn register_token_asset(
+    env: Env,
+    caller: Address,
+    key: Symbol,
+    token: Address,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    TokenRegistry::set_asset(&env, &caller, key, token)
+}
+
+pub fn set_primary_asset(env: Env, caller: Address, token: Address) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    TokenRegistry::set_primary_asset(&env, &caller, token)
+}
+
+pub fn get_registered_asset(env: Env, key: Symbol) -> Result<Option<Address>, ProtocolError> {
+    Ok(TokenRegistry::get_asset(&env, key))
+}
+
+pub fn set_user_role(
+    env: Env,
+    caller: Address,
+    user: Address,
+    role: UserRole,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    UserManager::set_role(&env, &caller, &user, role)
+}
+
+pub fn set_user_verification(
+    env: Env,
+    caller: Address,
+    user: Address,
+    status: VerificationStatus,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    UserManager::set_verification_status(&env, &caller, &user, status)
+}
+
+pub fn set_user_limits(
+    env: Env,
+    caller: Address,
+    user: Address,
+    max_deposit: i128,
+    max_borrow: i128,
+    max_withdraw: i128,
+    daily_limit: i128,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    UserManager::set_limits(
+        &env,
+        &caller,
+        &user,
+        max_deposit,
+        max_borrow,
+        max_withdraw,
+        daily_limit,
+    )
+}
+
+pub fn freeze_user(env: Env, caller: Address, user: Address) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    UserManager::freeze_user(&env, &caller, &user)
+}
+
+pub fn set_freeze_level(
+    env: Env,
+    caller: Address,
+    user: Address,
+    level: FreezeLevel,
+    expires_at: u64,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    UserManager::set_freeze_level(&env, &caller, &user, level, expires_at)
+}
+
+pub fn unfreeze_user(env: Env, caller: Address, user: Address) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    UserManager::unfreeze_user(&env, &caller, &user)
+}
+
+pub fn get_user_profile(env: Env, user: Address) -> Result<UserProfile, ProtocolError> {
+    Ok(UserManager::get_profile(&env, &user))
+}
+
+pub fn flag_suspicious_activity(
+    env: Env,
+    caller: Address,
+    user: Address,
+    severity: AlertSeverity,
+    reason: Symbol,
+) -> Result<(), ProtocolError> {
+    let _guard = ReentrancyScope::enter(&env)?;
+    caller.require_auth();
+    UserManager::flag_suspicious(&env, &caller, &user, severity, reason)
+}
+
+pub fn get_compliance_summary(env: Env) -> Result<ComplianceSummary, ProtocolError> {
+    Ok(UserManager::get_compliance_summary(&env))
+}
+
+#[contractimpl]
+impl Contract {
+    /// Initializes the contract with its admin, treasury, default asset and risk parameters.
+    /// Reverts with `InvalidAddress` or `InvalidParameters` if the configuration is unsound.
+    pub fn initialize(env: Env, admin: Address, config: InitConfig) -> Result<(), ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        if env.storage().instance().has(&ProtocolConfig::admin_key()) {
+            return Err(ProtocolError::AlreadyInitialized);
+        }
+        ConfigurationValidator::validate_init(&env, &config)?;
+        ProtocolConfig::set_admin(&env, &admin);
+        UserManager::bootstrap_admin(&env, &admin);
+        ProtocolConfig::set_treasury(&env, &config.treasury);
+        TokenRegistry::set_primary_asset(&env, &admin, config.native_asset)?;
+        env.storage().instance().set(
+            &ProtocolConfig::min_collateral_ratio_key(),
+            &config.min_collateral_ratio,
+        );
+
+        // Initialize interest rate system with default configuration
+        InterestRateStorage::save_config(&env, &InterestRateConfig::default());
+
+        let state = InterestRateState::initial();
+        InterestRateStorage::save_state(&env, &state);
+
+        // Initialize risk management system with the supplied liquidation parameters
+        let risk_config = RiskConfig {
+            close_factor: config.close_factor,
+            liquidation_incentive: config.liquidation_incentive,
+            ..Default::default()
+        };
+        RiskConfigStorage::save(&env, &risk_config);
+
+        Ok(())
+    }
+
+    /// Get the protocol treasury address
+    pub fn get_treasury(env: Env) -> Option<Address> {
+        ProtocolConfig::get_treasury(&env)
+    }
+
+    /// Set the minimum collateral ratio (admin only)
+    pub fn set_min_collateral_ratio(
+        env: Env,
+        caller: Address,
+        ratio: i128,
+    ) -> Result<(), ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        caller.require_auth();
+        ProtocolConfig::require_admin(&env, &caller)?;
+        BorrowLimits::ensure_param_change(
+            &env,
+            "min_collateral_ratio",
+            ProtocolConfig::get_min_collateral_ratio(&env),
+            ratio,
+        )?;
+        ProtocolConfig::set_min_collateral_ratio(&env, &caller, ratio)?;
+        Ok(())
+    }
+
+    /// Set the health factor buffer in basis points (admin only): user-initiated borrows
+    /// and withdrawals must leave the health factor at or above 1 + buffer, while
+    /// positions stay liquidatable only below 1.0
+    pub fn set_health_factor_buffer(
+        env: Env,
+        caller: Address,
+        bps: i128,
+    ) -> Result<(), ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        caller.require_auth();
+        ProtocolConfig::set_health_factor_buffer(&env, &caller, bps)
+    }
+
+    /// Query the health factor buffer in basis points
+    pub fn get_health_factor_buffer(env: Env) -> i128 {
+        ProtocolConfig::get_health_factor_buffer(&env)
+    }
+
+    /// Deposit `amount` of primary asset collateral, in the asset's smallest unit (see
+    /// `normalize_amount` for values held at another precision)
+    pub fn deposit_collateral(
+        env: Env,
+        depositor: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        deposit_collateral(env, depositor, amount)
+    }
+
+    /// Borrow `amount` of the primary asset, in its smallest unit
+    pub fn borrow(env: Env, borrower: Address, amount: i128) -> Result<(), ProtocolError> {
+        borrow(env, borrower, amount)
+    }
+
+    /// Quote the current borrow rate for `amount` of `asset`; the quote can be executed
+    /// once with `borrow_with_quote` within QUOTE_VALIDITY_LEDGERS ledgers
+    pub fn quote_borrow(
+        env: Env,
+        asset: Address,
+        amount: i128,
+    ) -> Result<BorrowQuote, ProtocolError> {
+        quote_borrow(env, asset, amount)
+    }
+
+    /// Borrow a live quote's amount; the debt accrues at the quoted rate until its first
+    /// accrual
+    pub fn borrow_with_quote(env: Env, user: Address, quote_id: u64) -> Result<(), ProtocolError> {
+        borrow_with_quote(env, user, quote_id)
+    }
+
+    /// Repay up to `amount` of borrowed primary asset, in its smallest unit; accrued
+    /// interest is settled before principal
+    pub fn repay(env: Env, repayer: Address, amount: i128) -> Result<(), ProtocolError> {
+        repay(env, repayer, amount)
+    }
+
+    /// Withdraw `amount` of primary asset collateral, in its smallest unit
+    pub fn withdraw(env: Env, withdrawer: Address, amount: i128) -> Result<(), ProtocolError> {
+        withdraw(env, withdrawer, amount)
+    }
+
+    /// Relayer: submit a deposit, borrow, repay or withdrawal the user signed off-chain.
+    /// The user's authorization entry for this call carries the signature; the payload's
+    /// nonce must be the user's next relay nonce and its deadline must not have passed.
+    pub fn execute_signed(
+        env: Env,
+        user: Address,
+        payload: SignedAction,
+    ) -> Result<(), ProtocolError> {
+        execute_signed(env, user, payload)
+    }
+
+    /// Query the nonce a user's next signed action must carry
+    pub fn get_relay_nonce(env: Env, user: Address) -> u64 {
+        RelayStorage::get_nonce(&env, &user)
+    }
+
+    /// Liquidate an undercollateralized position
+    pub fn liquidate(
+        env: Env,
+        liquidator: Address,
+        user: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        liquidate(env, liquidator, user, amount)
+    }
+
+    /// Liquidate an undercollateralized position by seizing `collateral_asset`, which must be
+    /// the next asset in the user's seizure order. `receiver` is the beneficiary of the
+    /// seized collateral (e.g. a cold wallet or vault, never the liquidated user) and
+    /// defaults to the liquidator; both are named in the liquidation event.
+    pub fn liquidate_collateral(
+        env: Env,
+        liquidator: Address,
+        user: Address,
+        collateral_asset: Address,
+        amount: i128,
+        receiver: Option<Address>,
+    ) -> Result<(), ProtocolError> {
+        liquidate_collateral(env, liquidator, user, collateral_asset, amount, receiver)
+    }
+
+    /// Rank the caller's collateral for seizure (first listed is seized first); an empty
+    /// list falls back to the protocol default
+    pub fn set_seizure_order(
+        env: Env,
+        user: Address,
+        order: Vec<Address>,
+    ) -> Result<(), ProtocolError> {
+        set_seizure_order(env, user, order)
+    }
+
+    /// Admin: set the seizure order for users without a preference of their own
+    pub fn set_default_seizure_order(
+        env: Env,
+        caller: Address,
+        order: Vec<Address>,
+    ) -> Result<(), ProtocolError> {
+        set_default_seizure_order(env, caller, order)
+    }
+
+    /// Query the collateral assets a user holds in the order liquidators must seize them
+    pub fn get_seizure_order(env: Env, user: Address) -> Vec<Address> {
+        liquidate::LiquidationModule::seizure_order(&env, &user)
+    }
+
+    /// Admin: have liquidations repay only what brings a position back to `target` health
+    /// factor (e.g. 105 for 1.05), within the close factor, instead of whatever the
+    /// liquidator asks for; `None` restores liquidator-sized repayments
+    pub fn set_liquidation_target_hf(
+        env: Env,
+        caller: Address,
+        target: Option<i128>,
+    ) -> Result<(), ProtocolError> {
+        set_liquidation_target_hf(env, caller, target)
+    }
+
+    /// Query the post-liquidation health factor target, if set
+    pub fn get_liquidation_target_hf(env: Env) -> Option<i128> {
+        liquidate::LiquidationTargetStorage::get(&env)
+    }
+
+    /// Page through accounts whose health factor fell below 1.2 at their last interaction,
+    /// in the order they were added (`limit` capped at MAX_WATCHLIST_PAGE)
+    pub fn get_watchlist(env: Env, offset: u32, limit: u32) -> Vec<Address> {
+        LiquidationWatchlist::page(&env, offset, limit)
+    }
+
+    /// Admin: round seized collateral in the borrower's favor (default) or the protocol's,
+    /// crediting the remainder to reserves
+    pub fn set_seizure_rounding(
+        env: Env,
+        caller: Address,
+        rounding: liquidate::SeizureRounding,
+    ) -> Result<(), ProtocolError> {
+        set_seizure_rounding(env, caller, rounding)
+    }
+
+    /// Query the rounding direction applied to seized collateral
+    pub fn get_seizure_rounding(env: Env) -> liquidate::SeizureRounding {
+        liquidate::SeizureRoundingStorage::get(&env)
+    }
+
+    /// Admin: charge `fee_bps` of the collateral seized from `asset` as a protocol fee,
+    /// credited to the asset's reserves on top of the keeper bonus; zero removes it
+    pub fn set_liquidation_fee(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        fee_bps: i128,
+    ) -> Result<(), ProtocolError> {
+        set_liquidation_fee(env, caller, asset, fee_bps)
+    }
+
+    /// Query the protocol liquidation fee (bps of seized collateral) for an asset
+    pub fn get_liquidation_fee(env: Env, asset: Address) -> i128 {
+        liquidate::LiquidationFeeStorage::get(&env, &asset)
+    }
+
+    /// Admin: replace the emission epochs for the supply or borrow side of an asset
+    pub fn set_emission_schedule(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        side: RewardSide,
+        epochs: Vec<EmissionEpoch>,
+    ) -> Result<(), ProtocolError> {
+        set_emission_schedule(env, caller, asset, side, epochs)
+    }
+
+    /// Query the emission schedule for one side of an asset
+    pub fn get_emission_schedule(env: Env, asset: Address, side: RewardSide) -> Vec<EmissionEpoch> {
+        RewardStorage::get_schedule(&env, &asset, side)
+    }
+
+    /// Admin: scale the supply or borrow emissions of the primary market by utilization
+    /// band, e.g. boost borrow incentives only below the kink; an empty list removes them
+    pub fn set_emission_bands(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        side: RewardSide,
+        bands: Vec<UtilizationBand>,
+    ) -> Result<(), ProtocolError> {
+        set_emission_bands(env, caller, asset, side, bands)
+    }
+
+    /// Query the utilization bands scaling emissions on one side of an asset
+    pub fn get_emission_bands(env: Env, asset: Address, side: RewardSide) -> Vec<UtilizationBand> {
+        RewardStorage::get_bands(&env, &asset, side)
+    }
+
+    /// Query the emission epochs in force right now for both sides of an asset
+    pub fn get_current_epoch_emissions(env: Env, asset: Address) -> CurrentEmissions {
+        RewardManager::current(&env, &asset)
+    }
+
+    /// Query the liquidity incentives a user has accrued in an asset's market on both
+    /// sides, up to the current second
+    pub fn get_pending_rewards(env: Env, user: Address, asset: Address) -> i128 {
+        RewardManager::pending(&env, &user, &asset)
+    }
+
+    /// Admin: set (or clear with `None`) the health-factor-scaled liquidation bonus for an asset
+    pub fn set_liquidation_incentive_curve(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        curve: Option<liquidate::IncentiveCurve>,
+    ) -> Result<(), ProtocolError> {
+        set_liquidation_incentive_curve(env, caller, asset, curve)
+    }
+
+    /// Get the liquidation incentive curve configured for an asset
+    pub fn get_liquidation_incentive_curve(
+        env: Env,
+        asset: Address,
+    ) -> Option<liquidate::IncentiveCurve> {
+        liquidate::IncentiveCurveStorage::get_all(&env).get(asset)
+    }
+
+    /// Admin: set the close factor per health factor band, ordered by descending
+    /// threshold and ending at zero; an empty table restores the flat close factor
+    pub fn set_close_factor_bands(
+        env: Env,
+        caller: Address,
+        bands: Vec<liquidate::CloseFactorBand>,
+    ) -> Result<(), ProtocolError> {
+        set_close_factor_bands(env, caller, bands)
+    }
+
+    /// Get the close factor table
+    pub fn get_close_factor_bands(env: Env) -> Vec<liquidate::CloseFactorBand> {
+        liquidate::CloseFactorStorage::get_bands(&env)
+    }
+
+    /// Admin: withhold `tax_bps` of each liquidation bonus for the liquidated borrower,
+    /// claimable after `cooldown` seconds without another liquidation
+    pub fn set_liquidation_rebate(
+        env: Env,
+        caller: Address,
+        tax_bps: i128,
+        cooldown: u64,
+    ) -> Result<(), ProtocolError> {
+        set_liquidation_rebate(env, caller, tax_bps, cooldown)
+    }
+
+    /// Get the liquidation rebate settings
+    pub fn get_liquidation_rebate_config(env: Env) -> RebateConfig {
+        RebateStorage::get_config(&env)
+    }
+
+    /// Fees an account paid (origination, interest, liquidation penalties) in daily buckets
+    /// starting within `[from_ts, to_ts]`, plus lifetime totals
+    pub fn get_user_fee_statement(
+        env: Env,
+        user: Address,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> FeeStatement {
+        FeeLedger::statement(&env, &user, from_ts, to_ts)
+    }
+
+    /// Get a borrower's unclaimed liquidation rebate
+    pub fn get_rebate_pot(env: Env, user: Address) -> RebatePot {
+        RebateStorage::get_pot(&env, &user)
+    }
+
+    /// Claim the liquidation rebate pot, returning the amount paid out
+    pub fn claim_liquidation_rebate(env: Env, user: Address) -> Result<i128, ProtocolError> {
+        claim_liquidation_rebate(env, user)
+    }
+
+    /// Liquidate several `(target, debt_asset, collateral_asset, amount)` entries in one call,
+    /// returning a result per entry; ineligible entries are skipped instead of reverting
+    pub fn liquidate_batch(
+        env: Env,
+        liquidator: Address,
+        entries: Vec<(Address, Address, Address, i128)>,
+    ) -> Result<Vec<liquidate::BatchLiquidationResult>, ProtocolError> {
+        liquidate_batch(env, liquidator, entries)
+    }
+
+    /// Preview a deposit: tokens in, receipt units minted, pending supply interest, storage
+    /// fee and the resulting position
+    pub fn preview_deposit(
+        env: Env,
+        user: Address,
+        amount: i128,
+    ) -> Result<ActionPreview, ProtocolError> {
+        preview_deposit(env, user, amount)
+    }
+
+    /// Preview a withdrawal: tokens out, receipt units burned and the resulting position
+    pub fn preview_withdraw(
+        env: Env,
+        user: Address,
+        amount: i128,
+    ) -> Result<ActionPreview, ProtocolError> {
+        preview_withdraw(env, user, amount)
+    }
+
+    /// Preview a borrow: tokens out, accrued borrow interest and the resulting position
+    pub fn preview_borrow(
+        env: Env,
+        user: Address,
+        amount: i128,
+    ) -> Result<ActionPreview, ProtocolError> {
+        preview_borrow(env, user, amount)
+    }
+
+    /// Preview a repayment: tokens actually pulled, interest settled and the resulting
+    /// position
+    pub fn preview_repay(
+        env: Env,
+        user: Address,
+        amount: i128,
+    ) -> Result<ActionPreview, ProtocolError> {
+        preview_repay(env, user, amount)
+    }
+
+    /// Preview repay accepted, collateral seized, bonus and resulting health factor
+    pub fn simulate_liquidation(
+        env: Env,
+        target: Address,
+        debt_asset: Address,
+        collateral_asset: Address,
+        repay_amount: i128,
+    ) -> Result<liquidate::LiquidationSimulation, ProtocolError> {
+        simulate_liquidation(env, target, debt_asset, collateral_asset, repay_amount)
+    }
+
+    /// Grant a delegate bounded rights to deposit or repay on the user's behalf
+    pub fn grant_session(
+        env: Env,
+        user: Address,
+        delegate: Address,
+        max_amount_per_action: i128,
+        allow_deposit: bool,
+        allow_repay: bool,
+        expires_at: u64,
+    ) -> Result<(), ProtocolError> {
+        grant_session(
+            env,
+            user,
+            delegate,
+            max_amount_per_action,
+            allow_deposit,
+            allow_repay,
+            expires_at,
+        )
+    }
+
+    /// Revoke a previously granted session
+    pub fn revoke_session(env: Env, user: Address, delegate: Address) -> Result<(), ProtocolError> {
+        revoke_session(env, user, delegate)
+    }
+
+    /// Get the session a user granted to a delegate, if any
+    pub fn get_session(env: Env, user: Address, delegate: Address) -> Option<SessionPolicy> {
+        SessionStorage::get(&env, &user, &delegate)
+    }
+
+    /// Deposit collateral for a user through a session delegate
+    pub fn deposit_collateral_for(
+        env: Env,
+        delegate: Address,
+        user: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        deposit_collateral_for(env, delegate, user, amount)
+    }
+
+    /// Repay a user's debt through a session delegate
+    pub fn repay_for(
+        env: Env,
+        delegate: Address,
+        user: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        repay_for(env, delegate, user, amount)
+    }
+
+    /// Allow keepers to repay from the user's token allowance up to `cap` in total (0 = opt out)
+    pub fn set_auto_repay_cap(env: Env, user: Address, cap: i128) -> Result<(), ProtocolError> {
+        set_auto_repay_cap(env, user, cap)
+    }
+
+    /// Remaining amount keepers may still pull for a user's repayments
+    pub fn get_auto_repay_cap(env: Env, user: Address) -> i128 {
+        repay::AutoRepayStorage::get_cap(&env, &user)
+    }
+
+    /// Keeper: repay a user's debt by pulling tokens they approved to this contract
+    pub fn pull_repay(
+        env: Env,
+        keeper: Address,
+        user: Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        pull_repay(env, keeper, user, amount)
+    }
+
+    /// Admin: set the interest-free window in hours for new borrows of an asset (0 = off)
+    pub fn set_grace_period(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        hours: u64,
+    ) -> Result<(), ProtocolError> {
+        set_grace_period(env, caller, asset, hours)
+    }
+
+    /// Get the interest-free window in hours for new borrows of an asset
+    pub fn get_grace_period(env: Env, asset: Address) -> u64 {
+        GracePeriod::get_window_hours(&env, &asset)
+    }
+
+    /// Admin: set the global debt ceiling and utilization freeze/resume thresholds
+    pub fn set_protocol_parameters(
+        env: Env,
+        caller: Address,
+        params: ProtocolParameters,
+    ) -> Result<(), ProtocolError> {
+        set_protocol_parameters(env, caller, params)
+    }
+
+    /// Get the global debt ceiling and utilization kill-switch thresholds
+    pub fn get_protocol_parameters(env: Env) -> ProtocolParameters {
+        limits::LimitsStorage::get_params(&env)
+    }
+
+    /// Keeper: extend the contract instance, which holds the protocol's state, and the
+    /// contract code to the full TTL; returns the ledger they now live until
+    pub fn bump_core_state(env: Env) -> u32 {
+        Ttl::bump_core_state(&env)
+    }
+
+    /// Keeper: extend the given entries to the full TTL, returning for each the ledger it
+    /// now lives until, or zero when it does not exist
+    pub fn bump_entries(env: Env, entries: Vec<StateEntry>) -> Result<Vec<u32>, ProtocolError> {
+        bump_entries(env, entries)
+    }
+
+    /// Whether new borrows are frozen by the utilization kill-switch
+    pub fn is_borrow_frozen(env: Env) -> bool {
+        limits::LimitsStorage::is_frozen(&env)
+    }
+
+    /// Admin: enter recovery mode automatically when all collateral held falls below
+    /// `enter_ratio` percent of outstanding debt, applying the configured LTV and bonus
+    /// multipliers and borrow freezes until the ratio regains `exit_ratio`; `None` disarms it
+    pub fn set_recovery_mode_config(
+        env: Env,
+        caller: Address,
+        config: Option<RecoveryConfig>,
+    ) -> Result<(), ProtocolError> {
+        set_recovery_mode_config(env, caller, config)
+    }
+
+    /// Query the recovery mode thresholds and multipliers, if armed
+    pub fn get_recovery_mode_config(env: Env) -> Option<RecoveryConfig> {
+        RecoveryStorage::get_config(&env)
+    }
+
+    /// Whether recovery mode is in force
+    pub fn is_recovery_mode(env: Env) -> bool {
+        RecoveryStorage::is_active(&env)
+    }
+
+    /// Re-evaluate recovery mode against current prices and balances, e.g. after a price
+    /// move; anyone may call it. Returns whether recovery mode is in force.
+    pub fn refresh_recovery_mode(env: Env) -> bool {
+        RecoveryMode::refresh(&env)
+    }
+
+    /// Value of all collateral held as a percentage of outstanding debt; zero when
+    /// nothing is borrowed
+    pub fn get_global_collateral_ratio(env: Env) -> i128 {
+        RecoveryMode::global_ratio(&env)
+    }
+
+    /// Opt a supplied asset in or out of counting as collateral; opted-out assets cannot
+    /// be seized, and opting out is refused if it would leave the position unhealthy
+    pub fn set_use_as_collateral(
+        env: Env,
+        user: Address,
+        asset: Address,
+        enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        set_use_as_collateral(env, user, asset, enabled)
+    }
+
+    /// Whether a user's supplied asset counts as collateral
+    pub fn is_collateral_enabled(env: Env, user: Address, asset: Address) -> bool {
+        cross_asset::CrossAssetManager::is_collateral_enabled(&env, &user, &asset)
+    }
+
+    /// Assets the user currently holds collateral or debt in, in the order they were entered
+    pub fn get_user_markets(env: Env, user: Address) -> Vec<Address> {
+        cross_asset::UserMarkets::get(&env, &user)
+    }
+
+    /// Current liquidity index of the primary asset pool (scaled by 1e18)
+    pub fn get_liquidity_index(env: Env) -> i128 {
+        InterestRateStorage::get_state(&env).liquidity_index
+    }
+
+    /// Admin: cap the collateral a single account may hold in an asset (0 = uncapped)
+    pub fn set_max_position_size(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        max_size: i128,
+    ) -> Result<(), ProtocolError> {
+        set_max_position_size(env, caller, asset, max_size)
+    }
+
+    /// Get the per-account collateral cap for an asset (0 = uncapped)
+    pub fn get_max_position_size(env: Env, asset: Address) -> i128 {
+        BorrowLimits::get_max_position_size(&env, &asset)
+    }
+
+    /// Compliance officer: cap how much `user` may supply of `asset` to limit
+    /// single-counterparty exposure; `None` removes the cap
+    pub fn set_address_supply_cap(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        user: Address,
+        cap: Option<i128>,
+    ) -> Result<(), ProtocolError> {
+        set_address_supply_cap(env, caller, asset, user, cap)
+    }
+
+    /// Admin: require liquidators to be on the allowlist (regulated deployments) or
+    /// restore permissionless liquidations
+    pub fn set_restricted_liquidations(
+        env: Env,
+        caller: Address,
+        restricted: bool,
+    ) -> Result<(), ProtocolError> {
+        set_restricted_liquidations(env, caller, restricted)
+    }
+
+    /// Whether liquidations are limited to allowlisted liquidators
+    pub fn is_restricted_liquidations(env: Env) -> bool {
+        liquidate::LiquidatorAllowlistStorage::is_restricted(&env)
+    }
+
+    /// Compliance officer: approve or revoke a liquidator for restricted mode
+    pub fn set_approved_liquidator(
+        env: Env,
+        caller: Address,
+        liquidator: Address,
+        approved: bool,
+    ) -> Result<(), ProtocolError> {
+        set_approved_liquidator(env, caller, liquidator, approved)
+    }
+
+    /// Whether a liquidator is on the allowlist
+    pub fn is_approved_liquidator(env: Env, liquidator: Address) -> bool {
+        liquidate::LiquidatorAllowlistStorage::is_approved(&env, &liquidator)
+    }
+
+    /// Get the supply cap placed on one address for an asset, if any
+    pub fn get_address_supply_cap(env: Env, asset: Address, user: Address) -> Option<i128> {
+        limits::LimitsStorage::get_address_cap(&env, &asset, &user)
+    }
+
+    /// Get how much more of an asset a user may supply under the position and address
+    /// caps; `None` when the user is uncapped
+    pub fn get_remaining_supply_capacity(
+        env: Env,
+        user: Address,
+        asset: Address,
+    ) -> Result<Option<i128>, ProtocolError> {
+        BorrowLimits::remaining_supply_capacity(&env, &user, &asset)
+    }
+
+    /// Admin: pause borrowing an asset for `cooldown` seconds whenever its utilization
+    /// rises more than `max_jump` within one ledger; `None` removes the breaker
+    pub fn set_utilization_breaker(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        breaker: Option<UtilizationBreaker>,
+    ) -> Result<(), ProtocolError> {
+        set_utilization_breaker(env, caller, asset, breaker)
+    }
+
+    /// Get the utilization circuit breaker configured for an asset
+    pub fn get_utilization_breaker(env: Env, asset: Address) -> Option<UtilizationBreaker> {
+        limits::LimitsStorage::get_breakers(&env).get(asset)
+    }
+
+    /// Admin: borrow `asset` at a fixed rate instead of the kinked model while its
+    /// supplied liquidity is below `min_liquidity`; `None` clears it
+    pub fn set_thin_market_rate(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        rate: Option<ThinMarketRate>,
+    ) -> Result<(), ProtocolError> {
+        set_thin_market_rate(env, caller, asset, rate)
+    }
+
+    /// Get the thin-market rate configured for an asset
+    pub fn get_thin_market_rate(env: Env, asset: Address) -> Option<ThinMarketRate> {
+        limits::LimitsStorage::get_thin_market_rates(&env).get(asset)
+    }
+
+    /// Get the timestamp until which the breaker pauses borrowing an asset (0 = never
+    /// tripped)
+    pub fn get_borrow_cooldown(env: Env, asset: Address) -> u64 {
+        limits::LimitsStorage::get_cooldown(&env, &asset)
+    }
+
+    /// Risk dashboard snapshot: per-asset utilization, cap usage, positions below a 1.1
+    /// health factor and oracle staleness, plus total bad debt and all pause states
+    pub fn get_risk_overview(env: Env) -> Result<RiskOverview, ProtocolError> {
+        RiskMonitor::overview(&env)
+    }
+
+    // SEP-41 interface over deposit receipts; one receipt equals one unit of primary collateral
+
+    pub fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        ReceiptToken::allowance(&env, &from, &spender)
+    }
+
+    pub fn approve(
+        env: Env,
+        from: Address,
+        spender: Address,
+        amount: i128,
+        expiration_ledger: u32,
+    ) -> Result<(), ProtocolError> {
+        approve(env, from, spender, amount, expiration_ledger)
+    }
+
+    pub fn balance(env: Env, id: Address) -> i128 {
+        ReceiptToken::balance(&env, &id)
+    }
+
+    pub fn transfer(
+        env: Env,
+        from: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        transfer(env, from, to, amount)
+    }
+
+    pub fn transfer_from(
+        env: Env,
+        spender: Address,
+        from: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        transfer_from(env, spender, from, to, amount)
+    }
+
+    /// Burning receipts withdraws the underlying primary asset to `from`
+    pub fn burn(env: Env, from: Address, amount: i128) -> Result<(), ProtocolError> {
+        burn(env, from, amount)
+    }
+
+    pub fn burn_from(
+        env: Env,
+        spender: Address,
+        from: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        burn_from(env, spender, from, amount)
+    }
+
+    pub fn decimals(env: Env) -> u32 {
+        ReceiptToken::decimals(&env)
+    }
+
+    pub fn name(env: Env) -> String {
+        ReceiptToken::name(&env)
+    }
+
+    pub fn symbol(env: Env) -> String {
+        ReceiptToken::symbol(&env)
+    }
+
+    /// Allow or stop a protector topping up collateral and repaying debt for the user
+    pub fn set_protector(
+        env: Env,
+        user: Address,
+        protector: Address,
+        enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        set_protector(env, user, protector, enabled)
+    }
+
+    /// Check whether `protector` may deposit and repay on the user's behalf
+    pub fn is_protector(env: Env, user: Address, protector: Address) -> bool {
+        ProtectorStorage::is_protector(&env, &user, &protector)
+    }
+
+    /// Add collateral to a user's position as one of their protectors
+    pub fn deposit_on_behalf(
+        env: Env,
+        protector: Address,
+        user: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        deposit_collateral_for(env, protector, user, amount)
+    }
+
+    /// Repay a user's debt as one of their protectors
+    pub fn repay_on_behalf(
+        env: Env,
+        protector: Address,
+        user: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        repay_for(env, protector, user, amount)
+    }
+
+    /// Get user position
+    pub fn get_position(env: Env, user: Address) -> Result<(i128, i128, i128), ProtocolError> {
+        get_position(env, user)
+    }
+
+    /// Get a user's most recent `limit` position changes, oldest first
+    pub fn get_position_history(env: Env, user: Address, limit: u32) -> Vec<PositionHistoryEntry> {
+        PositionHistory::get(&env, &user, limit)
+    }
+
+    /// Move `amount` of debt in `asset`, with the same share of every collateral balance,
+    /// from `user` to `target_account`; both accounts must authorize and stay healthy
+    pub fn split_position(
+        env: Env,
+        user: Address,
+        asset: Address,
+        amount: i128,
+        target_account: Address,
+    ) -> Result<(), ProtocolError> {
+        split_position(env, user, asset, amount, target_account)
+    }
+
+    /// Fold `from_account`'s whole position into `to_account`'s; `user` must be one of
+    /// the two and both must authorize
+    pub fn merge_positions(
+        env: Env,
+        user: Address,
+        from_account: Address,
+        to_account: Address,
+    ) -> Result<(), ProtocolError> {
+        merge_positions(env, user, from_account, to_account)
+    }
+
+    /// Deposit `amount` of collateral in a non-primary asset, in that asset's smallest unit
+    pub fn deposit_collateral_asset(
+        env: Env,
+        user: Address,
+        asset: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        deposit_collateral_asset(env, user, asset, amount)
+    }
+
+    /// Withdraw `amount` of collateral held in a non-primary asset, in that asset's
+    /// smallest unit
+    pub fn withdraw_asset(
+        env: Env,
+        user: Address,
+        asset: Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        withdraw_asset(env, user, asset, amount)
+    }
+
+    /// Get risk-adjusted collateral, debt and collateral ratio for a user
+    pub fn get_cross_position_summary(
+        env: Env,
+        user: Address,
+    ) -> Result<(i128, i128, i128), ProtocolError> {
+        get_cross_position_summary(env, user)
+    }
+
+    /// Get a user's full state (positions, risk, compliance flags, activity) in one call
+    pub fn get_full_user_state(env: Env, user: Address) -> Result<UserState, ProtocolError> {
+        get_full_user_state(env, user)
+    }
+
+    /// Admin: configure fee share, protocol token, DEX adapter and burn/stream mode for buybacks
+    pub fn set_buyback_config(
+        env: Env,
+        caller: Address,
+        config: BuybackConfig,
+    ) -> Result<(), ProtocolError> {
+        set_buyback_config(env, caller, config)
+    }
+
+    /// Admin: buy back the protocol token with the accumulated buyback reserve
+    pub fn execute_buyback(
+        env: Env,
+        caller: Address,
+        min_out: i128,
+    ) -> Result<i128, ProtocolError> {
+        execute_buyback(env, caller, min_out)
+    }
+
+    /// Admin or treasury manager: transfer reserve income segregated in `asset` to the
+    /// treasury, or proportionally to every fee split recipient when a split is set
+    pub fn distribute_fees_to_treasury(
+        env: Env,
+        caller: Address,
+        asset: Address,
+    ) -> Result<i128, ProtocolError> {
+        distribute_fees_to_treasury(env, caller, asset)
+    }
+
+    /// Admin or treasury manager: replace the weighted recipients of treasury
+    /// distributions; weights are in basis points and must sum to 10000, and an empty
+    /// list restores the single treasury address
+    pub fn set_fee_splits(
+        env: Env,
+        caller: Address,
+        splits: Vec<FeeSplit>,
+    ) -> Result<(), ProtocolError> {
+        set_fee_splits(env, caller, splits)
+    }
+
+    /// Current fee split; empty when distributions go to the treasury address
+    pub fn get_fee_splits(env: Env) -> Vec<FeeSplit> {
+        revenue::RevenueStorage::get_fee_splits(&env)
+    }
+
+    /// Compare the treasury's claim on `asset` with the contract's token balance
+    pub fn get_treasury_reconciliation(env: Env, asset: Address) -> TreasuryReconciliation {
+        RevenueManager::reconcile_treasury(&env, &asset)
+    }
+
+    /// Get cumulative protocol revenue and buyback accounting
+    pub fn get_revenue_metrics(env: Env) -> RevenueMetrics {
+        RevenueManager::get_metrics(&env)
+    }
+
+    /// Get the revenue one asset has earned the protocol, split into interest, fees and
+    /// liquidations, in units of the asset
+    pub fn get_asset_revenue(env: Env, asset: Address) -> AssetRevenue {
+        RevenueManager::asset_revenue(&env, &asset)
+    }
+
+    /// Get the revenue breakdown of every asset that has earned the protocol revenue
+    pub fn get_revenue_by_asset(env: Env) -> Map<Address, AssetRevenue> {
+        revenue::RevenueStorage::get_revenue_by_asset(&env)
+    }
+
+    /// Get the most recent daily, weekly or monthly fee buckets, oldest first
+    pub fn get_revenue_history(
+        env: Env,
+        granularity: RevenueGranularity,
+        limit: u32,
+    ) -> Vec<RevenueBucket> {
+        RevenueManager::get_history(&env, granularity, limit)
+    }
+
+    /// Opt in or out of compounding supply interest into collateral
+    pub fn set_auto_compound(env: Env, user: Address, enabled: bool) -> Result<(), ProtocolError> {
+        set_auto_compound(env, user, enabled)
+    }
+
+    /// Opt in to health warnings: `prefs.hint` is echoed in `health_warning` events once
+    /// the health factor falls below `prefs.warn_health_factor`; `None` opts out
+    pub fn set_notification_prefs(
+        env: Env,
+        user: Address,
+        prefs: Option<NotificationPrefs>,
+    ) -> Result<(), ProtocolError> {
+        set_notification_prefs(env, user, prefs)
+    }
+
+    /// Get a user's notification preferences, if any
+    pub fn get_notification_prefs(env: Env, user: Address) -> Option<NotificationPrefs> {
+        NotificationStorage::get(&env, &user)
+    }
+
+    /// Recompute a user's health factor and publish a warning if it is below their
+    /// threshold, e.g. after a price move; permissionless. Returns 0 without debt.
+    pub fn check_health(env: Env, user: Address) -> Result<i128, ProtocolError> {
+        Notifications::check(&env, &user)
+    }
+
+    /// Delete a debt-free account: returns all collateral and supply interest, removes
+    /// positions, activity records, KYC status and session grants, and emits an
+    /// `account_purged` tombstone. Returns the primary asset paid out.
+    pub fn purge_account(env: Env, user: Address) -> Result<i128, ProtocolError> {
+        purge_account(env, user)
+    }
+
+    /// Claim accrued supply interest for positions that do not auto-compound
+    pub fn claim_supply_interest(env: Env, user: Address) -> Result<i128, ProtocolError> {
+        claim_supply_interest(env, user)
+    }
+
+    /// Swap collateral from one supported asset to another through an allowed DEX
+    /// adapter, keeping the position healthy
+    pub fn migrate_collateral(
+        env: Env,
+        user: Address,
+        from_asset: Address,
+        to_asset: Address,
+        amount: i128,
+        dex_adapter: Address,
+        min_out: i128,
+    ) -> Result<i128, ProtocolError> {
+        migrate_collateral(
+            env,
+            user,
+            from_asset,
+            to_asset,
+            amount,
+            dex_adapter,
+            min_out,
+        )
+    }
+
+    /// Set an asset's collateral factor (risk manager or admin)
+    pub fn set_asset_params(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        collateral_factor: i128,
+        deposit_enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        set_asset_params(env, caller, asset, collateral_factor, deposit_enabled)
+    }
+
+    /// Admin: set the listing fee, paid in a stable asset, and the share of a new market's
+    /// reserve revenue its proposer earns for a number of months; `None` closes listings
+    pub fn set_listing_config(
+        env: Env,
+        caller: Address,
+        config: Option<ListingConfig>,
+    ) -> Result<(), ProtocolError> {
+        set_listing_config(env, caller, config)
+    }
+
+    /// Query the terms for permissionless listings
+    pub fn get_listing_config(env: Env) -> Option<ListingConfig> {
+        ListingStorage::get_config(&env)
+    }
+
+    /// Propose listing an asset at a collateral factor, escrowing the listing fee; returns
+    /// the proposal id
+    pub fn propose_market_listing(
+        env: Env,
+        proposer: Address,
+        asset: Address,
+        collateral_factor: i128,
+    ) -> Result<u64, ProtocolError> {
+        propose_market_listing(env, proposer, asset, collateral_factor)
+    }
+
+    /// Risk manager: list a proposed asset, keeping the fee and starting the proposer's
+    /// revenue share
+    pub fn approve_market_listing(env: Env, caller: Address, id: u64) -> Result<(), ProtocolError> {
+        approve_market_listing(env, caller, id)
+    }
+
+    /// Risk manager: decline a listing proposal and refund its fee
+    pub fn reject_market_listing(env: Env, caller: Address, id: u64) -> Result<(), ProtocolError> {
+        reject_market_listing(env, caller, id)
+    }
+
+    /// Query a listing proposal by id
+    pub fn get_market_listing(env: Env, id: u64) -> Option<ListingProposal> {
+        ListingStorage::get_proposal(&env, id)
+    }
+
+    /// Claim the proposer's unclaimed share of a listed market's reserve revenue
+    pub fn claim_listing_revenue(
+        env: Env,
+        proposer: Address,
+        asset: Address,
+    ) -> Result<i128, ProtocolError> {
+        claim_listing_revenue(env, proposer, asset)
+    }
+
+    /// Query the proposer revenue share of a listed market
+    pub fn get_listing_revenue_share(env: Env, asset: Address) -> Option<ListingRevenueShare> {
+        ListingStorage::get_share(&env, &asset)
+    }
+
+    /// Admin: let settled, unclaimed supply interest in an asset count as collateral
+    pub fn set_interest_as_collateral(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        set_interest_as_collateral(env, caller, asset, enabled)
+    }
+
+    /// Admin: allow or disallow a DEX adapter that `migrate_collateral` may swap through
+    pub fn set_dex_adapter(
+        env: Env,
+        caller: Address,
+        adapter: Address,
+        allowed: bool,
+    ) -> Result<(), ProtocolError> {
+        set_dex_adapter(env, caller, adapter, allowed)
+    }
+
+    /// Whether `migrate_collateral` may swap through a DEX adapter
+    pub fn is_dex_adapter(env: Env, adapter: Address) -> bool {
+        cross_asset::CrossAssetManager::is_dex_adapter(&env, &adapter)
+    }
+
+    /// Admin: value a listed yield-bearing asset through an adapter contract implementing
+    /// `CollateralAdapter`, which is also notified of every seizure; `None` reverts the
+    /// asset to its price feed
+    pub fn set_collateral_adapter(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        adapter: Option<Address>,
+    ) -> Result<(), ProtocolError> {
+        set_collateral_adapter(env, caller, asset, adapter)
+    }
+
+    /// Query the adapter valuing an asset, if any
+    pub fn get_collateral_adapter(env: Env, asset: Address) -> Option<Address> {
+        CollateralAdapters::get(&env, &asset)
+    }
+
+    /// Admin: only let flash loans, swaps and collateral adapters call out to the listed
+    /// contracts; `None` allows any target
+    pub fn set_external_call_allowlist(
+        env: Env,
+        caller: Address,
+        allowlist: Option<Vec<Address>>,
+    ) -> Result<(), ProtocolError> {
+        set_external_call_allowlist(env, caller, allowlist)
+    }
+
+    /// Query the external call allowlist, if one is set
+    pub fn get_external_call_allowlist(env: Env) -> Option<Vec<Address>> {
+        ExternalCallStorage::get_allowlist(&env)
+    }
+
+    /// Risk manager: record issuer, bridge and canonical status of a listed asset
+    pub fn set_asset_provenance(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        provenance: cross_asset::AssetProvenance,
+    ) -> Result<(), ProtocolError> {
+        set_asset_provenance(env, caller, asset, provenance)
+    }
+
+    /// Get the provenance recorded for an asset
+    pub fn get_asset_provenance(env: Env, asset: Address) -> Option<cross_asset::AssetProvenance> {
+        cross_asset::AssetRegistryStorage::get_provenance_map(&env).get(asset)
+    }
+
+    /// Admin: reduce non-canonical wrappers' collateral factor by `haircut` (scaled by 1e8)
+    pub fn set_wrapper_haircut(
+        env: Env,
+        caller: Address,
+        haircut: i128,
+    ) -> Result<(), ProtocolError> {
+        set_wrapper_haircut(env, caller, haircut)
+    }
+
+    /// List every listed token representing a logical asset (canonical and bridged)
+    pub fn get_asset_wrappers(env: Env, logical_asset: Symbol) -> Vec<Address> {
+        cross_asset::CrossAssetManager::get_wrappers(&env, &logical_asset)
+    }
+
+    /// Admin: set the oracle heartbeat after which asset prices count as stale
+    pub fn set_oracle_heartbeat(env: Env, caller: Address, ttl: u64) -> Result<(), ProtocolError> {
+        set_oracle_heartbeat(env, caller, ttl)
+    }
+
+    /// Admin: set the default max deviation between consecutive synced prices
+    pub fn set_oracle_max_deviation(
+        env: Env,
+        caller: Address,
+        bps: i128,
+    ) -> Result<(), ProtocolError> {
+        set_oracle_max_deviation(env, caller, bps)
+    }
+
+    /// Admin: give an asset its own oracle heartbeat, max deviation and fallback price
+    pub fn set_asset_oracle_config(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        config: Option<AssetOracleConfig>,
+    ) -> Result<(), ProtocolError> {
+        set_asset_oracle_config(env, caller, asset, config)
+    }
+
+    /// Oracle settings in force for an asset, after applying the global defaults
+    pub fn get_asset_oracle_config(env: Env, asset: Address) -> AssetOracleConfig {
+        Oracle::effective_config(&env, &asset)
+    }
+
+    /// Whether an asset's price is older than its oracle heartbeat
+    pub fn is_price_stale(env: Env, asset: Address) -> bool {
+        cross_asset::CrossAssetManager::is_price_stale(&env, &asset)
+    }
+
+    /// Admin: accept a fee-on-transfer or rebasing asset, crediting only what is received
+    pub fn set_fee_on_transfer(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        allowed: bool,
+    ) -> Result<(), ProtocolError> {
+        set_fee_on_transfer(env, caller, asset, allowed)
+    }
+
+    /// Set an asset's price (admin only)
+    pub fn set_asset_price(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        price: i128,
+    ) -> Result<(), ProtocolError> {
+        set_asset_price(env, caller, asset, price)
+    }
+
+    /// Admin: register or reweight an oracle feed exposing `get_price(asset)` and,
+    /// optionally, `get_confidence(asset)`
+    pub fn set_oracle_source(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        source: Address,
+        weight: i128,
+    ) -> Result<(), ProtocolError> {
+        set_oracle_source(env, caller, asset, source, weight)
+    }
+
+    /// Admin: set the feed an asset fails over to when its registered sources are stale or
+    /// out of bounds, ahead of the static fallback price; `None` removes it
+    pub fn set_secondary_oracle(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        source: Option<Address>,
+    ) -> Result<(), ProtocolError> {
+        set_secondary_oracle(env, caller, asset, source)
+    }
+
+    /// Query an asset's secondary oracle feed, if any
+    pub fn get_secondary_oracle(env: Env, asset: Address) -> Option<Address> {
+        OracleStorage::get_secondary(&env, &asset).map(|source| source.addr)
+    }
+
+    /// Pull an asset's aggregated price and widest reported confidence interval from its
+    /// oracle feeds, failing over to its secondary feed and then its fallback price, and
+    /// emit `price_served` naming the tier used; returns (price, confidence)
+    pub fn sync_asset_price(env: Env, asset: Address) -> Result<(i128, i128), ProtocolError> {
+        sync_asset_price(env, asset)
+    }
+
+    /// Admin: accept signed price payloads from the given provider keys, charging the
+    /// update fee per payload, or stop accepting them with `None`
+    pub fn set_price_push_config(
+        env: Env,
+        caller: Address,
+        config: Option<PricePushConfig>,
+    ) -> Result<(), ProtocolError> {
+        set_price_push_config(env, caller, config)
+    }
+
+    /// Provider keys and update fee for signed price payloads, if accepted
+    pub fn get_price_push_config(env: Env) -> Option<PricePushConfig> {
+        PricePushStorage::get_config(&env)
+    }
 
-pub fn unfreeze_user(env: Env, caller: String, user: Address) -> Result<(), ProtocolError> {
-    let _guard = ReentrancyScope::enter(&env)?;
-    let caller_addr = Address::from_string(&caller);
-    UserManager::unfreeze_user(&env, &caller_addr, &user)
-}
+    /// Bring provider-signed prices on-chain, e.g. ahead of a borrow in the same
+    /// transaction; `payer` pays the update fee for every payload. Returns the fee charged.
+    pub fn update_price_feeds(
+        env: Env,
+        payer: Address,
+        payloads: Vec<PricePayload>,
+    ) -> Result<i128, ProtocolError> {
+        update_price_feeds(env, payer, payloads)
+    }
 
-pub fn get_user_profile(env: Env, user: Address) -> Result<UserProfile, ProtocolError> {
-    Ok(UserManager::get_profile(&env, &user))
-}
+    /// Admin: subtract `k` confidence intervals (scaled by 1e8) from collateral prices when
+    /// computing borrowing power; liquidation checks always use the undiscounted price
+    pub fn set_confidence_multiplier(
+        env: Env,
+        caller: Address,
+        k: i128,
+    ) -> Result<(), ProtocolError> {
+        set_confidence_multiplier(env, caller, k)
+    }
 
-#[contractimpl]
-impl Contract {
-    /// Initializes the contract and sets the admin address
-    pub fn initialize(env: Env, admin: String) -> Result<(), ProtocolError> {
-        let _guard = ReentrancyScope::enter(&env)?;
-        let admin_addr = Address::from_string(&admin);
-        if env
-            .storage()
-            .instance()
-            .has(&ProtocolConfig::admin_key(&env))
-        {
-            return Err(ProtocolError::AlreadyInitialized);
-        }
-        ProtocolConfig::set_admin(&env, &admin_addr);
-        UserManager::bootstrap_admin(&env, &admin_addr);
+    /// Get the confidence interval last reported for an asset's price (0 when none)
+    pub fn get_asset_confidence(env: Env, asset: Address) -> i128 {
+        cross_asset::AssetRegistryStorage::get_confidences(&env)
+            .get(asset)
+            .unwrap_or(0)
+    }
 
-        // Initialize interest rate system with default configuration
-        let config = InterestRateConfig::default();
-        InterestRateStorage::save_config(&env, &config);
+    /// Set risk parameters (admin only)
+    pub fn set_risk_params(
+        env: Env,
+        caller: Address,
+        close_factor: i128,
+        liquidation_incentive: i128,
+    ) -> Result<(), ProtocolError> {
+        set_risk_params(env, caller, close_factor, liquidation_incentive)
+    }
 
-        let state = InterestRateState::initial();
-        InterestRateStorage::save_state(&env, &state);
+    /// Cap unpaid borrow interest at `cap_bps` of principal; 0 removes the cap
+    pub fn set_interest_cap(env: Env, caller: Address, cap_bps: i128) -> Result<(), ProtocolError> {
+        set_interest_cap(env, caller, cap_bps)
+    }
 
-        // Initialize risk management system with default configuration
-        let risk_config = RiskConfig::default();
-        RiskConfigStorage::save(&env, &risk_config);
+    /// Admin: charge primary collateral above a per-user floor an annual storage fee that
+    /// accrues into the reserves, e.g. while a market winds down; `None` stops the fee
+    pub fn set_supply_fee(
+        env: Env,
+        caller: Address,
+        config: Option<SupplyFeeConfig>,
+    ) -> Result<(), ProtocolError> {
+        set_supply_fee(env, caller, config)
+    }
 
-        Ok(())
+    /// Storage fee charged on idle collateral, if any
+    pub fn get_supply_fee(env: Env) -> Option<SupplyFeeConfig> {
+        SupplyFeeStorage::get(&env)
     }
 
-    /// Set the minimum collateral ratio (admin only)
-    pub fn set_min_collateral_ratio(
+    /// Admin: set the base borrow rate (1e8 scale)
+    pub fn set_base_rate(env: Env, caller: Address, base_rate: i128) -> Result<(), ProtocolError> {
+        set_base_rate(env, caller, base_rate)
+    }
+
+    /// Admin: set the rate slope below the kink (1e8 scale)
+    pub fn set_multiplier(
         env: Env,
-        caller: String,
-        ratio: i128,
+        caller: Address,
+        multiplier: i128,
     ) -> Result<(), ProtocolError> {
-        let caller_addr = Address::from_string(&caller);
-        ProtocolConfig::set_min_collateral_ratio(&env, &caller_addr, ratio)?;
-        Ok(())
+        set_multiplier(env, caller, multiplier)
     }
 
-    /// Deposit collateral into the protocol
-    pub fn deposit_collateral(
+    /// Admin: set the share of interest kept as protocol reserves (1e8 scale)
+    pub fn set_reserve_factor(
         env: Env,
-        depositor: String,
-        amount: i128,
+        caller: Address,
+        reserve_factor: i128,
     ) -> Result<(), ProtocolError> {
-        deposit_collateral(env, depositor, amount)
+        set_reserve_factor(env, caller, reserve_factor)
     }
 
-    /// Borrow assets from the protocol
-    pub fn borrow(env: Env, borrower: String, amount: i128) -> Result<(), ProtocolError> {
-        borrow(env, borrower, amount)
+    /// Admin: open `asset`'s market in the bootstrapping phase (deposits only) until it
+    /// holds `tvl_threshold` or `start_time` passes; zero disables either condition
+    pub fn start_market_bootstrap(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        tvl_threshold: i128,
+        start_time: u64,
+    ) -> Result<(), ProtocolError> {
+        start_market_bootstrap(env, caller, asset, tvl_threshold, start_time)
     }
 
-    /// Repay borrowed assets
-    pub fn repay(env: Env, repayer: String, amount: i128) -> Result<(), ProtocolError> {
-        repay(env, repayer, amount)
+    /// Query whether `asset`'s market is still bootstrapping
+    pub fn get_market_phase(env: Env, asset: Address) -> MarketPhase {
+        BorrowLimits::market_phase(&env, &asset)
     }
 
-    /// Withdraw collateral from the protocol
-    pub fn withdraw(env: Env, withdrawer: String, amount: i128) -> Result<(), ProtocolError> {
-        withdraw(env, withdrawer, amount)
+    /// Query the activation conditions of a bootstrapping market
+    pub fn get_market_bootstrap(env: Env, asset: Address) -> Option<BootstrapConfig> {
+        limits::LimitsStorage::get_bootstraps(&env).get(asset)
     }
 
-    /// Liquidate an undercollateralized position
-    pub fn liquidate(
+    /// Admin: once an account deposits, borrows or withdraws `asset`, refuse its further
+    /// borrows and withdrawals until the next ledger
+    pub fn set_same_ledger_guard(
         env: Env,
-        liquidator: String,
-        user: String,
-        amount: i128,
+        caller: Address,
+        asset: Address,
+        enabled: bool,
     ) -> Result<(), ProtocolError> {
-        liquidate(env, liquidator, user, amount)
+        set_same_ledger_guard(env, caller, asset, enabled)
     }
 
-    /// Get user position
-    pub fn get_position(env: Env, user: String) -> Result<(i128, i128, i128), ProtocolError> {
-        get_position(env, user)
+    /// Query whether same-ledger action restrictions apply to `asset`
+    pub fn is_same_ledger_guarded(env: Env, asset: Address) -> bool {
+        BorrowLimits::is_same_ledger_guarded(&env, &asset)
     }
 
-    /// Set risk parameters (admin only)
-    pub fn set_risk_params(
+    /// Admin: cap relative changes to rate and collateral parameters per window (0 disables)
+    pub fn set_param_change_limit(
         env: Env,
-        caller: String,
-        close_factor: i128,
-        liquidation_incentive: i128,
+        caller: Address,
+        max_change_bps: i128,
+        window: u64,
     ) -> Result<(), ProtocolError> {
-        set_risk_params(env, caller, close_factor, liquidation_incentive)
+        set_param_change_limit(env, caller, max_change_bps, window)
+    }
+
+    /// Query the parameter change guardrail
+    pub fn get_param_change_limit(env: Env) -> ParamChangeLimit {
+        limits::LimitsStorage::get_change_limit(&env)
+    }
+
+    /// Admin: set the half-life in seconds of the utilization EWMA driving rates (0 = raw)
+    pub fn set_utilization_half_life(
+        env: Env,
+        caller: Address,
+        half_life: u64,
+    ) -> Result<(), ProtocolError> {
+        set_utilization_half_life(env, caller, half_life)
+    }
+
+    /// Bring up to MAX_ACCRUAL_BATCH positions current against the global index, e.g.
+    /// before snapshots or migrations; returns how many positions were accrued
+    pub fn accrue_positions(
+        env: Env,
+        asset: Address,
+        users: Vec<Address>,
+    ) -> Result<u32, ProtocolError> {
+        accrue_positions(env, asset, users)
+    }
+
+    /// Admin: set the staking token, the stake earning the full discount, the discount cap
+    /// and the lock period
+    pub fn set_staking_config(
+        env: Env,
+        caller: Address,
+        config: StakingConfig,
+    ) -> Result<(), ProtocolError> {
+        set_staking_config(env, caller, config)
+    }
+
+    /// Stake protocol tokens for a borrow rate discount; restarts the lock period
+    pub fn stake(env: Env, user: Address, amount: i128) -> Result<StakePosition, ProtocolError> {
+        stake(env, user, amount)
+    }
+
+    /// Unstake protocol tokens once the lock period has passed
+    pub fn unstake(env: Env, user: Address, amount: i128) -> Result<StakePosition, ProtocolError> {
+        unstake(env, user, amount)
+    }
+
+    /// Query a user's stake
+    pub fn get_stake(env: Env, user: Address) -> StakePosition {
+        StakingStorage::get_stake(&env, &user)
+    }
+
+    /// Query the staking configuration
+    pub fn get_staking_config(env: Env) -> Option<StakingConfig> {
+        StakingStorage::get_config(&env)
+    }
+
+    /// Query the borrow rate a user accrues at after their staking discount (scaled by 1e8)
+    pub fn get_user_borrow_rate(
+        env: Env,
+        user: Address,
+        asset: Address,
+    ) -> Result<i128, ProtocolError> {
+        get_user_borrow_rate(env, user, asset)
+    }
+
+    /// Query the signed net yield of a user's portfolio on their net worth (scaled by 1e8),
+    /// combining supply and discounted borrow rates with liquidity incentives
+    pub fn get_user_net_apy(env: Env, user: Address) -> Result<i128, ProtocolError> {
+        NetApy::for_user(&env, &user)
+    }
+
+    /// Query raw and time-weighted utilization and the current borrow rate
+    pub fn get_utilization_metrics(env: Env) -> UtilizationMetrics {
+        get_utilization_metrics(env)
+    }
+
+    /// Query a market's supply, borrows, liquidity, reserves, rates and liquidity index in
+    /// the asset's own units
+    pub fn get_market_data(env: Env, asset: Address) -> Result<MarketData, ProtocolError> {
+        get_market_data(env, asset)
+    }
+
+    /// Convert `amount`, held with `decimals` decimal places, into `asset`'s smallest unit,
+    /// the unit every amount parameter takes. A 7-decimal value sent to a 6-decimal market
+    /// must end in a zero digit or it is refused with `AmountPrecisionLoss`; values that
+    /// overflow after scaling up fail with `AmountOutOfRange`.
+    pub fn normalize_amount(
+        env: Env,
+        asset: Address,
+        amount: i128,
+        decimals: u32,
+    ) -> Result<i128, ProtocolError> {
+        Units::normalize_amount(&env, &asset, amount, decimals)
     }
 
     /// Set pause switches (admin only)
     pub fn set_pause_switches(
         env: Env,
-        caller: String,
+        caller: Address,
         pause_borrow: bool,
         pause_deposit: bool,
         pause_withdraw: bool,
@@ -3294,6 +7280,53 @@ impl Contract {
         )
     }
 
+    /// Set pause switches (admin only) with a reason code and an optional auto-expiry
+    /// after `duration` seconds (0 = until unpaused)
+    #[allow(clippy::too_many_arguments)]
+    pub fn pause_with_reason(
+        env: Env,
+        caller: Address,
+        pause_borrow: bool,
+        pause_deposit: bool,
+        pause_withdraw: bool,
+        pause_liquidate: bool,
+        reason_code: u32,
+        duration: u64,
+    ) -> Result<(), ProtocolError> {
+        pause_with_reason(
+            env,
+            caller,
+            pause_borrow,
+            pause_deposit,
+            pause_withdraw,
+            pause_liquidate,
+            reason_code,
+            duration,
+        )
+    }
+
+    /// Who paused an asset's market, why and until when; `None` when it is not paused
+    pub fn get_pause_info(env: Env, asset: Address) -> Option<PauseInfo> {
+        get_pause_info(env, asset)
+    }
+
+    /// Admin or emergency manager: pause deposits, withdrawals, borrows or liquidations in
+    /// one asset's market without touching the others, e.g. stop a risky borrow side while
+    /// its suppliers can still leave. Repayment has no switch and is never paused.
+    pub fn set_market_pauses(
+        env: Env,
+        caller: Address,
+        asset: Address,
+        pauses: MarketPauses,
+    ) -> Result<(), ProtocolError> {
+        set_market_pauses(env, caller, asset, pauses)
+    }
+
+    /// Query the pause switches of one asset's market
+    pub fn get_market_pauses(env: Env, asset: Address) -> MarketPauses {
+        MarketPauseStorage::get(&env, &asset)
+    }
+
     /// Get protocol parameters
     pub fn get_protocol_params(
         env: Env,
@@ -3315,8 +7348,8 @@ impl Contract {
 
     pub fn set_emergency_manager(
         env: Env,
-        caller: String,
-        manager: String,
+        caller: Address,
+        manager: Address,
         enabled: bool,
     ) -> Result<(), ProtocolError> {
         set_emergency_manager(env, caller, manager, enabled)
@@ -3324,27 +7357,89 @@ impl Contract {
 
     pub fn trigger_emergency_pause(
         env: Env,
-        caller: String,
+        caller: Address,
         reason: Option<String>,
     ) -> Result<(), ProtocolError> {
         trigger_emergency_pause(env, caller, reason)
     }
 
+    /// Emergency manager: pause everything under a new incident id. The pause lifts by
+    /// itself unless the admin confirms the incident within 48 hours. Each emergency
+    /// manager can do this once until the admin resets its break.
+    pub fn circuit_break(env: Env, caller: Address) -> Result<u64, ProtocolError> {
+        circuit_break(env, caller)
+    }
+
+    /// Admin: confirm a circuit break so the protocol stays paused until resumed
+    pub fn confirm_circuit_break(
+        env: Env,
+        caller: Address,
+        incident_id: u64,
+    ) -> Result<(), ProtocolError> {
+        confirm_circuit_break(env, caller, incident_id)
+    }
+
+    /// The circuit break on record with its incident id and confirmation deadline
+    pub fn get_circuit_break(env: Env) -> Option<CircuitBreak> {
+        CircuitBreaker::get(&env)
+    }
+
+    /// Admin: let an emergency manager that used its circuit break trip one again
+    pub fn reset_circuit_break(
+        env: Env,
+        caller: Address,
+        guardian: Address,
+    ) -> Result<(), ProtocolError> {
+        reset_circuit_break(env, caller, guardian)
+    }
+
+    /// Whether an emergency manager has used its circuit break
+    pub fn is_circuit_break_used(env: Env, guardian: Address) -> bool {
+        CircuitBreaker::is_used(&env, &guardian)
+    }
+
     pub fn enter_recovery_mode(
         env: Env,
-        caller: String,
+        caller: Address,
         plan: Option<String>,
     ) -> Result<(), ProtocolError> {
         enter_recovery_mode(env, caller, plan)
     }
 
-    pub fn resume_operations(env: Env, caller: String) -> Result<(), ProtocolError> {
+    pub fn resume_operations(env: Env, caller: Address) -> Result<(), ProtocolError> {
         resume_operations(env, caller)
     }
 
+    /// Admin: seconds the protocol must stay paused before users may redeem collateral
+    /// directly; zero disables emergency redemptions
+    pub fn set_emergency_redeem_delay(
+        env: Env,
+        caller: Address,
+        delay: u64,
+    ) -> Result<(), ProtocolError> {
+        set_emergency_redeem_delay(env, caller, delay)
+    }
+
+    /// Time from which emergency redemptions are open, if the protocol is paused and a
+    /// delay is configured
+    pub fn get_emergency_redeem_opens_at(env: Env) -> Option<u64> {
+        EmergencyRedemption::available_at(&env)
+    }
+
+    /// Withdraw all of the caller's collateral in `asset` once the protocol has been
+    /// paused past the redemption delay, without oracle or ratio checks. Primary
+    /// collateral is netted against debt and paid pro rata to what the pool still holds.
+    pub fn emergency_redeem(
+        env: Env,
+        user: Address,
+        asset: Address,
+    ) -> Result<i128, ProtocolError> {
+        emergency_redeem(env, user, asset)
+    }
+
     pub fn record_recovery_step(
         env: Env,
-        caller: String,
+        caller: Address,
         step: String,
     ) -> Result<(), ProtocolError> {
         record_recovery_step(env, caller, step)
@@ -3352,20 +7447,20 @@ impl Contract {
 
     pub fn queue_emergency_param_update(
         env: Env,
-        caller: String,
+        caller: Address,
         parameter: Symbol,
         value: i128,
     ) -> Result<(), ProtocolError> {
         queue_emergency_param_update(env, caller, parameter, value)
     }
 
-    pub fn apply_emergency_param_updates(env: Env, caller: String) -> Result<(), ProtocolError> {
+    pub fn apply_emergency_param_updates(env: Env, caller: Address) -> Result<(), ProtocolError> {
         apply_emergency_param_updates(env, caller)
     }
 
     pub fn adjust_emergency_fund(
         env: Env,
-        caller: String,
+        caller: Address,
         token: Option<Address>,
         delta: i128,
         reserve_delta: i128,
@@ -3373,6 +7468,111 @@ impl Contract {
         adjust_emergency_fund(env, caller, token, delta, reserve_delta)
     }
 
+    /// Admin: set who may propose and confirm emergency reserve withdrawals, and how many
+    /// confirmations a withdrawal needs
+    pub fn set_emergency_signers(
+        env: Env,
+        caller: Address,
+        signers: Vec<Address>,
+        threshold: u32,
+    ) -> Result<(), ProtocolError> {
+        set_emergency_signers(env, caller, signers, threshold)
+    }
+
+    /// Signer: propose paying treasury reserves of `asset` to `recipient` under an incident
+    /// reference; counts as the first confirmation. Returns the withdrawal id.
+    pub fn propose_emergency_withdrawal(
+        env: Env,
+        signer: Address,
+        asset: Address,
+        amount: i128,
+        recipient: Address,
+        incident_ref: String,
+    ) -> Result<u64, ProtocolError> {
+        propose_emergency_withdrawal(env, signer, asset, amount, recipient, incident_ref)
+    }
+
+    /// Signer: confirm a withdrawal; at the threshold its 24 hour delay starts
+    pub fn confirm_emergency_withdrawal(
+        env: Env,
+        signer: Address,
+        id: u64,
+    ) -> Result<(), ProtocolError> {
+        confirm_emergency_withdrawal(env, signer, id)
+    }
+
+    /// Guardian: veto a withdrawal before it is executed
+    pub fn veto_emergency_withdrawal(
+        env: Env,
+        guardian: Address,
+        id: u64,
+    ) -> Result<(), ProtocolError> {
+        veto_emergency_withdrawal(env, guardian, id)
+    }
+
+    /// Pay out a confirmed withdrawal once its delay has passed; returns the amount paid
+    pub fn execute_emergency_withdrawal(env: Env, id: u64) -> Result<i128, ProtocolError> {
+        execute_emergency_withdrawal(env, id)
+    }
+
+    /// Get an emergency withdrawal by id
+    pub fn get_emergency_withdrawal(env: Env, id: u64) -> Option<EmergencyWithdrawal> {
+        EmergencyWithdrawStorage::get(&env, id)
+    }
+
+    /// Admin: cap on insurance funds spent by `cover_position` per incident
+    pub fn set_cover_cap(env: Env, caller: Address, cap: i128) -> Result<(), ProtocolError> {
+        set_cover_cap(env, caller, cap)
+    }
+
+    /// Admin: auction protocol tokens to cover `user`'s bad debt once the insurance fund
+    /// cannot; the contract must be allowed to mint `params.protocol_token`
+    pub fn start_debt_auction(
+        env: Env,
+        caller: Address,
+        user: Address,
+        params: DebtAuctionParams,
+    ) -> Result<u64, ProtocolError> {
+        start_debt_auction(env, caller, user, params)
+    }
+
+    /// Keeper: buy up to `amount` protocol tokens (capped at the lot size) at the current
+    /// auction price, failing if it exceeds `max_price`; returns the tokens minted
+    pub fn bid_debt_auction(
+        env: Env,
+        bidder: Address,
+        auction_id: u64,
+        amount: i128,
+        max_price: i128,
+    ) -> Result<i128, ProtocolError> {
+        bid_debt_auction(env, bidder, auction_id, amount, max_price)
+    }
+
+    /// Query a debt auction
+    pub fn get_debt_auction(env: Env, auction_id: u64) -> Option<DebtAuction> {
+        auction::AuctionStorage::get(&env, auction_id)
+    }
+
+    /// Query the current price of a debt auction (debt asset per protocol token, 1e8 scale)
+    pub fn get_debt_auction_price(env: Env, auction_id: u64) -> Result<i128, ProtocolError> {
+        let auction =
+            auction::AuctionStorage::get(&env, auction_id).ok_or(ProtocolError::NotFound)?;
+        Ok(DebtAuctions::price(&env, &auction))
+    }
+
+    /// Guardian: repay a user's debt from the unreserved insurance fund during an incident,
+    /// with a public reason recorded in the emitted event
+    pub fn cover_position(
+        env: Env,
+        caller: Address,
+        user: Address,
+        asset: Address,
+        amount: i128,
+        reason: String,
+    ) -> Result<i128, ProtocolError> {
+        cover_position(env, caller, user, asset, amount, reason)
+    }
+
     pub fn get_emergency_state(env: Env) -> Result<EmergencyState, ProtocolError> {
         get_emergency_state(env)
     }
@@ -3381,6 +7581,13 @@ impl Contract {
         get_event_summary(env)
     }
 
+    /// Query the sequence number of the last event emitted. Every event carries its
+    /// number as the last topic, so a gap between consecutive events tells an indexer
+    /// it missed some and should resume from an earlier cursor or reload state.
+    pub fn get_last_event_seq(env: Env) -> u64 {
+        EventSeq::last(&env)
+    }
+
     pub fn get_event_aggregates(env: Env) -> Result<Map<Symbol, EventAggregate>, ProtocolError> {
         get_event_aggregates(env)
     }
@@ -3399,7 +7606,7 @@ impl Contract {
 
     pub fn register_token_asset(
         env: Env,
-        caller: String,
+        caller: Address,
         key: Symbol,
         token: Address,
     ) -> Result<(), ProtocolError> {
@@ -3408,7 +7615,7 @@ impl Contract {
 
     pub fn set_primary_asset(
         env: Env,
-        caller: String,
+        caller: Address,
         token: Address,
     ) -> Result<(), ProtocolError> {
         set_primary_asset(env, caller, token)
@@ -3420,7 +7627,7 @@ impl Contract {
 
     pub fn set_user_role(
         env: Env,
-        caller: String,
+        caller: Address,
         user: Address,
         role: UserRole,
     ) -> Result<(), ProtocolError> {
@@ -3429,7 +7636,7 @@ impl Contract {
 
     pub fn set_user_verification(
         env: Env,
-        caller: String,
+        caller: Address,
         user: Address,
         status: VerificationStatus,
     ) -> Result<(), ProtocolError> {
@@ -3438,7 +7645,7 @@ impl Contract {
 
     pub fn set_user_limits(
         env: Env,
-        caller: String,
+        caller: Address,
         user: Address,
         max_deposit: i128,
         max_borrow: i128,
@@ -3456,11 +7663,24 @@ impl Contract {
         )
     }
 
-    pub fn freeze_user(env: Env, caller: String, user: Address) -> Result<(), ProtocolError> {
+    pub fn freeze_user(env: Env, caller: Address, user: Address) -> Result<(), ProtocolError> {
         freeze_user(env, caller, user)
     }
 
-    pub fn unfreeze_user(env: Env, caller: String, user: Address) -> Result<(), ProtocolError> {
+    /// Restrict a user proportionally: `WithdrawOnly` leaves repaying and withdrawing open,
+    /// `Halted` blocks everything. A non-zero `expires_at` lifts the freeze at that time
+    /// unless it is renewed on review.
+    pub fn set_freeze_level(
+        env: Env,
+        caller: Address,
+        user: Address,
+        level: FreezeLevel,
+        expires_at: u64,
+    ) -> Result<(), ProtocolError> {
+        set_freeze_level(env, caller, user, level, expires_at)
+    }
+
+    pub fn unfreeze_user(env: Env, caller: Address, user: Address) -> Result<(), ProtocolError> {
         unfreeze_user(env, caller, user)
     }
 
@@ -3468,14 +7688,36 @@ impl Contract {
         get_user_profile(env, user)
     }
 
+    pub fn flag_suspicious_activity(
+        env: Env,
+        caller: Address,
+        user: Address,
+        severity: AlertSeverity,
+        reason: Symbol,
+    ) -> Result<(), ProtocolError> {
+        flag_suspicious_activity(env, caller, user, severity, reason)
+    }
+
+    pub fn get_compliance_summary(env: Env) -> Result<ComplianceSummary, ProtocolError> {
+        get_compliance_summary(env)
+    }
+
     // Analytics and Reporting Functions
     pub fn get_protocol_report(env: Env) -> Result<analytics::ProtocolReport, ProtocolError> {
         analytics::AnalyticsModule::get_protocol_report(&env)
     }
 
-    pub fn get_user_report(env: Env, user: String) -> Result<analytics::UserReport, ProtocolError> {
-        let user_addr = Address::from_string(&user);
-        analytics::AnalyticsModule::get_user_report(&env, &user_addr)
+    /// Daily checkpoints of TVL, total debt, reserves and users, oldest first; `limit`
+    /// keeps only the latest ones, zero returns the whole retained history
+    pub fn get_protocol_history(env: Env, limit: u32) -> Vec<analytics::ProtocolCheckpoint> {
+        analytics::AnalyticsModule::get_protocol_history(&env, limit)
+    }
+
+    pub fn get_user_report(
+        env: Env,
+        user: Address,
+    ) -> Result<analytics::UserReport, ProtocolError> {
+        analytics::AnalyticsModule::get_user_report(&env, &user)
     }
 
     pub fn get_asset_report(
@@ -3497,16 +7739,119 @@ impl Contract {
         analytics::AnalyticsModule::update_performance_metrics(&env, processing_time, success)
     }
 
-    pub fn record_activity(
+    /// Open a governance proposal that would switch the protocol to `config`
+    pub fn propose_configuration(
         env: Env,
-        user: String,
-        activity_type: String,
-        amount: i128,
-        asset: Option<Address>,
-    ) -> Result<(), ProtocolError> {
-        let user_addr = Address::from_string(&user);
-        // For now, we'll use a placeholder string since soroban_sdk::String doesn't implement Display
-        // In a real implementation, you might want to modify the analytics module to accept soroban_sdk::String
-        analytics::AnalyticsModule::record_activity(&env, &user_addr, "activity", amount, asset)
+        proposer: Address,
+        title: String,
+        voting_period_secs: u64,
+        config: ProtocolConfiguration,
+    ) -> Result<u64, ProtocolError> {
+        propose_configuration(env, proposer, title, voting_period_secs, config)
+    }
+
+    /// Query the rate and risk configuration the core flows currently use
+    pub fn get_configuration(env: Env) -> ProtocolConfiguration {
+        ConfigManager::current(&env)
+    }
+
+    /// Execute a proposal once its timelock has passed; a configuration it carries is
+    /// written into the live rate and risk parameters in the same call
+    pub fn execute_proposal(env: Env, proposal_id: u64) -> Result<Proposal, ProtocolError> {
+        execute_proposal(env, proposal_id)
+    }
+
+    /// Admin: activate `config` as a new configuration version, returning its number
+    pub fn apply_configuration(
+        env: Env,
+        caller: Address,
+        config: ProtocolConfiguration,
+    ) -> Result<u32, ProtocolError> {
+        apply_configuration(env, caller, config)
+    }
+
+    /// Admin: reactivate historic configuration `version` as a new version after
+    /// re-validating it
+    pub fn rollback_configuration(
+        env: Env,
+        caller: Address,
+        version: u32,
+    ) -> Result<u32, ProtocolError> {
+        rollback_configuration(env, caller, version)
+    }
+
+    /// Query a configuration version and when it was activated
+    pub fn get_configuration_version(env: Env, version: u32) -> Option<ConfigVersion> {
+        ConfigStorage::get_version(&env, version)
+    }
+
+    /// Query the number of the active configuration version (0 before any activation)
+    pub fn get_latest_configuration_version(env: Env) -> u32 {
+        ConfigStorage::latest_version(&env)
+    }
+
+    /// Query the fields a configuration proposal would change as
+    /// (field_name, current_value, proposed_value)
+    pub fn get_proposal_diff(
+        env: Env,
+        proposal_id: u64,
+    ) -> Result<Vec<(String, String, String)>, ProtocolError> {
+        ConfigManager::diff(&env, proposal_id)
+    }
+
+    /// Page through proposals in creation order (`limit` capped at MAX_PROPOSAL_PAGE)
+    pub fn get_proposals(env: Env, offset: u64, limit: u32) -> Vec<Proposal> {
+        Governance::get_proposals(&env, offset, limit)
+    }
+
+    /// Page through proposals currently in the given status
+    pub fn get_proposals_by_status(
+        env: Env,
+        status: ProposalStatus,
+        offset: u32,
+        limit: u32,
+    ) -> Vec<Proposal> {
+        Governance::get_proposals_by_status(&env, status, offset, limit)
+    }
+}
+
+/// Interest model stress entrypoints for test deployments only
+#[cfg(feature = "testutils")]
+#[contractimpl]
+impl Contract {
+    /// Admin: accrue the pool and the listed positions as if `seconds` had passed
+    pub fn stress_fast_forward(
+        env: Env,
+        caller: Address,
+        seconds: u64,
+        users: Vec<Address>,
+    ) -> Result<InterestRateState, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        caller.require_auth();
+        StressTools::fast_forward(&env, &caller, seconds, &users)
+    }
+
+    /// Admin: force pool utilization (scaled by 1e8) and reprice
+    pub fn stress_set_utilization(
+        env: Env,
+        caller: Address,
+        utilization: i128,
+    ) -> Result<InterestRateState, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        caller.require_auth();
+        StressTools::set_utilization(&env, &caller, utilization)
+    }
+
+    /// Admin: step through rate configs, accruing `step` seconds under each, and return
+    /// the pool state after every step
+    pub fn stress_replay_rates(
+        env: Env,
+        caller: Address,
+        configs: Vec<InterestRateConfig>,
+        step: u64,
+    ) -> Result<Vec<InterestRateState>, ProtocolError> {
+        let _guard = ReentrancyScope::enter(&env)?;
+        caller.require_auth();
+        StressTools::replay_rates(&env, &caller, &configs, step)
     }
 }
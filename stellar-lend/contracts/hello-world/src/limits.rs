@@ -0,0 +1,775 @@
+//! Limits module for StellarLend protocol
+//! Protocol-wide debt ceiling, a utilization kill-switch that freezes new borrows
+//! while the pool is close to being drained, per-asset position size caps and
+//! per-address supply caps set for compliance, guardrails on how quickly risk parameters can change, a bootstrapping phase
+//! that keeps borrowing closed on new markets until they have enough liquidity and
+//! same-ledger action restrictions on thin markets, a circuit breaker that pauses
+//! borrowing for a cooldown when utilization spikes within a single ledger, and a fixed
+//! conservative borrow rate for markets whose liquidity is still too thin to price
+
+use crate::cross_asset::{CrossAssetManager, CrossStateHelper};
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{
+    InterestRateStorage, ProtocolConfig, ProtocolError, StateHelper, TokenRegistry, UserManager,
+};
+use soroban_sdk::{contracttype, token::TokenClient, Address, Env, Map, Symbol};
+
+/// Protocol-wide borrowing limits; a zero value disables the corresponding limit
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct ProtocolParameters {
+    /// Cap on total outstanding debt across all assets, in primary asset units
+    pub debt_ceiling: i128,
+    /// Utilization (scaled by 1e8) at or above which new borrows are frozen
+    pub freeze_utilization: i128,
+    /// Utilization (scaled by 1e8) below which a freeze lifts automatically
+    pub resume_utilization: i128,
+}
+
+/// Guardrail on how far risk parameters may move within a rolling window; a zero
+/// `max_change_bps` disables it
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct ParamChangeLimit {
+    /// Largest relative change from the value at the start of the window (basis points)
+    pub max_change_bps: i128,
+    /// Window length in seconds
+    pub window: u64,
+}
+
+/// Circuit breaker on single-ledger utilization jumps, the signature of flash borrow
+/// patterns
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UtilizationBreaker {
+    /// Largest utilization increase within one ledger (scaled by 1e8, so 10000000 = 10
+    /// percentage points)
+    pub max_jump: i128,
+    /// Seconds borrowing stays paused once the breaker trips
+    pub cooldown: u64,
+}
+
+/// Fixed borrow rate used instead of the kinked model while a market's supplied liquidity
+/// is below `min_liquidity`, so utilization on dust supply cannot be gamed
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ThinMarketRate {
+    pub min_liquidity: i128,
+    /// Annual borrow rate (scaled by 1e8)
+    pub fixed_rate: i128,
+}
+
+/// Utilization at the start of the current ledger and after the latest change
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct UtilizationTracker {
+    pub ledger: u32,
+    pub baseline: i128,
+    pub last: i128,
+}
+
+/// Lifecycle phase of a market
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum MarketPhase {
+    /// Deposits and withdrawals only; borrowing is closed
+    Bootstrapping,
+    Active,
+}
+
+/// Conditions under which a bootstrapping market opens for borrowing; whichever is met
+/// first wins and a zero value disables that condition
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BootstrapConfig {
+    /// Liquidity supplied to the market, in the asset's units
+    pub tvl_threshold: i128,
+    /// Timestamp after which borrowing opens regardless of liquidity
+    pub start_time: u64,
+}
+
+/// Storage helper for protocol parameters and the borrow freeze flag
+pub struct LimitsStorage;
+
+impl LimitsStorage {
+    fn params_key() -> DataKey {
+        DataKey::ProtocolParams
+    }
+
+    fn frozen_key() -> DataKey {
+        DataKey::BorrowFrozen
+    }
+
+    fn position_caps_key() -> DataKey {
+        DataKey::PositionCaps
+    }
+
+    fn address_cap_key(asset: &Address, user: &Address) -> DataKey {
+        DataKey::AddressCap(asset.clone(), user.clone())
+    }
+
+    /// Supply cap a compliance officer placed on one address in `asset`
+    pub fn get_address_cap(env: &Env, asset: &Address, user: &Address) -> Option<i128> {
+        env.storage()
+            .persistent()
+            .get(&Self::address_cap_key(asset, user))
+    }
+
+    pub fn set_address_cap(env: &Env, asset: &Address, user: &Address, cap: Option<i128>) {
+        let key = Self::address_cap_key(asset, user);
+        match cap {
+            Some(cap) => env.storage().persistent().set(&key, &cap),
+            None => env.storage().persistent().remove(&key),
+        }
+    }
+
+    fn bootstrap_key() -> DataKey {
+        DataKey::MarketBootstrap
+    }
+
+    /// Markets still bootstrapping and the conditions that activate them
+    pub fn get_bootstraps(env: &Env) -> Map<Address, BootstrapConfig> {
+        env.storage()
+            .instance()
+            .get(&Self::bootstrap_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn save_bootstraps(env: &Env, bootstraps: &Map<Address, BootstrapConfig>) {
+        env.storage()
+            .instance()
+            .set(&Self::bootstrap_key(), bootstraps);
+    }
+
+    fn change_limit_key() -> DataKey {
+        DataKey::ParamChangeLimit
+    }
+
+    fn change_anchor_key(param: &Symbol) -> DataKey {
+        DataKey::ParamAnchor(param.clone())
+    }
+
+    pub fn get_change_limit(env: &Env) -> ParamChangeLimit {
+        env.storage()
+            .instance()
+            .get(&Self::change_limit_key())
+            .unwrap_or_default()
+    }
+
+    pub fn save_change_limit(env: &Env, limit: &ParamChangeLimit) {
+        env.storage()
+            .instance()
+            .set(&Self::change_limit_key(), limit);
+    }
+
+    /// Value of `param` at the start of its current window and when that window opened
+    pub fn get_change_anchor(env: &Env, param: &Symbol) -> Option<(i128, u64)> {
+        env.storage()
+            .instance()
+            .get(&Self::change_anchor_key(param))
+    }
+
+    pub fn save_change_anchor(env: &Env, param: &Symbol, anchor: &(i128, u64)) {
+        env.storage()
+            .instance()
+            .set(&Self::change_anchor_key(param), anchor);
+    }
+
+    pub fn get_params(env: &Env) -> ProtocolParameters {
+        env.storage()
+            .instance()
+            .get(&Self::params_key())
+            .unwrap_or_default()
+    }
+
+    pub fn save_params(env: &Env, params: &ProtocolParameters) {
+        env.storage().instance().set(&Self::params_key(), params);
+    }
+
+    pub fn is_frozen(env: &Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&Self::frozen_key())
+            .unwrap_or(false)
+    }
+
+    pub fn get_position_caps(env: &Env) -> Map<Address, i128> {
+        env.storage()
+            .instance()
+            .get(&Self::position_caps_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_position_caps(env: &Env, caps: &Map<Address, i128>) {
+        env.storage()
+            .instance()
+            .set(&Self::position_caps_key(), caps);
+    }
+
+    pub fn set_frozen(env: &Env, frozen: bool) {
+        env.storage().instance().set(&Self::frozen_key(), &frozen);
+    }
+
+    fn breakers_key() -> DataKey {
+        DataKey::UtilBreakers
+    }
+
+    fn thin_market_rates_key() -> DataKey {
+        DataKey::ThinMarketRates
+    }
+
+    fn tracker_key(asset: &Address) -> DataKey {
+        DataKey::UtilTracker(asset.clone())
+    }
+
+    fn cooldown_key(asset: &Address) -> DataKey {
+        DataKey::BorrowCooldown(asset.clone())
+    }
+
+    pub fn get_breakers(env: &Env) -> Map<Address, UtilizationBreaker> {
+        env.storage()
+            .instance()
+            .get(&Self::breakers_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_breakers(env: &Env, breakers: &Map<Address, UtilizationBreaker>) {
+        env.storage()
+            .instance()
+            .set(&Self::breakers_key(), breakers);
+    }
+
+    pub fn get_thin_market_rates(env: &Env) -> Map<Address, ThinMarketRate> {
+        env.storage()
+            .instance()
+            .get(&Self::thin_market_rates_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_thin_market_rates(env: &Env, rates: &Map<Address, ThinMarketRate>) {
+        env.storage()
+            .instance()
+            .set(&Self::thin_market_rates_key(), rates);
+    }
+
+    pub fn get_tracker(env: &Env, asset: &Address) -> UtilizationTracker {
+        env.storage()
+            .instance()
+            .get(&Self::tracker_key(asset))
+            .unwrap_or_default()
+    }
+
+    pub fn put_tracker(env: &Env, asset: &Address, tracker: &UtilizationTracker) {
+        env.storage()
+            .instance()
+            .set(&Self::tracker_key(asset), tracker);
+    }
+
+    /// Timestamp until which borrowing `asset` is paused by the breaker (0 = not paused)
+    pub fn get_cooldown(env: &Env, asset: &Address) -> u64 {
+        env.storage()
+            .instance()
+            .get(&Self::cooldown_key(asset))
+            .unwrap_or(0)
+    }
+
+    pub fn set_cooldown(env: &Env, asset: &Address, until: u64) {
+        env.storage()
+            .instance()
+            .set(&Self::cooldown_key(asset), &until);
+    }
+
+    fn ledger_guard_key() -> DataKey {
+        DataKey::SameLedgerGuard
+    }
+
+    fn guarded_action_key(user: &Address) -> DataKey {
+        DataKey::GuardedAction(user.clone())
+    }
+
+    /// Assets whose actions restrict borrowing and withdrawing for the rest of the ledger
+    pub fn get_ledger_guards(env: &Env) -> Map<Address, bool> {
+        env.storage()
+            .instance()
+            .get(&Self::ledger_guard_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_ledger_guards(env: &Env, guards: &Map<Address, bool>) {
+        env.storage()
+            .instance()
+            .set(&Self::ledger_guard_key(), guards);
+    }
+
+    /// Ledger sequence of the user's last action on a guarded asset
+    pub fn get_guarded_action(env: &Env, user: &Address) -> Option<u32> {
+        env.storage()
+            .persistent()
+            .get(&Self::guarded_action_key(user))
+    }
+
+    pub fn set_guarded_action(env: &Env, user: &Address, sequence: u32) {
+        env.storage()
+            .persistent()
+            .set(&Self::guarded_action_key(user), &sequence);
+    }
+}
+
+/// Debt ceiling and utilization kill-switch enforcement
+pub struct BorrowLimits;
+
+impl BorrowLimits {
+    pub fn set_parameters(
+        env: &Env,
+        caller: &Address,
+        params: ProtocolParameters,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if params.debt_ceiling < 0 || !(0..=100000000).contains(&params.freeze_utilization) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        // The resume threshold sits below the freeze threshold so the switch cannot flap
+        if params.freeze_utilization > 0
+            && !(0..params.freeze_utilization).contains(&params.resume_utilization)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        LimitsStorage::save_params(env, &params);
+        env.publish_event(
+            (Symbol::new(env, "protocol_params_updated"), caller.clone()),
+            (
+                params.debt_ceiling,
+                params.freeze_utilization,
+                params.resume_utilization,
+            ),
+        );
+        Self::refresh(env, InterestRateStorage::get_state(env).utilization_rate);
+        Ok(())
+    }
+
+    /// Freeze borrows once utilization reaches the freeze threshold and lift the freeze
+    /// once it falls below the resume threshold; returns whether borrows are frozen
+    pub fn refresh(env: &Env, utilization: i128) -> bool {
+        let params = LimitsStorage::get_params(env);
+        let frozen = LimitsStorage::is_frozen(env);
+        let next = if params.freeze_utilization == 0 {
+            false
+        } else if frozen {
+            utilization >= params.resume_utilization
+        } else {
+            utilization >= params.freeze_utilization
+        };
+
+        if let Ok(primary) = TokenRegistry::require_primary_asset(env) {
+            Self::track_utilization(env, &primary, utilization);
+        }
+
+        if next != frozen {
+            LimitsStorage::set_frozen(env, next);
+            let topic = if next {
+                "borrow_frozen"
+            } else {
+                "borrow_unfrozen"
+            };
+            env.publish_event((Symbol::new(env, topic),), utilization);
+        }
+        next
+    }
+
+    /// Arm, retune or (with `None`) remove the utilization circuit breaker for `asset`
+    pub fn set_utilization_breaker(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        breaker: Option<UtilizationBreaker>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut breakers = LimitsStorage::get_breakers(env);
+        match breaker {
+            Some(breaker) => {
+                if !(1..=100000000).contains(&breaker.max_jump) || breaker.cooldown == 0 {
+                    return Err(ProtocolError::InvalidParameters);
+                }
+                breakers.set(asset.clone(), breaker.clone());
+                // Measure jumps from the utilization in force when the breaker is armed
+                let utilization = InterestRateStorage::get_state(env).utilization_rate;
+                LimitsStorage::put_tracker(
+                    env,
+                    asset,
+                    &UtilizationTracker {
+                        ledger: env.ledger().sequence(),
+                        baseline: utilization,
+                        last: utilization,
+                    },
+                );
+                env.publish_event(
+                    (Symbol::new(env, "utilization_breaker_set"), asset.clone()),
+                    (breaker.max_jump, breaker.cooldown),
+                );
+            }
+            None => {
+                breakers.remove(asset.clone());
+                env.publish_event(
+                    (Symbol::new(env, "utilization_breaker_set"), asset.clone()),
+                    (0i128, 0u64),
+                );
+            }
+        }
+        LimitsStorage::put_breakers(env, &breakers);
+        Ok(())
+    }
+
+    /// Admin: set or (with `None`) clear the fixed rate `asset` is borrowed at while its
+    /// liquidity is thin
+    pub fn set_thin_market_rate(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        rate: Option<ThinMarketRate>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut rates = LimitsStorage::get_thin_market_rates(env);
+        match &rate {
+            Some(rate) => {
+                if rate.min_liquidity <= 0 || rate.fixed_rate < 0 {
+                    return Err(ProtocolError::InvalidParameters);
+                }
+                rates.set(asset.clone(), rate.clone());
+            }
+            None => {
+                rates.remove(asset.clone());
+            }
+        }
+        LimitsStorage::put_thin_market_rates(env, &rates);
+        env.publish_event(
+            (Symbol::new(env, "thin_market_rate_set"), asset.clone()),
+            rate,
+        );
+        Ok(())
+    }
+
+    /// Fixed rate the primary market borrows at while `liquidity` is below its configured
+    /// minimum, if any
+    pub fn thin_market_rate(env: &Env, liquidity: i128) -> Option<i128> {
+        let primary = TokenRegistry::require_primary_asset(env).ok()?;
+        let rate = LimitsStorage::get_thin_market_rates(env).get(primary)?;
+        (liquidity < rate.min_liquidity).then_some(rate.fixed_rate)
+    }
+
+    /// Record a utilization change for `asset` and trip its breaker when utilization has
+    /// risen more than the allowed jump since the start of the ledger
+    fn track_utilization(env: &Env, asset: &Address, utilization: i128) {
+        let Some(breaker) = LimitsStorage::get_breakers(env).get(asset.clone()) else {
+            return;
+        };
+        let sequence = env.ledger().sequence();
+        let mut tracker = LimitsStorage::get_tracker(env, asset);
+        if tracker.ledger != sequence {
+            tracker.ledger = sequence;
+            tracker.baseline = tracker.last;
+        }
+        tracker.last = utilization;
+        LimitsStorage::put_tracker(env, asset, &tracker);
+
+        let jump = utilization - tracker.baseline;
+        let now = env.ledger().timestamp();
+        if jump > breaker.max_jump && LimitsStorage::get_cooldown(env, asset) <= now {
+            let until = now + breaker.cooldown;
+            LimitsStorage::set_cooldown(env, asset, until);
+            env.publish_event(
+                (
+                    Symbol::new(env, "utilization_breaker_tripped"),
+                    asset.clone(),
+                ),
+                (tracker.baseline, utilization, until),
+            );
+        }
+    }
+
+    /// Reject borrowing `asset` while its utilization breaker cooldown runs
+    pub fn ensure_no_breaker_cooldown(env: &Env, asset: &Address) -> Result<(), ProtocolError> {
+        if env.ledger().timestamp() < LimitsStorage::get_cooldown(env, asset) {
+            return Err(ProtocolError::ProtocolPaused);
+        }
+        Ok(())
+    }
+
+    /// Reject a new borrow of `amount` while frozen or when it would breach the debt ceiling
+    pub fn ensure_can_borrow(env: &Env, amount: i128) -> Result<(), ProtocolError> {
+        if LimitsStorage::is_frozen(env) {
+            return Err(ProtocolError::ProtocolPaused);
+        }
+        let params = LimitsStorage::get_params(env);
+        let total_borrowed = InterestRateStorage::get_state(env).total_borrowed;
+        if params.debt_ceiling > 0 && total_borrowed + amount > params.debt_ceiling {
+            return Err(ProtocolError::CapExceeded);
+        }
+        Ok(())
+    }
+
+    /// Put `asset`'s market into the bootstrapping phase until it holds `tvl_threshold`
+    /// or `start_time` passes
+    pub fn start_bootstrap(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        config: BootstrapConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if config.tvl_threshold < 0
+            || (config.tvl_threshold == 0 && config.start_time <= env.ledger().timestamp())
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut bootstraps = LimitsStorage::get_bootstraps(env);
+        bootstraps.set(asset.clone(), config.clone());
+        LimitsStorage::save_bootstraps(env, &bootstraps);
+        env.publish_event(
+            (Symbol::new(env, "market_bootstrapping"), asset.clone()),
+            (config.tvl_threshold, config.start_time),
+        );
+        Ok(())
+    }
+
+    /// Liquidity supplied to `asset`'s market: tracked supply for the primary asset,
+    /// the contract's token balance otherwise
+    pub fn market_tvl(env: &Env, asset: &Address) -> i128 {
+        match TokenRegistry::require_primary_asset(env) {
+            Ok(primary) if primary == *asset => InterestRateStorage::get_state(env).total_supplied,
+            _ => TokenClient::new(env, asset).balance(&env.current_contract_address()),
+        }
+    }
+
+    fn bootstrap_complete(env: &Env, asset: &Address, config: &BootstrapConfig) -> bool {
+        (config.start_time > 0 && env.ledger().timestamp() >= config.start_time)
+            || (config.tvl_threshold > 0 && Self::market_tvl(env, asset) >= config.tvl_threshold)
+    }
+
+    pub fn market_phase(env: &Env, asset: &Address) -> MarketPhase {
+        match LimitsStorage::get_bootstraps(env).get(asset.clone()) {
+            Some(config) if !Self::bootstrap_complete(env, asset, &config) => {
+                MarketPhase::Bootstrapping
+            }
+            _ => MarketPhase::Active,
+        }
+    }
+
+    /// Reject borrowing `asset` while its market is bootstrapping, activating the market
+    /// once one of its conditions is met
+    pub fn ensure_market_active(env: &Env, asset: &Address) -> Result<(), ProtocolError> {
+        let mut bootstraps = LimitsStorage::get_bootstraps(env);
+        let Some(config) = bootstraps.get(asset.clone()) else {
+            return Ok(());
+        };
+        if !Self::bootstrap_complete(env, asset, &config) {
+            return Err(ProtocolError::MarketBootstrapping);
+        }
+        bootstraps.remove(asset.clone());
+        LimitsStorage::save_bootstraps(env, &bootstraps);
+        env.publish_event(
+            (Symbol::new(env, "market_activated"), asset.clone()),
+            Self::market_tvl(env, asset),
+        );
+        Ok(())
+    }
+
+    pub fn set_change_limit(
+        env: &Env,
+        caller: &Address,
+        limit: ParamChangeLimit,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if limit.max_change_bps < 0 || (limit.max_change_bps > 0 && limit.window == 0) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        LimitsStorage::save_change_limit(env, &limit);
+        env.publish_event(
+            (
+                Symbol::new(env, "param_change_limit_updated"),
+                caller.clone(),
+            ),
+            (limit.max_change_bps, limit.window),
+        );
+        Ok(())
+    }
+
+    /// Reject moving `param` from `current` to `next` when that drifts further than the
+    /// configured limit from its value at the start of the window. Emergency parameter
+    /// updates do not go through this check.
+    pub fn ensure_param_change(
+        env: &Env,
+        param: &str,
+        current: i128,
+        next: i128,
+    ) -> Result<(), ProtocolError> {
+        let limit = LimitsStorage::get_change_limit(env);
+        if limit.max_change_bps == 0 {
+            return Ok(());
+        }
+        let param = Symbol::new(env, param);
+        let now = env.ledger().timestamp();
+        let anchor = match LimitsStorage::get_change_anchor(env, &param) {
+            Some((value, opened)) if now < opened + limit.window => (value, opened),
+            _ => (current, now),
+        };
+
+        let allowed = anchor.0.abs() * limit.max_change_bps / 10000;
+        if (next - anchor.0).abs() > allowed {
+            return Err(ProtocolError::ParameterChangeTooLarge);
+        }
+        LimitsStorage::save_change_anchor(env, &param, &anchor);
+        Ok(())
+    }
+
+    /// Cap a single account's collateral in `asset`; zero removes the cap
+    pub fn set_max_position_size(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        max_size: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if max_size < 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut caps = LimitsStorage::get_position_caps(env);
+        if max_size == 0 {
+            caps.remove(asset.clone());
+        } else {
+            caps.set(asset.clone(), max_size);
+        }
+        LimitsStorage::put_position_caps(env, &caps);
+        env.publish_event(
+            (Symbol::new(env, "position_cap_updated"), asset.clone()),
+            max_size,
+        );
+        Ok(())
+    }
+
+    /// Largest collateral balance one account may hold in `asset` (0 = uncapped)
+    pub fn get_max_position_size(env: &Env, asset: &Address) -> i128 {
+        LimitsStorage::get_position_caps(env)
+            .get(asset.clone())
+            .unwrap_or(0)
+    }
+
+    /// Turn the same-ledger restriction on or off for `asset`. While on, depositing,
+    /// borrowing or withdrawing the asset blocks further borrows and withdrawals by the
+    /// same account until the next ledger.
+    pub fn set_same_ledger_guard(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut guards = LimitsStorage::get_ledger_guards(env);
+        if enabled {
+            guards.set(asset.clone(), true);
+        } else {
+            guards.remove(asset.clone());
+        }
+        LimitsStorage::put_ledger_guards(env, &guards);
+        env.publish_event(
+            (Symbol::new(env, "same_ledger_guard_updated"), asset.clone()),
+            enabled,
+        );
+        Ok(())
+    }
+
+    pub fn is_same_ledger_guarded(env: &Env, asset: &Address) -> bool {
+        LimitsStorage::get_ledger_guards(env)
+            .get(asset.clone())
+            .unwrap_or(false)
+    }
+
+    /// Note an action on `asset` so later borrows and withdrawals in this ledger are refused
+    pub fn record_ledger_action(env: &Env, user: &Address, asset: &Address) {
+        if Self::is_same_ledger_guarded(env, asset) {
+            LimitsStorage::set_guarded_action(env, user, env.ledger().sequence());
+        }
+    }
+
+    /// Reject a borrow or withdrawal in the same ledger as an action on a guarded asset
+    pub fn ensure_not_same_ledger(env: &Env, user: &Address) -> Result<(), ProtocolError> {
+        match LimitsStorage::get_guarded_action(env, user) {
+            Some(sequence) if sequence == env.ledger().sequence() => {
+                Err(ProtocolError::RateLimitExceeded)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reject a position whose collateral in `asset` would grow to `new_size`, checking
+    /// the asset-wide position cap and then any cap on this address
+    pub fn ensure_position_size(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        new_size: i128,
+    ) -> Result<(), ProtocolError> {
+        if let Some(cap) = LimitsStorage::get_position_caps(env).get(asset.clone()) {
+            if new_size > cap {
+                return Err(ProtocolError::CapExceeded);
+            }
+        }
+        match LimitsStorage::get_address_cap(env, asset, user) {
+            Some(cap) if new_size > cap => Err(ProtocolError::AddressCapExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    /// Compliance: cap how much `user` may supply of `asset`, limiting single-counterparty
+    /// exposure; `None` removes the cap. Existing balances above a new cap are left alone
+    /// but cannot grow.
+    pub fn set_address_supply_cap(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        user: &Address,
+        cap: Option<i128>,
+    ) -> Result<(), ProtocolError> {
+        UserManager::ensure_compliance_officer(env, caller)?;
+        if cap.is_some_and(|cap| cap < 0) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if *asset != TokenRegistry::require_primary_asset(env)? {
+            CrossAssetManager::get_asset_params(env, asset)?;
+        }
+        LimitsStorage::set_address_cap(env, asset, user, cap);
+        env.publish_event(
+            (Symbol::new(env, "address_cap_updated"), user.clone()),
+            (asset.clone(), cap.unwrap_or(-1)),
+        );
+        Ok(())
+    }
+
+    /// How much more of `asset` `user` may supply under the asset-wide and per-address
+    /// caps; `None` when neither applies
+    pub fn remaining_supply_capacity(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+    ) -> Result<Option<i128>, ProtocolError> {
+        let position_cap = LimitsStorage::get_position_caps(env).get(asset.clone());
+        let address_cap = LimitsStorage::get_address_cap(env, asset, user);
+        let cap = match (position_cap, address_cap) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(cap), None) | (None, Some(cap)) => cap,
+            (None, None) => return Ok(None),
+        };
+        let balance = if *asset == TokenRegistry::require_primary_asset(env)? {
+            StateHelper::get_position(env, user)
+                .map(|position| position.collateral)
+                .unwrap_or(0)
+        } else {
+            CrossStateHelper::get_or_init_position(env, user)
+                .collateral
+                .get(asset.clone())
+                .unwrap_or(0)
+        };
+        Ok(Some((cap - balance).max(0)))
+    }
+}
@@ -1,12 +1,26 @@
 //! Liquidation module for StellarLend protocol
 //! Handles liquidation functionality and related operations
 
-use crate::analytics::AnalyticsModule;
+use crate::adapter::CollateralAdapters;
+use crate::analytics::{ActivityKind, AnalyticsModule};
+use crate::compliance::ComplianceGate;
+use crate::cross_asset::{CrossAssetManager, CrossPosition, CrossStateHelper};
+use crate::events::SequencedEvents;
+use crate::history::PositionHistory;
+use crate::notify::Notifications;
+use crate::rebate::RebateManager;
+use crate::recovery::RecoveryMode;
+use crate::revenue::RevenueManager;
+use crate::statement::{FeeKind, FeeLedger};
+use crate::storage::DataKey;
+use crate::ttl::Ttl;
 use crate::{
-    EmergencyManager, InterestRateManager, InterestRateStorage, OperationKind, ProtocolConfig,
-    ProtocolError, ProtocolEvent, ReentrancyGuard, RiskConfigStorage, StateHelper,
+    InterestRateManager, InterestRateStorage, OperationKind, Position, ProtocolConfig,
+    ProtocolError, ProtocolEvent, ReentrancyScope, RiskConfigStorage, StateHelper, TokenRegistry,
+    TransferEnforcer, UserManager,
 };
-use soroban_sdk::{contracterror, contracttype, Address, Env, String};
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{contracterror, contracttype, Address, Env, Map, Symbol, Vec};
 
 /// Liquidation-specific errors
 #[contracterror]
@@ -68,9 +82,19 @@ impl LiquidationParams {
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[contracttype]
 pub struct LiquidationResult {
+    /// In units of the seized collateral asset
     pub collateral_seized: i128,
     pub debt_repaid: i128,
     pub liquidation_incentive: i128,
+    /// Part of repay + bonus (primary asset units) left in the borrower's collateral
+    /// because seized collateral is rounded down to whole units
+    pub surplus_returned: i128,
+    /// Seized units (collateral asset) beyond what repay + bonus is worth, credited to
+    /// reserves when seizures round in the protocol's favor
+    pub reserve_remainder: i128,
+    /// Protocol liquidation fee (collateral asset units) taken from the borrower's
+    /// collateral on top of the seizure and credited to the asset's reserves
+    pub protocol_fee: i128,
 }
 
 impl LiquidationResult {
@@ -79,186 +103,1025 @@ impl LiquidationResult {
             collateral_seized,
             debt_repaid,
             liquidation_incentive,
+            surplus_returned: 0,
+            reserve_remainder: 0,
+            protocol_fee: 0,
         }
     }
 }
 
+/// Preview of a liquidation for keepers sizing their transactions
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct LiquidationSimulation {
+    /// Repay amount the protocol would accept after applying the close factor
+    pub repay_amount: i128,
+    pub collateral_seized: i128,
+    /// Collateral paid to the liquidator on top of the repaid debt, net of the rebate tax and
+    /// valued in the primary asset
+    pub bonus: i128,
+    /// Health factor of the position after the liquidation
+    pub health_factor_after: i128,
+}
+
+/// Liquidation bonus that grows with how far a position's health factor is below 100
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct IncentiveCurve {
+    /// Bonus paid at the liquidation threshold (scaled by 1e8)
+    pub base_incentive: i128,
+    /// Extra bonus per health factor point below 100 (scaled by 1e8)
+    pub slope: i128,
+    /// Upper bound on the bonus (scaled by 1e8)
+    pub max_incentive: i128,
+}
+
+impl IncentiveCurve {
+    /// Bonus for a position at `health_factor` (100 = at the minimum collateral ratio)
+    pub fn incentive_at(&self, health_factor: i128) -> i128 {
+        let shortfall = (100 - health_factor).max(0);
+        (self.base_incentive + self.slope * shortfall).min(self.max_incentive)
+    }
+}
+
+/// Storage helper for per-asset incentive curves
+pub struct IncentiveCurveStorage;
+
+impl IncentiveCurveStorage {
+    fn key() -> DataKey {
+        DataKey::IncentiveCurves
+    }
+
+    pub fn get_all(env: &Env) -> Map<Address, IncentiveCurve> {
+        env.storage()
+            .instance()
+            .get(&Self::key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_all(env: &Env, curves: &Map<Address, IncentiveCurve>) {
+        env.storage().instance().set(&Self::key(), curves);
+    }
+}
+
+/// Close factor applied while a position's health factor is at or above `min_health_factor`
+/// and below the previous band's threshold
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CloseFactorBand {
+    /// Lower bound of the band (100 = at the minimum collateral ratio)
+    pub min_health_factor: i128,
+    /// Max share of debt repayable in one liquidation within the band (scaled by 1e8)
+    pub close_factor: i128,
+}
+
+/// Most bands accepted in a close factor table
+pub const MAX_CLOSE_FACTOR_BANDS: u32 = 8;
+
+/// Storage helper for the close factor table
+pub struct CloseFactorStorage;
+
+impl CloseFactorStorage {
+    fn key() -> DataKey {
+        DataKey::CloseFactorBands
+    }
+
+    pub fn get_bands(env: &Env) -> Vec<CloseFactorBand> {
+        env.storage()
+            .instance()
+            .get(&Self::key())
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_bands(env: &Env, bands: &Vec<CloseFactorBand>) {
+        env.storage().instance().set(&Self::key(), bands);
+    }
+}
+
+/// Most assets a seizure order may rank
+pub const MAX_SEIZURE_ORDER: u32 = 10;
+
+/// Storage helper for collateral seizure priorities; assets listed first are seized first
+pub struct SeizureOrderStorage;
+
+impl SeizureOrderStorage {
+    fn user_key(user: &Address) -> DataKey {
+        DataKey::SeizureOrder(user.clone())
+    }
+
+    fn default_key() -> DataKey {
+        DataKey::SeizureOrderDefault
+    }
+
+    pub fn get_user(env: &Env, user: &Address) -> Option<Vec<Address>> {
+        env.storage().persistent().get(&Self::user_key(user))
+    }
+
+    pub fn save_user(env: &Env, user: &Address, order: &Vec<Address>) {
+        let key = Self::user_key(user);
+        if order.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, order);
+        }
+    }
+
+    pub fn get_default(env: &Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&Self::default_key())
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_default(env: &Env, order: &Vec<Address>) {
+        env.storage().instance().set(&Self::default_key(), order);
+    }
+}
+
+/// Which side keeps the fractional unit when seized collateral is rounded to whole units
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum SeizureRounding {
+    /// Round down; the remainder stays in the borrower's collateral
+    FavorBorrower,
+    /// Round up; the remainder is credited to the collateral asset's reserves
+    FavorProtocol,
+}
+
+impl SeizureRounding {
+    /// `numerator / denominator` for positive operands, rounded in this direction
+    pub fn divide(&self, numerator: i128, denominator: i128) -> i128 {
+        let quotient = numerator / denominator;
+        match self {
+            SeizureRounding::FavorProtocol if numerator % denominator != 0 => quotient + 1,
+            _ => quotient,
+        }
+    }
+}
+
+/// Storage helper for the seizure rounding direction
+pub struct SeizureRoundingStorage;
+
+impl SeizureRoundingStorage {
+    fn key() -> DataKey {
+        DataKey::SeizureRounding
+    }
+
+    pub fn get(env: &Env) -> SeizureRounding {
+        env.storage()
+            .instance()
+            .get(&Self::key())
+            .unwrap_or(SeizureRounding::FavorBorrower)
+    }
+
+    pub fn save(env: &Env, rounding: &SeizureRounding) {
+        env.storage().instance().set(&Self::key(), rounding);
+    }
+}
+
+/// Storage helper for the post-liquidation health factor target
+pub struct LiquidationTargetStorage;
+
+impl LiquidationTargetStorage {
+    fn key() -> DataKey {
+        DataKey::LiquidationTargetHf
+    }
+
+    /// Health factor liquidations restore positions to (100 = at the minimum collateral
+    /// ratio), if targeted liquidations are enabled
+    pub fn get(env: &Env) -> Option<i128> {
+        env.storage().instance().get(&Self::key())
+    }
+
+    pub fn save(env: &Env, target: &Option<i128>) {
+        match target {
+            Some(target) => env.storage().instance().set(&Self::key(), target),
+            None => env.storage().instance().remove(&Self::key()),
+        }
+    }
+}
+
+/// Storage helper for per-asset protocol liquidation fees
+pub struct LiquidationFeeStorage;
+
+impl LiquidationFeeStorage {
+    fn key() -> DataKey {
+        DataKey::LiquidationFees
+    }
+
+    pub fn get_all(env: &Env) -> Map<Address, i128> {
+        env.storage()
+            .instance()
+            .get(&Self::key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    /// Fee in bps of seized collateral charged when seizing `asset`
+    pub fn get(env: &Env, asset: &Address) -> i128 {
+        Self::get_all(env).get(asset.clone()).unwrap_or(0)
+    }
+
+    pub fn put_all(env: &Env, fees: &Map<Address, i128>) {
+        env.storage().instance().set(&Self::key(), fees);
+    }
+}
+
+/// Storage helper for the restricted liquidation mode and its liquidator allowlist
+pub struct LiquidatorAllowlistStorage;
+
+impl LiquidatorAllowlistStorage {
+    fn restricted_key() -> DataKey {
+        DataKey::RestrictedLiquidations
+    }
+
+    fn liquidator_key(liquidator: &Address) -> DataKey {
+        DataKey::ApprovedLiquidator(liquidator.clone())
+    }
+
+    pub fn is_restricted(env: &Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&Self::restricted_key())
+            .unwrap_or(false)
+    }
+
+    pub fn set_restricted(env: &Env, restricted: bool) {
+        env.storage()
+            .instance()
+            .set(&Self::restricted_key(), &restricted);
+    }
+
+    pub fn is_approved(env: &Env, liquidator: &Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&Self::liquidator_key(liquidator))
+    }
+
+    pub fn set_approved(env: &Env, liquidator: &Address, approved: bool) {
+        let key = Self::liquidator_key(liquidator);
+        if approved {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+    }
+}
+
+/// A validated liquidation that has not been saved yet
+struct PlannedLiquidation {
+    position: Position,
+    /// Cross-asset balances after the seizure when the seized collateral is not the
+    /// primary asset
+    cross: Option<CrossPosition>,
+    collateral_asset: Address,
+    result: LiquidationResult,
+}
+
+/// Most entries accepted by a single `liquidate_batch` call
+pub const MAX_LIQUIDATION_BATCH: u32 = 10;
+
+/// Outcome of one entry in a liquidation batch
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BatchLiquidationResult {
+    pub target: Address,
+    /// False when the entry was skipped, e.g. because the position was no longer eligible
+    pub executed: bool,
+    pub debt_repaid: i128,
+    pub collateral_seized: i128,
+    /// `ProtocolError` code explaining a skipped entry, zero when executed
+    pub error_code: u32,
+}
+
 /// Liquidation module implementation
 pub struct LiquidationModule;
 
 impl LiquidationModule {
-    /// Liquidate an undercollateralized position
+    /// Liquidate an undercollateralized position, seizing primary asset collateral
     pub fn liquidate(
         env: &Env,
-        liquidator: &String,
-        user: &String,
+        liquidator: &Address,
+        user: &Address,
         amount: i128,
     ) -> Result<LiquidationResult, ProtocolError> {
-        ReentrancyGuard::enter(env)?;
-        let result = (|| -> Result<LiquidationResult, ProtocolError> {
-            // Input validation
-            if liquidator.is_empty() || user.is_empty() {
-                return Err(LiquidationError::InvalidAddress.into());
-            }
-            if amount <= 0 {
-                return Err(LiquidationError::InvalidAmount.into());
-            }
-
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Liquidate)?;
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        Self::liquidate_collateral(env, liquidator, liquidator, user, &primary, amount)
+    }
 
-            // Check if liquidation is paused
-            let risk_config = RiskConfigStorage::get(env);
-            if risk_config.pause_liquidate {
-                return Err(LiquidationError::ProtocolPaused.into());
-            }
+    /// Liquidate an undercollateralized position, seizing `collateral_asset` on behalf of
+    /// `receiver`. The asset must be the first one the user still holds in their seizure
+    /// order.
+    pub fn liquidate_collateral(
+        env: &Env,
+        liquidator: &Address,
+        receiver: &Address,
+        user: &Address,
+        collateral_asset: &Address,
+        amount: i128,
+    ) -> Result<LiquidationResult, ProtocolError> {
+        let _guard = ReentrancyScope::enter(env)?;
+        Ttl::extend_instance(env);
+        liquidator.require_auth();
+        ComplianceGate::check(env, liquidator, OperationKind::Liquidate, amount)?;
+        ComplianceGate::ensure_market_open(env, collateral_asset, OperationKind::Liquidate)?;
+        Self::ensure_approved_liquidator(env, liquidator)?;
+        // Seized collateral must leave the liquidated position, not route back into it
+        if receiver == user || *receiver == env.current_contract_address() {
+            return Err(ProtocolError::InvalidAddress);
+        }
+        let planned = Self::plan(env, user, collateral_asset, amount)?;
+        Self::execute(env, liquidator, receiver, user, planned)
+    }
 
-            let liquidator_addr = Address::from_string(liquidator);
-            let user_addr = Address::from_string(user);
+    /// Liquidate several positions in one call. Entries that fail validation (no longer
+    /// eligible, unsupported assets, ...) are skipped rather than reverting the batch.
+    pub fn liquidate_batch(
+        env: &Env,
+        liquidator: &Address,
+        entries: &Vec<(Address, Address, Address, i128)>,
+    ) -> Result<Vec<BatchLiquidationResult>, ProtocolError> {
+        if entries.is_empty() || entries.len() > MAX_LIQUIDATION_BATCH {
+            return Err(ProtocolError::InvalidParameters);
+        }
 
-            // Load user position
-            let mut position = match StateHelper::get_position(env, &user_addr) {
-                Some(pos) => pos,
-                None => return Err(LiquidationError::PositionNotFound.into()),
-            };
+        let _guard = ReentrancyScope::enter(env)?;
+        liquidator.require_auth();
+        let requested = entries.iter().fold(0i128, |total, (_, _, _, amount)| {
+            total.saturating_add(amount)
+        });
+        ComplianceGate::check(env, liquidator, OperationKind::Liquidate, requested)?;
+        Self::ensure_approved_liquidator(env, liquidator)?;
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        let mut results = Vec::new(env);
 
-            // Check if position is eligible for liquidation
-            let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-            let collateral_ratio = if position.debt > 0 {
-                (position.collateral * 100) / position.debt
+        for (target, debt_asset, collateral_asset, amount) in entries.iter() {
+            let planned = if debt_asset != primary {
+                Err(ProtocolError::AssetNotSupported)
+            } else if let Err(error) =
+                ComplianceGate::ensure_market_open(env, &collateral_asset, OperationKind::Liquidate)
+            {
+                Err(error)
             } else {
-                0
+                Self::plan(env, &target, &collateral_asset, amount)
             };
 
-            if collateral_ratio >= min_ratio {
-                return Err(LiquidationError::NotEligibleForLiquidation.into());
-            }
-
-            // Calculate liquidation amount
-            let max_liquidation = (position.debt * risk_config.close_factor) / 100000000;
-            let liquidation_amount = if amount > max_liquidation {
-                max_liquidation
-            } else {
-                amount
+            let entry = match planned {
+                Ok(planned) => {
+                    let executed = Self::execute(env, liquidator, liquidator, &target, planned)?;
+                    BatchLiquidationResult {
+                        target,
+                        executed: true,
+                        debt_repaid: executed.debt_repaid,
+                        collateral_seized: executed.collateral_seized,
+                        error_code: 0,
+                    }
+                }
+                Err(err) => BatchLiquidationResult {
+                    target,
+                    executed: false,
+                    debt_repaid: 0,
+                    collateral_seized: 0,
+                    error_code: err as u32,
+                },
             };
+            results.push_back(entry);
+        }
 
-            // Calculate collateral to seize
-            let collateral_seized =
-                (liquidation_amount * (100000000 + risk_config.liquidation_incentive)) / 100000000;
+        Ok(results)
+    }
 
-            // Update position
-            position.debt -= liquidation_amount;
-            position.collateral -= collateral_seized;
-            StateHelper::save_position(env, &position);
+    /// In restricted mode only allowlisted liquidators may liquidate
+    fn ensure_approved_liquidator(env: &Env, liquidator: &Address) -> Result<(), ProtocolError> {
+        if LiquidatorAllowlistStorage::is_restricted(env)
+            && !LiquidatorAllowlistStorage::is_approved(env, liquidator)
+        {
+            return Err(ProtocolError::Unauthorized);
+        }
+        Ok(())
+    }
 
-            let result = LiquidationResult::new(
-                collateral_seized,
-                liquidation_amount,
-                risk_config.liquidation_incentive,
-            );
+    /// Admin: switch the deployment between permissionless liquidations and liquidations
+    /// by allowlisted liquidators only
+    pub fn set_restricted_liquidations(
+        env: &Env,
+        caller: &Address,
+        restricted: bool,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        LiquidatorAllowlistStorage::set_restricted(env, restricted);
+        env.publish_event(
+            (
+                Symbol::new(env, "restricted_liquidations_set"),
+                caller.clone(),
+            ),
+            restricted,
+        );
+        Ok(())
+    }
 
-            // Emit liquidation event
-            ProtocolEvent::LiquidationExecuted(
-                liquidator_addr.clone(),
-                user_addr,
-                collateral_seized,
-                liquidation_amount,
-            )
-            .emit(env);
+    /// Compliance: add `liquidator` to or remove it from the liquidator allowlist
+    pub fn set_approved_liquidator(
+        env: &Env,
+        caller: &Address,
+        liquidator: &Address,
+        approved: bool,
+    ) -> Result<(), ProtocolError> {
+        UserManager::ensure_compliance_officer(env, caller)?;
+        LiquidatorAllowlistStorage::set_approved(env, liquidator, approved);
+        env.publish_event(
+            (
+                Symbol::new(env, "liquidator_approval_set"),
+                liquidator.clone(),
+            ),
+            approved,
+        );
+        Ok(())
+    }
+
+    /// Apply a planned liquidation: save the position, pull the repayment from the
+    /// liquidator, send the seized collateral to the receiver, emit the event naming both,
+    /// and record analytics
+    fn execute(
+        env: &Env,
+        liquidator: &Address,
+        receiver: &Address,
+        user: &Address,
+        planned: PlannedLiquidation,
+    ) -> Result<LiquidationResult, ProtocolError> {
+        let PlannedLiquidation {
+            position,
+            cross,
+            collateral_asset,
+            result,
+        } = planned;
+        StateHelper::save_position(env, &position);
+        if let Some(cross) = &cross {
+            CrossStateHelper::save_position(env, cross);
+        }
 
-            // Analytics
-            AnalyticsModule::record_activity(
+        let repaid = TransferEnforcer::transfer_in(
+            env,
+            liquidator,
+            result.debt_repaid,
+            Symbol::new(env, "liquidate"),
+        )?;
+        if repaid < result.debt_repaid {
+            return Err(ProtocolError::BalanceInvariantViolation);
+        }
+        if cross.is_none() {
+            TransferEnforcer::transfer_out(
+                env,
+                receiver,
+                result.collateral_seized,
+                Symbol::new(env, "liquidate"),
+            )?;
+        } else {
+            TokenClient::new(env, &collateral_asset).transfer(
+                &env.current_contract_address(),
+                receiver,
+                &result.collateral_seized,
+            );
+            CollateralAdapters::notify_seizure(
                 env,
-                &liquidator_addr,
-                "liquidate",
-                liquidation_amount,
-                None,
+                &collateral_asset,
+                user,
+                result.collateral_seized,
+                receiver,
             )?;
+        }
+        if result.surplus_returned > 0 {
+            env.publish_event(
+                (Symbol::new(env, "liquidation_surplus"), user.clone()),
+                (collateral_asset.clone(), result.surplus_returned),
+            );
+        }
+        if result.reserve_remainder > 0 {
+            RevenueManager::record_liquidation_remainder(
+                env,
+                &collateral_asset,
+                result.reserve_remainder,
+            );
+            env.publish_event(
+                (Symbol::new(env, "liquidation_remainder"), user.clone()),
+                (collateral_asset.clone(), result.reserve_remainder),
+            );
+        }
+        if result.protocol_fee > 0 {
+            let value = if collateral_asset == TokenRegistry::require_primary_asset(env)? {
+                result.protocol_fee
+            } else {
+                CrossAssetManager::value_of_units(env, &collateral_asset, result.protocol_fee)?
+            };
+            RevenueManager::record_liquidation_fee(
+                env,
+                &collateral_asset,
+                result.protocol_fee,
+                value,
+            );
+            env.publish_event(
+                (Symbol::new(env, "liquidation_fee"), user.clone()),
+                (collateral_asset.clone(), result.protocol_fee),
+            );
+        }
+        let bonus = Self::bonus_value(&result);
+        RebateManager::collect(env, user, bonus);
+        FeeLedger::record(env, user, FeeKind::LiquidationPenalty, bonus);
+        PositionHistory::record(env, user, ActivityKind::Liquidate, None, result.debt_repaid);
 
-            Ok(result)
-        })();
+        ProtocolEvent::LiquidationExecuted(
+            liquidator.clone(),
+            receiver.clone(),
+            user.clone(),
+            result.collateral_seized,
+            result.debt_repaid,
+        )
+        .emit(env);
+
+        AnalyticsModule::record_activity(
+            env,
+            liquidator,
+            ActivityKind::Liquidate,
+            result.debt_repaid,
+            None,
+        )?;
+        Notifications::check(env, user)?;
 
-        ReentrancyGuard::exit(env);
-        result
+        Ok(result)
     }
 
-    /// Check if a position is eligible for liquidation
-    pub fn is_eligible_for_liquidation(env: &Env, user: &Address) -> Result<bool, ProtocolError> {
-        let position = match StateHelper::get_position(env, user) {
-            Some(pos) => pos,
-            None => return Err(LiquidationError::PositionNotFound.into()),
-        };
+    /// Liquidation bonus in primary asset units, whichever collateral was seized
+    fn bonus_value(result: &LiquidationResult) -> i128 {
+        result.debt_repaid * result.liquidation_incentive / 100000000
+    }
 
+    /// Validate a liquidation and size it against the close factor, returning the
+    /// position as it would look afterwards without saving it
+    fn plan(
+        env: &Env,
+        user: &Address,
+        collateral_asset: &Address,
+        amount: i128,
+    ) -> Result<PlannedLiquidation, ProtocolError> {
+        // Input validation
+        if amount <= 0 {
+            return Err(LiquidationError::InvalidAmount.into());
+        }
+
+        // Load user position with interest accrued up to now
+        let mut position = Self::accrued_position(env, user)?;
+
+        // Check if position is eligible for liquidation
         let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-        let collateral_ratio = if position.debt > 0 {
-            (position.collateral * 100) / position.debt
+        let owed = position.debt + position.borrow_interest;
+        let collateral_ratio =
+            CrossAssetManager::liquidation_ratio(env, user, position.collateral, owed)?;
+
+        if collateral_ratio >= min_ratio {
+            return Err(LiquidationError::NotEligibleForLiquidation.into());
+        }
+
+        // Collateral the user opted out of cannot be seized, and lower-ranked assets in the
+        // seizure order must be exhausted first
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        if !CrossAssetManager::is_collateral_enabled(env, user, collateral_asset) {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+        if Self::seizure_order(env, user)
+            .first()
+            .is_some_and(|next| next != *collateral_asset)
+        {
+            return Err(ProtocolError::SeizureOrderViolation);
+        }
+
+        // Deeper shortfalls earn a larger bonus when the collateral asset has a curve
+        let incentive = match IncentiveCurveStorage::get_all(env).get(collateral_asset.clone()) {
+            Some(curve) => curve.incentive_at(collateral_ratio * 100 / min_ratio),
+            None => RiskConfigStorage::get(env).liquidation_incentive,
+        };
+        let incentive = RecoveryMode::liquidation_incentive(env, incentive);
+
+        // Calculate liquidation amount; deeper shortfalls may be closed out further, and
+        // with a health factor target set no more is repaid than restoring it takes
+        let close_factor = Self::close_factor_at(env, collateral_ratio * 100 / min_ratio);
+        let max_liquidation = (owed * close_factor) / 100000000;
+        let mut liquidation_amount = if amount > max_liquidation {
+            max_liquidation
         } else {
-            0
+            amount
+        };
+        if let Some(target) = LiquidationTargetStorage::get(env) {
+            let needed =
+                Self::repay_to_target(env, user, collateral_asset, &position, incentive, target)?;
+            liquidation_amount = liquidation_amount.min(needed);
+        }
+
+        // Calculate collateral to seize, valued in the primary asset. The liquidator is owed
+        // the rounded-down value; the configured rounding decides whether the borrower keeps
+        // the fractional unit or reserves take it.
+        let rounding = SeizureRoundingStorage::get(env);
+        let owed_scaled = liquidation_amount * (100000000 + incentive);
+        let seized_value = owed_scaled / 100000000;
+
+        // Repaid debt settles accrued interest before principal
+        let interest_repaid = liquidation_amount.min(position.borrow_interest);
+        position.borrow_interest -= interest_repaid;
+        position.debt -= liquidation_amount - interest_repaid;
+
+        // The protocol fee comes out of whatever collateral of the asset the seizure leaves
+        let fee_bps = LiquidationFeeStorage::get(env, collateral_asset);
+        let (cross, collateral_seized, surplus, remainder, fee) = if *collateral_asset == primary {
+            let seized = rounding.divide(owed_scaled, 100000000);
+            let pledged = CrossAssetManager::pledged_interest(env, user);
+            if seized > position.collateral + pledged {
+                return Err(ProtocolError::InsufficientCollateral);
+            }
+            // Seize collateral first, then any supply interest pledged alongside it
+            let from_interest = (seized - position.collateral).clamp(0, pledged);
+            position.collateral -= seized - from_interest;
+            position.supply_interest -= from_interest;
+            let fee = (seized * fee_bps / 10000).clamp(0, position.collateral.max(0));
+            position.collateral -= fee;
+            (None, seized, 0, seized - seized_value, fee)
+        } else {
+            let mut cross = CrossStateHelper::get_or_init_position(env, user);
+            let balance = cross.collateral.get(collateral_asset.clone()).unwrap_or(0);
+            let price = CrossAssetManager::asset_price(env, collateral_asset)?;
+            if price <= 0 {
+                return Err(ProtocolError::OracleFailure);
+            }
+            // Rounded down, the seizure is never worth more than repay + bonus and the
+            // borrower keeps the surplus; rounded up, it never falls short and the extra
+            // units go to reserves
+            let seized = rounding.divide(owed_scaled, price);
+            let surplus = (seized_value
+                - CrossAssetManager::value_of_units(env, collateral_asset, seized)?)
+            .max(0);
+            if seized > balance {
+                return Err(ProtocolError::InsufficientCollateral);
+            }
+            let fee = (seized * fee_bps / 10000).min(balance - seized);
+            cross
+                .collateral
+                .set(collateral_asset.clone(), balance - seized - fee);
+            cross.last_update = env.ledger().timestamp();
+            (
+                Some(cross),
+                seized,
+                surplus,
+                seized - owed_scaled / price,
+                fee,
+            )
         };
 
-        Ok(collateral_ratio < min_ratio)
+        Ok(PlannedLiquidation {
+            position,
+            cross,
+            collateral_asset: collateral_asset.clone(),
+            result: LiquidationResult {
+                surplus_returned: surplus,
+                reserve_remainder: remainder,
+                protocol_fee: fee,
+                ..LiquidationResult::new(collateral_seized, liquidation_amount, incentive)
+            },
+        })
+    }
+
+    /// Collateral assets the user holds, in the order liquidators must seize them: the
+    /// user's preference (or the risk config default), then the primary asset, then any
+    /// remaining cross-asset collateral
+    pub fn seizure_order(env: &Env, user: &Address) -> Vec<Address> {
+        let primary = TokenRegistry::require_primary_asset(env).ok();
+        let primary_collateral = StateHelper::get_position(env, user)
+            .map(|position| position.collateral)
+            .unwrap_or(0);
+        let cross = CrossStateHelper::get_or_init_position(env, user);
+        let held = |asset: &Address| {
+            let balance = if primary.as_ref() == Some(asset) {
+                primary_collateral
+            } else {
+                cross.collateral.get(asset.clone()).unwrap_or(0)
+            };
+            balance > 0 && CrossAssetManager::is_collateral_enabled(env, user, asset)
+        };
+
+        let mut candidates = SeizureOrderStorage::get_user(env, user)
+            .unwrap_or_else(|| SeizureOrderStorage::get_default(env));
+        if let Some(primary) = primary.clone() {
+            candidates.push_back(primary);
+        }
+        for (asset, _) in cross.collateral.iter() {
+            candidates.push_back(asset);
+        }
+
+        let mut order = Vec::new(env);
+        for asset in candidates.iter() {
+            if !order.contains(&asset) && held(&asset) {
+                order.push_back(asset);
+            }
+        }
+        order
+    }
+
+    fn validate_seizure_order(env: &Env, order: &Vec<Address>) -> Result<(), ProtocolError> {
+        if order.len() > MAX_SEIZURE_ORDER {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        for (index, asset) in order.iter().enumerate() {
+            if order.first_index_of(&asset) != Some(index as u32) {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            if asset != primary {
+                CrossAssetManager::get_asset_params(env, &asset)?;
+            }
+        }
+        Ok(())
     }
 
-    /// Calculate maximum liquidation amount for a position
-    pub fn calculate_max_liquidation_amount(
+    /// Rank the user's collateral for seizure; an empty order falls back to the default
+    pub fn set_seizure_order(
         env: &Env,
         user: &Address,
-    ) -> Result<i128, ProtocolError> {
-        let position = match StateHelper::get_position(env, user) {
-            Some(pos) => pos,
-            None => return Err(LiquidationError::PositionNotFound.into()),
-        };
+        order: Vec<Address>,
+    ) -> Result<(), ProtocolError> {
+        Self::validate_seizure_order(env, &order)?;
+        SeizureOrderStorage::save_user(env, user, &order);
+        env.publish_event(
+            (Symbol::new(env, "seizure_order_updated"), user.clone()),
+            order,
+        );
+        Ok(())
+    }
+
+    /// Set the seizure order used for users without a preference of their own
+    pub fn set_default_seizure_order(
+        env: &Env,
+        caller: &Address,
+        order: Vec<Address>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        Self::validate_seizure_order(env, &order)?;
+        SeizureOrderStorage::save_default(env, &order);
+        env.publish_event(
+            (
+                Symbol::new(env, "default_seizure_order_updated"),
+                caller.clone(),
+            ),
+            order,
+        );
+        Ok(())
+    }
 
-        let risk_config = RiskConfigStorage::get(env);
-        let max_liquidation = (position.debt * risk_config.close_factor) / 100000000;
+    /// Close factor for a position at `health_factor`, falling back to the flat risk
+    /// config value when no band table is set
+    pub fn close_factor_at(env: &Env, health_factor: i128) -> i128 {
+        for band in CloseFactorStorage::get_bands(env).iter() {
+            if health_factor >= band.min_health_factor {
+                return band.close_factor;
+            }
+        }
+        RiskConfigStorage::get(env).close_factor
+    }
 
-        Ok(max_liquidation)
+    /// Replace the close factor table; bands must be ordered by descending threshold and
+    /// end at zero so every health factor is covered. An empty table restores the flat
+    /// close factor.
+    pub fn set_close_factor_bands(
+        env: &Env,
+        caller: &Address,
+        bands: Vec<CloseFactorBand>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if bands.len() > MAX_CLOSE_FACTOR_BANDS {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut previous: Option<i128> = None;
+        for band in bands.iter() {
+            if band.close_factor <= 0 || band.close_factor > 100000000 {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            if band.min_health_factor < 0 || previous.is_some_and(|p| band.min_health_factor >= p) {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            previous = Some(band.min_health_factor);
+        }
+        if previous.is_some_and(|last| last != 0) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        CloseFactorStorage::save_bands(env, &bands);
+        env.publish_event(
+            (
+                Symbol::new(env, "close_factor_bands_updated"),
+                caller.clone(),
+            ),
+            bands.len(),
+        );
+        Ok(())
     }
 
-    /// Calculate collateral to seize for a given liquidation amount
-    pub fn calculate_collateral_to_seize(
+    /// Repayment that brings the position back to `target` health factor when
+    /// `collateral_asset` is seized for it, counting the incentive and protocol fee the
+    /// seizure removes. Positions a seizure cannot heal (the collateral is weighted above
+    /// the target ratio's reach) are left to the close factor.
+    fn repay_to_target(
         env: &Env,
-        liquidation_amount: i128,
+        user: &Address,
+        collateral_asset: &Address,
+        position: &Position,
+        incentive: i128,
+        target: i128,
     ) -> Result<i128, ProtocolError> {
-        let risk_config = RiskConfigStorage::get(env);
-        let collateral_seized =
-            (liquidation_amount * (100000000 + risk_config.liquidation_incentive)) / 100000000;
+        let owed = position.debt + position.borrow_interest;
+        let cross = CrossStateHelper::get_or_init_position(env, user);
+        let value = CrossAssetManager::liquidation_value(
+            env,
+            user,
+            position.collateral,
+            &cross.collateral,
+        )?;
+        // Target collateral ratio (percent) and weighted value lost per unit repaid (1e8)
+        let target_ratio = target * ProtocolConfig::get_min_collateral_ratio(env) / 100;
+        let fee_bps = LiquidationFeeStorage::get(env, collateral_asset);
+        let value_lost = (100000000 + incentive)
+            * CrossAssetManager::liquidation_factor(env, collateral_asset)?
+            / 100000000
+            * (10000 + fee_bps)
+            / 10000;
+
+        let shortfall = target_ratio * owed - 100 * value;
+        let gain_per_unit = target_ratio * 100000000 - 100 * value_lost;
+        if gain_per_unit <= 0 {
+            return Ok(owed);
+        }
+        // Rounded up so the position reaches the target rather than stopping just short
+        Ok(((shortfall * 100000000 + gain_per_unit - 1) / gain_per_unit).min(owed))
+    }
 
-        Ok(collateral_seized)
+    /// Enable targeted liquidations that repay only what restores a position to `target`
+    /// health factor (100 = at the minimum collateral ratio), still capped by the close
+    /// factor, or (with `None`) let liquidators repay up to the close factor
+    pub fn set_target_health_factor(
+        env: &Env,
+        caller: &Address,
+        target: Option<i128>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if target.is_some_and(|target| target <= 100) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        LiquidationTargetStorage::save(env, &target);
+        env.publish_event(
+            (
+                Symbol::new(env, "liquidation_target_updated"),
+                caller.clone(),
+            ),
+            target,
+        );
+        Ok(())
     }
 
-    /// Validate liquidation parameters
-    pub fn validate_liquidation_params(params: &LiquidationParams) -> Result<(), LiquidationError> {
-        if params.amount <= 0 {
-            return Err(LiquidationError::InvalidAmount);
+    /// Set which side keeps the fractional unit when seized collateral is rounded
+    pub fn set_seizure_rounding(
+        env: &Env,
+        caller: &Address,
+        rounding: SeizureRounding,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        SeizureRoundingStorage::save(env, &rounding);
+        env.publish_event(
+            (Symbol::new(env, "seizure_rounding_set"), caller.clone()),
+            rounding,
+        );
+        Ok(())
+    }
+
+    /// Charge `fee_bps` of the collateral seized from `asset` as a protocol fee on top of
+    /// the seizure; zero stops charging it
+    pub fn set_liquidation_fee(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        fee_bps: i128,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=10000).contains(&fee_bps) {
+            return Err(ProtocolError::InvalidParameters);
         }
+        if *asset != TokenRegistry::require_primary_asset(env)? {
+            CrossAssetManager::get_asset_params(env, asset)?;
+        }
+        let mut fees = LiquidationFeeStorage::get_all(env);
+        if fee_bps == 0 {
+            fees.remove(asset.clone());
+        } else {
+            fees.set(asset.clone(), fee_bps);
+        }
+        LiquidationFeeStorage::put_all(env, &fees);
+        env.publish_event(
+            (Symbol::new(env, "liquidation_fee_set"), asset.clone()),
+            fee_bps,
+        );
         Ok(())
     }
 
-    /// Calculate liquidation incentive
-    pub fn calculate_liquidation_incentive(env: &Env, liquidation_amount: i128) -> i128 {
-        let risk_config = RiskConfigStorage::get(env);
-        (liquidation_amount * risk_config.liquidation_incentive) / 100000000
+    /// Set or clear (`None`) the dynamic incentive curve for a collateral asset
+    pub fn set_incentive_curve(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        curve: Option<IncentiveCurve>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let mut curves = IncentiveCurveStorage::get_all(env);
+        match curve {
+            Some(curve) => {
+                if curve.base_incentive < 0
+                    || curve.slope < 0
+                    || curve.max_incentive < curve.base_incentive
+                    || curve.max_incentive > 100000000
+                {
+                    return Err(ProtocolError::InvalidParameters);
+                }
+                env.publish_event(
+                    (Symbol::new(env, "incentive_curve_updated"), asset.clone()),
+                    (curve.base_incentive, curve.slope, curve.max_incentive),
+                );
+                curves.set(asset.clone(), curve);
+            }
+            None => {
+                curves.remove(asset.clone());
+                env.publish_event(
+                    (Symbol::new(env, "incentive_curve_removed"), asset.clone()),
+                    (),
+                );
+            }
+        }
+        IncentiveCurveStorage::put_all(env, &curves);
+        Ok(())
     }
 
-    /// Get liquidation health factor
-    pub fn get_health_factor(env: &Env, user: &Address) -> Result<i128, ProtocolError> {
-        let position = match StateHelper::get_position(env, user) {
-            Some(pos) => pos,
-            None => return Err(LiquidationError::PositionNotFound.into()),
-        };
+    /// Preview a liquidation without executing it; only the position's interest accrual is
+    /// persisted. Only the primary asset can be repaid, so other debt assets are rejected.
+    pub fn simulate(
+        env: &Env,
+        user: &Address,
+        debt_asset: &Address,
+        collateral_asset: &Address,
+        amount: i128,
+    ) -> Result<LiquidationSimulation, ProtocolError> {
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        if *debt_asset != primary {
+            return Err(ProtocolError::AssetNotSupported);
+        }
 
+        let PlannedLiquidation {
+            position,
+            cross,
+            result,
+            ..
+        } = Self::plan(env, user, collateral_asset, amount)?;
         let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-        let collateral_ratio = if position.debt > 0 {
-            (position.collateral * 100) / position.debt
+        let cross = cross.unwrap_or_else(|| CrossStateHelper::get_or_init_position(env, user));
+        let owed = position.debt + position.borrow_interest;
+        let ratio_after = if owed > 0 {
+            CrossAssetManager::liquidation_value(env, user, position.collateral, &cross.collateral)?
+                * 100
+                / owed
         } else {
             0
         };
-
-        // Health factor = collateral_ratio / min_ratio
-        if min_ratio > 0 {
-            Ok((collateral_ratio * 100) / min_ratio)
+        let health_factor_after = if min_ratio > 0 {
+            (ratio_after * 100) / min_ratio
         } else {
-            Ok(0)
-        }
+            0
+        };
+
+        let bonus = Self::bonus_value(&result);
+        Ok(LiquidationSimulation {
+            repay_amount: result.debt_repaid,
+            collateral_seized: result.collateral_seized,
+            bonus: bonus - RebateManager::tax_for(env, bonus),
+            health_factor_after,
+        })
+    }
+
+    /// Load a position and accrue its borrow and supply interest to the current indexes,
+    /// saving it so valuation paths that read storage (e.g. pledged supply interest) see
+    /// the same figures as the eligibility check
+    fn accrued_position(env: &Env, user: &Address) -> Result<Position, ProtocolError> {
+        let mut position = match StateHelper::get_position(env, user) {
+            Some(pos) => pos,
+            None => return Err(LiquidationError::PositionNotFound.into()),
+        };
+        let state = InterestRateStorage::update_state(env);
+        InterestRateManager::accrue_interest_for_position(
+            env,
+            &mut position,
+            state.current_borrow_rate,
+            state.liquidity_index,
+        );
+        StateHelper::save_position(env, &position);
+        Ok(position)
     }
 }
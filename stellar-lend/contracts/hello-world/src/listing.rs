@@ -0,0 +1,309 @@
+//! Listing module for StellarLend protocol
+//! Permissionless market listings. Anyone can propose an asset for listing by escrowing
+//! the listing fee in the configured stable asset; a risk manager approves the proposal,
+//! which lists the asset and keeps the fee as reserves, or rejects it and refunds the fee.
+//! An approved proposer can be granted a share of the new market's reserve revenue for a
+//! number of 30-day months, accrued as reserves come in and claimable on-chain.
+
+use crate::cross_asset::{AssetRegistryStorage, CrossAssetManager};
+use crate::events::SequencedEvents;
+use crate::revenue::RevenueManager;
+use crate::storage::DataKey;
+use crate::{ProtocolConfig, ProtocolError, UserManager, UserRole};
+use soroban_sdk::{contracttype, token::TokenClient, Address, Env, Symbol};
+
+/// Length of a revenue share month
+pub const LISTING_SHARE_MONTH: u64 = 30 * 24 * 60 * 60;
+
+/// Largest proposer revenue share (10%, in basis points)
+pub const MAX_PROPOSER_SHARE_BPS: i128 = 1000;
+
+/// Admin-managed terms for permissionless listings
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ListingConfig {
+    /// Stable asset the listing fee is paid in
+    pub fee_asset: Address,
+    pub fee: i128,
+    /// Share of the market's reserve revenue paid to the proposer (basis points)
+    pub proposer_share_bps: i128,
+    /// Months after approval during which the proposer earns the share
+    pub share_months: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum ListingStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// Proposal to list an asset, with the fee escrowed by its proposer
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ListingProposal {
+    pub id: u64,
+    pub proposer: Address,
+    pub asset: Address,
+    /// Requested collateral factor (1e8 scale)
+    pub collateral_factor: i128,
+    pub fee_asset: Address,
+    pub fee: i128,
+    pub status: ListingStatus,
+    pub created_at: u64,
+}
+
+/// Proposer's share of a listed market's reserve revenue
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ListingRevenueShare {
+    pub proposer: Address,
+    pub share_bps: i128,
+    /// Reserves credited after this time are no longer shared
+    pub ends_at: u64,
+    /// Units of the market's asset earned by the proposer
+    pub accrued: i128,
+    pub claimed: i128,
+}
+
+/// Storage helper for market listings
+pub struct ListingStorage;
+
+impl ListingStorage {
+    fn config_key() -> DataKey {
+        DataKey::ListingConfig
+    }
+
+    fn proposal_key(id: u64) -> DataKey {
+        DataKey::ListingProposal(id)
+    }
+
+    fn seq_key() -> DataKey {
+        DataKey::ListingSeq
+    }
+
+    fn share_key(asset: &Address) -> DataKey {
+        DataKey::ListingRevenueShare(asset.clone())
+    }
+
+    pub fn get_config(env: &Env) -> Option<ListingConfig> {
+        env.storage().instance().get(&Self::config_key())
+    }
+
+    pub fn put_config(env: &Env, config: &Option<ListingConfig>) {
+        match config {
+            Some(config) => env.storage().instance().set(&Self::config_key(), config),
+            None => env.storage().instance().remove(&Self::config_key()),
+        }
+    }
+
+    pub fn get_proposal(env: &Env, id: u64) -> Option<ListingProposal> {
+        env.storage().persistent().get(&Self::proposal_key(id))
+    }
+
+    pub fn save_proposal(env: &Env, proposal: &ListingProposal) {
+        env.storage()
+            .persistent()
+            .set(&Self::proposal_key(proposal.id), proposal);
+    }
+
+    pub fn get_share(env: &Env, asset: &Address) -> Option<ListingRevenueShare> {
+        env.storage().instance().get(&Self::share_key(asset))
+    }
+
+    pub fn save_share(env: &Env, asset: &Address, share: &ListingRevenueShare) {
+        env.storage().instance().set(&Self::share_key(asset), share);
+    }
+
+    fn next_id(env: &Env) -> u64 {
+        let id = env
+            .storage()
+            .instance()
+            .get::<DataKey, u64>(&Self::seq_key())
+            .unwrap_or(0)
+            + 1;
+        env.storage().instance().set(&Self::seq_key(), &id);
+        id
+    }
+}
+
+/// Permissionless listing proposals and proposer revenue shares
+pub struct MarketListings;
+
+impl MarketListings {
+    /// Admin: set the listing fee and proposer revenue share, or (with `None`) stop
+    /// accepting listing proposals
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: Option<ListingConfig>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if let Some(config) = &config {
+            if config.fee < 0 || !(0..=MAX_PROPOSER_SHARE_BPS).contains(&config.proposer_share_bps)
+            {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+        ListingStorage::put_config(env, &config);
+        env.publish_event(
+            (Symbol::new(env, "listing_config_updated"), caller.clone()),
+            config.is_some(),
+        );
+        Ok(())
+    }
+
+    /// Propose listing `asset` at `collateral_factor`, escrowing the listing fee from the
+    /// proposer. Returns the proposal id.
+    pub fn propose(
+        env: &Env,
+        proposer: &Address,
+        asset: &Address,
+        collateral_factor: i128,
+    ) -> Result<u64, ProtocolError> {
+        let config = ListingStorage::get_config(env).ok_or(ProtocolError::ConfigurationError)?;
+        if !(0..=100000000).contains(&collateral_factor) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if AssetRegistryStorage::get_params_map(env).contains_key(asset.clone()) {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        if config.fee > 0 {
+            TokenClient::new(env, &config.fee_asset).transfer(
+                proposer,
+                &env.current_contract_address(),
+                &config.fee,
+            );
+        }
+        let proposal = ListingProposal {
+            id: ListingStorage::next_id(env),
+            proposer: proposer.clone(),
+            asset: asset.clone(),
+            collateral_factor,
+            fee_asset: config.fee_asset,
+            fee: config.fee,
+            status: ListingStatus::Pending,
+            created_at: env.ledger().timestamp(),
+        };
+        ListingStorage::save_proposal(env, &proposal);
+        env.publish_event(
+            (Symbol::new(env, "listing_proposed"), proposer.clone()),
+            (proposal.id, asset.clone(), collateral_factor, proposal.fee),
+        );
+        Ok(proposal.id)
+    }
+
+    /// Risk manager: list the proposed asset, keep the fee as reserves and start the
+    /// proposer's revenue share under the current terms
+    pub fn approve(env: &Env, caller: &Address, id: u64) -> Result<(), ProtocolError> {
+        UserManager::ensure_can_manage(env, caller, UserRole::Manager)?;
+        let mut proposal = Self::pending(env, id)?;
+        if AssetRegistryStorage::get_params_map(env).contains_key(proposal.asset.clone()) {
+            return Err(ProtocolError::AlreadyExists);
+        }
+        CrossAssetManager::set_asset_params(
+            env,
+            caller,
+            &proposal.asset,
+            proposal.collateral_factor,
+            true,
+        )?;
+        proposal.status = ListingStatus::Approved;
+        ListingStorage::save_proposal(env, &proposal);
+        RevenueManager::record_reserve(env, &proposal.fee_asset, proposal.fee);
+
+        if let Some(config) = ListingStorage::get_config(env) {
+            if config.proposer_share_bps > 0 && config.share_months > 0 {
+                ListingStorage::save_share(
+                    env,
+                    &proposal.asset,
+                    &ListingRevenueShare {
+                        proposer: proposal.proposer.clone(),
+                        share_bps: config.proposer_share_bps,
+                        ends_at: env.ledger().timestamp()
+                            + config.share_months as u64 * LISTING_SHARE_MONTH,
+                        accrued: 0,
+                        claimed: 0,
+                    },
+                );
+            }
+        }
+        env.publish_event(
+            (Symbol::new(env, "listing_approved"), proposal.asset.clone()),
+            (id, proposal.proposer),
+        );
+        Ok(())
+    }
+
+    /// Risk manager: decline a proposal and refund its fee
+    pub fn reject(env: &Env, caller: &Address, id: u64) -> Result<(), ProtocolError> {
+        UserManager::ensure_can_manage(env, caller, UserRole::Manager)?;
+        let mut proposal = Self::pending(env, id)?;
+        proposal.status = ListingStatus::Rejected;
+        ListingStorage::save_proposal(env, &proposal);
+        if proposal.fee > 0 {
+            TokenClient::new(env, &proposal.fee_asset).transfer(
+                &env.current_contract_address(),
+                &proposal.proposer,
+                &proposal.fee,
+            );
+        }
+        env.publish_event(
+            (Symbol::new(env, "listing_rejected"), proposal.asset.clone()),
+            (id, proposal.proposer),
+        );
+        Ok(())
+    }
+
+    /// Split `amount` of reserves credited in `asset` with its proposer while the share
+    /// runs; returns the part earned by the proposer
+    pub fn take_proposer_share(env: &Env, asset: &Address, amount: i128) -> i128 {
+        let Some(mut share) = ListingStorage::get_share(env, asset) else {
+            return 0;
+        };
+        if env.ledger().timestamp() >= share.ends_at {
+            return 0;
+        }
+        let earned = amount * share.share_bps / 10000;
+        if earned > 0 {
+            share.accrued += earned;
+            ListingStorage::save_share(env, asset, &share);
+        }
+        earned
+    }
+
+    /// Pay the proposer of `asset` the revenue share earned but not yet claimed, as far as
+    /// the contract's holdings cover it; returns the amount paid
+    pub fn claim(env: &Env, proposer: &Address, asset: &Address) -> Result<i128, ProtocolError> {
+        let mut share = ListingStorage::get_share(env, asset).ok_or(ProtocolError::NotFound)?;
+        if share.proposer != *proposer {
+            return Err(ProtocolError::Unauthorized);
+        }
+        let client = TokenClient::new(env, asset);
+        let contract = env.current_contract_address();
+        let amount = (share.accrued - share.claimed).min(client.balance(&contract));
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        share.claimed += amount;
+        ListingStorage::save_share(env, asset, &share);
+        client.transfer(&contract, proposer, &amount);
+        env.publish_event(
+            (
+                Symbol::new(env, "listing_revenue_claimed"),
+                proposer.clone(),
+            ),
+            (asset.clone(), amount),
+        );
+        Ok(amount)
+    }
+
+    fn pending(env: &Env, id: u64) -> Result<ListingProposal, ProtocolError> {
+        let proposal = ListingStorage::get_proposal(env, id).ok_or(ProtocolError::NotFound)?;
+        if proposal.status != ListingStatus::Pending {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        Ok(proposal)
+    }
+}
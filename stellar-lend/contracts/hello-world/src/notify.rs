@@ -0,0 +1,106 @@
+//! Notify module for StellarLend protocol
+//! Opt-in borrower notification preferences: an off-chain routing hint and a health
+//! factor threshold, echoed in health warning events so indexers can deliver alerts
+//! without keeping their own address-to-channel mapping
+
+use crate::cross_asset::CrossAssetManager;
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{ProtocolConfig, ProtocolError, StateHelper};
+use soroban_sdk::{contracttype, Address, Env, String, Symbol};
+
+/// Longest notification hint accepted, in bytes
+pub const MAX_NOTIFICATION_HINT_LEN: u32 = 64;
+
+/// Highest warning threshold a user may choose (health factor in percent)
+pub const MAX_WARNING_HEALTH_FACTOR: i128 = 1000;
+
+/// Where and when a borrower wants to be warned about their position
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct NotificationPrefs {
+    /// Opaque routing hint for indexers, e.g. a webhook id or a hash of a contact
+    pub hint: String,
+    /// Warn once the health factor (percent of the minimum ratio) drops below this
+    pub warn_health_factor: i128,
+}
+
+/// Storage helper for notification preferences
+pub struct NotificationStorage;
+
+impl NotificationStorage {
+    fn prefs_key(user: &Address) -> DataKey {
+        DataKey::NotificationPrefs(user.clone())
+    }
+
+    pub fn get(env: &Env, user: &Address) -> Option<NotificationPrefs> {
+        env.storage().persistent().get(&Self::prefs_key(user))
+    }
+
+    pub fn save(env: &Env, user: &Address, prefs: &Option<NotificationPrefs>) {
+        let key = Self::prefs_key(user);
+        match prefs {
+            Some(prefs) => env.storage().persistent().set(&key, prefs),
+            None => env.storage().persistent().remove(&key),
+        }
+    }
+}
+
+/// Health warnings for opted-in borrowers
+pub struct Notifications;
+
+impl Notifications {
+    /// Register or replace the user's preferences; `None` opts out
+    pub fn set_prefs(
+        env: &Env,
+        user: &Address,
+        prefs: Option<NotificationPrefs>,
+    ) -> Result<(), ProtocolError> {
+        if let Some(prefs) = &prefs {
+            if prefs.hint.len() > MAX_NOTIFICATION_HINT_LEN
+                || prefs.warn_health_factor <= 100
+                || prefs.warn_health_factor > MAX_WARNING_HEALTH_FACTOR
+            {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+        NotificationStorage::save(env, user, &prefs);
+        env.publish_event(
+            (Symbol::new(env, "notification_prefs_set"), user.clone()),
+            prefs.map(|prefs| prefs.warn_health_factor).unwrap_or(0),
+        );
+        Ok(())
+    }
+
+    /// Health factor (percent of the minimum ratio) of the user's saved position, or
+    /// `None` without debt
+    fn health_factor(env: &Env, user: &Address) -> Result<Option<i128>, ProtocolError> {
+        let Some(position) = StateHelper::get_position(env, user) else {
+            return Ok(None);
+        };
+        let owed = position.debt + position.borrow_interest;
+        let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
+        if owed <= 0 || min_ratio <= 0 {
+            return Ok(None);
+        }
+        let ratio = CrossAssetManager::liquidation_ratio(env, user, position.collateral, owed)?;
+        Ok(Some(ratio * 100 / min_ratio))
+    }
+
+    /// Publish a health warning carrying the user's hint when their health factor sits
+    /// below their chosen threshold; returns the health factor (0 without debt)
+    pub fn check(env: &Env, user: &Address) -> Result<i128, ProtocolError> {
+        let Some(health_factor) = Self::health_factor(env, user)? else {
+            return Ok(0);
+        };
+        if let Some(prefs) = NotificationStorage::get(env, user) {
+            if health_factor < prefs.warn_health_factor {
+                env.publish_event(
+                    (Symbol::new(env, "health_warning"), user.clone()),
+                    (health_factor, prefs.warn_health_factor, prefs.hint),
+                );
+            }
+        }
+        Ok(health_factor)
+    }
+}
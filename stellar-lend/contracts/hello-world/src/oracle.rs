@@ -1,4 +1,5 @@
 #![allow(dead_code)]
+use crate::storage::DataKey;
 use soroban_sdk::{contracttype, vec, Address, Env, IntoVal, Symbol, Vec};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -15,37 +16,89 @@ impl OracleSource {
     }
 }
 
+/// Per-asset oracle settings; zero fields fall back to the global defaults
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AssetOracleConfig {
+    pub heartbeat: u64,          // max price age in seconds
+    pub max_deviation_bps: i128, // max move of a synced price vs the last one (0 = unbounded)
+    pub fallback_price: i128,    // recorded when no source is healthy (0 = none)
+}
+
+/// Which tier of an asset's failover sequence served a synced price
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum PriceTier {
+    Primary,   // aggregated registered sources
+    Secondary, // the asset's secondary feed
+    Fallback,  // the asset's static fallback price
+}
+
 pub struct OracleStorage;
 
 impl OracleStorage {
-    fn sources_key(env: &Env) -> Symbol { Symbol::new(env, "oracle_sources") }
-    fn heartbeat_ttl_key(env: &Env) -> Symbol { Symbol::new(env, "oracle_heartbeat_ttl") }
-    fn mode_key(env: &Env) -> Symbol { Symbol::new(env, "oracle_mode") }
-    fn perf_count_key(env: &Env) -> Symbol { Symbol::new(env, "oracle_perf_count") }
+    fn sources_key(asset: &Address) -> DataKey { DataKey::OracleSource(asset.clone()) }
+    fn secondary_key(asset: &Address) -> DataKey { DataKey::OracleSecondary(asset.clone()) }
+    fn heartbeat_ttl_key() -> DataKey { DataKey::OracleHeartbeatTtl }
+    fn max_deviation_key() -> DataKey { DataKey::OracleMaxDeviation }
+    fn asset_config_key(asset: &Address) -> DataKey { DataKey::OracleAssetConfig(asset.clone()) }
+    fn mode_key() -> DataKey { DataKey::OracleMode }
+    fn perf_count_key() -> DataKey { DataKey::OraclePerfCount }
 
     pub fn get_sources(env: &Env, asset: &Address) -> Vec<OracleSource> {
-        let key = (Self::sources_key(env), asset.clone());
+        let key = Self::sources_key(asset);
         env.storage().instance().get(&key).unwrap_or_else(|| Vec::new(env))
     }
 
     pub fn put_sources(env: &Env, asset: &Address, sources: &Vec<OracleSource>) {
-        let key = (Self::sources_key(env), asset.clone());
+        let key = Self::sources_key(asset);
         env.storage().instance().set(&key, sources);
     }
 
+    pub fn get_secondary(env: &Env, asset: &Address) -> Option<OracleSource> {
+        env.storage().instance().get(&Self::secondary_key(asset))
+    }
+
+    pub fn put_secondary(env: &Env, asset: &Address, source: &Option<OracleSource>) {
+        match source {
+            Some(source) => env.storage().instance().set(&Self::secondary_key(asset), source),
+            None => env.storage().instance().remove(&Self::secondary_key(asset)),
+        }
+    }
+
     pub fn get_heartbeat_ttl(env: &Env) -> u64 {
-        env.storage().instance().get(&Self::heartbeat_ttl_key(env)).unwrap_or(300)
+        env.storage().instance().get(&Self::heartbeat_ttl_key()).unwrap_or(300)
     }
 
     pub fn set_heartbeat_ttl(env: &Env, ttl: u64) {
-        env.storage().instance().set(&Self::heartbeat_ttl_key(env), &ttl);
+        env.storage().instance().set(&Self::heartbeat_ttl_key(), &ttl);
+    }
+
+    /// Default max deviation in basis points for assets without their own (0 = unbounded)
+    pub fn get_max_deviation(env: &Env) -> i128 {
+        env.storage().instance().get(&Self::max_deviation_key()).unwrap_or(0)
+    }
+
+    pub fn set_max_deviation(env: &Env, bps: i128) {
+        env.storage().instance().set(&Self::max_deviation_key(), &bps);
+    }
+
+    pub fn get_asset_config(env: &Env, asset: &Address) -> Option<AssetOracleConfig> {
+        env.storage().instance().get(&Self::asset_config_key(asset))
+    }
+
+    pub fn put_asset_config(env: &Env, asset: &Address, config: &Option<AssetOracleConfig>) {
+        match config {
+            Some(config) => env.storage().instance().set(&Self::asset_config_key(asset), config),
+            None => env.storage().instance().remove(&Self::asset_config_key(asset)),
+        }
     }
 
-    pub fn set_mode(env: &Env, mode: i128) { env.storage().instance().set(&Self::mode_key(env), &mode); }
-    pub fn get_mode(env: &Env) -> i128 { env.storage().instance().get(&Self::mode_key(env)).unwrap_or(0) } // 0=median,1=twap
+    pub fn set_mode(env: &Env, mode: i128) { env.storage().instance().set(&Self::mode_key(), &mode); }
+    pub fn get_mode(env: &Env) -> i128 { env.storage().instance().get(&Self::mode_key()).unwrap_or(0) } // 0=median,1=twap
     pub fn inc_perf(env: &Env) -> i128 {
-        let cur: i128 = env.storage().instance().get(&Self::perf_count_key(env)).unwrap_or(0) + 1;
-        env.storage().instance().set(&Self::perf_count_key(env), &cur);
+        let cur: i128 = env.storage().instance().get(&Self::perf_count_key()).unwrap_or(0) + 1;
+        env.storage().instance().set(&Self::perf_count_key(), &cur);
         cur
     }
 }
@@ -53,10 +106,24 @@ impl OracleStorage {
 pub struct Oracle;
 
 impl Oracle {
+    /// Oracle settings in force for an asset, with unset fields resolved to the global defaults
+    pub fn effective_config(env: &Env, asset: &Address) -> AssetOracleConfig {
+        let own = OracleStorage::get_asset_config(env, asset);
+        let mut config = own.unwrap_or(AssetOracleConfig { heartbeat: 0, max_deviation_bps: 0, fallback_price: 0 });
+        if config.heartbeat == 0 { config.heartbeat = OracleStorage::get_heartbeat_ttl(env); }
+        if config.max_deviation_bps == 0 { config.max_deviation_bps = OracleStorage::get_max_deviation(env); }
+        config
+    }
+
+    /// Max age in seconds of an asset's price and of its sources' heartbeats
+    pub fn heartbeat(env: &Env, asset: &Address) -> u64 {
+        Self::effective_config(env, asset).heartbeat
+    }
+
     /// Register or update an oracle source for an asset
-    pub fn set_source(env: &Env, caller: &Address, asset: &Address, source: OracleSource) {
+    pub fn set_source(env: &Env, asset: &Address, source: OracleSource) {
         // Access control left to caller via lib.rs admin checks
-        let mut list = OracleStorage::get_sources(env, asset);
+        let list = OracleStorage::get_sources(env, asset);
         // Replace if exists
         let mut replaced = false;
         let mut out: Vec<OracleSource> = Vec::new(env);
@@ -68,17 +135,40 @@ impl Oracle {
     }
 
     /// Remove a source
-    pub fn remove_source(env: &Env, caller: &Address, asset: &Address, addr: &Address) {
+    pub fn remove_source(env: &Env, asset: &Address, addr: &Address) {
         let list = OracleStorage::get_sources(env, asset);
         let mut out: Vec<OracleSource> = Vec::new(env);
         for s in list.iter() { if s.addr != *addr { out.push_back(s); } }
         OracleStorage::put_sources(env, asset, &out);
     }
 
+    /// Set or clear (`None`) the feed consulted when the primary sources fail
+    pub fn set_secondary(env: &Env, asset: &Address, addr: Option<Address>) {
+        let source = addr.map(|addr| OracleSource::new(addr, 1, env.ledger().timestamp()));
+        OracleStorage::put_secondary(env, asset, &source);
+    }
+
+    /// Price and confidence (0 when not reported) from the asset's secondary feed, if it is
+    /// within its heartbeat and answers with a positive price
+    pub fn secondary_price(env: &Env, asset: &Address) -> Option<(i128, i128)> {
+        let s = OracleStorage::get_secondary(env, asset)?;
+        if env.ledger().timestamp().saturating_sub(s.last_heartbeat) > Self::heartbeat(env, asset) { return None; }
+        let args = vec![env, asset.clone().into_val(env)];
+        let price = match env.try_invoke_contract::<i128, soroban_sdk::Error>(&s.addr, &Symbol::new(env, "get_price"), args.clone()) {
+            Ok(Ok(price)) if price > 0 => price,
+            _ => return None,
+        };
+        let confidence = match env.try_invoke_contract::<i128, soroban_sdk::Error>(&s.addr, &Symbol::new(env, "get_confidence"), args) {
+            Ok(Ok(confidence)) if confidence >= 0 => confidence,
+            _ => 0,
+        };
+        Some((price, confidence))
+    }
+
     /// Fetch prices from all sources (stubbed as calling `get_price()` on source contracts)
     pub fn fetch_prices(env: &Env, asset: &Address) -> Vec<i128> {
         let list = OracleStorage::get_sources(env, asset);
-        let ttl = OracleStorage::get_heartbeat_ttl(env);
+        let ttl = Self::heartbeat(env, asset);
         let now = env.ledger().timestamp();
         let mut prices: Vec<i128> = Vec::new(env);
         for s in list.iter() {
@@ -91,6 +181,28 @@ impl Oracle {
         prices
     }
 
+    /// Fetch confidence intervals (in the price scale) from healthy sources exposing `get_confidence()`; feeds without one are skipped
+    pub fn fetch_confidences(env: &Env, asset: &Address) -> Vec<i128> {
+        let list = OracleStorage::get_sources(env, asset);
+        let ttl = Self::heartbeat(env, asset);
+        let now = env.ledger().timestamp();
+        let mut confidences: Vec<i128> = Vec::new(env);
+        for s in list.iter() {
+            if now.saturating_sub(s.last_heartbeat) > ttl { continue; }
+            let args = vec![env, asset.clone().into_val(env)];
+            let result = env.try_invoke_contract::<i128, soroban_sdk::Error>(&s.addr, &Symbol::new(env, "get_confidence"), args);
+            if let Ok(Ok(confidence)) = result { if confidence >= 0 { confidences.push_back(confidence); } }
+        }
+        confidences
+    }
+
+    /// Widest confidence interval reported by a healthy source; 0 when no feed reports one
+    pub fn aggregate_confidence(env: &Env, asset: &Address) -> i128 {
+        let mut widest: i128 = 0;
+        for c in Self::fetch_confidences(env, asset).iter() { if c > widest { widest = c; } }
+        widest
+    }
+
     /// Aggregate prices using median; returns None if no healthy sources
     pub fn aggregate_price(env: &Env, asset: &Address) -> Option<i128> {
         let mut prices = Self::fetch_prices(env, asset);
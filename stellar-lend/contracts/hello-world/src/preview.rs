@@ -0,0 +1,214 @@
+//! Preview module for StellarLend protocol
+//! ERC-4626 style previews of the four core flows. Each preview brings the user's position
+//! current against the live indexes, exactly as the action itself would, and reports the
+//! tokens that would move, receipt units minted or burned, interest and fees, so
+//! integrators can display precise numbers and routers can size transactions.
+
+use crate::cross_asset::CrossAssetManager;
+use crate::limits::BorrowLimits;
+use crate::recovery::RecoveryMode;
+use crate::repay::RepayModule;
+use crate::supply_fee::SupplyFee;
+use crate::{
+    InterestRateManager, InterestRateStorage, Position, ProtocolConfig, ProtocolError, StateHelper,
+    TokenRegistry,
+};
+use soroban_sdk::{contracttype, Address, Env};
+
+/// Outcome of a deposit, withdrawal, borrow or repayment at the current indexes
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ActionPreview {
+    /// Primary-asset tokens that would move between the user and the protocol
+    pub amount: i128,
+    /// Receipt units minted (deposit) or burned (withdraw)
+    pub shares: i128,
+    /// Borrow interest settled by a repayment, or accrued interest outstanding on the side
+    /// the action touches (supply interest for deposits and withdrawals)
+    pub interest: i128,
+    /// Storage fee taken from collateral when the position is brought current
+    pub fee: i128,
+    pub collateral_after: i128,
+    pub debt_after: i128,
+    /// Collateral ratio (percent) after the action; zero without debt
+    pub collateral_ratio: i128,
+}
+
+/// Read-side previews of the core flows
+pub struct ActionPreviews;
+
+impl ActionPreviews {
+    /// Preview depositing `amount` of primary collateral
+    pub fn deposit(
+        env: &Env,
+        user: &Address,
+        amount: i128,
+    ) -> Result<ActionPreview, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let (position, fee) = Self::current_position(env, user, false)?;
+        let collateral_after = position.collateral + amount;
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        BorrowLimits::ensure_position_size(env, user, &primary, collateral_after)?;
+        Self::build(
+            env,
+            &position,
+            amount,
+            amount,
+            position.supply_interest,
+            fee,
+            collateral_after,
+            position.debt,
+        )
+    }
+
+    /// Preview withdrawing `amount` of primary collateral
+    pub fn withdraw(
+        env: &Env,
+        user: &Address,
+        amount: i128,
+    ) -> Result<ActionPreview, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let (position, fee) = Self::current_position(env, user, true)?;
+        if position.collateral < amount {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+        let collateral_after = position.collateral - amount;
+        if position.debt > 0 {
+            Self::ensure_healthy(env, user, collateral_after, position.debt)?;
+        }
+        Self::build(
+            env,
+            &position,
+            amount,
+            amount,
+            position.supply_interest,
+            fee,
+            collateral_after,
+            position.debt,
+        )
+    }
+
+    /// Preview borrowing `amount` of the primary asset
+    pub fn borrow(env: &Env, user: &Address, amount: i128) -> Result<ActionPreview, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let (position, fee) = Self::current_position(env, user, true)?;
+        BorrowLimits::ensure_can_borrow(env, amount)?;
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        BorrowLimits::ensure_market_active(env, &primary)?;
+        RecoveryMode::ensure_borrowable(env, &primary)?;
+        let debt_after = position.debt + amount;
+        Self::ensure_healthy(env, user, position.collateral, debt_after)?;
+        Self::build(
+            env,
+            &position,
+            amount,
+            0,
+            position.borrow_interest,
+            fee,
+            position.collateral,
+            debt_after,
+        )
+    }
+
+    /// Preview repaying up to `amount`; overpayments stay with the payer, and accrued
+    /// interest is settled before principal
+    pub fn repay(env: &Env, user: &Address, amount: i128) -> Result<ActionPreview, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let (mut position, fee) = Self::current_position(env, user, true)?;
+        let payoff = RepayModule::payoff_amount(&position);
+        if payoff == 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let repaid = core::cmp::min(amount, payoff);
+        let interest_paid = RepayModule::apply_repayment(&mut position, repaid);
+        Self::build(
+            env,
+            &position,
+            repaid,
+            0,
+            interest_paid,
+            fee,
+            position.collateral,
+            position.debt,
+        )
+    }
+
+    /// Load the user's position and accrue it to the current indexes, saving the checkpoint
+    /// as any flow touching it would; returns it with the storage fee the accrual charged
+    fn current_position(
+        env: &Env,
+        user: &Address,
+        required: bool,
+    ) -> Result<(Position, i128), ProtocolError> {
+        let state = InterestRateStorage::update_state(env);
+        let Some(mut position) = StateHelper::get_position(env, user) else {
+            if required {
+                return Err(ProtocolError::PositionNotFound);
+            }
+            return Ok((Position::new(user.clone(), 0, 0), 0));
+        };
+        let elapsed = if position.last_accrual_time == 0 {
+            0
+        } else {
+            env.ledger().timestamp() - position.last_accrual_time
+        };
+        let fee = SupplyFee::due(env, &position, elapsed);
+        InterestRateManager::accrue_interest_for_position(
+            env,
+            &mut position,
+            state.current_borrow_rate,
+            state.liquidity_index,
+        );
+        StateHelper::save_position(env, &position);
+        Ok((position, fee))
+    }
+
+    fn ensure_healthy(
+        env: &Env,
+        user: &Address,
+        collateral: i128,
+        debt: i128,
+    ) -> Result<(), ProtocolError> {
+        CrossAssetManager::ensure_prices_fresh(env, user)?;
+        let ratio = CrossAssetManager::collateral_ratio(env, user, collateral, debt)?;
+        if ratio < ProtocolConfig::get_action_min_ratio(env) {
+            return Err(ProtocolError::InsufficientCollateralRatio);
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build(
+        env: &Env,
+        position: &Position,
+        amount: i128,
+        shares: i128,
+        interest: i128,
+        fee: i128,
+        collateral_after: i128,
+        debt_after: i128,
+    ) -> Result<ActionPreview, ProtocolError> {
+        Ok(ActionPreview {
+            amount,
+            shares,
+            interest,
+            fee,
+            collateral_after,
+            debt_after,
+            collateral_ratio: CrossAssetManager::collateral_ratio(
+                env,
+                &position.user,
+                collateral_after,
+                debt_after,
+            )?,
+        })
+    }
+}
@@ -0,0 +1,178 @@
+//! Price push module for StellarLend protocol
+//! Pull-based oracle updates: price providers sign payloads off-chain and anyone can bring
+//! them on-chain with `update_price_feeds`, e.g. in the same transaction as a borrow. Each
+//! payload must carry an ed25519 signature from a registered provider key over this
+//! contract, the asset, the price, its confidence interval and the publish time. Payloads
+//! no newer than the asset's last pushed price are refused, so they cannot be replayed,
+//! and the submitter pays an update fee per payload in the primary asset.
+
+use crate::cross_asset::CrossAssetManager;
+use crate::events::SequencedEvents;
+use crate::oracle::Oracle;
+use crate::revenue::RevenueManager;
+use crate::storage::DataKey;
+use crate::{ProtocolConfig, ProtocolError, TransferEnforcer};
+use soroban_sdk::{contracttype, xdr::ToXdr, Address, Bytes, BytesN, Env, Map, Symbol, Vec};
+
+/// Most payloads accepted by a single `update_price_feeds` call
+pub const MAX_PRICE_PAYLOADS: u32 = 10;
+
+/// Provider keys and fee for signed price updates
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PricePushConfig {
+    /// Ed25519 public keys whose signatures are accepted
+    pub signers: Vec<BytesN<32>>,
+    /// Fee per payload, in the primary asset
+    pub update_fee: i128,
+}
+
+/// Price observation signed by a provider
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct PricePayload {
+    pub asset: Address,
+    /// Price in 1e8 scale, denominated in the primary asset
+    pub price: i128,
+    /// Confidence interval in the price scale
+    pub confidence: i128,
+    /// Ledger timestamp at which the provider observed the price
+    pub publish_time: u64,
+    pub signer: BytesN<32>,
+    pub signature: BytesN<64>,
+}
+
+/// Storage helper for signed price updates
+pub struct PricePushStorage;
+
+impl PricePushStorage {
+    fn config_key() -> DataKey {
+        DataKey::PricePushConfig
+    }
+
+    fn times_key() -> DataKey {
+        DataKey::PricePushTimes
+    }
+
+    pub fn get_config(env: &Env) -> Option<PricePushConfig> {
+        env.storage().instance().get(&Self::config_key())
+    }
+
+    pub fn put_config(env: &Env, config: &Option<PricePushConfig>) {
+        match config {
+            Some(config) => env.storage().instance().set(&Self::config_key(), config),
+            None => env.storage().instance().remove(&Self::config_key()),
+        }
+    }
+
+    /// Publish time of the last payload accepted for each asset
+    pub fn get_times(env: &Env) -> Map<Address, u64> {
+        env.storage()
+            .instance()
+            .get(&Self::times_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn put_times(env: &Env, times: &Map<Address, u64>) {
+        env.storage().instance().set(&Self::times_key(), times);
+    }
+}
+
+/// Verification and recording of signed price payloads
+pub struct PricePush;
+
+impl PricePush {
+    /// Admin: register provider keys and the update fee, or (with `None`) stop accepting
+    /// signed payloads
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: Option<PricePushConfig>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if let Some(config) = &config {
+            if config.signers.is_empty() || config.update_fee < 0 {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+        PricePushStorage::put_config(env, &config);
+        env.publish_event(
+            (
+                Symbol::new(env, "price_push_config_updated"),
+                caller.clone(),
+            ),
+            config.is_some(),
+        );
+        Ok(())
+    }
+
+    /// Bytes a provider signs for a payload
+    pub fn message(env: &Env, payload: &PricePayload) -> Bytes {
+        (
+            env.current_contract_address(),
+            payload.asset.clone(),
+            payload.price,
+            payload.confidence,
+            payload.publish_time,
+        )
+            .to_xdr(env)
+    }
+
+    /// Verify each payload, record its price and charge `payer` the update fee for all of
+    /// them. An invalid signature aborts the whole call. Returns the fee charged.
+    pub fn update(
+        env: &Env,
+        payer: &Address,
+        payloads: &Vec<PricePayload>,
+    ) -> Result<i128, ProtocolError> {
+        let config = PricePushStorage::get_config(env).ok_or(ProtocolError::ConfigurationError)?;
+        if payloads.is_empty() || payloads.len() > MAX_PRICE_PAYLOADS {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        let now = env.ledger().timestamp();
+        let mut times = PricePushStorage::get_times(env);
+        for payload in payloads.iter() {
+            if !config.signers.contains(&payload.signer) {
+                return Err(ProtocolError::Unauthorized);
+            }
+            if payload.publish_time > now
+                || now - payload.publish_time > Oracle::heartbeat(env, &payload.asset)
+            {
+                return Err(ProtocolError::OracleStale);
+            }
+            if times
+                .get(payload.asset.clone())
+                .is_some_and(|last| payload.publish_time <= last)
+            {
+                return Err(ProtocolError::OracleStale);
+            }
+            env.crypto().ed25519_verify(
+                &payload.signer,
+                &Self::message(env, &payload),
+                &payload.signature,
+            );
+
+            CrossAssetManager::record_pushed_price(
+                env,
+                &payload.asset,
+                payload.price,
+                payload.confidence,
+            )?;
+            times.set(payload.asset.clone(), payload.publish_time);
+            env.publish_event(
+                (Symbol::new(env, "price_pushed"), payload.asset.clone()),
+                (payload.price, payload.confidence, payload.publish_time),
+            );
+        }
+        PricePushStorage::put_times(env, &times);
+
+        let fee = config.update_fee * payloads.len() as i128;
+        if fee > 0 {
+            let received =
+                TransferEnforcer::transfer_in(env, payer, fee, Symbol::new(env, "price_update"))?;
+            RevenueManager::record_fees(env, received);
+        }
+        Ok(fee)
+    }
+}
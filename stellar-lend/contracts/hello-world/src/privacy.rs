@@ -0,0 +1,97 @@
+//! Privacy module for StellarLend protocol
+//! Self-serve account deletion for regulated deployments: a debt-free user can close
+//! out their balances and have the contract forget the records it keeps about them
+
+use crate::analytics::AnalyticsStorage;
+use crate::cross_asset::{CollateralUsageStorage, CrossAssetManager, CrossStateHelper};
+use crate::events::SequencedEvents;
+use crate::grace::GraceStorage;
+use crate::history::PositionHistoryStorage;
+use crate::liquidate::SeizureOrderStorage;
+use crate::notify::NotificationStorage;
+use crate::quote::QuoteStorage;
+use crate::repay::{AutoRepayStorage, RepayModule};
+use crate::session::SessionManager;
+use crate::staking::StakingStorage;
+use crate::statement::FeeStatementStorage;
+use crate::{
+    EventStorage, FreezeLevel, InterestRateManager, InterestRateStorage, ProtocolError,
+    StateHelper, TransferEnforcer, UserManager, VerificationStatus,
+};
+use soroban_sdk::{Address, Env, Symbol, Vec};
+
+/// Account deletion
+pub struct AccountPurge;
+
+impl AccountPurge {
+    /// Return the user's collateral and accrued supply interest in every asset, then
+    /// delete their positions, activity records, KYC profile and session grants and
+    /// publish a tombstone. Fails while any debt is outstanding, while tokens are still
+    /// staked, or for frozen and rejected accounts so a purge cannot clear a compliance
+    /// restriction. Returns the primary asset paid out.
+    pub fn purge(env: &Env, user: &Address) -> Result<i128, ProtocolError> {
+        let profile = UserManager::view_profile(env, user);
+        if profile.freeze_level(env) != FreezeLevel::None
+            || profile.verification == VerificationStatus::Rejected
+        {
+            return Err(ProtocolError::UserSuspended);
+        }
+        if StakingStorage::get_stake(env, user).amount > 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+
+        let mut returned = 0;
+        if let Some(mut position) = StateHelper::get_position(env, user) {
+            let state = InterestRateStorage::update_state(env);
+            InterestRateManager::accrue_interest_for_position(
+                env,
+                &mut position,
+                state.current_borrow_rate,
+                state.liquidity_index,
+            );
+            if RepayModule::payoff_amount(&position) > 0 {
+                return Err(ProtocolError::InvalidOperation);
+            }
+            returned = position.collateral + position.supply_interest;
+            position.collateral = 0;
+            position.supply_interest = 0;
+            StateHelper::save_position(env, &position);
+            if returned > 0 {
+                TransferEnforcer::transfer_out(
+                    env,
+                    user,
+                    returned,
+                    Symbol::new(env, "purge_account"),
+                )?;
+            }
+            StateHelper::remove_position(env, user);
+        }
+
+        let cross = CrossStateHelper::get_or_init_position(env, user);
+        for (asset, amount) in cross.collateral.iter() {
+            if amount > 0 {
+                CrossAssetManager::withdraw(env, user, &asset, amount, 0, 0)?;
+            }
+        }
+        CrossStateHelper::remove_position(env, user);
+        CollateralUsageStorage::put_disabled(env, user, &Vec::new(env));
+        SeizureOrderStorage::save_user(env, user, &Vec::new(env));
+        GraceStorage::put_tranches(env, user, &Vec::new(env));
+        QuoteStorage::remove_lock(env, user);
+        AutoRepayStorage::set_cap(env, user, 0);
+
+        PositionHistoryStorage::remove(env, user);
+        FeeStatementStorage::remove(env, user);
+        AnalyticsStorage::forget_user(env, user);
+        EventStorage::forget_user(env, user);
+        SessionManager::clear(env, user);
+        NotificationStorage::save(env, user, &None);
+        UserManager::forget_user(env, user);
+
+        env.publish_event(
+            (Symbol::new(env, "account_purged"), user.clone()),
+            env.ledger().timestamp(),
+        );
+        Ok(returned)
+    }
+}
@@ -0,0 +1,160 @@
+//! Quote module for StellarLend protocol
+//! Short-lived borrow rate quotes that integrators can display and then honor: a borrow
+//! executed against a live quote accrues at the quoted rate until its first accrual
+
+use crate::cross_asset::CrossAssetManager;
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{InterestRateStorage, ProtocolError, SafeMath, TokenRegistry};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Ledgers a borrow quote stays valid for after it is issued
+pub const QUOTE_VALIDITY_LEDGERS: u32 = 12;
+
+/// Borrow rate offered for `amount` of `asset` until `expires_at_ledger`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BorrowQuote {
+    pub id: u64,
+    pub asset: Address,
+    pub amount: i128,
+    /// Annual borrow rate (scaled by 1e8)
+    pub rate: i128,
+    /// Last ledger sequence in which the quote can be executed
+    pub expires_at_ledger: u32,
+}
+
+/// Quoted debt that accrues at `rate` until the position's next accrual
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RateLock {
+    pub amount: i128,
+    pub rate: i128,
+}
+
+/// Storage helper for quotes and rate locks; quotes live in temporary storage and
+/// expire with their validity window
+pub struct QuoteStorage;
+
+impl QuoteStorage {
+    fn quote_key(id: u64) -> DataKey {
+        DataKey::BorrowQuote(id)
+    }
+
+    fn next_id_key() -> DataKey {
+        DataKey::BorrowQuoteSeq
+    }
+
+    fn lock_key(user: &Address) -> DataKey {
+        DataKey::RateLock(user.clone())
+    }
+
+    pub fn next_id(env: &Env) -> u64 {
+        let id: u64 = env
+            .storage()
+            .instance()
+            .get(&Self::next_id_key())
+            .unwrap_or(0)
+            + 1;
+        env.storage().instance().set(&Self::next_id_key(), &id);
+        id
+    }
+
+    pub fn get_quote(env: &Env, id: u64) -> Option<BorrowQuote> {
+        env.storage().temporary().get(&Self::quote_key(id))
+    }
+
+    pub fn save_quote(env: &Env, quote: &BorrowQuote) {
+        let key = Self::quote_key(quote.id);
+        env.storage().temporary().set(&key, quote);
+        env.storage()
+            .temporary()
+            .extend_ttl(&key, QUOTE_VALIDITY_LEDGERS, QUOTE_VALIDITY_LEDGERS);
+    }
+
+    pub fn remove_quote(env: &Env, id: u64) {
+        env.storage().temporary().remove(&Self::quote_key(id));
+    }
+
+    pub fn get_lock(env: &Env, user: &Address) -> Option<RateLock> {
+        env.storage().persistent().get(&Self::lock_key(user))
+    }
+
+    pub fn save_lock(env: &Env, user: &Address, lock: &RateLock) {
+        env.storage().persistent().set(&Self::lock_key(user), lock);
+    }
+
+    pub fn remove_lock(env: &Env, user: &Address) {
+        env.storage().persistent().remove(&Self::lock_key(user));
+    }
+}
+
+/// Borrow quote issuance and rate locks
+pub struct RateQuotes;
+
+impl RateQuotes {
+    /// Quote the current borrow rate for `amount` of `asset`, valid for
+    /// QUOTE_VALIDITY_LEDGERS ledgers
+    pub fn quote(env: &Env, asset: &Address, amount: i128) -> Result<BorrowQuote, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if *asset != TokenRegistry::require_primary_asset(env)? {
+            CrossAssetManager::get_asset_params(env, asset)?;
+        }
+        let quote = BorrowQuote {
+            id: QuoteStorage::next_id(env),
+            asset: asset.clone(),
+            amount,
+            rate: InterestRateStorage::update_state(env).current_borrow_rate,
+            expires_at_ledger: env.ledger().sequence() + QUOTE_VALIDITY_LEDGERS,
+        };
+        QuoteStorage::save_quote(env, &quote);
+        env.publish_event(
+            (Symbol::new(env, "borrow_quoted"), asset.clone()),
+            (quote.id, amount, quote.rate, quote.expires_at_ledger),
+        );
+        Ok(quote)
+    }
+
+    /// Consume a live quote; each quote can be executed once
+    pub fn take(env: &Env, id: u64) -> Result<BorrowQuote, ProtocolError> {
+        let quote = QuoteStorage::get_quote(env, id).ok_or(ProtocolError::NotFound)?;
+        if env.ledger().sequence() > quote.expires_at_ledger {
+            return Err(ProtocolError::NotFound);
+        }
+        QuoteStorage::remove_quote(env, id);
+        Ok(quote)
+    }
+
+    /// Lock `rate` for `amount` of the user's debt until the next accrual, blending with any
+    /// lock still pending
+    pub fn lock(env: &Env, user: &Address, amount: i128, rate: i128) {
+        let lock = match QuoteStorage::get_lock(env, user) {
+            Some(existing) => {
+                let total = existing.amount + amount;
+                RateLock {
+                    amount: total,
+                    rate: (existing.amount * existing.rate + amount * rate) / total,
+                }
+            }
+            None => RateLock { amount, rate },
+        };
+        QuoteStorage::save_lock(env, user, &lock);
+    }
+
+    /// Rate to accrue `debt` at for the current period, blending any locked debt at its
+    /// quoted rate with the rest at `market_rate`. The lock is spent by this accrual.
+    pub fn accrual_rate(env: &Env, user: &Address, debt: i128, market_rate: i128) -> i128 {
+        let Some(lock) = QuoteStorage::get_lock(env, user) else {
+            return market_rate;
+        };
+        QuoteStorage::remove_lock(env, user);
+        if debt <= 0 {
+            return market_rate;
+        }
+        let locked = lock.amount.min(debt);
+        SafeMath::mul_div(locked, lock.rate, debt)
+            + SafeMath::mul_div(debt - locked, market_rate, debt)
+    }
+}
@@ -0,0 +1,133 @@
+//! Rebate module for StellarLend protocol
+//! Redirects a share of each liquidation bonus into a pot for the liquidated borrower,
+//! claimable once the position is healthy again and has stayed liquidation-free for a while
+
+use crate::cross_asset::CrossAssetManager;
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{ProtocolConfig, ProtocolError, StateHelper, TransferEnforcer};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Admin-managed liquidation tax settings
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct RebateConfig {
+    /// Share of the liquidation bonus withheld from keepers (basis points)
+    pub tax_bps: i128,
+    /// Seconds a borrower must stay liquidation-free before claiming
+    pub cooldown: u64,
+}
+
+/// Liquidation tax accumulated for one borrower
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct RebatePot {
+    pub amount: i128,
+    pub last_liquidated: u64,
+}
+
+/// Storage helper for rebate settings and pots
+pub struct RebateStorage;
+
+impl RebateStorage {
+    fn config_key() -> DataKey {
+        DataKey::RebateConfig
+    }
+
+    fn pot_key(user: &Address) -> DataKey {
+        DataKey::RebatePot(user.clone())
+    }
+
+    pub fn get_config(env: &Env) -> RebateConfig {
+        env.storage()
+            .instance()
+            .get(&Self::config_key())
+            .unwrap_or_default()
+    }
+
+    pub fn save_config(env: &Env, config: &RebateConfig) {
+        env.storage().instance().set(&Self::config_key(), config);
+    }
+
+    pub fn get_pot(env: &Env, user: &Address) -> RebatePot {
+        env.storage()
+            .persistent()
+            .get(&Self::pot_key(user))
+            .unwrap_or_default()
+    }
+
+    pub fn save_pot(env: &Env, user: &Address, pot: &RebatePot) {
+        env.storage().persistent().set(&Self::pot_key(user), pot);
+    }
+
+    pub fn remove_pot(env: &Env, user: &Address) {
+        env.storage().persistent().remove(&Self::pot_key(user));
+    }
+}
+
+/// Liquidation tax collection and rebate claims
+pub struct RebateManager;
+
+impl RebateManager {
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: RebateConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=10000).contains(&config.tax_bps) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        RebateStorage::save_config(env, &config);
+        env.publish_event(
+            (Symbol::new(env, "rebate_config_updated"), caller.clone()),
+            (config.tax_bps, config.cooldown),
+        );
+        Ok(())
+    }
+
+    /// Portion of a liquidation `bonus` withheld from the keeper
+    pub fn tax_for(env: &Env, bonus: i128) -> i128 {
+        bonus.max(0) * RebateStorage::get_config(env).tax_bps / 10000
+    }
+
+    /// Credit the tax on a liquidation bonus to the liquidated user's pot and restart
+    /// their cooldown; returns the amount credited
+    pub fn collect(env: &Env, user: &Address, bonus: i128) -> i128 {
+        let tax = Self::tax_for(env, bonus);
+        let mut pot = RebateStorage::get_pot(env, user);
+        if tax > 0 || pot.amount > 0 {
+            pot.amount += tax;
+            pot.last_liquidated = env.ledger().timestamp();
+            RebateStorage::save_pot(env, user, &pot);
+        }
+        tax
+    }
+
+    /// Pay out the user's pot once their position is healthy and the cooldown has passed
+    pub fn claim(env: &Env, user: &Address) -> Result<i128, ProtocolError> {
+        let pot = RebateStorage::get_pot(env, user);
+        if pot.amount <= 0 {
+            return Err(ProtocolError::NotFound);
+        }
+        let config = RebateStorage::get_config(env);
+        if env.ledger().timestamp() < pot.last_liquidated + config.cooldown {
+            return Err(ProtocolError::RateLimitExceeded);
+        }
+        if let Some(position) = StateHelper::get_position(env, user) {
+            let ratio =
+                CrossAssetManager::collateral_ratio(env, user, position.collateral, position.debt)?;
+            if position.debt > 0 && ratio < ProtocolConfig::get_min_collateral_ratio(env) {
+                return Err(ProtocolError::InsufficientCollateralRatio);
+            }
+        }
+
+        RebateStorage::remove_pot(env, user);
+        TransferEnforcer::transfer_out(env, user, pot.amount, Symbol::new(env, "rebate"))?;
+        env.publish_event(
+            (Symbol::new(env, "rebate_claimed"), user.clone()),
+            pot.amount,
+        );
+        Ok(pot.amount)
+    }
+}
@@ -0,0 +1,262 @@
+//! Receipt module for StellarLend protocol
+//! Exposes primary-asset collateral as a SEP-41 compatible deposit receipt (sToken) so
+//! wallets can show deposits as balances and other protocols can hold or pledge them.
+//! One receipt unit represents one unit of primary collateral.
+
+use crate::compliance::ComplianceGate;
+use crate::limits::BorrowLimits;
+use crate::storage::DataKey;
+use crate::units::Units;
+use crate::withdraw::WithdrawModule;
+use crate::{
+    InterestRateManager, InterestRateStorage, OperationKind, Position, ProtocolError, StateHelper,
+    TokenRegistry, TransferEnforcer,
+};
+use soroban_sdk::{contracttype, Address, Env, String, Symbol};
+
+/// Amount a spender may move from an owner's receipt balance
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ReceiptAllowance {
+    pub amount: i128,
+    /// Last ledger sequence on which the allowance can be used
+    pub expiration_ledger: u32,
+}
+
+/// Storage helper for receipt allowances and decimals
+pub struct ReceiptStorage;
+
+impl ReceiptStorage {
+    fn decimals_key() -> DataKey {
+        DataKey::ReceiptDecimals
+    }
+
+    pub fn get_decimals(env: &Env) -> Option<u32> {
+        env.storage().instance().get(&Self::decimals_key())
+    }
+
+    pub fn set_decimals(env: &Env, decimals: u32) {
+        env.storage()
+            .instance()
+            .set(&Self::decimals_key(), &decimals);
+    }
+
+    fn allowance_key(from: &Address, spender: &Address) -> DataKey {
+        DataKey::ReceiptAllowance(from.clone(), spender.clone())
+    }
+
+    pub fn get_allowance(env: &Env, from: &Address, spender: &Address) -> Option<ReceiptAllowance> {
+        env.storage()
+            .persistent()
+            .get(&Self::allowance_key(from, spender))
+    }
+
+    pub fn save_allowance(
+        env: &Env,
+        from: &Address,
+        spender: &Address,
+        allowance: &ReceiptAllowance,
+    ) {
+        let key = Self::allowance_key(from, spender);
+        if allowance.amount == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, allowance);
+        }
+    }
+}
+
+/// SEP-41 token behaviour over supplier positions
+pub struct ReceiptToken;
+
+impl ReceiptToken {
+    pub fn name(env: &Env) -> String {
+        String::from_str(env, "StellarLend Deposit Receipt")
+    }
+
+    pub fn symbol(env: &Env) -> String {
+        String::from_str(env, "sLEND")
+    }
+
+    /// Decimals of the primary asset, since one receipt unit is one unit of it
+    pub fn decimals(env: &Env) -> u32 {
+        ReceiptStorage::get_decimals(env).unwrap_or(0)
+    }
+
+    /// Take on the decimals of `primary`, read from the token when it becomes the
+    /// primary asset
+    pub fn sync_decimals(env: &Env, primary: &Address) -> Result<(), ProtocolError> {
+        ReceiptStorage::set_decimals(env, Units::asset_decimals(env, primary)?);
+        Ok(())
+    }
+
+    pub fn balance(env: &Env, id: &Address) -> i128 {
+        StateHelper::get_position(env, id)
+            .map(|position| position.collateral)
+            .unwrap_or(0)
+    }
+
+    /// Unexpired allowance granted by `from` to `spender`
+    pub fn allowance(env: &Env, from: &Address, spender: &Address) -> i128 {
+        match ReceiptStorage::get_allowance(env, from, spender) {
+            Some(allowance) if allowance.expiration_ledger >= env.ledger().sequence() => {
+                allowance.amount
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn approve(
+        env: &Env,
+        from: &Address,
+        spender: &Address,
+        amount: i128,
+        expiration_ledger: u32,
+    ) -> Result<(), ProtocolError> {
+        from.require_auth();
+        if amount < 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if amount > 0 && expiration_ledger < env.ledger().sequence() {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        ReceiptStorage::save_allowance(
+            env,
+            from,
+            spender,
+            &ReceiptAllowance {
+                amount,
+                expiration_ledger,
+            },
+        );
+        env.events().publish(
+            (Symbol::new(env, "approve"), from.clone(), spender.clone()),
+            (amount, expiration_ledger),
+        );
+        Ok(())
+    }
+
+    pub fn transfer(
+        env: &Env,
+        from: &Address,
+        to: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        from.require_auth();
+        Self::move_balance(env, from, to, amount)
+    }
+
+    pub fn transfer_from(
+        env: &Env,
+        spender: &Address,
+        from: &Address,
+        to: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        spender.require_auth();
+        Self::spend_allowance(env, from, spender, amount)?;
+        Self::move_balance(env, from, to, amount)
+    }
+
+    /// Burning receipts redeems them for the underlying primary asset
+    pub fn burn(env: &Env, from: &Address, amount: i128) -> Result<(), ProtocolError> {
+        from.require_auth();
+        Self::redeem(env, from, amount)
+    }
+
+    pub fn burn_from(
+        env: &Env,
+        spender: &Address,
+        from: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        spender.require_auth();
+        Self::spend_allowance(env, from, spender, amount)?;
+        Self::redeem(env, from, amount)
+    }
+
+    fn spend_allowance(
+        env: &Env,
+        from: &Address,
+        spender: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        let available = Self::allowance(env, from, spender);
+        if amount > available {
+            return Err(ProtocolError::InsufficientAllowance);
+        }
+        if let Some(mut allowance) = ReceiptStorage::get_allowance(env, from, spender) {
+            allowance.amount = available - amount;
+            ReceiptStorage::save_allowance(env, from, spender, &allowance);
+        }
+        Ok(())
+    }
+
+    /// Take `amount` of collateral out of `from`'s position under the same checks as a withdrawal.
+    /// The returned position has been accrued but not saved.
+    fn debit(env: &Env, from: &Address, amount: i128) -> Result<Position, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        ComplianceGate::check(env, from, OperationKind::Withdraw, amount)?;
+
+        let mut position =
+            StateHelper::get_position(env, from).ok_or(ProtocolError::PositionNotFound)?;
+        let state = InterestRateStorage::update_state(env);
+        InterestRateManager::accrue_interest_for_position(
+            env,
+            &mut position,
+            state.current_borrow_rate,
+            state.liquidity_index,
+        );
+        if position.collateral < amount {
+            return Err(ProtocolError::InsufficientCollateral);
+        }
+        position.collateral -= amount;
+        WithdrawModule::ensure_can_release(env, from, position.collateral, position.debt)?;
+        Ok(position)
+    }
+
+    fn move_balance(
+        env: &Env,
+        from: &Address,
+        to: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        if from == to {
+            return Err(ProtocolError::InvalidAddress);
+        }
+        let sender = Self::debit(env, from, amount)?;
+        ComplianceGate::check(env, to, OperationKind::Deposit, amount)?;
+
+        let mut recipient =
+            StateHelper::get_position(env, to).unwrap_or_else(|| Position::new(to.clone(), 0, 0));
+        let state = InterestRateStorage::get_state(env);
+        InterestRateManager::accrue_interest_for_position(
+            env,
+            &mut recipient,
+            state.current_borrow_rate,
+            state.liquidity_index,
+        );
+        recipient.collateral += amount;
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        BorrowLimits::ensure_position_size(env, to, &primary, recipient.collateral)?;
+
+        StateHelper::save_position(env, &sender);
+        StateHelper::save_position(env, &recipient);
+        env.events().publish(
+            (Symbol::new(env, "transfer"), from.clone(), to.clone()),
+            amount,
+        );
+        Ok(())
+    }
+
+    fn redeem(env: &Env, from: &Address, amount: i128) -> Result<(), ProtocolError> {
+        let position = Self::debit(env, from, amount)?;
+        StateHelper::save_position(env, &position);
+        TransferEnforcer::transfer_out(env, from, amount, Symbol::new(env, "burn"))?;
+        env.events()
+            .publish((Symbol::new(env, "burn"), from.clone()), amount);
+        Ok(())
+    }
+}
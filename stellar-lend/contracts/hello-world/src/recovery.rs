@@ -0,0 +1,178 @@
+//! Recovery module for StellarLend protocol
+//! Automatic recovery mode for when protocol-wide collateralization deteriorates. Once the
+//! value of all collateral held falls below a configured multiple of outstanding debt,
+//! pre-configured multipliers tighten borrowing power, raise the liquidation bonus and
+//! freeze borrowing of risky assets on top of the normal parameters, until the ratio
+//! climbs back above the exit threshold. Unlike the emergency `Recovery` status this
+//! needs no admin action to enter or leave.
+
+use crate::cross_asset::{AssetRegistryStorage, CrossAssetManager};
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{InterestRateStorage, ProtocolConfig, ProtocolError, TokenRegistry};
+use soroban_sdk::{contracttype, token::TokenClient, Address, Env, Symbol, Vec};
+
+/// Thresholds and multipliers of recovery mode
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RecoveryConfig {
+    /// Global collateral-to-debt ratio (percent) below which recovery mode starts
+    pub enter_ratio: i128,
+    /// Ratio (percent) the protocol must regain before recovery mode ends
+    pub exit_ratio: i128,
+    /// Applied to borrowing power while in recovery (scaled by 1e8, at most 1e8)
+    pub ltv_multiplier: i128,
+    /// Applied to the liquidation bonus while in recovery (scaled by 1e8, at least 1e8)
+    pub incentive_multiplier: i128,
+    /// Assets that cannot be borrowed while in recovery
+    pub frozen_assets: Vec<Address>,
+}
+
+/// Storage helper for recovery mode
+pub struct RecoveryStorage;
+
+impl RecoveryStorage {
+    fn config_key() -> DataKey {
+        DataKey::RecoveryConfig
+    }
+
+    fn active_key() -> DataKey {
+        DataKey::RecoveryActive
+    }
+
+    pub fn get_config(env: &Env) -> Option<RecoveryConfig> {
+        env.storage().instance().get(&Self::config_key())
+    }
+
+    pub fn put_config(env: &Env, config: &Option<RecoveryConfig>) {
+        match config {
+            Some(config) => env.storage().instance().set(&Self::config_key(), config),
+            None => env.storage().instance().remove(&Self::config_key()),
+        }
+    }
+
+    pub fn is_active(env: &Env) -> bool {
+        env.storage()
+            .instance()
+            .get(&Self::active_key())
+            .unwrap_or(false)
+    }
+
+    pub fn set_active(env: &Env, active: bool) {
+        env.storage().instance().set(&Self::active_key(), &active);
+    }
+}
+
+/// Automatic recovery mode
+pub struct RecoveryMode;
+
+impl RecoveryMode {
+    /// Admin: arm, retune or (with `None`) disarm recovery mode, re-evaluating it at once
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: Option<RecoveryConfig>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if let Some(config) = &config {
+            if config.enter_ratio <= 0
+                || config.exit_ratio < config.enter_ratio
+                || !(1..=100000000).contains(&config.ltv_multiplier)
+                || config.incentive_multiplier < 100000000
+            {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+        RecoveryStorage::put_config(env, &config);
+        env.publish_event(
+            (Symbol::new(env, "recovery_config_updated"), caller.clone()),
+            config.is_some(),
+        );
+        Self::refresh(env);
+        Ok(())
+    }
+
+    /// Value of all collateral the protocol holds as a percentage of outstanding debt;
+    /// zero when nothing is borrowed. Listed assets without a price count for nothing.
+    pub fn global_ratio(env: &Env) -> i128 {
+        let state = InterestRateStorage::get_state(env);
+        if state.total_borrowed <= 0 {
+            return 0;
+        }
+        let primary = TokenRegistry::require_primary_asset(env).ok();
+        let contract = env.current_contract_address();
+        let mut value = state.total_supplied;
+        for (asset, _) in AssetRegistryStorage::get_params_map(env).iter() {
+            if primary.as_ref() == Some(&asset) {
+                continue;
+            }
+            let held = TokenClient::new(env, &asset).balance(&contract);
+            value += CrossAssetManager::value_of_units(env, &asset, held).unwrap_or(0);
+        }
+        value * 100 / state.total_borrowed
+    }
+
+    /// Enter or leave recovery mode according to the current global ratio; returns
+    /// whether it is active
+    pub fn refresh(env: &Env) -> bool {
+        let active = RecoveryStorage::is_active(env);
+        let (next, ratio) = match RecoveryStorage::get_config(env) {
+            Some(config) => {
+                let ratio = Self::global_ratio(env);
+                let next = if ratio == 0 {
+                    false
+                } else if active {
+                    ratio < config.exit_ratio
+                } else {
+                    ratio < config.enter_ratio
+                };
+                (next, ratio)
+            }
+            None => (false, 0),
+        };
+
+        if next != active {
+            RecoveryStorage::set_active(env, next);
+            let topic = if next {
+                "recovery_mode_entered"
+            } else {
+                "recovery_mode_exited"
+            };
+            env.publish_event((Symbol::new(env, topic),), ratio);
+        }
+        next
+    }
+
+    fn active_config(env: &Env) -> Option<RecoveryConfig> {
+        if !RecoveryStorage::is_active(env) {
+            return None;
+        }
+        RecoveryStorage::get_config(env)
+    }
+
+    /// Borrowing power after the recovery LTV multiplier
+    pub fn borrowing_power(env: &Env, power: i128) -> i128 {
+        match Self::active_config(env) {
+            Some(config) => power * config.ltv_multiplier / 100000000,
+            None => power,
+        }
+    }
+
+    /// Liquidation bonus after the recovery incentive multiplier, capped at 100%
+    pub fn liquidation_incentive(env: &Env, incentive: i128) -> i128 {
+        match Self::active_config(env) {
+            Some(config) => (incentive * config.incentive_multiplier / 100000000).min(100000000),
+            None => incentive,
+        }
+    }
+
+    /// Refuse borrowing an asset frozen by recovery mode
+    pub fn ensure_borrowable(env: &Env, asset: &Address) -> Result<(), ProtocolError> {
+        match Self::active_config(env) {
+            Some(config) if config.frozen_assets.contains(asset) => {
+                Err(ProtocolError::RecoveryModeRestricted)
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Relay module for StellarLend protocol
+//! Fee-sponsored actions: a user signs a structured action once and any relayer can
+//! submit it through `execute_signed`, paying the transaction fee. The signature travels
+//! in the user's authorization entry for `execute_signed(user, payload)`, so the user's
+//! account (a plain key or a custom account contract) verifies it like any other call.
+//! A per-user nonce and a deadline keep a signed payload from being replayed or held back.
+
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{borrow, deposit, repay, withdraw, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Action a relayer may submit on a user's behalf, with its amount
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum RelayAction {
+    Deposit(i128),
+    Borrow(i128),
+    Repay(i128),
+    Withdraw(i128),
+}
+
+/// Payload a user signs for a relayer to submit
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SignedAction {
+    /// Must equal the user's next relay nonce
+    pub nonce: u64,
+    /// Last ledger timestamp at which the payload may be executed
+    pub deadline: u64,
+    pub action: RelayAction,
+}
+
+/// Storage helper for relay nonces
+pub struct RelayStorage;
+
+impl RelayStorage {
+    fn nonce_key(user: &Address) -> DataKey {
+        DataKey::RelayNonce(user.clone())
+    }
+
+    /// Nonce the user's next signed action must carry
+    pub fn get_nonce(env: &Env, user: &Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&Self::nonce_key(user))
+            .unwrap_or(0)
+    }
+
+    pub fn set_nonce(env: &Env, user: &Address, nonce: u64) {
+        env.storage()
+            .persistent()
+            .set(&Self::nonce_key(user), &nonce);
+    }
+}
+
+/// Execution of signed actions submitted by relayers
+pub struct Relay;
+
+impl Relay {
+    /// Spend the payload's nonce and run its action as `user`. Every action requires the
+    /// user's authorization for this invocation, payload included, so the relayer cannot
+    /// alter anything the user signed.
+    pub fn execute(env: &Env, user: &Address, payload: SignedAction) -> Result<(), ProtocolError> {
+        if env.ledger().timestamp() > payload.deadline {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        let nonce = RelayStorage::get_nonce(env, user);
+        if payload.nonce != nonce {
+            return Err(ProtocolError::InvalidInput);
+        }
+        RelayStorage::set_nonce(env, user, nonce + 1);
+
+        match payload.action {
+            RelayAction::Deposit(amount) => {
+                deposit::DepositModule::deposit_collateral(env, user, amount)
+            }
+            RelayAction::Borrow(amount) => borrow::BorrowModule::borrow(env, user, amount),
+            RelayAction::Repay(amount) => repay::RepayModule::repay(env, user, amount),
+            RelayAction::Withdraw(amount) => withdraw::WithdrawModule::withdraw(env, user, amount),
+        }?;
+
+        env.publish_event(
+            (Symbol::new(env, "signed_action_executed"), user.clone()),
+            nonce,
+        );
+        Ok(())
+    }
+}
@@ -1,12 +1,18 @@
 //! Repay module for StellarLend protocol
 //! Handles debt repayment functionality and related operations
 
-use crate::analytics::AnalyticsModule;
+use crate::analytics::{ActivityKind, AnalyticsModule};
+use crate::compliance::ComplianceGate;
+use crate::events::SequencedEvents;
+use crate::history::PositionHistory;
+use crate::session::SessionManager;
+use crate::storage::DataKey;
+use crate::ttl::Ttl;
 use crate::{
-    EmergencyManager, InterestRateManager, InterestRateStorage, OperationKind, ProtocolError,
-    ProtocolEvent, ReentrancyGuard, StateHelper, TransferEnforcer, UserManager,
+    ErrorContext, InterestRateManager, InterestRateStorage, OperationKind, Position, ProtocolError,
+    ProtocolEvent, ReentrancyScope, StateHelper, TransferEnforcer, UserManager,
 };
-use soroban_sdk::{contracterror, contracttype, Address, Env, String, Symbol};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol};
 
 /// Repay-specific errors
 #[contracterror]
@@ -71,21 +77,60 @@ impl RepayParams {
     }
 }
 
+/// Storage helper for keeper-driven repayment caps
+pub struct AutoRepayStorage;
+
+impl AutoRepayStorage {
+    fn cap_key(user: &Address) -> DataKey {
+        DataKey::AutoRepayCap(user.clone())
+    }
+
+    /// Remaining amount keepers may pull from `user` for repayments
+    pub fn get_cap(env: &Env, user: &Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&Self::cap_key(user))
+            .unwrap_or(0)
+    }
+
+    pub fn set_cap(env: &Env, user: &Address, cap: i128) {
+        let key = Self::cap_key(user);
+        if cap == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &cap);
+        }
+    }
+}
+
 /// Repay module implementation
 pub struct RepayModule;
 
 impl RepayModule {
     /// Repay borrowed assets
     pub fn repay(env: &Env, repayer: &Address, amount: i128) -> Result<(), ProtocolError> {
-        ReentrancyGuard::enter(env)?;
+        Self::repay_for(env, repayer, repayer, amount)
+    }
+
+    /// Repay `repayer`'s debt with funds from `payer`.
+    /// A payer other than the borrower must hold a session allowing repayments.
+    pub fn repay_for(
+        env: &Env,
+        payer: &Address,
+        repayer: &Address,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        let _guard =
+            ErrorContext::report(env, ReentrancyScope::enter(env), "repay", repayer, amount)?;
+        Ttl::extend_instance(env);
         let result = (|| -> Result<(), ProtocolError> {
+            payer.require_auth();
             if amount <= 0 {
                 return Err(RepayError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Repay)?;
-
-            UserManager::ensure_operation_allowed(env, repayer, OperationKind::Repay, amount)?;
+            ComplianceGate::check(env, repayer, OperationKind::Repay, amount)?;
+            SessionManager::authorize(env, repayer, payer, OperationKind::Repay, amount)?;
 
             // Load user position
             let mut position = match StateHelper::get_position(env, repayer) {
@@ -99,21 +144,24 @@ impl RepayModule {
                 env,
                 &mut position,
                 state.current_borrow_rate,
-                state.current_supply_rate,
+                state.liquidity_index,
             );
 
             // Check if user has debt to repay
-            if position.debt == 0 {
+            let payoff = Self::payoff_amount(&position);
+            if payoff == 0 {
                 return Err(RepayError::InvalidOperation.into());
             }
 
-            // Update position
-            let repay_amount = core::cmp::min(amount, position.debt);
+            // Pull no more than the exact payoff so overpayments stay with the payer
+            let repay_amount = core::cmp::min(amount, payoff);
 
-            TransferEnforcer::transfer_in(env, repayer, repay_amount, Symbol::new(env, "repay"))?;
+            let repay_amount =
+                TransferEnforcer::transfer_in(env, payer, repay_amount, Symbol::new(env, "repay"))?;
 
-            position.debt -= repay_amount;
+            let interest_paid = Self::apply_repayment(&mut position, repay_amount);
             StateHelper::save_position(env, &position);
+            Self::emit_settled(env, repayer, amount, repay_amount, interest_paid);
 
             // Emit event
             let collateral_ratio = if position.debt > 0 {
@@ -131,152 +179,132 @@ impl RepayModule {
             .emit(env);
 
             // Analytics
-            AnalyticsModule::record_activity(env, repayer, "repay", repay_amount, None)?;
+            AnalyticsModule::record_activity(
+                env,
+                repayer,
+                ActivityKind::Repay,
+                repay_amount,
+                None,
+            )?;
             UserManager::record_activity(env, repayer, OperationKind::Repay, repay_amount)?;
+            PositionHistory::record(env, repayer, ActivityKind::Repay, None, repay_amount);
 
             Ok(())
         })();
-
-        ReentrancyGuard::exit(env);
-        result
+        ErrorContext::report(env, result, "repay", repayer, amount)
     }
 
-    /// Repay debt for a specific asset
-    pub fn repay_asset(
-        env: &Env,
-        user: &String,
-        asset: &Address,
-        amount: i128,
-    ) -> Result<(), ProtocolError> {
-        ReentrancyGuard::enter(env)?;
-        let result = (|| -> Result<(), ProtocolError> {
-            if user.is_empty() {
-                return Err(RepayError::InvalidAddress.into());
-            }
-            if amount <= 0 {
-                return Err(RepayError::InvalidAmount.into());
-            }
-
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Repay)?;
-
-            let user_addr = Address::from_string(user);
-
-            // For cross-asset repayment, we would need to implement cross-asset position handling
-            // This is a simplified version for the modular structure
-            let mut position = match StateHelper::get_position(env, &user_addr) {
-                Some(pos) => pos,
-                None => return Err(RepayError::PositionNotFound.into()),
-            };
-
-            if position.debt == 0 {
-                return Err(RepayError::InvalidOperation.into());
-            }
-
-            let repay_amount = if amount > position.debt {
-                position.debt
-            } else {
-                amount
-            };
-            position.debt -= repay_amount;
-            StateHelper::save_position(env, &position);
+    /// Outstanding principal plus accrued borrow interest
+    pub fn payoff_amount(position: &Position) -> i128 {
+        position.debt + position.borrow_interest
+    }
 
-            // Emit cross-asset repay event
-            ProtocolEvent::CrossRepay(user_addr, asset.clone(), repay_amount).emit(env);
+    /// Apply a repayment to accrued interest first, then principal; returns the interest paid
+    pub(crate) fn apply_repayment(position: &mut Position, amount: i128) -> i128 {
+        let interest_paid = core::cmp::min(amount, position.borrow_interest);
+        position.borrow_interest -= interest_paid;
+        position.debt -= amount - interest_paid;
+        interest_paid
+    }
 
-            Ok(())
-        })();
+    fn emit_settled(env: &Env, user: &Address, requested: i128, repaid: i128, interest_paid: i128) {
+        env.publish_event(
+            (Symbol::new(env, "repay_settled"), user.clone()),
+            (requested, repaid, interest_paid),
+        );
+    }
 
-        ReentrancyGuard::exit(env);
-        result
+    /// Let keepers pull up to `cap` of the user's tokens (through the token allowance granted
+    /// to this contract) to repay their debt; zero opts out
+    pub fn set_auto_repay_cap(env: &Env, user: &Address, cap: i128) -> Result<(), ProtocolError> {
+        if cap < 0 {
+            return Err(RepayError::InvalidAmount.into());
+        }
+        AutoRepayStorage::set_cap(env, user, cap);
+        env.publish_event(
+            (Symbol::new(env, "auto_repay_cap_updated"), user.clone()),
+            cap,
+        );
+        Ok(())
     }
 
-    /// Full repayment of all debt
-    pub fn full_repay(env: &Env, repayer: &String) -> Result<i128, ProtocolError> {
-        ReentrancyGuard::enter(env)?;
+    /// Repay `user`'s debt from their token allowance on behalf of a keeper, consuming the
+    /// user's auto-repay cap. Returns the amount repaid.
+    pub fn pull_repay(
+        env: &Env,
+        keeper: &Address,
+        user: &Address,
+        amount: i128,
+    ) -> Result<i128, ProtocolError> {
+        let _guard = ReentrancyScope::enter(env)?;
         let result = (|| -> Result<i128, ProtocolError> {
-            if repayer.is_empty() {
-                return Err(RepayError::InvalidAddress.into());
+            keeper.require_auth();
+            if amount <= 0 {
+                return Err(RepayError::InvalidAmount.into());
             }
 
-            let repayer_addr = Address::from_string(repayer);
+            let cap = AutoRepayStorage::get_cap(env, user);
+            if cap == 0 {
+                return Err(ProtocolError::Unauthorized);
+            }
 
-            // Load user position
-            let mut position = match StateHelper::get_position(env, &repayer_addr) {
+            let mut position = match StateHelper::get_position(env, user) {
                 Some(pos) => pos,
                 None => return Err(RepayError::PositionNotFound.into()),
             };
-
-            // Accrue interest
             let state = InterestRateStorage::update_state(env);
             InterestRateManager::accrue_interest_for_position(
                 env,
                 &mut position,
                 state.current_borrow_rate,
-                state.current_supply_rate,
+                state.liquidity_index,
             );
-
-            let total_debt = position.debt;
-            if total_debt == 0 {
+            let payoff = Self::payoff_amount(&position);
+            if payoff == 0 {
                 return Err(RepayError::InvalidOperation.into());
             }
 
-            // Clear all debt
-            position.debt = 0;
+            let requested = core::cmp::min(amount, payoff);
+            if requested > cap {
+                return Err(ProtocolError::CapExceeded);
+            }
+            ComplianceGate::check(env, user, OperationKind::Repay, requested)?;
+
+            let repaid = TransferEnforcer::transfer_in_from_allowance(
+                env,
+                user,
+                requested,
+                Symbol::new(env, "pull_repay"),
+            )?;
+            AutoRepayStorage::set_cap(env, user, cap - requested);
+
+            let interest_paid = Self::apply_repayment(&mut position, repaid);
             StateHelper::save_position(env, &position);
+            Self::emit_settled(env, user, amount, repaid, interest_paid);
 
-            // Emit event
+            let collateral_ratio = if position.debt > 0 {
+                (position.collateral * 100) / position.debt
+            } else {
+                0
+            };
             ProtocolEvent::PositionUpdated(
-                repayer_addr.clone(),
+                user.clone(),
                 position.collateral,
                 position.debt,
-                0, // No debt means no ratio
+                collateral_ratio,
             )
             .emit(env);
+            env.publish_event(
+                (Symbol::new(env, "pull_repay"), keeper.clone(), user.clone()),
+                repaid,
+            );
 
-            // Analytics
-            AnalyticsModule::record_activity(env, &repayer_addr, "repay", total_debt, None)?;
+            AnalyticsModule::record_activity(env, user, ActivityKind::Repay, repaid, None)?;
+            UserManager::record_activity(env, user, OperationKind::Repay, repaid)?;
+            PositionHistory::record(env, user, ActivityKind::Repay, None, repaid);
 
-            Ok(total_debt)
+            Ok(repaid)
         })();
-
-        ReentrancyGuard::exit(env);
-        result
-    }
-
-    /// Validate repay parameters
-    pub fn validate_repay_params(params: &RepayParams) -> Result<(), RepayError> {
-        if !params.is_full_repay && params.amount <= 0 {
-            return Err(RepayError::InvalidAmount);
-        }
-        Ok(())
-    }
-
-    /// Calculate actual repay amount (considering debt limits)
-    pub fn calculate_repay_amount(
-        requested_amount: i128,
-        current_debt: i128,
-        is_full_repay: bool,
-    ) -> i128 {
-        if is_full_repay {
-            current_debt
-        } else if requested_amount > current_debt {
-            current_debt
-        } else {
-            requested_amount
-        }
-    }
-
-    /// Check if position can be fully repaid
-    pub fn can_full_repay(current_debt: i128) -> bool {
-        current_debt > 0
-    }
-
-    /// Calculate remaining debt after repayment
-    pub fn calculate_remaining_debt(current_debt: i128, repay_amount: i128) -> i128 {
-        if repay_amount >= current_debt {
-            0
-        } else {
-            current_debt - repay_amount
-        }
+        ErrorContext::report(env, result, "pull_repay", user, amount)
     }
 }
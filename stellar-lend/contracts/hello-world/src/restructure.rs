@@ -0,0 +1,185 @@
+//! Restructure module for StellarLend protocol
+//! Large borrowers can carve a tranche off a position into another account they control,
+//! e.g. to sell it, or fold one account's position into another. A tranche carries the
+//! same share of every collateral balance as of the debt, and both accounts must come out
+//! of either operation above the minimum collateral ratio.
+
+use crate::compliance::ComplianceGate;
+use crate::cross_asset::{CrossAssetManager, CrossStateHelper};
+use crate::events::SequencedEvents;
+use crate::limits::BorrowLimits;
+use crate::{
+    InterestRateManager, InterestRateState, InterestRateStorage, OperationKind, Position,
+    ProtocolConfig, ProtocolError, SafeMath, StateHelper, TokenRegistry,
+};
+use soroban_sdk::{Address, Env, Symbol};
+
+/// Splitting and merging of debt positions
+pub struct PositionRestructure;
+
+impl PositionRestructure {
+    /// Move `amount` of `user`'s debt in `asset`, with the same share of their collateral
+    /// and accrued borrow interest, into `target`'s position. Both accounts must authorize.
+    pub fn split(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        amount: i128,
+        target: &Address,
+    ) -> Result<(), ProtocolError> {
+        if user == target {
+            return Err(ProtocolError::InvalidInput);
+        }
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        // Debt is only ever owed in the primary asset
+        if *asset != TokenRegistry::require_primary_asset(env)? {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+        ComplianceGate::check(env, target, OperationKind::Borrow, amount)?;
+
+        let state = InterestRateStorage::update_state(env);
+        let mut source =
+            Self::accrued_position(env, &state, user).ok_or(ProtocolError::PositionNotFound)?;
+        if amount > source.debt {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let mut dest = Self::accrued_position(env, &state, target)
+            .unwrap_or_else(|| Self::fresh_position(env, &state, target));
+
+        let debt = source.debt;
+        let collateral = SafeMath::mul_div(source.collateral, amount, debt);
+        let interest = SafeMath::mul_div(source.borrow_interest, amount, debt);
+        source.collateral -= collateral;
+        source.debt -= amount;
+        source.borrow_interest -= interest;
+        dest.collateral += collateral;
+        dest.debt += amount;
+        dest.borrow_interest += interest;
+        Self::move_cross_collateral(env, user, target, amount, debt)?;
+        StateHelper::save_position(env, &source);
+        StateHelper::save_position(env, &dest);
+
+        Self::ensure_healthy(env, &source)?;
+        Self::ensure_healthy(env, &dest)?;
+        env.publish_event(
+            (Symbol::new(env, "position_split"), user.clone()),
+            (target.clone(), amount, collateral),
+        );
+        Ok(())
+    }
+
+    /// Fold everything `from` holds and owes into `to`'s position. `user` must be one of
+    /// the two accounts and both must authorize.
+    pub fn merge(
+        env: &Env,
+        user: &Address,
+        from: &Address,
+        to: &Address,
+    ) -> Result<(), ProtocolError> {
+        if from == to || (user != from && user != to) {
+            return Err(ProtocolError::InvalidInput);
+        }
+        let state = InterestRateStorage::update_state(env);
+        let mut source =
+            Self::accrued_position(env, &state, from).ok_or(ProtocolError::PositionNotFound)?;
+        if source.debt > 0 {
+            ComplianceGate::check(env, to, OperationKind::Borrow, source.debt)?;
+        }
+        let mut dest = Self::accrued_position(env, &state, to)
+            .unwrap_or_else(|| Self::fresh_position(env, &state, to));
+
+        let debt = source.debt;
+        dest.collateral += source.collateral;
+        dest.debt += source.debt;
+        dest.borrow_interest += source.borrow_interest;
+        dest.supply_interest += source.supply_interest;
+        source.collateral = 0;
+        source.debt = 0;
+        source.borrow_interest = 0;
+        source.supply_interest = 0;
+        source.interest_capped = false;
+        Self::move_cross_collateral(env, from, to, 1, 1)?;
+        StateHelper::save_position(env, &source);
+        StateHelper::save_position(env, &dest);
+
+        Self::ensure_healthy(env, &dest)?;
+        env.publish_event(
+            (Symbol::new(env, "positions_merged"), to.clone()),
+            (from.clone(), debt, dest.debt),
+        );
+        Ok(())
+    }
+
+    /// The user's position brought current, if they have one
+    fn accrued_position(env: &Env, state: &InterestRateState, user: &Address) -> Option<Position> {
+        let mut position = StateHelper::get_position(env, user)?;
+        InterestRateManager::accrue_interest_for_position(
+            env,
+            &mut position,
+            state.current_borrow_rate,
+            state.liquidity_index,
+        );
+        Some(position)
+    }
+
+    /// An empty position checkpointed at the current index
+    fn fresh_position(env: &Env, state: &InterestRateState, user: &Address) -> Position {
+        let mut position = Position::new(user.clone(), 0, 0);
+        InterestRateManager::accrue_interest_for_position(
+            env,
+            &mut position,
+            state.current_borrow_rate,
+            state.liquidity_index,
+        );
+        position
+    }
+
+    /// Move `numerator / denominator` of each cross-asset balance from one account to
+    /// another, rounding the moved amounts down
+    fn move_cross_collateral(
+        env: &Env,
+        from: &Address,
+        to: &Address,
+        numerator: i128,
+        denominator: i128,
+    ) -> Result<(), ProtocolError> {
+        let mut source = CrossStateHelper::get_or_init_position(env, from);
+        let mut dest = CrossStateHelper::get_or_init_position(env, to);
+        for (asset, balance) in source.collateral.clone().iter() {
+            let moved = SafeMath::mul_div(balance, numerator, denominator);
+            if moved <= 0 {
+                continue;
+            }
+            let received = dest.collateral.get(asset.clone()).unwrap_or(0) + moved;
+            BorrowLimits::ensure_position_size(env, to, &asset, received)?;
+            source.collateral.set(asset.clone(), balance - moved);
+            dest.collateral.set(asset, received);
+        }
+        let now = env.ledger().timestamp();
+        source.last_update = now;
+        dest.last_update = now;
+        CrossStateHelper::save_position(env, &source);
+        CrossStateHelper::save_position(env, &dest);
+        Ok(())
+    }
+
+    /// Refuse a resulting position that carries debt below the minimum collateral ratio
+    fn ensure_healthy(env: &Env, position: &Position) -> Result<(), ProtocolError> {
+        if position.debt <= 0 {
+            return Ok(());
+        }
+        CrossAssetManager::ensure_prices_fresh(env, &position.user)?;
+        let ratio = CrossAssetManager::collateral_ratio(
+            env,
+            &position.user,
+            position.collateral,
+            position.debt,
+        )?;
+        if ratio < ProtocolConfig::get_action_min_ratio(env) {
+            return Err(ProtocolError::InsufficientCollateralRatio);
+        }
+        Ok(())
+    }
+}
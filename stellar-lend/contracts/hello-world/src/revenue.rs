@@ -0,0 +1,591 @@
+//! Revenue module for StellarLend protocol
+//! Accounts for protocol fees, routes a configurable share into buybacks of the
+//! protocol token, which are then burned or streamed to stakers, and segregates the
+//! rest per asset until it is paid out to the treasury or split between several
+//! recipients
+
+use crate::cross_asset::CrossAssetManager;
+use crate::events::SequencedEvents;
+use crate::listing::MarketListings;
+use crate::storage::DataKey;
+use crate::{ProtocolConfig, ProtocolError, TokenRegistry, UserManager};
+use soroban_sdk::{contracttype, token::TokenClient, Address, Env, Map, Symbol, Vec};
+
+/// What happens to protocol tokens acquired by a buyback
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum BuybackMode {
+    /// Tokens are burned, reducing supply
+    Burn,
+    /// Tokens are sent to the staking pool for distribution
+    Stream,
+}
+
+/// Admin-managed buyback routing
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BuybackConfig {
+    /// Share of collected fees set aside for buybacks (basis points)
+    pub share_bps: i128,
+    pub protocol_token: Address,
+    /// Whitelisted adapter exposing `swap(from, to, amount_in, min_out, recipient)`
+    pub dex_adapter: Address,
+    pub mode: BuybackMode,
+    /// Receives bought tokens in `Stream` mode
+    pub staking_pool: Option<Address>,
+    /// Minimum seconds between buybacks
+    pub min_interval: u64,
+}
+
+/// Length of a revenue history bucket
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum RevenueGranularity {
+    Daily,
+    Weekly,
+    /// 30-day buckets
+    Monthly,
+}
+
+impl RevenueGranularity {
+    fn period_secs(self) -> u64 {
+        match self {
+            RevenueGranularity::Daily => 24 * 60 * 60,
+            RevenueGranularity::Weekly => 7 * 24 * 60 * 60,
+            RevenueGranularity::Monthly => 30 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Fees collected during one epoch (`timestamp / period`) of a granularity
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RevenueBucket {
+    pub epoch: u64,
+    pub fees: i128,
+}
+
+/// Buckets retained per granularity; older ones are dropped
+pub const MAX_REVENUE_HISTORY: u32 = 30;
+
+/// Cumulative protocol revenue and buyback accounting (primary asset units unless noted)
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct RevenueMetrics {
+    /// Reserve share of borrow interest accrued by the protocol
+    pub total_fees: i128,
+    /// Fees collected in the current day, week and 30-day month
+    pub daily_fees: i128,
+    pub weekly_fees: i128,
+    pub monthly_fees: i128,
+    /// Fees earmarked for the next buyback
+    pub buyback_reserve: i128,
+    pub total_buyback_spent: i128,
+    /// Protocol tokens acquired across all buybacks
+    pub total_tokens_bought: i128,
+    /// Protocol tokens burned
+    pub total_burned: i128,
+    /// Protocol tokens sent to stakers
+    pub total_streamed: i128,
+    pub last_buyback: u64,
+    /// Protocol liquidation fees credited to reserves, valued when charged
+    pub total_liquidation_fees: i128,
+}
+
+/// Reserve income segregated for the treasury in one asset
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct TreasuryBalance {
+    /// Reserve share credited to the treasury over the protocol's lifetime
+    pub accrued: i128,
+    /// Tokens already transferred to the treasury
+    pub distributed: i128,
+}
+
+/// Treasury sub-balance of an asset compared with what the contract actually holds
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TreasuryReconciliation {
+    pub asset: Address,
+    pub accrued: i128,
+    pub distributed: i128,
+    /// Owed to the treasury but not yet transferred
+    pub pending: i128,
+    /// Tokens of `asset` held by the contract
+    pub contract_balance: i128,
+    /// Part of `pending` the contract cannot currently cover, e.g. interest not yet repaid
+    pub shortfall: i128,
+}
+
+/// Protocol revenue credited in one asset since launch, by source, in units of the asset
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct AssetRevenue {
+    /// Reserve factor's share of borrow interest
+    pub interest: i128,
+    /// Update, storage and listing fees
+    pub fees: i128,
+    /// Protocol liquidation fees and seizure rounding remainders
+    pub liquidations: i128,
+}
+
+/// Where a credit to protocol revenue came from
+#[derive(Clone, Copy)]
+enum RevenueSource {
+    Interest,
+    Fees,
+    Liquidations,
+}
+
+/// Most recipients a fee split may pay
+pub const MAX_FEE_RECIPIENTS: u32 = 10;
+
+/// One payee of treasury distributions and its share (basis points)
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FeeSplit {
+    pub recipient: Address,
+    pub weight_bps: i128,
+}
+
+/// Storage helper for revenue accounting
+pub struct RevenueStorage;
+
+impl RevenueStorage {
+    fn metrics_key() -> DataKey {
+        DataKey::RevenueMetrics
+    }
+
+    fn buyback_key() -> DataKey {
+        DataKey::BuybackConfig
+    }
+
+    fn history_key(granularity: RevenueGranularity) -> DataKey {
+        DataKey::RevenueHistory(granularity)
+    }
+
+    fn splits_key() -> DataKey {
+        DataKey::FeeSplits
+    }
+
+    fn treasury_key(asset: &Address) -> DataKey {
+        DataKey::TreasuryBalance(asset.clone())
+    }
+
+    fn by_asset_key() -> DataKey {
+        DataKey::RevenueByAsset
+    }
+
+    pub fn get_revenue_by_asset(env: &Env) -> Map<Address, AssetRevenue> {
+        env.storage()
+            .instance()
+            .get(&Self::by_asset_key())
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    pub fn save_revenue_by_asset(env: &Env, revenue: &Map<Address, AssetRevenue>) {
+        env.storage().instance().set(&Self::by_asset_key(), revenue);
+    }
+
+    pub fn get_treasury_balance(env: &Env, asset: &Address) -> TreasuryBalance {
+        env.storage()
+            .instance()
+            .get(&Self::treasury_key(asset))
+            .unwrap_or_default()
+    }
+
+    pub fn save_treasury_balance(env: &Env, asset: &Address, balance: &TreasuryBalance) {
+        env.storage()
+            .instance()
+            .set(&Self::treasury_key(asset), balance);
+    }
+
+    pub fn get_metrics(env: &Env) -> RevenueMetrics {
+        env.storage()
+            .instance()
+            .get(&Self::metrics_key())
+            .unwrap_or_default()
+    }
+
+    pub fn save_metrics(env: &Env, metrics: &RevenueMetrics) {
+        env.storage().instance().set(&Self::metrics_key(), metrics);
+    }
+
+    pub fn get_history(env: &Env, granularity: RevenueGranularity) -> Vec<RevenueBucket> {
+        env.storage()
+            .instance()
+            .get(&Self::history_key(granularity))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_history(env: &Env, granularity: RevenueGranularity, history: &Vec<RevenueBucket>) {
+        env.storage()
+            .instance()
+            .set(&Self::history_key(granularity), history);
+    }
+
+    pub fn get_fee_splits(env: &Env) -> Vec<FeeSplit> {
+        env.storage()
+            .instance()
+            .get(&Self::splits_key())
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_fee_splits(env: &Env, splits: &Vec<FeeSplit>) {
+        if splits.is_empty() {
+            env.storage().instance().remove(&Self::splits_key());
+        } else {
+            env.storage().instance().set(&Self::splits_key(), splits);
+        }
+    }
+
+    pub fn get_buyback_config(env: &Env) -> Option<BuybackConfig> {
+        env.storage().instance().get(&Self::buyback_key())
+    }
+
+    pub fn save_buyback_config(env: &Env, config: &BuybackConfig) {
+        env.storage().instance().set(&Self::buyback_key(), config);
+    }
+}
+
+/// Fee accounting and buyback execution
+pub struct RevenueManager;
+
+impl RevenueManager {
+    /// Record the reserve factor's share of borrow interest, in the primary asset, as
+    /// protocol fees
+    pub fn record_interest(env: &Env, amount: i128) {
+        Self::record_primary(env, amount, RevenueSource::Interest);
+    }
+
+    /// Record protocol fees in the primary asset, earmarking the configured share for
+    /// buybacks and segregating the rest for the treasury
+    pub fn record_fees(env: &Env, amount: i128) {
+        Self::record_primary(env, amount, RevenueSource::Fees);
+    }
+
+    fn record_primary(env: &Env, amount: i128, source: RevenueSource) {
+        if amount <= 0 {
+            return;
+        }
+        let mut metrics = RevenueStorage::get_metrics(env);
+        metrics.total_fees += amount;
+        metrics.daily_fees = Self::roll_bucket(env, RevenueGranularity::Daily, amount);
+        metrics.weekly_fees = Self::roll_bucket(env, RevenueGranularity::Weekly, amount);
+        metrics.monthly_fees = Self::roll_bucket(env, RevenueGranularity::Monthly, amount);
+        let mut buyback = 0;
+        if let Some(config) = RevenueStorage::get_buyback_config(env) {
+            buyback = amount * config.share_bps / 10000;
+            metrics.buyback_reserve += buyback;
+        }
+        RevenueStorage::save_metrics(env, &metrics);
+
+        if let Ok(asset) = TokenRegistry::require_primary_asset(env) {
+            let mut treasury = RevenueStorage::get_treasury_balance(env, &asset);
+            treasury.accrued += amount - buyback;
+            RevenueStorage::save_treasury_balance(env, &asset, &treasury);
+            Self::attribute(env, &asset, source, amount);
+        }
+    }
+
+    /// Credit `amount` units of `asset` held by the contract to the treasury sub-balance,
+    /// outside fee metrics and buybacks, less any listing proposer's share
+    pub fn record_reserve(env: &Env, asset: &Address, amount: i128) {
+        Self::credit_reserve(env, asset, amount, RevenueSource::Fees);
+    }
+
+    /// Credit a liquidation's seizure rounding remainder of `amount` units of `asset` to
+    /// the asset's reserves
+    pub fn record_liquidation_remainder(env: &Env, asset: &Address, amount: i128) {
+        Self::credit_reserve(env, asset, amount, RevenueSource::Liquidations);
+    }
+
+    fn credit_reserve(env: &Env, asset: &Address, amount: i128, source: RevenueSource) {
+        if amount <= 0 {
+            return;
+        }
+        Self::attribute(env, asset, source, amount);
+        let proposer_share = MarketListings::take_proposer_share(env, asset, amount);
+        let mut treasury = RevenueStorage::get_treasury_balance(env, asset);
+        treasury.accrued += amount - proposer_share;
+        RevenueStorage::save_treasury_balance(env, asset, &treasury);
+    }
+
+    /// Credit a protocol liquidation fee of `units` of `asset`, worth `value`, to the
+    /// asset's reserves
+    pub fn record_liquidation_fee(env: &Env, asset: &Address, units: i128, value: i128) {
+        if units <= 0 {
+            return;
+        }
+        Self::credit_reserve(env, asset, units, RevenueSource::Liquidations);
+        let mut metrics = RevenueStorage::get_metrics(env);
+        metrics.total_liquidation_fees += value;
+        RevenueStorage::save_metrics(env, &metrics);
+    }
+
+    /// Add `amount` to the revenue `asset` has earned from `source`
+    fn attribute(env: &Env, asset: &Address, source: RevenueSource, amount: i128) {
+        let mut by_asset = RevenueStorage::get_revenue_by_asset(env);
+        let mut revenue = by_asset.get(asset.clone()).unwrap_or_default();
+        match source {
+            RevenueSource::Interest => revenue.interest += amount,
+            RevenueSource::Fees => revenue.fees += amount,
+            RevenueSource::Liquidations => revenue.liquidations += amount,
+        }
+        by_asset.set(asset.clone(), revenue);
+        RevenueStorage::save_revenue_by_asset(env, &by_asset);
+    }
+
+    /// Revenue `asset` has earned the protocol, by source
+    pub fn asset_revenue(env: &Env, asset: &Address) -> AssetRevenue {
+        RevenueStorage::get_revenue_by_asset(env)
+            .get(asset.clone())
+            .unwrap_or_default()
+    }
+
+    /// Compare the treasury sub-balance of `asset` with the contract's token balance
+    pub fn reconcile_treasury(env: &Env, asset: &Address) -> TreasuryReconciliation {
+        let balance = RevenueStorage::get_treasury_balance(env, asset);
+        let pending = balance.accrued - balance.distributed;
+        let contract_balance =
+            TokenClient::new(env, asset).balance(&env.current_contract_address());
+        TreasuryReconciliation {
+            asset: asset.clone(),
+            accrued: balance.accrued,
+            distributed: balance.distributed,
+            pending,
+            contract_balance,
+            shortfall: (pending - contract_balance).max(0),
+        }
+    }
+
+    /// Replace the recipients treasury distributions are split between. Weights must be
+    /// positive and sum to 100%; an empty list pays the single treasury address again.
+    pub fn set_fee_splits(
+        env: &Env,
+        caller: &Address,
+        splits: Vec<FeeSplit>,
+    ) -> Result<(), ProtocolError> {
+        UserManager::ensure_treasury_manager(env, caller)?;
+        if splits.len() > MAX_FEE_RECIPIENTS {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut total = 0;
+        for (idx, split) in splits.iter().enumerate() {
+            if split.weight_bps <= 0
+                || splits
+                    .iter()
+                    .skip(idx + 1)
+                    .any(|other| other.recipient == split.recipient)
+            {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            total += split.weight_bps;
+        }
+        if !splits.is_empty() && total != 10000 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        RevenueStorage::save_fee_splits(env, &splits);
+        env.publish_event(
+            (Symbol::new(env, "fee_splits_updated"), caller.clone()),
+            splits.len(),
+        );
+        Ok(())
+    }
+
+    /// Transfer the pending treasury sub-balance of `asset` to the fee split recipients,
+    /// or the treasury when no split is set, as far as the contract's holdings cover it;
+    /// returns the amount sent
+    pub fn distribute_to_treasury(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+    ) -> Result<i128, ProtocolError> {
+        UserManager::ensure_treasury_manager(env, caller)?;
+        let mut splits = RevenueStorage::get_fee_splits(env);
+        if splits.is_empty() {
+            let treasury =
+                ProtocolConfig::get_treasury(env).ok_or(ProtocolError::ConfigurationError)?;
+            splits.push_back(FeeSplit {
+                recipient: treasury,
+                weight_bps: 10000,
+            });
+        }
+        let reconciliation = Self::reconcile_treasury(env, asset);
+        let amount = reconciliation.pending.min(reconciliation.contract_balance);
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+
+        let mut balance = RevenueStorage::get_treasury_balance(env, asset);
+        balance.distributed += amount;
+        RevenueStorage::save_treasury_balance(env, asset, &balance);
+
+        let client = TokenClient::new(env, asset);
+        let contract = env.current_contract_address();
+        let last = splits.len() - 1;
+        let mut paid = 0;
+        for (idx, split) in splits.iter().enumerate() {
+            // The last recipient takes the rounding remainder so the full amount goes out
+            let share = if idx as u32 == last {
+                amount - paid
+            } else {
+                amount * split.weight_bps / 10000
+            };
+            paid += share;
+            if share > 0 {
+                client.transfer(&contract, &split.recipient, &share);
+            }
+            env.publish_event(
+                (Symbol::new(env, "treasury_distributed"), asset.clone()),
+                (split.recipient, share, reconciliation.pending - amount),
+            );
+        }
+        Ok(amount)
+    }
+
+    /// Add `amount` to the current epoch's bucket, opening a new one when the epoch
+    /// changed, and return the bucket total
+    fn roll_bucket(env: &Env, granularity: RevenueGranularity, amount: i128) -> i128 {
+        let epoch = env.ledger().timestamp() / granularity.period_secs();
+        let mut history = RevenueStorage::get_history(env, granularity);
+        let bucket = match history.last() {
+            Some(last) if last.epoch == epoch => {
+                history.pop_back();
+                RevenueBucket {
+                    epoch,
+                    fees: last.fees + amount,
+                }
+            }
+            _ => RevenueBucket {
+                epoch,
+                fees: amount,
+            },
+        };
+        let fees = bucket.fees;
+        history.push_back(bucket);
+        while history.len() > MAX_REVENUE_HISTORY {
+            history.pop_front();
+        }
+        RevenueStorage::save_history(env, granularity, &history);
+        fees
+    }
+
+    /// Fees collected in the current epoch of `granularity`
+    fn current_bucket_fees(env: &Env, granularity: RevenueGranularity) -> i128 {
+        let epoch = env.ledger().timestamp() / granularity.period_secs();
+        match RevenueStorage::get_history(env, granularity).last() {
+            Some(last) if last.epoch == epoch => last.fees,
+            _ => 0,
+        }
+    }
+
+    /// Revenue metrics with the period totals reset once their epoch has passed
+    pub fn get_metrics(env: &Env) -> RevenueMetrics {
+        let mut metrics = RevenueStorage::get_metrics(env);
+        metrics.daily_fees = Self::current_bucket_fees(env, RevenueGranularity::Daily);
+        metrics.weekly_fees = Self::current_bucket_fees(env, RevenueGranularity::Weekly);
+        metrics.monthly_fees = Self::current_bucket_fees(env, RevenueGranularity::Monthly);
+        metrics
+    }
+
+    /// Most recent `limit` buckets of `granularity`, oldest first
+    pub fn get_history(
+        env: &Env,
+        granularity: RevenueGranularity,
+        limit: u32,
+    ) -> Vec<RevenueBucket> {
+        let history = RevenueStorage::get_history(env, granularity);
+        let start = history.len().saturating_sub(limit);
+        history.slice(start..)
+    }
+
+    pub fn set_buyback_config(
+        env: &Env,
+        caller: &Address,
+        config: BuybackConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=10000).contains(&config.share_bps) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if config.mode == BuybackMode::Stream && config.staking_pool.is_none() {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        RevenueStorage::save_buyback_config(env, &config);
+        env.publish_event(
+            (Symbol::new(env, "buyback_config_updated"), caller.clone()),
+            (
+                config.share_bps,
+                config.protocol_token,
+                config.dex_adapter,
+                config.mode,
+            ),
+        );
+        Ok(())
+    }
+
+    /// Spend the buyback reserve on the protocol token and burn or stream the proceeds
+    pub fn execute_buyback(
+        env: &Env,
+        caller: &Address,
+        min_out: i128,
+    ) -> Result<i128, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        let config =
+            RevenueStorage::get_buyback_config(env).ok_or(ProtocolError::ConfigurationError)?;
+        let mut metrics = RevenueStorage::get_metrics(env);
+
+        let now = env.ledger().timestamp();
+        if metrics.last_buyback != 0 && now < metrics.last_buyback + config.min_interval {
+            return Err(ProtocolError::RateLimitExceeded);
+        }
+        let amount = metrics.buyback_reserve;
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        let contract = env.current_contract_address();
+        if TokenClient::new(env, &primary).balance(&contract) < amount {
+            return Err(ProtocolError::InsufficientLiquidity);
+        }
+
+        let bought = CrossAssetManager::swap_via_adapter(
+            env,
+            &config.dex_adapter,
+            &primary,
+            &config.protocol_token,
+            amount,
+            min_out,
+        )?;
+
+        match config.mode {
+            BuybackMode::Burn => {
+                TokenClient::new(env, &config.protocol_token).burn(&contract, &bought);
+                metrics.total_burned += bought;
+            }
+            BuybackMode::Stream => {
+                let pool = config
+                    .staking_pool
+                    .as_ref()
+                    .ok_or(ProtocolError::ConfigurationError)?;
+                TokenClient::new(env, &config.protocol_token).transfer(&contract, pool, &bought);
+                metrics.total_streamed += bought;
+            }
+        }
+
+        metrics.buyback_reserve = 0;
+        metrics.total_buyback_spent += amount;
+        metrics.total_tokens_bought += bought;
+        metrics.last_buyback = now;
+        RevenueStorage::save_metrics(env, &metrics);
+
+        env.publish_event(
+            (Symbol::new(env, "buyback_executed"), caller.clone()),
+            (amount, bought, config.mode),
+        );
+        Ok(bought)
+    }
+}
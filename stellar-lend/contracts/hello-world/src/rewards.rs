@@ -0,0 +1,431 @@
+//! Rewards module for StellarLend protocol
+//! Liquidity incentive emissions configured as a schedule of epochs per asset and market
+//! side, so incentives can decay on a pre-programmed path without a governance action
+//! for every change of rate. Emissions accrue per second through a reward index per
+//! market side, checkpointed on every balance change like the liquidity index, so a
+//! holder who never interacts still earns their share. Emissions can be scaled by
+//! utilization bands, e.g. boosting borrow incentives only while utilization is low and
+//! supply incentives only while it is high, steering markets toward the kink. The band
+//! multiplier is re-read from pool utilization on every accrual and applies until the next.
+
+use crate::cross_asset::CrossAssetManager;
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{InterestRateStorage, ProtocolConfig, ProtocolError, SafeMath, TokenRegistry};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Most epochs a single emission schedule can hold
+pub const MAX_EMISSION_EPOCHS: u32 = 52;
+
+/// Fixed-point scale of reward indexes
+pub const REWARD_INDEX_SCALE: i128 = 1_000_000_000_000_000_000;
+
+const SECONDS_PER_YEAR: i128 = 365 * 24 * 60 * 60;
+
+/// Most utilization bands one side of a market can hold
+pub const MAX_UTILIZATION_BANDS: u32 = 10;
+
+/// Largest emission multiplier a band may apply (5x, in basis points)
+pub const MAX_BAND_MULTIPLIER_BPS: i128 = 50000;
+
+/// Multiplier applied when no band covers the current utilization
+const NEUTRAL_MULTIPLIER_BPS: i128 = 10000;
+
+/// Side of a market that earns emissions
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum RewardSide {
+    Supply,
+    Borrow,
+}
+
+/// Emission rate in force from `start` (inclusive) to `end` (exclusive)
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct EmissionEpoch {
+    pub start: u64,
+    pub end: u64,
+    /// Reward units emitted per second across the whole side of the market
+    pub rate_per_second: i128,
+}
+
+/// Emission multiplier for utilization up to and including `max_utilization`
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct UtilizationBand {
+    /// Upper bound of the band (scaled by 1e8)
+    pub max_utilization: i128,
+    /// Share of the scheduled rate emitted inside the band (basis points)
+    pub multiplier_bps: i128,
+}
+
+/// Emission rates currently in force for both sides of a market, after utilization band
+/// multipliers; a side outside any epoch reports a zero rate and zero end
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct CurrentEmissions {
+    pub supply_rate_per_second: i128,
+    pub supply_epoch_end: u64,
+    pub borrow_rate_per_second: i128,
+    pub borrow_epoch_end: u64,
+}
+
+/// Cumulative emissions per unit of balance on one side of a market
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct RewardIndex {
+    /// Rewards emitted per unit of balance so far (scaled by REWARD_INDEX_SCALE)
+    pub index: i128,
+    /// Balance earning emissions across all checkpointed holders
+    pub total_balance: i128,
+    pub last_update: u64,
+    /// Utilization band multiplier in force since `last_update` (basis points)
+    pub multiplier_bps: i128,
+}
+
+/// A holder's balance and rewards as of their last checkpoint
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct RewardCheckpoint {
+    pub balance: i128,
+    /// Reward index the balance last accrued up to
+    pub index: i128,
+    /// Rewards accrued up to that index
+    pub accrued: i128,
+}
+
+/// Storage helper for emission schedules and reward indexes
+pub struct RewardStorage;
+
+impl RewardStorage {
+    fn schedule_key(asset: &Address, side: RewardSide) -> DataKey {
+        DataKey::EmissionSchedule(asset.clone(), side)
+    }
+
+    fn index_key(asset: &Address, side: RewardSide) -> DataKey {
+        DataKey::RewardIndex(asset.clone(), side)
+    }
+
+    fn checkpoint_key(user: &Address, asset: &Address, side: RewardSide) -> DataKey {
+        DataKey::RewardCheckpoint(user.clone(), asset.clone(), side)
+    }
+
+    fn bands_key(asset: &Address, side: RewardSide) -> DataKey {
+        DataKey::EmissionBands(asset.clone(), side)
+    }
+
+    pub fn get_bands(env: &Env, asset: &Address, side: RewardSide) -> Vec<UtilizationBand> {
+        env.storage()
+            .instance()
+            .get(&Self::bands_key(asset, side))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_bands(env: &Env, asset: &Address, side: RewardSide, bands: &Vec<UtilizationBand>) {
+        let key = Self::bands_key(asset, side);
+        if bands.is_empty() {
+            env.storage().instance().remove(&key);
+        } else {
+            env.storage().instance().set(&key, bands);
+        }
+    }
+
+    pub fn get_index(env: &Env, asset: &Address, side: RewardSide) -> RewardIndex {
+        env.storage()
+            .instance()
+            .get(&Self::index_key(asset, side))
+            .unwrap_or_default()
+    }
+
+    pub fn save_index(env: &Env, asset: &Address, side: RewardSide, index: &RewardIndex) {
+        env.storage()
+            .instance()
+            .set(&Self::index_key(asset, side), index);
+    }
+
+    pub fn get_checkpoint(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        side: RewardSide,
+    ) -> RewardCheckpoint {
+        env.storage()
+            .persistent()
+            .get(&Self::checkpoint_key(user, asset, side))
+            .unwrap_or_default()
+    }
+
+    pub fn save_checkpoint(
+        env: &Env,
+        user: &Address,
+        asset: &Address,
+        side: RewardSide,
+        checkpoint: &RewardCheckpoint,
+    ) {
+        env.storage()
+            .persistent()
+            .set(&Self::checkpoint_key(user, asset, side), checkpoint);
+    }
+
+    pub fn get_schedule(env: &Env, asset: &Address, side: RewardSide) -> Vec<EmissionEpoch> {
+        env.storage()
+            .instance()
+            .get(&Self::schedule_key(asset, side))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_schedule(
+        env: &Env,
+        asset: &Address,
+        side: RewardSide,
+        schedule: &Vec<EmissionEpoch>,
+    ) {
+        let key = Self::schedule_key(asset, side);
+        if schedule.is_empty() {
+            env.storage().instance().remove(&key);
+        } else {
+            env.storage().instance().set(&key, schedule);
+        }
+    }
+}
+
+/// Emission schedule management and lookups
+pub struct RewardManager;
+
+impl RewardManager {
+    /// Replace the emission schedule for one side of `asset`'s market. Epochs must be
+    /// ordered and non-overlapping; an empty schedule stops emissions.
+    pub fn set_schedule(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        side: RewardSide,
+        epochs: Vec<EmissionEpoch>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if *asset != TokenRegistry::require_primary_asset(env)? {
+            CrossAssetManager::get_asset_params(env, asset)?;
+        }
+        if epochs.len() > MAX_EMISSION_EPOCHS {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut previous_end = 0;
+        for epoch in epochs.iter() {
+            if epoch.start >= epoch.end || epoch.start < previous_end || epoch.rate_per_second < 0 {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            previous_end = epoch.end;
+        }
+
+        // Emissions up to now follow the schedule they were promised under
+        let index = Self::accrued_index(env, asset, side);
+        RewardStorage::save_index(env, asset, side, &index);
+        RewardStorage::save_schedule(env, asset, side, &epochs);
+        env.publish_event(
+            (Symbol::new(env, "emission_schedule_set"), asset.clone()),
+            (side, epochs.len()),
+        );
+        Ok(())
+    }
+
+    /// Replace the utilization bands scaling emissions on one side of the primary market.
+    /// Bands are ordered by strictly increasing upper bound; utilization above the last
+    /// band, or any utilization with no bands, emits at the scheduled rate.
+    pub fn set_bands(
+        env: &Env,
+        caller: &Address,
+        asset: &Address,
+        side: RewardSide,
+        bands: Vec<UtilizationBand>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        // Only the primary market keeps borrow-side accounting to measure utilization by
+        if *asset != TokenRegistry::require_primary_asset(env)? {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+        if bands.len() > MAX_UTILIZATION_BANDS {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let mut previous_max = 0;
+        for band in bands.iter() {
+            if band.max_utilization <= previous_max
+                || band.max_utilization > 100000000
+                || !(0..=MAX_BAND_MULTIPLIER_BPS).contains(&band.multiplier_bps)
+            {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            previous_max = band.max_utilization;
+        }
+
+        // Emissions up to now keep the multiplier they accrued under
+        RewardStorage::save_bands(env, asset, side, &bands);
+        let index = Self::accrued_index(env, asset, side);
+        RewardStorage::save_index(env, asset, side, &index);
+        env.publish_event(
+            (Symbol::new(env, "emission_bands_set"), asset.clone()),
+            (side, bands.len()),
+        );
+        Ok(())
+    }
+
+    /// Band multiplier for one side of `asset`'s market at the pool's current utilization
+    pub fn band_multiplier(env: &Env, asset: &Address, side: RewardSide) -> i128 {
+        let bands = RewardStorage::get_bands(env, asset, side);
+        if bands.is_empty() {
+            return NEUTRAL_MULTIPLIER_BPS;
+        }
+        let state = InterestRateStorage::get_state(env);
+        let utilization = SafeMath::utilization(state.total_borrowed, state.total_supplied);
+        bands
+            .iter()
+            .find(|band| utilization <= band.max_utilization)
+            .map(|band| band.multiplier_bps)
+            .unwrap_or(NEUTRAL_MULTIPLIER_BPS)
+    }
+
+    /// Accrue both sides of the primary market that carry bands, so their multipliers
+    /// follow utilization even when no balance on that side changes. Called on every
+    /// pool accrual.
+    pub fn refresh_bands(env: &Env) {
+        let Ok(primary) = TokenRegistry::require_primary_asset(env) else {
+            return;
+        };
+        for side in [RewardSide::Supply, RewardSide::Borrow] {
+            if !RewardStorage::get_bands(env, &primary, side).is_empty() {
+                let index = Self::accrued_index(env, &primary, side);
+                RewardStorage::save_index(env, &primary, side, &index);
+            }
+        }
+    }
+
+    /// Epoch in force for one side of `asset`'s market at `timestamp`, if any
+    pub fn epoch_at(
+        env: &Env,
+        asset: &Address,
+        side: RewardSide,
+        timestamp: u64,
+    ) -> Option<EmissionEpoch> {
+        RewardStorage::get_schedule(env, asset, side)
+            .iter()
+            .find(|epoch| epoch.start <= timestamp && timestamp < epoch.end)
+    }
+
+    /// Epochs in force for both sides of `asset`'s market right now
+    pub fn current(env: &Env, asset: &Address) -> CurrentEmissions {
+        let now = env.ledger().timestamp();
+        let mut current = CurrentEmissions::default();
+        if let Some(epoch) = Self::epoch_at(env, asset, RewardSide::Supply, now) {
+            current.supply_rate_per_second =
+                Self::scaled_rate(env, asset, RewardSide::Supply, epoch.rate_per_second);
+            current.supply_epoch_end = epoch.end;
+        }
+        if let Some(epoch) = Self::epoch_at(env, asset, RewardSide::Borrow, now) {
+            current.borrow_rate_per_second =
+                Self::scaled_rate(env, asset, RewardSide::Borrow, epoch.rate_per_second);
+            current.borrow_epoch_end = epoch.end;
+        }
+        current
+    }
+
+    fn scaled_rate(env: &Env, asset: &Address, side: RewardSide, rate: i128) -> i128 {
+        SafeMath::mul_div(rate, Self::band_multiplier(env, asset, side), 10000)
+    }
+
+    /// Reward units the schedule emits to one side of `asset`'s market over
+    /// `[from, to)`
+    fn emitted_between(env: &Env, asset: &Address, side: RewardSide, from: u64, to: u64) -> i128 {
+        let mut emitted = 0i128;
+        for epoch in RewardStorage::get_schedule(env, asset, side).iter() {
+            let start = epoch.start.max(from);
+            let end = epoch.end.min(to);
+            if end > start {
+                emitted = emitted
+                    .saturating_add(epoch.rate_per_second.saturating_mul((end - start) as i128));
+            }
+        }
+        emitted
+    }
+
+    /// The side's reward index brought forward to now, at the band multiplier in force
+    /// since the last update, which is then re-read. Emissions during stretches with no
+    /// balance earning them are not distributed.
+    fn accrued_index(env: &Env, asset: &Address, side: RewardSide) -> RewardIndex {
+        let mut index = RewardStorage::get_index(env, asset, side);
+        let now = env.ledger().timestamp();
+        if index.last_update > 0 && now > index.last_update && index.total_balance > 0 {
+            let emitted = SafeMath::mul_div(
+                Self::emitted_between(env, asset, side, index.last_update, now),
+                index.multiplier_bps,
+                10000,
+            );
+            index.index = index.index.saturating_add(SafeMath::mul_div(
+                emitted,
+                REWARD_INDEX_SCALE,
+                index.total_balance,
+            ));
+        }
+        index.last_update = now;
+        index.multiplier_bps = Self::band_multiplier(env, asset, side);
+        index
+    }
+
+    /// Rewards the checkpointed balance has earned up to `index`
+    fn settle(checkpoint: &mut RewardCheckpoint, index: i128) {
+        let earned = SafeMath::mul_div(
+            checkpoint.balance,
+            index - checkpoint.index,
+            REWARD_INDEX_SCALE,
+        );
+        checkpoint.accrued = checkpoint.accrued.saturating_add(earned.max(0));
+        checkpoint.index = index;
+    }
+
+    /// Accrue the user's rewards on one side of `asset`'s market at their old balance,
+    /// then record `balance` as what they hold from now on. Called on every balance
+    /// change.
+    pub fn checkpoint(env: &Env, user: &Address, asset: &Address, side: RewardSide, balance: i128) {
+        let mut index = Self::accrued_index(env, asset, side);
+        let mut checkpoint = RewardStorage::get_checkpoint(env, user, asset, side);
+        Self::settle(&mut checkpoint, index.index);
+
+        let balance = balance.max(0);
+        index.total_balance = (index.total_balance + balance - checkpoint.balance).max(0);
+        checkpoint.balance = balance;
+        RewardStorage::save_index(env, asset, side, &index);
+        RewardStorage::save_checkpoint(env, user, asset, side, &checkpoint);
+    }
+
+    /// Reward units the user's checkpointed balances on both sides of `asset`'s market
+    /// would earn over a year at the emission rates in force now
+    pub fn annual_rewards(env: &Env, user: &Address, asset: &Address) -> i128 {
+        let now = env.ledger().timestamp();
+        let mut annual = 0i128;
+        for side in [RewardSide::Supply, RewardSide::Borrow] {
+            let Some(epoch) = Self::epoch_at(env, asset, side, now) else {
+                continue;
+            };
+            let total = RewardStorage::get_index(env, asset, side).total_balance;
+            let balance = RewardStorage::get_checkpoint(env, user, asset, side).balance;
+            let rate = Self::scaled_rate(env, asset, side, epoch.rate_per_second);
+            annual = annual.saturating_add(SafeMath::mul_div(
+                rate.saturating_mul(SECONDS_PER_YEAR),
+                balance,
+                total,
+            ));
+        }
+        annual
+    }
+
+    /// Rewards accrued so far on both sides of `asset`'s market, including those since
+    /// the user's last checkpoint
+    pub fn pending(env: &Env, user: &Address, asset: &Address) -> i128 {
+        let mut pending = 0;
+        for side in [RewardSide::Supply, RewardSide::Borrow] {
+            let index = Self::accrued_index(env, asset, side);
+            let mut checkpoint = RewardStorage::get_checkpoint(env, user, asset, side);
+            Self::settle(&mut checkpoint, index.index);
+            pending += checkpoint.accrued;
+        }
+        pending
+    }
+}
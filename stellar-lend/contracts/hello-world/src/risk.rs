@@ -0,0 +1,180 @@
+//! Risk module for StellarLend protocol
+//! Read-only protocol health snapshot for the risk team's monitoring dashboard:
+//! per-asset utilization, cap usage, positions close to liquidation and oracle
+//! freshness, alongside bad debt and every pause state in a single call
+
+use crate::analytics::AnalyticsStorage;
+use crate::auction::DebtAuctions;
+use crate::cross_asset::{AssetRegistryStorage, CrossAssetManager, CrossStateHelper};
+use crate::limits::LimitsStorage;
+use crate::{
+    EmergencyStatus, EmergencyStorage, InterestRateStorage, ProtocolConfig, ProtocolError,
+    RiskConfigStorage, StateHelper, TokenRegistry,
+};
+use soroban_sdk::{contracttype, Address, Env, Map, Vec};
+
+/// Health factor (percent of the minimum collateral ratio) below which a position is
+/// reported as at risk
+pub const AT_RISK_HEALTH_FACTOR: i128 = 110;
+
+/// Risk figures for one listed asset
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AssetRiskOverview {
+    pub asset: Address,
+    /// Pool utilization (scaled by 1e8); collateral-only assets report zero
+    pub utilization: i128,
+    /// Amount of the asset supplied across all positions
+    pub total_supplied: i128,
+    /// Largest single position as a share of the asset's position cap (basis points);
+    /// zero when the asset is uncapped
+    pub position_cap_usage_bps: i128,
+    /// Indebted positions holding the asset whose health factor is below 1.1
+    pub positions_at_risk: u32,
+    /// Seconds since the asset's price was last updated; zero for the primary asset
+    pub price_age: u64,
+    pub price_stale: bool,
+    pub deposits_enabled: bool,
+    /// Market still bootstrapping, so borrowing against it is closed
+    pub bootstrapping: bool,
+    /// End of a utilization breaker borrow cooldown; zero or past means none
+    pub borrow_cooldown_until: u64,
+}
+
+/// Protocol-wide risk snapshot
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct RiskOverview {
+    pub assets: Vec<AssetRiskOverview>,
+    /// Debt owed beyond risk-adjusted collateral, summed over all positions
+    pub total_bad_debt: i128,
+    /// Outstanding debt as a share of the debt ceiling (basis points); zero when unset
+    pub debt_ceiling_usage_bps: i128,
+    pub emergency_status: EmergencyStatus,
+    /// Borrowing frozen by the utilization kill-switch
+    pub borrowing_frozen: bool,
+    pub pause_borrow: bool,
+    pub pause_deposit: bool,
+    pub pause_withdraw: bool,
+    pub pause_liquidate: bool,
+}
+
+/// Risk dashboard queries
+pub struct RiskMonitor;
+
+impl RiskMonitor {
+    fn usage_bps(used: i128, cap: i128) -> i128 {
+        if cap <= 0 {
+            return 0;
+        }
+        used * 10000 / cap
+    }
+
+    /// Build the risk snapshot from stored positions. Positions are found through the
+    /// analytics user index and valued as last saved, without accruing interest.
+    pub fn overview(env: &Env) -> Result<RiskOverview, ProtocolError> {
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
+        let params = AssetRegistryStorage::get_params_map(env);
+
+        let mut supplied: Map<Address, i128> = Map::new(env);
+        let mut largest: Map<Address, i128> = Map::new(env);
+        let mut at_risk: Map<Address, u32> = Map::new(env);
+        let mut total_bad_debt = 0;
+
+        for user in AnalyticsStorage::get_analytics_users(env).iter() {
+            let Some(position) = StateHelper::get_position(env, &user) else {
+                continue;
+            };
+            let cross = CrossStateHelper::get_or_init_position(env, &user);
+            let mut holdings = cross.collateral.clone();
+            holdings.set(primary.clone(), position.collateral);
+
+            let owed = position.debt + position.borrow_interest;
+            let risky = owed > 0 && min_ratio > 0 && {
+                let ratio =
+                    CrossAssetManager::liquidation_ratio(env, &user, position.collateral, owed)?;
+                ratio * 100 / min_ratio < AT_RISK_HEALTH_FACTOR
+            };
+            if owed > 0 {
+                total_bad_debt += DebtAuctions::bad_debt(env, &position)?;
+            }
+
+            for (asset, amount) in holdings.iter() {
+                if amount <= 0 {
+                    continue;
+                }
+                supplied.set(
+                    asset.clone(),
+                    supplied.get(asset.clone()).unwrap_or(0) + amount,
+                );
+                if amount > largest.get(asset.clone()).unwrap_or(0) {
+                    largest.set(asset.clone(), amount);
+                }
+                if risky {
+                    at_risk.set(asset.clone(), at_risk.get(asset.clone()).unwrap_or(0) + 1);
+                }
+            }
+        }
+
+        let state = InterestRateStorage::get_state(env);
+        let caps = LimitsStorage::get_position_caps(env);
+        let bootstraps = LimitsStorage::get_bootstraps(env);
+        let price_times = AssetRegistryStorage::get_price_times(env);
+        let now = env.ledger().timestamp();
+
+        let mut listed = Vec::new(env);
+        listed.push_back(primary.clone());
+        for asset in params.keys().iter() {
+            if asset != primary {
+                listed.push_back(asset);
+            }
+        }
+
+        let mut assets = Vec::new(env);
+        for asset in listed.iter() {
+            let is_primary = asset == primary;
+            assets.push_back(AssetRiskOverview {
+                utilization: if is_primary {
+                    state.utilization_rate
+                } else {
+                    0
+                },
+                total_supplied: supplied.get(asset.clone()).unwrap_or(0),
+                position_cap_usage_bps: Self::usage_bps(
+                    largest.get(asset.clone()).unwrap_or(0),
+                    caps.get(asset.clone()).unwrap_or(0),
+                ),
+                positions_at_risk: at_risk.get(asset.clone()).unwrap_or(0),
+                price_age: price_times
+                    .get(asset.clone())
+                    .map(|updated_at| now.saturating_sub(updated_at))
+                    .unwrap_or(0),
+                price_stale: CrossAssetManager::is_price_stale(env, &asset),
+                deposits_enabled: is_primary
+                    || params
+                        .get(asset.clone())
+                        .is_some_and(|params| params.deposit_enabled),
+                bootstrapping: bootstraps.contains_key(asset.clone()),
+                borrow_cooldown_until: LimitsStorage::get_cooldown(env, &asset),
+                asset,
+            });
+        }
+
+        let risk_config = RiskConfigStorage::get(env);
+        Ok(RiskOverview {
+            assets,
+            total_bad_debt,
+            debt_ceiling_usage_bps: Self::usage_bps(
+                state.total_borrowed,
+                LimitsStorage::get_params(env).debt_ceiling,
+            ),
+            emergency_status: EmergencyStorage::get(env).status,
+            borrowing_frozen: LimitsStorage::is_frozen(env),
+            pause_borrow: risk_config.pause_borrow,
+            pause_deposit: risk_config.pause_deposit,
+            pause_withdraw: risk_config.pause_withdraw,
+            pause_liquidate: risk_config.pause_liquidate,
+        })
+    }
+}
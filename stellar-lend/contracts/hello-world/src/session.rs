@@ -0,0 +1,243 @@
+//! Session module for StellarLend protocol
+//! Lets a user pre-authorize a delegate (bot, keeper) for bounded recurring actions,
+//! or designate protectors that may top up collateral and repay debt without limits
+
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{OperationKind, ProtocolError};
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Bounded policy a user grants to a delegate address
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SessionPolicy {
+    /// Address allowed to act on the user's behalf
+    pub delegate: Address,
+    /// Largest amount accepted for a single delegated action
+    pub max_amount_per_action: i128,
+    /// Delegate may top up the user's collateral
+    pub allow_deposit: bool,
+    /// Delegate may repay the user's debt
+    pub allow_repay: bool,
+    /// Ledger timestamp after which the session is no longer valid
+    pub expires_at: u64,
+}
+
+/// Add or drop `member` in the address list stored under `key`
+fn track(env: &Env, key: &DataKey, member: &Address, present: bool) {
+    let mut members: Vec<Address> = env
+        .storage()
+        .persistent()
+        .get(key)
+        .unwrap_or_else(|| Vec::new(env));
+    match (members.first_index_of(member), present) {
+        (None, true) => members.push_back(member.clone()),
+        (Some(index), false) => {
+            members.remove(index);
+        }
+        _ => return,
+    }
+    if members.is_empty() {
+        env.storage().persistent().remove(key);
+    } else {
+        env.storage().persistent().set(key, &members);
+    }
+}
+
+/// Storage helper for session policies
+pub struct SessionStorage;
+
+impl SessionStorage {
+    fn key(user: &Address, delegate: &Address) -> DataKey {
+        DataKey::Session(user.clone(), delegate.clone())
+    }
+
+    fn delegates_key(user: &Address) -> DataKey {
+        DataKey::SessionDelegates(user.clone())
+    }
+
+    /// Delegates the user has granted a session to
+    pub fn delegates(env: &Env, user: &Address) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&Self::delegates_key(user))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn get(env: &Env, user: &Address, delegate: &Address) -> Option<SessionPolicy> {
+        env.storage().persistent().get(&Self::key(user, delegate))
+    }
+
+    pub fn save(env: &Env, user: &Address, policy: &SessionPolicy) {
+        env.storage()
+            .persistent()
+            .set(&Self::key(user, &policy.delegate), policy);
+        track(env, &Self::delegates_key(user), &policy.delegate, true);
+    }
+
+    pub fn remove(env: &Env, user: &Address, delegate: &Address) {
+        env.storage()
+            .persistent()
+            .remove(&Self::key(user, delegate));
+        track(env, &Self::delegates_key(user), delegate, false);
+    }
+}
+
+/// Storage helper for protector designations
+pub struct ProtectorStorage;
+
+impl ProtectorStorage {
+    fn key(user: &Address, protector: &Address) -> DataKey {
+        DataKey::Protector(user.clone(), protector.clone())
+    }
+
+    fn protectors_key(user: &Address) -> DataKey {
+        DataKey::Protectors(user.clone())
+    }
+
+    /// Protectors the user has designated
+    pub fn protectors(env: &Env, user: &Address) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&Self::protectors_key(user))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn is_protector(env: &Env, user: &Address, protector: &Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&Self::key(user, protector))
+            .unwrap_or(false)
+    }
+
+    pub fn set(env: &Env, user: &Address, protector: &Address, enabled: bool) {
+        let key = Self::key(user, protector);
+        if enabled {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        track(env, &Self::protectors_key(user), protector, enabled);
+    }
+}
+
+/// Session grant, revocation and enforcement
+pub struct SessionManager;
+
+impl SessionManager {
+    pub fn grant(env: &Env, user: &Address, policy: SessionPolicy) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if policy.delegate == *user {
+            return Err(ProtocolError::InvalidAddress);
+        }
+        if policy.max_amount_per_action <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if policy.expires_at <= env.ledger().timestamp() {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if !policy.allow_deposit && !policy.allow_repay {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        SessionStorage::save(env, user, &policy);
+        env.publish_event(
+            (Symbol::new(env, "session_granted"), user.clone()),
+            (
+                Symbol::new(env, "delegate"),
+                policy.delegate.clone(),
+                Symbol::new(env, "max_amount"),
+                policy.max_amount_per_action,
+                Symbol::new(env, "expires_at"),
+                policy.expires_at,
+            ),
+        );
+        Ok(())
+    }
+
+    pub fn revoke(env: &Env, user: &Address, delegate: &Address) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if SessionStorage::get(env, user, delegate).is_none() {
+            return Err(ProtocolError::NotFound);
+        }
+        SessionStorage::remove(env, user, delegate);
+        env.publish_event(
+            (Symbol::new(env, "session_revoked"), user.clone()),
+            (Symbol::new(env, "delegate"), delegate.clone()),
+        );
+        Ok(())
+    }
+
+    /// Allow or stop `protector` depositing and repaying on the user's behalf
+    pub fn set_protector(
+        env: &Env,
+        user: &Address,
+        protector: &Address,
+        enabled: bool,
+    ) -> Result<(), ProtocolError> {
+        user.require_auth();
+        if protector == user {
+            return Err(ProtocolError::InvalidAddress);
+        }
+        if !enabled && !ProtectorStorage::is_protector(env, user, protector) {
+            return Err(ProtocolError::NotFound);
+        }
+
+        ProtectorStorage::set(env, user, protector, enabled);
+        env.publish_event(
+            (Symbol::new(env, "protector_updated"), user.clone()),
+            (protector.clone(), enabled),
+        );
+        Ok(())
+    }
+
+    /// Drop every session and protector the user has granted
+    pub fn clear(env: &Env, user: &Address) {
+        for delegate in SessionStorage::delegates(env, user).iter() {
+            SessionStorage::remove(env, user, &delegate);
+        }
+        for protector in ProtectorStorage::protectors(env, user).iter() {
+            ProtectorStorage::set(env, user, &protector, false);
+        }
+    }
+
+    /// Check that `actor` may perform `operation` for `user`; the owner is always allowed
+    /// and protectors may deposit or repay any amount.
+    /// The caller is expected to have already authenticated `actor`.
+    pub fn authorize(
+        env: &Env,
+        user: &Address,
+        actor: &Address,
+        operation: OperationKind,
+        amount: i128,
+    ) -> Result<(), ProtocolError> {
+        if actor == user {
+            return Ok(());
+        }
+
+        let protective = matches!(operation, OperationKind::Deposit | OperationKind::Repay);
+        if protective && ProtectorStorage::is_protector(env, user, actor) {
+            return Ok(());
+        }
+
+        let policy = SessionStorage::get(env, user, actor).ok_or(ProtocolError::Unauthorized)?;
+        if env.ledger().timestamp() > policy.expires_at {
+            return Err(ProtocolError::Unauthorized);
+        }
+
+        let allowed = match operation {
+            OperationKind::Deposit => policy.allow_deposit,
+            OperationKind::Repay => policy.allow_repay,
+            _ => false,
+        };
+        if !allowed {
+            return Err(ProtocolError::Unauthorized);
+        }
+
+        if amount > policy.max_amount_per_action {
+            return Err(ProtocolError::UserLimitExceeded);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+//! Shutdown module for StellarLend protocol
+//! Last-resort exit after a prolonged emergency pause: once the protocol has been paused
+//! for longer than the configured delay, users can pull their collateral out pro rata to
+//! what the pool still holds, without depending on oracles or risk configuration
+
+use crate::cross_asset::CrossStateHelper;
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{
+    EmergencyStatus, EmergencyStorage, FreezeLevel, InterestRateStorage, ProtocolConfig,
+    ProtocolError, SafeMath, StateHelper, TokenRegistry, TransferEnforcer, UserManager,
+};
+use soroban_sdk::{token::TokenClient, Address, Env, Symbol};
+
+/// Storage helper for the emergency redemption delay
+pub struct ShutdownStorage;
+
+impl ShutdownStorage {
+    fn delay_key() -> DataKey {
+        DataKey::EmergencyRedeemDelay
+    }
+
+    /// Seconds the protocol must stay paused before redemptions open; zero disables them
+    pub fn get_delay(env: &Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&Self::delay_key())
+            .unwrap_or(0)
+    }
+
+    pub fn set_delay(env: &Env, delay: u64) {
+        env.storage().instance().set(&Self::delay_key(), &delay);
+    }
+}
+
+/// Pro-rata collateral redemption during a prolonged shutdown
+pub struct EmergencyRedemption;
+
+impl EmergencyRedemption {
+    pub fn set_delay(env: &Env, caller: &Address, delay: u64) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        ShutdownStorage::set_delay(env, delay);
+        env.publish_event(
+            (
+                Symbol::new(env, "emergency_redeem_delay_set"),
+                caller.clone(),
+            ),
+            delay,
+        );
+        Ok(())
+    }
+
+    /// Time from which redemptions are open, if the protocol is paused and a delay is set
+    pub fn available_at(env: &Env) -> Option<u64> {
+        let state = EmergencyStorage::get(env);
+        let delay = ShutdownStorage::get_delay(env);
+        if state.status != EmergencyStatus::Paused || delay == 0 {
+            return None;
+        }
+        Some(state.paused_at.saturating_add(delay))
+    }
+
+    /// Withdraw the user's whole balance of `asset`. Primary collateral is netted against
+    /// outstanding debt and paid pro rata to the pool's remaining balance when the pool
+    /// cannot cover every supplier; cross-asset collateral is only released once the debt
+    /// has been netted. Returns the amount paid out.
+    pub fn redeem(env: &Env, user: &Address, asset: &Address) -> Result<i128, ProtocolError> {
+        match Self::available_at(env) {
+            Some(opens) if env.ledger().timestamp() >= opens => {}
+            _ => return Err(ProtocolError::InvalidOperation),
+        }
+        // A redemption must not become a way around a compliance freeze; withdraw-only
+        // accounts may still wind down
+        if UserManager::view_profile(env, user).freeze_level(env) == FreezeLevel::Halted {
+            return Err(ProtocolError::UserSuspended);
+        }
+
+        let contract = env.current_contract_address();
+        let primary = TokenRegistry::require_primary_asset(env)?;
+        let mut position =
+            StateHelper::get_position(env, user).ok_or(ProtocolError::PositionNotFound)?;
+        let owed = position.debt + position.borrow_interest;
+
+        let (claim, payout) = if *asset == primary {
+            let claim = position.collateral + position.supply_interest - owed;
+            if claim <= 0 {
+                return Err(ProtocolError::InsufficientCollateral);
+            }
+            // What the pool owes suppliers in aggregate after netting every borrower's debt
+            let state = InterestRateStorage::get_state(env);
+            let outstanding = (state.total_supplied - state.total_borrowed).max(claim);
+            let balance = TokenClient::new(env, asset).balance(&contract);
+            let payout = if balance >= outstanding {
+                claim
+            } else {
+                SafeMath::mul_div(claim, balance, outstanding)
+            };
+
+            position.collateral = 0;
+            position.supply_interest = 0;
+            position.debt = 0;
+            position.borrow_interest = 0;
+            StateHelper::save_position(env, &position);
+            if payout > 0 {
+                TransferEnforcer::transfer_out(
+                    env,
+                    user,
+                    payout,
+                    Symbol::new(env, "emergency_redeem"),
+                )?;
+            }
+            (claim, payout)
+        } else {
+            if owed > 0 {
+                return Err(ProtocolError::InvalidOperation);
+            }
+            let mut cross = CrossStateHelper::get_or_init_position(env, user);
+            let claim = cross.collateral.get(asset.clone()).unwrap_or(0);
+            if claim <= 0 {
+                return Err(ProtocolError::InsufficientCollateral);
+            }
+            let client = TokenClient::new(env, asset);
+            let payout = claim.min(client.balance(&contract));
+
+            cross.collateral.set(asset.clone(), 0);
+            cross.last_update = env.ledger().timestamp();
+            CrossStateHelper::save_position(env, &cross);
+            if payout > 0 {
+                client.transfer(&contract, user, &payout);
+            }
+            (claim, payout)
+        };
+
+        env.publish_event(
+            (Symbol::new(env, "emergency_redeemed"), user.clone()),
+            (asset.clone(), claim, payout),
+        );
+        Ok(payout)
+    }
+}
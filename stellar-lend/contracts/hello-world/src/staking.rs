@@ -0,0 +1,183 @@
+//! Staking module for StellarLend protocol
+//! Users lock protocol tokens with the contract in exchange for a personal discount on
+//! the borrow rate, scaling linearly with the stake up to a capped relative discount
+
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{ProtocolConfig, ProtocolError, SafeMath, TokenRegistry};
+use soroban_sdk::{contracttype, token::TokenClient, Address, Env, Symbol};
+
+/// Largest relative borrow rate discount staking can grant (basis points)
+pub const MAX_STAKING_DISCOUNT_BPS: i128 = 5000;
+
+/// Admin-managed staking settings
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct StakingConfig {
+    /// Protocol token users stake
+    pub token: Address,
+    /// Stake at which the full discount applies
+    pub full_discount_stake: i128,
+    /// Relative discount on the borrow rate at the full stake (basis points)
+    pub max_discount_bps: i128,
+    /// Seconds a stake stays locked after it was last increased
+    pub lock_period: u64,
+}
+
+/// Protocol tokens one user has staked
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct StakePosition {
+    pub amount: i128,
+    pub unlocks_at: u64,
+}
+
+/// Storage helper for staking settings and stakes
+pub struct StakingStorage;
+
+impl StakingStorage {
+    fn config_key() -> DataKey {
+        DataKey::StakingConfig
+    }
+
+    fn stake_key(user: &Address) -> DataKey {
+        DataKey::Stake(user.clone())
+    }
+
+    pub fn get_config(env: &Env) -> Option<StakingConfig> {
+        env.storage().instance().get(&Self::config_key())
+    }
+
+    pub fn save_config(env: &Env, config: &StakingConfig) {
+        env.storage().instance().set(&Self::config_key(), config);
+    }
+
+    pub fn get_stake(env: &Env, user: &Address) -> StakePosition {
+        env.storage()
+            .persistent()
+            .get(&Self::stake_key(user))
+            .unwrap_or_default()
+    }
+
+    pub fn save_stake(env: &Env, user: &Address, stake: &StakePosition) {
+        let key = Self::stake_key(user);
+        if stake.amount == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, stake);
+        }
+    }
+}
+
+/// Protocol token staking and the borrow discounts it earns
+pub struct Staking;
+
+impl Staking {
+    pub fn set_config(
+        env: &Env,
+        caller: &Address,
+        config: StakingConfig,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if config.full_discount_stake <= 0
+            || !(0..=MAX_STAKING_DISCOUNT_BPS).contains(&config.max_discount_bps)
+        {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        // Staked tokens must never mix with the lending pool's liquidity
+        if TokenRegistry::require_primary_asset(env).ok().as_ref() == Some(&config.token) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if let Some(existing) = StakingStorage::get_config(env) {
+            // Outstanding stakes are denominated in the existing token
+            if existing.token != config.token {
+                return Err(ProtocolError::InvalidOperation);
+            }
+        }
+        StakingStorage::save_config(env, &config);
+        env.publish_event(
+            (Symbol::new(env, "staking_config_updated"), caller.clone()),
+            (config.full_discount_stake, config.max_discount_bps),
+        );
+        Ok(())
+    }
+
+    /// Lock `amount` more protocol tokens, restarting the lock period
+    pub fn stake(env: &Env, user: &Address, amount: i128) -> Result<StakePosition, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let config = StakingStorage::get_config(env).ok_or(ProtocolError::NotInitialized)?;
+        let client = TokenClient::new(env, &config.token);
+        let contract = env.current_contract_address();
+        let before = client.balance(&contract);
+        client.transfer(user, &contract, &amount);
+        let received = client.balance(&contract).saturating_sub(before);
+        if received != amount {
+            return Err(ProtocolError::BalanceInvariantViolation);
+        }
+
+        let mut stake = StakingStorage::get_stake(env, user);
+        stake.amount += received;
+        stake.unlocks_at = env.ledger().timestamp() + config.lock_period;
+        StakingStorage::save_stake(env, user, &stake);
+        env.publish_event(
+            (Symbol::new(env, "tokens_staked"), user.clone()),
+            (received, stake.amount),
+        );
+        Ok(stake)
+    }
+
+    /// Release `amount` staked tokens once the lock period has passed
+    pub fn unstake(
+        env: &Env,
+        user: &Address,
+        amount: i128,
+    ) -> Result<StakePosition, ProtocolError> {
+        if amount <= 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let config = StakingStorage::get_config(env).ok_or(ProtocolError::NotInitialized)?;
+        let mut stake = StakingStorage::get_stake(env, user);
+        if stake.amount < amount {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        if env.ledger().timestamp() < stake.unlocks_at {
+            return Err(ProtocolError::InvalidOperation);
+        }
+
+        stake.amount -= amount;
+        StakingStorage::save_stake(env, user, &stake);
+        TokenClient::new(env, &config.token).transfer(
+            &env.current_contract_address(),
+            user,
+            &amount,
+        );
+        env.publish_event(
+            (Symbol::new(env, "tokens_unstaked"), user.clone()),
+            (amount, stake.amount),
+        );
+        Ok(stake)
+    }
+
+    /// Relative borrow rate discount the user's stake earns (basis points)
+    pub fn discount_bps(env: &Env, user: &Address) -> i128 {
+        let Some(config) = StakingStorage::get_config(env) else {
+            return 0;
+        };
+        let staked = StakingStorage::get_stake(env, user).amount;
+        if staked >= config.full_discount_stake {
+            return config.max_discount_bps;
+        }
+        SafeMath::mul_div(staked, config.max_discount_bps, config.full_discount_stake)
+    }
+
+    /// Borrow rate after the user's staking discount
+    pub fn discounted_rate(env: &Env, user: &Address, rate: i128) -> i128 {
+        let discount = Self::discount_bps(env, user);
+        if discount == 0 {
+            return rate;
+        }
+        rate - SafeMath::mul_div(rate, discount, 10000)
+    }
+}
@@ -0,0 +1,160 @@
+//! Statement module for StellarLend protocol
+//! Keeps per-account fee totals in daily buckets so back offices can reconcile what each
+//! account paid without replaying raw events
+
+use crate::storage::DataKey;
+use soroban_sdk::{contracttype, Address, Env, Vec};
+
+/// Length of a fee statement bucket
+pub const FEE_BUCKET_SECS: u64 = 24 * 60 * 60;
+
+/// Daily buckets retained per account; older ones only remain in the lifetime totals
+pub const MAX_FEE_BUCKETS: u32 = 90;
+
+/// Kind of fee charged to an account
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum FeeKind {
+    /// Fees charged when opening a loan, e.g. flash loan fees
+    Origination,
+    /// Borrow interest accrued on the account's debt
+    Interest,
+    /// Liquidation bonus taken from the account's collateral
+    LiquidationPenalty,
+}
+
+/// Fees an account paid during one day (`timestamp / FEE_BUCKET_SECS`), or over its lifetime
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct FeeBucket {
+    pub day: u64,
+    pub origination: i128,
+    pub interest: i128,
+    pub liquidation_penalty: i128,
+}
+
+impl FeeBucket {
+    fn add(&mut self, kind: FeeKind, amount: i128) {
+        match kind {
+            FeeKind::Origination => self.origination += amount,
+            FeeKind::Interest => self.interest += amount,
+            FeeKind::LiquidationPenalty => self.liquidation_penalty += amount,
+        }
+    }
+}
+
+/// Fee totals for an account over a time range
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FeeStatement {
+    pub user: Address,
+    pub from_ts: u64,
+    pub to_ts: u64,
+    /// Sums over the daily buckets that fall inside the range
+    pub totals: FeeBucket,
+    pub buckets: Vec<FeeBucket>,
+    /// Everything the account has paid since it first interacted
+    pub lifetime: FeeBucket,
+}
+
+/// Storage helper for per-account fee buckets
+pub struct FeeStatementStorage;
+
+impl FeeStatementStorage {
+    fn buckets_key(user: &Address) -> DataKey {
+        DataKey::FeeBuckets(user.clone())
+    }
+
+    fn lifetime_key(user: &Address) -> DataKey {
+        DataKey::FeeTotals(user.clone())
+    }
+
+    pub fn get_buckets(env: &Env, user: &Address) -> Vec<FeeBucket> {
+        env.storage()
+            .persistent()
+            .get(&Self::buckets_key(user))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    pub fn save_buckets(env: &Env, user: &Address, buckets: &Vec<FeeBucket>) {
+        env.storage()
+            .persistent()
+            .set(&Self::buckets_key(user), buckets);
+    }
+
+    pub fn get_lifetime(env: &Env, user: &Address) -> FeeBucket {
+        env.storage()
+            .persistent()
+            .get(&Self::lifetime_key(user))
+            .unwrap_or_default()
+    }
+
+    pub fn save_lifetime(env: &Env, user: &Address, totals: &FeeBucket) {
+        env.storage()
+            .persistent()
+            .set(&Self::lifetime_key(user), totals);
+    }
+
+    pub fn remove(env: &Env, user: &Address) {
+        env.storage().persistent().remove(&Self::buckets_key(user));
+        env.storage().persistent().remove(&Self::lifetime_key(user));
+    }
+}
+
+/// Per-account fee accounting
+pub struct FeeLedger;
+
+impl FeeLedger {
+    /// Add `amount` of `kind` to the account's current day and lifetime totals
+    pub fn record(env: &Env, user: &Address, kind: FeeKind, amount: i128) {
+        if amount <= 0 {
+            return;
+        }
+        let day = env.ledger().timestamp() / FEE_BUCKET_SECS;
+        let mut buckets = FeeStatementStorage::get_buckets(env, user);
+        let mut bucket = match buckets.last() {
+            Some(last) if last.day == day => {
+                buckets.pop_back();
+                last
+            }
+            _ => FeeBucket {
+                day,
+                ..FeeBucket::default()
+            },
+        };
+        bucket.add(kind, amount);
+        buckets.push_back(bucket);
+        while buckets.len() > MAX_FEE_BUCKETS {
+            buckets.pop_front();
+        }
+        FeeStatementStorage::save_buckets(env, user, &buckets);
+
+        let mut lifetime = FeeStatementStorage::get_lifetime(env, user);
+        lifetime.add(kind, amount);
+        FeeStatementStorage::save_lifetime(env, user, &lifetime);
+    }
+
+    /// Daily buckets starting within `[from_ts, to_ts]` and their totals
+    pub fn statement(env: &Env, user: &Address, from_ts: u64, to_ts: u64) -> FeeStatement {
+        let mut totals = FeeBucket::default();
+        let mut buckets = Vec::new(env);
+        for bucket in FeeStatementStorage::get_buckets(env, user).iter() {
+            let start = bucket.day * FEE_BUCKET_SECS;
+            if start < from_ts || start > to_ts {
+                continue;
+            }
+            totals.origination += bucket.origination;
+            totals.interest += bucket.interest;
+            totals.liquidation_penalty += bucket.liquidation_penalty;
+            buckets.push_back(bucket);
+        }
+        FeeStatement {
+            user: user.clone(),
+            from_ts,
+            to_ts,
+            totals,
+            buckets,
+            lifetime: FeeStatementStorage::get_lifetime(env, user),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Storage module for StellarLend protocol
+//! Typed keys for every item the contract stores. Keeping them in one enum means two
+//! modules can never write to the same slot by picking the same symbol, and no key is
+//! assembled from strings at runtime. The enum is crate-private so it stays out of the
+//! contract spec. Global configuration and counters live in instance storage; entries
+//! keyed by a user or an id live in persistent storage, so the instance that every call
+//! loads does not grow with the number of users.
+
+use crate::governance::ProposalStatus;
+use crate::revenue::RevenueGranularity;
+use crate::rewards::RewardSide;
+use soroban_sdk::{contracttype, Address, Symbol};
+
+/// Storage key for every contract item
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub(crate) enum DataKey {
+    // Protocol configuration
+    Admin,
+    Oracle,
+    MinCollateralRatio,
+    HealthFactorBuffer,
+    FlashFeeBps,
+    Treasury,
+    TokenRegistry,
+    RiskConfig,
+    PauseInfo,
+    ConfigProposal(u64),
+    ConfigVersion(u32),
+    ConfigLatestVersion,
+
+    // Emergency controls
+    EmergencyState,
+    CoverCap,
+    CoverUsage,
+    EmergencyRedeemDelay,
+    CircuitBreak,
+    CircuitBreakUsed(Address),
+    IncidentSeq,
+    EmergencySigners,
+    EmergencyWithdrawal(u64),
+    EmergencyWithdrawalSeq,
+    /// Held in temporary storage for the duration of a call
+    Reentrancy,
+    ExternalCallAllowlist,
+    /// Held in temporary storage while an external call is in progress
+    ExternalCallDepth,
+
+    // Interest and positions
+    InterestConfig,
+    InterestState,
+    SupplyFee,
+    Position(Address),
+    PositionHistory(Address),
+    CrossPosition(Address),
+    CollateralDisabled(Address),
+    UserMarkets(Address),
+    BorrowTranches(Address),
+    GraceWindows,
+    AutoRepayCap(Address),
+    NotificationPrefs(Address),
+
+    // Users
+    UserProfile(Address),
+    ComplianceSummary,
+    Session(Address, Address),
+    SessionDelegates(Address),
+    Protector(Address, Address),
+    Protectors(Address),
+    ReceiptAllowance(Address, Address),
+    ReceiptDecimals,
+    RelayNonce(Address),
+
+    // Asset registry and prices
+    AssetParams,
+    AssetPrices,
+    AssetPriceTimes,
+    AssetProvenance,
+    AssetConfidences,
+    ConfidenceK,
+    WrapperHaircut,
+    CollateralAdapters,
+    InterestCollateral,
+    DexAdapters,
+    OracleSource(Address),
+    OracleSecondary(Address),
+    OracleHeartbeatTtl,
+    OracleMaxDeviation,
+    OracleAssetConfig(Address),
+    OracleMode,
+    OraclePerfCount,
+    PricePushConfig,
+    PricePushTimes,
+    ListingConfig,
+    ListingProposal(u64),
+    ListingSeq,
+    ListingRevenueShare(Address),
+
+    // Limits and circuit breakers
+    ProtocolParams,
+    BorrowFrozen,
+    MarketPauses(Address),
+    PositionCaps,
+    AddressCap(Address, Address),
+    MarketBootstrap,
+    ParamChangeLimit,
+    ParamAnchor(Symbol),
+    UtilBreakers,
+    UtilTracker(Address),
+    ThinMarketRates,
+    BorrowCooldown(Address),
+    SameLedgerGuard,
+    GuardedAction(Address),
+    RecoveryConfig,
+    RecoveryActive,
+
+    // Liquidation
+    IncentiveCurves,
+    CloseFactorBands,
+    SeizureOrder(Address),
+    SeizureOrderDefault,
+    SeizureRounding,
+    LiquidationTargetHf,
+    LiquidationFees,
+    RestrictedLiquidations,
+    ApprovedLiquidator(Address),
+    DebtAuction(u64),
+    DebtAuctionSeq,
+    LiquidationWatchlist,
+
+    // Quotes, rebates and staking
+    BorrowQuote(u64),
+    BorrowQuoteSeq,
+    RateLock(Address),
+    RebateConfig,
+    RebatePot(Address),
+    StakingConfig,
+    Stake(Address),
+
+    // Revenue and rewards
+    RevenueMetrics,
+    BuybackConfig,
+    RevenueHistory(RevenueGranularity),
+    FeeSplits,
+    TreasuryBalance(Address),
+    RevenueByAsset,
+    FeeBuckets(Address),
+    FeeTotals(Address),
+    EmissionSchedule(Address, RewardSide),
+    RewardIndex(Address, RewardSide),
+    RewardCheckpoint(Address, Address, RewardSide),
+    EmissionBands(Address, RewardSide),
+
+    // Governance
+    GovProposal(u64),
+    GovStatus(ProposalStatus),
+    GovReceipts(u64),
+    GovCounter,
+    GovQuorumBps,
+    GovTimelock,
+    GovDelegation(Address),
+
+    // Events and analytics
+    EventAggregates,
+    EventLogs,
+    EventSummary,
+    EventSeq,
+    ProtocolMetrics,
+    UserAnalytics(Address),
+    AnalyticsUsers,
+    ActiveUsers(u64, u64),
+    AssetAnalytics,
+    HistoricalData,
+    ProtocolCheckpoints,
+    RiskAnalytics,
+    PerformanceMetrics,
+    ActivityLog,
+}
@@ -0,0 +1,113 @@
+//! Stress module for StellarLend protocol
+//! Interest model stress helpers compiled only with the `testutils` feature, so
+//! integrators and auditors can drive deterministic rate scenarios against a deployed
+//! test instance. Never build production contracts with this feature.
+
+use crate::events::SequencedEvents;
+use crate::{
+    InterestRateConfig, InterestRateState, InterestRateStorage, ProtocolConfig, ProtocolError,
+    SafeMath, StateHelper,
+};
+use soroban_sdk::{Address, Env, Symbol, Vec};
+
+/// Most configs or positions a single stress call may touch
+pub const MAX_STRESS_BATCH: u32 = 50;
+
+/// Admin-only interest model manipulation for test deployments
+pub struct StressTools;
+
+impl StressTools {
+    /// Backdate the pool's and the listed positions' accrual clocks by `seconds`, then
+    /// accrue the pool, as if that much time had passed at the current rates
+    pub fn fast_forward(
+        env: &Env,
+        caller: &Address,
+        seconds: u64,
+        users: &Vec<Address>,
+    ) -> Result<InterestRateState, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if seconds == 0 || users.len() > MAX_STRESS_BATCH {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        let mut state = InterestRateStorage::get_state(env);
+        state.last_accrual_time = state.last_accrual_time.saturating_sub(seconds);
+        InterestRateStorage::save_state(env, &state);
+        for user in users.iter() {
+            if let Some(mut position) = StateHelper::get_position(env, &user) {
+                position.last_accrual_time = position.last_accrual_time.saturating_sub(seconds);
+                StateHelper::save_position(env, &position);
+            }
+        }
+
+        let state = InterestRateStorage::update_state(env);
+        env.publish_event(
+            (Symbol::new(env, "stress_fast_forward"), caller.clone()),
+            seconds,
+        );
+        Ok(state)
+    }
+
+    /// Rewrite total borrows so the pool sits at `utilization` (scaled by 1e8) and
+    /// recompute rates from it
+    pub fn set_utilization(
+        env: &Env,
+        caller: &Address,
+        utilization: i128,
+    ) -> Result<InterestRateState, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if !(0..=100000000).contains(&utilization) {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        let mut state = InterestRateStorage::get_state(env);
+        if state.total_supplied <= 0 {
+            return Err(ProtocolError::InvalidOperation);
+        }
+        state.total_borrowed = SafeMath::mul_div(state.total_supplied, utilization, 100000000);
+        InterestRateStorage::save_state(env, &state);
+
+        let state = InterestRateStorage::update_state(env);
+        env.publish_event(
+            (Symbol::new(env, "stress_utilization_set"), caller.clone()),
+            utilization,
+        );
+        Ok(state)
+    }
+
+    /// Apply each config in turn for `step` seconds of accrual and return the pool state
+    /// after every step. The last config stays in force.
+    pub fn replay_rates(
+        env: &Env,
+        caller: &Address,
+        configs: &Vec<InterestRateConfig>,
+        step: u64,
+    ) -> Result<Vec<InterestRateState>, ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if configs.is_empty() || configs.len() > MAX_STRESS_BATCH || step == 0 {
+            return Err(ProtocolError::InvalidParameters);
+        }
+
+        let mut states = Vec::new(env);
+        for config in configs.iter() {
+            if config.rate_floor > config.rate_ceiling
+                || !(0..=10000).contains(&config.smoothing_bps)
+            {
+                return Err(ProtocolError::InvalidParameters);
+            }
+            // Settle accrual under the previous config, then reprice under the new one
+            // before letting the step elapse
+            InterestRateStorage::update_state(env);
+            InterestRateStorage::save_config(env, &config);
+            let mut state = InterestRateStorage::update_state(env);
+            state.last_accrual_time = state.last_accrual_time.saturating_sub(step);
+            InterestRateStorage::save_state(env, &state);
+            states.push_back(InterestRateStorage::update_state(env));
+        }
+        env.publish_event(
+            (Symbol::new(env, "stress_rates_replayed"), caller.clone()),
+            (configs.len(), step),
+        );
+        Ok(states)
+    }
+}
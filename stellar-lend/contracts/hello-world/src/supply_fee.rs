@@ -0,0 +1,115 @@
+//! Supply fee module for StellarLend protocol
+//! Markets being wound down can discourage idle deposits with a storage fee: a negative
+//! supply rate that decays primary collateral into the reserves as positions accrue.
+//! Every charge is announced to the user it hits, and no position is charged below the
+//! configured floor.
+
+use crate::events::SequencedEvents;
+use crate::revenue::RevenueManager;
+use crate::storage::DataKey;
+use crate::{Position, ProtocolConfig, ProtocolError, SafeMath, TokenRegistry};
+use soroban_sdk::{contracttype, Address, Env, Symbol};
+
+/// Storage fee charged on primary collateral
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SupplyFeeConfig {
+    /// Annual fee on collateral (scaled by 1e8, e.g. 2% = 2000000)
+    pub annual_rate: i128,
+    /// Collateral each user keeps free of the fee
+    pub floor: i128,
+}
+
+/// Storage helper for the supply fee
+pub struct SupplyFeeStorage;
+
+impl SupplyFeeStorage {
+    fn key() -> DataKey {
+        DataKey::SupplyFee
+    }
+
+    pub fn get(env: &Env) -> Option<SupplyFeeConfig> {
+        env.storage().instance().get(&Self::key())
+    }
+
+    pub fn put(env: &Env, config: &Option<SupplyFeeConfig>) {
+        match config {
+            Some(config) => env.storage().instance().set(&Self::key(), config),
+            None => env.storage().instance().remove(&Self::key()),
+        }
+    }
+}
+
+/// Storage fee on idle collateral
+pub struct SupplyFee;
+
+impl SupplyFee {
+    /// Admin: charge idle collateral a storage fee, or stop charging it with `None`
+    pub fn set(
+        env: &Env,
+        caller: &Address,
+        config: Option<SupplyFeeConfig>,
+    ) -> Result<(), ProtocolError> {
+        ProtocolConfig::require_admin(env, caller)?;
+        if let Some(config) = &config {
+            if !(1..=100000000).contains(&config.annual_rate) || config.floor < 0 {
+                return Err(ProtocolError::InvalidParameters);
+            }
+        }
+        SupplyFeeStorage::put(env, &config);
+        env.publish_event(
+            (Symbol::new(env, "supply_fee_updated"), caller.clone()),
+            config,
+        );
+        Ok(())
+    }
+
+    /// Collateral of `position` the fee applies to
+    pub fn chargeable(env: &Env, position: &Position) -> i128 {
+        match SupplyFeeStorage::get(env) {
+            Some(config) => (position.collateral - config.floor).max(0),
+            None => 0,
+        }
+    }
+
+    /// Annual fee rate in force (scaled by 1e8), zero when no fee is charged
+    pub fn annual_rate(env: &Env) -> i128 {
+        SupplyFeeStorage::get(env).map_or(0, |config| config.annual_rate)
+    }
+
+    /// Fee the position owes for `elapsed` seconds
+    pub fn due(env: &Env, position: &Position, elapsed: u64) -> i128 {
+        let chargeable = Self::chargeable(env, position);
+        if chargeable == 0 || elapsed == 0 {
+            return 0;
+        }
+        SafeMath::mul_div(
+            chargeable,
+            Self::annual_rate(env) * elapsed as i128,
+            365 * 24 * 60 * 60 * 100000000,
+        )
+        .min(chargeable)
+    }
+
+    /// Move the fee accrued over `elapsed` seconds from the position's collateral to the
+    /// reserves; returns the amount charged
+    pub fn charge(env: &Env, position: &mut Position, elapsed: u64) -> i128 {
+        let fee = Self::due(env, position, elapsed);
+        if fee <= 0 {
+            return 0;
+        }
+
+        position.collateral -= fee;
+        if let Ok(asset) = TokenRegistry::require_primary_asset(env) {
+            RevenueManager::record_reserve(env, &asset, fee);
+        }
+        env.publish_event(
+            (
+                Symbol::new(env, "supply_fee_charged"),
+                position.user.clone(),
+            ),
+            (fee, position.collateral),
+        );
+        fee
+    }
+}
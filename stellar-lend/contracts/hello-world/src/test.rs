@@ -1,11 +1,34 @@
 #![cfg(test)]
 
 use super::*;
+use alloc::string::ToString;
 use soroban_sdk::{
-    contract, contractimpl, testutils::Address as TestAddress, Address, Env, Map, String, Symbol,
+    auth::{Context, ContractContext},
+    contract, contractimpl,
+    testutils::{
+        storage::{Instance as _, Persistent as _},
+        Address as TestAddress, Events, Ledger,
+    },
+    vec,
+    xdr::{
+        InvokeContractArgs, ScAddress, ScSymbol, ScVal, SorobanAddressCredentials,
+        SorobanAuthorizationEntry, SorobanAuthorizedFunction, SorobanAuthorizedInvocation,
+        SorobanCredentials, VecM,
+    },
+    Address, BytesN, Env, IntoVal, Map, String, Symbol, TryFromVal, Val, Vec,
 };
 
-use crate::{FlashLoan, ProtocolError, ReentrancyGuard};
+use crate::adapter::CollateralAdapter;
+use crate::analytics::ActivityKind;
+use crate::flash_loan::FlashLoan;
+use crate::governance::MAX_PROPOSAL_PAGE;
+use crate::history::MAX_POSITION_HISTORY;
+use crate::liquidate::{CloseFactorBand, IncentiveCurve, SeizureRounding, MAX_LIQUIDATION_BATCH};
+use crate::relay::RelayAction;
+use crate::revenue::{AssetRevenue, BuybackMode, FeeSplit, MAX_REVENUE_HISTORY};
+use crate::statement::FeeBucket;
+use crate::ttl::{StateEntry, TTL_EXTEND_TO, TTL_THRESHOLD};
+use crate::{ProtocolError, ReentrancyGuard, ReentrancyScope};
 
 #[contract]
 pub struct MockToken;
@@ -23,13 +46,58 @@ impl MockToken {
     }
 
     pub fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        let fee_bps: i128 = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "fee_bps"))
+            .unwrap_or(0);
         Self::deduct_balance(&env, &from, amount);
-        Self::add_balance(&env, &to, amount);
+        Self::add_balance(&env, &to, amount - amount * fee_bps / 10000);
+    }
+
+    pub fn approve(env: Env, from: Address, spender: Address, amount: i128, _expiration: u32) {
+        env.storage()
+            .instance()
+            .set(&(Symbol::new(&env, "allowance"), from, spender), &amount);
+    }
+
+    pub fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        let key = (Symbol::new(&env, "allowance"), from.clone(), spender);
+        let allowance: i128 = env.storage().instance().get(&key).unwrap_or(0);
+        if allowance < amount {
+            panic!("insufficient allowance");
+        }
+        env.storage().instance().set(&key, &(allowance - amount));
+        Self::transfer(env, from, to, amount);
+    }
+
+    /// Make transfers deliver `fee_bps` less than requested, like a fee-on-transfer token
+    pub fn set_transfer_fee(env: Env, fee_bps: i128) {
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "fee_bps"), &fee_bps);
     }
 
     pub fn balance(env: Env, id: Address) -> i128 {
         Self::get_balance(&env, &id)
     }
+
+    pub fn burn(env: Env, from: Address, amount: i128) {
+        Self::deduct_balance(&env, &from, amount);
+    }
+
+    pub fn decimals(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "decimals"))
+            .unwrap_or(7)
+    }
+
+    pub fn set_decimals(env: Env, decimals: u32) {
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "decimals"), &decimals);
+    }
 }
 
 impl MockToken {
@@ -87,6 +155,138 @@ impl FlashLoanReceiver {
     }
 }
 
+/// DEX adapter paying out a fixed percentage of the input amount
+#[contract]
+pub struct MockDex;
+
+#[contractimpl]
+impl MockDex {
+    pub fn set_rate_bps(env: Env, rate_bps: i128) {
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "rate_bps"), &rate_bps);
+    }
+
+    pub fn swap(
+        env: Env,
+        _from_asset: Address,
+        to_asset: Address,
+        amount_in: i128,
+        _min_out: i128,
+        recipient: Address,
+    ) -> i128 {
+        let rate_bps: i128 = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "rate_bps"))
+            .unwrap_or(10000);
+        let amount_out = amount_in * rate_bps / 10000;
+        MockTokenClient::new(&env, &to_asset).mint(&recipient, &amount_out);
+        amount_out
+    }
+}
+
+/// Price feed reporting a fixed price and confidence interval for every asset
+#[contract]
+pub struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    pub fn set_feed(env: Env, price: i128, confidence: i128) {
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "feed"), &(price, confidence));
+    }
+
+    pub fn get_price(env: Env, _asset: Address) -> i128 {
+        let (price, _): (i128, i128) = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "feed"))
+            .unwrap();
+        price
+    }
+
+    pub fn get_confidence(env: Env, _asset: Address) -> i128 {
+        let (_, confidence): (i128, i128) = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "feed"))
+            .unwrap();
+        confidence
+    }
+}
+
+/// Collateral adapter valuing its asset at a settable multiple of the units held and
+/// recording the last seizure
+#[contract]
+pub struct MockAdapter;
+
+#[contractimpl]
+impl MockAdapter {
+    pub fn set_rate(env: Env, rate: i128) {
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "rate"), &rate);
+    }
+
+    pub fn last_seizure(env: Env) -> Option<(Address, i128, Address)> {
+        env.storage().instance().get(&Symbol::new(&env, "seizure"))
+    }
+}
+
+#[contractimpl]
+impl CollateralAdapter for MockAdapter {
+    fn value_of(env: Env, _asset: Address, amount: i128) -> i128 {
+        let rate: i128 = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "rate"))
+            .unwrap();
+        amount * rate
+    }
+
+    fn on_seize(env: Env, _asset: Address, user: Address, amount: i128, receiver: Address) {
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "seizure"), &(user, amount, receiver));
+    }
+}
+
+/// Smart wallet that only signs calls into the protocol it was bound to
+#[contract]
+pub struct MockWallet;
+
+#[contractimpl]
+impl MockWallet {
+    pub fn bind(env: Env, protocol: Address) {
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "protocol"), &protocol);
+    }
+
+    #[allow(non_snake_case)]
+    pub fn __check_auth(
+        env: Env,
+        _signature_payload: BytesN<32>,
+        _signature: (),
+        auth_contexts: Vec<Context>,
+    ) -> Result<(), ProtocolError> {
+        let protocol: Address = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "protocol"))
+            .ok_or(ProtocolError::NotInitialized)?;
+        for context in auth_contexts.iter() {
+            match context {
+                Context::Contract(call) if call.contract == protocol => {}
+                _ => return Err(ProtocolError::Unauthorized),
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Test utilities for creating test environments and addresses
 pub struct TestUtils;
 
@@ -135,18 +335,18 @@ impl TestUtils {
 
     pub fn setup_contract_with_token(env: &Env, users: &[Address]) -> (Address, Address, Address) {
         let admin = Self::create_admin_address(env);
-        let contract_id = env.register(Contract, ());
-        env.as_contract(&contract_id, || {
-            Contract::initialize(env.clone(), admin.to_string()).unwrap();
-        });
-
         let token_id = env.register_contract(None, MockToken);
         env.as_contract(&token_id, || {
             MockToken::initialize(env.clone(), admin.clone());
         });
 
+        let contract_id = env.register(Contract, ());
+        let config = InitConfig {
+            native_asset: token_id.clone(),
+            ..Self::init_config(env)
+        };
         env.as_contract(&contract_id, || {
-            Contract::set_primary_asset(env.clone(), admin.to_string(), token_id.clone()).unwrap();
+            Contract::initialize(env.clone(), admin.clone(), config).unwrap();
         });
 
         env.as_contract(&token_id, || {
@@ -159,24 +359,37 @@ impl TestUtils {
         (admin, contract_id, token_id)
     }
 
+    /// Default deployment settings with a fresh treasury and primary token
+    pub fn init_config(env: &Env) -> InitConfig {
+        InitConfig {
+            treasury: Address::generate(env),
+            native_asset: env.register(MockToken, ()),
+            min_collateral_ratio: 150,
+            close_factor: 50000000,
+            liquidation_incentive: 10000000,
+        }
+    }
+
     /// Initialize the contract with test admin
     pub fn initialize_contract(env: &Env) -> Address {
         let admin = Self::create_admin_address(env);
         let contract_id = env.register(Contract, ());
         env.as_contract(&contract_id, || {
-            Contract::initialize(env.clone(), admin.to_string()).unwrap();
+            Contract::initialize(env.clone(), admin.clone(), Self::init_config(env)).unwrap();
         });
         admin
     }
 
     /// Mark a user as verified for testing convenience
-    pub fn verify_user(env: &Env, admin: &Address, user: &Address) {
-        Contract::set_user_verification(
-            env.clone(),
-            admin.to_string(),
-            user.clone(),
-            VerificationStatus::Verified,
-        )
+    pub fn verify_user(env: &Env, contract_id: &Address, admin: &Address, user: &Address) {
+        env.as_contract(contract_id, || {
+            Contract::set_user_verification(
+                env.clone(),
+                admin.clone(),
+                user.clone(),
+                VerificationStatus::Verified,
+            )
+        })
         .unwrap();
     }
 }
@@ -190,11 +403,59 @@ fn test_contract_initialization() {
 
     let contract_id = env.register(Contract, ());
     env.as_contract(&contract_id, || {
-        let result = Contract::initialize(env.clone(), admin.to_string());
+        let result = Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env));
         assert!(result.is_ok());
     });
 }
 
+#[test]
+fn test_initialize_validates_config() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = TestUtils::create_admin_address(&env);
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let same_addresses = TestUtils::init_config(&env);
+    let same_addresses = InitConfig {
+        native_asset: same_addresses.treasury.clone(),
+        ..same_addresses
+    };
+    assert_eq!(
+        client.try_initialize(&admin, &same_addresses),
+        Err(Ok(ProtocolError::InvalidAddress))
+    );
+    let undercollateralized = InitConfig {
+        min_collateral_ratio: 90,
+        ..TestUtils::init_config(&env)
+    };
+    assert_eq!(
+        client.try_initialize(&admin, &undercollateralized),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    let no_close_factor = InitConfig {
+        close_factor: 0,
+        ..TestUtils::init_config(&env)
+    };
+    assert_eq!(
+        client.try_initialize(&admin, &no_close_factor),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+
+    let config = InitConfig {
+        min_collateral_ratio: 120,
+        close_factor: 40000000,
+        ..TestUtils::init_config(&env)
+    };
+    client.initialize(&admin, &config);
+    assert_eq!(client.get_treasury(), Some(config.treasury));
+    let primary = env.as_contract(&contract_id, || TokenRegistry::require_primary_asset(&env));
+    assert_eq!(primary, Ok(config.native_asset));
+    let risk = client.get_risk_config();
+    assert_eq!(risk.0, 40000000);
+}
+
 #[test]
 fn test_contract_already_initialized() {
     let env = Env::default();
@@ -205,11 +466,11 @@ fn test_contract_already_initialized() {
     let contract_id = env.register(Contract, ());
     env.as_contract(&contract_id, || {
         // First initialization should succeed
-        let result = Contract::initialize(env.clone(), admin.to_string());
+        let result = Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env));
         assert!(result.is_ok());
 
         // Second initialization should fail
-        let result = Contract::initialize(env.clone(), admin.to_string());
+        let result = Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env));
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), ProtocolError::AlreadyInitialized);
     });
@@ -223,18 +484,22 @@ fn test_deposit_collateral() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
-
-        // Test successful deposit
-        let result = Contract::deposit_collateral(env.clone(), user.to_string(), 1000);
-        assert!(result.is_ok());
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        // Verify position
-        let position = Contract::get_position(env.clone(), user.to_string()).unwrap();
-        assert_eq!(position.0, 1000); // collateral
-        assert_eq!(position.1, 0); // debt
+    // Test successful deposit
+    let result = env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 1000)
     });
+    assert!(result.is_ok());
+
+    // Verify position
+    let position = env
+        .as_contract(&contract_id, || {
+            Contract::get_position(env.clone(), user.clone())
+        })
+        .unwrap();
+    assert_eq!(position.0, 1000); // collateral
+    assert_eq!(position.1, 0); // debt
 }
 
 #[test]
@@ -245,38 +510,37 @@ fn test_deposit_collateral_invalid_amount() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        // Test deposit with zero amount
-        let result = Contract::deposit_collateral(env.clone(), user.to_string(), 0);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), ProtocolError::InvalidAmount);
+    // Test deposit with zero amount
+    let result = env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 0)
+    });
+    assert!(result.is_err());
+    assert_eq!(result.unwrap_err(), ProtocolError::InvalidAmount);
 
-        // Test deposit with negative amount
-        let result = Contract::deposit_collateral(env.clone(), user.to_string(), -100);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), ProtocolError::InvalidAmount);
+    // Test deposit with negative amount
+    let result = env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), -100)
     });
+    assert!(result.is_err());
+    assert_eq!(result.unwrap_err(), ProtocolError::InvalidAmount);
 }
 
 #[test]
-fn test_deposit_collateral_invalid_address() {
+fn test_deposit_collateral_requires_depositor_auth() {
     let env = Env::default();
-    env.mock_all_auths();
 
     let admin = TestUtils::create_admin_address(&env);
+    let user = TestUtils::create_user_address(&env, 0);
 
     let contract_id = env.register(Contract, ());
-    env.as_contract(&contract_id, || {
-        // Initialize contract
-        Contract::initialize(env.clone(), admin.to_string()).unwrap();
+    let client = ContractClient::new(&env, &contract_id);
+    client.initialize(&admin, &TestUtils::init_config(&env));
 
-        // Test deposit with empty address
-        let result = Contract::deposit_collateral(env.clone(), String::from_str(&env, ""), 1000);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), ProtocolError::InvalidAddress);
-    });
+    // No authorization from the depositor is supplied
+    let result = client.try_deposit_collateral(&user, &1000);
+    assert!(result.is_err());
 }
 
 #[test]
@@ -287,21 +551,28 @@ fn test_borrow_success() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
-
-        // Deposit collateral first
-        Contract::deposit_collateral(env.clone(), user.to_string(), 2000).unwrap();
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        // Test successful borrow
-        let result = Contract::borrow(env.clone(), user.to_string(), 1000);
-        assert!(result.is_ok());
+    // Deposit collateral first
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 2000)
+    })
+    .unwrap();
 
-        // Verify position
-        let position = Contract::get_position(env.clone(), user.to_string()).unwrap();
-        assert_eq!(position.0, 2000); // collateral
-        assert_eq!(position.1, 1000); // debt
+    // Test successful borrow
+    let result = env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 1000)
     });
+    assert!(result.is_ok());
+
+    // Verify position
+    let position = env
+        .as_contract(&contract_id, || {
+            Contract::get_position(env.clone(), user.clone())
+        })
+        .unwrap();
+    assert_eq!(position.0, 2000); // collateral
+    assert_eq!(position.1, 1000); // debt
 }
 
 #[test]
@@ -312,20 +583,23 @@ fn test_borrow_insufficient_collateral_ratio() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        // Deposit small amount of collateral
-        Contract::deposit_collateral(env.clone(), user.to_string(), 100).unwrap();
+    // Deposit small amount of collateral
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 100)
+    })
+    .unwrap();
 
-        // Try to borrow too much (should fail due to insufficient collateral ratio)
-        let result = Contract::borrow(env.clone(), user.to_string(), 1000);
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err(),
-            ProtocolError::InsufficientCollateralRatio
-        );
+    // Try to borrow too much (should fail due to insufficient collateral ratio)
+    let result = env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 1000)
     });
+    assert!(result.is_err());
+    assert_eq!(
+        result.unwrap_err(),
+        ProtocolError::InsufficientCollateralRatio
+    );
 }
 
 #[test]
@@ -336,20 +610,28 @@ fn test_emergency_pause_blocks_deposit() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        let reason = Some(String::from_str(&env, "halt"));
-        Contract::trigger_emergency_pause(env.clone(), admin.to_string(), reason).unwrap();
+    let reason = Some(String::from_str(&env, "halt"));
+    env.as_contract(&contract_id, || {
+        Contract::trigger_emergency_pause(env.clone(), admin.clone(), reason)
+    })
+    .unwrap();
 
-        let result = Contract::deposit_collateral(env.clone(), user.to_string(), 1000);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), ProtocolError::ProtocolPaused);
+    let result = env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 1000)
+    });
+    assert!(result.is_err());
+    assert_eq!(result.unwrap_err(), ProtocolError::ProtocolPaused);
 
-        Contract::resume_operations(env.clone(), admin.to_string()).unwrap();
-        let result = Contract::deposit_collateral(env.clone(), user.to_string(), 1000);
-        assert!(result.is_ok());
+    env.as_contract(&contract_id, || {
+        Contract::resume_operations(env.clone(), admin.clone())
+    })
+    .unwrap();
+    let result = env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 1000)
     });
+    assert!(result.is_ok());
 }
 
 #[test]
@@ -360,38 +642,53 @@ fn test_recovery_mode_allows_repay_blocks_borrow() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        Contract::deposit_collateral(env.clone(), user.to_string(), 2000).unwrap();
-        Contract::borrow(env.clone(), user.to_string(), 500).unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 2000)
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 500)
+    })
+    .unwrap();
 
-        let plan = Some(String::from_str(&env, "staged restart"));
-        Contract::enter_recovery_mode(env.clone(), admin.to_string(), plan).unwrap();
+    let plan = Some(String::from_str(&env, "staged restart"));
+    env.as_contract(&contract_id, || {
+        Contract::enter_recovery_mode(env.clone(), admin.clone(), plan)
+    })
+    .unwrap();
 
+    env.as_contract(&contract_id, || {
         Contract::record_recovery_step(
             env.clone(),
-            admin.to_string(),
+            admin.clone(),
             String::from_str(&env, "notified stakeholders"),
         )
-        .unwrap();
-
-        // Repay should be allowed in recovery mode
-        let repay_result = Contract::repay(env.clone(), user.to_string(), 200);
-        assert!(repay_result.is_ok());
+    })
+    .unwrap();
 
-        // Borrow should be restricted while in recovery
-        let borrow_result = Contract::borrow(env.clone(), user.to_string(), 100);
-        assert!(borrow_result.is_err());
-        assert_eq!(
-            borrow_result.unwrap_err(),
-            ProtocolError::RecoveryModeRestricted
-        );
+    // Repay should be allowed in recovery mode
+    let repay_result = env.as_contract(&contract_id, || {
+        Contract::repay(env.clone(), user.clone(), 200)
+    });
+    assert!(repay_result.is_ok());
 
-        let state = Contract::get_emergency_state(env.clone()).unwrap();
-        assert_eq!(state.status, EmergencyStatus::Recovery);
-        assert_eq!(state.recovery_steps.len(), 1u32);
+    // Borrow should be restricted while in recovery
+    let borrow_result = env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 100)
     });
+    assert!(borrow_result.is_err());
+    assert_eq!(
+        borrow_result.unwrap_err(),
+        ProtocolError::RecoveryModeRestricted
+    );
+
+    let state = env
+        .as_contract(&contract_id, || Contract::get_emergency_state(env.clone()))
+        .unwrap();
+    assert_eq!(state.status, EmergencyStatus::Recovery);
+    assert_eq!(state.recovery_steps.len(), 1u32);
 }
 
 #[test]
@@ -403,24 +700,32 @@ fn test_emergency_param_updates_apply() {
 
     let contract_id = env.register(Contract, ());
     env.as_contract(&contract_id, || {
-        Contract::initialize(env.clone(), admin.to_string()).unwrap();
+        Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env))
+    })
+    .unwrap();
 
-        let base_rate_symbol = Symbol::new(&env, "base_rate");
+    let base_rate_symbol = Symbol::new(&env, "base_rate");
+    env.as_contract(&contract_id, || {
         Contract::queue_emergency_param_update(
             env.clone(),
-            admin.to_string(),
+            admin.clone(),
             base_rate_symbol,
             5000000,
         )
-        .unwrap();
-        Contract::apply_emergency_param_updates(env.clone(), admin.to_string()).unwrap();
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::apply_emergency_param_updates(env.clone(), admin.clone())
+    })
+    .unwrap();
 
-        let config = InterestRateStorage::get_config(&env);
-        assert_eq!(config.base_rate, 5000000);
+    let config = env.as_contract(&contract_id, || InterestRateStorage::get_config(&env));
+    assert_eq!(config.base_rate, 5000000);
 
-        let state = Contract::get_emergency_state(env.clone()).unwrap();
-        assert_eq!(state.pending_param_updates.len(), 0u32);
-    });
+    let state = env
+        .as_contract(&contract_id, || Contract::get_emergency_state(env.clone()))
+        .unwrap();
+    assert_eq!(state.pending_param_updates.len(), 0u32);
 }
 
 #[test]
@@ -433,28 +738,90 @@ fn test_emergency_fund_management() {
 
     let contract_id = env.register(Contract, ());
     env.as_contract(&contract_id, || {
-        Contract::initialize(env.clone(), admin.to_string()).unwrap();
+        Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env))
+    })
+    .unwrap();
 
-        let token = Some(recipient.clone());
+    let token = Some(recipient.clone());
+    env.as_contract(&contract_id, || {
         Contract::adjust_emergency_fund(
             env.clone(),
-            admin.to_string(),
+            admin.clone(),
             token.clone(),
             1_000_000,
             500_000,
         )
+    })
+    .unwrap();
+
+    let state = env
+        .as_contract(&contract_id, || Contract::get_emergency_state(env.clone()))
         .unwrap();
+    assert_eq!(state.fund.balance, 1_000_000);
+    assert_eq!(state.fund.reserved, 500_000);
+    assert_eq!(state.fund.token, token);
+
+    let err = env
+        .as_contract(&contract_id, || {
+            Contract::adjust_emergency_fund(env.clone(), admin.clone(), None, -2_000_000, 0)
+        })
+        .unwrap_err();
+    assert_eq!(err, ProtocolError::EmergencyFundInsufficient);
+}
 
-        let state = Contract::get_emergency_state(env.clone()).unwrap();
-        assert_eq!(state.fund.balance, 1_000_000);
-        assert_eq!(state.fund.reserved, 500_000);
-        assert_eq!(state.fund.token, token);
+#[test]
+fn test_cover_position_spends_insurance_fund_within_incident_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
 
-        let err =
-            Contract::adjust_emergency_fund(env.clone(), admin.to_string(), None, -2_000_000, 0)
-                .unwrap_err();
-        assert_eq!(err, ProtocolError::EmergencyFundInsufficient);
-    });
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&user, &3_000);
+    client.borrow(&user, &1_000);
+    client.adjust_emergency_fund(&admin, &Some(token.clone()), &5_000, &4_000);
+    client.set_cover_cap(&admin, &600);
+    let reason = String::from_str(&env, "oracle exploit reimbursement");
+
+    // Only during an incident
+    assert_eq!(
+        client.try_cover_position(&admin, &user, &token, &100, &reason),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+    client.trigger_emergency_pause(&admin, &None);
+    assert_eq!(
+        client.try_cover_position(&admin, &user, &token, &100, &String::from_str(&env, "")),
+        Err(Ok(ProtocolError::InvalidInput))
+    );
+
+    assert_eq!(
+        client.cover_position(&admin, &user, &token, &400, &reason),
+        400
+    );
+    assert_eq!(client.get_position(&user).1, 600);
+    assert_eq!(client.get_emergency_state().fund.balance, 4_600);
+
+    // 400 of the 600 cap is spent; the unreserved fund also has 600 left
+    assert_eq!(
+        client.try_cover_position(&admin, &user, &token, &300, &reason),
+        Err(Ok(ProtocolError::CapExceeded))
+    );
+    assert_eq!(
+        client.try_cover_position(&Address::generate(&env), &user, &token, &100, &reason),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+
+    // A new incident gets a fresh cap, bounded by the unreserved balance
+    client.resume_operations(&admin);
+    env.ledger().with_mut(|li| li.timestamp += 10);
+    client.trigger_emergency_pause(&admin, &None);
+    assert_eq!(
+        client.cover_position(&admin, &user, &token, &600, &reason),
+        600
+    );
+    assert_eq!(client.get_position(&user).1, 0);
 }
 
 #[test]
@@ -465,22 +832,32 @@ fn test_repay_success() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
-
-        // Deposit and borrow
-        Contract::deposit_collateral(env.clone(), user.to_string(), 2000).unwrap();
-        Contract::borrow(env.clone(), user.to_string(), 1000).unwrap();
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        // Test successful repayment
-        let result = Contract::repay(env.clone(), user.to_string(), 500);
-        assert!(result.is_ok());
+    // Deposit and borrow
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 2000)
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 1000)
+    })
+    .unwrap();
 
-        // Verify position
-        let position = Contract::get_position(env.clone(), user.to_string()).unwrap();
-        assert_eq!(position.0, 2000); // collateral
-        assert_eq!(position.1, 500); // debt
+    // Test successful repayment
+    let result = env.as_contract(&contract_id, || {
+        Contract::repay(env.clone(), user.clone(), 500)
     });
+    assert!(result.is_ok());
+
+    // Verify position
+    let position = env
+        .as_contract(&contract_id, || {
+            Contract::get_position(env.clone(), user.clone())
+        })
+        .unwrap();
+    assert_eq!(position.0, 2000); // collateral
+    assert_eq!(position.1, 500); // debt
 }
 
 #[test]
@@ -491,22 +868,32 @@ fn test_repay_full_amount() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        // Deposit and borrow
-        Contract::deposit_collateral(env.clone(), user.to_string(), 2000).unwrap();
-        Contract::borrow(env.clone(), user.to_string(), 1000).unwrap();
-
-        // Test full repayment
-        let result = Contract::repay(env.clone(), user.to_string(), 1000);
-        assert!(result.is_ok());
+    // Deposit and borrow
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 2000)
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 1000)
+    })
+    .unwrap();
 
-        // Verify position
-        let position = Contract::get_position(env.clone(), user.to_string()).unwrap();
-        assert_eq!(position.0, 2000); // collateral
-        assert_eq!(position.1, 0); // debt
+    // Test full repayment
+    let result = env.as_contract(&contract_id, || {
+        Contract::repay(env.clone(), user.clone(), 1000)
     });
+    assert!(result.is_ok());
+
+    // Verify position
+    let position = env
+        .as_contract(&contract_id, || {
+            Contract::get_position(env.clone(), user.clone())
+        })
+        .unwrap();
+    assert_eq!(position.0, 2000); // collateral
+    assert_eq!(position.1, 0); // debt
 }
 
 #[test]
@@ -517,21 +904,28 @@ fn test_withdraw_success() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
-
-        // Deposit collateral
-        Contract::deposit_collateral(env.clone(), user.to_string(), 2000).unwrap();
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        // Test successful withdrawal
-        let result = Contract::withdraw(env.clone(), user.to_string(), 1000);
-        assert!(result.is_ok());
+    // Deposit collateral
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 2000)
+    })
+    .unwrap();
 
-        // Verify position
-        let position = Contract::get_position(env.clone(), user.to_string()).unwrap();
-        assert_eq!(position.0, 1000); // collateral
-        assert_eq!(position.1, 0); // debt
+    // Test successful withdrawal
+    let result = env.as_contract(&contract_id, || {
+        Contract::withdraw(env.clone(), user.clone(), 1000)
     });
+    assert!(result.is_ok());
+
+    // Verify position
+    let position = env
+        .as_contract(&contract_id, || {
+            Contract::get_position(env.clone(), user.clone())
+        })
+        .unwrap();
+    assert_eq!(position.0, 1000); // collateral
+    assert_eq!(position.1, 0); // debt
 }
 
 #[test]
@@ -542,29 +936,43 @@ fn test_event_summary_updates() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
-
-        Contract::deposit_collateral(env.clone(), user.to_string(), 1200).unwrap();
-        Contract::withdraw(env.clone(), user.to_string(), 200).unwrap();
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        let summary = Contract::get_event_summary(env.clone()).unwrap();
-        let totals = summary.totals;
-        let key = Symbol::new(&env, "position_updated");
-        let aggregate = totals.get(key).unwrap();
-        assert!(aggregate.count > 0);
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 1200)
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::withdraw(env.clone(), user.clone(), 200)
+    })
+    .unwrap();
 
-        let recent_types = Contract::get_recent_event_types(env.clone()).unwrap();
-        assert!(recent_types.len() > 0);
+    let summary = env
+        .as_contract(&contract_id, || Contract::get_event_summary(env.clone()))
+        .unwrap();
+    let totals = summary.totals;
+    let key = Symbol::new(&env, "position_updated");
+    let aggregate = totals.get(key).unwrap();
+    assert!(aggregate.count > 0);
+
+    let recent_types = env
+        .as_contract(&contract_id, || {
+            Contract::get_recent_event_types(env.clone())
+        })
+        .unwrap();
+    assert!(recent_types.len() > 0);
 
-        let events =
+    let events = env
+        .as_contract(&contract_id, || {
             Contract::get_events_for_type(env.clone(), Symbol::new(&env, "position_updated"), 5)
-                .unwrap();
-        assert!(events.len() > 0);
+        })
+        .unwrap();
+    assert!(events.len() > 0);
 
-        let aggregates = Contract::get_event_aggregates(env.clone()).unwrap();
-        assert!(aggregates.len() >= totals.len());
-    });
+    let aggregates = env
+        .as_contract(&contract_id, || Contract::get_event_aggregates(env.clone()))
+        .unwrap();
+    assert!(aggregates.len() >= totals.len());
 }
 
 #[test]
@@ -577,7 +985,7 @@ fn test_deposit_reentrancy_blocked() {
 
     env.as_contract(&contract_id, || {
         ReentrancyGuard::enter(&env).unwrap();
-        let result = Contract::deposit_collateral(env.clone(), user.to_string(), 100);
+        let result = Contract::deposit_collateral(env.clone(), user.clone(), 100);
         ReentrancyGuard::exit(&env);
         assert_eq!(Err(ProtocolError::ReentrancyDetected), result);
     });
@@ -591,17 +999,20 @@ fn test_withdraw_insufficient_collateral() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        // Deposit small amount
-        Contract::deposit_collateral(env.clone(), user.to_string(), 100).unwrap();
+    // Deposit small amount
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 100)
+    })
+    .unwrap();
 
-        // Try to withdraw more than deposited
-        let result = Contract::withdraw(env.clone(), user.to_string(), 200);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), ProtocolError::InsufficientCollateral);
+    // Try to withdraw more than deposited
+    let result = env.as_contract(&contract_id, || {
+        Contract::withdraw(env.clone(), user.clone(), 200)
     });
+    assert!(result.is_err());
+    assert_eq!(result.unwrap_err(), ProtocolError::InsufficientCollateral);
 }
 
 #[test]
@@ -612,21 +1023,62 @@ fn test_withdraw_insufficient_collateral_ratio() {
     let user = TestUtils::create_user_address(&env, 0);
 
     let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
 
-        // Deposit and borrow
-        Contract::deposit_collateral(env.clone(), user.to_string(), 2000).unwrap();
-        Contract::borrow(env.clone(), user.to_string(), 1000).unwrap();
+    // Deposit and borrow
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 2000)
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 1000)
+    })
+    .unwrap();
 
-        // Try to withdraw too much (would make collateral ratio too low)
-        let result = Contract::withdraw(env.clone(), user.to_string(), 1500);
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err(),
-            ProtocolError::InsufficientCollateralRatio
-        );
+    // Try to withdraw too much (would make collateral ratio too low)
+    let result = env.as_contract(&contract_id, || {
+        Contract::withdraw(env.clone(), user.clone(), 1500)
     });
+    assert!(result.is_err());
+    assert_eq!(
+        result.unwrap_err(),
+        ProtocolError::InsufficientCollateralRatio
+    );
+}
+
+#[test]
+fn test_pull_repay_respects_cap_and_opt_out() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let keeper = Address::generate(&env);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&user, &3_000);
+    client.borrow(&user, &1_000);
+    MockTokenClient::new(&env, &token).approve(&user, &contract_id, &10_000, &1_000);
+
+    assert_eq!(
+        client.try_pull_repay(&keeper, &user, &100),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+
+    client.set_auto_repay_cap(&user, &500);
+    assert_eq!(client.pull_repay(&keeper, &user, &300), 300);
+    assert_eq!(client.get_position(&user).1, 700);
+    assert_eq!(client.get_auto_repay_cap(&user), 200);
+    assert_eq!(
+        client.try_pull_repay(&keeper, &user, &300),
+        Err(Ok(ProtocolError::CapExceeded))
+    );
+
+    client.set_auto_repay_cap(&user, &0);
+    assert_eq!(
+        client.try_pull_repay(&keeper, &user, &100),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
 }
 
 #[test]
@@ -639,25 +1091,250 @@ fn test_liquidate_success() {
 
     let (admin, contract_id, _token) =
         TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
-        TestUtils::verify_user(&env, &admin, &liquidator);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
 
-        // Set a very low minimum collateral ratio for testing
-        Contract::set_min_collateral_ratio(env.clone(), admin.to_string(), 50).unwrap();
+    // Set a very low minimum collateral ratio for testing
+    env.as_contract(&contract_id, || {
+        Contract::set_min_collateral_ratio(env.clone(), admin.clone(), 50)
+    })
+    .unwrap();
 
-        // Deposit collateral and borrow to create undercollateralized position
-        Contract::deposit_collateral(env.clone(), user.to_string(), 1000).unwrap();
-        Contract::borrow(env.clone(), user.to_string(), 1000).unwrap();
+    // Deposit collateral and borrow to create undercollateralized position
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 1000)
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 1000)
+    })
+    .unwrap();
 
-        // Now set the minimum ratio back to a higher value to make the position undercollateralized
-        Contract::set_min_collateral_ratio(env.clone(), admin.to_string(), 150).unwrap();
+    // Now set the minimum ratio back to a higher value to make the position undercollateralized
+    env.as_contract(&contract_id, || {
+        Contract::set_min_collateral_ratio(env.clone(), admin.clone(), 150)
+    })
+    .unwrap();
 
-        // Test successful liquidation
-        let result =
-            Contract::liquidate(env.clone(), liquidator.to_string(), user.to_string(), 500);
-        assert!(result.is_ok());
+    // Test successful liquidation
+    let result = env.as_contract(&contract_id, || {
+        Contract::liquidate(env.clone(), liquidator.clone(), user.clone(), 500)
     });
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_liquidate_batch_skips_ineligible_entries() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let risky = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let healthy = Address::generate(&env);
+
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(
+        &env,
+        &[risky.clone(), liquidator.clone(), healthy.clone()],
+    );
+    for user in [&risky, &liquidator, &healthy] {
+        TestUtils::verify_user(&env, &contract_id, &admin, user);
+    }
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&risky, &1_000);
+    client.borrow(&risky, &1_000);
+    client.deposit_collateral(&healthy, &10_000);
+    client.borrow(&healthy, &1_000);
+    client.set_min_collateral_ratio(&admin, &150);
+
+    let other_asset = Address::generate(&env);
+    let entries = Vec::from_array(
+        &env,
+        [
+            (healthy.clone(), token.clone(), token.clone(), 500),
+            (risky.clone(), other_asset, token.clone(), 500),
+            (risky.clone(), token.clone(), token.clone(), 400),
+        ],
+    );
+    let results = client.liquidate_batch(&liquidator, &entries);
+    assert_eq!(results.len(), 3);
+
+    let skipped = results.get(0).unwrap();
+    assert!(!skipped.executed);
+    assert_eq!(
+        skipped.error_code,
+        ProtocolError::NotEligibleForLiquidation as u32
+    );
+    assert_eq!(
+        results.get(1).unwrap().error_code,
+        ProtocolError::AssetNotSupported as u32
+    );
+
+    let executed = results.get(2).unwrap();
+    assert!(executed.executed);
+    assert_eq!(executed.debt_repaid, 400);
+    assert_eq!(executed.collateral_seized, 440);
+    let position = env
+        .as_contract(&contract_id, || StateHelper::get_position(&env, &risky))
+        .unwrap();
+    assert_eq!(position.debt, 600);
+
+    // Oversized batches are rejected outright
+    let mut oversized = Vec::new(&env);
+    for _ in 0..=MAX_LIQUIDATION_BATCH {
+        oversized.push_back((risky.clone(), token.clone(), token.clone(), 1));
+    }
+    assert_eq!(
+        client.try_liquidate_batch(&liquidator, &oversized),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+}
+
+#[test]
+fn test_liquidation_incentive_scales_with_shortfall() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let shallow = TestUtils::create_user_address(&env, 0);
+    let deep = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[shallow.clone(), deep.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &shallow);
+    TestUtils::verify_user(&env, &contract_id, &admin, &deep);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&shallow, &1_450);
+    client.borrow(&shallow, &1_000);
+    client.deposit_collateral(&deep, &1_000);
+    client.borrow(&deep, &1_000);
+    client.set_min_collateral_ratio(&admin, &150);
+
+    // Without a curve both pay the flat 10% bonus
+    let flat = client.simulate_liquidation(&shallow, &token, &token, &100);
+    assert_eq!(flat.bonus, 10);
+
+    let curve = IncentiveCurve {
+        base_incentive: 2_000_000,
+        slope: 500_000,
+        max_incentive: 15_000_000,
+    };
+    client.set_liquidation_incentive_curve(&admin, &token, &Some(curve.clone()));
+    assert_eq!(client.get_liquidation_incentive_curve(&token), Some(curve));
+
+    // Health factor 96: 2% + 4 * 0.5%
+    let shallow_preview = client.simulate_liquidation(&shallow, &token, &token, &100);
+    assert_eq!(shallow_preview.bonus, 4);
+    // Health factor 66 would earn 19%, capped at 15%
+    let deep_preview = client.simulate_liquidation(&deep, &token, &token, &100);
+    assert_eq!(deep_preview.bonus, 15);
+
+    let invalid = IncentiveCurve {
+        base_incentive: 5_000_000,
+        slope: 0,
+        max_incentive: 1_000_000,
+    };
+    assert_eq!(
+        client.try_set_liquidation_incentive_curve(&admin, &token, &Some(invalid)),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    client.set_liquidation_incentive_curve(&admin, &token, &None);
+    assert_eq!(
+        client
+            .simulate_liquidation(&deep, &token, &token, &100)
+            .bonus,
+        10
+    );
+}
+
+#[test]
+fn test_liquidation_rebate_claimable_after_recovery() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_liquidation_rebate(&admin, &2_500, &3_600);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &1_000);
+    client.borrow(&user, &1_000);
+    client.set_min_collateral_ratio(&admin, &150);
+
+    // A quarter of the 10% bonus is withheld from the keeper
+    let preview = client.simulate_liquidation(&user, &token, &token, &400);
+    assert_eq!(preview.bonus, 30);
+    client.liquidate(&liquidator, &user, &400);
+    assert_eq!(client.get_rebate_pot(&user).amount, 10);
+
+    assert_eq!(
+        client.try_claim_liquidation_rebate(&user),
+        Err(Ok(ProtocolError::RateLimitExceeded))
+    );
+    env.ledger().with_mut(|li| li.timestamp += 3_600);
+    assert_eq!(
+        client.try_claim_liquidation_rebate(&user),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+
+    // Restoring health unlocks the pot
+    let debt = client.get_position(&user).1;
+    client.repay(&user, &debt);
+    let balance_before = MockTokenClient::new(&env, &token).balance(&user);
+    assert_eq!(client.claim_liquidation_rebate(&user), 10);
+    assert_eq!(
+        MockTokenClient::new(&env, &token).balance(&user),
+        balance_before + 10
+    );
+    assert_eq!(client.get_rebate_pot(&user).amount, 0);
+}
+
+#[test]
+fn test_user_fee_statement_buckets_by_day() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let day = 24 * 60 * 60;
+    env.ledger().with_mut(|li| li.timestamp = 10 * day);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &100_000);
+    client.borrow(&user, &100_000);
+
+    env.ledger().with_mut(|li| li.timestamp += 30 * day);
+    client.deposit_collateral(&user, &1);
+    client.set_min_collateral_ratio(&admin, &150);
+    client.liquidate(&liquidator, &user, &10_000);
+
+    let statement = client.get_user_fee_statement(&user, &(40 * day), &(41 * day - 1));
+    assert_eq!(statement.buckets.len(), 1);
+    assert!(statement.totals.interest > 0);
+    // 10% bonus on the 10_000 repaid
+    assert_eq!(statement.totals.liquidation_penalty, 1_000);
+    assert_eq!(
+        statement.totals,
+        FeeBucket {
+            day: 0,
+            ..statement.lifetime.clone()
+        }
+    );
+
+    let earlier = client.get_user_fee_statement(&user, &0, &(40 * day - 1));
+    assert_eq!(earlier.buckets.len(), 0);
+    assert_eq!(earlier.lifetime, statement.lifetime);
 }
 
 #[test]
@@ -670,23 +1347,28 @@ fn test_liquidate_not_eligible() {
 
     let (admin, contract_id, _token) =
         TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
-    env.as_contract(&contract_id, || {
-        TestUtils::verify_user(&env, &admin, &user);
-        TestUtils::verify_user(&env, &admin, &liquidator);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
 
-        // Deposit large amount and borrow small amount (healthy position)
-        Contract::deposit_collateral(env.clone(), user.to_string(), 2000).unwrap();
-        Contract::borrow(env.clone(), user.to_string(), 1000).unwrap();
+    // Deposit large amount and borrow small amount (healthy position)
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 2000)
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 1000)
+    })
+    .unwrap();
 
-        // Try to liquidate (should fail)
-        let result =
-            Contract::liquidate(env.clone(), liquidator.to_string(), user.to_string(), 500);
-        assert!(result.is_err());
-        assert_eq!(
-            result.unwrap_err(),
-            ProtocolError::NotEligibleForLiquidation
-        );
+    // Try to liquidate (should fail)
+    let result = env.as_contract(&contract_id, || {
+        Contract::liquidate(env.clone(), liquidator.clone(), user.clone(), 500)
     });
+    assert!(result.is_err());
+    assert_eq!(
+        result.unwrap_err(),
+        ProtocolError::NotEligibleForLiquidation
+    );
 }
 
 #[test]
@@ -717,10 +1399,10 @@ fn test_set_risk_params() {
     let contract_id = env.register(Contract, ());
     env.as_contract(&contract_id, || {
         // Initialize contract
-        Contract::initialize(env.clone(), admin.to_string()).unwrap();
+        Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env)).unwrap();
 
         // Test setting risk parameters
-        let result = Contract::set_risk_params(env.clone(), admin.to_string(), 60000000, 15000000);
+        let result = Contract::set_risk_params(env.clone(), admin.clone(), 60000000, 15000000);
         assert!(result.is_ok());
 
         // Verify the parameters were set
@@ -741,10 +1423,10 @@ fn test_set_risk_params_unauthorized() {
     let contract_id = env.register(Contract, ());
     env.as_contract(&contract_id, || {
         // Initialize contract
-        Contract::initialize(env.clone(), admin.to_string()).unwrap();
+        Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env)).unwrap();
 
         // Test setting risk parameters with non-admin (should fail)
-        let result = Contract::set_risk_params(env.clone(), user.to_string(), 60000000, 15000000);
+        let result = Contract::set_risk_params(env.clone(), user.clone(), 60000000, 15000000);
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), ProtocolError::Unauthorized);
     });
@@ -760,12 +1442,12 @@ fn test_set_pause_switches() {
     let contract_id = env.register(Contract, ());
     env.as_contract(&contract_id, || {
         // Initialize contract
-        Contract::initialize(env.clone(), admin.to_string()).unwrap();
+        Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env)).unwrap();
 
         // Test setting pause switches
         let result = Contract::set_pause_switches(
             env.clone(),
-            admin.to_string(),
+            admin.clone(),
             true,  // pause_borrow
             false, // pause_deposit
             true,  // pause_withdraw
@@ -792,7 +1474,7 @@ fn test_get_protocol_params() {
     let contract_id = env.register(Contract, ());
     env.as_contract(&contract_id, || {
         // Initialize contract
-        Contract::initialize(env.clone(), admin.to_string()).unwrap();
+        Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env)).unwrap();
 
         // Test getting protocol parameters
         let params = Contract::get_protocol_params(env.clone()).unwrap();
@@ -815,7 +1497,7 @@ fn test_get_system_stats() {
     let contract_id = env.register(Contract, ());
     env.as_contract(&contract_id, || {
         // Initialize contract
-        Contract::initialize(env.clone(), admin.to_string()).unwrap();
+        Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env)).unwrap();
 
         // Test getting system stats
         let stats = Contract::get_system_stats(env.clone()).unwrap();
@@ -837,11 +1519,4923 @@ fn test_get_position_not_found() {
     let contract_id = env.register(Contract, ());
     env.as_contract(&contract_id, || {
         // Initialize contract
-        Contract::initialize(env.clone(), admin.to_string()).unwrap();
+        Contract::initialize(env.clone(), admin.clone(), TestUtils::init_config(&env)).unwrap();
 
         // Test getting position for user who hasn't deposited
-        let result = Contract::get_position(env.clone(), user.to_string());
+        let result = Contract::get_position(env.clone(), user.clone());
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), ProtocolError::PositionNotFound);
     });
 }
+
+#[test]
+fn test_failed_deposit_returns_only_its_error_code() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+
+    // An off-chain caller receives the error code alone
+    assert_eq!(
+        client.try_deposit_collateral(&user, &-5),
+        Err(Ok(ProtocolError::InvalidAmount))
+    );
+
+    // The context survives only as a diagnostic of the failed call, as the test host and
+    // transaction simulation report it
+    let (_, topics, data) = env.events().all().last().unwrap();
+    let topic: Symbol = topics.get(0).unwrap().into_val(&env);
+    assert_eq!(topic, Symbol::new(&env, "error_context"));
+    let context: ErrorContext = data.into_val(&env);
+    assert_eq!(context.code, ProtocolError::InvalidAmount as u32);
+    assert_eq!(context.operation, Symbol::new(&env, "deposit"));
+    assert_eq!(context.user, Some(user.clone()));
+    assert_eq!(context.amount, -5);
+}
+
+#[test]
+fn test_session_delegate_deposit_within_policy() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let delegate = TestUtils::create_user_address(&env, 1);
+
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), delegate.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+
+    let expires_at = env.ledger().timestamp() + 3600;
+    env.as_contract(&contract_id, || {
+        Contract::grant_session(
+            env.clone(),
+            user.clone(),
+            delegate.clone(),
+            1000,
+            true,
+            false,
+            expires_at,
+        )
+    })
+    .unwrap();
+
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral_for(env.clone(), delegate.clone(), user.clone(), 600)
+    })
+    .unwrap();
+    let (collateral, _, _) = env
+        .as_contract(&contract_id, || {
+            Contract::get_position(env.clone(), user.clone())
+        })
+        .unwrap();
+    assert_eq!(collateral, 600);
+
+    let over_limit = env.as_contract(&contract_id, || {
+        Contract::deposit_collateral_for(env.clone(), delegate.clone(), user.clone(), 1500)
+    });
+    assert_eq!(over_limit.unwrap_err(), ProtocolError::UserLimitExceeded);
+
+    let repay = env.as_contract(&contract_id, || {
+        Contract::repay_for(env.clone(), delegate.clone(), user.clone(), 100)
+    });
+    assert_eq!(repay.unwrap_err(), ProtocolError::Unauthorized);
+}
+
+#[test]
+fn test_session_revoked_delegate_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let delegate = TestUtils::create_user_address(&env, 1);
+
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), delegate.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+
+    let expires_at = env.ledger().timestamp() + 3600;
+    env.as_contract(&contract_id, || {
+        Contract::grant_session(
+            env.clone(),
+            user.clone(),
+            delegate.clone(),
+            1000,
+            true,
+            true,
+            expires_at,
+        )
+    })
+    .unwrap();
+    assert!(env
+        .as_contract(&contract_id, || Contract::get_session(
+            env.clone(),
+            user.clone(),
+            delegate.clone()
+        ))
+        .is_some());
+
+    env.as_contract(&contract_id, || {
+        Contract::revoke_session(env.clone(), user.clone(), delegate.clone())
+    })
+    .unwrap();
+    assert!(env
+        .as_contract(&contract_id, || Contract::get_session(
+            env.clone(),
+            user.clone(),
+            delegate.clone()
+        ))
+        .is_none());
+
+    let result = env.as_contract(&contract_id, || {
+        Contract::deposit_collateral_for(env.clone(), delegate.clone(), user.clone(), 100)
+    });
+    assert_eq!(result.unwrap_err(), ProtocolError::Unauthorized);
+}
+
+#[test]
+fn test_protector_can_only_deposit_and_repay_on_behalf() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let treasurer = TestUtils::create_user_address(&env, 0);
+    let dao = TestUtils::create_user_address(&env, 1);
+
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[treasurer.clone(), dao.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &treasurer);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&treasurer, &2000);
+    client.borrow(&treasurer, &1000);
+
+    assert_eq!(
+        client.try_deposit_on_behalf(&dao, &treasurer, &500),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+
+    client.set_protector(&treasurer, &dao, &true);
+    assert!(client.is_protector(&treasurer, &dao));
+
+    // Protectors are not bound by a per-action limit
+    client.deposit_on_behalf(&dao, &treasurer, &50_000);
+    client.repay_on_behalf(&dao, &treasurer, &400);
+    let (collateral, debt, _) = client.get_position(&treasurer);
+    assert_eq!(collateral, 52_000);
+    assert_eq!(debt, 600);
+
+    client.set_protector(&treasurer, &dao, &false);
+    assert_eq!(
+        client.try_repay_on_behalf(&dao, &treasurer, &100),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+}
+
+#[test]
+fn test_compliance_summary_tracks_profile_changes() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user1 = TestUtils::create_user_address(&env, 0);
+    let user2 = TestUtils::create_user_address(&env, 1);
+
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user1.clone(), user2.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user1);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user2);
+    env.as_contract(&contract_id, || {
+        Contract::set_user_verification(
+            env.clone(),
+            admin.clone(),
+            user2.clone(),
+            VerificationStatus::Rejected,
+        )
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::freeze_user(env.clone(), admin.clone(), user1.clone())
+    })
+    .unwrap();
+
+    let summary = env
+        .as_contract(&contract_id, || {
+            Contract::get_compliance_summary(env.clone())
+        })
+        .unwrap();
+    // The admin is verified at initialization
+    assert_eq!(summary.kyc_verified, 2);
+    assert_eq!(summary.blacklisted, 1);
+    assert_eq!(summary.frozen, 2);
+
+    env.as_contract(&contract_id, || {
+        Contract::unfreeze_user(env.clone(), admin.clone(), user1.clone())
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::flag_suspicious_activity(
+            env.clone(),
+            admin.clone(),
+            user1.clone(),
+            AlertSeverity::High,
+            Symbol::new(&env, "structuring"),
+        )
+    })
+    .unwrap();
+
+    let summary = env
+        .as_contract(&contract_id, || {
+            Contract::get_compliance_summary(env.clone())
+        })
+        .unwrap();
+    assert_eq!(summary.frozen, 1);
+    assert_eq!(summary.suspicious_high, 1);
+    assert_eq!(summary.suspicious_low, 0);
+}
+
+#[test]
+fn test_borrow_interest_stops_at_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    env.as_contract(&contract_id, || {
+        Contract::set_interest_cap(env.clone(), admin.clone(), 100)
+    })
+    .unwrap();
+
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 10000)
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 1000)
+    })
+    .unwrap();
+
+    env.ledger()
+        .with_mut(|li| li.timestamp += 2 * 365 * 24 * 60 * 60);
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral(env.clone(), user.clone(), 100)
+    })
+    .unwrap();
+
+    let position = env
+        .as_contract(&contract_id, || StateHelper::get_position(&env, &user))
+        .unwrap();
+    assert_eq!(position.borrow_interest, 10);
+    assert!(position.interest_capped);
+
+    let negative = env.as_contract(&contract_id, || {
+        Contract::set_interest_cap(env.clone(), admin.clone(), -1)
+    });
+    assert_eq!(negative.unwrap_err(), ProtocolError::InvalidParameters);
+}
+
+#[test]
+fn test_grace_window_skips_interest_for_new_borrows() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let promo_user = TestUtils::create_user_address(&env, 0);
+    let regular_user = TestUtils::create_user_address(&env, 1);
+
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[promo_user.clone(), regular_user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &promo_user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &regular_user);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&promo_user, &500_000);
+    client.deposit_collateral(&regular_user, &500_000);
+
+    client.set_grace_period(&admin, &token, &24);
+    assert_eq!(client.get_grace_period(&token), 24);
+    client.borrow(&promo_user, &100_000);
+
+    // Switching the promotion off only affects borrows opened afterwards
+    client.set_grace_period(&admin, &token, &0);
+    client.borrow(&regular_user, &100_000);
+
+    let hour = 60 * 60;
+    env.ledger().with_mut(|li| li.timestamp += 12 * hour);
+    client.deposit_collateral(&promo_user, &1);
+    let position = env
+        .as_contract(&contract_id, || {
+            StateHelper::get_position(&env, &promo_user)
+        })
+        .unwrap();
+    assert_eq!(position.borrow_interest, 0);
+
+    env.ledger().with_mut(|li| li.timestamp += 365 * 24 * hour);
+    client.deposit_collateral(&promo_user, &1);
+    client.deposit_collateral(&regular_user, &1);
+    let promo = env
+        .as_contract(&contract_id, || {
+            StateHelper::get_position(&env, &promo_user)
+        })
+        .unwrap();
+    let regular = env
+        .as_contract(&contract_id, || {
+            StateHelper::get_position(&env, &regular_user)
+        })
+        .unwrap();
+    // The promo borrower pays for everything except the first 24 hours (~11 at 4% APR,
+    // since 20% utilization adds 2% to the base rate)
+    assert!(promo.borrow_interest > 0);
+    assert_eq!(regular.borrow_interest - promo.borrow_interest, 11);
+}
+
+#[test]
+fn test_pledged_supply_interest_adds_borrowing_power() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let supplier = TestUtils::create_user_address(&env, 0);
+    let borrower = Address::generate(&env);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[supplier.clone(), borrower.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &supplier);
+    TestUtils::verify_user(&env, &contract_id, &admin, &borrower);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&supplier, &500_000);
+    client.borrow(&supplier, &100_000);
+    client.deposit_collateral(&borrower, &500_000);
+    client.borrow(&borrower, &300_000);
+
+    env.ledger()
+        .with_mut(|li| li.timestamp += 365 * 24 * 60 * 60);
+    client.deposit_collateral(&supplier, &1);
+    let unpledged_ratio = client.get_position(&supplier).2;
+
+    client.set_interest_as_collateral(&admin, &token, &true);
+    let pledged_ratio = client.get_position(&supplier).2;
+    assert!(pledged_ratio > unpledged_ratio);
+
+    // Claiming would pull interest that is backing the debt
+    client.set_min_collateral_ratio(&admin, &pledged_ratio);
+    assert_eq!(
+        client.try_claim_supply_interest(&supplier),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    // Leaving exactly the minimum ratio is not enough once a health factor buffer is set
+    client.set_min_collateral_ratio(&admin, &unpledged_ratio);
+    client.set_health_factor_buffer(&admin, &200);
+    assert_eq!(
+        client.try_claim_supply_interest(&supplier),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    client.set_health_factor_buffer(&admin, &0);
+    client.set_min_collateral_ratio(&admin, &150);
+    assert!(client.claim_supply_interest(&supplier) > 0);
+    assert_eq!(client.get_position(&supplier).2, unpledged_ratio);
+}
+
+#[test]
+fn test_auto_compound_folds_supply_interest_into_collateral() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let compounder = TestUtils::create_user_address(&env, 0);
+    let claimer = TestUtils::create_user_address(&env, 1);
+    let borrower = Address::generate(&env);
+
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(
+        &env,
+        &[compounder.clone(), claimer.clone(), borrower.clone()],
+    );
+    for user in [&compounder, &claimer, &borrower] {
+        TestUtils::verify_user(&env, &contract_id, &admin, user);
+    }
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&compounder, &500_000);
+    client.deposit_collateral(&claimer, &500_000);
+    client.deposit_collateral(&borrower, &500_000);
+    client.borrow(&borrower, &300_000);
+    client.set_auto_compound(&compounder, &true);
+
+    // Nothing has accrued yet, so there is nothing to claim
+    assert_eq!(
+        client.try_claim_supply_interest(&claimer),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+
+    env.ledger()
+        .with_mut(|li| li.timestamp += 365 * 24 * 60 * 60);
+    client.deposit_collateral(&compounder, &1);
+    let compounded = env
+        .as_contract(&contract_id, || {
+            StateHelper::get_position(&env, &compounder)
+        })
+        .unwrap();
+    assert!(compounded.collateral > 500_001);
+    assert_eq!(compounded.supply_interest, 0);
+
+    let balance_before = MockTokenClient::new(&env, &token).balance(&claimer);
+    let claimed = client.claim_supply_interest(&claimer);
+    assert!(claimed > 0);
+    assert_eq!(
+        MockTokenClient::new(&env, &token).balance(&claimer),
+        balance_before + claimed
+    );
+    let position = env
+        .as_contract(&contract_id, || StateHelper::get_position(&env, &claimer))
+        .unwrap();
+    assert_eq!(position.collateral, 500_000);
+    assert_eq!(position.supply_interest, 0);
+}
+
+#[test]
+fn test_debt_ceiling_and_utilization_kill_switch() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let lender = TestUtils::create_user_address(&env, 0);
+    let borrower = TestUtils::create_user_address(&env, 1);
+
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[lender.clone(), borrower.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &lender);
+    TestUtils::verify_user(&env, &contract_id, &admin, &borrower);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&lender, &600_000);
+    client.deposit_collateral(&borrower, &400_000);
+
+    // Resume threshold must sit below the freeze threshold
+    let flapping = ProtocolParameters {
+        debt_ceiling: 250_000,
+        freeze_utilization: 30_000_000,
+        resume_utilization: 30_000_000,
+    };
+    assert_eq!(
+        client.try_set_protocol_parameters(&admin, &flapping),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    let params = ProtocolParameters {
+        resume_utilization: 20_000_000,
+        ..flapping
+    };
+    client.set_protocol_parameters(&admin, &params);
+    assert_eq!(client.get_protocol_parameters(), params);
+
+    client.borrow(&borrower, &200_000);
+    assert_eq!(
+        client.try_borrow(&borrower, &60_000),
+        Err(Ok(ProtocolError::CapExceeded))
+    );
+
+    // Withdrawing supply pushes utilization to 33% and trips the kill-switch
+    client.withdraw(&lender, &400_000);
+    assert!(client.is_borrow_frozen());
+    assert_eq!(
+        client.try_borrow(&borrower, &1),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+
+    // 25% is inside the hysteresis band, so borrows stay frozen
+    client.deposit_collateral(&lender, &200_000);
+    assert!(client.is_borrow_frozen());
+
+    // Falling below 20% lifts the freeze automatically
+    client.repay(&borrower, &50_000);
+    assert!(!client.is_borrow_frozen());
+    client.borrow(&borrower, &10_000);
+}
+
+#[test]
+fn test_deposit_receipts_follow_token_interface() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let holder = TestUtils::create_user_address(&env, 0);
+    let recipient = TestUtils::create_user_address(&env, 1);
+    let spender = Address::generate(&env);
+
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[holder.clone(), recipient.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &holder);
+    TestUtils::verify_user(&env, &contract_id, &admin, &recipient);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&holder, &300_000);
+    assert_eq!(client.balance(&holder), 300_000);
+    assert_eq!(client.decimals(), 7);
+
+    client.approve(&holder, &spender, &50_000, &(env.ledger().sequence() + 100));
+    client.transfer_from(&spender, &holder, &recipient, &40_000);
+    assert_eq!(client.allowance(&holder, &spender), 10_000);
+    assert_eq!(
+        client.try_transfer_from(&spender, &holder, &recipient, &20_000),
+        Err(Ok(ProtocolError::InsufficientAllowance))
+    );
+    assert_eq!(client.balance(&holder), 260_000);
+    assert_eq!(client.balance(&recipient), 40_000);
+
+    // Receipts backing debt leave only under the checks a withdrawal gets
+    client.set_same_ledger_guard(&admin, &token, &true);
+    client.set_health_factor_buffer(&admin, &2_000);
+    client.borrow(&holder, &100_000);
+    assert_eq!(
+        client.try_transfer(&holder, &recipient, &1_000),
+        Err(Ok(ProtocolError::RateLimitExceeded))
+    );
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    assert_eq!(
+        client.try_transfer(&holder, &recipient, &200_000),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    // 170% clears the minimum ratio but not the health factor buffer on top of it
+    assert_eq!(
+        client.try_burn(&holder, &90_000),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    client.transfer(&holder, &recipient, &70_000);
+    assert_eq!(client.balance(&holder), 190_000);
+
+    // Burning redeems the underlying asset
+    let before = MockTokenClient::new(&env, &token).balance(&recipient);
+    client.burn(&recipient, &15_000);
+    assert_eq!(client.balance(&recipient), 95_000);
+    assert_eq!(
+        MockTokenClient::new(&env, &token).balance(&recipient),
+        before + 15_000
+    );
+}
+
+#[test]
+fn test_utilization_math_is_overflow_safe_and_positions_are_capped() {
+    // Huge balances against a tiny supply saturate instead of overflowing
+    assert_eq!(SafeMath::utilization(i128::MAX, i128::MAX), 100_000_000);
+    assert_eq!(SafeMath::utilization(i128::MAX / 2, 3), i128::MAX);
+    assert_eq!(SafeMath::mul_div(i128::MAX, 2, 4), i128::MAX / 2);
+    assert_eq!(SafeMath::utilization(5, 0), 0);
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_max_position_size(&admin, &token, &100_000);
+    assert_eq!(client.get_max_position_size(&token), 100_000);
+
+    client.deposit_collateral(&user, &80_000);
+    assert_eq!(
+        client.try_deposit_collateral(&user, &30_000),
+        Err(Ok(ProtocolError::CapExceeded))
+    );
+
+    client.set_max_position_size(&admin, &token, &0);
+    client.deposit_collateral(&user, &30_000);
+}
+
+#[test]
+fn test_liquidity_index_pays_suppliers_exactly_across_rate_changes() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let frequent = TestUtils::create_user_address(&env, 0);
+    let idle = TestUtils::create_user_address(&env, 1);
+    let borrower = Address::generate(&env);
+
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(
+        &env,
+        &[frequent.clone(), idle.clone(), borrower.clone()],
+    );
+    for user in [&frequent, &idle, &borrower] {
+        TestUtils::verify_user(&env, &contract_id, &admin, user);
+    }
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&frequent, &400_000);
+    client.deposit_collateral(&idle, &400_000);
+    client.deposit_collateral(&borrower, &400_000);
+    client.borrow(&borrower, &250_000);
+
+    let half_year = 182 * 24 * 60 * 60;
+    env.ledger().with_mut(|li| li.timestamp += half_year);
+    // Only one supplier checkpoints before rates are raised
+    client.set_auto_compound(&frequent, &false);
+    env.as_contract(&contract_id, || {
+        let mut config = InterestRateStorage::get_config(&env);
+        config.base_rate = 20_000_000;
+        InterestRateStorage::save_config(&env, &config);
+    });
+
+    env.ledger().with_mut(|li| li.timestamp += half_year);
+    client.set_auto_compound(&frequent, &false);
+    client.set_auto_compound(&idle, &false);
+
+    let frequent_position = env
+        .as_contract(&contract_id, || StateHelper::get_position(&env, &frequent))
+        .unwrap();
+    let idle_position = env
+        .as_contract(&contract_id, || StateHelper::get_position(&env, &idle))
+        .unwrap();
+    assert!(client.get_liquidity_index() > LIQUIDITY_INDEX_SCALE);
+    assert!(idle_position.supply_interest > 0);
+    // Both suppliers are owed the same interest however often they were checkpointed
+    assert!((frequent_position.supply_interest - idle_position.supply_interest).abs() <= 1);
+}
+
+#[test]
+fn test_non_canonical_wrappers_get_ltv_haircut() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let usdc = Symbol::new(&env, "USDC");
+    let issuer = Address::generate(&env);
+    let bridge = Address::generate(&env);
+
+    let mut listed = Vec::new(&env);
+    for _ in 0..2 {
+        let asset = env.register(MockToken, ());
+        env.as_contract(&asset, || {
+            MockToken::initialize(env.clone(), admin.clone());
+            MockToken::mint(env.clone(), user.clone(), 10_000);
+        });
+        client.set_asset_params(&admin, &asset, &80_000_000, &true);
+        client.set_asset_price(&admin, &asset, &100_000_000);
+        listed.push_back(asset);
+    }
+    let (native, wrapped) = (listed.get(0).unwrap(), listed.get(1).unwrap());
+    client.set_asset_provenance(
+        &admin,
+        &native,
+        &cross_asset::AssetProvenance {
+            logical_asset: usdc.clone(),
+            issuer: issuer.clone(),
+            bridge: None,
+            canonical: true,
+        },
+    );
+    client.set_asset_provenance(
+        &admin,
+        &wrapped,
+        &cross_asset::AssetProvenance {
+            logical_asset: usdc.clone(),
+            issuer,
+            bridge: Some(bridge.clone()),
+            canonical: false,
+        },
+    );
+    assert_eq!(client.get_asset_wrappers(&usdc), listed);
+    assert_eq!(
+        client.get_asset_provenance(&wrapped).unwrap().bridge,
+        Some(bridge)
+    );
+
+    client.set_wrapper_haircut(&admin, &25_000_000);
+    client.deposit_collateral_asset(&user, &native, &1_000);
+    client.deposit_collateral_asset(&user, &wrapped, &1_000);
+    // 1_000 * 80% canonical + 1_000 * 80% * 75% wrapped
+    assert_eq!(client.get_cross_position_summary(&user).0, 1_400);
+}
+
+#[test]
+fn test_stale_prices_block_borrow_and_withdraw_only() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_oracle_heartbeat(&admin, &600);
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+    client.deposit_collateral(&user, &1_000);
+    client.deposit_collateral_asset(&user, &other_token, &2_000);
+    client.borrow(&user, &1_000);
+
+    env.ledger().with_mut(|li| li.timestamp += 601);
+    assert!(client.is_price_stale(&other_token));
+    assert_eq!(
+        client.try_borrow(&user, &100),
+        Err(Ok(ProtocolError::OracleStale))
+    );
+    assert_eq!(
+        client.try_withdraw(&user, &100),
+        Err(Ok(ProtocolError::OracleStale))
+    );
+    assert_eq!(
+        client.try_withdraw_asset(&user, &other_token, &100),
+        Err(Ok(ProtocolError::OracleStale))
+    );
+
+    // Risk-reducing actions stay available
+    client.deposit_collateral(&user, &100);
+    client.repay(&user, &500);
+
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+    assert!(!client.is_price_stale(&other_token));
+    client.borrow(&user, &100);
+}
+
+#[test]
+fn test_fee_on_transfer_assets_require_opt_in() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    env.as_contract(&token, || MockToken::set_transfer_fee(env.clone(), 100));
+
+    // The 1% shortfall is caught instead of crediting the requested amount
+    assert_eq!(
+        client.try_deposit_collateral(&user, &10_000),
+        Err(Ok(ProtocolError::BalanceInvariantViolation))
+    );
+
+    client.set_fee_on_transfer(&admin, &token, &true);
+    client.deposit_collateral(&user, &10_000);
+    assert_eq!(client.get_position(&user).0, 9_900);
+
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+        MockToken::set_transfer_fee(env.clone(), 200);
+    });
+    client.set_asset_params(&admin, &other_token, &50_000_000, &true);
+    assert_eq!(
+        client.try_deposit_collateral_asset(&user, &other_token, &1_000),
+        Err(Ok(ProtocolError::BalanceInvariantViolation))
+    );
+    client.set_fee_on_transfer(&admin, &other_token, &true);
+    // Updating risk weights keeps the opt-in
+    client.set_asset_params(&admin, &other_token, &60_000_000, &true);
+    client.deposit_collateral_asset(&user, &other_token, &1_000);
+    let cross = env.as_contract(&contract_id, || {
+        cross_asset::CrossStateHelper::get_or_init_position(&env, &user)
+    });
+    assert_eq!(cross.collateral.get(other_token).unwrap(), 980);
+}
+
+#[test]
+fn test_user_markets_track_entered_assets() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_asset_params(&admin, &other_token, &50_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+    assert_eq!(client.get_user_markets(&user).len(), 0);
+
+    client.deposit_collateral(&user, &5_000);
+    client.deposit_collateral_asset(&user, &other_token, &2_000);
+    assert_eq!(
+        client.get_user_markets(&user),
+        Vec::from_array(&env, [token.clone(), other_token.clone()])
+    );
+
+    // Closing a balance prunes the market
+    client.withdraw(&user, &5_000);
+    assert_eq!(
+        client.get_user_markets(&user),
+        Vec::from_array(&env, [other_token.clone()])
+    );
+    client.withdraw_asset(&user, &other_token, &2_000);
+    assert_eq!(client.get_user_markets(&user).len(), 0);
+}
+
+#[test]
+fn test_disabled_collateral_is_excluded_and_protected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 100_000);
+    });
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+
+    client.deposit_collateral(&user, &60_000);
+    client.deposit_collateral_asset(&user, &other_token, &30_000);
+    client.borrow(&user, &30_000);
+    assert!(client.is_collateral_enabled(&user, &token));
+
+    // Without the primary asset the position would be at 100%, below the 150% minimum
+    assert_eq!(
+        client.try_set_use_as_collateral(&user, &token, &false),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    client.set_use_as_collateral(&user, &other_token, &false);
+    assert!(!client.is_collateral_enabled(&user, &other_token));
+    assert_eq!(
+        client.try_borrow(&user, &20_000),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+
+    // Opting the primary asset out keeps it from being seized once the position is unhealthy
+    client.set_use_as_collateral(&user, &other_token, &true);
+    client.deposit_collateral_asset(&user, &other_token, &30_000);
+    client.set_use_as_collateral(&user, &token, &false);
+    client.set_asset_price(&admin, &other_token, &50_000_000);
+    assert_eq!(
+        client.try_liquidate(&liquidator, &user, &10_000),
+        Err(Ok(ProtocolError::InsufficientCollateral))
+    );
+}
+
+#[test]
+fn test_rates_follow_time_weighted_utilization() {
+    assert_eq!(InterestRateManager::decay_weight(0, 3_600), 100_000_000);
+    assert_eq!(InterestRateManager::decay_weight(3_600, 3_600), 50_000_000);
+    assert_eq!(InterestRateManager::decay_weight(1_800, 3_600), 75_000_000);
+    assert_eq!(InterestRateManager::decay_weight(u64::MAX, 1), 0);
+
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let lender = TestUtils::create_user_address(&env, 0);
+    let whale = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[lender.clone(), whale.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &lender);
+    TestUtils::verify_user(&env, &contract_id, &admin, &whale);
+
+    let client = ContractClient::new(&env, &contract_id);
+    let day = 24 * 60 * 60;
+    client.set_utilization_half_life(&admin, &day);
+    client.deposit_collateral(&lender, &500_000);
+    client.deposit_collateral(&whale, &500_000);
+    client.borrow(&whale, &100_000);
+
+    env.ledger().with_mut(|li| li.timestamp += 30 * day);
+    client.deposit_collateral(&lender, &1);
+    let settled = client.get_utilization_metrics();
+    assert_eq!(settled.half_life, day);
+    assert!((settled.smoothed_utilization - 10_000_000).abs() < 10_000);
+
+    // A whale borrow triples raw utilization but barely moves the rate an hour later
+    client.borrow(&whale, &200_000);
+    env.ledger().with_mut(|li| li.timestamp += 60 * 60);
+    client.deposit_collateral(&lender, &1);
+    let after_hour = client.get_utilization_metrics();
+    assert!(after_hour.raw_utilization > 29_000_000);
+    assert!(after_hour.smoothed_utilization < 11_000_000);
+
+    // One half-life later the average has closed half the gap
+    env.ledger().with_mut(|li| li.timestamp += day);
+    client.deposit_collateral(&lender, &1);
+    let after_day = client.get_utilization_metrics();
+    assert!(after_day.smoothed_utilization > 19_000_000);
+    assert!(after_day.smoothed_utilization < 21_000_000);
+    assert!(after_day.borrow_rate > after_hour.borrow_rate);
+}
+
+#[test]
+fn test_pause_records_reason_and_expires() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.pause_with_reason(&admin, &false, &true, &false, &false, &7, &3_600);
+    let info = client.get_pause_info(&token).unwrap();
+    assert_eq!(info.paused_by, admin);
+    assert_eq!(info.reason_code, 7);
+    assert_eq!(info.expires_at, 4_600);
+    assert_eq!(client.get_pause_info(&Address::generate(&env)), None);
+    assert_eq!(
+        client.try_deposit_collateral(&user, &1_000),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+
+    // The market reopens by itself once the pause expires
+    env.ledger().with_mut(|li| li.timestamp = 4_600);
+    assert_eq!(client.get_pause_info(&token), None);
+    client.deposit_collateral(&user, &1_000);
+
+    // Pauses without a duration hold until lifted
+    client.set_pause_switches(&admin, &true, &false, &false, &false);
+    assert_eq!(client.get_pause_info(&token).unwrap().expires_at, 0);
+    client.set_pause_switches(&admin, &false, &false, &false, &false);
+    assert_eq!(client.get_pause_info(&token), None);
+}
+
+#[test]
+fn test_cross_asset_collateral_uses_risk_weight() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    let volatile_token = env.register(MockToken, ());
+    env.as_contract(&volatile_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+
+    // Unverified callers cannot set risk weights
+    let unauthorized = env.as_contract(&contract_id, || {
+        Contract::set_asset_params(
+            env.clone(),
+            user.clone(),
+            volatile_token.clone(),
+            50_000_000,
+            true,
+        )
+    });
+    assert_eq!(unauthorized.unwrap_err(), ProtocolError::UserRoleViolation);
+
+    // 50% haircut on an asset priced at 2 primary units
+    env.as_contract(&contract_id, || {
+        Contract::set_asset_params(
+            env.clone(),
+            admin.clone(),
+            volatile_token.clone(),
+            50_000_000,
+            true,
+        )
+    })
+    .unwrap();
+    env.as_contract(&contract_id, || {
+        Contract::set_asset_price(
+            env.clone(),
+            admin.clone(),
+            volatile_token.clone(),
+            200_000_000,
+        )
+    })
+    .unwrap();
+
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral_asset(env.clone(), user.clone(), volatile_token.clone(), 1000)
+    })
+    .unwrap();
+    let (weighted, debt, _) = env
+        .as_contract(&contract_id, || {
+            Contract::get_cross_position_summary(env.clone(), user.clone())
+        })
+        .unwrap();
+    assert_eq!(weighted, 1000);
+    assert_eq!(debt, 0);
+
+    let too_much = env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 700)
+    });
+    assert_eq!(
+        too_much.unwrap_err(),
+        ProtocolError::InsufficientCollateralRatio
+    );
+    env.as_contract(&contract_id, || {
+        Contract::borrow(env.clone(), user.clone(), 600)
+    })
+    .unwrap();
+
+    let unsafe_withdraw = env.as_contract(&contract_id, || {
+        Contract::withdraw_asset(env.clone(), user.clone(), volatile_token.clone(), 200)
+    });
+    assert_eq!(
+        unsafe_withdraw.unwrap_err(),
+        ProtocolError::InsufficientCollateralRatio
+    );
+}
+
+#[test]
+fn test_migrate_collateral_through_dex() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    let volatile_token = env.register(MockToken, ());
+    let stable_token = env.register(MockToken, ());
+    let dex = env.register(MockDex, ());
+    env.as_contract(&volatile_token, || {
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    for (asset, factor) in [
+        (volatile_token.clone(), 50_000_000),
+        (stable_token.clone(), 90_000_000),
+    ] {
+        env.as_contract(&contract_id, || {
+            Contract::set_asset_params(env.clone(), admin.clone(), asset.clone(), factor, true)
+        })
+        .unwrap();
+        env.as_contract(&contract_id, || {
+            Contract::set_asset_price(env.clone(), admin.clone(), asset, 100_000_000)
+        })
+        .unwrap();
+    }
+
+    env.as_contract(&contract_id, || {
+        Contract::deposit_collateral_asset(env.clone(), user.clone(), volatile_token.clone(), 1000)
+    })
+    .unwrap();
+
+    // Collateral only ever goes to an adapter the admin has allowed
+    let result = env.as_contract(&contract_id, || {
+        Contract::migrate_collateral(
+            env.clone(),
+            user.clone(),
+            volatile_token.clone(),
+            stable_token.clone(),
+            1000,
+            dex.clone(),
+            990,
+        )
+    });
+    assert_eq!(result.unwrap_err(), ProtocolError::Unauthorized);
+    let held = env.as_contract(&volatile_token, || {
+        MockToken::balance(env.clone(), contract_id.clone())
+    });
+    assert_eq!(held, 1000);
+    env.as_contract(&contract_id, || {
+        Contract::set_dex_adapter(env.clone(), admin.clone(), dex.clone(), true)
+    })
+    .unwrap();
+
+    // Primary collateral has its own book on the position, so it is never a swap leg
+    env.as_contract(&contract_id, || {
+        Contract::set_asset_params(env.clone(), admin.clone(), token.clone(), 100_000_000, true)
+    })
+    .unwrap();
+    let result = env.as_contract(&contract_id, || {
+        Contract::migrate_collateral(
+            env.clone(),
+            user.clone(),
+            volatile_token.clone(),
+            token.clone(),
+            1000,
+            dex.clone(),
+            990,
+        )
+    });
+    assert_eq!(result.unwrap_err(), ProtocolError::InvalidParameters);
+
+    let received = env
+        .as_contract(&contract_id, || {
+            Contract::migrate_collateral(
+                env.clone(),
+                user.clone(),
+                volatile_token.clone(),
+                stable_token.clone(),
+                1000,
+                dex.clone(),
+                990,
+            )
+        })
+        .unwrap();
+    assert_eq!(received, 1000);
+
+    // 1000 stable at 90% now backs more debt than the volatile collateral did
+    let (weighted, _, _) = env
+        .as_contract(&contract_id, || {
+            Contract::get_cross_position_summary(env.clone(), user.clone())
+        })
+        .unwrap();
+    assert_eq!(weighted, 900);
+
+    env.as_contract(&dex, || MockDex::set_rate_bps(env.clone(), 9000));
+    let result = env.as_contract(&contract_id, || {
+        Contract::migrate_collateral(
+            env.clone(),
+            user.clone(),
+            stable_token.clone(),
+            volatile_token.clone(),
+            500,
+            dex.clone(),
+            490,
+        )
+    });
+    assert_eq!(result.unwrap_err(), ProtocolError::SlippageExceeded);
+}
+
+#[test]
+fn test_full_user_state_aggregates_position_and_flags() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let stranger = TestUtils::create_user_address(&env, 1);
+
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    let volatile_token = env.register(MockToken, ());
+    env.as_contract(&volatile_token, || {
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_asset_params(&admin, &volatile_token, &50_000_000, &true);
+    client.set_asset_price(&admin, &volatile_token, &100_000_000);
+    client.deposit_collateral(&user, &2000);
+    client.deposit_collateral_asset(&user, &volatile_token, &1000);
+    client.borrow(&user, &1000);
+    client.freeze_user(&admin, &user);
+
+    let state = client.get_full_user_state(&user);
+    assert_eq!(state.collateral, 2000);
+    assert_eq!(state.debt, 1000);
+    assert_eq!(
+        state.asset_collateral.get(volatile_token.clone()),
+        Some(1000)
+    );
+    assert_eq!(state.weighted_collateral, 2500);
+    assert_eq!(state.collateral_ratio, 250);
+    assert_eq!(state.health_factor, 166);
+    assert_eq!(state.verification, VerificationStatus::Verified);
+    assert_eq!(state.freeze, FreezeLevel::Halted);
+    assert!(!state.blacklisted);
+
+    // Unknown users get an empty state rather than an error
+    let empty = client.get_full_user_state(&stranger);
+    assert_eq!(empty.collateral, 0);
+    assert_eq!(empty.debt, 0);
+    assert_eq!(empty.verification, VerificationStatus::Unverified);
+}
+
+#[test]
+fn test_simulate_liquidation_matches_execution() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &1000);
+    client.borrow(&user, &1000);
+    client.set_min_collateral_ratio(&admin, &150);
+
+    // Requesting more than the close factor allows is trimmed to 50% of the debt
+    let preview = client.simulate_liquidation(&user, &token, &token, &800);
+    assert_eq!(preview.repay_amount, 500);
+    assert_eq!(preview.collateral_seized, 550);
+    assert_eq!(preview.bonus, 50);
+    assert_eq!(preview.health_factor_after, 60);
+    assert_eq!(client.get_position(&user), (1000, 1000, 100));
+
+    let other_asset = env.register(MockToken, ());
+    assert_eq!(
+        client.try_simulate_liquidation(&user, &other_asset, &token, &800),
+        Err(Ok(ProtocolError::AssetNotSupported))
+    );
+
+    client.liquidate(&liquidator, &user, &800);
+    let (collateral, debt, _) = client.get_position(&user);
+    assert_eq!(collateral, 1000 - preview.collateral_seized);
+    assert_eq!(debt, 1000 - preview.repay_amount);
+}
+
+#[test]
+fn test_proposals_paginate_and_index_by_status() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let proposer = TestUtils::create_user_address(&env, 0);
+    let voter = TestUtils::create_user_address(&env, 1);
+    let contract_id = env.register(Contract, ());
+    env.as_contract(&contract_id, || {
+        for _ in 0..3 {
+            Governance::propose(&env, &proposer, String::from_str(&env, "tune"), 0);
+        }
+        Governance::vote(&env, 2, &voter, true, 100);
+        Governance::queue(&env, 2);
+    });
+
+    let client = ContractClient::new(&env, &contract_id);
+    let page = client.get_proposals(&1, &10);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(0).unwrap().id, 2);
+    assert_eq!(client.get_proposals(&0, &1).len(), 1);
+    assert_eq!(client.get_proposals(&3, &10).len(), 0);
+
+    let active = client.get_proposals_by_status(&ProposalStatus::Active, &0, &10);
+    assert_eq!(active.len(), 2);
+    let queued = client.get_proposals_by_status(&ProposalStatus::Queued, &0, &10);
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued.get(0).unwrap().id, 2);
+
+    // Page size is capped regardless of the requested limit
+    env.as_contract(&contract_id, || {
+        for _ in 0..MAX_PROPOSAL_PAGE {
+            Governance::propose(&env, &proposer, String::from_str(&env, "bulk"), 0);
+        }
+    });
+    let capped = client.get_proposals(&0, &u32::MAX);
+    assert_eq!(capped.len(), MAX_PROPOSAL_PAGE);
+}
+
+#[test]
+fn test_buyback_spends_fee_share_and_burns() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    let protocol_token = env.register(MockToken, ());
+    let dex = env.register(MockDex, ());
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_buyback_config(
+        &admin,
+        &BuybackConfig {
+            share_bps: 5000,
+            protocol_token: protocol_token.clone(),
+            dex_adapter: dex.clone(),
+            mode: BuybackMode::Burn,
+            staking_pool: None,
+            min_interval: 86_400,
+        },
+    );
+
+    // A year of borrow interest produces reserve-factor revenue
+    client.deposit_collateral(&user, &500_000);
+    client.borrow(&user, &200_000);
+    env.ledger()
+        .with_mut(|li| li.timestamp += 365 * 24 * 60 * 60);
+    client.repay(&user, &1_000);
+
+    let metrics = client.get_revenue_metrics();
+    assert!(metrics.total_fees > 0);
+    assert_eq!(metrics.buyback_reserve, metrics.total_fees / 2);
+
+    let reserve = metrics.buyback_reserve;
+    let bought = client.execute_buyback(&admin, &reserve);
+    assert_eq!(bought, reserve);
+
+    let metrics = client.get_revenue_metrics();
+    assert_eq!(metrics.buyback_reserve, 0);
+    assert_eq!(metrics.total_buyback_spent, reserve);
+    assert_eq!(metrics.total_burned, bought);
+    assert_eq!(
+        MockTokenClient::new(&env, &protocol_token).balance(&contract_id),
+        0
+    );
+
+    // Buybacks are spaced by the configured interval
+    assert_eq!(
+        client.try_execute_buyback(&admin, &0),
+        Err(Ok(ProtocolError::RateLimitExceeded))
+    );
+}
+
+#[test]
+fn test_revenue_history_buckets_by_epoch() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let day = 24 * 60 * 60;
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    env.ledger().with_mut(|li| li.timestamp = 10 * day);
+    env.as_contract(&contract_id, || {
+        RevenueManager::record_fees(&env, 100);
+        RevenueManager::record_fees(&env, 50);
+    });
+    env.ledger().with_mut(|li| li.timestamp += day);
+    env.as_contract(&contract_id, || RevenueManager::record_fees(&env, 25));
+
+    let metrics = client.get_revenue_metrics();
+    assert_eq!(metrics.total_fees, 175);
+    assert_eq!(metrics.daily_fees, 25);
+    assert_eq!(metrics.monthly_fees, 175);
+
+    let daily = client.get_revenue_history(&RevenueGranularity::Daily, &10);
+    assert_eq!(daily.len(), 2);
+    assert_eq!(
+        daily.get(0).unwrap(),
+        RevenueBucket {
+            epoch: 10,
+            fees: 150
+        }
+    );
+    assert_eq!(daily.get(1).unwrap().fees, 25);
+    assert_eq!(
+        client
+            .get_revenue_history(&RevenueGranularity::Daily, &1)
+            .len(),
+        1
+    );
+
+    // Period totals reset once the epoch passes, and history stays bounded
+    for _ in 0..MAX_REVENUE_HISTORY {
+        env.ledger().with_mut(|li| li.timestamp += day);
+        env.as_contract(&contract_id, || RevenueManager::record_fees(&env, 1));
+    }
+    env.ledger().with_mut(|li| li.timestamp += day);
+    assert_eq!(client.get_revenue_metrics().daily_fees, 0);
+    let daily = client.get_revenue_history(&RevenueGranularity::Daily, &u32::MAX);
+    assert_eq!(daily.len(), MAX_REVENUE_HISTORY);
+    assert_eq!(daily.get(0).unwrap().epoch, 12);
+}
+
+/// Signed authorization entry for a contract wallet; the wallet's `__check_auth` decides
+fn wallet_auth_entry(
+    env: &Env,
+    wallet: &Address,
+    contract_id: &Address,
+    fn_name: &str,
+    args: Vec<Val>,
+) -> SorobanAuthorizationEntry {
+    let args: alloc::vec::Vec<ScVal> = args
+        .iter()
+        .map(|arg| ScVal::try_from_val(env, &arg).unwrap())
+        .collect();
+    SorobanAuthorizationEntry {
+        credentials: SorobanCredentials::Address(SorobanAddressCredentials {
+            address: ScAddress::from(wallet),
+            nonce: 1,
+            signature_expiration_ledger: env.ledger().sequence() + 100,
+            signature: ScVal::Void,
+        }),
+        root_invocation: SorobanAuthorizedInvocation {
+            function: SorobanAuthorizedFunction::ContractFn(InvokeContractArgs {
+                contract_address: ScAddress::from(contract_id),
+                function_name: ScSymbol(fn_name.try_into().unwrap()),
+                args: args.try_into().unwrap(),
+            }),
+            sub_invocations: VecM::default(),
+        },
+    }
+}
+
+#[test]
+fn test_contract_wallet_deposits_through_custom_auth() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let wallet = env.register(MockWallet, ());
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[wallet.clone()]);
+    env.as_contract(&wallet, || {
+        MockWallet::bind(env.clone(), contract_id.clone())
+    });
+    TestUtils::verify_user(&env, &contract_id, &admin, &wallet);
+
+    // From here on the wallet's own __check_auth must approve every call
+    let client = ContractClient::new(&env, &contract_id);
+    let args: Vec<Val> = (wallet.clone(), 1000_i128).into_val(&env);
+    let entry = wallet_auth_entry(&env, &wallet, &contract_id, "deposit_collateral", args);
+    client
+        .set_auths(&[entry])
+        .deposit_collateral(&wallet, &1000);
+
+    let (collateral, debt, _) = client.get_position(&wallet);
+    assert_eq!(collateral, 1000);
+    assert_eq!(debt, 0);
+
+    // The wallet policy refuses to sign for any other contract
+    let foreign = Context::Contract(ContractContext {
+        contract: admin.clone(),
+        fn_name: Symbol::new(&env, "transfer"),
+        args: Vec::new(&env),
+    });
+    let result = env.try_invoke_contract_check_auth::<ProtocolError>(
+        &wallet,
+        &BytesN::from_array(&env, &[0; 32]),
+        ().into_val(&env),
+        &Vec::from_array(&env, [foreign]),
+    );
+    assert_eq!(result, Err(Ok(ProtocolError::Unauthorized)));
+}
+
+#[test]
+fn test_param_change_limit_caps_moves_per_window() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[]);
+    let client = ContractClient::new(&env, &contract_id);
+    let day = 24 * 60 * 60;
+
+    // Disabled by default, so any valid value goes through
+    client.set_base_rate(&admin, &2_000_000);
+    client.set_param_change_limit(&admin, &2000, &day);
+    assert_eq!(client.get_param_change_limit().max_change_bps, 2000);
+
+    // Drift is measured from the value at the start of the window, not the last update
+    client.set_base_rate(&admin, &2_400_000);
+    assert_eq!(
+        client.try_set_base_rate(&admin, &2_500_000),
+        Err(Ok(ProtocolError::ParameterChangeTooLarge))
+    );
+    client.set_base_rate(&admin, &1_800_000);
+    assert_eq!(
+        client.try_set_min_collateral_ratio(&admin, &200),
+        Err(Ok(ProtocolError::ParameterChangeTooLarge))
+    );
+    client.set_min_collateral_ratio(&admin, &170);
+
+    // A new window re-anchors on the current value
+    env.ledger().with_mut(|li| li.timestamp += day);
+    client.set_base_rate(&admin, &2_160_000);
+
+    // The emergency queue bypasses the guardrail
+    client.queue_emergency_param_update(&admin, &Symbol::new(&env, "base_rate"), &10_000_000);
+    client.apply_emergency_param_updates(&admin);
+    assert_eq!(client.get_protocol_params().0, 10_000_000);
+}
+
+#[test]
+fn test_reserve_income_is_segregated_and_paid_to_treasury() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let treasury = client.get_treasury().unwrap();
+
+    client.deposit_collateral(&user, &500_000);
+    client.borrow(&user, &200_000);
+    env.ledger()
+        .with_mut(|li| li.timestamp += 365 * 24 * 60 * 60);
+    client.repay(&user, &1_000);
+
+    // Without a buyback share all reserve income belongs to the treasury
+    let fees = client.get_revenue_metrics().total_fees;
+    let before = client.get_treasury_reconciliation(&token);
+    assert!(fees > 0);
+    assert_eq!(before.accrued, fees);
+    assert_eq!(before.pending, fees);
+    assert_eq!(before.shortfall, 0);
+
+    assert_eq!(client.distribute_fees_to_treasury(&admin, &token), fees);
+    assert_eq!(MockTokenClient::new(&env, &token).balance(&treasury), fees);
+    let after = client.get_treasury_reconciliation(&token);
+    assert_eq!(after.distributed, fees);
+    assert_eq!(after.pending, 0);
+    assert_eq!(after.contract_balance, before.contract_balance - fees);
+
+    assert_eq!(
+        client.try_distribute_fees_to_treasury(&admin, &token),
+        Err(Ok(ProtocolError::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_fee_splits_pay_recipients_proportionally() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let manager = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let ops = Address::generate(&env);
+    let grants = Address::generate(&env);
+
+    let mut splits = Vec::new(&env);
+    splits.push_back(FeeSplit {
+        recipient: ops.clone(),
+        weight_bps: 7_000,
+    });
+    splits.push_back(FeeSplit {
+        recipient: grants.clone(),
+        weight_bps: 2_000,
+    });
+
+    // Only the admin or a treasury manager may set the split
+    assert_eq!(
+        client.try_set_fee_splits(&manager, &splits),
+        Err(Ok(ProtocolError::UserNotVerified))
+    );
+    client.set_user_role(&admin, &manager, &UserRole::TreasuryManager);
+    assert_eq!(
+        client.try_set_fee_splits(&manager, &splits),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    splits.set(
+        1,
+        FeeSplit {
+            recipient: grants.clone(),
+            weight_bps: 3_000,
+        },
+    );
+    client.set_fee_splits(&manager, &splits);
+    assert_eq!(client.get_fee_splits(), splits);
+
+    client.deposit_collateral(&user, &500_000);
+    client.borrow(&user, &200_000);
+    env.ledger()
+        .with_mut(|li| li.timestamp += 365 * 24 * 60 * 60);
+    client.repay(&user, &1_000);
+
+    let fees = client.get_revenue_metrics().total_fees;
+    assert!(fees > 0);
+    assert_eq!(client.distribute_fees_to_treasury(&manager, &token), fees);
+    let token_client = MockTokenClient::new(&env, &token);
+    let ops_share = fees * 7_000 / 10_000;
+    assert_eq!(token_client.balance(&ops), ops_share);
+    assert_eq!(token_client.balance(&grants), fees - ops_share);
+    assert_eq!(token_client.balance(&client.get_treasury().unwrap()), 0);
+
+    // Clearing the split hands distributions back to the treasury
+    client.set_fee_splits(&manager, &Vec::new(&env));
+    assert!(client.get_fee_splits().is_empty());
+}
+
+#[test]
+fn test_close_factor_follows_health_bands() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let shallow = TestUtils::create_user_address(&env, 0);
+    let middle = TestUtils::create_user_address(&env, 1);
+    let deep = Address::generate(&env);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(
+        &env,
+        &[shallow.clone(), middle.clone(), deep.clone()],
+    );
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_min_collateral_ratio(&admin, &50);
+    for (user, collateral) in [(&shallow, 1_450), (&middle, 1_400), (&deep, 1_000)] {
+        TestUtils::verify_user(&env, &contract_id, &admin, user);
+        client.deposit_collateral(user, &collateral);
+        client.borrow(user, &1_000);
+    }
+    client.set_min_collateral_ratio(&admin, &150);
+
+    let band = |min_health_factor: i128, close_factor: i128| CloseFactorBand {
+        min_health_factor,
+        close_factor,
+    };
+    let bands = Vec::from_array(
+        &env,
+        [
+            band(95, 25_000_000),
+            band(90, 50_000_000),
+            band(0, 100_000_000),
+        ],
+    );
+    client.set_close_factor_bands(&admin, &bands);
+    assert_eq!(client.get_close_factor_bands(), bands);
+    MockTokenClient::new(&env, &token).mint(&admin, &1_000);
+
+    // Health factors 96, 93 and 66 fall into successively wider bands
+    let repay = |user: &Address| {
+        client
+            .simulate_liquidation(user, &token, &token, &1_000)
+            .repay_amount
+    };
+    assert_eq!(repay(&shallow), 250);
+    assert_eq!(repay(&middle), 500);
+    // The widest band lets most of the debt go at once; repaying it all would take more
+    // collateral than the position holds
+    assert_eq!(
+        client
+            .simulate_liquidation(&deep, &token, &token, &900)
+            .repay_amount,
+        900
+    );
+    assert_eq!(
+        client.try_liquidate(&admin, &deep, &1_000),
+        Err(Ok(ProtocolError::InsufficientCollateral))
+    );
+    client.liquidate(&admin, &deep, &900);
+    assert_eq!(client.get_position(&deep).0, 10);
+    assert_eq!(client.get_position(&deep).1, 100);
+
+    // Tables must cover every health factor
+    let open_ended = Vec::from_array(&env, [band(95, 25_000_000), band(90, 50_000_000)]);
+    assert_eq!(
+        client.try_set_close_factor_bands(&admin, &open_ended),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+
+    // Clearing the table restores the flat 50% close factor
+    client.set_close_factor_bands(&admin, &Vec::new(&env));
+    assert_eq!(repay(&shallow), 500);
+}
+
+#[test]
+fn test_activity_is_recorded_from_protocol_flows_per_user() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 2 * 24 * 60 * 60);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let other = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), other.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+
+    client.deposit_collateral(&user, &1_000);
+    client.borrow(&user, &200);
+    client.repay(&user, &50);
+    client.withdraw(&user, &100);
+
+    let report = client.get_user_report(&user);
+    assert_eq!(report.analytics.total_deposits, 1_000);
+    assert_eq!(report.analytics.total_borrows, 200);
+    assert_eq!(report.analytics.total_repayments, 50);
+    assert_eq!(report.analytics.total_withdrawals, 100);
+    assert_eq!(report.analytics.transaction_count, 4);
+    let mut kinds = Vec::new(&env);
+    for entry in report.recent_activities.iter() {
+        kinds.push_back(entry.activity_type);
+    }
+    assert_eq!(
+        kinds,
+        Vec::from_array(
+            &env,
+            [
+                ActivityKind::Deposit,
+                ActivityKind::Borrow,
+                ActivityKind::Repay,
+                ActivityKind::Withdraw,
+            ],
+        )
+    );
+
+    // Other accounts' stats are untouched and only active users are counted
+    assert_eq!(
+        client.get_user_report(&other).analytics.transaction_count,
+        0
+    );
+    assert_eq!(client.get_protocol_report().total_users, 1);
+}
+
+#[test]
+fn test_protocol_metrics_count_users_and_activity_natively() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let hour = 60 * 60;
+    env.ledger().with_mut(|li| li.timestamp = 10 * 24 * hour);
+
+    let alice = TestUtils::create_user_address(&env, 0);
+    let bob = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[alice.clone(), bob.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &alice);
+    TestUtils::verify_user(&env, &contract_id, &admin, &bob);
+    let client = ContractClient::new(&env, &contract_id);
+
+    client.deposit_collateral(&alice, &1_000);
+    client.deposit_collateral(&bob, &1_000);
+    env.ledger().with_mut(|li| li.timestamp += 2 * hour);
+    client.borrow(&alice, &100);
+
+    let metrics = client.get_protocol_report().protocol_metrics;
+    assert_eq!(metrics.total_users, 2);
+    assert_eq!(metrics.total_transactions, 3);
+    assert_eq!(metrics.active_users, 2);
+    assert_eq!(metrics.active_users_7d, 2);
+
+    // Bob drops out of the 24 hour window first, then both leave the 7 day window
+    env.ledger().with_mut(|li| li.timestamp += 23 * hour);
+    let metrics = client.get_protocol_report().protocol_metrics;
+    assert_eq!(metrics.active_users, 1);
+    assert_eq!(metrics.active_users_7d, 2);
+    assert_eq!(client.get_system_stats().3, 1);
+
+    env.ledger().with_mut(|li| li.timestamp += 7 * 24 * hour);
+    let report = client.get_protocol_report();
+    assert_eq!(report.protocol_metrics.active_users_7d, 0);
+    assert_eq!(report.total_users, 2);
+}
+
+#[test]
+fn test_position_history_journals_recent_changes() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+
+    client.deposit_collateral(&user, &1_500);
+    client.borrow(&user, &500);
+    env.ledger().with_mut(|li| li.timestamp += 60);
+    client.repay(&user, &100);
+
+    let history = client.get_position_history(&user, &10);
+    assert_eq!(history.len(), 3);
+    let borrow = history.get(1).unwrap();
+    assert_eq!(borrow.kind, ActivityKind::Borrow);
+    assert_eq!(borrow.asset, Some(token.clone()));
+    assert_eq!(borrow.amount, 500);
+    // 1500 / 500 = 300% against a 150% minimum
+    assert_eq!(borrow.health_factor_after, 200);
+    assert_eq!(history.get(0).unwrap().health_factor_after, 0);
+    assert_eq!(history.get(2).unwrap().timestamp, 1_060);
+
+    // `limit` keeps the newest entries
+    let latest = client.get_position_history(&user, &1);
+    assert_eq!(latest.len(), 1);
+    assert_eq!(latest.get(0).unwrap().kind, ActivityKind::Repay);
+
+    for _ in 0..MAX_POSITION_HISTORY {
+        client.deposit_collateral(&user, &1);
+    }
+    let history = client.get_position_history(&user, &u32::MAX);
+    assert_eq!(history.len(), MAX_POSITION_HISTORY);
+    assert_eq!(history.get(0).unwrap().kind, ActivityKind::Deposit);
+}
+
+#[test]
+fn test_repay_pulls_only_the_exact_payoff() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let token_client = MockTokenClient::new(&env, &token);
+
+    client.deposit_collateral(&user, &500_000);
+    client.borrow(&user, &100_000);
+    env.ledger()
+        .with_mut(|li| li.timestamp += 365 * 24 * 60 * 60);
+
+    // Overpaying settles principal and accrued interest and leaves the surplus with the user
+    let before = token_client.balance(&user);
+    client.repay(&user, &200_000);
+    let paid = before - token_client.balance(&user);
+    assert!(paid > 100_000 && paid < 200_000);
+
+    let state = client.get_full_user_state(&user);
+    assert_eq!(state.debt, 0);
+    assert_eq!(state.borrow_interest, 0);
+    assert_eq!(
+        client.try_repay(&user, &1),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+}
+
+#[test]
+fn test_liquidation_eligibility_accrues_interest_first() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+
+    client.deposit_collateral(&user, &1_600);
+    client.borrow(&user, &1_000);
+    assert_eq!(
+        client.try_liquidate(&admin, &user, &100),
+        Err(Ok(ProtocolError::NotEligibleForLiquidation))
+    );
+
+    // Nothing touches the position for years; the stored debt alone still looks healthy
+    env.ledger()
+        .with_mut(|li| li.timestamp += 5 * 365 * 24 * 60 * 60);
+    assert_eq!(client.get_position(&user).1, 1_000);
+
+    let preview = client.simulate_liquidation(&user, &token, &token, &100);
+    assert_eq!(preview.repay_amount, 100);
+    let interest = client.get_full_user_state(&user).borrow_interest;
+    assert!(interest > 67);
+
+    // Repaid debt settles the accrued interest before principal
+    MockTokenClient::new(&env, &token).mint(&admin, &50);
+    client.liquidate(&admin, &user, &50);
+    let state = client.get_full_user_state(&user);
+    assert_eq!(state.borrow_interest, interest - 50);
+    assert_eq!(state.debt, 1_000);
+}
+
+#[test]
+fn test_reentrancy_lock_released_on_error_paths() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+
+    // Early returns from guarded flows still drop their scope
+    assert_eq!(
+        client.try_withdraw(&user, &100),
+        Err(Ok(ProtocolError::PositionNotFound))
+    );
+    assert_eq!(
+        client.try_liquidate(&admin, &user, &100),
+        Err(Ok(ProtocolError::PositionNotFound))
+    );
+    client.deposit_collateral(&user, &100);
+
+    // The lock is only ever held in temporary storage
+    env.as_contract(&contract_id, || {
+        let key = DataKey::Reentrancy;
+        let scope = ReentrancyScope::enter(&env).unwrap();
+        assert!(env.storage().temporary().has(&key));
+        assert!(!env.storage().instance().has(&key));
+        drop(scope);
+        assert!(!env.storage().temporary().has(&key));
+    });
+}
+
+#[test]
+fn test_storage_uses_typed_keys() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&user, &1_000);
+
+    env.as_contract(&contract_id, || {
+        let instance = env.storage().instance();
+        assert_eq!(
+            instance.get::<_, Address>(&DataKey::Admin),
+            Some(admin.clone())
+        );
+        assert!(instance.has(&DataKey::InterestState));
+        // Per-user entries live in persistent storage, outside the instance footprint
+        let persistent = env.storage().persistent();
+        assert!(persistent.has(&DataKey::Position(user.clone())));
+        assert!(persistent.has(&DataKey::UserProfile(user.clone())));
+        assert!(!instance.has(&DataKey::Position(user.clone())));
+        assert!(!instance.has(&DataKey::UserProfile(user.clone())));
+        // Nothing is left under the old symbol keys
+        assert!(!instance.has(&Symbol::new(&env, "admin")));
+        assert!(!instance.has(&(Symbol::new(&env, "position"), user.clone())));
+    });
+}
+
+#[test]
+fn test_bootstrapping_market_opens_borrowing_on_liquidity_or_time() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+
+    assert_eq!(
+        client.try_start_market_bootstrap(&admin, &token, &0, &500),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+
+    // Deposit-only until 5_000 is supplied
+    client.start_market_bootstrap(&admin, &token, &5_000, &0);
+    assert_eq!(client.get_market_phase(&token), MarketPhase::Bootstrapping);
+    client.deposit_collateral(&user, &3_000);
+    assert_eq!(
+        client.try_borrow(&user, &1_000),
+        Err(Ok(ProtocolError::MarketBootstrapping))
+    );
+    client.deposit_collateral(&user, &2_000);
+    assert_eq!(client.get_market_phase(&token), MarketPhase::Active);
+    client.borrow(&user, &1_000);
+    assert_eq!(client.get_market_bootstrap(&token), None);
+
+    // A start time opens the market regardless of liquidity
+    client.start_market_bootstrap(&admin, &token, &0, &2_000);
+    assert_eq!(
+        client.try_borrow(&user, &100),
+        Err(Ok(ProtocolError::MarketBootstrapping))
+    );
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    client.borrow(&user, &100);
+}
+
+#[test]
+fn test_liquidations_follow_seizure_order() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &1_000);
+    client.deposit_collateral_asset(&user, &other_token, &1_000);
+    client.borrow(&user, &1_500);
+    client.set_min_collateral_ratio(&admin, &150);
+
+    // Without a preference the primary asset is seized first
+    assert_eq!(
+        client.get_seizure_order(&user),
+        vec![&env, token.clone(), other_token.clone()]
+    );
+    assert_eq!(
+        client.try_liquidate_collateral(&liquidator, &user, &other_token, &100, &None),
+        Err(Ok(ProtocolError::SeizureOrderViolation))
+    );
+
+    client.set_seizure_order(&user, &vec![&env, other_token.clone()]);
+    assert_eq!(
+        client.get_seizure_order(&user),
+        vec![&env, other_token.clone(), token.clone()]
+    );
+    assert_eq!(
+        client.try_liquidate(&liquidator, &user, &100),
+        Err(Ok(ProtocolError::SeizureOrderViolation))
+    );
+
+    // 500 repaid plus the 10% bonus comes out of the preferred asset
+    client.liquidate_collateral(&liquidator, &user, &other_token, &500, &None);
+    let remaining = env.as_contract(&contract_id, || {
+        crate::cross_asset::CrossStateHelper::get_or_init_position(&env, &user)
+            .collateral
+            .get(other_token.clone())
+    });
+    assert_eq!(remaining, Some(450));
+    assert_eq!(client.get_position(&user).0, 1_000);
+
+    assert_eq!(
+        client.try_set_seizure_order(&user, &vec![&env, other_token.clone(), other_token.clone()]),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+}
+
+#[test]
+fn test_liquidation_names_collateral_receiver() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    let client = ContractClient::new(&env, &contract_id);
+    let vault = Address::generate(&env);
+
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &1_000);
+    client.borrow(&user, &1_500);
+    client.set_min_collateral_ratio(&admin, &150);
+
+    // Seized collateral cannot be routed back to the liquidated user
+    assert_eq!(
+        client.try_liquidate_collateral(&liquidator, &user, &token, &100, &Some(user.clone())),
+        Err(Ok(ProtocolError::InvalidAddress))
+    );
+
+    // The liquidator pays the debt down and the vault receives the seized collateral
+    let token_client = MockTokenClient::new(&env, &token);
+    let preview = client.simulate_liquidation(&user, &token, &token, &100);
+    let paid_before = token_client.balance(&liquidator);
+    client.liquidate_collateral(&liquidator, &user, &token, &100, &Some(vault.clone()));
+    let executed = Symbol::new(&env, "liquidation_executed");
+    let (_, _, data) = env
+        .events()
+        .all()
+        .iter()
+        .find(|(_, topics, _)| {
+            Symbol::try_from_val(&env, &topics.get(0).unwrap()).is_ok_and(|topic| topic == executed)
+        })
+        .unwrap();
+    let data: Vec<Val> = data.into_val(&env);
+    let field = |index: u32| Address::try_from_val(&env, &data.get(index).unwrap()).unwrap();
+    assert_eq!(field(1), liquidator);
+    assert_eq!(field(3), vault);
+    assert_eq!(field(5), user);
+
+    assert_eq!(paid_before - token_client.balance(&liquidator), 100);
+    assert_eq!(token_client.balance(&vault), preview.collateral_seized);
+    assert!(preview.collateral_seized > 100);
+}
+
+#[test]
+fn test_collateral_adapter_values_and_seizes_asset() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    let lp_share = env.register(MockToken, ());
+    env.as_contract(&lp_share, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+    let adapter = env.register(MockAdapter, ());
+    let adapter_client = MockAdapterClient::new(&env, &adapter);
+    adapter_client.set_rate(&2);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_asset_params(&admin, &lp_share, &100_000_000, &true);
+    client.set_asset_price(&admin, &lp_share, &100_000_000);
+    client.deposit_collateral(&user, &300);
+    client.deposit_collateral_asset(&user, &lp_share, &1_000);
+
+    // At the feed price 1_300 of collateral cannot carry 1_200 of debt
+    assert!(client.try_borrow(&user, &1_200).is_err());
+    client.set_collateral_adapter(&admin, &lp_share, &Some(adapter.clone()));
+    assert_eq!(
+        client.get_collateral_adapter(&lp_share),
+        Some(adapter.clone())
+    );
+    client.borrow(&user, &1_200);
+
+    // The adapter marks the shares down and the position becomes liquidatable
+    adapter_client.set_rate(&1);
+    client.set_seizure_order(&user, &vec![&env, lp_share.clone()]);
+    client.liquidate_collateral(&liquidator, &user, &lp_share, &100, &None);
+    assert_eq!(
+        adapter_client.last_seizure(),
+        Some((user.clone(), 110, liquidator.clone()))
+    );
+
+    client.set_collateral_adapter(&admin, &lp_share, &None);
+    assert_eq!(client.get_collateral_adapter(&lp_share), None);
+}
+
+#[test]
+fn test_health_factor_buffer_applies_to_borrow_and_withdraw_only() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    let client = ContractClient::new(&env, &contract_id);
+
+    assert_eq!(
+        client.try_set_health_factor_buffer(&admin, &6_000),
+        Err(Ok(ProtocolError::InvalidInput))
+    );
+    // A 2% buffer on a 150% minimum means borrows and withdrawals must leave 153%
+    client.set_health_factor_buffer(&admin, &200);
+    assert_eq!(client.get_health_factor_buffer(), 200);
+    client.deposit_collateral(&user, &1_000);
+
+    assert_eq!(
+        client.try_borrow(&user, &660),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    client.borrow(&user, &650);
+    assert_eq!(
+        client.try_withdraw(&user, &15),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    client.withdraw(&user, &5);
+
+    // Liquidation eligibility still starts below 150%
+    assert_eq!(
+        client.try_liquidate(&liquidator, &user, &100),
+        Err(Ok(ProtocolError::NotEligibleForLiquidation))
+    );
+}
+
+#[test]
+fn test_same_ledger_guard_blocks_deposit_borrow_withdraw_chains() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+
+    // Unguarded markets allow the whole chain in one ledger
+    client.deposit_collateral(&user, &3_000);
+    client.borrow(&user, &500);
+    client.withdraw(&user, &100);
+
+    client.set_same_ledger_guard(&admin, &token, &true);
+    assert!(client.is_same_ledger_guarded(&token));
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    client.deposit_collateral(&user, &1_000);
+    assert_eq!(
+        client.try_borrow(&user, &500),
+        Err(Ok(ProtocolError::RateLimitExceeded))
+    );
+    assert_eq!(
+        client.try_withdraw(&user, &100),
+        Err(Ok(ProtocolError::RateLimitExceeded))
+    );
+    // Risk-reducing actions stay available
+    client.repay(&user, &100);
+
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    client.borrow(&user, &500);
+    assert_eq!(
+        client.try_withdraw(&user, &100),
+        Err(Ok(ProtocolError::RateLimitExceeded))
+    );
+}
+
+#[test]
+fn test_borrow_quote_locks_rate_for_first_accrual() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&user, &300_000);
+
+    // Quotes lapse after their validity window
+    let stale = client.quote_borrow(&token, &100_000);
+    env.ledger()
+        .with_mut(|li| li.sequence_number += crate::quote::QUOTE_VALIDITY_LEDGERS + 1);
+    assert_eq!(
+        client.try_borrow_with_quote(&user, &stale.id),
+        Err(Ok(ProtocolError::NotFound))
+    );
+
+    let quote = client.quote_borrow(&token, &100_000);
+    client.borrow_with_quote(&user, &quote.id);
+    assert_eq!(
+        client.try_borrow_with_quote(&user, &quote.id),
+        Err(Ok(ProtocolError::NotFound))
+    );
+
+    // A rate hike after the quote does not apply to the first accrual period
+    client.set_base_rate(&admin, &50_000_000);
+    env.ledger()
+        .with_mut(|li| li.timestamp += 365 * 24 * 60 * 60);
+    client.deposit_collateral(&user, &1);
+    let interest = env.as_contract(&contract_id, || {
+        StateHelper::get_position(&env, &user)
+            .unwrap()
+            .borrow_interest
+    });
+    assert_eq!(interest, 100_000 * quote.rate / 100_000_000);
+}
+
+#[test]
+fn test_market_data_reports_token_denominated_figures() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&user, &3_000);
+    client.borrow(&user, &1_000);
+
+    let data = client.get_market_data(&token);
+    assert_eq!(data.decimals, 7);
+    assert_eq!(data.total_supplied, 3_000);
+    assert_eq!(data.total_borrowed, 1_000);
+    assert_eq!(data.utilization, 1_000 * 100_000_000 / 3_000);
+    assert_eq!(data.available_liquidity, 1_000_000 + 3_000 - 1_000);
+    assert_eq!(data.reserve_balance, 0);
+
+    let unknown = Address::generate(&env);
+    assert_eq!(
+        client.try_get_market_data(&unknown),
+        Err(Ok(ProtocolError::AssetNotSupported))
+    );
+}
+
+#[test]
+fn test_proposal_diff_lists_changed_fields() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let proposer = TestUtils::create_user_address(&env, 0);
+    let (_admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[]);
+    let client = ContractClient::new(&env, &contract_id);
+
+    let mut config = client.get_configuration();
+    let old_ratio = config.min_collateral_ratio;
+    config.min_collateral_ratio = 175;
+    config.base_rate += 1_000_000;
+    let id = client.propose_configuration(
+        &proposer,
+        &String::from_str(&env, "tighten"),
+        &3_600,
+        &config,
+    );
+
+    let diff = client.get_proposal_diff(&id);
+    assert_eq!(diff.len(), 2);
+    assert_eq!(
+        diff.get(0).unwrap(),
+        (
+            String::from_str(&env, "min_collateral_ratio"),
+            String::from_str(&env, &old_ratio.to_string()),
+            String::from_str(&env, "175"),
+        )
+    );
+    assert_eq!(diff.get(1).unwrap().0, String::from_str(&env, "base_rate"));
+
+    config.rate_floor = config.rate_ceiling + 1;
+    assert_eq!(
+        client.try_propose_configuration(
+            &proposer,
+            &String::from_str(&env, "bad"),
+            &3_600,
+            &config
+        ),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    assert_eq!(
+        client.try_get_proposal_diff(&(id + 1)),
+        Err(Ok(ProtocolError::NotFound))
+    );
+}
+
+#[test]
+fn test_configuration_rollback_reactivates_historic_version() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[]);
+    let client = ContractClient::new(&env, &contract_id);
+    let original = client.get_configuration();
+    assert_eq!(client.get_latest_configuration_version(), 0);
+
+    let mut tightened = original.clone();
+    tightened.min_collateral_ratio = 200;
+    tightened.close_factor = 25_000_000;
+    assert_eq!(client.apply_configuration(&admin, &tightened), 2);
+    assert_eq!(client.get_configuration(), tightened);
+    assert_eq!(
+        client.get_configuration_version(&1).unwrap().config,
+        original
+    );
+
+    // Rolling back re-activates the old values under a new version number
+    assert_eq!(client.rollback_configuration(&admin, &1), 3);
+    assert_eq!(client.get_configuration(), original);
+    assert_eq!(client.get_latest_configuration_version(), 3);
+
+    assert_eq!(
+        client.try_rollback_configuration(&admin, &9),
+        Err(Ok(ProtocolError::NotFound))
+    );
+    let outsider = Address::generate(&env);
+    assert_eq!(
+        client.try_rollback_configuration(&outsider, &2),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+}
+
+#[test]
+fn test_executed_configuration_proposal_drives_core_flows() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let voter = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&user, &3_000);
+    assert_eq!(
+        client.try_borrow(&user, &2_100),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+
+    let mut config = client.get_configuration();
+    config.min_collateral_ratio = 125;
+    config.base_rate += 3_000_000;
+    let id = client.propose_configuration(&user, &String::from_str(&env, "loosen"), &0, &config);
+    env.as_contract(&contract_id, || {
+        Governance::vote(&env, id, &voter, true, 100);
+        Governance::queue(&env, id);
+    });
+
+    // Nothing changes until the timelock has passed
+    assert!(!client.execute_proposal(&id).executed);
+    assert_eq!(client.get_latest_configuration_version(), 0);
+
+    env.ledger().with_mut(|li| li.timestamp += 60);
+    assert!(client.execute_proposal(&id).executed);
+    assert_eq!(client.get_latest_configuration_version(), 2);
+    assert_eq!(client.get_proposal_diff(&id).len(), 0);
+    assert_eq!(
+        env.as_contract(&contract_id, || InterestRateStorage::get_config(&env)
+            .base_rate),
+        config.base_rate
+    );
+    client.borrow(&user, &2_100);
+}
+
+#[test]
+fn test_accrue_positions_brings_batch_current() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let alice = TestUtils::create_user_address(&env, 0);
+    let bob = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[alice.clone(), bob.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &alice);
+    TestUtils::verify_user(&env, &contract_id, &admin, &bob);
+    let client = ContractClient::new(&env, &contract_id);
+    for user in [&alice, &bob] {
+        client.deposit_collateral(user, &300_000);
+        client.borrow(user, &100_000);
+    }
+
+    env.ledger()
+        .with_mut(|li| li.timestamp += 30 * 24 * 60 * 60);
+    let stranger = Address::generate(&env);
+    let users = vec![&env, alice.clone(), bob.clone(), stranger];
+    assert_eq!(client.accrue_positions(&token, &users), 2);
+    for user in [&alice, &bob] {
+        let position = env.as_contract(&contract_id, || {
+            StateHelper::get_position(&env, user).unwrap()
+        });
+        assert!(position.borrow_interest > 0);
+        assert_eq!(position.last_accrual_time, env.ledger().timestamp());
+    }
+
+    let mut oversized = Vec::new(&env);
+    for _ in 0..=MAX_ACCRUAL_BATCH {
+        oversized.push_back(alice.clone());
+    }
+    assert_eq!(
+        client.try_accrue_positions(&token, &oversized),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    assert_eq!(
+        client.try_accrue_positions(&Address::generate(&env), &users),
+        Err(Ok(ProtocolError::AssetNotSupported))
+    );
+}
+
+#[test]
+fn test_debt_auction_mints_protocol_tokens_to_write_down_bad_debt() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let keeper = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), keeper.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let protocol_token = env.register(MockToken, ());
+    env.as_contract(&protocol_token, || {
+        MockToken::initialize(env.clone(), contract_id.clone())
+    });
+
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &1_000);
+    client.borrow(&user, &1_500);
+
+    let params = DebtAuctionParams {
+        protocol_token: protocol_token.clone(),
+        lot_size: 1_000,
+        start_price: 200_000_000,
+        min_price: 100_000_000,
+        duration: 1_000,
+    };
+    let id = client.start_debt_auction(&admin, &user, &params);
+    let auction = client.get_debt_auction(&id).unwrap();
+    assert_eq!(auction.debt_target, 500);
+
+    // Halfway through, the price has decayed to 1.5 debt units per token
+    env.ledger().with_mut(|li| li.timestamp += 500);
+    assert_eq!(client.get_debt_auction_price(&id), 150_000_000);
+    assert_eq!(
+        client.try_bid_debt_auction(&keeper, &id, &200, &140_000_000),
+        Err(Ok(ProtocolError::SlippageExceeded))
+    );
+    assert_eq!(
+        client.bid_debt_auction(&keeper, &id, &200, &150_000_000),
+        200
+    );
+    assert_eq!(client.get_position(&user).1, 1_200);
+
+    // The last bid is trimmed to what is left of the bad debt
+    client.bid_debt_auction(&keeper, &id, &1_000, &150_000_000);
+    let auction = client.get_debt_auction(&id).unwrap();
+    assert!(auction.settled);
+    assert_eq!(auction.raised, 500);
+    assert_eq!(client.get_position(&user).1, 1_000);
+    assert_eq!(
+        MockTokenClient::new(&env, &protocol_token).balance(&keeper),
+        auction.sold
+    );
+    assert_eq!(
+        client.try_bid_debt_auction(&keeper, &id, &1, &150_000_000),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+}
+
+#[test]
+fn test_emission_schedule_steps_through_epochs() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[]);
+    let client = ContractClient::new(&env, &contract_id);
+
+    // Incentives halve every epoch, then stop
+    let epochs = Vec::from_array(
+        &env,
+        [
+            EmissionEpoch {
+                start: 1_000,
+                end: 2_000,
+                rate_per_second: 100,
+            },
+            EmissionEpoch {
+                start: 2_000,
+                end: 3_000,
+                rate_per_second: 50,
+            },
+        ],
+    );
+    client.set_emission_schedule(&admin, &token, &RewardSide::Supply, &epochs);
+    assert_eq!(
+        client.get_emission_schedule(&token, &RewardSide::Supply),
+        epochs
+    );
+
+    let current = client.get_current_epoch_emissions(&token);
+    assert_eq!(current.supply_rate_per_second, 100);
+    assert_eq!(current.supply_epoch_end, 2_000);
+    assert_eq!(current.borrow_rate_per_second, 0);
+
+    env.ledger().with_mut(|li| li.timestamp = 2_500);
+    let current = client.get_current_epoch_emissions(&token);
+    assert_eq!(current.supply_rate_per_second, 50);
+    assert_eq!(current.supply_epoch_end, 3_000);
+
+    env.ledger().with_mut(|li| li.timestamp = 3_000);
+    assert_eq!(
+        client.get_current_epoch_emissions(&token),
+        CurrentEmissions::default()
+    );
+
+    // Overlapping epochs are rejected
+    let overlapping = Vec::from_array(
+        &env,
+        [
+            EmissionEpoch {
+                start: 4_000,
+                end: 5_000,
+                rate_per_second: 10,
+            },
+            EmissionEpoch {
+                start: 4_500,
+                end: 6_000,
+                rate_per_second: 5,
+            },
+        ],
+    );
+    assert_eq!(
+        client.try_set_emission_schedule(&admin, &token, &RewardSide::Borrow, &overlapping),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+}
+
+#[test]
+fn test_rewards_accrue_per_second_without_interaction() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let alice = TestUtils::create_user_address(&env, 0);
+    let bob = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[alice.clone(), bob.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &alice);
+    TestUtils::verify_user(&env, &contract_id, &admin, &bob);
+    let client = ContractClient::new(&env, &contract_id);
+
+    let epochs = Vec::from_array(
+        &env,
+        [EmissionEpoch {
+            start: 1_000,
+            end: 2_000,
+            rate_per_second: 100,
+        }],
+    );
+    client.set_emission_schedule(&admin, &token, &RewardSide::Supply, &epochs);
+    client.deposit_collateral(&alice, &3_000);
+    client.deposit_collateral(&bob, &1_000);
+
+    // Nobody touches their position, yet both earn their share every second
+    env.ledger().with_mut(|li| li.timestamp = 1_500);
+    assert_eq!(client.get_pending_rewards(&alice, &token), 37_500);
+    assert_eq!(client.get_pending_rewards(&bob, &token), 12_500);
+
+    // A balance change checkpoints the old share before the new one applies
+    client.deposit_collateral(&bob, &2_000);
+    env.ledger().with_mut(|li| li.timestamp = 2_500);
+    // 25_000 each for the second half (rounded down), nothing after the epoch ends
+    assert_eq!(client.get_pending_rewards(&alice, &token), 62_499);
+    assert_eq!(client.get_pending_rewards(&bob, &token), 37_499);
+}
+
+#[test]
+fn test_staking_discounts_personal_borrow_rate() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    let client = ContractClient::new(&env, &contract_id);
+    let protocol_token = env.register(MockToken, ());
+    env.as_contract(&protocol_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 5_000);
+    });
+
+    client.set_staking_config(
+        &admin,
+        &StakingConfig {
+            token: protocol_token.clone(),
+            full_discount_stake: 1_000,
+            max_discount_bps: 1_000,
+            lock_period: 100,
+        },
+    );
+    let market_rate = client.get_user_borrow_rate(&user, &token);
+
+    // Half the full stake earns half the 10% discount
+    client.stake(&user, &500);
+    assert_eq!(
+        client.get_user_borrow_rate(&user, &token),
+        market_rate - market_rate * 500 / 10_000
+    );
+
+    // The discount is capped however much is staked
+    client.stake(&user, &4_000);
+    assert_eq!(
+        client.get_user_borrow_rate(&user, &token),
+        market_rate - market_rate * 1_000 / 10_000
+    );
+
+    assert_eq!(
+        client.try_unstake(&user, &4_500),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+    env.ledger().with_mut(|li| li.timestamp += 100);
+    client.unstake(&user, &4_500);
+    assert_eq!(client.get_stake(&user).amount, 0);
+    assert_eq!(
+        MockTokenClient::new(&env, &protocol_token).balance(&user),
+        5_000
+    );
+    assert_eq!(client.get_user_borrow_rate(&user, &token), market_rate);
+}
+
+#[test]
+fn test_compliance_officer_caps_supply_per_address() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let officer = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), officer.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+
+    // Only compliance officers (or the admin) can place caps
+    assert_eq!(
+        client.try_set_address_supply_cap(&officer, &token, &user, &Some(5_000)),
+        Err(Ok(ProtocolError::UserNotVerified))
+    );
+    client.set_user_role(&admin, &officer, &UserRole::ComplianceOfficer);
+    assert_eq!(client.get_remaining_supply_capacity(&user, &token), None);
+    client.set_address_supply_cap(&officer, &token, &user, &Some(5_000));
+
+    client.deposit_collateral(&user, &3_000);
+    assert_eq!(
+        client.get_remaining_supply_capacity(&user, &token),
+        Some(2_000)
+    );
+    assert_eq!(
+        client.try_deposit_collateral(&user, &2_001),
+        Err(Ok(ProtocolError::AddressCapExceeded))
+    );
+
+    // The tighter of the asset-wide and per-address caps applies
+    client.set_max_position_size(&admin, &token, &4_000);
+    assert_eq!(
+        client.get_remaining_supply_capacity(&user, &token),
+        Some(1_000)
+    );
+
+    client.set_address_supply_cap(&officer, &token, &user, &None);
+    client.set_max_position_size(&admin, &token, &0);
+    client.deposit_collateral(&user, &10_000);
+    assert_eq!(client.get_remaining_supply_capacity(&user, &token), None);
+}
+
+#[test]
+fn test_purge_account_returns_funds_and_forgets_user() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let delegate = Address::generate(&env);
+    let protector = Address::generate(&env);
+
+    client.deposit_collateral(&user, &5_000);
+    client.borrow(&user, &1_000);
+    client.grant_session(&user, &delegate, &100, &true, &false, &2_000);
+    client.set_protector(&user, &protector, &true);
+
+    // Outstanding debt blocks the purge
+    assert_eq!(
+        client.try_purge_account(&user),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+    client.repay(&user, &1_000);
+
+    let balance_before = MockTokenClient::new(&env, &token).balance(&user);
+    let returned = client.purge_account(&user);
+    assert_eq!(returned, 5_000);
+    assert_eq!(
+        MockTokenClient::new(&env, &token).balance(&user),
+        balance_before + 5_000
+    );
+
+    assert_eq!(
+        client.try_get_position(&user),
+        Err(Ok(ProtocolError::PositionNotFound))
+    );
+    assert_eq!(client.get_position_history(&user, &10).len(), 0);
+    assert_eq!(client.get_session(&user, &delegate), None);
+    assert!(!client.is_protector(&user, &protector));
+    assert_eq!(
+        client.get_user_profile(&user).verification,
+        VerificationStatus::Unverified
+    );
+    let log = env.as_contract(&contract_id, || {
+        crate::analytics::AnalyticsStorage::get_activity_log(&env)
+    });
+    assert!(log.iter().all(|entry| entry.user != user));
+}
+
+#[test]
+fn test_utilization_spike_trips_borrow_breaker() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| {
+        li.timestamp = 1_000;
+        li.sequence_number = 10;
+    });
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&user, &100_000);
+
+    // Utilization may rise at most 20 percentage points within a ledger
+    client.set_utilization_breaker(
+        &admin,
+        &token,
+        &Some(UtilizationBreaker {
+            max_jump: 20_000_000,
+            cooldown: 600,
+        }),
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    client.borrow(&user, &15_000);
+    assert_eq!(client.get_borrow_cooldown(&token), 0);
+
+    // A second borrow in the same ledger takes the jump past the limit
+    client.borrow(&user, &10_000);
+    assert_eq!(client.get_borrow_cooldown(&token), 1_600);
+
+    env.ledger().with_mut(|li| {
+        li.sequence_number += 1;
+        li.timestamp += 300;
+    });
+    assert_eq!(
+        client.try_borrow(&user, &1_000),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+
+    env.ledger().with_mut(|li| {
+        li.sequence_number += 1;
+        li.timestamp += 300;
+    });
+    client.borrow(&user, &1_000);
+}
+
+#[test]
+fn test_confidence_interval_discounts_borrowing_power_only() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    let client = ContractClient::new(&env, &contract_id);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+    let oracle = env.register(MockOracle, ());
+    MockOracleClient::new(&env, &oracle).set_feed(&100_000_000, &10_000_000);
+
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_oracle_source(&admin, &other_token, &oracle, &1);
+    assert_eq!(
+        client.sync_asset_price(&other_token),
+        (100_000_000, 10_000_000)
+    );
+    assert_eq!(client.get_asset_confidence(&other_token), 10_000_000);
+
+    // Two confidence intervals come off the price when sizing borrows
+    client.set_confidence_multiplier(&admin, &200_000_000);
+    client.set_min_collateral_ratio(&admin, &100);
+    client.deposit_collateral(&user, &100);
+    client.deposit_collateral_asset(&user, &other_token, &1_000);
+    assert_eq!(
+        client.try_borrow(&user, &901),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    client.borrow(&user, &900);
+
+    // Liquidation checks value the collateral at the undiscounted price
+    assert_eq!(
+        client.try_liquidate(&liquidator, &user, &100),
+        Err(Ok(ProtocolError::NotEligibleForLiquidation))
+    );
+
+    // A manual price carries no confidence interval
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+    assert_eq!(client.get_asset_confidence(&other_token), 0);
+    client.borrow(&user, &200);
+}
+
+#[test]
+fn test_core_flows_share_compliance_gate() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+    client.deposit_collateral(&user, &1_000);
+    client.deposit_collateral_asset(&user, &other_token, &1_000);
+
+    // The withdraw pause switch covers every path that takes collateral out
+    client.set_pause_switches(&admin, &false, &false, &true, &false);
+    assert_eq!(
+        client.try_withdraw(&user, &100),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+    assert_eq!(
+        client.try_withdraw_asset(&user, &other_token, &100),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+    assert_eq!(
+        client.try_migrate_collateral(&user, &other_token, &other_token, &100, &admin, &0),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+    client.set_pause_switches(&admin, &false, &false, &false, &false);
+    client.withdraw(&user, &100);
+
+    // Frozen users are stopped at the same gate in every flow
+    client.freeze_user(&admin, &user);
+    assert_eq!(
+        client.try_withdraw_asset(&user, &other_token, &100),
+        Err(Ok(ProtocolError::UserSuspended))
+    );
+    assert_eq!(
+        client.try_repay(&user, &10),
+        Err(Ok(ProtocolError::UserSuspended))
+    );
+    client.unfreeze_user(&admin, &user);
+    client.borrow(&user, &100);
+
+    // Liquidators go through the same verification as every other actor
+    assert_eq!(
+        client.try_liquidate(&liquidator, &user, &10),
+        Err(Ok(ProtocolError::UserNotVerified))
+    );
+    client.set_pause_switches(&admin, &false, &false, &false, &true);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    assert_eq!(
+        client.try_liquidate(&liquidator, &user, &10),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+}
+
+#[test]
+fn test_risk_overview_reports_assets_and_pauses() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let at_risk = TestUtils::create_user_address(&env, 0);
+    let saver = TestUtils::create_user_address(&env, 1);
+    let insolvent = Address::generate(&env);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(
+        &env,
+        &[at_risk.clone(), saver.clone(), insolvent.clone()],
+    );
+    for user in [&at_risk, &saver, &insolvent] {
+        TestUtils::verify_user(&env, &contract_id, &admin, user);
+    }
+    let client = ContractClient::new(&env, &contract_id);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), insolvent.clone(), 10_000);
+    });
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+    client.set_oracle_heartbeat(&admin, &3_600);
+    client.set_min_collateral_ratio(&admin, &100);
+    client.set_max_position_size(&admin, &token, &4_000);
+
+    client.deposit_collateral(&at_risk, &1_000);
+    client.borrow(&at_risk, &950);
+    client.deposit_collateral(&saver, &2_000);
+    client.deposit_collateral(&insolvent, &10);
+    client.deposit_collateral_asset(&insolvent, &other_token, &1_000);
+    client.borrow(&insolvent, &900);
+
+    // Halving the collateral price leaves the second borrower 390 short
+    client.set_asset_price(&admin, &other_token, &50_000_000);
+    env.ledger().with_mut(|li| li.timestamp += 7_200);
+    client.set_pause_switches(&admin, &false, &true, &false, &false);
+
+    let overview = client.get_risk_overview();
+    assert_eq!(overview.total_bad_debt, 390);
+    assert!(overview.pause_deposit);
+    assert!(!overview.pause_borrow);
+    assert_eq!(overview.emergency_status, EmergencyStatus::Operational);
+
+    let primary = overview.assets.get(0).unwrap();
+    assert_eq!(primary.asset, token);
+    assert_eq!(primary.total_supplied, 3_010);
+    assert_eq!(primary.position_cap_usage_bps, 5_000);
+    assert_eq!(primary.positions_at_risk, 2);
+    assert!(primary.utilization > 0);
+    assert!(!primary.price_stale);
+
+    let other = overview.assets.get(1).unwrap();
+    assert_eq!(other.asset, other_token);
+    assert_eq!(other.total_supplied, 1_000);
+    assert_eq!(other.utilization, 0);
+    assert_eq!(other.positions_at_risk, 1);
+    assert_eq!(other.price_age, 7_200);
+    assert!(other.price_stale);
+}
+
+#[cfg(feature = "testutils")]
+#[test]
+fn test_stress_helpers_drive_interest_model() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&user, &10_000);
+    client.borrow(&user, &1_000);
+
+    let state = client.stress_set_utilization(&admin, &80_000_000);
+    assert_eq!(state.utilization_rate, 80_000_000);
+    assert_eq!(
+        client.try_stress_set_utilization(&user, &80_000_000),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+
+    // A year of accrual reaches positions listed in the fast-forward
+    client.stress_fast_forward(&admin, &31_536_000, &vec![&env, user.clone()]);
+    client.accrue_positions(&token, &vec![&env, user.clone()]);
+    let interest = env.as_contract(&contract_id, || {
+        StateHelper::get_position(&env, &user)
+            .unwrap()
+            .borrow_interest
+    });
+    assert!(interest > 0);
+
+    let calm = InterestRateConfig::default();
+    let stressed = InterestRateConfig {
+        base_rate: calm.base_rate * 10,
+        rate_ceiling: 100_000_000,
+        ..Default::default()
+    };
+    let states = client.stress_replay_rates(&admin, &vec![&env, calm, stressed], &86_400);
+    assert_eq!(states.len(), 2);
+    let (first, second) = (states.get(0).unwrap(), states.get(1).unwrap());
+    assert!(second.current_borrow_rate > first.current_borrow_rate);
+    assert!(second.liquidity_index > first.liquidity_index);
+}
+
+#[test]
+fn test_restricted_liquidations_require_allowlisted_liquidator() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let officer = Address::generate(&env);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    TestUtils::verify_user(&env, &contract_id, &admin, &officer);
+    let client = ContractClient::new(&env, &contract_id);
+
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &1_000);
+    client.borrow(&user, &1_000);
+    client.set_min_collateral_ratio(&admin, &150);
+
+    client.set_restricted_liquidations(&admin, &true);
+    assert!(client.is_restricted_liquidations());
+    assert_eq!(
+        client.try_liquidate(&liquidator, &user, &100),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+
+    // The allowlist belongs to compliance officers
+    assert_eq!(
+        client.try_set_approved_liquidator(&officer, &liquidator, &true),
+        Err(Ok(ProtocolError::UserRoleViolation))
+    );
+    client.set_user_role(&admin, &officer, &UserRole::ComplianceOfficer);
+    client.set_approved_liquidator(&officer, &liquidator, &true);
+    assert!(client.is_approved_liquidator(&liquidator));
+    client.liquidate(&liquidator, &user, &100);
+
+    // Public deployments stay permissionless
+    client.set_approved_liquidator(&officer, &liquidator, &false);
+    client.set_restricted_liquidations(&admin, &false);
+    client.liquidate(&liquidator, &user, &100);
+}
+
+#[test]
+fn test_health_warnings_carry_notification_hint() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_min_collateral_ratio(&admin, &100);
+
+    let hint = String::from_str(&env, "webhook-42");
+    assert_eq!(
+        client.try_set_notification_prefs(
+            &user,
+            &Some(NotificationPrefs {
+                hint: hint.clone(),
+                warn_health_factor: 100,
+            })
+        ),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    let prefs = NotificationPrefs {
+        hint: hint.clone(),
+        warn_health_factor: 150,
+    };
+    client.set_notification_prefs(&user, &Some(prefs.clone()));
+    assert_eq!(client.get_notification_prefs(&user), Some(prefs));
+
+    client.deposit_collateral(&user, &1_000);
+    assert_eq!(client.check_health(&user), 0);
+    client.borrow(&user, &800);
+
+    assert_eq!(client.check_health(&user), 125);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    let topic: Symbol = topics.get(0).unwrap().into_val(&env);
+    assert_eq!(topic, Symbol::new(&env, "health_warning"));
+    let (health_factor, threshold, echoed): (i128, i128, String) = data.into_val(&env);
+    assert_eq!((health_factor, threshold, echoed), (125, 150, hint));
+
+    // Opting out silences further warnings
+    client.set_notification_prefs(&user, &None);
+    assert_eq!(client.check_health(&user), 125);
+    assert!(env.events().all().is_empty());
+}
+
+#[test]
+fn test_emergency_redeem_pays_pro_rata_after_prolonged_pause() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let saver = TestUtils::create_user_address(&env, 0);
+    let borrower = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[saver.clone(), borrower.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &saver);
+    TestUtils::verify_user(&env, &contract_id, &admin, &borrower);
+    let client = ContractClient::new(&env, &contract_id);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), saver.clone(), 1_000);
+    });
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+
+    client.set_min_collateral_ratio(&admin, &100);
+    client.deposit_collateral(&saver, &1_000);
+    client.deposit_collateral_asset(&saver, &other_token, &500);
+    client.deposit_collateral(&borrower, &1_000);
+    client.borrow(&borrower, &800);
+    // The incident leaves the pool holding half of the 1_200 it owes suppliers
+    let pool = MockTokenClient::new(&env, &token);
+    pool.burn(&contract_id, &(pool.balance(&contract_id) - 600));
+
+    client.set_emergency_redeem_delay(&admin, &86_400);
+    assert_eq!(client.get_emergency_redeem_opens_at(), None);
+    client.trigger_emergency_pause(&admin, &None);
+    assert_eq!(client.get_emergency_redeem_opens_at(), Some(87_400));
+    assert_eq!(
+        client.try_emergency_redeem(&saver, &token),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 87_400);
+    // Cross-asset collateral stays put until the primary redemption has netted the debt
+    assert_eq!(
+        client.try_emergency_redeem(&borrower, &other_token),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+    assert_eq!(client.emergency_redeem(&saver, &token), 500);
+    assert_eq!(client.emergency_redeem(&saver, &other_token), 500);
+    assert_eq!(client.emergency_redeem(&borrower, &token), 100);
+    assert_eq!(
+        client.try_emergency_redeem(&saver, &token),
+        Err(Ok(ProtocolError::InsufficientCollateral))
+    );
+}
+
+#[test]
+fn test_liquidation_rounding_surplus_stays_with_borrower() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &300_000_000);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &1_000);
+    client.deposit_collateral_asset(&user, &other_token, &400);
+    client.borrow(&user, &1_500);
+    client.set_min_collateral_ratio(&admin, &150);
+    client.set_seizure_order(&user, &vec![&env, other_token.clone()]);
+
+    // Repay + bonus is worth 110, i.e. 36.67 units at a price of 3; only 36 whole units
+    // are seized and the 2 left over stay in the borrower's collateral
+    client.liquidate_collateral(&liquidator, &user, &other_token, &100, &None);
+    let surplus = env
+        .events()
+        .all()
+        .iter()
+        .find(|(_, topics, _)| {
+            topics.get(0).map(|t| {
+                Symbol::try_from_val(&env, &t).ok()
+                    == Some(Symbol::new(&env, "liquidation_surplus"))
+            }) == Some(true)
+        })
+        .map(|(_, _, data)| <(Address, i128)>::try_from_val(&env, &data).unwrap());
+    assert_eq!(surplus, Some((other_token.clone(), 2)));
+    let remaining = env.as_contract(&contract_id, || {
+        crate::cross_asset::CrossStateHelper::get_or_init_position(&env, &user)
+            .collateral
+            .get(other_token.clone())
+            .unwrap()
+    });
+    let seized = 400 - remaining;
+    assert_eq!(seized, 36);
+    assert!(seized * 3 <= 100 + 10);
+    assert!((seized + 1) * 3 > 100 + 10);
+}
+
+#[test]
+fn test_asset_oracle_config_overrides_global_defaults() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    let client = ContractClient::new(&env, &contract_id);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+    });
+    let oracle = env.register(MockOracle, ());
+    let feed = MockOracleClient::new(&env, &oracle);
+    feed.set_feed(&100_000_000, &0);
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_oracle_source(&admin, &other_token, &oracle, &1);
+
+    // Without an override the global heartbeat applies
+    assert_eq!(
+        client.get_asset_oracle_config(&other_token),
+        AssetOracleConfig {
+            heartbeat: 300,
+            max_deviation_bps: 0,
+            fallback_price: 0
+        }
+    );
+    client.sync_asset_price(&other_token);
+
+    let config = AssetOracleConfig {
+        heartbeat: 3_600,
+        max_deviation_bps: 1_000,
+        fallback_price: 90_000_000,
+    };
+    client.set_asset_oracle_config(&admin, &other_token, &Some(config.clone()));
+    assert_eq!(client.get_asset_oracle_config(&other_token), config);
+    assert_eq!(
+        client.try_set_asset_oracle_config(
+            &admin,
+            &other_token,
+            &Some(AssetOracleConfig {
+                max_deviation_bps: 10_001,
+                ..config.clone()
+            })
+        ),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+
+    // A 20% jump is outside the asset's 10% band; 5% is inside it
+    feed.set_feed(&120_000_000, &0);
+    assert_eq!(
+        client.try_sync_asset_price(&other_token),
+        Err(Ok(ProtocolError::OracleFailure))
+    );
+    feed.set_feed(&105_000_000, &0);
+    assert_eq!(client.sync_asset_price(&other_token), (105_000_000, 0));
+
+    // The asset's own heartbeat keeps the price fresh past the global one
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    assert!(!client.is_price_stale(&other_token));
+    client.set_asset_oracle_config(&admin, &other_token, &None);
+    assert!(client.is_price_stale(&other_token));
+
+    // Once every source has missed its heartbeat the fallback price is recorded
+    client.set_asset_oracle_config(&admin, &other_token, &Some(config));
+    env.ledger().with_mut(|li| li.timestamp = 5_000);
+    assert_eq!(client.sync_asset_price(&other_token), (90_000_000, 0));
+    assert!(!client.is_price_stale(&other_token));
+}
+
+#[test]
+fn test_circuit_break_lifts_unless_confirmed() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let guardian = Address::generate(&env);
+
+    assert_eq!(
+        client.try_circuit_break(&guardian),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+    client.set_emergency_manager(&admin, &guardian, &true);
+    // An emergency manager can only pause through a circuit break
+    assert_eq!(
+        client.try_trigger_emergency_pause(&guardian, &None),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_enter_recovery_mode(&guardian, &None),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+    assert_eq!(client.circuit_break(&guardian), 1);
+    assert!(client.is_circuit_break_used(&guardian));
+    assert_eq!(
+        client.get_circuit_break().map(|record| record.confirm_by),
+        Some(1_000 + 48 * 60 * 60)
+    );
+    assert_eq!(
+        client.try_deposit_collateral(&user, &100),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+    assert_eq!(
+        client.try_circuit_break(&guardian),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+    assert_eq!(
+        client.try_confirm_circuit_break(&guardian, &1),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+
+    // Left unconfirmed, the pause lifts once the window closes
+    env.ledger().with_mut(|li| li.timestamp += 48 * 60 * 60);
+    client.deposit_collateral(&user, &100);
+    assert_eq!(
+        client.try_confirm_circuit_break(&admin, &1),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+
+    // The break is spent even after it lapsed, until the admin hands it back
+    assert_eq!(
+        client.try_circuit_break(&guardian),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_reset_circuit_break(&guardian, &guardian),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+    client.reset_circuit_break(&admin, &guardian);
+    assert!(!client.is_circuit_break_used(&guardian));
+
+    // A confirmed incident keeps the protocol paused until it is resumed
+    assert_eq!(client.circuit_break(&guardian), 2);
+    assert_eq!(
+        client.try_confirm_circuit_break(&admin, &1),
+        Err(Ok(ProtocolError::NotFound))
+    );
+    client.confirm_circuit_break(&admin, &2);
+    env.ledger().with_mut(|li| li.timestamp += 48 * 60 * 60);
+    assert_eq!(
+        client.try_deposit_collateral(&user, &100),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+    client.resume_operations(&admin);
+    assert_eq!(client.get_circuit_break(), None);
+    client.deposit_collateral(&user, &100);
+}
+
+#[test]
+fn test_seizure_rounding_bounds_over_fuzzed_amounts_and_prices() {
+    // Deterministic LCG so failures reproduce
+    let mut seed: u64 = 0x5eed_1234_abcd_0001;
+    let mut next = |bound: i128| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        1 + (seed >> 11) as i128 % bound
+    };
+    for _ in 0..10_000 {
+        let amount = next(1_000_000_000_000);
+        let incentive = next(100_000_000) - 1;
+        let price = next(1_000_000_000_000);
+        let owed_scaled = amount * (100_000_000 + incentive);
+
+        let down = SeizureRounding::FavorBorrower.divide(owed_scaled, price);
+        let up = SeizureRounding::FavorProtocol.divide(owed_scaled, price);
+        // Rounded down the seizure never exceeds repay + bonus; rounded up it never falls short
+        assert!(down * price <= owed_scaled && owed_scaled < (down + 1) * price);
+        assert!(up * price >= owed_scaled && owed_scaled > (up - 1) * price);
+        // Only the fractional unit is in play
+        assert!(up - down == 0 || up - down == 1);
+        assert_eq!(up == down, owed_scaled % price == 0);
+    }
+}
+
+#[test]
+fn test_seizure_rounding_favoring_protocol_credits_reserves() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &300_000_000);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &1_000);
+    client.deposit_collateral_asset(&user, &other_token, &400);
+    client.borrow(&user, &1_500);
+    client.set_min_collateral_ratio(&admin, &150);
+    client.set_seizure_order(&user, &vec![&env, other_token.clone()]);
+
+    assert_eq!(
+        client.get_seizure_rounding(),
+        SeizureRounding::FavorBorrower
+    );
+    client.set_seizure_rounding(&admin, &SeizureRounding::FavorProtocol);
+    assert_eq!(
+        client.get_seizure_rounding(),
+        SeizureRounding::FavorProtocol
+    );
+
+    // Repay + bonus is worth 36.67 units; 37 are seized and the extra one goes to reserves
+    client.liquidate_collateral(&liquidator, &user, &other_token, &100, &None);
+    let remaining = env.as_contract(&contract_id, || {
+        crate::cross_asset::CrossStateHelper::get_or_init_position(&env, &user)
+            .collateral
+            .get(other_token.clone())
+            .unwrap()
+    });
+    assert_eq!(400 - remaining, 37);
+    assert!((400 - remaining) * 3 >= 100 + 10);
+    assert_eq!(client.get_treasury_reconciliation(&other_token).accrued, 1);
+}
+
+#[test]
+fn test_user_net_apy_combines_rates_and_rewards() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    assert_eq!(client.get_user_net_apy(&user), 0);
+
+    client.deposit_collateral(&user, &1_000);
+    client.borrow(&user, &400);
+    let state = env.as_contract(&contract_id, || InterestRateStorage::get_state(&env));
+    let income = 1_000 * state.current_supply_rate / 100_000_000
+        - 400 * state.current_borrow_rate / 100_000_000;
+    // 17 earned on supply less 8 paid on debt, on a net worth of 600
+    assert_eq!(income, 9);
+    assert_eq!(client.get_user_net_apy(&user), 1_500_000);
+
+    // Supply-side incentives are annualized into the same rate
+    let epochs = Vec::from_array(
+        &env,
+        [EmissionEpoch {
+            start: 1_000,
+            end: 100_000_000,
+            rate_per_second: 1,
+        }],
+    );
+    client.set_emission_schedule(&admin, &token, &RewardSide::Supply, &epochs);
+    client.deposit_collateral(&user, &200);
+    let state = env.as_contract(&contract_id, || InterestRateStorage::get_state(&env));
+    let income = 1_200 * state.current_supply_rate / 100_000_000
+        - 400 * state.current_borrow_rate / 100_000_000
+        + 365 * 24 * 60 * 60;
+    assert_eq!(client.get_user_net_apy(&user), income * 100_000_000 / 800);
+
+    // Debt above the supplied value leaves no net worth to rate against
+    client.set_min_collateral_ratio(&admin, &50);
+    client.borrow(&user, &1_000);
+    assert_eq!(client.get_user_net_apy(&user), 0);
+}
+
+#[test]
+fn test_relayer_executes_signed_action_once() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let wallet = env.register(MockWallet, ());
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[wallet.clone()]);
+    env.as_contract(&wallet, || {
+        MockWallet::bind(env.clone(), contract_id.clone())
+    });
+    TestUtils::verify_user(&env, &contract_id, &admin, &wallet);
+    let client = ContractClient::new(&env, &contract_id);
+
+    // The wallet authorizes exactly this payload; whoever submits it pays the fee
+    let payload = SignedAction {
+        nonce: 0,
+        deadline: 1_100,
+        action: RelayAction::Deposit(1_000),
+    };
+    let signed = |payload: &SignedAction| {
+        let args: Vec<Val> = (wallet.clone(), payload.clone()).into_val(&env);
+        wallet_auth_entry(&env, &wallet, &contract_id, "execute_signed", args)
+    };
+    client
+        .set_auths(&[signed(&payload)])
+        .execute_signed(&wallet, &payload);
+    assert_eq!(client.get_position(&wallet).0, 1_000);
+    assert_eq!(client.get_relay_nonce(&wallet), 1);
+
+    // A replayed payload carries a spent nonce
+    assert_eq!(
+        client
+            .set_auths(&[signed(&payload)])
+            .try_execute_signed(&wallet, &payload),
+        Err(Ok(ProtocolError::InvalidInput))
+    );
+
+    // A relayer cannot alter what the user signed
+    let next = SignedAction {
+        nonce: 1,
+        ..payload.clone()
+    };
+    let altered = SignedAction {
+        action: RelayAction::Borrow(500),
+        ..next.clone()
+    };
+    assert!(client
+        .set_auths(&[signed(&next)])
+        .try_execute_signed(&wallet, &altered)
+        .is_err());
+
+    env.ledger().with_mut(|li| li.timestamp = 1_101);
+    assert_eq!(
+        client
+            .set_auths(&[signed(&next)])
+            .try_execute_signed(&wallet, &next),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+    assert_eq!(client.get_relay_nonce(&wallet), 1);
+}
+
+#[test]
+fn test_supply_fee_decays_idle_collateral_down_to_floor() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    let year = 365 * 24 * 60 * 60;
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let users = vec![&env, user.clone()];
+
+    let config = SupplyFeeConfig {
+        annual_rate: 10_000_000,
+        floor: 100_000,
+    };
+    assert_eq!(
+        client.try_set_supply_fee(
+            &admin,
+            &Some(SupplyFeeConfig {
+                annual_rate: 0,
+                ..config.clone()
+            })
+        ),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    client.set_supply_fee(&admin, &Some(config.clone()));
+    assert_eq!(client.get_supply_fee(), Some(config));
+
+    // 10% a year on the 100,000 above the floor
+    client.deposit_collateral(&user, &200_000);
+    env.ledger().with_mut(|li| li.timestamp += year);
+    client.accrue_positions(&token, &users);
+    let charged = env.events().all().iter().find_map(|(_, topics, data)| {
+        let name = Symbol::try_from_val(&env, &topics.get(0).unwrap()).ok()?;
+        (name == Symbol::new(&env, "supply_fee_charged"))
+            .then(|| <(i128, i128)>::try_from_val(&env, &data).unwrap())
+    });
+    assert_eq!(charged, Some((10_000, 190_000)));
+    assert_eq!(client.get_position(&user).0, 190_000);
+    assert_eq!(client.get_treasury_reconciliation(&token).accrued, 10_000);
+
+    // The fee never takes collateral below the floor
+    client.set_supply_fee(
+        &admin,
+        &Some(SupplyFeeConfig {
+            annual_rate: 100_000_000,
+            floor: 100_000,
+        }),
+    );
+    env.ledger().with_mut(|li| li.timestamp += 2 * year);
+    client.accrue_positions(&token, &users);
+    assert_eq!(client.get_position(&user).0, 100_000);
+    assert_eq!(client.get_treasury_reconciliation(&token).accrued, 100_000);
+
+    client.set_supply_fee(&admin, &None);
+    env.ledger().with_mut(|li| li.timestamp += year);
+    client.accrue_positions(&token, &users);
+    assert_eq!(client.get_position(&user).0, 100_000);
+}
+
+#[test]
+fn test_split_and_merge_positions_carry_collateral_with_debt() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let buyer = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), buyer.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 1_000);
+    });
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+
+    client.deposit_collateral(&user, &3_000);
+    client.deposit_collateral_asset(&user, &other_token, &1_000);
+    client.borrow(&user, &2_000);
+
+    // The buyer must be allowed to borrow to take on debt
+    assert!(client
+        .try_split_position(&user, &token, &500, &buyer)
+        .is_err());
+    TestUtils::verify_user(&env, &contract_id, &admin, &buyer);
+    assert_eq!(
+        client.try_split_position(&user, &token, &2_001, &buyer),
+        Err(Ok(ProtocolError::InvalidAmount))
+    );
+    assert_eq!(
+        client.try_split_position(&user, &other_token, &500, &buyer),
+        Err(Ok(ProtocolError::AssetNotSupported))
+    );
+
+    // A quarter of the debt takes a quarter of each collateral balance with it
+    client.split_position(&user, &token, &500, &buyer);
+    assert_eq!(client.get_position(&user), (2_250, 1_500, 200));
+    assert_eq!(client.get_position(&buyer), (750, 500, 200));
+    assert_eq!(client.get_cross_position_summary(&buyer), (1_000, 500, 200));
+
+    // Neither side may end up below the minimum ratio
+    client.set_min_collateral_ratio(&admin, &250);
+    assert_eq!(
+        client.try_split_position(&user, &token, &500, &buyer),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    client.set_min_collateral_ratio(&admin, &150);
+
+    let stranger = Address::generate(&env);
+    assert_eq!(
+        client.try_merge_positions(&stranger, &buyer, &user),
+        Err(Ok(ProtocolError::InvalidInput))
+    );
+    client.merge_positions(&user, &buyer, &user);
+    assert_eq!(client.get_position(&user), (3_000, 2_000, 200));
+    assert_eq!(client.get_position(&buyer), (0, 0, 0));
+    assert_eq!(client.get_cross_position_summary(&buyer), (0, 0, 0));
+}
+
+#[test]
+fn test_liquidation_fee_goes_to_asset_reserves() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &300_000_000);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &1_000);
+    client.deposit_collateral_asset(&user, &other_token, &400);
+    client.borrow(&user, &1_500);
+    client.set_min_collateral_ratio(&admin, &150);
+    client.set_seizure_order(&user, &vec![&env, other_token.clone()]);
+
+    assert_eq!(
+        client.try_set_liquidation_fee(&admin, &other_token, &10_001),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    client.set_liquidation_fee(&admin, &other_token, &500);
+    assert_eq!(client.get_liquidation_fee(&other_token), 500);
+
+    // Repay + bonus is worth 110 units; 5% of them is charged on top for the reserves
+    client.liquidate_collateral(&liquidator, &user, &other_token, &300, &None);
+    let remaining = env.as_contract(&contract_id, || {
+        crate::cross_asset::CrossStateHelper::get_or_init_position(&env, &user)
+            .collateral
+            .get(other_token.clone())
+            .unwrap()
+    });
+    assert_eq!(remaining, 400 - 110 - 5);
+    assert_eq!(client.get_treasury_reconciliation(&other_token).accrued, 5);
+    assert_eq!(client.get_revenue_metrics().total_liquidation_fees, 15);
+}
+
+#[test]
+fn test_recovery_mode_tracks_global_collateralization() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    let client = ContractClient::new(&env, &contract_id);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 1_000);
+    });
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+
+    client.deposit_collateral(&user, &1_000);
+    client.deposit_collateral_asset(&user, &other_token, &1_000);
+    client.borrow(&user, &900);
+    assert_eq!(client.get_global_collateral_ratio(), 222);
+
+    let config = RecoveryConfig {
+        enter_ratio: 180,
+        exit_ratio: 220,
+        ltv_multiplier: 50_000_000,
+        incentive_multiplier: 200_000_000,
+        frozen_assets: vec![&env, token.clone()],
+    };
+    assert_eq!(
+        client.try_set_recovery_mode_config(
+            &admin,
+            &Some(RecoveryConfig {
+                exit_ratio: 170,
+                ..config.clone()
+            })
+        ),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    client.set_recovery_mode_config(&admin, &Some(config.clone()));
+    assert_eq!(client.get_recovery_mode_config(), Some(config));
+    assert!(!client.is_recovery_mode());
+
+    // Collateral worth 140% of debt: borrowing power halves and the asset is frozen
+    client.set_asset_price(&admin, &other_token, &26_000_000);
+    assert!(client.refresh_recovery_mode());
+    assert!(client.is_recovery_mode());
+    assert_eq!(client.get_cross_position_summary(&user).0, 630);
+    assert_eq!(
+        client.try_borrow(&user, &1),
+        Err(Ok(ProtocolError::RecoveryModeRestricted))
+    );
+
+    // The liquidation bonus doubles from 10% to 20%
+    client.liquidate(&liquidator, &user, &100);
+    assert_eq!(client.get_position(&user).0, 1_000 - 120);
+
+    // Leaving takes more than climbing back over the entry threshold
+    client.set_asset_price(&admin, &other_token, &60_000_000);
+    assert_eq!(client.get_global_collateral_ratio(), 185);
+    assert!(client.refresh_recovery_mode());
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+    assert_eq!(client.get_global_collateral_ratio(), 235);
+    assert!(!client.refresh_recovery_mode());
+    client.set_asset_price(&admin, &other_token, &80_000_000);
+    client.borrow(&user, &1);
+}
+
+#[test]
+fn test_keepers_and_core_flows_extend_ttls() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let instance_ttl = || env.as_contract(&contract_id, || env.storage().instance().get_ttl());
+    let position_ttl = || {
+        env.as_contract(&contract_id, || {
+            env.storage()
+                .persistent()
+                .get_ttl(&DataKey::Position(user.clone()))
+        })
+    };
+
+    // A deposit tops up an instance close to archival, and the position it writes
+    assert!(instance_ttl() < TTL_THRESHOLD);
+    client.deposit_collateral(&user, &1_000);
+    assert_eq!(instance_ttl(), TTL_EXTEND_TO);
+    assert_eq!(position_ttl(), TTL_EXTEND_TO);
+
+    env.ledger().with_mut(|li| li.sequence_number += 1_000);
+    let live_until = env.ledger().sequence() + TTL_EXTEND_TO;
+    assert_eq!(client.bump_core_state(), live_until);
+    assert_eq!(instance_ttl(), TTL_EXTEND_TO);
+
+    let stranger = Address::generate(&env);
+    assert_eq!(
+        client.bump_entries(&vec![
+            &env,
+            StateEntry::Position(user.clone()),
+            StateEntry::Position(stranger),
+        ]),
+        vec![&env, live_until, 0]
+    );
+    // The persistent entry itself was extended to the ledger reported
+    assert_eq!(env.ledger().sequence() + position_ttl(), live_until);
+    assert_eq!(
+        client.try_bump_entries(&Vec::new(&env)),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+}
+
+#[test]
+fn test_previews_match_executed_flows() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let preview = client.preview_deposit(&user, &3_000);
+    assert_eq!(preview.amount, 3_000);
+    assert_eq!(preview.shares, 3_000);
+    assert_eq!(preview.collateral_after, 3_000);
+    client.deposit_collateral(&user, &3_000);
+    assert_eq!(client.balance(&user), preview.shares);
+
+    let preview = client.preview_borrow(&user, &1_000);
+    assert_eq!((preview.debt_after, preview.collateral_ratio), (1_000, 300));
+    assert_eq!(
+        client.try_preview_borrow(&user, &2_500),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    client.borrow(&user, &1_000);
+
+    // A year on, repaying more than is owed pulls only the payoff, interest first
+    env.ledger()
+        .with_mut(|li| li.timestamp += 365 * 24 * 60 * 60);
+    let preview = client.preview_repay(&user, &5_000);
+    assert!(preview.interest > 0);
+    assert_eq!(preview.amount, 1_000 + preview.interest);
+    assert_eq!(preview.debt_after, 0);
+    let repaid = client.preview_repay(&user, &100);
+    assert_eq!(
+        (repaid.amount, repaid.interest),
+        (100, 100.min(preview.interest))
+    );
+    client.repay(&user, &5_000);
+    assert_eq!(client.get_position(&user).1, 0);
+
+    let preview = client.preview_withdraw(&user, &1_000);
+    assert_eq!((preview.amount, preview.shares), (1_000, 1_000));
+    client.withdraw(&user, &1_000);
+    assert_eq!(client.get_position(&user).0, preview.collateral_after);
+    assert_eq!(
+        client.try_preview_withdraw(&user, &1_000_000),
+        Err(Ok(ProtocolError::InsufficientCollateral))
+    );
+}
+
+#[test]
+fn test_signed_price_payloads_update_feeds_for_a_fee() {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 1_000);
+    });
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+
+    let provider = SigningKey::from_bytes(&[7; 32]);
+    let signer = BytesN::from_array(&env, &provider.verifying_key().to_bytes());
+    let sign = |price: i128, publish_time: u64| {
+        let mut payload = PricePayload {
+            asset: other_token.clone(),
+            price,
+            confidence: 1_000_000,
+            publish_time,
+            signer: signer.clone(),
+            signature: BytesN::from_array(&env, &[0; 64]),
+        };
+        let message = env.as_contract(&contract_id, || PricePush::message(&env, &payload));
+        let mut buf = [0u8; 256];
+        let len = message.len() as usize;
+        message.copy_into_slice(&mut buf[..len]);
+        payload.signature = BytesN::from_array(&env, &provider.sign(&buf[..len]).to_bytes());
+        payload
+    };
+
+    assert_eq!(
+        client.try_update_price_feeds(&user, &vec![&env, sign(200_000_000, 990)]),
+        Err(Ok(ProtocolError::ConfigurationError))
+    );
+    client.set_price_push_config(
+        &admin,
+        &Some(PricePushConfig {
+            signers: vec![&env, signer.clone()],
+            update_fee: 10,
+        }),
+    );
+
+    // The pushed price values collateral and the submitter pays the fee
+    let token_client = MockTokenClient::new(&env, &token);
+    let before = token_client.balance(&user);
+    assert_eq!(
+        client.update_price_feeds(&user, &vec![&env, sign(200_000_000, 990)]),
+        10
+    );
+    assert_eq!(token_client.balance(&user), before - 10);
+    assert_eq!(client.get_asset_confidence(&other_token), 1_000_000);
+    client.deposit_collateral_asset(&user, &other_token, &1_000);
+    assert_eq!(client.get_cross_position_summary(&user).0, 2_000);
+
+    // Replayed, stale and unregistered payloads are refused
+    assert_eq!(
+        client.try_update_price_feeds(&user, &vec![&env, sign(200_000_000, 990)]),
+        Err(Ok(ProtocolError::OracleStale))
+    );
+    assert_eq!(
+        client.try_update_price_feeds(&user, &vec![&env, sign(200_000_000, 500)]),
+        Err(Ok(ProtocolError::OracleStale))
+    );
+    let mut forged = sign(300_000_000, 995);
+    forged.signer = BytesN::from_array(&env, &[1; 32]);
+    assert_eq!(
+        client.try_update_price_feeds(&user, &vec![&env, forged]),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+
+    // A tampered price fails signature verification
+    let mut tampered = sign(300_000_000, 995);
+    tampered.price = 900_000_000;
+    assert!(client
+        .try_update_price_feeds(&user, &vec![&env, tampered])
+        .is_err());
+}
+
+#[test]
+fn test_freeze_levels_restrict_accounts_proportionally() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&user, &3_000);
+    client.borrow(&user, &1_000);
+
+    // Withdraw-only accounts can wind their position down but not grow it
+    client.set_freeze_level(&admin, &user, &FreezeLevel::WithdrawOnly, &5_000);
+    assert_eq!(client.get_compliance_summary().frozen, 1);
+    assert_eq!(
+        client.try_deposit_collateral(&user, &100),
+        Err(Ok(ProtocolError::UserSuspended))
+    );
+    assert_eq!(
+        client.try_borrow(&user, &100),
+        Err(Ok(ProtocolError::UserSuspended))
+    );
+    client.repay(&user, &500);
+    client.withdraw(&user, &500);
+
+    // Halted accounts can do nothing
+    client.set_freeze_level(&admin, &user, &FreezeLevel::Halted, &5_000);
+    assert_eq!(
+        client.try_repay(&user, &100),
+        Err(Ok(ProtocolError::UserSuspended))
+    );
+    assert_eq!(
+        client.get_full_user_state(&user).freeze,
+        FreezeLevel::Halted
+    );
+
+    // The freeze lapses at its review expiry
+    env.ledger().with_mut(|li| li.timestamp = 5_000);
+    assert_eq!(client.get_full_user_state(&user).freeze, FreezeLevel::None);
+    client.borrow(&user, &100);
+
+    assert_eq!(
+        client.try_set_freeze_level(&admin, &user, &FreezeLevel::Halted, &4_000),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    client.set_freeze_level(&admin, &user, &FreezeLevel::None, &0);
+    assert_eq!(client.get_compliance_summary().frozen, 0);
+}
+
+#[test]
+fn test_thin_markets_borrow_at_a_fixed_rate() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let borrow_rate = || {
+        env.as_contract(&contract_id, || {
+            InterestRateStorage::update_state(&env).current_borrow_rate
+        })
+    };
+
+    let thin = ThinMarketRate {
+        min_liquidity: 5_000,
+        fixed_rate: 3_000_000,
+    };
+    client.set_thin_market_rate(&admin, &token, &Some(thin.clone()));
+    assert_eq!(client.get_thin_market_rate(&token), Some(thin));
+
+    // Dust supply borrows at the fixed rate however hard it is utilized
+    client.deposit_collateral(&user, &1_000);
+    client.borrow(&user, &600);
+    assert_eq!(borrow_rate(), 3_000_000);
+
+    // Past the liquidity minimum the kinked model prices the market again
+    client.deposit_collateral(&user, &9_000);
+    env.as_contract(&contract_id, || {
+        let state = InterestRateStorage::update_state(&env);
+        let config = InterestRateStorage::get_config(&env);
+        assert_eq!(
+            state.current_borrow_rate,
+            InterestRateManager::calculate_borrow_rate(&config, state.smoothed_utilization)
+        );
+    });
+
+    assert_eq!(
+        client.try_set_thin_market_rate(
+            &admin,
+            &token,
+            &Some(ThinMarketRate {
+                min_liquidity: 0,
+                fixed_rate: 3_000_000,
+            })
+        ),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    client.set_thin_market_rate(&admin, &token, &None);
+    assert_eq!(client.get_thin_market_rate(&token), None);
+}
+
+#[test]
+fn test_emergency_withdrawals_need_signers_and_survive_a_veto_window() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[]);
+    let client = ContractClient::new(&env, &contract_id);
+    env.as_contract(&contract_id, || {
+        RevenueManager::record_reserve(&env, &token, 5_000)
+    });
+    let (alice, bob, carol) = (
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    let guardian = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    client.set_emergency_manager(&admin, &guardian, &true);
+    client.set_emergency_signers(
+        &admin,
+        &vec![&env, alice.clone(), bob.clone(), carol.clone()],
+        &2,
+    );
+    let incident = String::from_str(&env, "INC-7");
+
+    assert_eq!(
+        client.try_propose_emergency_withdrawal(&admin, &token, &1_000, &recipient, &incident),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_propose_emergency_withdrawal(
+            &alice,
+            &token,
+            &1_000,
+            &recipient,
+            &String::from_str(&env, "")
+        ),
+        Err(Ok(ProtocolError::InvalidInput))
+    );
+
+    // One confirmation is not enough, and the delay runs from the threshold
+    let id = client.propose_emergency_withdrawal(&alice, &token, &1_000, &recipient, &incident);
+    assert_eq!(
+        client.try_execute_emergency_withdrawal(&id),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+    assert_eq!(
+        client.try_confirm_emergency_withdrawal(&alice, &id),
+        Err(Ok(ProtocolError::AlreadyExists))
+    );
+    client.confirm_emergency_withdrawal(&bob, &id);
+    let withdrawal = client.get_emergency_withdrawal(&id).unwrap();
+    assert_eq!(withdrawal.executable_at, 1_000 + 24 * 60 * 60);
+    assert_eq!(
+        client.try_execute_emergency_withdrawal(&id),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp += 24 * 60 * 60);
+    assert_eq!(client.execute_emergency_withdrawal(&id), 1_000);
+    assert_eq!(
+        MockTokenClient::new(&env, &token).balance(&recipient),
+        1_000
+    );
+    assert_eq!(client.get_treasury_reconciliation(&token).pending, 4_000);
+
+    // Any guardian can stop a withdrawal during its delay
+    let id = client.propose_emergency_withdrawal(&bob, &token, &4_000, &recipient, &incident);
+    client.confirm_emergency_withdrawal(&carol, &id);
+    client.veto_emergency_withdrawal(&guardian, &id);
+    env.ledger().with_mut(|li| li.timestamp += 24 * 60 * 60);
+    assert_eq!(
+        client.try_execute_emergency_withdrawal(&id),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+}
+
+#[test]
+fn test_listing_fee_and_proposer_revenue_share() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let proposer = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, stable) =
+        TestUtils::setup_contract_with_token(&env, &[proposer.clone()]);
+    let client = ContractClient::new(&env, &contract_id);
+    let stable_client = MockTokenClient::new(&env, &stable);
+    let listed = env.register(MockToken, ());
+    let declined = Address::generate(&env);
+    env.as_contract(&listed, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), contract_id.clone(), 10_000);
+    });
+
+    assert_eq!(
+        client.try_propose_market_listing(&proposer, &listed, &50_000_000),
+        Err(Ok(ProtocolError::ConfigurationError))
+    );
+    client.set_listing_config(
+        &admin,
+        &Some(ListingConfig {
+            fee_asset: stable.clone(),
+            fee: 500,
+            proposer_share_bps: 500,
+            share_months: 3,
+        }),
+    );
+
+    let id = client.propose_market_listing(&proposer, &listed, &50_000_000);
+    let declined_id = client.propose_market_listing(&proposer, &declined, &50_000_000);
+    assert_eq!(stable_client.balance(&proposer), 1_000_000 - 1_000);
+    client.reject_market_listing(&admin, &declined_id);
+    assert_eq!(stable_client.balance(&proposer), 1_000_000 - 500);
+    assert_eq!(
+        client.try_approve_market_listing(&admin, &declined_id),
+        Err(Ok(ProtocolError::InvalidOperation))
+    );
+
+    client.approve_market_listing(&admin, &id);
+    assert_eq!(
+        client.get_market_listing(&id).unwrap().status,
+        listing::ListingStatus::Approved
+    );
+    assert_eq!(client.get_treasury_reconciliation(&stable).accrued, 500);
+    assert_eq!(
+        client.try_propose_market_listing(&proposer, &listed, &50_000_000),
+        Err(Ok(ProtocolError::AlreadyExists))
+    );
+
+    env.as_contract(&contract_id, || {
+        RevenueManager::record_reserve(&env, &listed, 1_000)
+    });
+    assert_eq!(client.get_treasury_reconciliation(&listed).accrued, 950);
+    assert_eq!(
+        client.get_listing_revenue_share(&listed).unwrap().accrued,
+        50
+    );
+    assert_eq!(
+        client.try_claim_listing_revenue(&admin, &listed),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+    assert_eq!(client.claim_listing_revenue(&proposer, &listed), 50);
+    assert_eq!(MockTokenClient::new(&env, &listed).balance(&proposer), 50);
+    assert_eq!(
+        client.try_claim_listing_revenue(&proposer, &listed),
+        Err(Ok(ProtocolError::InvalidAmount))
+    );
+
+    // Reserves credited after the share period stay with the treasury
+    env.ledger()
+        .with_mut(|li| li.timestamp += 3 * listing::LISTING_SHARE_MONTH);
+    env.as_contract(&contract_id, || {
+        RevenueManager::record_reserve(&env, &listed, 1_000)
+    });
+    assert_eq!(client.get_treasury_reconciliation(&listed).accrued, 1_950);
+    assert_eq!(
+        client.get_listing_revenue_share(&listed).unwrap().accrued,
+        50
+    );
+}
+
+#[test]
+fn test_targeted_liquidation_repays_only_what_restores_health() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &2000);
+    client.borrow(&user, &1000);
+    client.set_min_collateral_ratio(&admin, &210);
+
+    assert_eq!(
+        client.try_set_liquidation_target_hf(&admin, &Some(100)),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+    assert_eq!(
+        client
+            .simulate_liquidation(&user, &token, &token, &500)
+            .repay_amount,
+        500
+    );
+
+    // A 1.05 target at a 210% minimum needs a 220% ratio: (2000 - 1.1x) / (1000 - x)
+    client.set_liquidation_target_hf(&admin, &Some(105));
+    assert_eq!(client.get_liquidation_target_hf(), Some(105));
+    let preview = client.simulate_liquidation(&user, &token, &token, &500);
+    assert_eq!(preview.repay_amount, 182);
+    assert_eq!(
+        client
+            .simulate_liquidation(&user, &token, &token, &100)
+            .repay_amount,
+        100
+    );
+
+    client.liquidate(&liquidator, &user, &500);
+    let (collateral, debt, _) = client.get_position(&user);
+    assert_eq!(debt, 1000 - 182);
+    assert!(collateral * 100 / debt >= 220);
+    assert_eq!(
+        client.try_liquidate(&liquidator, &user, &100),
+        Err(Ok(ProtocolError::NotEligibleForLiquidation))
+    );
+}
+
+#[test]
+fn test_protocol_history_keeps_one_checkpoint_per_day() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 86400 + 100);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let other = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), other.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &other);
+    let client = ContractClient::new(&env, &contract_id);
+    assert_eq!(client.get_protocol_history(&0).len(), 0);
+
+    client.deposit_collateral(&user, &2_000);
+    client.borrow(&user, &500);
+    let history = client.get_protocol_history(&0);
+    assert_eq!(history.len(), 1);
+    let first = history.get(0).unwrap();
+    assert_eq!(first.day, 1);
+    assert_eq!(first.total_debt, 500);
+    assert_eq!(first.total_users, 1);
+    assert!(first.total_value_locked >= 2_000);
+
+    env.ledger().with_mut(|li| li.timestamp += 86400);
+    client.deposit_collateral(&other, &1_000);
+    let history = client.get_protocol_history(&0);
+    assert_eq!(history.len(), 2);
+    let second = history.get(1).unwrap();
+    assert_eq!(second.day, 2);
+    assert_eq!(second.total_users, 2);
+    assert!(second.total_value_locked > first.total_value_locked);
+    assert_eq!(client.get_protocol_history(&1), vec![&env, second]);
+
+    // The ring buffer drops the oldest days once full
+    for _ in 0..analytics::MAX_PROTOCOL_CHECKPOINTS {
+        env.ledger().with_mut(|li| li.timestamp += 86400);
+        client.deposit_collateral(&other, &1);
+    }
+    let history = client.get_protocol_history(&0);
+    assert_eq!(history.len(), analytics::MAX_PROTOCOL_CHECKPOINTS);
+    assert_eq!(history.get(0).unwrap().day, 3);
+}
+
+#[test]
+fn test_rate_setters_emit_config_updated_with_old_and_new_values() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[]);
+    let client = ContractClient::new(&env, &contract_id);
+    let config_updates = || {
+        let mut updates = Vec::new(&env);
+        for (_, topics, data) in env.events().all().iter() {
+            let name = Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap();
+            if name == Symbol::new(&env, "config_updated") {
+                let (_, caller, _, old, _, new) =
+                    <(Symbol, Address, Symbol, i128, Symbol, i128)>::try_from_val(&env, &data)
+                        .unwrap();
+                let param = Symbol::try_from_val(&env, &topics.get(1).unwrap()).unwrap();
+                updates.push_back((param, caller, old, new));
+            }
+        }
+        updates
+    };
+
+    let old_base_rate = InterestRateConfig::default().base_rate;
+    client.set_base_rate(&admin, &3_000_000);
+    assert_eq!(
+        config_updates(),
+        vec![
+            &env,
+            (
+                Symbol::new(&env, "base_rate"),
+                admin.clone(),
+                old_base_rate,
+                3_000_000
+            )
+        ]
+    );
+
+    client.set_interest_cap(&admin, &500);
+    client.set_interest_cap(&admin, &700);
+    assert_eq!(
+        config_updates(),
+        vec![
+            &env,
+            (
+                Symbol::new(&env, "interest_cap_bps"),
+                admin.clone(),
+                500,
+                700
+            )
+        ]
+    );
+}
+
+#[test]
+fn test_oracle_fails_over_to_secondary_then_fallback() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[]);
+    let client = ContractClient::new(&env, &contract_id);
+    let asset = env.register(MockToken, ());
+    let primary = env.register(MockOracle, ());
+    let secondary = env.register(MockOracle, ());
+    MockOracleClient::new(&env, &primary).set_feed(&100_000_000, &0);
+    MockOracleClient::new(&env, &secondary).set_feed(&102_000_000, &1_000_000);
+    client.set_asset_params(&admin, &asset, &100_000_000, &true);
+    client.set_oracle_source(&admin, &asset, &primary, &1);
+    client.set_asset_oracle_config(
+        &admin,
+        &asset,
+        &Some(AssetOracleConfig {
+            heartbeat: 300,
+            max_deviation_bps: 1_000,
+            fallback_price: 95_000_000,
+        }),
+    );
+    let served_tier = || {
+        env.events().all().iter().find_map(|(_, topics, data)| {
+            let name = Symbol::try_from_val(&env, &topics.get(0).unwrap()).ok()?;
+            (name == Symbol::new(&env, "price_served"))
+                .then(|| <(PriceTier, i128, i128)>::try_from_val(&env, &data).unwrap())
+        })
+    };
+
+    client.sync_asset_price(&asset);
+    assert_eq!(served_tier(), Some((PriceTier::Primary, 100_000_000, 0)));
+
+    // An out-of-bounds primary is passed over for the secondary feed
+    env.ledger().with_mut(|li| li.timestamp = 1_200);
+    client.set_secondary_oracle(&admin, &asset, &Some(secondary.clone()));
+    assert_eq!(client.get_secondary_oracle(&asset), Some(secondary));
+    MockOracleClient::new(&env, &primary).set_feed(&150_000_000, &0);
+    assert_eq!(client.sync_asset_price(&asset), (102_000_000, 1_000_000));
+    assert_eq!(
+        served_tier(),
+        Some((PriceTier::Secondary, 102_000_000, 1_000_000))
+    );
+
+    // ... as is a stale one
+    env.ledger().with_mut(|li| li.timestamp = 1_400);
+    MockOracleClient::new(&env, &primary).set_feed(&101_000_000, &0);
+    client.sync_asset_price(&asset);
+    assert_eq!(
+        served_tier(),
+        Some((PriceTier::Secondary, 102_000_000, 1_000_000))
+    );
+
+    // With every feed stale the static fallback price is served
+    env.ledger().with_mut(|li| li.timestamp = 1_600);
+    assert_eq!(client.sync_asset_price(&asset), (95_000_000, 0));
+    assert_eq!(served_tier(), Some((PriceTier::Fallback, 95_000_000, 0)));
+}
+
+#[test]
+fn test_revenue_is_attributed_to_its_source_asset() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (_admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[]);
+    let client = ContractClient::new(&env, &contract_id);
+    let other = Address::generate(&env);
+    assert_eq!(client.get_asset_revenue(&token), AssetRevenue::default());
+
+    env.as_contract(&contract_id, || {
+        RevenueManager::record_interest(&env, 100);
+        RevenueManager::record_fees(&env, 30);
+        RevenueManager::record_reserve(&env, &token, 20);
+        RevenueManager::record_liquidation_fee(&env, &token, 7, 7);
+        RevenueManager::record_liquidation_remainder(&env, &other, 3);
+    });
+
+    assert_eq!(
+        client.get_asset_revenue(&token),
+        AssetRevenue {
+            interest: 100,
+            fees: 50,
+            liquidations: 7,
+        }
+    );
+    let by_asset = client.get_revenue_by_asset();
+    assert_eq!(by_asset.len(), 2);
+    assert_eq!(by_asset.get(other).unwrap().liquidations, 3);
+    // Aggregate metrics still count interest alongside other fees
+    assert_eq!(client.get_revenue_metrics().total_fees, 130);
+}
+
+#[test]
+fn test_external_calls_respect_allowlist_and_call_depth() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let initiator = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token_id) =
+        TestUtils::setup_contract_with_token(&env, &[initiator.clone()]);
+    let client = ContractClient::new(&env, &contract_id);
+    let receiver = env.register(FlashLoanReceiver, ());
+    let flash_loan = || {
+        env.as_contract(&contract_id, || {
+            FlashLoan::execute(&env, &initiator, &token_id, 100, 10, &receiver)
+        })
+    };
+
+    // Targets off the allowlist are refused before any effect
+    client.set_external_call_allowlist(&admin, &Some(vec![&env, Address::generate(&env)]));
+    assert_eq!(flash_loan(), Err(ProtocolError::Unauthorized));
+
+    client.set_external_call_allowlist(&admin, &Some(vec![&env, receiver.clone()]));
+    assert_eq!(flash_loan(), Ok(()));
+    assert_eq!(
+        client.get_external_call_allowlist(),
+        Some(vec![&env, receiver.clone()])
+    );
+    assert_eq!(
+        client.try_set_external_call_allowlist(&initiator, &None),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+
+    // While a call is in progress, guarded entry and nested calls are both rejected
+    env.as_contract(&contract_id, || {
+        assert_eq!(ExternalCallStorage::get_depth(&env), 0);
+        ExternalCallStorage::put_depth(&env, 1);
+        assert_eq!(
+            ReentrancyGuard::enter(&env),
+            Err(ProtocolError::ReentrancyDetected)
+        );
+        assert_eq!(
+            ExternalCall::invoke::<(), _>(&env, &receiver, "on_flash_loan", vec![&env], || {
+                Ok(())
+            }),
+            Err(ProtocolError::ReentrancyDetected)
+        );
+        ExternalCallStorage::put_depth(&env, 0);
+    });
+}
+
+#[test]
+fn test_normalize_amount_to_asset_decimals() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    // The mock token has 7 decimals
+    let (_admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[]);
+    let client = ContractClient::new(&env, &contract_id);
+
+    assert_eq!(client.normalize_amount(&token, &1_500_000, &6), 15_000_000);
+    assert_eq!(client.normalize_amount(&token, &15_000_000, &7), 15_000_000);
+    assert_eq!(
+        client.normalize_amount(&token, &123_456_780, &8),
+        12_345_678
+    );
+    assert_eq!(
+        client.try_normalize_amount(&token, &123_456_789, &8),
+        Err(Ok(ProtocolError::AmountPrecisionLoss))
+    );
+    assert_eq!(
+        client.try_normalize_amount(&token, &(i128::MAX / 10), &0),
+        Err(Ok(ProtocolError::AmountOutOfRange))
+    );
+    assert_eq!(
+        client.try_normalize_amount(&token, &1, &19),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+
+    // A 7-decimal value sent to a 6-decimal market is refused unless it converts exactly
+    assert_eq!(
+        Units::rescale(1_234_567, 7, 6),
+        Err(ProtocolError::AmountPrecisionLoss)
+    );
+    assert_eq!(Units::rescale(1_234_560, 7, 6), Ok(123_456));
+}
+
+#[test]
+fn test_emission_bands_follow_utilization() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+
+    let epochs = Vec::from_array(
+        &env,
+        [EmissionEpoch {
+            start: 1_000,
+            end: 10_000,
+            rate_per_second: 100,
+        }],
+    );
+    client.set_emission_schedule(&admin, &token, &RewardSide::Borrow, &epochs);
+    // Borrow incentives double below 50% utilization and stop above it
+    let bands = Vec::from_array(
+        &env,
+        [
+            UtilizationBand {
+                max_utilization: 50_000_000,
+                multiplier_bps: 20_000,
+            },
+            UtilizationBand {
+                max_utilization: 100_000_000,
+                multiplier_bps: 0,
+            },
+        ],
+    );
+    client.set_emission_bands(&admin, &token, &RewardSide::Borrow, &bands);
+    assert_eq!(
+        client.get_emission_bands(&token, &RewardSide::Borrow),
+        bands
+    );
+
+    client.deposit_collateral(&user, &3_000);
+    client.borrow(&user, &1_000);
+    assert_eq!(
+        client
+            .get_current_epoch_emissions(&token)
+            .borrow_rate_per_second,
+        200
+    );
+    env.ledger().with_mut(|li| li.timestamp = 1_100);
+    assert_eq!(client.get_pending_rewards(&user, &token), 20_000);
+
+    // Pushing utilization past the band switches borrow incentives off from then on
+    client.borrow(&user, &700);
+    assert_eq!(
+        client
+            .get_current_epoch_emissions(&token)
+            .borrow_rate_per_second,
+        0
+    );
+    env.ledger().with_mut(|li| li.timestamp = 1_200);
+    assert_eq!(client.get_pending_rewards(&user, &token), 20_000);
+
+    let unordered = Vec::from_array(
+        &env,
+        [
+            UtilizationBand {
+                max_utilization: 80_000_000,
+                multiplier_bps: 10_000,
+            },
+            UtilizationBand {
+                max_utilization: 20_000_000,
+                multiplier_bps: 10_000,
+            },
+        ],
+    );
+    assert_eq!(
+        client.try_set_emission_bands(&admin, &token, &RewardSide::Supply, &unordered),
+        Err(Ok(ProtocolError::InvalidParameters))
+    );
+}
+
+#[test]
+fn test_every_event_is_preceded_by_its_sequence_number() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let other = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), other.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &other);
+    let client = ContractClient::new(&env, &contract_id);
+    let before = client.get_last_event_seq();
+    let seq_topic = Symbol::new(&env, "event_seq");
+
+    client.deposit_collateral(&user, &1_000);
+    let events = env.events().all();
+    assert!(!events.is_empty() && events.len() % 2 == 0);
+    let mut expected = before;
+    for index in (0..events.len()).step_by(2) {
+        let (_, topics, data) = events.get(index).unwrap();
+        expected += 1;
+        assert_eq!(topics, vec![&env, seq_topic.into_val(&env)]);
+        assert_eq!(u64::try_from_val(&env, &data).unwrap(), expected);
+        // The numbered event keeps its own topics
+        let (_, topics, _) = events.get(index + 1).unwrap();
+        assert!(u64::try_from_val(&env, &topics.last().unwrap()).is_err());
+    }
+    assert_eq!(client.get_last_event_seq(), expected);
+
+    // Receipt token transfers keep the standard SEP-41 layout and are not numbered
+    client.transfer(&user, &other, &100);
+    let transfer = Symbol::new(&env, "transfer");
+    let (_, topics, data) = env
+        .events()
+        .all()
+        .iter()
+        .find(|(_, topics, _)| {
+            Symbol::try_from_val(&env, &topics.get(0).unwrap()).is_ok_and(|topic| topic == transfer)
+        })
+        .unwrap();
+    assert_eq!(
+        topics,
+        vec![
+            &env,
+            Symbol::new(&env, "transfer").into_val(&env),
+            user.into_val(&env),
+            other.into_val(&env),
+        ]
+    );
+    assert_eq!(i128::try_from_val(&env, &data).unwrap(), 100);
+}
+
+#[test]
+fn test_watchlist_tracks_accounts_near_liquidation() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let other = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), other.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &other);
+    let client = ContractClient::new(&env, &contract_id);
+
+    // 200% collateralized against a 150% minimum: health factor 133
+    client.deposit_collateral(&user, &2_000);
+    client.borrow(&user, &1_000);
+    client.deposit_collateral(&other, &2_000);
+    assert_eq!(client.get_watchlist(&0, &10).len(), 0);
+
+    // 166%: health factor 111, below the 1.2 watch threshold
+    client.borrow(&user, &200);
+    assert_eq!(client.get_watchlist(&0, &10), vec![&env, user.clone()]);
+    assert_eq!(client.get_watchlist(&1, &10).len(), 0);
+
+    // Repaying back above the threshold drops the account again
+    client.repay(&user, &500);
+    assert_eq!(client.get_watchlist(&0, &10).len(), 0);
+}
+
+#[test]
+fn test_market_pauses_stop_single_flows_but_never_repay() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.deposit_collateral(&user, &2_000);
+    client.borrow(&user, &500);
+
+    // Only the borrow side is risky: suppliers can still come and go, borrowers repay
+    let borrow_paused = MarketPauses {
+        borrow: true,
+        ..MarketPauses::default()
+    };
+    client.set_market_pauses(&admin, &token, &borrow_paused);
+    assert_eq!(client.get_market_pauses(&token), borrow_paused);
+    assert_eq!(
+        client.try_borrow(&user, &100),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+    client.deposit_collateral(&user, &100);
+    client.withdraw(&user, &100);
+    client.repay(&user, &200);
+
+    // Not even a protocol-wide pause stops repayment
+    client.trigger_emergency_pause(&admin, &None);
+    assert_eq!(
+        client.try_deposit_collateral(&user, &100),
+        Err(Ok(ProtocolError::ProtocolPaused))
+    );
+    client.repay(&user, &100);
+    client.resume_operations(&admin);
+
+    assert_eq!(
+        client.try_set_market_pauses(&user, &token, &MarketPauses::default()),
+        Err(Ok(ProtocolError::Unauthorized))
+    );
+
+    client.set_market_pauses(&admin, &token, &MarketPauses::default());
+    client.borrow(&user, &100);
+}
+
+#[test]
+fn test_primary_seizure_cannot_exceed_primary_collateral() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let liquidator = TestUtils::create_user_address(&env, 1);
+    let (admin, contract_id, _token) =
+        TestUtils::setup_contract_with_token(&env, &[user.clone(), liquidator.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    TestUtils::verify_user(&env, &contract_id, &admin, &liquidator);
+    let other_token = env.register(MockToken, ());
+    env.as_contract(&other_token, || {
+        MockToken::initialize(env.clone(), admin.clone());
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+
+    // Most of the debt is backed by the other asset, so little primary collateral is held
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_asset_params(&admin, &other_token, &100_000_000, &true);
+    client.set_asset_price(&admin, &other_token, &100_000_000);
+    client.set_min_collateral_ratio(&admin, &50);
+    client.deposit_collateral(&user, &100);
+    client.deposit_collateral_asset(&user, &other_token, &2_000);
+    client.borrow(&user, &1_500);
+    client.set_min_collateral_ratio(&admin, &150);
+
+    // 500 repaid plus the bonus is worth more than the 100 primary units on hand
+    assert_eq!(
+        client.try_liquidate(&liquidator, &user, &500),
+        Err(Ok(ProtocolError::InsufficientCollateral))
+    );
+    assert_eq!(client.get_position(&user).0, 100);
+
+    client.liquidate(&liquidator, &user, &90);
+    assert_eq!(client.get_position(&user).0, 1);
+}
+
+#[test]
+fn test_receipt_reports_primary_asset_decimals() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = TestUtils::create_admin_address(&env);
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let config = TestUtils::init_config(&env);
+    env.as_contract(&config.native_asset, || {
+        MockToken::set_decimals(env.clone(), 6)
+    });
+
+    client.initialize(&admin, &config);
+    assert_eq!(client.decimals(), 6);
+
+    // A token claiming more precision than amounts can carry is refused as primary asset
+    let too_precise = env.register(MockToken, ());
+    env.as_contract(&too_precise, || MockToken::set_decimals(env.clone(), 19));
+    assert_eq!(
+        client.try_set_primary_asset(&admin, &too_precise),
+        Err(Ok(ProtocolError::AssetNotSupported))
+    );
+    assert_eq!(client.decimals(), 6);
+}
+
+#[test]
+fn test_withdrawal_after_supply_fee_is_limited_to_what_remains() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    client.set_supply_fee(
+        &admin,
+        &Some(SupplyFeeConfig {
+            annual_rate: 10_000_000,
+            floor: 100_000,
+        }),
+    );
+    client.deposit_collateral(&user, &200_000);
+    env.ledger()
+        .with_mut(|li| li.timestamp += 365 * 24 * 60 * 60);
+
+    // The year's fee moved 10,000 to reserves, so the pre-fee balance is no longer there
+    assert_eq!(
+        client.try_withdraw(&user, &200_000),
+        Err(Ok(ProtocolError::InsufficientCollateral))
+    );
+    let before = MockTokenClient::new(&env, &token).balance(&user);
+    client.withdraw(&user, &190_000);
+    assert_eq!(client.get_position(&user).0, 0);
+    assert_eq!(
+        MockTokenClient::new(&env, &token).balance(&user),
+        before + 190_000
+    );
+    assert_eq!(client.get_treasury_reconciliation(&token).accrued, 10_000);
+}
+
+#[test]
+fn test_migration_keeps_the_withdrawal_health_buffer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let user = TestUtils::create_user_address(&env, 0);
+    let (admin, contract_id, _token) = TestUtils::setup_contract_with_token(&env, &[user.clone()]);
+    TestUtils::verify_user(&env, &contract_id, &admin, &user);
+    let client = ContractClient::new(&env, &contract_id);
+    let volatile_token = env.register(MockToken, ());
+    let stable_token = env.register(MockToken, ());
+    let dex = env.register(MockDex, ());
+    env.as_contract(&stable_token, || {
+        MockToken::mint(env.clone(), user.clone(), 10_000);
+    });
+    for (asset, factor) in [
+        (volatile_token.clone(), 50_000_000),
+        (stable_token.clone(), 90_000_000),
+    ] {
+        client.set_asset_params(&admin, &asset, &factor, &true);
+        client.set_asset_price(&admin, &asset, &100_000_000);
+    }
+    client.set_dex_adapter(&admin, &dex, &true);
+
+    // 100 primary + 900 weighted stable against 450 of debt
+    client.deposit_collateral(&user, &100);
+    client.deposit_collateral_asset(&user, &stable_token, &1_000);
+    client.borrow(&user, &450);
+    client.set_health_factor_buffer(&admin, &2_000);
+
+    // Swapping half into the 50% asset leaves 177%: above the 150% minimum, below 180%
+    assert_eq!(
+        client.try_migrate_collateral(&user, &stable_token, &volatile_token, &500, &dex, &500),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    client.set_health_factor_buffer(&admin, &0);
+    assert_eq!(
+        client.migrate_collateral(&user, &stable_token, &volatile_token, &500, &dex, &500),
+        500
+    );
+}
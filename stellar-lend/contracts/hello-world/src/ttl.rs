@@ -0,0 +1,113 @@
+//! TTL module for StellarLend protocol
+//! Keeps contract state ahead of Soroban state archival. Core flows top up the instance
+//! TTL, and the TTL of the positions they write, as they run. Keepers can extend the
+//! instance and contract code with `bump_core_state`, and the persistent per-user and
+//! per-id entries with `bump_entries`. Every keeper extension reports the ledger the
+//! entry now lives until.
+
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::ProtocolError;
+use soroban_sdk::{contracttype, Address, Env, Symbol, Vec};
+
+/// Ledgers in a day at ~5 second ledger close times
+pub const DAY_IN_LEDGERS: u32 = 17280;
+
+/// TTL every extension tops entries up to (30 days)
+pub const TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+
+/// Remaining TTL below which core flows extend the instance (7 days)
+pub const TTL_THRESHOLD: u32 = 7 * DAY_IN_LEDGERS;
+
+/// Most entries accepted by a single `bump_entries` call
+pub const MAX_BUMP_ENTRIES: u32 = 20;
+
+/// Stored entry a keeper can extend
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum StateEntry {
+    Position(Address),
+    CrossPosition(Address),
+    PositionHistory(Address),
+    UserProfile(Address),
+    UserMarkets(Address),
+    UserAnalytics(Address),
+    Stake(Address),
+    RelayNonce(Address),
+    GovProposal(u64),
+    DebtAuction(u64),
+}
+
+impl StateEntry {
+    fn data_key(&self) -> DataKey {
+        match self {
+            StateEntry::Position(user) => DataKey::Position(user.clone()),
+            StateEntry::CrossPosition(user) => DataKey::CrossPosition(user.clone()),
+            StateEntry::PositionHistory(user) => DataKey::PositionHistory(user.clone()),
+            StateEntry::UserProfile(user) => DataKey::UserProfile(user.clone()),
+            StateEntry::UserMarkets(user) => DataKey::UserMarkets(user.clone()),
+            StateEntry::UserAnalytics(user) => DataKey::UserAnalytics(user.clone()),
+            StateEntry::Stake(user) => DataKey::Stake(user.clone()),
+            StateEntry::RelayNonce(user) => DataKey::RelayNonce(user.clone()),
+            StateEntry::GovProposal(id) => DataKey::GovProposal(*id),
+            StateEntry::DebtAuction(id) => DataKey::DebtAuction(*id),
+        }
+    }
+}
+
+/// State archival housekeeping
+pub struct Ttl;
+
+impl Ttl {
+    fn live_until(env: &Env) -> u32 {
+        env.ledger().sequence() + TTL_EXTEND_TO
+    }
+
+    /// Top up the instance TTL once it falls below the threshold; called on hot paths
+    pub fn extend_instance(env: &Env) {
+        env.storage()
+            .instance()
+            .extend_ttl(TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+
+    /// Top up a persistent entry's TTL once it falls below the threshold; called when the
+    /// entry is written
+    pub(crate) fn extend_entry(env: &Env, key: &DataKey) {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+
+    /// Extend the contract instance, which holds the protocol's state, and the contract
+    /// code to the full TTL; returns the ledger both now live until
+    pub fn bump_core_state(env: &Env) -> u32 {
+        env.deployer()
+            .extend_ttl(env.current_contract_address(), TTL_EXTEND_TO, TTL_EXTEND_TO);
+        let live_until = Self::live_until(env);
+        env.publish_event((Symbol::new(env, "core_state_bumped"),), live_until);
+        live_until
+    }
+
+    /// Extend each persistent entry to the full TTL, returning the ledger it now lives
+    /// until, or zero for an entry that does not exist
+    pub fn bump_entries(env: &Env, entries: &Vec<StateEntry>) -> Result<Vec<u32>, ProtocolError> {
+        if entries.is_empty() || entries.len() > MAX_BUMP_ENTRIES {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        let live_until = Self::live_until(env);
+        let mut results = Vec::new(env);
+        for entry in entries.iter() {
+            let key = entry.data_key();
+            if env.storage().persistent().has(&key) {
+                env.storage()
+                    .persistent()
+                    .extend_ttl(&key, TTL_EXTEND_TO, TTL_EXTEND_TO);
+                env.publish_event((Symbol::new(env, "entry_bumped"), entry), live_until);
+                results.push_back(live_until);
+            } else {
+                results.push_back(0);
+            }
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,60 @@
+//! Units module for StellarLend protocol
+//! Every amount the protocol accepts or reports is an integer in the asset's smallest
+//! unit, as defined by the token's `decimals` (1 XLM = 10^7 stroops, 1 unit of a
+//! 6-decimal stablecoin = 10^6). Integrators holding a value at some other precision
+//! convert it here first. A conversion that would overflow, or drop a fractional part
+//! the market cannot hold, is refused rather than silently rounded.
+
+use crate::ProtocolError;
+use soroban_sdk::{token::TokenClient, Address, Env};
+
+/// Most decimals a token or a converted amount may carry
+pub const MAX_DECIMALS: u32 = 18;
+
+/// Conversions between an asset's smallest unit and other precisions
+pub struct Units;
+
+impl Units {
+    /// Decimals of `asset`, i.e. how many smallest units make one whole token
+    pub fn asset_decimals(env: &Env, asset: &Address) -> Result<u32, ProtocolError> {
+        let decimals = TokenClient::new(env, asset).decimals();
+        if decimals > MAX_DECIMALS {
+            return Err(ProtocolError::AssetNotSupported);
+        }
+        Ok(decimals)
+    }
+
+    /// Convert `amount`, expressed with `decimals` decimal places, into `asset`'s smallest
+    /// unit. Scaling up fails with `AmountOutOfRange` on overflow; scaling down fails with
+    /// `AmountPrecisionLoss` unless the dropped digits are zero.
+    pub fn normalize_amount(
+        env: &Env,
+        asset: &Address,
+        amount: i128,
+        decimals: u32,
+    ) -> Result<i128, ProtocolError> {
+        if decimals > MAX_DECIMALS {
+            return Err(ProtocolError::InvalidParameters);
+        }
+        if amount < 0 {
+            return Err(ProtocolError::InvalidAmount);
+        }
+        let target = Self::asset_decimals(env, asset)?;
+        Self::rescale(amount, decimals, target)
+    }
+
+    /// Express `amount` of smallest units at `to` decimals instead of `from`
+    pub fn rescale(amount: i128, from: u32, to: u32) -> Result<i128, ProtocolError> {
+        if to >= from {
+            amount
+                .checked_mul(10i128.pow(to - from))
+                .ok_or(ProtocolError::AmountOutOfRange)
+        } else {
+            let factor = 10i128.pow(from - to);
+            if amount % factor != 0 {
+                return Err(ProtocolError::AmountPrecisionLoss);
+            }
+            Ok(amount / factor)
+        }
+    }
+}
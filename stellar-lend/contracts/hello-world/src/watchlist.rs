@@ -0,0 +1,93 @@
+//! Watchlist module for StellarLend protocol
+//! Public list of accounts close to liquidation. Whenever a position is saved, its owner
+//! is added if their health factor has dropped below WATCHLIST_HEALTH_FACTOR and removed
+//! once it is back above it or the debt is gone, so keepers only need to follow a small
+//! set of addresses instead of every borrower.
+
+use crate::cross_asset::CrossAssetManager;
+use crate::events::SequencedEvents;
+use crate::storage::DataKey;
+use crate::{Position, ProtocolConfig};
+use soroban_sdk::{Address, Env, Symbol, Vec};
+
+/// Health factor (percent of the minimum ratio) below which an account is watched
+pub const WATCHLIST_HEALTH_FACTOR: i128 = 120;
+
+/// Most addresses returned by one `get_watchlist` page
+pub const MAX_WATCHLIST_PAGE: u32 = 50;
+
+/// Accounts whose last saved position was near liquidation
+pub struct LiquidationWatchlist;
+
+impl LiquidationWatchlist {
+    fn key() -> DataKey {
+        DataKey::LiquidationWatchlist
+    }
+
+    pub fn get(env: &Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&Self::key())
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    fn save(env: &Env, watchlist: &Vec<Address>) {
+        if watchlist.is_empty() {
+            env.storage().instance().remove(&Self::key());
+        } else {
+            env.storage().instance().set(&Self::key(), watchlist);
+        }
+    }
+
+    /// Add or remove the position's owner according to its health factor. A position
+    /// that cannot be priced right now keeps its current membership.
+    pub fn sync(env: &Env, position: &Position) {
+        let owed = position.debt + position.borrow_interest;
+        let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
+        let health_factor = if owed <= 0 || min_ratio <= 0 {
+            None
+        } else {
+            match CrossAssetManager::liquidation_ratio(
+                env,
+                &position.user,
+                position.collateral,
+                owed,
+            ) {
+                Ok(ratio) => Some(ratio * 100 / min_ratio),
+                Err(_) => return,
+            }
+        };
+        let at_risk = health_factor.is_some_and(|hf| hf < WATCHLIST_HEALTH_FACTOR);
+
+        let mut watchlist = Self::get(env);
+        match (at_risk, watchlist.first_index_of(&position.user)) {
+            (true, None) => {
+                watchlist.push_back(position.user.clone());
+                Self::save(env, &watchlist);
+                env.publish_event(
+                    (Symbol::new(env, "watchlist_added"), position.user.clone()),
+                    health_factor.unwrap_or(0),
+                );
+            }
+            (false, Some(index)) => {
+                watchlist.remove(index);
+                Self::save(env, &watchlist);
+                env.publish_event(
+                    (Symbol::new(env, "watchlist_removed"), position.user.clone()),
+                    health_factor.unwrap_or(0),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Watched accounts in the order they were added, `limit` capped at MAX_WATCHLIST_PAGE
+    pub fn page(env: &Env, offset: u32, limit: u32) -> Vec<Address> {
+        let watchlist = Self::get(env);
+        let start = offset.min(watchlist.len());
+        let end = watchlist
+            .len()
+            .min(start.saturating_add(limit.min(MAX_WATCHLIST_PAGE)));
+        watchlist.slice(start..end)
+    }
+}
@@ -1,13 +1,19 @@
 //! Withdraw module for StellarLend protocol
 //! Handles collateral withdrawal functionality and related operations
 
-use crate::analytics::AnalyticsModule;
+use crate::analytics::{ActivityKind, AnalyticsModule};
+use crate::compliance::ComplianceGate;
+use crate::cross_asset::CrossAssetManager;
+use crate::history::PositionHistory;
+use crate::limits::BorrowLimits;
+use crate::notify::Notifications;
+use crate::ttl::Ttl;
 use crate::{
-    EmergencyManager, InterestRateManager, InterestRateStorage, OperationKind, ProtocolConfig,
-    ProtocolError, ProtocolEvent, ReentrancyGuard, RiskConfigStorage, StateHelper,
-    TransferEnforcer, UserManager,
+    ErrorContext, InterestRateManager, InterestRateStorage, OperationKind, ProtocolConfig,
+    ProtocolError, ProtocolEvent, ReentrancyScope, StateHelper, TokenRegistry, TransferEnforcer,
+    UserManager,
 };
-use soroban_sdk::{contracterror, contracttype, Address, Env, String, Symbol};
+use soroban_sdk::{contracterror, contracttype, Address, Env, Symbol};
 
 /// Withdraw-specific errors
 #[contracterror]
@@ -73,26 +79,21 @@ pub struct WithdrawModule;
 impl WithdrawModule {
     /// Withdraw collateral from the protocol
     pub fn withdraw(env: &Env, withdrawer: &Address, amount: i128) -> Result<(), ProtocolError> {
-        ReentrancyGuard::enter(env)?;
+        let _guard = ErrorContext::report(
+            env,
+            ReentrancyScope::enter(env),
+            "withdraw",
+            withdrawer,
+            amount,
+        )?;
+        Ttl::extend_instance(env);
         let result = (|| -> Result<(), ProtocolError> {
+            withdrawer.require_auth();
             if amount <= 0 {
                 return Err(WithdrawError::InvalidAmount.into());
             }
 
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Withdraw)?;
-
-            // Check if withdraw is paused
-            let risk_config = RiskConfigStorage::get(env);
-            if risk_config.pause_withdraw {
-                return Err(WithdrawError::ProtocolPaused.into());
-            }
-
-            UserManager::ensure_operation_allowed(
-                env,
-                withdrawer,
-                OperationKind::Withdraw,
-                amount,
-            )?;
+            ComplianceGate::check(env, withdrawer, OperationKind::Withdraw, amount)?;
 
             // Load user position
             let mut position = match StateHelper::get_position(env, withdrawer) {
@@ -100,37 +101,28 @@ impl WithdrawModule {
                 None => return Err(WithdrawError::PositionNotFound.into()),
             };
 
-            // Check if user has enough collateral
-            if position.collateral < amount {
-                return Err(WithdrawError::InsufficientCollateral.into());
-            }
-
-            // Accrue interest
+            // Accrue interest (and any supply fee) before checking what is left
             let state = InterestRateStorage::update_state(env);
             InterestRateManager::accrue_interest_for_position(
                 env,
                 &mut position,
                 state.current_borrow_rate,
-                state.current_supply_rate,
+                state.liquidity_index,
             );
 
-            // Check collateral ratio after withdrawal (only if there's debt)
+            // Check if user has enough collateral
+            if position.collateral < amount {
+                return Err(WithdrawError::InsufficientCollateral.into());
+            }
+
             let new_collateral = position.collateral - amount;
-            let collateral_ratio = if position.debt > 0 {
-                let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-                let ratio = (new_collateral * 100) / position.debt;
-                if ratio < min_ratio {
-                    return Err(WithdrawError::InsufficientCollateralRatio.into());
-                }
-                ratio
-            } else {
-                0
-            };
+            let collateral_ratio =
+                Self::ensure_can_release(env, withdrawer, new_collateral, position.debt)?;
 
             // Update position
             position.collateral = new_collateral;
-            TransferEnforcer::transfer_out(env, withdrawer, amount, Symbol::new(env, "withdraw"))?;
             StateHelper::save_position(env, &position);
+            TransferEnforcer::transfer_out(env, withdrawer, amount, Symbol::new(env, "withdraw"))?;
 
             // Emit event
             ProtocolEvent::PositionUpdated(
@@ -142,134 +134,85 @@ impl WithdrawModule {
             .emit(env);
 
             // Analytics
-            AnalyticsModule::record_activity(env, withdrawer, "withdraw", amount, None)?;
+            AnalyticsModule::record_activity(
+                env,
+                withdrawer,
+                ActivityKind::Withdraw,
+                amount,
+                None,
+            )?;
             UserManager::record_activity(env, withdrawer, OperationKind::Withdraw, amount)?;
+            PositionHistory::record(env, withdrawer, ActivityKind::Withdraw, None, amount);
+            BorrowLimits::record_ledger_action(
+                env,
+                withdrawer,
+                &TokenRegistry::require_primary_asset(env)?,
+            );
+            Notifications::check(env, withdrawer)?;
 
             Ok(())
         })();
+        ErrorContext::report(env, result, "withdraw", withdrawer, amount)
+    }
 
-        ReentrancyGuard::exit(env);
-        result
+    /// Checks for primary collateral leaving `user`'s position, by withdrawal or by moving
+    /// deposit receipts: no guarded action earlier in this ledger and, with debt
+    /// outstanding, fresh prices and at least the action minimum ratio on what remains.
+    /// Returns the resulting collateral ratio, 0 without debt.
+    pub fn ensure_can_release(
+        env: &Env,
+        user: &Address,
+        new_collateral: i128,
+        debt: i128,
+    ) -> Result<i128, ProtocolError> {
+        BorrowLimits::ensure_not_same_ledger(env, user)?;
+        if debt <= 0 {
+            return Ok(0);
+        }
+        CrossAssetManager::ensure_prices_fresh(env, user)?;
+        let ratio = CrossAssetManager::collateral_ratio(env, user, new_collateral, debt)?;
+        if ratio < ProtocolConfig::get_action_min_ratio(env) {
+            return Err(WithdrawError::InsufficientCollateralRatio.into());
+        }
+        Ok(ratio)
     }
 
     /// Withdraw collateral for a specific asset (checks cross-asset ratio)
     pub fn withdraw_asset(
         env: &Env,
-        user: &String,
+        user: &Address,
         asset: &Address,
         amount: i128,
     ) -> Result<(), ProtocolError> {
-        ReentrancyGuard::enter(env)?;
-        let result = (|| -> Result<(), ProtocolError> {
-            if user.is_empty() {
-                return Err(WithdrawError::InvalidAddress.into());
-            }
-            if amount <= 0 {
-                return Err(WithdrawError::InvalidAmount.into());
-            }
-
-            EmergencyManager::ensure_operation_allowed(env, OperationKind::Withdraw)?;
-
-            let user_addr = Address::from_string(user);
-
-            // For cross-asset withdrawal, we would need to implement cross-asset position handling
-            // This is a simplified version for the modular structure
-            let mut position = match StateHelper::get_position(env, &user_addr) {
-                Some(pos) => pos,
-                None => return Err(WithdrawError::PositionNotFound.into()),
-            };
-
-            if position.collateral < amount {
-                return Err(WithdrawError::InsufficientCollateral.into());
-            }
-
-            // Check ratio after withdrawal
-            let new_collateral = position.collateral - amount;
-            let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-            let ratio = if position.debt > 0 {
-                (new_collateral * 100) / position.debt
-            } else {
-                0
-            };
-
-            if position.debt > 0 && ratio < min_ratio {
-                return Err(WithdrawError::InsufficientCollateralRatio.into());
-            }
-
-            // Update position
-            position.collateral = new_collateral;
-            StateHelper::save_position(env, &position);
-
-            // Emit cross-asset withdraw event
-            ProtocolEvent::CrossWithdraw(user_addr, asset.clone(), amount).emit(env);
-
-            Ok(())
-        })();
+        let _guard = ReentrancyScope::enter(env)?;
+        user.require_auth();
+        if amount <= 0 {
+            return Err(WithdrawError::InvalidAmount.into());
+        }
 
-        ReentrancyGuard::exit(env);
-        result
-    }
+        ComplianceGate::check_market(env, user, asset, OperationKind::Withdraw, amount)?;
+        BorrowLimits::ensure_not_same_ledger(env, user)?;
 
-    /// Calculate maximum withdrawable amount
-    pub fn calculate_max_withdrawable(env: &Env, user: &Address) -> Result<i128, ProtocolError> {
         let position = match StateHelper::get_position(env, user) {
             Some(pos) => pos,
             None => return Err(WithdrawError::PositionNotFound.into()),
         };
 
-        if position.debt == 0 {
-            return Ok(position.collateral);
-        }
-
-        let min_ratio = ProtocolConfig::get_min_collateral_ratio(env);
-        let required_collateral = (position.debt * min_ratio) / 100;
+        CrossAssetManager::withdraw(env, user, asset, amount, position.collateral, position.debt)?;
+        UserManager::record_activity(env, user, OperationKind::Withdraw, amount)?;
+        BorrowLimits::record_ledger_action(env, user, asset);
+        PositionHistory::record(
+            env,
+            user,
+            ActivityKind::Withdraw,
+            Some(asset.clone()),
+            amount,
+        );
 
-        if position.collateral > required_collateral {
-            Ok(position.collateral - required_collateral)
-        } else {
-            Ok(0)
-        }
-    }
+        // Emit cross-asset withdraw event
+        ProtocolEvent::CrossWithdraw(user.clone(), asset.clone(), amount).emit(env);
+        Notifications::check(env, user)?;
 
-    /// Validate withdraw parameters
-    pub fn validate_withdraw_params(params: &WithdrawParams) -> Result<(), WithdrawError> {
-        if params.amount <= 0 {
-            return Err(WithdrawError::InvalidAmount);
-        }
         Ok(())
     }
-
-    /// Check if withdrawal is allowed based on collateral ratio
-    pub fn is_withdrawal_allowed(
-        current_collateral: i128,
-        current_debt: i128,
-        withdraw_amount: i128,
-        min_collateral_ratio: i128,
-    ) -> bool {
-        if current_debt == 0 {
-            return withdraw_amount <= current_collateral;
-        }
-
-        let new_collateral = current_collateral - withdraw_amount;
-        if new_collateral < 0 {
-            return false;
-        }
-
-        let collateral_ratio = (new_collateral * 100) / current_debt;
-        collateral_ratio >= min_collateral_ratio
-    }
-
-    /// Calculate collateral ratio after withdrawal
-    pub fn calculate_collateral_ratio_after_withdrawal(
-        current_collateral: i128,
-        current_debt: i128,
-        withdraw_amount: i128,
-    ) -> i128 {
-        let new_collateral = current_collateral - withdraw_amount;
-        if current_debt > 0 && new_collateral >= 0 {
-            (new_collateral * 100) / current_debt
-        } else {
-            0
-        }
-    }
 }
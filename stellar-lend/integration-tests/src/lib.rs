@@ -0,0 +1,7 @@
+//! Integration tests for the StellarLend contract
+//! The tests under `tests/` deploy the contract next to mock token, oracle and flash loan
+//! receiver contracts and drive it only through its generated client, so every flow
+//! crosses real contract boundaries: authorization, token transfers and cross-contract
+//! calls behave as they would on chain. Shared setup lives in `tests/common`.
+
+#![no_std]
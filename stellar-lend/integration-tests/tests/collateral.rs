@@ -0,0 +1,62 @@
+//! Cross-asset collateral priced by an external oracle contract, through to liquidation
+
+mod common;
+
+use common::Harness;
+use hello_world::ProtocolError;
+
+#[test]
+fn cross_asset_collateral_follows_the_oracle_into_liquidation() {
+    let h = Harness::new();
+    let user = h.user(0);
+    let liquidator = h.user(500);
+    let asset = h.list_asset(100_000_000);
+    let oracle = h.price_feed(&asset.address, 100_000_000);
+    asset.mint(&user, &3_000);
+
+    h.protocol
+        .deposit_collateral_asset(&user, &asset.address, &3_000);
+    assert_eq!(asset.balance(&user), 0);
+    assert_eq!(asset.balance(&h.protocol.address), 3_000);
+
+    // 3,000 units at par support 2,000 of debt at the 150% minimum
+    assert_eq!(
+        h.protocol.try_borrow(&user, &2_001),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    h.protocol.borrow(&user, &2_000);
+    assert_eq!(h.token.balance(&user), 2_000);
+    assert_eq!(
+        h.protocol.try_liquidate(&liquidator, &user, &500),
+        Err(Ok(ProtocolError::NotEligibleForLiquidation))
+    );
+
+    // Halving the oracle price leaves the position under water
+    oracle.set_feed(&asset.address, &50_000_000, &0);
+    assert_eq!(h.protocol.sync_asset_price(&asset.address), (50_000_000, 0));
+    let (_, debt, ratio) = h.protocol.get_cross_position_summary(&user);
+    assert_eq!((debt, ratio), (2_000, 75));
+
+    h.protocol
+        .liquidate_collateral(&liquidator, &user, &asset.address, &500, &None);
+    let (_, debt, _) = h.protocol.get_cross_position_summary(&user);
+    assert_eq!(debt, 1_500);
+    // The liquidator pays the debt down and takes the seized units of the cross asset
+    assert_eq!(h.token.balance(&liquidator), 0);
+    assert!(asset.balance(&liquidator) > 1_000);
+}
+
+#[test]
+fn withdrawing_cross_collateral_returns_the_tokens() {
+    let h = Harness::new();
+    let user = h.user(0);
+    let asset = h.list_asset(100_000_000);
+    h.price_feed(&asset.address, 100_000_000);
+    asset.mint(&user, &1_000);
+
+    h.protocol
+        .deposit_collateral_asset(&user, &asset.address, &1_000);
+    h.protocol.withdraw_asset(&user, &asset.address, &400);
+    assert_eq!(asset.balance(&user), 400);
+    assert_eq!(asset.balance(&h.protocol.address), 600);
+}
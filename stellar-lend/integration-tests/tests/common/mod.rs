@@ -0,0 +1,183 @@
+//! Shared setup for the integration tests: mock token, oracle and flash loan receiver
+//! contracts, and a harness that deploys the protocol next to them.
+
+#![allow(dead_code)]
+
+use hello_world::{Contract, ContractClient, InitConfig, VerificationStatus};
+use soroban_sdk::{
+    contract, contractimpl, testutils::Address as _, token::TokenClient, Address, Env, Map, Symbol,
+};
+
+/// Token holding balances in a map; transfers need the sender's authorization
+#[contract]
+pub struct MockToken;
+
+#[contractimpl]
+impl MockToken {
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        Self::add_balance(&env, &to, amount);
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        Self::deduct_balance(&env, &from, amount);
+        Self::add_balance(&env, &to, amount);
+    }
+
+    pub fn balance(env: Env, id: Address) -> i128 {
+        Self::get_balances(&env).get(id).unwrap_or(0)
+    }
+
+    pub fn burn(env: Env, from: Address, amount: i128) {
+        from.require_auth();
+        Self::deduct_balance(&env, &from, amount);
+    }
+
+    pub fn decimals(_env: Env) -> u32 {
+        7
+    }
+}
+
+impl MockToken {
+    fn get_balances(env: &Env) -> Map<Address, i128> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, "balances"))
+            .unwrap_or_else(|| Map::new(env))
+    }
+
+    fn add_balance(env: &Env, addr: &Address, amount: i128) {
+        let mut balances = Self::get_balances(env);
+        let current = balances.get(addr.clone()).unwrap_or(0);
+        balances.set(addr.clone(), current + amount);
+        env.storage()
+            .instance()
+            .set(&Symbol::new(env, "balances"), &balances);
+    }
+
+    fn deduct_balance(env: &Env, addr: &Address, amount: i128) {
+        let current = Self::get_balances(env).get(addr.clone()).unwrap_or(0);
+        if current < amount {
+            panic!("insufficient balance");
+        }
+        Self::add_balance(env, addr, -amount);
+    }
+}
+
+/// Price feed reporting a settable price and confidence interval per asset
+#[contract]
+pub struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    pub fn set_feed(env: Env, asset: Address, price: i128, confidence: i128) {
+        env.storage().instance().set(&asset, &(price, confidence));
+    }
+
+    pub fn get_price(env: Env, asset: Address) -> i128 {
+        Self::feed(&env, &asset).0
+    }
+
+    pub fn get_confidence(env: Env, asset: Address) -> i128 {
+        Self::feed(&env, &asset).1
+    }
+}
+
+impl MockOracle {
+    fn feed(env: &Env, asset: &Address) -> (i128, i128) {
+        env.storage().instance().get(asset).expect("no feed")
+    }
+}
+
+/// Flash loan receiver that pays the loan and fee back to the lender it was bound to
+#[contract]
+pub struct MockFlashLoanReceiver;
+
+#[contractimpl]
+impl MockFlashLoanReceiver {
+    pub fn bind(env: Env, lender: Address) {
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "lender"), &lender);
+    }
+
+    pub fn on_flash_loan(env: Env, asset: Address, amount: i128, fee: i128, _initiator: Address) {
+        let lender: Address = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "lender"))
+            .expect("not bound");
+        TokenClient::new(&env, &asset).transfer(
+            &env.current_contract_address(),
+            &lender,
+            &(amount + fee),
+        );
+    }
+}
+
+/// Protocol deployed next to a mock primary token, with a funded liquidity pool
+pub struct Harness {
+    pub env: Env,
+    pub admin: Address,
+    pub protocol: ContractClient<'static>,
+    pub token: MockTokenClient<'static>,
+}
+
+impl Harness {
+    /// Liquidity the protocol holds in the primary token after setup
+    pub const POOL_LIQUIDITY: i128 = 1_000_000;
+
+    pub fn new() -> Self {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let token = MockTokenClient::new(&env, &env.register(MockToken, ()));
+        let protocol = ContractClient::new(&env, &env.register(Contract, ()));
+        protocol.initialize(
+            &admin,
+            &InitConfig {
+                treasury: Address::generate(&env),
+                native_asset: token.address.clone(),
+                min_collateral_ratio: 150,
+                close_factor: 50_000_000,
+                liquidation_incentive: 10_000_000,
+            },
+        );
+        token.mint(&protocol.address, &Self::POOL_LIQUIDITY);
+
+        Harness {
+            env,
+            admin,
+            protocol,
+            token,
+        }
+    }
+
+    /// A verified user holding `funds` of the primary token
+    pub fn user(&self, funds: i128) -> Address {
+        let user = Address::generate(&self.env);
+        self.protocol
+            .set_user_verification(&self.admin, &user, &VerificationStatus::Verified);
+        self.token.mint(&user, &funds);
+        user
+    }
+
+    /// A new token listed as cross collateral at `collateral_factor` (scaled by 1e8)
+    pub fn list_asset(&self, collateral_factor: i128) -> MockTokenClient<'static> {
+        let asset = MockTokenClient::new(&self.env, &self.env.register(MockToken, ()));
+        self.protocol
+            .set_asset_params(&self.admin, &asset.address, &collateral_factor, &true);
+        asset
+    }
+
+    /// A mock oracle registered as the sole price source of `asset`
+    pub fn price_feed(&self, asset: &Address, price: i128) -> MockOracleClient<'static> {
+        let oracle = MockOracleClient::new(&self.env, &self.env.register(MockOracle, ()));
+        oracle.set_feed(asset, &price, &0);
+        self.protocol
+            .set_oracle_source(&self.admin, asset, &oracle.address, &1);
+        self.protocol.sync_asset_price(asset);
+        oracle
+    }
+}
@@ -0,0 +1,61 @@
+//! Primary asset lifecycle driven through the contract client
+
+mod common;
+
+use common::Harness;
+use hello_world::ProtocolError;
+use soroban_sdk::testutils::Address as _;
+
+#[test]
+fn deposit_borrow_repay_withdraw_moves_tokens() {
+    let h = Harness::new();
+    let user = h.user(10_000);
+    let pool = Harness::POOL_LIQUIDITY;
+
+    h.protocol.deposit_collateral(&user, &5_000);
+    assert_eq!(h.token.balance(&user), 5_000);
+    assert_eq!(h.token.balance(&h.protocol.address), pool + 5_000);
+
+    h.protocol.borrow(&user, &2_000);
+    assert_eq!(h.token.balance(&user), 7_000);
+    assert_eq!(h.token.balance(&h.protocol.address), pool + 3_000);
+    let (collateral, debt, _) = h.protocol.get_position(&user);
+    assert_eq!((collateral, debt), (5_000, 2_000));
+
+    h.protocol.repay(&user, &2_000);
+    assert_eq!(h.token.balance(&user), 5_000);
+    assert_eq!(h.protocol.get_position(&user).1, 0);
+
+    h.protocol.withdraw(&user, &5_000);
+    assert_eq!(h.token.balance(&user), 10_000);
+    assert_eq!(h.token.balance(&h.protocol.address), pool);
+}
+
+#[test]
+fn undercollateralized_borrow_is_rejected_without_moving_tokens() {
+    let h = Harness::new();
+    let user = h.user(10_000);
+
+    h.protocol.deposit_collateral(&user, &1_500);
+    assert_eq!(
+        h.protocol.try_borrow(&user, &1_001),
+        Err(Ok(ProtocolError::InsufficientCollateralRatio))
+    );
+    assert_eq!(h.token.balance(&user), 8_500);
+
+    h.protocol.borrow(&user, &1_000);
+    assert_eq!(h.token.balance(&user), 9_500);
+}
+
+#[test]
+fn unverified_users_cannot_deposit() {
+    let h = Harness::new();
+    let stranger = soroban_sdk::Address::generate(&h.env);
+    h.token.mint(&stranger, &1_000);
+
+    assert!(h
+        .protocol
+        .try_deposit_collateral(&stranger, &1_000)
+        .is_err());
+    assert_eq!(h.token.balance(&stranger), 1_000);
+}